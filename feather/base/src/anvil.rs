@@ -1,9 +1,10 @@
 //! Loading and saving to/from
 //! world saves. Currently includes region file loading,
-//! player data loading, and level data loading.
+//! player data loading, level data loading and map loading.
 
 pub mod block_entity;
 pub mod entity;
 pub mod level;
+pub mod map;
 pub mod player;
 pub mod region;
@@ -6,6 +6,8 @@ use thiserror::Error;
 
 use crate::{vec3, Position, Vec3d};
 
+pub use types::item_nbt::{EnchantmentNbt, ItemDisplayNbt, ItemNbt};
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum EntityDataKind {
     Item,
@@ -77,6 +79,26 @@ pub enum EntityData {
 }
 
 impl EntityData {
+    /// Returns the tags common to all entities,
+    /// or `None` for unknown entities.
+    pub fn base(&self) -> Option<&BaseEntityData> {
+        match self {
+            EntityData::Item(data) => Some(&data.entity),
+            EntityData::Arrow(data) => Some(&data.entity),
+            EntityData::Cow(data)
+            | EntityData::Pig(data)
+            | EntityData::Chicken(data)
+            | EntityData::Sheep(data)
+            | EntityData::Horse(data)
+            | EntityData::Llama(data)
+            | EntityData::Mooshroom(data)
+            | EntityData::Rabbit(data)
+            | EntityData::Squid(data)
+            | EntityData::Donkey(data) => Some(&data.base),
+            EntityData::Unknown => None,
+        }
+    }
+
     pub(crate) fn serialize_unknown<S: Serializer>(_serializer: S) -> Result<S::Ok, S::Error> {
         Err(S::Error::custom("cannot serialize unknown entities"))
     }
@@ -226,37 +248,6 @@ where
     }
 }
 
-/// Represents NBT tags on an item.
-#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub struct ItemNbt {
-    #[serde(rename = "Damage")]
-    pub damage: Option<i32>,
-    // TODO enchantments, display name, ...
-}
-
-impl ItemNbt {
-    /// Create an `ItemStack` of the specified item and amount, setting any nbt present.
-    pub fn item_stack(nbt: &Option<Self>, item: Item, count: u8) -> ItemStack {
-        ItemStack {
-            count: count as u32,
-            item,
-            damage: nbt.as_ref().map(|n| n.damage).flatten().map(|x| x as u32),
-        }
-    }
-}
-
-impl<S> From<S> for ItemNbt
-where
-    S: std::borrow::Borrow<ItemStack>,
-{
-    fn from(s: S) -> Self {
-        let stack = s.borrow();
-        Self {
-            damage: stack.damage.map(|d| d as i32),
-        }
-    }
-}
-
 /// Data for an Item entity (`minecraft:item`).
 #[derive(Clone, Default, Serialize, Deserialize, Debug)]
 pub struct ItemEntityData {
@@ -0,0 +1,158 @@
+//! Loading and saving of the map data files in
+//! a world's `data` directory.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use super::region::DATA_VERSION;
+
+/// Number of pixels in a map, which is 128x128.
+pub const MAP_PIXELS: usize = 128 * 128;
+
+/// Largest scale of a map, at which a pixel covers 16x16 blocks.
+pub const MAX_MAP_SCALE: i8 = 4;
+
+/// Represents the contents of a `map_<id>.dat` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MapFile {
+    data: MapData,
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+}
+
+/// The area shown by a map and its pixels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapData {
+    /// Each level doubles the number of blocks covered by a pixel.
+    pub scale: i8,
+    /// Namespaced ID of the dimension, e.g. `minecraft:overworld`.
+    pub dimension: String,
+    #[serde(rename = "xCenter")]
+    pub x_center: i32,
+    #[serde(rename = "zCenter")]
+    pub z_center: i32,
+    /// Locked maps are no longer updated as players explore.
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(rename = "trackingPosition")]
+    pub tracking_position: bool,
+    #[serde(rename = "unlimitedTracking")]
+    pub unlimited_tracking: bool,
+    /// Color of each pixel, row by row.
+    #[serde(serialize_with = "nbt::i8_array")]
+    pub colors: Vec<i8>,
+}
+
+/// Represents the contents of the `idcounts.dat` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IdCountsFile {
+    data: IdCounts,
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IdCounts {
+    /// The last map ID given out.
+    map: i32,
+}
+
+pub fn load_map(world_dir: &Path, id: i32) -> Result<MapData, nbt::Error> {
+    let mut file = File::open(map_path(world_dir, id))?;
+    let map: MapFile = nbt::from_gzip_reader(&mut file)?;
+    Ok(map.data)
+}
+
+pub fn save_map(world_dir: &Path, id: i32, data: &MapData) -> Result<(), anyhow::Error> {
+    fs::create_dir_all(world_dir.join("data"))?;
+    let mut file = File::create(map_path(world_dir, id))?;
+    let map = MapFile {
+        data: data.clone(),
+        data_version: DATA_VERSION,
+    };
+    nbt::to_gzip_writer(&mut file, &map, None).map_err(anyhow::Error::from)
+}
+
+/// Returns the IDs of the maps stored in the world.
+pub fn map_ids(world_dir: &Path) -> Result<Vec<i32>, anyhow::Error> {
+    let dir = match fs::read_dir(world_dir.join("data")) {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut ids = Vec::new();
+    for entry in dir {
+        let name = entry?.file_name();
+        let id = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("map_"))
+            .and_then(|name| name.strip_suffix(".dat"))
+            .and_then(|id| id.parse().ok());
+        ids.extend(id);
+    }
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Loads the last map ID given out, or `None`
+/// if the world has no maps yet.
+pub fn load_last_map_id(world_dir: &Path) -> Result<Option<i32>, nbt::Error> {
+    let path = id_counts_path(world_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut file = File::open(path)?;
+    let counts: IdCountsFile = nbt::from_gzip_reader(&mut file)?;
+    Ok(Some(counts.data.map))
+}
+
+pub fn save_last_map_id(world_dir: &Path, id: i32) -> Result<(), anyhow::Error> {
+    fs::create_dir_all(world_dir.join("data"))?;
+    let mut file = File::create(id_counts_path(world_dir))?;
+    let counts = IdCountsFile {
+        data: IdCounts { map: id },
+        data_version: DATA_VERSION,
+    };
+    nbt::to_gzip_writer(&mut file, &counts, None).map_err(anyhow::Error::from)
+}
+
+fn map_path(world_dir: &Path, id: i32) -> PathBuf {
+    world_dir.join("data").join(format!("map_{}.dat", id))
+}
+
+fn id_counts_path(world_dir: &Path) -> PathBuf {
+    world_dir.join("data").join("idcounts.dat")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn map_file_round_trip() {
+        let map = MapFile {
+            data: MapData {
+                scale: 2,
+                dimension: "minecraft:overworld".to_owned(),
+                x_center: 448,
+                z_center: -64,
+                locked: true,
+                tracking_position: true,
+                unlimited_tracking: false,
+                colors: vec![4; MAP_PIXELS],
+            },
+            data_version: DATA_VERSION,
+        };
+
+        let mut buffer = Vec::new();
+        nbt::to_gzip_writer(&mut buffer, &map, None).unwrap();
+        let read: MapFile = nbt::from_gzip_reader(&mut Cursor::new(buffer)).unwrap();
+        assert_eq!(read.data, map.data);
+    }
+}
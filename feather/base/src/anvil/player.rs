@@ -26,6 +26,28 @@ pub struct PlayerData {
     pub inventory: Vec<InventorySlot>,
     #[serde(rename = "SelectedItemSlot")]
     pub held_item: i32,
+    /// Seed for the enchantments offered by enchanting tables.
+    #[serde(rename = "XpSeed", default)]
+    pub enchantment_seed: i32,
+    #[serde(rename = "recipeBook", default)]
+    pub recipe_book: RecipeBookData,
+}
+
+/// The recipes known by a player and the state of their recipe books.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecipeBookData {
+    pub recipes: Vec<String>,
+    /// Known recipes the player hasn't looked at yet.
+    pub to_be_displayed: Vec<String>,
+    pub is_gui_open: bool,
+    pub is_filtering_craftable: bool,
+    pub is_furnace_gui_open: bool,
+    pub is_furnace_filtering_craftable: bool,
+    pub is_blasting_furnace_gui_open: bool,
+    pub is_blasting_furnace_filtering_craftable: bool,
+    pub is_smoker_gui_open: bool,
+    pub is_smoker_filtering_craftable: bool,
 }
 
 /// Represents a single inventory slot (including position index).
@@ -142,6 +164,12 @@ pub fn load_player_data(world_dir: &Path, uuid: Uuid) -> Result<PlayerData, nbt:
     Ok(data)
 }
 
+/// Returns whether a player data file exists for the player,
+/// i.e. whether they have joined the world before.
+pub fn player_data_exists(world_dir: &Path, uuid: Uuid) -> bool {
+    file_path(world_dir, uuid).exists()
+}
+
 pub fn save_player_data(
     world_dir: &Path,
     uuid: Uuid,
@@ -175,7 +203,13 @@ mod tests {
         let player: PlayerData = nbt::from_gzip_reader(&mut cursor).unwrap();
         assert_eq!(player.gamemode, Gamemode::Creative.to_i32().unwrap());
         assert_eq!(player.inventory[0].item, "minecraft:diamond_shovel");
-        assert_eq!(player.inventory[0].nbt, Some(ItemNbt { damage: Some(3) }));
+        assert_eq!(
+            player.inventory[0].nbt,
+            Some(ItemNbt {
+                damage: Some(3),
+                ..Default::default()
+            })
+        );
     }
 
     #[test]
@@ -198,7 +232,10 @@ mod tests {
             count: 1,
             slot: 2,
             item: String::from(Item::DiamondAxe.name()),
-            nbt: Some(ItemNbt { damage: Some(42) }),
+            nbt: Some(ItemNbt {
+                damage: Some(42),
+                ..Default::default()
+            }),
         };
 
         let item_stack: ItemStack = slot.into();
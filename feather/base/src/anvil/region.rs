@@ -14,24 +14,33 @@ use generated::Biome;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::BTreeMap;
-use std::fmt::{self, Display, Formatter};
 use std::fs::{File, OpenOptions};
 use std::io::prelude::*;
 use std::io::{Cursor, SeekFrom};
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs, io, iter};
+use thiserror::Error;
 
 /// The length and width of a region, in chunks.
 const REGION_SIZE: usize = 32;
 
 /// The data version supported by this code, currently corresponding
 /// to 1.16.5.
-const DATA_VERSION: i32 = 2586;
+pub(crate) const DATA_VERSION: i32 = 2586;
 
 /// Length, in bytes, of a sector.
 const SECTOR_BYTES: usize = 4096;
 
+/// Set in the compression type of chunks too large for the region
+/// file, which are stored in a separate `c.<x>.<z>.mcc` file instead.
+const EXTERNAL_CHUNK_FLAG: u8 = 0x80;
+
+/// Maximum number of sectors a chunk can occupy in the region
+/// file, as the header stores sector counts in a single byte.
+const MAX_CHUNK_SECTORS: usize = 255;
+
 /// Represents the data for a chunk after the "Chunk [x, y]" tag.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -147,9 +156,83 @@ pub struct RegionHandle {
     header: RegionHeader,
     /// Sector allocator to allocate sectors where we can store chunks.
     allocator: SectorAllocator,
+    /// The position of this region.
+    position: RegionPosition,
+    /// The directory containing the region file.
+    dir: PathBuf,
 }
 
 impl RegionHandle {
+    /// Returns the position of this region.
+    pub fn position(&self) -> RegionPosition {
+        self.position
+    }
+
+    /// Returns the positions (global, not region-relative)
+    /// of the chunks stored in this region file.
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkPosition> + '_ {
+        let origin = ChunkPosition::new(
+            self.position.x * REGION_SIZE as i32,
+            self.position.z * REGION_SIZE as i32,
+        );
+        self.header
+            .locations
+            .iter()
+            .enumerate()
+            .filter(|(_, location)| location.exists())
+            .map(move |(index, _)| {
+                ChunkPosition::new(
+                    origin.x + (index % REGION_SIZE) as i32,
+                    origin.z + (index / REGION_SIZE) as i32,
+                )
+            })
+    }
+
+    /// Checks that the header points each stored chunk
+    /// to its own sectors within the file.
+    ///
+    /// Returns the chunks whose location is invalid. Their data
+    /// is not read; use [`RegionHandle::load_chunk`] to check it.
+    pub fn validate_header(&self) -> Result<Vec<(ChunkPosition, Error)>, Error> {
+        let file_sectors = (self.file.metadata().map_err(Error::Io)?.len() + SECTOR_BYTES as u64
+            - 1)
+            / SECTOR_BYTES as u64;
+
+        let mut owners: Vec<Option<ChunkPosition>> = vec![None; file_sectors as usize];
+        let mut problems = Vec::new();
+        for pos in self.chunks() {
+            let block = self.header.location_for_chunk(pos).0;
+            let end = u64::from(block.offset) + u64::from(block.count);
+            let problem = if block.offset < 2 {
+                Some("Chunk overlaps the region header")
+            } else if end > file_sectors {
+                Some("Chunk extends past the end of the region file")
+            } else if let Some(other) = owners[block.offset as usize..end as usize]
+                .iter()
+                .find_map(|owner| *owner)
+            {
+                problems.push((
+                    pos,
+                    Error::Header("Chunk overlaps the sectors of another chunk"),
+                ));
+                problems.push((
+                    other,
+                    Error::Header("Chunk overlaps the sectors of another chunk"),
+                ));
+                None
+            } else {
+                for owner in &mut owners[block.offset as usize..end as usize] {
+                    *owner = Some(pos);
+                }
+                None
+            };
+            if let Some(problem) = problem {
+                problems.push((pos, Error::Header(problem)));
+            }
+        }
+        Ok(problems)
+    }
+
     /// Loads the chunk at the given position (global, not region-relative).
     ///
     /// The specified chunk is expected to be contained within this region.
@@ -202,15 +285,26 @@ impl RegionHandle {
         self.file.read_exact(&mut buf).map_err(Error::Io)?;
 
         // The compression type is indicated by a byte.
-        // 1 corresponds to gzip compression, while 2
-        // corresponds to zlib.
+        // 1 corresponds to gzip compression, 2 to zlib
+        // and 3 to uncompressed data.
         let compression_type = buf[0];
 
+        // Oversized chunks only store their compression
+        // type in the region file.
+        let external;
+        let data = if compression_type & EXTERNAL_CHUNK_FLAG != 0 {
+            external = fs::read(self.external_chunk_path(original_pos)).map_err(Error::Io)?;
+            &external[..]
+        } else {
+            &buf[1..]
+        };
+
         // Parse NBT data
-        let cursor = Cursor::new(&buf[1..]);
-        let mut root: ChunkRoot = match compression_type {
+        let cursor = Cursor::new(data);
+        let mut root: ChunkRoot = match compression_type & !EXTERNAL_CHUNK_FLAG {
             1 => nbt::from_gzip_reader(cursor).map_err(Error::Nbt)?,
             2 => nbt::from_zlib_reader(cursor).map_err(Error::Nbt)?,
+            3 => nbt::from_reader(cursor).map_err(Error::Nbt)?,
             _ => return Err(Error::InvalidCompression(compression_type)),
         };
 
@@ -243,9 +337,19 @@ impl RegionHandle {
         Ok((chunk, level.entities.clone(), level.block_entities.clone()))
     }
 
+    /// Returns the path of the file storing the given
+    /// chunk if it's too large for the region file.
+    fn external_chunk_path(&self, pos: ChunkPosition) -> PathBuf {
+        self.dir.join(format!("c.{}.{}.mcc", pos.x, pos.z))
+    }
+
     /// Saves the given chunk to this region file. The header will be updated
     /// accordingly and saved as well.
     ///
+    /// If the chunk no longer fits in its previous sectors, it is moved
+    /// to free sectors or the end of the file. Chunks larger than
+    /// the region format allows are written to an external file.
+    ///
     /// Behavior may be unexpected if this region file does not contain the given
     /// chunk position.
     pub fn save_chunk(
@@ -275,6 +379,19 @@ impl RegionHandle {
 
         nbt::to_zlib_writer(&mut buf, &root, None).map_err(Error::Nbt)?;
 
+        // Chunks which need more sectors than the header can count
+        // are moved to an external file, leaving only their
+        // compression type in the region file.
+        let external_path = self.external_chunk_path(chunk_pos);
+        if (buf.len() + 4 + SECTOR_BYTES - 1) / SECTOR_BYTES > MAX_CHUNK_SECTORS {
+            fs::write(&external_path, &buf[1..]).map_err(Error::Io)?;
+            buf.truncate(1);
+            buf[0] |= EXTERNAL_CHUNK_FLAG;
+        } else if external_path.exists() {
+            // The chunk shrank since it was stored externally.
+            fs::remove_file(&external_path).map_err(Error::Io)?;
+        }
+
         let total_len = buf.len() + 4; // 4 bytes for length header
 
         let sectors = (total_len + SECTOR_BYTES - 1) / SECTOR_BYTES;
@@ -292,20 +409,72 @@ impl RegionHandle {
         self.file.write_all(&buf).map_err(Error::Io)?;
 
         // Write padding to align to sector count
-        let padding_count = SECTOR_BYTES - total_len % SECTOR_BYTES;
+        let padding_count = (SECTOR_BYTES - total_len % SECTOR_BYTES) % SECTOR_BYTES;
 
         for _ in 0..padding_count {
             self.file.write_u8(0).map_err(Error::Io)?;
         }
 
         // Update header
+        let local_pos = ChunkPosition::new(local_x, local_z);
         self.header
-            .set_location_for_chunk(ChunkPosition::new(local_x, local_z), ChunkLocation(block));
+            .set_location_for_chunk(local_pos, ChunkLocation(block));
+        self.header.set_timestamp_for_chunk(local_pos, unix_time());
         self.save_header().map_err(Error::Io)?;
 
         Ok(())
     }
 
+    /// Reads the data stored for the given chunk without
+    /// decompressing or parsing it, e.g. to back up a corrupt chunk.
+    pub fn read_raw_chunk(&mut self, mut pos: ChunkPosition) -> Result<Vec<u8>, Error> {
+        pos.x %= 32;
+        pos.z %= 32;
+
+        let location = self.header.location_for_chunk(pos);
+        if !location.exists() {
+            return Err(Error::ChunkNotExist);
+        }
+        let len = location.0.count as usize * SECTOR_BYTES;
+        if len > 1_048_576 + SECTOR_BYTES {
+            return Err(Error::ChunkTooLarge(len));
+        }
+
+        self.file
+            .seek(SeekFrom::Start(
+                u64::from(location.0.offset) * SECTOR_BYTES as u64,
+            ))
+            .map_err(Error::Io)?;
+        // The file may be truncated, so read what's there.
+        let mut buf = Vec::with_capacity(len);
+        (&mut self.file)
+            .take(len as u64)
+            .read_to_end(&mut buf)
+            .map_err(Error::Io)?;
+        Ok(buf)
+    }
+
+    /// Removes the given chunk from this region file.
+    /// The header is updated and saved.
+    pub fn remove_chunk(&mut self, mut pos: ChunkPosition) -> Result<(), Error> {
+        pos.x %= 32;
+        pos.z %= 32;
+
+        let location = self.header.location_for_chunk(pos);
+        if !location.exists() {
+            return Ok(());
+        }
+        self.allocator.free(location.0);
+        self.header.set_location_for_chunk(
+            pos,
+            ChunkLocation(SectorBlock {
+                offset: 0,
+                count: 0,
+            }),
+        );
+        self.save_header().map_err(Error::Io)
+    }
+
     fn save_header(&mut self) -> Result<(), io::Error> {
         self.file.seek(SeekFrom::Start(0))?;
 
@@ -504,9 +673,14 @@ impl SectorAllocator {
                 continue;
             }
 
-            let offset = chunk_location.0.offset;
-            let count = chunk_location.0.count;
-            (offset..offset + count).for_each(|sector| used_sectors.set(sector as usize, true));
+            // Corrupt headers may point past the end of the file;
+            // reserve those sectors rather than handing them out.
+            let offset = chunk_location.0.offset as usize;
+            let end = offset + chunk_location.0.count as usize;
+            if end > used_sectors.len() {
+                used_sectors.resize(end, false);
+            }
+            used_sectors[offset..end].set_all(true);
         }
 
         // Allocate two sectors at start for header
@@ -562,13 +736,19 @@ impl SectorAllocator {
             return block;
         }
 
-        // No sector found: must allocate into end
-
+        // No sector found: must allocate into end,
+        // reusing any free sectors the file ends with.
+        let offset = if length > 0 {
+            start
+        } else {
+            self.used_sectors.len()
+        };
         let block = SectorBlock {
-            offset: self.used_sectors.len() as u32,
+            offset: offset as u32,
             count: min_size,
         };
 
+        self.used_sectors.resize(offset, true);
         self.used_sectors
             .extend(iter::repeat(true).take(min_size as usize));
 
@@ -577,61 +757,65 @@ impl SectorAllocator {
 }
 
 /// An error which occurred during region file processing.
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum Error {
     /// The region file header was invalid
+    #[error("{0}")]
     Header(&'static str),
     /// The region file contained invalid NBT data
+    #[error("Region file contains invalid NBT: {0}")]
     Nbt(nbt::Error),
     /// The chunk was too large
+    #[error("Chunk is too large: {0} bytes")]
     ChunkTooLarge(usize),
     /// The chunk contained an invalid compression type
+    #[error("Chunk uses invalid compression type {0}")]
     InvalidCompression(u8),
     /// An IO error occurred
+    #[error(transparent)]
     Io(io::Error),
     /// There was an invalid block in the chunk
+    #[error("Chunk contains invalid block {0}")]
     InvalidBlock(String),
     /// The chunk does not exist
+    #[error("The chunk does not exist")]
     ChunkNotExist,
     /// The chunk uses an unsupported data version
+    #[error("The chunk uses an unsupported data version ({0}). Feather currently only supports 1.16.5 region files.")]
     UnsupportedDataVersion(i32),
     /// The palette for the chunk contained in invalid block type
+    #[error("Chunk contains invalid block type")]
     InvalidBlockType,
     /// The "Chunk [x, z]" tag was missing
+    #[error("Chunk is missing a root NBT tag")]
     MissingRootTag,
     /// Chunk section index was out of bounds
+    #[error("Section index out of bounds")]
     IndexOutOfBounds,
     /// Invalid biome ID
+    #[error("Invalid biome ID {0}")]
     InvalidBiomeId(i32),
 }
 
-impl Display for Error {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-        match self {
-            Error::Io(ierr) => ierr.fmt(f)?,
-            Error::Header(msg) => f.write_str(msg)?,
-            Error::Nbt(e) => f.write_str(&format!("Region file contains invalid NBT: {}", e))?,
-            Error::ChunkTooLarge(size) => {
-                f.write_str(&format!("Chunk is too large: {} bytes", size))?
-            }
-            Error::InvalidCompression(id) => {
-                f.write_str(&format!("Chunk uses invalid compression type {}", id))?
-            }
-            Error::InvalidBlock(name) => f.write_str(&format!("Chunk contains invalid block {}", name))?,
-            Error::ChunkNotExist => f.write_str("The chunk does not exist")?,
-            Error::UnsupportedDataVersion(_) => f.write_str("The chunk uses an unsupported data version. Feather currently only supports 1.16.5 region files.")?,
-            Error::InvalidBlockType => f.write_str("Chunk contains invalid block type")?,
-            Error::MissingRootTag => f.write_str("Chunk is missing a root NBT tag")?,
-            Error::IndexOutOfBounds => f.write_str("Section index out of bounds")?,
-            Error::InvalidBiomeId(id) => write!(f, "Invalid biome ID {}", id)?,
-        }
-
-        Ok(())
+impl Error {
+    /// Returns whether this error means the stored chunk
+    /// data is corrupt, as opposed to missing, unreadable,
+    /// or from an unsupported version.
+    pub fn is_corruption(&self) -> bool {
+        matches!(
+            self,
+            Error::Nbt(_)
+                | Error::ChunkTooLarge(_)
+                | Error::InvalidCompression(_)
+                | Error::InvalidBlock(_)
+                | Error::InvalidBlockType
+                | Error::MissingRootTag
+                | Error::IndexOutOfBounds
+                | Error::InvalidBiomeId(_)
+        )
     }
 }
 
-impl std::error::Error for Error {}
-
 /// Loads the region at the specified position
 /// from the specified world directory.
 ///
@@ -663,6 +847,8 @@ pub fn load_region(dir: &PathBuf, pos: RegionPosition) -> Result<RegionHandle, E
         file,
         header,
         allocator,
+        position: pos,
+        dir: region_dir(dir),
     })
 }
 
@@ -694,9 +880,18 @@ pub fn create_region(dir: &PathBuf, pos: RegionPosition) -> Result<RegionHandle,
         file,
         header,
         allocator,
+        position: pos,
+        dir: region_dir(dir),
     })
 }
 
+/// Returns the current UNIX time in seconds, as stored in region headers.
+fn unix_time() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as u32)
+}
+
 fn open_opts() -> OpenOptions {
     OpenOptions::new()
         .read(true)
@@ -705,16 +900,16 @@ fn open_opts() -> OpenOptions {
         .clone()
 }
 
+fn region_dir(dir: &PathBuf) -> PathBuf {
+    dir.join("region")
+}
+
 fn region_file_path(dir: &PathBuf, pos: RegionPosition) -> PathBuf {
-    let mut buf = dir.clone();
-    buf.push(format!("region/r.{}.{}.mca", pos.x, pos.z));
-    buf
+    region_dir(dir).join(pos.file_name())
 }
 
 fn create_region_dir(dir: &PathBuf) -> Result<(), io::Error> {
-    let mut dir = dir.clone();
-    dir.push("region");
-    fs::create_dir_all(dir.as_path())
+    fs::create_dir_all(region_dir(dir))
 }
 
 /// Reads the region header from the given file.
@@ -803,6 +998,12 @@ impl RegionHeader {
         self.locations[index] = location;
     }
 
+    /// Sets the time the given chunk was last saved.
+    fn set_timestamp_for_chunk(&mut self, pos: ChunkPosition, timestamp: u32) {
+        let index = Self::index(pos);
+        self.timestamps[index] = timestamp;
+    }
+
     /// Writes this header to the given writer.
     fn write_to<W>(&self, w: &mut W) -> Result<(), io::Error>
     where
@@ -848,6 +1049,18 @@ pub struct RegionPosition {
 }
 
 impl RegionPosition {
+    /// Parses the position of a region from
+    /// its file name, e.g. `r.0.-1.mca`.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+        let x = parts.next()?.parse().ok()?;
+        let z = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self { x, z })
+    }
+
     /// Returns the coordinates of the region corresponding
     /// to the specified chunk position.
     pub fn from_chunk(chunk_coords: ChunkPosition) -> Self {
@@ -856,6 +1069,11 @@ impl RegionPosition {
             z: chunk_coords.z >> 5,
         }
     }
+
+    /// Returns the name of this region's file, e.g. `r.0.-1.mca`.
+    pub fn file_name(self) -> String {
+        format!("r.{}.{}.mca", self.x, self.z)
+    }
 }
 
 #[cfg(test)]
@@ -913,4 +1131,141 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn remove_chunk() {
+        let dir = std::env::temp_dir().join(format!("feather-region-test-{}", std::process::id()));
+        let pos = ChunkPosition::new(3, -2);
+        let mut region = create_region(&dir, RegionPosition::from_chunk(pos)).unwrap();
+
+        region.save_chunk(&Chunk::new(pos), &[], &[]).unwrap();
+        assert!(region.load_chunk(pos).is_ok());
+        assert!(!region.read_raw_chunk(pos).unwrap().is_empty());
+
+        region.remove_chunk(pos).unwrap();
+        assert!(matches!(region.load_chunk(pos), Err(Error::ChunkNotExist)));
+        assert!(matches!(
+            region.read_raw_chunk(pos),
+            Err(Error::ChunkNotExist)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn external_chunks() {
+        let dir =
+            std::env::temp_dir().join(format!("feather-external-test-{}", std::process::id()));
+        let pos = ChunkPosition::new(-5, 40);
+        let mut region = create_region(&dir, RegionPosition::from_chunk(pos)).unwrap();
+        region.save_chunk(&Chunk::new(pos), &[], &[]).unwrap();
+        let root = chunk_to_chunk_root(&Chunk::new(pos), &[], &[]);
+
+        // Replace the stored chunk with a stub pointing to the external file.
+        let offset = region.header.location_for_chunk(pos).0.offset;
+        let mut store_externally = |compression_type: u8, data: Vec<u8>| {
+            fs::write(region.external_chunk_path(pos), data).unwrap();
+            region
+                .file
+                .seek(SeekFrom::Start(u64::from(offset) * SECTOR_BYTES as u64))
+                .unwrap();
+            region.file.write_u32::<BigEndian>(1).unwrap();
+            region
+                .file
+                .write_u8(compression_type | EXTERNAL_CHUNK_FLAG)
+                .unwrap();
+            region.load_chunk(pos).map(|(chunk, _, _)| chunk.position())
+        };
+
+        let mut zlib = Vec::new();
+        nbt::to_zlib_writer(&mut zlib, &root, None).unwrap();
+        assert_eq!(store_externally(2, zlib).unwrap(), pos);
+
+        let mut uncompressed = Vec::new();
+        nbt::to_writer(&mut uncompressed, &root, None).unwrap();
+        assert_eq!(store_externally(3, uncompressed).unwrap(), pos);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chunks_grow_past_their_sectors() {
+        let dir = std::env::temp_dir().join(format!("feather-growth-test-{}", std::process::id()));
+        let first = ChunkPosition::new(0, 0);
+        let second = ChunkPosition::new(1, 0);
+        let mut region = create_region(&dir, RegionPosition::from_chunk(first)).unwrap();
+        region.save_chunk(&Chunk::new(first), &[], &[]).unwrap();
+        region.save_chunk(&Chunk::new(second), &[], &[]).unwrap();
+        let old = region.header.location_for_chunk(first).0;
+
+        // Random blocks compress poorly, so the chunk needs more sectors.
+        let mut chunk = Chunk::new(first);
+        let mut seed = 1u32;
+        for y in 0..64 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    let block = BlockId::from_vanilla_id((seed >> 16) as u16 % 1000);
+                    chunk.set_block_at(x, y, z, block).unwrap();
+                }
+            }
+        }
+        region.save_chunk(&chunk, &[], &[]).unwrap();
+
+        let new = region.header.location_for_chunk(first).0;
+        assert!(new.count > old.count);
+        assert_ne!(new.offset, old.offset);
+        assert_ne!(region.header.timestamps[RegionHeader::index(first)], 0);
+        assert!(region.validate_header().unwrap().is_empty());
+        let (loaded, _, _) = region.load_chunk(first).unwrap();
+        for y in 0..64 {
+            assert_eq!(loaded.block_at(3, y, 9), chunk.block_at(3, y, 9));
+        }
+        assert!(region.load_chunk(second).is_ok());
+
+        // Shrinking the chunk again reuses the freed sectors.
+        region.save_chunk(&Chunk::new(first), &[], &[]).unwrap();
+        assert_eq!(region.header.location_for_chunk(first).0.offset, old.offset);
+        drop(region);
+
+        // Reopening the region keeps the saved chunks.
+        let mut region = load_region(&dir, RegionPosition::from_chunk(first)).unwrap();
+        assert!(region.validate_header().unwrap().is_empty());
+        assert!(region.load_chunk(first).is_ok());
+        assert!(region.load_chunk(second).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn region_file_names() {
+        let pos = RegionPosition::from_chunk(ChunkPosition::new(40, -3));
+        assert_eq!(RegionPosition::from_file_name(&pos.file_name()), Some(pos));
+        assert_eq!(RegionPosition::from_file_name("r.0.mca"), None);
+        assert_eq!(RegionPosition::from_file_name("r.0.1.2.mca"), None);
+        assert_eq!(RegionPosition::from_file_name("level.dat"), None);
+    }
+
+    #[test]
+    fn validate_region_header() {
+        let dir =
+            std::env::temp_dir().join(format!("feather-validate-test-{}", std::process::id()));
+        let first = ChunkPosition::new(-32, 1);
+        let second = ChunkPosition::new(-31, 1);
+        let mut region = create_region(&dir, RegionPosition::from_chunk(first)).unwrap();
+        region.save_chunk(&Chunk::new(first), &[], &[]).unwrap();
+        region.save_chunk(&Chunk::new(second), &[], &[]).unwrap();
+
+        assert_eq!(region.chunks().collect::<Vec<_>>(), vec![first, second]);
+        assert!(region.validate_header().unwrap().is_empty());
+
+        // Point the second chunk at the first chunk's sectors.
+        let location = region.header.location_for_chunk(first);
+        region.header.set_location_for_chunk(second, location);
+        let problems = region.validate_header().unwrap();
+        let chunks: Vec<_> = problems.iter().map(|(pos, _)| *pos).collect();
+        assert_eq!(chunks, vec![second, first]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
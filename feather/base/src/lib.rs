@@ -3,27 +3,34 @@
 //! * Inventories
 //! * The block ID system
 //! * The chunk data structure
+//!
+//! Chunks, entity metadata, item NBT and namespaced IDs live in
+//! `feather-types`, which the protocol depends on in place of this
+//! crate. They are reexported here under their usual paths.
 
 use std::time::Duration;
 
-use num_derive::{FromPrimitive, ToPrimitive};
-use serde::{Deserialize, Serialize};
-
 pub mod anvil;
-pub mod chunk;
 pub mod inventory;
-pub mod metadata;
+pub mod nbt_conversion;
 mod world;
 
 pub use blocks::*;
 pub use chunk::{Chunk, ChunkSection, CHUNK_HEIGHT, CHUNK_WIDTH};
-pub use generated::{Area, Biome, EntityKind, Inventory, Item, ItemStack};
+pub use generated::{
+    Area, Biome, Enchantment, EntityKind, Inventory, Item, ItemStack, ItemStackMeta,
+};
+#[doc(inline)]
+pub use id::NamespacedId;
 pub use libcraft_blocks::{BlockKind, BlockState};
-pub use libcraft_core::{position, vec3, BlockPosition, ChunkPosition, Gamemode, Position, Vec3d};
+pub use libcraft_core::{
+    position, vec3, BlockPosition, ChunkPosition, Difficulty, GameRules, Gamemode, Position, Vec3d,
+};
 pub use libcraft_particles::{Particle, ParticleKind};
-pub use libcraft_text::{deserialize_text, Text, Title};
+pub use libcraft_text::{deserialize_text, Text, TextComponentBuilder, TextValue, Title};
 #[doc(inline)]
 pub use metadata::EntityMetadata;
+pub use types::{anti_xray, chunk, id, metadata, Direction, ProfileProperty};
 
 /// Number of updates (ticks) to do per second.
 pub const TPS: u32 = 20;
@@ -34,21 +41,3 @@ pub const TICK_DURATION: Duration = Duration::from_millis(TICK_MILLIS as u64);
 
 /// Default port for Minecraft servers.
 pub const DEFAULT_PORT: u16 = 25565;
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, FromPrimitive, ToPrimitive)]
-pub enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-/// A profile property, which stores metadata
-/// for some player's account. This is usually
-/// used to store skin data.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct ProfileProperty {
-    pub name: String,
-    pub value: String,
-    pub signature: String,
-}
@@ -0,0 +1,563 @@
+//! Conversions between NBT values and their text
+//! representations: SNBT (the "stringified" NBT used in
+//! commands) and JSON.
+//!
+//! JSON has fewer types than NBT, so [`to_json`] keeps the
+//! exact type of each value:
+//! * `Int`s and `Double`s become JSON numbers.
+//! * Other numbers and arrays become strings holding their
+//!   SNBT representation, e.g. `"1b"`, `"5L"` or `"[B;1b,2b]"`.
+//! * Strings which would be mistaken for one of the above,
+//!   or which start with a backslash, are escaped with a
+//!   leading backslash.
+//!
+//! [`from_json`] reverses this, so any NBT value survives
+//! the round trip.
+
+use std::{convert::TryFrom, fmt::Write, mem};
+
+use nbt::Value;
+use serde_json::Number;
+
+/// Marks a JSON string as a plain NBT string.
+const JSON_STRING_ESCAPE: char = '\\';
+
+/// Error returned when converting text to NBT fails.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ConversionError {
+    #[error("expected {expected} at position {position}")]
+    Expected {
+        expected: &'static str,
+        position: usize,
+    },
+    #[error("unexpected trailing data at position {0}")]
+    TrailingData(usize),
+    #[error("invalid element in {0} array at position {1}")]
+    InvalidArrayElement(&'static str, usize),
+    #[error("list elements must all have the same type")]
+    MixedList,
+    #[error("JSON null has no NBT equivalent")]
+    Null,
+    #[error("number {0} cannot be stored in NBT")]
+    InvalidNumber(String),
+}
+
+/// Formats a value as SNBT, e.g. `{Count:1b,id:"minecraft:stone"}`.
+///
+/// Compound keys are sorted so the output is deterministic.
+pub fn to_snbt(value: &Value) -> String {
+    let mut out = String::new();
+    write_snbt(value, &mut out);
+    out
+}
+
+/// Parses a value from SNBT.
+pub fn from_snbt(snbt: &str) -> Result<Value, ConversionError> {
+    let mut parser = Parser::new(snbt);
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != snbt.len() {
+        return Err(ConversionError::TrailingData(parser.pos));
+    }
+    Ok(value)
+}
+
+/// Converts a value to JSON, keeping its exact type.
+/// See the [module documentation](self) for the format.
+pub fn to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Int(v) => serde_json::Value::from(*v),
+        Value::Double(v) => match Number::from_f64(*v) {
+            Some(number) => serde_json::Value::Number(number),
+            // JSON has no infinity or NaN.
+            None => serde_json::Value::String(to_snbt(value)),
+        },
+        Value::String(s) => {
+            if s.starts_with(JSON_STRING_ESCAPE) || typed_from_json_string(s).is_some() {
+                serde_json::Value::String(format!("{}{}", JSON_STRING_ESCAPE, s))
+            } else {
+                serde_json::Value::String(s.clone())
+            }
+        }
+        Value::List(values) => serde_json::Value::Array(values.iter().map(to_json).collect()),
+        Value::Compound(compound) => serde_json::Value::Object(
+            compound
+                .iter()
+                .map(|(key, value)| (key.clone(), to_json(value)))
+                .collect(),
+        ),
+        Value::Byte(_)
+        | Value::Short(_)
+        | Value::Long(_)
+        | Value::Float(_)
+        | Value::ByteArray(_)
+        | Value::IntArray(_)
+        | Value::LongArray(_) => serde_json::Value::String(to_snbt(value)),
+    }
+}
+
+/// Converts JSON produced by [`to_json`] back to NBT.
+///
+/// Other JSON is accepted too: booleans become bytes
+/// and integers too large for an `Int` become `Long`s.
+pub fn from_json(json: &serde_json::Value) -> Result<Value, ConversionError> {
+    Ok(match json {
+        serde_json::Value::Null => return Err(ConversionError::Null),
+        serde_json::Value::Bool(b) => Value::Byte(*b as i8),
+        serde_json::Value::Number(number) => {
+            if let Some(v) = number.as_i64() {
+                i32::try_from(v).map_or(Value::Long(v), Value::Int)
+            } else if number.is_f64() {
+                Value::Double(number.as_f64().unwrap_or_default())
+            } else {
+                return Err(ConversionError::InvalidNumber(number.to_string()));
+            }
+        }
+        serde_json::Value::String(s) => match s.strip_prefix(JSON_STRING_ESCAPE) {
+            Some(s) => Value::String(s.to_owned()),
+            None => typed_from_json_string(s).unwrap_or_else(|| Value::String(s.clone())),
+        },
+        serde_json::Value::Array(values) => Value::List(check_list(
+            values.iter().map(from_json).collect::<Result<_, _>>()?,
+        )?),
+        serde_json::Value::Object(object) => Value::Compound(
+            object
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), from_json(value)?)))
+                .collect::<Result<_, ConversionError>>()?,
+        ),
+    })
+}
+
+/// Parses a JSON string holding a number with a type
+/// suffix or an array, as written by [`to_json`].
+fn typed_from_json_string(s: &str) -> Option<Value> {
+    if s.starts_with("[B;") || s.starts_with("[I;") || s.starts_with("[L;") {
+        from_snbt(s).ok()
+    } else {
+        parse_suffixed_number(s)
+    }
+}
+
+fn write_snbt(value: &Value, out: &mut String) {
+    match value {
+        Value::Byte(v) => write!(out, "{}b", v).unwrap(),
+        Value::Short(v) => write!(out, "{}s", v).unwrap(),
+        Value::Int(v) => write!(out, "{}", v).unwrap(),
+        Value::Long(v) => write!(out, "{}L", v).unwrap(),
+        // Debug formatting always includes a decimal point.
+        Value::Float(v) => write!(out, "{:?}f", v).unwrap(),
+        Value::Double(v) => write!(out, "{:?}d", v).unwrap(),
+        Value::ByteArray(values) => write_array(out, "B", values.iter().map(|v| format!("{}b", v))),
+        Value::IntArray(values) => write_array(out, "I", values.iter().map(|v| v.to_string())),
+        Value::LongArray(values) => write_array(out, "L", values.iter().map(|v| format!("{}L", v))),
+        Value::String(s) => write_quoted(s, out),
+        Value::List(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write_snbt(value, out);
+            }
+            out.push(']');
+        }
+        Value::Compound(compound) => {
+            let mut entries: Vec<_> = compound.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                if !key.is_empty() && key.chars().all(is_unquoted_char) {
+                    out.push_str(key);
+                } else {
+                    write_quoted(key, out);
+                }
+                out.push(':');
+                write_snbt(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_array(out: &mut String, kind: &str, values: impl Iterator<Item = String>) {
+    write!(out, "[{};", kind).unwrap();
+    for (i, value) in values.enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        out.push_str(&value);
+    }
+    out.push(']');
+}
+
+fn write_quoted(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+/// Characters allowed in unquoted strings and keys.
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+/// Parses an unquoted number with a type suffix, e.g. `1b` or `2.5f`.
+fn parse_suffixed_number(token: &str) -> Option<Value> {
+    let suffix = token.chars().last()?;
+    let number = &token[..token.len() - suffix.len_utf8()];
+    if !is_number(number) {
+        return None;
+    }
+    match suffix.to_ascii_lowercase() {
+        'b' => number.parse().ok().map(Value::Byte),
+        's' => number.parse().ok().map(Value::Short),
+        'l' => number.parse().ok().map(Value::Long),
+        'f' => number.parse().ok().map(Value::Float),
+        'd' => number.parse().ok().map(Value::Double),
+        _ => None,
+    }
+}
+
+/// Checks that `s` looks like a number, so that
+/// words such as `inf` are kept as strings.
+fn is_number(s: &str) -> bool {
+    s.chars().any(|c| c.is_ascii_digit())
+        && s.chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+}
+
+/// Interprets an unquoted SNBT token.
+fn parse_unquoted(token: &str) -> Value {
+    match token {
+        "true" => return Value::Byte(1),
+        "false" => return Value::Byte(0),
+        _ => {}
+    }
+    if let Some(value) = parse_suffixed_number(token) {
+        return value;
+    }
+    if is_number(token) {
+        if let Ok(v) = token.parse() {
+            return Value::Int(v);
+        }
+        if let Ok(v) = token.parse() {
+            return Value::Double(v);
+        }
+    }
+    Value::String(token.to_owned())
+}
+
+/// Ensures the elements of a list have the same type.
+fn check_list(values: Vec<Value>) -> Result<Vec<Value>, ConversionError> {
+    if let Some(first) = values.first() {
+        let kind = mem::discriminant(first);
+        if values.iter().any(|value| mem::discriminant(value) != kind) {
+            return Err(ConversionError::MixedList);
+        }
+    }
+    Ok(values)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes `c` after any whitespace, returning whether it was there.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), ConversionError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.expected(expected))
+        }
+    }
+
+    fn expected(&self, expected: &'static str) -> ConversionError {
+        ConversionError::Expected {
+            expected,
+            position: self.pos,
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ConversionError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.compound(),
+            Some('[') => self.list_or_array(),
+            Some('"') | Some('\'') => self.quoted().map(Value::String),
+            _ => Ok(parse_unquoted(self.unquoted("a value")?)),
+        }
+    }
+
+    fn compound(&mut self) -> Result<Value, ConversionError> {
+        self.expect('{', "'{'")?;
+        let mut entries = Vec::new();
+        if !self.eat('}') {
+            loop {
+                self.skip_whitespace();
+                let key = match self.peek() {
+                    Some('"') | Some('\'') => self.quoted()?,
+                    _ => self.unquoted("a key")?.to_owned(),
+                };
+                self.expect(':', "':'")?;
+                entries.push((key, self.value()?));
+                if self.eat('}') {
+                    break;
+                }
+                self.expect(',', "',' or '}'")?;
+            }
+        }
+        Ok(Value::Compound(entries.into_iter().collect()))
+    }
+
+    fn list_or_array(&mut self) -> Result<Value, ConversionError> {
+        self.expect('[', "'['")?;
+        let rest = self.rest();
+        for &kind in &["B", "I", "L"] {
+            if rest.starts_with(kind) && rest[1..].trim_start().starts_with(';') {
+                self.pos += 1;
+                self.expect(';', "';'")?;
+                return self.array(kind);
+            }
+        }
+
+        let mut values = Vec::new();
+        if !self.eat(']') {
+            loop {
+                values.push(self.value()?);
+                if self.eat(']') {
+                    break;
+                }
+                self.expect(',', "',' or ']'")?;
+            }
+        }
+        Ok(Value::List(check_list(values)?))
+    }
+
+    fn array(&mut self, kind: &'static str) -> Result<Value, ConversionError> {
+        let mut bytes = Vec::new();
+        let mut ints = Vec::new();
+        let mut longs = Vec::new();
+        if !self.eat(']') {
+            loop {
+                self.skip_whitespace();
+                let position = self.pos;
+                match (kind, self.value()?) {
+                    ("B", Value::Byte(v)) => bytes.push(v),
+                    ("I", Value::Int(v)) => ints.push(v),
+                    ("L", Value::Long(v)) => longs.push(v),
+                    _ => return Err(ConversionError::InvalidArrayElement(kind, position)),
+                }
+                if self.eat(']') {
+                    break;
+                }
+                self.expect(',', "',' or ']'")?;
+            }
+        }
+        Ok(match kind {
+            "B" => Value::ByteArray(bytes),
+            "I" => Value::IntArray(ints),
+            _ => Value::LongArray(longs),
+        })
+    }
+
+    fn unquoted(&mut self, expected: &'static str) -> Result<&'a str, ConversionError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !is_unquoted_char(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.expected(expected));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn quoted(&mut self) -> Result<String, ConversionError> {
+        let quote = self.peek().ok_or_else(|| self.expected("a string"))?;
+        self.pos += 1;
+        let mut s = String::new();
+        let mut escaped = false;
+        for (i, c) in self.rest().char_indices() {
+            if escaped {
+                s.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                self.pos += i + 1;
+                return Ok(s);
+            } else {
+                s.push(c);
+            }
+        }
+        self.pos = self.input.len();
+        Err(self.expected("closing quote"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compound(entries: Vec<(&str, Value)>) -> Value {
+        Value::Compound(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    fn sample() -> Value {
+        compound(vec![
+            ("byte", Value::Byte(-1)),
+            ("short", Value::Short(300)),
+            ("int", Value::Int(70000)),
+            ("long", Value::Long(1 << 40)),
+            ("float", Value::Float(1.5)),
+            ("double", Value::Double(-2.0)),
+            ("bytes", Value::ByteArray(vec![1, -2])),
+            ("ints", Value::IntArray(vec![])),
+            ("longs", Value::LongArray(vec![3])),
+            ("string", Value::String("say \"hi\"".to_owned())),
+            ("looks typed", Value::String("1b".to_owned())),
+            ("escaped", Value::String("\\1".to_owned())),
+            ("list", Value::List(vec![Value::Short(1), Value::Short(2)])),
+            (
+                "nested",
+                compound(vec![("id", Value::String("minecraft:stone".to_owned()))]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn snbt_round_trip() {
+        let snbt = to_snbt(&sample());
+        assert_eq!(from_snbt(&snbt).unwrap(), sample());
+    }
+
+    #[test]
+    fn format_snbt() {
+        let value = compound(vec![
+            ("Count", Value::Byte(1)),
+            ("id", Value::String("minecraft:stone".to_owned())),
+            ("my key", Value::LongArray(vec![1, 2])),
+        ]);
+        assert_eq!(
+            to_snbt(&value),
+            r#"{Count:1b,id:"minecraft:stone","my key":[L;1L,2L]}"#
+        );
+    }
+
+    #[test]
+    fn parse_snbt() {
+        assert_eq!(
+            from_snbt("{ a : [1.5, 2e3] , 'b\\'c': true, d: stone, e: 2147483648 }").unwrap(),
+            compound(vec![
+                (
+                    "a",
+                    Value::List(vec![Value::Double(1.5), Value::Double(2000.0)])
+                ),
+                ("b'c", Value::Byte(1)),
+                ("d", Value::String("stone".to_owned())),
+                ("e", Value::Double(2147483648.0)),
+            ])
+        );
+        assert_eq!(
+            from_snbt("[B; 1b, 2B]").unwrap(),
+            Value::ByteArray(vec![1, 2])
+        );
+        assert_eq!(from_snbt("inf").unwrap(), Value::String("inf".to_owned()));
+    }
+
+    #[test]
+    fn invalid_snbt() {
+        assert_eq!(from_snbt("[1, 2b]"), Err(ConversionError::MixedList));
+        assert_eq!(
+            from_snbt("[I;1,2L]"),
+            Err(ConversionError::InvalidArrayElement("I", 5))
+        );
+        assert_eq!(from_snbt("{a:1} x"), Err(ConversionError::TrailingData(6)));
+        assert_eq!(
+            from_snbt("{a:1"),
+            Err(ConversionError::Expected {
+                expected: "',' or '}'",
+                position: 4
+            })
+        );
+        assert!(from_snbt("\"unterminated").is_err());
+    }
+
+    #[test]
+    fn json_keeps_types() {
+        let json = to_json(&sample());
+        assert_eq!(json["byte"], "-1b");
+        assert_eq!(json["int"], 70000);
+        assert_eq!(json["double"], -2.0);
+        assert_eq!(json["bytes"], "[B;1b,-2b]");
+        assert_eq!(json["looks typed"], "\\1b");
+        assert_eq!(json["escaped"], "\\\\1");
+        assert_eq!(from_json(&json).unwrap(), sample());
+
+        // Survives serialization to text, too.
+        let text = serde_json::to_string(&json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(from_json(&json).unwrap(), sample());
+    }
+
+    #[test]
+    fn plain_json() {
+        let json = serde_json::json!({"on": true, "big": 1u64 << 40, "name": "Steve"});
+        assert_eq!(
+            from_json(&json).unwrap(),
+            compound(vec![
+                ("on", Value::Byte(1)),
+                ("big", Value::Long(1 << 40)),
+                ("name", Value::String("Steve".to_owned())),
+            ])
+        );
+        assert_eq!(
+            from_json(&serde_json::json!([1, null])),
+            Err(ConversionError::Null)
+        );
+    }
+}
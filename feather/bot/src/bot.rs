@@ -0,0 +1,408 @@
+use std::{io::Cursor, net::SocketAddr};
+
+use anyhow::{bail, Context};
+use base::{ChunkPosition, Position, Text};
+use bytes::Bytes;
+use protocol::{
+    packets::{
+        client::{
+            ChatMessage, Handshake, HandshakeState, KeepAlive, LoginPluginResponse, LoginStart,
+            PlayerPositionAndRotation, TeleportConfirm,
+        },
+        server::{ChunkData, UpdateLight},
+    },
+    ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, MinecraftCodec, PositionDelta,
+    ProtocolVersion, Readable, ServerLoginPacket, ServerPlayPacket, VarInt, VariantOf, Writeable,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use uuid::Uuid;
+
+use crate::world::{TrackedEntity, World};
+
+/// Something that happened to a bot, returned by [`Bot::next_event`].
+#[derive(Debug, Clone)]
+pub enum Event {
+    ChatMessage(Text),
+    /// The server moved the bot.
+    Teleported(Position),
+    EntitySpawned(TrackedEntity),
+    EntityMoved {
+        id: i32,
+        position: Position,
+    },
+    EntitiesDespawned(Vec<TrackedEntity>),
+    ChunkLoaded(ChunkPosition),
+    ChunkUnloaded(ChunkPosition),
+    /// The server kicked the bot. The connection is closed.
+    Disconnected(Text),
+    /// A packet the bot doesn't handle itself.
+    Other(ServerPlayPacket),
+}
+
+/// A client connected to a server in offline mode.
+pub struct Bot {
+    stream: TcpStream,
+    codec: MinecraftCodec,
+    received: Box<[u8]>,
+    /// Encoded packets which haven't been written yet.
+    send_buffer: Vec<u8>,
+    /// Event handled but not yet returned by `next_event`.
+    ready_event: Option<Event>,
+
+    username: String,
+    uuid: Uuid,
+    entity_id: i32,
+    world: World,
+}
+
+impl Bot {
+    /// Connects to the server at `addr` and logs in as `username`,
+    /// returning once the bot has joined the game.
+    ///
+    /// Fails on servers in online mode, since bots can't
+    /// authenticate with Mojang.
+    pub async fn connect(addr: SocketAddr, username: &str) -> anyhow::Result<Self> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .with_context(|| format!("failed to connect to {}", addr))?;
+        stream.set_nodelay(true)?;
+
+        let mut bot = Self {
+            stream,
+            codec: MinecraftCodec::new(),
+            received: vec![0; 8192].into_boxed_slice(),
+            send_buffer: Vec::new(),
+            ready_event: None,
+            username: username.to_owned(),
+            uuid: Uuid::nil(),
+            entity_id: 0,
+            world: World::default(),
+        };
+        bot.login(addr).await?;
+        bot.join().await?;
+        log::debug!("{} joined {}", bot.username, addr);
+        Ok(bot)
+    }
+
+    async fn login(&mut self, addr: SocketAddr) -> anyhow::Result<()> {
+        self.send_packet(&ClientHandshakePacket::Handshake(Handshake {
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
+            server_address: addr.ip().to_string(),
+            server_port: addr.port(),
+            next_state: HandshakeState::Login,
+        }))
+        .await?;
+        self.send_packet(&ClientLoginPacket::LoginStart(LoginStart {
+            name: self.username.clone(),
+        }))
+        .await?;
+
+        loop {
+            let frame = self.read_frame().await?;
+            match ServerLoginPacket::read(&mut Cursor::new(&frame[..]), self.codec.version())? {
+                ServerLoginPacket::SetCompression(packet) => {
+                    self.codec
+                        .enable_compression(packet.threshold.max(0) as usize);
+                }
+                ServerLoginPacket::LoginPluginRequest(request) => {
+                    self.send_packet(&ClientLoginPacket::LoginPluginResponse(
+                        LoginPluginResponse {
+                            message_id: request.message_id,
+                            successful: false,
+                            data: Vec::new(),
+                        },
+                    ))
+                    .await?;
+                }
+                ServerLoginPacket::LoginSuccess(packet) => {
+                    self.uuid = packet.uuid;
+                    self.username = packet.username;
+                    return Ok(());
+                }
+                ServerLoginPacket::EncryptionRequest(_) => {
+                    bail!("server is in online mode, which bots don't support")
+                }
+                ServerLoginPacket::DisconnectLogin(packet) => {
+                    bail!(
+                        "kicked while logging in: {}",
+                        packet.reason.to_plain_string()
+                    )
+                }
+            }
+        }
+    }
+
+    /// Waits for the Join Game packet.
+    async fn join(&mut self) -> anyhow::Result<()> {
+        loop {
+            match self.next_event().await? {
+                Event::Other(ServerPlayPacket::JoinGame(packet)) => {
+                    self.entity_id = packet.entity_id;
+                    return Ok(());
+                }
+                Event::Disconnected(reason) => {
+                    bail!("kicked while joining: {}", reason.to_plain_string())
+                }
+                event => log::debug!("{} received {:?} before joining", self.username, event),
+            }
+        }
+    }
+
+    /// The name the server gave the bot.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// The bot's network entity ID.
+    pub fn entity_id(&self) -> i32 {
+        self.entity_id
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The bot's position, as last set by the server or by [`Bot::move_to`].
+    pub fn position(&self) -> Position {
+        self.world.position()
+    }
+
+    /// Waits for the next event.
+    ///
+    /// Keep alives and teleports are answered
+    /// automatically, so this needs to be called
+    /// regularly to stay connected.
+    ///
+    /// This is cancel safe: it can be used in `tokio::select!`
+    /// to send packets while waiting for events.
+    pub async fn next_event(&mut self) -> anyhow::Result<Event> {
+        loop {
+            // Replies to the last packet are written
+            // before the event is returned.
+            self.flush().await?;
+            if let Some(event) = self.ready_event.take() {
+                return Ok(event);
+            }
+            let frame = self.read_frame().await?;
+            self.ready_event = self.handle_frame(frame)?;
+        }
+    }
+
+    fn handle_frame(&mut self, frame: Bytes) -> anyhow::Result<Option<Event>> {
+        let version = self.codec.version();
+        let mut cursor = Cursor::new(&frame[..]);
+
+        // Chunk data isn't decoded by the protocol crate yet,
+        // so only the fields at its start are read.
+        let id = VarInt::read(&mut cursor, version)?.0;
+        if id as u32 == <ChunkData as VariantOf<ServerPlayPacket>>::discriminant_id() {
+            let x = i32::read(&mut cursor, version)?;
+            let z = i32::read(&mut cursor, version)?;
+            let full_chunk = bool::read(&mut cursor, version)?;
+            if !full_chunk {
+                // Sections of an already loaded chunk were overwritten.
+                return Ok(None);
+            }
+            let chunk = ChunkPosition::new(x, z);
+            self.world.load_chunk(chunk);
+            return Ok(Some(Event::ChunkLoaded(chunk)));
+        }
+        if id as u32 == <UpdateLight as VariantOf<ServerPlayPacket>>::discriminant_id() {
+            // Bots don't track light, and the protocol
+            // crate can't decode the packet.
+            return Ok(None);
+        }
+
+        cursor.set_position(0);
+        let event = match ServerPlayPacket::read(&mut cursor, version)? {
+            ServerPlayPacket::KeepAlive(packet) => {
+                self.queue_packet(&ClientPlayPacket::KeepAlive(KeepAlive {
+                    id: packet.id as u64,
+                }));
+                return Ok(None);
+            }
+            ServerPlayPacket::PlayerPositionAndLook(packet) => {
+                let position = self.world.teleport(&packet);
+                self.queue_packet(&ClientPlayPacket::TeleportConfirm(TeleportConfirm {
+                    teleport_id: packet.teleport_id,
+                }));
+                Event::Teleported(position)
+            }
+            ServerPlayPacket::ChatMessage(packet) => Event::ChatMessage(packet.message),
+            ServerPlayPacket::SpawnPlayer(packet) => self.spawn(TrackedEntity {
+                id: packet.entity_id,
+                uuid: packet.player_uuid,
+                kind: None,
+                position: Position {
+                    x: packet.x,
+                    y: packet.y,
+                    z: packet.z,
+                    yaw: packet.yaw,
+                    pitch: packet.pitch,
+                },
+            }),
+            ServerPlayPacket::SpawnLivingEntity(packet) => self.spawn(TrackedEntity {
+                id: packet.entity_id,
+                uuid: packet.entity_uuid,
+                kind: Some(packet.kind),
+                position: Position {
+                    x: packet.x,
+                    y: packet.y,
+                    z: packet.z,
+                    yaw: packet.yaw,
+                    pitch: packet.pitch,
+                },
+            }),
+            ServerPlayPacket::SpawnEntity(packet) => self.spawn(TrackedEntity {
+                id: packet.entity_id,
+                uuid: packet.uuid,
+                kind: Some(packet.kind),
+                position: Position {
+                    x: packet.x,
+                    y: packet.y,
+                    z: packet.z,
+                    yaw: packet.yaw,
+                    pitch: packet.pitch,
+                },
+            }),
+            ServerPlayPacket::EntityTeleport(packet) => {
+                let position = Position {
+                    x: packet.x,
+                    y: packet.y,
+                    z: packet.z,
+                    yaw: packet.yaw,
+                    pitch: packet.pitch,
+                };
+                match self.world.move_entity_to(packet.entity_id, position) {
+                    Some(position) => Event::EntityMoved {
+                        id: packet.entity_id,
+                        position,
+                    },
+                    None => return Ok(None),
+                }
+            }
+            ServerPlayPacket::EntityPosition(packet) => {
+                let delta = [
+                    PositionDelta(packet.delta_x),
+                    PositionDelta(packet.delta_y),
+                    PositionDelta(packet.delta_z),
+                ];
+                match self.world.move_entity_by(packet.entity_id, delta, None) {
+                    Some(position) => Event::EntityMoved {
+                        id: packet.entity_id,
+                        position,
+                    },
+                    None => return Ok(None),
+                }
+            }
+            ServerPlayPacket::EntityPositionAndRotation(packet) => {
+                let delta = [
+                    PositionDelta(packet.delta_x),
+                    PositionDelta(packet.delta_y),
+                    PositionDelta(packet.delta_z),
+                ];
+                let rotation = Some((packet.yaw, packet.pitch));
+                match self.world.move_entity_by(packet.entity_id, delta, rotation) {
+                    Some(position) => Event::EntityMoved {
+                        id: packet.entity_id,
+                        position,
+                    },
+                    None => return Ok(None),
+                }
+            }
+            ServerPlayPacket::DestroyEntities(packet) => Event::EntitiesDespawned(
+                packet
+                    .entity_ids
+                    .iter()
+                    .filter_map(|&id| self.world.despawn(id))
+                    .collect(),
+            ),
+            ServerPlayPacket::UnloadChunk(packet) => {
+                let chunk = ChunkPosition::new(packet.chunk_x, packet.chunk_z);
+                self.world.unload_chunk(chunk);
+                Event::ChunkUnloaded(chunk)
+            }
+            ServerPlayPacket::Disconnect(packet) => Event::Disconnected(packet.reason),
+            packet => Event::Other(packet),
+        };
+        Ok(Some(event))
+    }
+
+    fn spawn(&mut self, entity: TrackedEntity) -> Event {
+        self.world.spawn(entity);
+        Event::EntitySpawned(entity)
+    }
+
+    /// Sends a chat message, or runs a command if it starts with `/`.
+    pub async fn send_chat(&mut self, message: impl Into<String>) -> anyhow::Result<()> {
+        self.send_play_packet(ChatMessage {
+            message: message.into(),
+        })
+        .await
+    }
+
+    /// Moves the bot to `position`. The server may
+    /// teleport it back if the movement is invalid.
+    pub async fn move_to(&mut self, position: Position) -> anyhow::Result<()> {
+        self.world.set_position(position);
+        self.send_play_packet(PlayerPositionAndRotation {
+            x: position.x,
+            feet_y: position.y,
+            z: position.z,
+            yaw: position.yaw,
+            pitch: position.pitch,
+            on_ground: true,
+        })
+        .await
+    }
+
+    /// Sends a play packet to the server.
+    pub async fn send_play_packet(
+        &mut self,
+        packet: impl Into<ClientPlayPacket>,
+    ) -> anyhow::Result<()> {
+        self.send_packet(&packet.into()).await
+    }
+
+    async fn send_packet(&mut self, packet: &impl Writeable) -> anyhow::Result<()> {
+        self.queue_packet(packet);
+        self.flush().await
+    }
+
+    fn queue_packet(&mut self, packet: &impl Writeable) {
+        self.codec.encode(packet, &mut self.send_buffer);
+    }
+
+    /// Writes queued packets. Bytes are removed from the queue as
+    /// they're written, so a cancelled flush resumes where it stopped.
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        while !self.send_buffer.is_empty() {
+            let written = self.stream.write(&self.send_buffer).await?;
+            if written == 0 {
+                bail!("server closed the connection");
+            }
+            self.send_buffer.drain(..written);
+        }
+        Ok(())
+    }
+
+    async fn read_frame(&mut self) -> anyhow::Result<Bytes> {
+        loop {
+            if let Some(frame) = self.codec.next_frame()? {
+                return Ok(frame);
+            }
+            let read = self.stream.read(&mut self.received).await?;
+            if read == 0 {
+                bail!("server closed the connection");
+            }
+            self.codec.accept(&self.received[..read]);
+        }
+    }
+}
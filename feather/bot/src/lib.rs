@@ -0,0 +1,33 @@
+//! An async Minecraft client for load testing and automating
+//! servers, built on `feather-protocol`.
+//!
+//! A [`Bot`] logs in to a server in offline mode, answers
+//! keep alives and teleports, and keeps track of the entities
+//! and chunks sent to it. Everything else it receives is
+//! returned from [`Bot::next_event`]. [`ping`] measures
+//! latency to a server without logging in.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use feather_bot::{Bot, Event};
+//!
+//! let mut bot = Bot::connect("127.0.0.1:25565".parse()?, "bot0").await?;
+//! bot.send_chat("Hello!").await?;
+//! loop {
+//!     match bot.next_event().await? {
+//!         Event::ChatMessage(message) => println!("{}", message.to_plain_string()),
+//!         Event::Disconnected(reason) => break println!("kicked: {}", reason.to_plain_string()),
+//!         _ => {}
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod bot;
+mod status;
+mod world;
+
+pub use bot::{Bot, Event};
+pub use status::ping;
+pub use world::{TrackedEntity, World};
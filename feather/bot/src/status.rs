@@ -0,0 +1,69 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use anyhow::bail;
+use protocol::{
+    packets::client::{Handshake, HandshakeState, Ping, Request},
+    ClientHandshakePacket, ClientStatusPacket, MinecraftCodec, ProtocolVersion, ServerStatusPacket,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// Pings the server at `addr` like the server list does,
+/// returning its status response (JSON) and the round-trip
+/// time of the ping.
+///
+/// The ping is answered without waiting for the server's
+/// tick, so the time is close to the network latency.
+pub async fn ping(addr: SocketAddr) -> anyhow::Result<(String, Duration)> {
+    let mut stream = TcpStream::connect(addr).await?;
+    let mut codec = MinecraftCodec::new();
+
+    let mut buffer = Vec::new();
+    codec.encode(
+        &ClientHandshakePacket::Handshake(Handshake {
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
+            server_address: addr.ip().to_string(),
+            server_port: addr.port(),
+            next_state: HandshakeState::Status,
+        }),
+        &mut buffer,
+    );
+    codec.encode(&ClientStatusPacket::Request(Request {}), &mut buffer);
+    stream.write_all(&buffer).await?;
+
+    let status = match read_packet(&mut stream, &mut codec).await? {
+        ServerStatusPacket::Response(response) => response.response,
+        packet => bail!("expected a status response, got {:?}", packet),
+    };
+
+    buffer.clear();
+    codec.encode(&ClientStatusPacket::Ping(Ping { payload: 0 }), &mut buffer);
+    let sent = Instant::now();
+    stream.write_all(&buffer).await?;
+    match read_packet(&mut stream, &mut codec).await? {
+        ServerStatusPacket::Pong(_) => Ok((status, sent.elapsed())),
+        packet => bail!("expected a pong, got {:?}", packet),
+    }
+}
+
+async fn read_packet(
+    stream: &mut TcpStream,
+    codec: &mut MinecraftCodec,
+) -> anyhow::Result<ServerStatusPacket> {
+    let mut received = [0; 1024];
+    loop {
+        if let Some(packet) = codec.next_packet()? {
+            return Ok(packet);
+        }
+        let read = stream.read(&mut received).await?;
+        if read == 0 {
+            bail!("server closed the connection");
+        }
+        codec.accept(&received[..read]);
+    }
+}
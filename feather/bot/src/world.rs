@@ -0,0 +1,169 @@
+use ahash::{AHashMap, AHashSet};
+use base::{ChunkPosition, Position};
+use protocol::{packets::server::PlayerPositionAndLook, PositionDelta};
+use uuid::Uuid;
+
+/// Flags of `PlayerPositionAndLook` marking fields
+/// relative to the current position.
+const RELATIVE_X: u8 = 0x01;
+const RELATIVE_Y: u8 = 0x02;
+const RELATIVE_Z: u8 = 0x04;
+const RELATIVE_YAW: u8 = 0x08;
+const RELATIVE_PITCH: u8 = 0x10;
+
+/// An entity spawned on the bot's client.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrackedEntity {
+    /// Network ID of the entity.
+    pub id: i32,
+    pub uuid: Uuid,
+    /// Entity type ID, or `None` for players.
+    pub kind: Option<i32>,
+    pub position: Position,
+}
+
+/// What a bot knows about the world: its own position,
+/// the entities it sees and the chunks it has loaded.
+#[derive(Debug, Default)]
+pub struct World {
+    position: Position,
+    entities: AHashMap<i32, TrackedEntity>,
+    chunks: AHashSet<ChunkPosition>,
+}
+
+impl World {
+    /// The bot's position, as last set by the server or the bot.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn entity(&self, id: i32) -> Option<&TrackedEntity> {
+        self.entities.get(&id)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = &TrackedEntity> + '_ {
+        self.entities.values()
+    }
+
+    pub fn is_chunk_loaded(&self, chunk: ChunkPosition) -> bool {
+        self.chunks.contains(&chunk)
+    }
+
+    pub fn loaded_chunks(&self) -> impl Iterator<Item = ChunkPosition> + '_ {
+        self.chunks.iter().copied()
+    }
+
+    pub(crate) fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    /// Applies a teleport sent by the server, returning the new position.
+    pub(crate) fn teleport(&mut self, packet: &PlayerPositionAndLook) -> Position {
+        let old = self.position;
+        let relative = |flag: u8, old: f64, new: f64| {
+            if packet.flags & flag != 0 {
+                old + new
+            } else {
+                new
+            }
+        };
+        self.position = Position {
+            x: relative(RELATIVE_X, old.x, packet.x),
+            y: relative(RELATIVE_Y, old.y, packet.y),
+            z: relative(RELATIVE_Z, old.z, packet.z),
+            yaw: relative(RELATIVE_YAW, old.yaw.into(), packet.yaw.into()) as f32,
+            pitch: relative(RELATIVE_PITCH, old.pitch.into(), packet.pitch.into()) as f32,
+        };
+        self.position
+    }
+
+    pub(crate) fn spawn(&mut self, entity: TrackedEntity) {
+        self.entities.insert(entity.id, entity);
+    }
+
+    pub(crate) fn despawn(&mut self, id: i32) -> Option<TrackedEntity> {
+        self.entities.remove(&id)
+    }
+
+    /// Teleports an entity, returning `None` if it isn't tracked.
+    pub(crate) fn move_entity_to(&mut self, id: i32, position: Position) -> Option<Position> {
+        let entity = self.entities.get_mut(&id)?;
+        entity.position = position;
+        Some(position)
+    }
+
+    /// Moves an entity by a relative movement,
+    /// returning `None` if it isn't tracked.
+    pub(crate) fn move_entity_by(
+        &mut self,
+        id: i32,
+        delta: [PositionDelta; 3],
+        rotation: Option<(f32, f32)>,
+    ) -> Option<Position> {
+        let entity = self.entities.get_mut(&id)?;
+        let position = &mut entity.position;
+        position.x += delta[0].blocks();
+        position.y += delta[1].blocks();
+        position.z += delta[2].blocks();
+        if let Some((yaw, pitch)) = rotation {
+            position.yaw = yaw;
+            position.pitch = pitch;
+        }
+        Some(*position)
+    }
+
+    pub(crate) fn load_chunk(&mut self, chunk: ChunkPosition) {
+        self.chunks.insert(chunk);
+    }
+
+    pub(crate) fn unload_chunk(&mut self, chunk: ChunkPosition) {
+        self.chunks.remove(&chunk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn relative_teleports() {
+        let mut world = World::default();
+        world.set_position(position!(10.0, 64.0, 10.0, 0.0, 90.0));
+        let position = world.teleport(&PlayerPositionAndLook {
+            x: 1.5,
+            y: 70.0,
+            z: -2.0,
+            yaw: 0.0,
+            pitch: 10.0,
+            flags: RELATIVE_X | RELATIVE_Z | RELATIVE_YAW,
+            teleport_id: 1,
+        });
+        assert_eq!(position, position!(11.5, 70.0, 8.0, 10.0, 90.0));
+    }
+
+    #[test]
+    fn tracks_entity_movement() {
+        let mut world = World::default();
+        world.spawn(TrackedEntity {
+            id: 5,
+            uuid: Uuid::nil(),
+            kind: None,
+            position: position!(0.0, 64.0, 0.0),
+        });
+        let delta = [
+            PositionDelta::between(0.0, 1.0).unwrap(),
+            PositionDelta(0),
+            PositionDelta::between(0.0, -0.5).unwrap(),
+        ];
+        assert_eq!(
+            world.move_entity_by(5, delta, None),
+            Some(position!(1.0, 64.0, -0.5))
+        );
+        assert_eq!(world.move_entity_by(6, delta, None), None);
+
+        assert!(world.despawn(5).is_some());
+        assert_eq!(world.entities().count(), 0);
+    }
+}
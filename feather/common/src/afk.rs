@@ -0,0 +1,83 @@
+//! AFK (away from keyboard) detection.
+//!
+//! The server calls [`record_activity`] when a player does
+//! something, like moving or chatting. Players inactive for
+//! longer than the configured timeout get `Afk(true)` and an
+//! [`AfkChangeEvent`]; any activity clears it again.
+//!
+//! Gameplay systems should use [`is_afk`] to go easy on AFK
+//! players, e.g. to leave them out of mob targeting.
+
+use std::time::Duration;
+
+use base::TPS;
+use ecs::{Entity, EntityRef, SysResult, SystemExecutor};
+use quill_common::components::Afk;
+
+use crate::{events::AfkChangeEvent, Game};
+
+/// Ticks between checks for inactive players.
+const CHECK_INTERVAL: u64 = TPS as u64;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(AfkSettings::default());
+    systems.add_system(detect_afk_players);
+}
+
+/// Resource configuring AFK detection.
+#[derive(Debug, Default)]
+pub struct AfkSettings {
+    /// Ticks of inactivity after which a player
+    /// becomes AFK, or `None` to never mark them AFK.
+    timeout_ticks: Option<u64>,
+}
+
+impl AfkSettings {
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout_ticks = timeout.map(|timeout| timeout.as_secs() * TPS as u64);
+    }
+}
+
+/// Component storing the tick of a player's last activity.
+#[derive(Copy, Clone, Debug)]
+pub struct LastActivity(pub u64);
+
+/// Returns whether `entity` is an AFK player.
+pub fn is_afk(entity: &EntityRef) -> bool {
+    entity.get::<Afk>().map(|afk| afk.0).unwrap_or(false)
+}
+
+/// Records that `player` did something, bringing
+/// them back if they were AFK.
+pub fn record_activity(game: &mut Game, player: Entity) -> SysResult {
+    game.ecs.get_mut::<LastActivity>(player)?.0 = game.tick_count;
+    let was_afk = std::mem::replace(&mut game.ecs.get_mut::<Afk>(player)?.0, false);
+    if was_afk {
+        game.ecs
+            .insert_entity_event(player, AfkChangeEvent { afk: false })?;
+    }
+    Ok(())
+}
+
+fn detect_afk_players(game: &mut Game) -> SysResult {
+    if game.tick_count % CHECK_INTERVAL != 0 {
+        return Ok(());
+    }
+    let timeout = match game.resources.get::<AfkSettings>()?.timeout_ticks {
+        Some(timeout) => timeout,
+        None => return Ok(()),
+    };
+
+    let mut now_afk = Vec::new();
+    for (player, (last_activity, afk)) in game.ecs.query::<(&LastActivity, &mut Afk)>().iter() {
+        if !afk.0 && game.tick_count.saturating_sub(last_activity.0) >= timeout {
+            afk.0 = true;
+            now_afk.push(player);
+        }
+    }
+    for player in now_afk {
+        game.ecs
+            .insert_entity_event(player, AfkChangeEvent { afk: true })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,313 @@
+//! Mob AI goals.
+//!
+//! A mob's behavior is made of [`Goal`]s stored in its [`Goals`]
+//! component. Each tick, goals which can run are started,
+//! most important (lowest priority number) first. A goal can't
+//! start while a more important goal holds one of its
+//! [`GoalControls`]; starting a goal stops any less important
+//! goals holding its controls.
+//!
+//! Mobs spawn with vanilla goals, which plugins can inspect,
+//! remove and add to.
+
+use std::mem;
+
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::goals::{GoalControls, GoalInfo};
+
+use crate::Game;
+
+pub mod vanilla;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.add_entity_spawn_callback(vanilla::add_default_goals);
+    systems.add_system(run_goals);
+}
+
+/// Something a mob does, like looking at a player.
+pub trait Goal: Send + Sync + 'static {
+    /// Returns whether the goal should start this tick.
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> SysResult<bool>;
+
+    /// Returns whether the running goal should keep running.
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> SysResult<bool> {
+        self.can_start(game, entity)
+    }
+
+    fn start(&mut self, _game: &mut Game, _entity: Entity) -> SysResult {
+        Ok(())
+    }
+
+    /// Called each tick while the goal is running.
+    fn tick(&mut self, game: &mut Game, entity: Entity) -> SysResult;
+
+    fn stop(&mut self, _game: &mut Game, _entity: Entity) -> SysResult {
+        Ok(())
+    }
+}
+
+struct GoalEntry {
+    name: String,
+    priority: u32,
+    controls: GoalControls,
+    running: bool,
+    goal: Box<dyn Goal>,
+}
+
+/// Component storing the goals of a mob.
+///
+/// While a mob's goals run, its `Goals` component is
+/// empty; goals added to it meanwhile are kept, but
+/// goals can't see or remove each other.
+#[derive(Default)]
+pub struct Goals {
+    /// Sorted by priority.
+    entries: Vec<GoalEntry>,
+}
+
+impl Goals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a goal. Goals with the same priority
+    /// are considered in the order they were added.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        priority: u32,
+        controls: GoalControls,
+        goal: impl Goal,
+    ) -> &mut Self {
+        self.insert_entry(GoalEntry {
+            name: name.into(),
+            priority,
+            controls,
+            running: false,
+            goal: Box::new(goal),
+        });
+        self
+    }
+
+    fn insert_entry(&mut self, entry: GoalEntry) {
+        let index = self
+            .entries
+            .iter()
+            .position(|existing| existing.priority > entry.priority)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+    }
+
+    /// Removes the goals with the given name,
+    /// returning how many were removed.
+    ///
+    /// Removed goals are not stopped.
+    pub fn remove(&mut self, name: &str) -> usize {
+        let len = self.entries.len();
+        self.entries.retain(|entry| entry.name != name);
+        len - self.entries.len()
+    }
+
+    /// Keeps only the goals whose name matches `keep`.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.entries.retain(|entry| keep(&entry.name));
+    }
+
+    /// Describes the goals, most important first.
+    pub fn infos(&self) -> Vec<GoalInfo> {
+        self.entries
+            .iter()
+            .map(|entry| GoalInfo {
+                name: entry.name.clone(),
+                priority: entry.priority,
+                controls: entry.controls,
+                running: entry.running,
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Moves goals added while this instance
+    /// was detached from its entity.
+    fn merge(&mut self, added: Goals) {
+        for entry in added.entries {
+            self.insert_entry(entry);
+        }
+    }
+
+    /// Runs one tick of goal selection, then ticks the running goals.
+    ///
+    /// A failing goal is logged and stopped
+    /// without affecting the other goals.
+    fn run(&mut self, game: &mut Game, entity: Entity) {
+        for entry in self.entries.iter_mut().filter(|entry| entry.running) {
+            let keep_running = entry.goal.should_continue(game, entity);
+            if !matches!(keep_running, Ok(true)) {
+                log_error(entry, keep_running.map(drop));
+                stop(entry, game, entity);
+            }
+        }
+
+        for i in 0..self.entries.len() {
+            let (priority, controls) = (self.entries[i].priority, self.entries[i].controls);
+            let blocked = self.entries.iter().any(|other| {
+                other.running && other.controls.intersects(controls) && other.priority <= priority
+            });
+            if self.entries[i].running || blocked {
+                continue;
+            }
+            match self.entries[i].goal.can_start(game, entity) {
+                Ok(true) => {}
+                result => {
+                    log_error(&self.entries[i], result.map(drop));
+                    continue;
+                }
+            }
+
+            for other in self.entries.iter_mut() {
+                if other.running && other.controls.intersects(controls) {
+                    stop(other, game, entity);
+                }
+            }
+            let entry = &mut self.entries[i];
+            let result = entry.goal.start(game, entity);
+            entry.running = result.is_ok();
+            log_error(entry, result);
+        }
+
+        for entry in self.entries.iter_mut().filter(|entry| entry.running) {
+            let result = entry.goal.tick(game, entity);
+            if result.is_err() {
+                log_error(entry, result);
+                stop(entry, game, entity);
+            }
+        }
+    }
+}
+
+fn stop(entry: &mut GoalEntry, game: &mut Game, entity: Entity) {
+    entry.running = false;
+    let result = entry.goal.stop(game, entity);
+    log_error(entry, result);
+}
+
+fn log_error(entry: &GoalEntry, result: SysResult) {
+    if let Err(e) = result {
+        log::error!("Goal {} failed: {:?}", entry.name, e);
+    }
+}
+
+fn run_goals(game: &mut Game) -> SysResult {
+    let entities: Vec<Entity> = game
+        .ecs
+        .query::<&Goals>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in entities {
+        // Detach the goals so they can access the game.
+        let mut goals = match game.ecs.get_mut::<Goals>(entity) {
+            Ok(mut goals) => mem::take(&mut *goals),
+            Err(_) => continue,
+        };
+        goals.run(game, entity);
+
+        // The entity may have been despawned by a goal.
+        if let Ok(mut slot) = game.ecs.get_mut::<Goals>(entity) {
+            let added = mem::replace(&mut *slot, goals);
+            slot.merge(added);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Runs while `active` is set, logging its calls.
+    struct TestGoal {
+        name: &'static str,
+        active: Arc<Mutex<bool>>,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Goal for TestGoal {
+        fn can_start(&mut self, _game: &mut Game, _entity: Entity) -> SysResult<bool> {
+            Ok(*self.active.lock().unwrap())
+        }
+
+        fn start(&mut self, _game: &mut Game, _entity: Entity) -> SysResult {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("start {}", self.name));
+            Ok(())
+        }
+
+        fn tick(&mut self, _game: &mut Game, _entity: Entity) -> SysResult {
+            Ok(())
+        }
+
+        fn stop(&mut self, _game: &mut Game, _entity: Entity) -> SysResult {
+            self.log.lock().unwrap().push(format!("stop {}", self.name));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn important_goals_take_controls() {
+        let mut game = Game::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let important_active = Arc::new(Mutex::new(false));
+        let mut goals = Goals::new();
+        goals
+            .insert(
+                "wander",
+                5,
+                GoalControls::MOVE,
+                TestGoal {
+                    name: "wander",
+                    active: Arc::new(Mutex::new(true)),
+                    log: Arc::clone(&log),
+                },
+            )
+            .insert(
+                "flee",
+                1,
+                GoalControls::MOVE | GoalControls::JUMP,
+                TestGoal {
+                    name: "flee",
+                    active: Arc::clone(&important_active),
+                    log: Arc::clone(&log),
+                },
+            );
+        let entity = game.ecs.spawn((goals,));
+
+        run_goals(&mut game).unwrap();
+        *important_active.lock().unwrap() = true;
+        run_goals(&mut game).unwrap();
+        run_goals(&mut game).unwrap();
+        *important_active.lock().unwrap() = false;
+        run_goals(&mut game).unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "start wander",
+                "stop wander",
+                "start flee",
+                "stop flee",
+                "start wander"
+            ]
+        );
+        let infos = game.ecs.get::<Goals>(entity).unwrap().infos();
+        assert_eq!(infos[0].name, "flee");
+        assert!(infos[1].running);
+    }
+}
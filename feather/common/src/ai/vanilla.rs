@@ -0,0 +1,186 @@
+//! Goals mobs have in vanilla.
+
+use std::cmp::Ordering;
+
+use base::{Gamemode, Position};
+use ecs::{Entity, EntityBuilder, SysResult};
+use quill_common::{entities::Player, entity_init::EntityInit, goals::GoalControls};
+use rand::Rng;
+
+use super::{Goal, Goals};
+use crate::{vanish::Vanished, Game};
+
+/// Chance per tick that an idle mob starts looking around.
+const LOOK_CHANCE: f32 = 0.02;
+
+/// Gives mobs their vanilla goals when they spawn.
+pub fn add_default_goals(builder: &mut EntityBuilder, init: &EntityInit) {
+    use EntityInit::*;
+
+    // (look_at_player priority and range, random_look_around priority)
+    let (look_priority, range, look_around_priority) = match init {
+        Blaze | CaveSpider | Creeper | Drowned | Enderman | Endermite | Evoker | Husk
+        | Illusioner | Piglin | PiglinBrute | Pillager | Silverfish | Skeleton | Spider | Stray
+        | Vindicator | Witch | WitherSkeleton | Zombie | ZombieVillager | ZombifiedPiglin => {
+            (8, 8., 8)
+        }
+        Cat | Chicken | Cow | Donkey | Fox | Horse | IronGolem | Llama | Mooshroom | Mule
+        | Ocelot | Panda | Pig | PolarBear | Rabbit | Sheep | SnowGolem | TraderLlama | Turtle
+        | Villager | WanderingTrader | Wolf => (6, 6., 7),
+        _ => return,
+    };
+
+    let mut goals = Goals::new();
+    goals
+        .insert(
+            "look_at_player",
+            look_priority,
+            GoalControls::LOOK,
+            LookAtPlayerGoal::new(range),
+        )
+        .insert(
+            "random_look_around",
+            look_around_priority,
+            GoalControls::MOVE | GoalControls::LOOK,
+            RandomLookAroundGoal::default(),
+        );
+    builder.add(goals);
+}
+
+/// Looks at the nearest player within range for a few seconds.
+pub struct LookAtPlayerGoal {
+    range: f64,
+    target: Option<Entity>,
+    ticks_left: u32,
+}
+
+impl LookAtPlayerGoal {
+    pub fn new(range: f64) -> Self {
+        Self {
+            range,
+            target: None,
+            ticks_left: 0,
+        }
+    }
+
+    fn target_position(&self, game: &Game, entity: Entity) -> SysResult<Option<Position>> {
+        let position = *game.ecs.get::<Position>(entity)?;
+        let target = match self
+            .target
+            .and_then(|target| game.ecs.get::<Position>(target).ok())
+        {
+            Some(target) => *target,
+            None => return Ok(None),
+        };
+        Ok(Some(target).filter(|target| target.distance_to(position) <= self.range))
+    }
+}
+
+impl Goal for LookAtPlayerGoal {
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> SysResult<bool> {
+        if rand::random::<f32>() >= LOOK_CHANCE {
+            return Ok(false);
+        }
+        let position = *game.ecs.get::<Position>(entity)?;
+        self.target = nearest_player(game, position, self.range);
+        Ok(self.target.is_some())
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> SysResult<bool> {
+        Ok(self.ticks_left > 0 && self.target_position(game, entity)?.is_some())
+    }
+
+    fn start(&mut self, _game: &mut Game, _entity: Entity) -> SysResult {
+        self.ticks_left = 40 + rand::thread_rng().gen_range(0..40);
+        Ok(())
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) -> SysResult {
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+        if let Some(target) = self.target_position(game, entity)? {
+            look_at(&mut *game.ecs.get_mut::<Position>(entity)?, target);
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self, _game: &mut Game, _entity: Entity) -> SysResult {
+        self.target = None;
+        Ok(())
+    }
+}
+
+/// Looks in a random direction for a second or two.
+#[derive(Default)]
+pub struct RandomLookAroundGoal {
+    yaw: f32,
+    ticks_left: u32,
+}
+
+impl Goal for RandomLookAroundGoal {
+    fn can_start(&mut self, _game: &mut Game, _entity: Entity) -> SysResult<bool> {
+        Ok(rand::random::<f32>() < LOOK_CHANCE)
+    }
+
+    fn should_continue(&mut self, _game: &mut Game, _entity: Entity) -> SysResult<bool> {
+        Ok(self.ticks_left > 0)
+    }
+
+    fn start(&mut self, _game: &mut Game, _entity: Entity) -> SysResult {
+        let mut rng = rand::thread_rng();
+        self.yaw = rng.gen_range(-180.0..180.0);
+        self.ticks_left = 20 + rng.gen_range(0..20);
+        Ok(())
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) -> SysResult {
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+        let mut position = game.ecs.get_mut::<Position>(entity)?;
+        position.yaw = self.yaw;
+        position.pitch = 0.;
+        Ok(())
+    }
+}
+
+/// Finds the nearest player within `range` who is
+/// visible to mobs, i.e. not a spectator and not vanished.
+fn nearest_player(game: &Game, position: Position, range: f64) -> Option<Entity> {
+    game.ecs
+        .query::<(&Position, Option<&Gamemode>, &Player)>()
+        .iter()
+        .filter(|(_, (_, gamemode, _))| gamemode.copied() != Some(Gamemode::Spectator))
+        .filter(|&(player, _)| game.ecs.get::<Vanished>(player).is_err())
+        .map(|(player, (player_position, _, _))| (player, player_position.distance_to(position)))
+        .filter(|&(_, distance)| distance <= range)
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(player, _)| player)
+}
+
+/// Rotates `position` to face `target`.
+fn look_at(position: &mut Position, target: Position) {
+    let (dx, dy, dz) = (
+        target.x - position.x,
+        target.y - position.y,
+        target.z - position.z,
+    );
+    position.yaw = (-dx).atan2(dz).to_degrees() as f32;
+    position.pitch = (-dy).atan2((dx * dx + dz * dz).sqrt()).to_degrees() as f32;
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn look_at_faces_target() {
+        let mut position = position!(0.0, 64.0, 0.0);
+        look_at(&mut position, position!(-5.0, 64.0, 0.0));
+        assert!((position.yaw - 90.).abs() < 1e-4);
+        assert!(position.pitch.abs() < 1e-4);
+
+        look_at(&mut position, position!(0.0, 69.0, 5.0));
+        assert!(position.yaw.abs() < 1e-4);
+        assert!((position.pitch + 45.).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,440 @@
+//! Beacons: pyramid scanning, effect selection, beam
+//! tinting and periodic effect application.
+//!
+//! Selecting effects consumes one payment item from
+//! the beacon window.
+
+use std::sync::Arc;
+
+use ahash::AHashMap;
+use base::{Area, BlockPosition, Inventory, Position};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::entities::Player;
+
+use crate::{
+    effects::{add_effect, ActiveEffect, StatusEffect},
+    interactable::InteractableRegistry,
+    window::{self, BackingWindow, Window},
+    Game,
+};
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(Beacons::default());
+    game.resources
+        .get_mut::<InteractableRegistry>()
+        .expect("interactable registry not registered")
+        .register(BlockKind::Beacon);
+    systems.add_system(apply_beacon_effects);
+}
+
+/// Ticks between two applications of beacon effects.
+pub const BEACON_EFFECT_PERIOD: u64 = 80;
+
+/// Highest pyramid level.
+pub const MAX_LEVEL: u8 = 4;
+
+/// Blocks a beacon pyramid can be built from.
+pub const BASE_BLOCKS: [BlockKind; 5] = [
+    BlockKind::IronBlock,
+    BlockKind::GoldBlock,
+    BlockKind::EmeraldBlock,
+    BlockKind::DiamondBlock,
+    BlockKind::NetheriteBlock,
+];
+
+/// Returns the lowest pyramid level at which an effect
+/// can be selected as the primary effect.
+pub fn primary_effect_level(effect: StatusEffect) -> Option<u8> {
+    match effect {
+        StatusEffect::Speed | StatusEffect::Haste => Some(1),
+        StatusEffect::Resistance | StatusEffect::JumpBoost => Some(2),
+        StatusEffect::Strength => Some(3),
+        _ => None,
+    }
+}
+
+/// Effects selected in a beacon.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BeaconEffects {
+    pub primary: Option<StatusEffect>,
+    /// Either regeneration or the primary effect, which
+    /// then applies at level II. Requires a full pyramid.
+    pub secondary: Option<StatusEffect>,
+}
+
+impl BeaconEffects {
+    /// Returns whether these effects can be selected
+    /// in a beacon with the given pyramid level.
+    pub fn is_valid(&self, level: u8) -> bool {
+        let primary_valid = match self.primary {
+            Some(primary) => primary_effect_level(primary).map_or(false, |min| level >= min),
+            None => true,
+        };
+        let secondary_valid = match self.secondary {
+            Some(secondary) => {
+                level >= MAX_LEVEL
+                    && self.primary.is_some()
+                    && (secondary == StatusEffect::Regeneration || Some(secondary) == self.primary)
+            }
+            None => true,
+        };
+        primary_valid && secondary_valid
+    }
+}
+
+/// The effects selected in each beacon.
+#[derive(Default)]
+pub struct Beacons {
+    beacons: AHashMap<BlockPosition, BeaconEffects>,
+}
+
+impl Beacons {
+    pub fn get(&self, position: BlockPosition) -> BeaconEffects {
+        self.beacons.get(&position).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, position: BlockPosition, effects: BeaconEffects) {
+        self.beacons.insert(position, effects);
+    }
+}
+
+/// Component for a player who has a beacon window open.
+#[derive(Copy, Clone, Debug)]
+pub struct OpenBeacon {
+    pub window_id: u8,
+    pub position: BlockPosition,
+}
+
+/// Returns the number of complete pyramid layers below a beacon.
+pub fn pyramid_level(game: &Game, position: BlockPosition) -> u8 {
+    for level in 1..=MAX_LEVEL {
+        let y = position.y - level as i32;
+        let radius = level as i32;
+        for x in position.x - radius..=position.x + radius {
+            for z in position.z - radius..=position.z + radius {
+                let is_base = game
+                    .block(BlockPosition::new(x, y, z))
+                    .map_or(false, |block| BASE_BLOCKS.contains(&block.kind()));
+                if !is_base {
+                    return level - 1;
+                }
+            }
+        }
+    }
+    MAX_LEVEL
+}
+
+/// Returns whether the block at `position` is a beacon.
+pub fn is_beacon(game: &Game, position: BlockPosition) -> bool {
+    game.block(position)
+        .map_or(false, |block| block.kind() == BlockKind::Beacon)
+}
+
+/// Color of an untinted beam.
+const WHITE: [f32; 3] = [249.0 / 255.0, 1.0, 254.0 / 255.0];
+
+/// A part of a beacon beam with a single color.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BeamSegment {
+    /// RGB color, each component between 0 and 1.
+    pub color: [f32; 3],
+    /// Height in blocks.
+    pub height: u32,
+}
+
+impl BeamSegment {
+    fn new(color: [f32; 3]) -> Self {
+        Self { color, height: 1 }
+    }
+}
+
+/// Returns the color stained glass of `kind` tints beams with.
+pub fn beam_color(kind: BlockKind) -> Option<[f32; 3]> {
+    let rgb: u32 = match kind {
+        BlockKind::WhiteStainedGlass | BlockKind::WhiteStainedGlassPane => 0xF9FFFE,
+        BlockKind::OrangeStainedGlass | BlockKind::OrangeStainedGlassPane => 0xF9801D,
+        BlockKind::MagentaStainedGlass | BlockKind::MagentaStainedGlassPane => 0xC74EBD,
+        BlockKind::LightBlueStainedGlass | BlockKind::LightBlueStainedGlassPane => 0x3AB3DA,
+        BlockKind::YellowStainedGlass | BlockKind::YellowStainedGlassPane => 0xFED83D,
+        BlockKind::LimeStainedGlass | BlockKind::LimeStainedGlassPane => 0x80C71F,
+        BlockKind::PinkStainedGlass | BlockKind::PinkStainedGlassPane => 0xF38BAA,
+        BlockKind::GrayStainedGlass | BlockKind::GrayStainedGlassPane => 0x474F52,
+        BlockKind::LightGrayStainedGlass | BlockKind::LightGrayStainedGlassPane => 0x9D9D97,
+        BlockKind::CyanStainedGlass | BlockKind::CyanStainedGlassPane => 0x169C9C,
+        BlockKind::PurpleStainedGlass | BlockKind::PurpleStainedGlassPane => 0x8932B8,
+        BlockKind::BlueStainedGlass | BlockKind::BlueStainedGlassPane => 0x3C44AA,
+        BlockKind::BrownStainedGlass | BlockKind::BrownStainedGlassPane => 0x835432,
+        BlockKind::GreenStainedGlass | BlockKind::GreenStainedGlassPane => 0x5E7C16,
+        BlockKind::RedStainedGlass | BlockKind::RedStainedGlassPane => 0xB02E26,
+        BlockKind::BlackStainedGlass | BlockKind::BlackStainedGlassPane => 0x1D1D21,
+        _ => return None,
+    };
+    let component = |shift: u32| ((rgb >> shift) & 0xFF) as f32 / 255.0;
+    Some([component(16), component(8), component(0)])
+}
+
+/// Returns the colored segments of the beam of the beacon
+/// at `position`, from bottom to top. Empty if the beam is
+/// blocked by a block which isn't transparent.
+pub fn beam_segments(game: &Game, position: BlockPosition) -> Vec<BeamSegment> {
+    let mut blocks = Vec::new();
+    let mut above = position.up();
+    while let Some(block) = game.block(above) {
+        blocks.push(block.kind());
+        above = above.up();
+    }
+    segments_through(blocks)
+}
+
+/// Computes beam segments through the blocks above a beacon.
+///
+/// Like in vanilla, the first stained glass block sets the color,
+/// and each following one of another color mixes with the color
+/// of the segment below it.
+fn segments_through(blocks: impl IntoIterator<Item = BlockKind>) -> Vec<BeamSegment> {
+    let mut segments = vec![BeamSegment::new(WHITE)];
+    for kind in blocks {
+        let last = segments.len() - 1;
+        match beam_color(kind) {
+            Some(color) if segments.len() == 1 => segments.push(BeamSegment::new(color)),
+            Some(color) if color == segments[last].color => segments[last].height += 1,
+            Some(color) => {
+                let mixed = mix(segments[last].color, color);
+                segments.push(BeamSegment::new(mixed));
+            }
+            None if !kind.transparent() => return Vec::new(),
+            None => segments[last].height += 1,
+        }
+    }
+    segments
+}
+
+fn mix(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        (a[0] + b[0]) / 2.0,
+        (a[1] + b[1]) / 2.0,
+        (a[2] + b[2]) / 2.0,
+    ]
+}
+
+/// Returns whether only transparent blocks, like glass,
+/// are above a beacon, so its beam can reach the sky.
+pub fn has_sky_access(game: &Game, position: BlockPosition) -> bool {
+    !beam_segments(game, position).is_empty()
+}
+
+/// Opens the beacon at `position` for `player`.
+pub fn open_beacon(
+    game: &mut Game,
+    player: Entity,
+    position: BlockPosition,
+    window_id: u8,
+) -> SysResult {
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let window = Window::new(BackingWindow::Beacon {
+        beacon: Inventory::beacon(),
+        player: inventory,
+    });
+    game.ecs.insert(player, window)?;
+    game.ecs.insert(
+        player,
+        OpenBeacon {
+            window_id,
+            position,
+        },
+    )?;
+    Ok(())
+}
+
+/// Returns the payment slot of the player's beacon window.
+fn beacon_inventory(game: &Game, player: Entity) -> anyhow::Result<Inventory> {
+    match game.ecs.get::<Window>(player)?.inner() {
+        BackingWindow::Beacon { beacon, .. } => Ok(beacon.new_handle()),
+        _ => anyhow::bail!("player has no beacon window open"),
+    }
+}
+
+/// Changes the effects of the beacon `player` has open,
+/// consuming one item from the payment slot.
+///
+/// Effects which aren't unlocked by the pyramid,
+/// or selected without a payment, are rejected.
+pub fn select_effects(game: &mut Game, player: Entity, effects: BeaconEffects) -> SysResult {
+    let position = game.ecs.get::<OpenBeacon>(player)?.position;
+    if !is_beacon(game, position) {
+        anyhow::bail!("player selected effects of a beacon which no longer exists");
+    }
+    if !effects.is_valid(pyramid_level(game, position)) {
+        anyhow::bail!("player selected effects the beacon doesn't allow");
+    }
+
+    let beacon = beacon_inventory(game, player)?;
+    let mut payment = beacon
+        .item(Area::BeaconPayment, 0)
+        .expect("beacon has a payment slot");
+    if payment.is_none() {
+        anyhow::bail!("player selected beacon effects without a payment");
+    }
+    window::consume_one(&mut payment);
+    drop(payment);
+
+    game.resources.get_mut::<Beacons>()?.set(position, effects);
+    Ok(())
+}
+
+/// Closes the player's beacon window, giving
+/// back the item left in the payment slot.
+pub fn close_beacon(game: &mut Game, player: Entity) -> SysResult {
+    let beacon = beacon_inventory(game, player)?;
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    game.ecs.remove::<OpenBeacon>(player)?;
+    game.ecs.insert(
+        player,
+        Window::new(BackingWindow::Player { player: inventory }),
+    )?;
+
+    let payment = beacon
+        .item(Area::BeaconPayment, 0)
+        .and_then(|mut item| item.take());
+    window::return_items(game, player, payment)
+}
+
+/// Applies the effects of active beacons to players in range.
+fn apply_beacon_effects(game: &mut Game) -> SysResult {
+    if game.tick_count % BEACON_EFFECT_PERIOD != 0 {
+        return Ok(());
+    }
+
+    let resources = Arc::clone(&game.resources);
+    let mut beacons = resources.get_mut::<Beacons>()?;
+    // Forget beacons which were broken.
+    beacons
+        .beacons
+        .retain(|&position, _| is_beacon(game, position));
+
+    let mut applied = Vec::new();
+    for (&position, effects) in &beacons.beacons {
+        let primary = match effects.primary {
+            Some(primary) => primary,
+            None => continue,
+        };
+        let level = pyramid_level(game, position);
+        if level == 0 || !effects.is_valid(level) || !has_sky_access(game, position) {
+            continue;
+        }
+
+        let range = level as f64 * 10.0 + 10.0;
+        let duration = (9 + level as u32 * 2) * 20;
+        let mut beacon_effects = Vec::new();
+        let amplifier = if effects.secondary == Some(primary) {
+            1
+        } else {
+            0
+        };
+        beacon_effects.push(ActiveEffect {
+            ambient: true,
+            ..ActiveEffect::new(primary, amplifier, duration)
+        });
+        if let Some(secondary) = effects.secondary.filter(|&secondary| secondary != primary) {
+            beacon_effects.push(ActiveEffect {
+                ambient: true,
+                ..ActiveEffect::new(secondary, 0, duration)
+            });
+        }
+
+        let center: Position = position.into();
+        for (player, (_, player_position)) in game.ecs.query::<(&Player, &Position)>().iter() {
+            let in_range = (player_position.x - center.x).abs() <= range + 0.5
+                && (player_position.z - center.z).abs() <= range + 0.5
+                && player_position.y >= center.y - range - 0.5;
+            if in_range {
+                for &effect in &beacon_effects {
+                    applied.push((player, effect));
+                }
+            }
+        }
+    }
+    drop(beacons);
+
+    for (player, effect) in applied {
+        add_effect(game, player, effect)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effect_validity() {
+        let speed = BeaconEffects {
+            primary: Some(StatusEffect::Speed),
+            secondary: None,
+        };
+        assert!(speed.is_valid(1));
+
+        let strength = BeaconEffects {
+            primary: Some(StatusEffect::Strength),
+            secondary: None,
+        };
+        assert!(!strength.is_valid(2));
+        assert!(strength.is_valid(3));
+
+        let boosted = BeaconEffects {
+            primary: Some(StatusEffect::Speed),
+            secondary: Some(StatusEffect::Speed),
+        };
+        assert!(!boosted.is_valid(3));
+        assert!(boosted.is_valid(4));
+
+        let invalid_secondary = BeaconEffects {
+            primary: Some(StatusEffect::Speed),
+            secondary: Some(StatusEffect::Haste),
+        };
+        assert!(!invalid_secondary.is_valid(4));
+    }
+
+    #[test]
+    fn stained_glass_tints_beam() {
+        let red = beam_color(BlockKind::RedStainedGlass).unwrap();
+        let blue = beam_color(BlockKind::BlueStainedGlassPane).unwrap();
+        let segments = segments_through(vec![
+            BlockKind::Air,
+            BlockKind::RedStainedGlass,
+            BlockKind::RedStainedGlass,
+            BlockKind::BlueStainedGlassPane,
+            BlockKind::Air,
+        ]);
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(
+            segments[0],
+            BeamSegment {
+                color: WHITE,
+                height: 2
+            }
+        );
+        assert_eq!(
+            segments[1],
+            BeamSegment {
+                color: red,
+                height: 2
+            }
+        );
+        assert_eq!(
+            segments[2],
+            BeamSegment {
+                color: mix(red, blue),
+                height: 2
+            }
+        );
+    }
+
+    #[test]
+    fn opaque_blocks_stop_beam() {
+        let segments = segments_through(vec![BlockKind::Glass, BlockKind::Stone]);
+        assert!(segments.is_empty());
+    }
+}
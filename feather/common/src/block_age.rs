@@ -0,0 +1,321 @@
+//! Random ticks for blocks which advance through
+//! age stages, like crops growing or turtle eggs hatching.
+//!
+//! Each such block is described by an [`AgedBlock`]
+//! in the [`AgedBlockRegistry`] resource.
+//!
+//! Turtle eggs can also be trampled by entities
+//! standing on them.
+
+use std::sync::Arc;
+
+use ahash::{AHashMap, AHashSet};
+use base::{
+    chunk::SECTION_HEIGHT, BlockId, BlockPosition, ChunkPosition, EntityKind, Position, CHUNK_WIDTH,
+};
+use blocks::BlockKind;
+use ecs::{SysResult, SystemExecutor};
+use quill_common::{entities::Player, entity_init::EntityInit};
+use rand::Rng;
+
+use crate::Game;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(AgedBlockRegistry::default());
+    systems
+        .add_system(random_tick_aged_blocks)
+        .add_system(trample_turtle_eggs);
+}
+
+/// Number of blocks picked in each chunk section every tick.
+pub const RANDOM_TICK_SPEED: usize = 3;
+
+/// Only chunks within this many chunks of a
+/// player get random ticks, like in vanilla.
+pub const RANDOM_TICK_CHUNK_RADIUS: i32 = 8;
+
+/// Chance for an entity standing on turtle
+/// eggs to crack one of them each tick.
+pub const TRAMPLE_CHANCE: f64 = 1.0 / 100.0;
+
+/// How a block advances through its age stages on random ticks.
+#[derive(Copy, Clone)]
+pub struct AgedBlock {
+    /// Returns the age of the block.
+    pub age: fn(BlockId) -> Option<i32>,
+    /// Returns the block with its age set to the given value.
+    pub with_age: fn(BlockId, i32) -> BlockId,
+    pub max_age: i32,
+    /// Probability that a random tick advances the age.
+    pub chance: f64,
+    /// Blocks this block has to stand on to age. Empty
+    /// if it ages on any block.
+    pub support: &'static [BlockKind],
+    /// Called when a random tick would advance the
+    /// block past `max_age`.
+    pub on_max_age: Option<fn(&mut Game, BlockPosition, BlockId)>,
+}
+
+/// Registry of blocks that age on random ticks.
+pub struct AgedBlockRegistry {
+    blocks: AHashMap<BlockKind, AgedBlock>,
+}
+
+impl AgedBlockRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            blocks: AHashMap::new(),
+        }
+    }
+
+    /// Registers aging behavior for a block kind,
+    /// replacing any existing behavior.
+    pub fn register(&mut self, kind: BlockKind, aged: AgedBlock) {
+        self.blocks.insert(kind, aged);
+    }
+
+    pub fn get(&self, kind: BlockKind) -> Option<&AgedBlock> {
+        self.blocks.get(&kind)
+    }
+}
+
+impl Default for AgedBlockRegistry {
+    /// Creates a registry containing the vanilla aged blocks.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(
+            BlockKind::NetherWart,
+            AgedBlock {
+                age: BlockId::age_0_3,
+                with_age: BlockId::with_age_0_3,
+                max_age: 3,
+                chance: 0.1,
+                support: &[BlockKind::SoulSand],
+                on_max_age: None,
+            },
+        );
+        registry.register(
+            BlockKind::TurtleEgg,
+            AgedBlock {
+                age: BlockId::hatch,
+                with_age: BlockId::with_hatch,
+                max_age: 2,
+                chance: 1.0 / 500.0,
+                support: &[BlockKind::Sand, BlockKind::RedSand],
+                on_max_age: Some(hatch_turtle_eggs),
+            },
+        );
+        registry
+    }
+}
+
+/// Removes a turtle egg block and spawns a baby turtle for each egg.
+fn hatch_turtle_eggs(game: &mut Game, pos: BlockPosition, block: BlockId) {
+    game.break_block(pos);
+    for _ in 0..block.eggs().unwrap_or(1) {
+        let builder = game.create_entity_builder(pos.position(), EntityInit::Turtle);
+        game.spawn_entity(builder);
+    }
+}
+
+/// Returns the positions of the chunks near players.
+/// Some of them may not be loaded.
+fn random_ticked_chunks(game: &Game) -> AHashSet<ChunkPosition> {
+    let mut chunks = AHashSet::new();
+    for (_, (_, position)) in game.ecs.query::<(&Player, &Position)>().iter() {
+        let center = position.chunk();
+        for x in -RANDOM_TICK_CHUNK_RADIUS..=RANDOM_TICK_CHUNK_RADIUS {
+            for z in -RANDOM_TICK_CHUNK_RADIUS..=RANDOM_TICK_CHUNK_RADIUS {
+                chunks.insert(ChunkPosition::new(center.x + x, center.z + z));
+            }
+        }
+    }
+    chunks
+}
+
+fn random_tick_aged_blocks(game: &mut Game) -> SysResult {
+    let resources = Arc::clone(&game.resources);
+    let registry = resources.get::<AgedBlockRegistry>()?;
+    let mut rng = rand::thread_rng();
+
+    let mut ticked = Vec::new();
+    for chunk_pos in random_ticked_chunks(game) {
+        let chunk = match game.world.chunk_map().chunk_at(chunk_pos) {
+            Some(chunk) => chunk,
+            None => continue,
+        };
+        // The first section is below the world and never contains blocks.
+        for (index, section) in chunk.sections().iter().enumerate().skip(1) {
+            let section = match section {
+                Some(section) if !section.is_empty() => section,
+                _ => continue,
+            };
+            for _ in 0..RANDOM_TICK_SPEED {
+                let (x, y, z) = (
+                    rng.gen_range(0..CHUNK_WIDTH),
+                    rng.gen_range(0..SECTION_HEIGHT),
+                    rng.gen_range(0..CHUNK_WIDTH),
+                );
+                let block = match section.block_at(x, y, z) {
+                    Some(block) => block,
+                    None => continue,
+                };
+                if registry.get(block.kind()).is_some() {
+                    let pos = BlockPosition::new(
+                        chunk_pos.x * CHUNK_WIDTH as i32 + x as i32,
+                        ((index - 1) * SECTION_HEIGHT + y) as i32,
+                        chunk_pos.z * CHUNK_WIDTH as i32 + z as i32,
+                    );
+                    ticked.push((pos, block));
+                }
+            }
+        }
+    }
+
+    for (pos, block) in ticked {
+        let aged = *registry.get(block.kind()).unwrap();
+        if rng.gen_bool(aged.chance) {
+            advance_age(game, &aged, pos, block);
+        }
+    }
+    Ok(())
+}
+
+/// Advances the age of a block, if it stands on its support.
+fn advance_age(game: &mut Game, aged: &AgedBlock, pos: BlockPosition, block: BlockId) {
+    if !aged.support.is_empty() {
+        match game.block(pos.down()) {
+            Some(below) if aged.support.contains(&below.kind()) => {}
+            _ => return,
+        }
+    }
+
+    let age = (aged.age)(block).unwrap_or_default();
+    if age < aged.max_age {
+        game.set_block(pos, (aged.with_age)(block, age + 1));
+    } else if let Some(on_max_age) = aged.on_max_age {
+        on_max_age(game, pos, block);
+    }
+}
+
+/// Cracks turtle eggs which mobs and players stand on.
+fn trample_turtle_eggs(game: &mut Game) -> SysResult {
+    let mut rng = rand::thread_rng();
+    let mut trampled = Vec::new();
+    for (_, (position, &kind)) in game.ecs.query::<(&Position, &EntityKind)>().iter() {
+        if !tramples_eggs(kind) || !rng.gen_bool(TRAMPLE_CHANCE) {
+            continue;
+        }
+        let pos = position.block();
+        if matches!(game.block(pos), Some(block) if block.kind() == BlockKind::TurtleEgg) {
+            trampled.push(pos);
+        }
+    }
+
+    for pos in trampled {
+        crack_turtle_egg(game, pos);
+    }
+    Ok(())
+}
+
+/// Returns whether entities of `kind` crack turtle eggs they stand on.
+/// Like in vanilla, only living entities other than turtles and bats do.
+fn tramples_eggs(kind: EntityKind) -> bool {
+    kind.is_living() && kind != EntityKind::Turtle && kind != EntityKind::Bat
+}
+
+/// Removes one egg from a turtle egg block,
+/// breaking the block if it was the last one.
+fn crack_turtle_egg(game: &mut Game, pos: BlockPosition) {
+    let block = match game.block(pos) {
+        Some(block) if block.kind() == BlockKind::TurtleEgg => block,
+        _ => return,
+    };
+    match block.eggs() {
+        Some(eggs) if eggs > 1 => {
+            game.set_block(pos, block.with_eggs(eggs - 1));
+        }
+        _ => {
+            game.break_block(pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::Chunk;
+
+    use super::*;
+
+    fn game_with_chunk() -> Game {
+        let mut game = Game::new();
+        game.world
+            .chunk_map_mut()
+            .insert_chunk(Chunk::new(ChunkPosition::new(0, 0)));
+        game
+    }
+
+    #[test]
+    fn only_chunks_near_players_are_ticked() {
+        let mut game = Game::new();
+        assert!(random_ticked_chunks(&game).is_empty());
+
+        game.ecs.spawn((Player, Position::default()));
+        let chunks = random_ticked_chunks(&game);
+        assert_eq!(chunks.len(), 17 * 17);
+        assert!(chunks.contains(&ChunkPosition::new(8, -8)));
+        assert!(!chunks.contains(&ChunkPosition::new(9, 0)));
+    }
+
+    #[test]
+    fn blocks_age_only_on_their_support() {
+        let mut game = game_with_chunk();
+        let registry = AgedBlockRegistry::default();
+        let wart = *registry.get(BlockKind::NetherWart).unwrap();
+        let pos = BlockPosition::new(0, 64, 0);
+
+        game.set_block(pos, BlockId::nether_wart());
+        advance_age(&mut game, &wart, pos, BlockId::nether_wart());
+        assert_eq!(game.block(pos).unwrap().age_0_3(), Some(0));
+
+        game.set_block(pos.down(), BlockId::soul_sand());
+        advance_age(&mut game, &wart, pos, BlockId::nether_wart());
+        assert_eq!(game.block(pos).unwrap().age_0_3(), Some(1));
+    }
+
+    #[test]
+    fn trampling_cracks_one_egg_at_a_time() {
+        let mut game = game_with_chunk();
+        let pos = BlockPosition::new(0, 64, 0);
+        game.set_block(pos, BlockId::turtle_egg().with_eggs(2));
+
+        crack_turtle_egg(&mut game, pos);
+        assert_eq!(game.block(pos).unwrap().eggs(), Some(1));
+        crack_turtle_egg(&mut game, pos);
+        assert_eq!(game.block(pos), Some(BlockId::air()));
+    }
+
+    #[test]
+    fn only_mobs_trample_eggs() {
+        let mut game = game_with_chunk();
+        let pos = Position::default().block();
+        game.set_block(pos, BlockId::turtle_egg().with_eggs(4));
+
+        let item = game.ecs.spawn((Position::default(), EntityKind::Item));
+        for _ in 0..1000 {
+            trample_turtle_eggs(&mut game).unwrap();
+        }
+        assert_eq!(game.block(pos).unwrap().eggs(), Some(4));
+
+        game.ecs.despawn(item).unwrap();
+        game.ecs.spawn((Position::default(), EntityKind::Zombie));
+        for _ in 0..10_000 {
+            trample_turtle_eggs(&mut game).unwrap();
+            if game.block(pos).unwrap().eggs() != Some(4) {
+                break;
+            }
+        }
+        assert_eq!(game.block(pos).unwrap().eggs(), Some(3));
+    }
+}
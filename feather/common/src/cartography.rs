@@ -0,0 +1,242 @@
+//! Cartography tables: cloning, zooming out and locking filled maps.
+//!
+//! A filled map combined with an empty map makes two copies of it.
+//! Paper zooms it out and a glass pane locks it; both make a new
+//! map in the [`MapStore`] once the player takes the result.
+
+use base::{anvil::map::MapData, Area, BlockPosition, Inventory, Item, ItemStack};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult};
+
+use crate::{
+    interactable::InteractableRegistry,
+    maps::{self, MapStore},
+    window::{self, BackingWindow, Window},
+    Game,
+};
+
+pub fn register(game: &mut Game) {
+    game.resources
+        .get_mut::<InteractableRegistry>()
+        .expect("interactable registry not registered")
+        .register(BlockKind::CartographyTable);
+}
+
+/// What a cartography table makes of a filled map.
+#[derive(Clone, Debug, PartialEq)]
+enum Cartography {
+    /// More of the same map.
+    Copies(ItemStack),
+    /// A new map, whose ID is set once the result is shown.
+    NewMap(ItemStack, MapData),
+}
+
+/// Returns what combining `map` with `addition` makes,
+/// or `None` if they can't be combined.
+fn combine(store: &MapStore, map: &ItemStack, addition: Item) -> Option<Cartography> {
+    let data = store.get(maps::map_id(map)?)?;
+    let mut result = map.clone();
+    match addition {
+        Item::Map => {
+            result.count = 2;
+            Some(Cartography::Copies(result))
+        }
+        Item::Paper => {
+            result.count = 1;
+            Some(Cartography::NewMap(result, maps::zoomed_out(data)?))
+        }
+        Item::GlassPane => {
+            result.count = 1;
+            Some(Cartography::NewMap(result, maps::locked(data)?))
+        }
+        _ => None,
+    }
+}
+
+/// Component for a player who has a cartography table open.
+#[derive(Clone, Debug)]
+pub struct OpenCartographyTable {
+    pub position: BlockPosition,
+    /// Result put in the output slot, until the player takes it.
+    output: Option<ItemStack>,
+    /// Map stored once the player takes the result.
+    new_map: Option<MapData>,
+    /// ID given to new maps shown in the output slot. It is
+    /// kept while the table is open, so changing the inputs
+    /// doesn't use up IDs.
+    new_map_id: Option<i32>,
+}
+
+/// Returns whether the block at `position` is a cartography table.
+pub fn is_cartography_table(game: &Game, position: BlockPosition) -> bool {
+    game.block(position)
+        .map_or(false, |block| block.kind() == BlockKind::CartographyTable)
+}
+
+/// Opens the cartography table at `position` for `player`.
+pub fn open_cartography_table(
+    game: &mut Game,
+    player: Entity,
+    position: BlockPosition,
+) -> SysResult {
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let window = Window::new(BackingWindow::Cartography {
+        cartography_table: Inventory::cartography_table(),
+        player: inventory,
+    });
+    game.ecs.insert(player, window)?;
+    game.ecs.insert(
+        player,
+        OpenCartographyTable {
+            position,
+            output: None,
+            new_map: None,
+            new_map_id: None,
+        },
+    )?;
+    Ok(())
+}
+
+/// Returns the slots of the player's cartography window.
+fn table_inventory(game: &Game, player: Entity) -> anyhow::Result<Inventory> {
+    match game.ecs.get::<Window>(player)?.inner() {
+        BackingWindow::Cartography {
+            cartography_table, ..
+        } => Ok(cartography_table.new_handle()),
+        _ => anyhow::bail!("player has no cartography window open"),
+    }
+}
+
+/// Updates the player's cartography table after they clicked
+/// in its window, consuming the inputs and storing the new map
+/// if they took the result, and filling the output slot again.
+pub fn update(game: &mut Game, player: Entity) -> SysResult {
+    let table = table_inventory(game, player)?;
+    let mut open = game.ecs.get_mut::<OpenCartographyTable>(player)?;
+    let mut store = game.resources.get_mut::<MapStore>()?;
+    let mut map = table
+        .item(Area::CartographyMap, 0)
+        .expect("cartography table has a map slot");
+    let mut addition = table
+        .item(Area::CartographyPaper, 0)
+        .expect("cartography table has a paper slot");
+    let mut output = table
+        .item(Area::CartographyOutput, 0)
+        .expect("cartography table has an output slot");
+
+    if open.output.is_some() && *output != open.output {
+        window::consume_one(&mut map);
+        window::consume_one(&mut addition);
+        if let Some(data) = open.new_map.take() {
+            let id = open
+                .new_map_id
+                .take()
+                .expect("new maps are shown with an ID");
+            store.insert(id, data);
+        }
+        open.output = None;
+    }
+
+    let combined = match (map.as_ref(), addition.as_ref()) {
+        (Some(map), Some(addition)) => combine(&store, map, addition.item),
+        _ => None,
+    };
+    let (result, new_map) = match combined {
+        Some(Cartography::Copies(result)) => (Some(result), None),
+        Some(Cartography::NewMap(mut result, data)) => {
+            let id = *open.new_map_id.get_or_insert_with(|| store.next_id());
+            maps::set_map_id(&mut result, id);
+            (Some(result), Some(data))
+        }
+        None => (None, None),
+    };
+    // Leave items the player put in the output slot.
+    if *output == open.output || output.is_none() {
+        *output = result.clone();
+        open.output = result;
+        open.new_map = new_map;
+    }
+    Ok(())
+}
+
+/// Closes the player's cartography table, returning the
+/// items in it to the player's inventory.
+pub fn close_cartography_table(game: &mut Game, player: Entity) -> SysResult {
+    let table = table_inventory(game, player)?;
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let open = game.ecs.remove::<OpenCartographyTable>(player)?;
+    game.ecs.insert(
+        player,
+        Window::new(BackingWindow::Player { player: inventory }),
+    )?;
+
+    let mut items: Vec<ItemStack> = [Area::CartographyMap, Area::CartographyPaper]
+        .iter()
+        .filter_map(|&area| table.item(area, 0).and_then(|mut item| item.take()))
+        .collect();
+    let output = table
+        .item(Area::CartographyOutput, 0)
+        .and_then(|mut item| item.take());
+    if output != open.output {
+        items.extend(output);
+    }
+    window::return_items(game, player, items)
+}
+
+#[cfg(test)]
+mod tests {
+    use base::anvil::map::MAP_PIXELS;
+
+    use super::*;
+
+    fn filled_map(id: i32) -> ItemStack {
+        let mut stack = ItemStack::new(Item::FilledMap, 1);
+        maps::set_map_id(&mut stack, id);
+        stack
+    }
+
+    #[test]
+    fn taking_a_locked_map_stores_it() {
+        let mut game = Game::new();
+        let data = MapData {
+            scale: 0,
+            dimension: "minecraft:overworld".to_owned(),
+            x_center: 64,
+            z_center: 64,
+            locked: false,
+            tracking_position: true,
+            unlimited_tracking: false,
+            colors: vec![0; MAP_PIXELS],
+        };
+        game.insert_resource(MapStore::new(vec![(0, data)], Some(0)));
+        let player = game.ecs.spawn((Inventory::player(),));
+        open_cartography_table(&mut game, player, BlockPosition::new(0, 64, 0)).unwrap();
+        let table = table_inventory(&game, player).unwrap();
+        *table.item(Area::CartographyMap, 0).unwrap() = Some(filled_map(0));
+        *table.item(Area::CartographyPaper, 0).unwrap() = Some(ItemStack::new(Item::Map, 1));
+
+        update(&mut game, player).unwrap();
+        let mut copies = filled_map(0);
+        copies.count = 2;
+        assert_eq!(
+            *table.item(Area::CartographyOutput, 0).unwrap(),
+            Some(copies)
+        );
+
+        *table.item(Area::CartographyPaper, 0).unwrap() = Some(ItemStack::new(Item::GlassPane, 1));
+        update(&mut game, player).unwrap();
+        assert_eq!(
+            *table.item(Area::CartographyOutput, 0).unwrap(),
+            Some(filled_map(1))
+        );
+        assert!(game.resources.get::<MapStore>().unwrap().get(1).is_none());
+
+        table.item(Area::CartographyOutput, 0).unwrap().take();
+        update(&mut game, player).unwrap();
+        assert_eq!(*table.item(Area::CartographyMap, 0).unwrap(), None);
+        assert_eq!(*table.item(Area::CartographyPaper, 0).unwrap(), None);
+        let store = game.resources.get::<MapStore>().unwrap();
+        assert!(store.get(1).unwrap().locked);
+        assert!(!store.get(0).unwrap().locked);
+    }
+}
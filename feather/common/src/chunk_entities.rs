@@ -27,6 +27,13 @@ impl ChunkEntities {
             .unwrap_or_default()
     }
 
+    /// Iterates over chunks and the entities indexed in them.
+    pub fn iter(&self) -> impl Iterator<Item = (ChunkPosition, &[Entity])> + '_ {
+        self.entities
+            .iter()
+            .map(|(&chunk, entities)| (chunk, entities.as_slice()))
+    }
+
     fn update(
         &mut self,
         entity: Entity,
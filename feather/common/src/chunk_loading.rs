@@ -1,4 +1,8 @@
 //! Chunk loading and unloading based on player `View`s.
+//!
+//! Each player holds a ticket on the chunks they can see. Chunks
+//! without tickets are unloaded after a delay, or earlier to stay
+//! under the limit set in [`ChunkLoadSettings`].
 
 use std::{
     collections::VecDeque,
@@ -12,33 +16,70 @@ use ecs::{Entity, SysResult, SystemExecutor};
 use utils::vec_remove_item;
 
 use crate::{
-    events::{EntityRemoveEvent, ViewUpdateEvent},
+    events::{BlockChangeEvent, EntityRemoveEvent, ViewUpdateEvent},
     Game,
 };
 
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(ChunkLoadSettings::default());
     game.insert_resource(ChunkLoadState::default());
     systems
         .group::<ChunkLoadState>()
         .add_system(remove_dead_entities)
         .add_system(update_tickets_for_players)
+        .add_system(mark_changed_chunks)
         .add_system(unload_chunks)
         .add_system(load_chunks);
 }
 
-/// Amount of time to wait after a chunk has
-/// no tickets until it is unloaded.
-const UNLOAD_DELAY: Duration = Duration::from_secs(10);
+/// Resource configuring when chunks are unloaded.
+#[derive(Debug)]
+pub struct ChunkLoadSettings {
+    /// Amount of time to wait after a chunk has
+    /// no tickets until it is unloaded.
+    unload_delay: Duration,
+    /// Maximum number of loaded chunks, if any.
+    max_loaded_chunks: Option<usize>,
+}
+
+impl Default for ChunkLoadSettings {
+    fn default() -> Self {
+        Self {
+            unload_delay: Duration::from_secs(10),
+            max_loaded_chunks: None,
+        }
+    }
+}
+
+impl ChunkLoadSettings {
+    pub fn set_unload_delay(&mut self, delay: Duration) {
+        self.unload_delay = delay;
+    }
+
+    /// Sets the maximum number of loaded chunks. Past it, chunks
+    /// without tickets are unloaded before their delay is over,
+    /// least recently used first. Chunks with tickets are never
+    /// unloaded, so the limit can be exceeded while players see
+    /// more chunks than that.
+    pub fn set_max_loaded_chunks(&mut self, max: Option<usize>) {
+        self.max_loaded_chunks = max;
+    }
+}
 
 #[derive(Default)]
 struct ChunkLoadState {
-    /// Chunks that have been queued for unloading.
-    chunk_unload_queue: VecDeque<QueuedChunkUnload>,
+    /// Chunks without tickets, waiting to be unloaded.
+    idle_chunks: IdleChunks,
 
     chunk_tickets: ChunkTickets,
 }
 
 impl ChunkLoadState {
+    pub fn insert_ticket(&mut self, chunk: ChunkPosition, ticket: Ticket) {
+        self.chunk_tickets.insert_ticket(chunk, ticket);
+        self.idle_chunks.remove(chunk);
+    }
+
     pub fn remove_ticket(&mut self, chunk: ChunkPosition, ticket: Ticket) {
         self.chunk_tickets.remove_ticket(chunk, ticket);
 
@@ -46,25 +87,50 @@ impl ChunkLoadState {
         // unloaded.
         if self.chunk_tickets.num_tickets(chunk) == 0 {
             self.chunk_tickets.remove_chunk(chunk);
-            self.chunk_unload_queue
-                .push_back(QueuedChunkUnload::new(chunk));
+            self.idle_chunks.insert(chunk, Instant::now());
         }
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-struct QueuedChunkUnload {
-    pos: ChunkPosition,
-    /// Time after which the chunk should be unloaded.
-    unload_at_time: Instant,
+/// Chunks without tickets, in the order they lost their last
+/// ticket, which is also the order they were last used in.
+#[derive(Default)]
+struct IdleChunks {
+    /// May contain chunks which got a ticket
+    /// since, or which were queued again later.
+    queue: VecDeque<(ChunkPosition, Instant)>,
+    /// Time each chunk lost its last ticket.
+    since: AHashMap<ChunkPosition, Instant>,
 }
 
-impl QueuedChunkUnload {
-    pub fn new(pos: ChunkPosition) -> Self {
-        Self {
-            pos,
-            unload_at_time: Instant::now() + UNLOAD_DELAY,
+impl IdleChunks {
+    pub fn insert(&mut self, chunk: ChunkPosition, now: Instant) {
+        self.since.insert(chunk, now);
+        self.queue.push_back((chunk, now));
+    }
+
+    pub fn remove(&mut self, chunk: ChunkPosition) {
+        self.since.remove(&chunk);
+    }
+
+    /// Removes and returns the chunk idle for the longest
+    /// time, if `ready` returns `true` for the time it's
+    /// been idle since.
+    pub fn pop_oldest(&mut self, ready: impl Fn(Instant) -> bool) -> Option<ChunkPosition> {
+        while let Some(&(chunk, since)) = self.queue.front() {
+            if self.since.get(&chunk) != Some(&since) {
+                // Stale entry
+                self.queue.pop_front();
+                continue;
+            }
+            if !ready(since) {
+                return None;
+            }
+            self.queue.pop_front();
+            self.since.remove(&chunk);
+            return Some(chunk);
         }
+        None
     }
 }
 
@@ -115,6 +181,16 @@ impl ChunkTickets {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 struct Ticket(Entity);
 
+/// System to mark chunks with changed blocks so they're saved on unload.
+fn mark_changed_chunks(game: &mut Game, _state: &mut ChunkLoadState) -> SysResult {
+    for (_, event) in game.ecs.query::<&BlockChangeEvent>().iter() {
+        for (chunk, _, _) in event.iter_affected_chunk_sections() {
+            game.world.mark_chunk_changed(chunk);
+        }
+    }
+    Ok(())
+}
+
 /// System to populate chunk tickets based on players' views.
 fn update_tickets_for_players(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
     for (player, event) in game.ecs.query::<&ViewUpdateEvent>().iter() {
@@ -127,7 +203,7 @@ fn update_tickets_for_players(game: &mut Game, state: &mut ChunkLoadState) -> Sy
 
         // Create new tickets
         for &new_chunk in &event.new_chunks {
-            state.chunk_tickets.insert_ticket(new_chunk, player_ticket);
+            state.insert_ticket(new_chunk, player_ticket);
 
             // Load if needed
             if !game.world.is_chunk_loaded(new_chunk) && !game.world.is_chunk_loading(new_chunk) {
@@ -138,24 +214,32 @@ fn update_tickets_for_players(game: &mut Game, state: &mut ChunkLoadState) -> Sy
     Ok(())
 }
 
-/// System to unload chunks from the `ChunkUnloadQueue`.
+/// System to unload chunks which have been idle
+/// for too long or which exceed the chunk limit.
 fn unload_chunks(game: &mut Game, state: &mut ChunkLoadState) -> SysResult {
-    while let Some(&unload) = state.chunk_unload_queue.get(0) {
-        if unload.unload_at_time > Instant::now() {
-            // None of the remaining chunks in the queue are
-            // ready for unloading, because the queue is ordered
-            // by time.
-            break;
-        }
-
-        state.chunk_unload_queue.pop_front();
+    let settings = game.resources.get::<ChunkLoadSettings>()?;
+    let now = Instant::now();
+    while let Some(chunk) = state
+        .idle_chunks
+        .pop_oldest(|since| since + settings.unload_delay <= now)
+    {
+        game.world.unload_chunk(chunk);
+    }
 
-        // If the chunk has acquired new tickets, then abort unloading it.
-        if state.chunk_tickets.num_tickets(unload.pos) > 0 {
-            continue;
+    if let Some(max) = settings.max_loaded_chunks {
+        let mut evicted = 0;
+        while game.world.chunk_manager().loaded_count() > max {
+            match state.idle_chunks.pop_oldest(|_| true) {
+                Some(chunk) => {
+                    game.world.unload_chunk(chunk);
+                    evicted += 1;
+                }
+                None => break,
+            }
+        }
+        if evicted > 0 {
+            log::debug!("Unloaded {} chunks to stay under {} chunks", evicted, max);
         }
-
-        game.world.unload_chunk(unload.pos);
     }
     Ok(())
 }
@@ -175,3 +259,33 @@ fn load_chunks(game: &mut Game, _state: &mut ChunkLoadState) -> SysResult {
     game.world.load_chunks(&mut game.ecs);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idle_chunks_are_unloaded_least_recently_used_first() {
+        let start = Instant::now();
+        let later = start + Duration::from_secs(5);
+        let (a, b, c) = (
+            ChunkPosition::new(0, 0),
+            ChunkPosition::new(1, 0),
+            ChunkPosition::new(2, 0),
+        );
+        let mut idle = IdleChunks::default();
+        idle.insert(a, start);
+        idle.insert(b, start);
+        idle.insert(c, later);
+
+        // `a` is seen again, then left later than `c`.
+        idle.remove(a);
+        idle.insert(a, later + Duration::from_secs(1));
+
+        assert_eq!(idle.pop_oldest(|since| since <= start), Some(b));
+        assert_eq!(idle.pop_oldest(|since| since <= start), None);
+        assert_eq!(idle.pop_oldest(|_| true), Some(c));
+        assert_eq!(idle.pop_oldest(|_| true), Some(a));
+        assert_eq!(idle.pop_oldest(|_| true), None);
+    }
+}
@@ -0,0 +1,366 @@
+//! Combat logging: players disconnecting in the middle of a fight.
+//!
+//! Attacks between players tag both of them with a [`CombatTag`].
+//! A player disconnecting while tagged triggers a [`CombatLogEvent`]
+//! and, depending on the configured [`CombatLogAction`], drops their
+//! inventory or leaves behind a [`CombatLogNpc`] which other players
+//! can kill to get the items.
+//!
+//! Punishments empty the player's inventory before it is saved,
+//! so the items aren't there when they log in again.
+
+use std::time::Duration;
+
+use base::{Area, Inventory, Position, TPS};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::{components::Name, entities::Player, entity_init::EntityInit};
+use uuid::Uuid;
+
+use crate::{
+    events::{CombatLogEvent, CombatLogPunishEvent},
+    Game,
+};
+
+/// Areas of a player inventory taken away when a player is punished.
+const DROPPED_AREAS: [Area; 8] = [
+    Area::CraftingInput,
+    Area::Helmet,
+    Area::Chestplate,
+    Area::Leggings,
+    Area::Boots,
+    Area::Storage,
+    Area::Hotbar,
+    Area::Offhand,
+];
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(CombatLogSettings::default());
+    systems.add_system(despawn_expired_npcs);
+}
+
+/// What happens when a player disconnects during combat.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CombatLogAction {
+    /// Drop the player's inventory where they logged out.
+    DropInventory,
+    /// Spawn a stand-in holding the player's inventory
+    /// which drops it when killed.
+    Npc,
+    /// Only trigger a [`CombatLogEvent`] for plugins.
+    EventOnly,
+}
+
+/// Resource configuring combat logging.
+#[derive(Debug)]
+pub struct CombatLogSettings {
+    /// Ticks after a PvP attack during which a player is
+    /// in combat, or `None` to disable combat logging.
+    combat_ticks: Option<u64>,
+    action: CombatLogAction,
+    npc_lifetime_ticks: u64,
+}
+
+impl Default for CombatLogSettings {
+    fn default() -> Self {
+        Self {
+            combat_ticks: None,
+            action: CombatLogAction::EventOnly,
+            npc_lifetime_ticks: 30 * TPS as u64,
+        }
+    }
+}
+
+impl CombatLogSettings {
+    pub fn set_combat_duration(&mut self, duration: Option<Duration>) {
+        self.combat_ticks = duration.map(duration_to_ticks);
+    }
+
+    pub fn set_action(&mut self, action: CombatLogAction) {
+        self.action = action;
+    }
+
+    pub fn set_npc_lifetime(&mut self, lifetime: Duration) {
+        self.npc_lifetime_ticks = duration_to_ticks(lifetime);
+    }
+}
+
+fn duration_to_ticks(duration: Duration) -> u64 {
+    duration.as_secs() * TPS as u64
+}
+
+/// Component storing the tick of a player's last PvP attack,
+/// either dealt or taken.
+#[derive(Copy, Clone, Debug)]
+pub struct CombatTag(pub u64);
+
+/// Component of the stand-in left behind by a combat logger.
+#[derive(Debug)]
+pub struct CombatLogNpc {
+    pub owner: Uuid,
+    pub owner_name: String,
+    /// The items taken from the owner's inventory.
+    pub inventory: Inventory,
+    /// Tick at which the stand-in despawns.
+    pub despawn_tick: u64,
+}
+
+/// Returns whether `player` attacked or was
+/// attacked by another player recently.
+pub fn in_combat(game: &Game, player: Entity) -> bool {
+    let combat_ticks = match game
+        .resources
+        .get::<CombatLogSettings>()
+        .ok()
+        .and_then(|settings| settings.combat_ticks)
+    {
+        Some(ticks) => ticks,
+        None => return false,
+    };
+    game.ecs
+        .get::<CombatTag>(player)
+        .map(|tag| game.tick_count.saturating_sub(tag.0) < combat_ticks)
+        .unwrap_or(false)
+}
+
+/// Called when `attacker` attacks `target`. Tags players
+/// fighting each other and kills combat log stand-ins.
+pub fn on_attack(game: &mut Game, attacker: Entity, target: Entity) -> SysResult {
+    if game.ecs.get::<CombatLogNpc>(target).is_ok() {
+        return kill_npc(game, target);
+    }
+    if game.ecs.get::<Player>(attacker).is_ok() && game.ecs.get::<Player>(target).is_ok() {
+        let tag = CombatTag(game.tick_count);
+        game.ecs.insert(attacker, tag)?;
+        game.ecs.insert(target, tag)?;
+    }
+    Ok(())
+}
+
+/// Called when `player` disconnects, before their
+/// entity is removed and their inventory is saved.
+pub fn on_disconnect(game: &mut Game, player: Entity) -> SysResult {
+    if !in_combat(game, player) {
+        return Ok(());
+    }
+    let action = game.resources.get::<CombatLogSettings>()?.action;
+    let name = game.ecs.get::<Name>(player)?.to_string();
+    let uuid = *game.ecs.get::<Uuid>(player)?;
+    let position = *game.ecs.get::<Position>(player)?;
+    log::info!("{} disconnected during combat", name);
+    game.ecs
+        .insert_entity_event(player, CombatLogEvent { action })?;
+
+    match action {
+        CombatLogAction::DropInventory => {
+            let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+            drop_inventory(game, &inventory, position);
+            game.ecs.insert_event(CombatLogPunishEvent { player: uuid });
+        }
+        CombatLogAction::Npc => {
+            let inventory = Inventory::player();
+            move_items(&game.ecs.get::<Inventory>(player)?, &inventory);
+            let npc = CombatLogNpc {
+                owner: uuid,
+                owner_name: name,
+                inventory,
+                despawn_tick: game.tick_count
+                    + game
+                        .resources
+                        .get::<CombatLogSettings>()?
+                        .npc_lifetime_ticks,
+            };
+            let mut builder = game.create_entity_builder(position, EntityInit::Villager);
+            builder.add(npc);
+            game.spawn_entity(builder);
+        }
+        CombatLogAction::EventOnly => {}
+    }
+    Ok(())
+}
+
+/// Called when a player joins, before their entity is spawned.
+/// Removes the stand-in left behind by them, if any, and puts
+/// the items it held back into their `inventory`.
+pub fn on_rejoin(game: &mut Game, uuid: Uuid, inventory: &Inventory) -> SysResult {
+    let npcs: Vec<Entity> = game
+        .ecs
+        .query::<&CombatLogNpc>()
+        .iter()
+        .filter(|(_, npc)| npc.owner == uuid)
+        .map(|(entity, npc)| {
+            move_items(&npc.inventory, inventory);
+            entity
+        })
+        .collect();
+    for npc in npcs {
+        game.remove_entity(npc)?;
+    }
+    Ok(())
+}
+
+fn kill_npc(game: &mut Game, npc: Entity) -> SysResult {
+    let (owner, inventory) = {
+        let npc = game.ecs.get::<CombatLogNpc>(npc)?;
+        log::info!("The stand-in of {} was killed", npc.owner_name);
+        (npc.owner, npc.inventory.new_handle())
+    };
+    let position = *game.ecs.get::<Position>(npc)?;
+    drop_inventory(game, &inventory, position);
+    game.remove_entity(npc)?;
+    game.ecs
+        .insert_event(CombatLogPunishEvent { player: owner });
+    Ok(())
+}
+
+/// Moves the items of `from` into the same slots of `to`.
+/// Items whose slot in `to` is taken are kept in `from`.
+fn move_items(from: &Inventory, to: &Inventory) {
+    for &area in &DROPPED_AREAS {
+        let mut slot = 0;
+        while let Some(mut stack) = from.item(area, slot) {
+            if let Some(mut target) = to.item(area, slot) {
+                if target.is_none() {
+                    *target = stack.take();
+                }
+            }
+            slot += 1;
+        }
+    }
+}
+
+/// Empties `inventory`, spawning its items at `position`.
+fn drop_inventory(game: &mut Game, inventory: &Inventory, position: Position) {
+    for &area in &DROPPED_AREAS {
+        let mut slot = 0;
+        while let Some(mut stack) = inventory.item(area, slot) {
+            if let Some(item) = stack.take() {
+                let mut builder = game.create_entity_builder(position, EntityInit::Item);
+                builder.add(item);
+                game.spawn_entity(builder);
+            }
+            slot += 1;
+        }
+    }
+}
+
+fn despawn_expired_npcs(game: &mut Game) -> SysResult {
+    let expired: Vec<Entity> = game
+        .ecs
+        .query::<&CombatLogNpc>()
+        .iter()
+        .filter(|(_, npc)| npc.despawn_tick <= game.tick_count)
+        .map(|(entity, _)| entity)
+        .collect();
+    for npc in expired {
+        game.remove_entity(npc)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{Item, ItemStack};
+
+    use super::*;
+    use crate::events::EntityRemoveEvent;
+
+    fn game_with_action(action: CombatLogAction) -> Game {
+        let mut game = Game::new();
+        game.insert_resource(CombatLogSettings::default());
+        let mut settings = game.resources.get_mut::<CombatLogSettings>().unwrap();
+        settings.set_combat_duration(Some(Duration::from_secs(10)));
+        settings.set_action(action);
+        drop(settings);
+        game
+    }
+
+    /// Spawns a player in combat holding a diamond sword.
+    fn spawn_fighter(game: &mut Game) -> (Entity, Inventory) {
+        let inventory = Inventory::player();
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::DiamondSword, 1));
+        let player = game.ecs.spawn((
+            Player,
+            Name::new("Steve"),
+            Uuid::new_v4(),
+            Position::default(),
+            inventory.new_handle(),
+            CombatTag(game.tick_count),
+        ));
+        (player, inventory)
+    }
+
+    fn dropped_items(game: &Game) -> Vec<ItemStack> {
+        game.ecs
+            .query::<&ItemStack>()
+            .iter()
+            .map(|(_, item)| item.clone())
+            .collect()
+    }
+
+    #[test]
+    fn combat_loggers_drop_their_inventory() {
+        let mut game = game_with_action(CombatLogAction::DropInventory);
+        let (player, inventory) = spawn_fighter(&mut game);
+
+        on_disconnect(&mut game, player).unwrap();
+        assert_eq!(
+            dropped_items(&game),
+            vec![ItemStack::new(Item::DiamondSword, 1)]
+        );
+        assert_eq!(*inventory.item(Area::Hotbar, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn killed_npcs_drop_the_items() {
+        let mut game = game_with_action(CombatLogAction::Npc);
+        let (player, inventory) = spawn_fighter(&mut game);
+        let attacker = game.ecs.spawn((Player,));
+
+        on_disconnect(&mut game, player).unwrap();
+        assert_eq!(*inventory.item(Area::Hotbar, 0).unwrap(), None);
+        let (npc, _) = game.ecs.query::<&CombatLogNpc>().iter().next().unwrap();
+        assert!(dropped_items(&game).is_empty());
+
+        on_attack(&mut game, attacker, npc).unwrap();
+        assert_eq!(
+            dropped_items(&game),
+            vec![ItemStack::new(Item::DiamondSword, 1)]
+        );
+        assert!(game.ecs.get::<EntityRemoveEvent>(npc).is_ok());
+    }
+
+    #[test]
+    fn rejoining_players_get_their_items_back() {
+        let mut game = game_with_action(CombatLogAction::Npc);
+        let (player, _) = spawn_fighter(&mut game);
+        let uuid = *game.ecs.get::<Uuid>(player).unwrap();
+
+        on_disconnect(&mut game, player).unwrap();
+        let inventory = Inventory::player();
+        on_rejoin(&mut game, uuid, &inventory).unwrap();
+        assert_eq!(
+            *inventory.item(Area::Hotbar, 0).unwrap(),
+            Some(ItemStack::new(Item::DiamondSword, 1))
+        );
+        let (npc, _) = game.ecs.query::<&CombatLogNpc>().iter().next().unwrap();
+        assert!(game.ecs.get::<EntityRemoveEvent>(npc).is_ok());
+    }
+
+    #[test]
+    fn pvp_attacks_tag_both_players() {
+        let mut game = game_with_action(CombatLogAction::EventOnly);
+        let attacker = game.ecs.spawn((Player,));
+        let target = game.ecs.spawn((Player,));
+        let mob = game.ecs.spawn(());
+
+        on_attack(&mut game, attacker, mob).unwrap();
+        assert!(!in_combat(&game, attacker));
+
+        on_attack(&mut game, attacker, target).unwrap();
+        assert!(in_combat(&game, attacker));
+        assert!(in_combat(&game, target));
+
+        game.tick_count += 10 * TPS as u64;
+        assert!(!in_combat(&game, attacker));
+    }
+}
@@ -0,0 +1,455 @@
+//! Commands sent by players through chat.
+//!
+//! Commands are added to the [`CommandRegistry`] resource,
+//! both by this crate and by crates which cannot be depended
+//! on by `feather-common`, like the plugin host.
+//!
+//! [`complete`] provides tab completion of commands.
+
+use ahash::AHashMap;
+use anyhow::{anyhow, bail, Context};
+use base::{nbt_conversion, Position, Text};
+use ecs::{Entity, SysResult};
+use quill_common::{components::Name, entities::Player};
+
+use crate::{
+    chat::{ChatKind, ChatMessage},
+    entity_nbt::{self, spawn_with_nbt},
+    permissions::Permissions,
+    vanish::{self, Vanished},
+    Game,
+};
+
+/// Permission to use `/summon`.
+pub const SUMMON_PERMISSION: &str = "feather.summon";
+
+/// A command handler. Returns the feedback sent to the sender.
+pub type CommandFn = fn(&mut Game, Entity, &[&str]) -> anyhow::Result<Text>;
+
+/// Registry of commands, by name.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: AHashMap<&'static str, CommandFn>,
+}
+
+impl CommandRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a command, replacing any existing
+    /// command with the same name.
+    pub fn register(&mut self, name: &'static str, command: CommandFn) {
+        self.commands.insert(name, command);
+    }
+
+    pub fn get(&self, name: &str) -> Option<CommandFn> {
+        self.commands.get(name).copied()
+    }
+
+    /// Returns the names of all registered commands.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.commands.keys().copied()
+    }
+}
+
+pub fn register(game: &mut Game) {
+    let mut registry = CommandRegistry::new();
+    registry.register("summon", summon);
+    game.insert_resource(registry);
+}
+
+/// Executes a command, given without its leading slash.
+///
+/// Feedback and errors are sent to the chat box of `sender`.
+pub fn execute(game: &mut Game, sender: Entity, command: &str) -> SysResult {
+    let mut args = command.split_whitespace();
+    let name = args.next().unwrap_or_default();
+    let args: Vec<&str> = args.collect();
+
+    let registered = game.resources.get::<CommandRegistry>()?.get(name);
+    let result = match registered {
+        Some(command) => command(game, sender, &args),
+        None => Err(anyhow!("unknown command '{}'", name)),
+    };
+    let feedback = match result {
+        Ok(feedback) => feedback,
+        Err(e) => Text::of(e.to_string()),
+    };
+    game.send_message(sender, ChatMessage::new(ChatKind::System, feedback))
+}
+
+/// Fails unless `sender` has the permission `node`.
+pub fn check_permission(game: &Game, sender: Entity, node: &str) -> anyhow::Result<()> {
+    let allowed = game
+        .ecs
+        .get::<Permissions>(sender)
+        .map(|permissions| permissions.has(node))
+        .unwrap_or(false);
+    if !allowed {
+        bail!("you don't have permission to use this command");
+    }
+    Ok(())
+}
+
+/// Suggestions for the word being typed in a command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Completions {
+    /// Index of the first character of the word,
+    /// in UTF-16 code units as the client counts them.
+    pub start: usize,
+    /// Length of the word in UTF-16 code units.
+    pub length: usize,
+    pub matches: Vec<String>,
+}
+
+/// Completes the last word of a partially typed command, given
+/// without its leading slash. The first word completes to command
+/// names, and arguments to the names of players `sender` can see.
+pub fn complete(game: &Game, sender: Entity, command: &str) -> anyhow::Result<Completions> {
+    let word_start = command.rfind(' ').map_or(0, |space| space + 1);
+    let word = &command[word_start..];
+
+    let candidates: Vec<String> = if word_start == 0 {
+        command_names(game)?
+    } else {
+        visible_player_names(game, sender)
+    };
+
+    let prefix = word.to_lowercase();
+    let mut matches: Vec<String> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().starts_with(&prefix))
+        .collect();
+    matches.sort_unstable();
+    matches.dedup();
+
+    Ok(Completions {
+        start: command[..word_start].encode_utf16().count(),
+        length: word.encode_utf16().count(),
+        matches,
+    })
+}
+
+/// Returns the names of all commands, sorted,
+/// which are declared to clients when they join.
+pub fn command_names(game: &Game) -> anyhow::Result<Vec<String>> {
+    let registry = game.resources.get::<CommandRegistry>()?;
+    let mut names: Vec<String> = registry.names().map(str::to_owned).collect();
+    names.sort_unstable();
+    Ok(names)
+}
+
+fn visible_player_names(game: &Game, viewer: Entity) -> Vec<String> {
+    let sees_vanished = game
+        .ecs
+        .get::<Permissions>(viewer)
+        .map(|permissions| vanish::sees_vanished(&permissions))
+        .unwrap_or(false);
+    game.ecs
+        .query::<(&Player, &Name)>()
+        .iter()
+        .filter(|(player, _)| sees_vanished || game.ecs.get::<Vanished>(*player).is_err())
+        .map(|(_, (_, name))| name.to_string())
+        .collect()
+}
+
+/// `/summon <entity> [<x> <y> <z>] [<nbt>]`
+///
+/// The entity is summoned at the sender's position if no
+/// coordinates are given. `<nbt>` is an SNBT compound of
+/// tags applied with [`spawn_with_nbt`].
+fn summon(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    check_permission(game, sender, SUMMON_PERMISSION)?;
+    let name = args
+        .first()
+        .context("usage: /summon <entity> [<x> <y> <z>] [<nbt>]")?;
+    let kind = entity_nbt::summonable_kind(name)?;
+
+    let coordinates = match &args[1..] {
+        [] => ["~", "~", "~"],
+        [x, y, z, ..] => [*x, *y, *z],
+        _ => bail!("expected three coordinates"),
+    };
+    // Console and RCON senders have no position, so
+    // only look it up for coordinates relative to it.
+    let needs_origin = coordinates
+        .iter()
+        .any(|arg| arg.starts_with('~') || arg.starts_with('^'));
+    let origin = if needs_origin {
+        Some(
+            *game
+                .ecs
+                .get::<Position>(sender)
+                .map_err(|_| anyhow!("a position is required"))?,
+        )
+    } else {
+        None
+    };
+    let position = parse_position(coordinates, origin)?;
+
+    // SNBT may contain spaces, which split it into several arguments.
+    let nbt = if args.len() > 4 {
+        nbt_conversion::from_snbt(&args[4..].join(" "))
+            .map_err(|e| anyhow!("invalid entity data: {}", e))?
+    } else {
+        nbt::Value::Compound(Default::default())
+    };
+
+    spawn_with_nbt(game, kind, position, &nbt)?;
+    Ok(Text::translate_with(
+        "commands.summon.success",
+        vec![kind.name()],
+    ))
+}
+
+/// Parses the three coordinates of a position. They are either
+/// absolute or relative to `origin` (`~`), or all local
+/// coordinates (`^`) along the left, up and forward
+/// axes of `origin`'s rotation.
+///
+/// `origin` must be given if any coordinate is relative.
+fn parse_position(args: [&str; 3], origin: Option<Position>) -> anyhow::Result<Position> {
+    let origin = origin.unwrap_or_default();
+    let local = args.iter().filter(|arg| arg.starts_with('^')).count();
+    let mut position = Position {
+        pitch: 0.0,
+        yaw: 0.0,
+        ..origin
+    };
+    match local {
+        0 => {
+            position.x = parse_coordinate(args[0], origin.x)?;
+            position.y = parse_coordinate(args[1], origin.y)?;
+            position.z = parse_coordinate(args[2], origin.z)?;
+        }
+        3 => {
+            let [left, up, forward] = [
+                parse_local_coordinate(args[0])?,
+                parse_local_coordinate(args[1])?,
+                parse_local_coordinate(args[2])?,
+            ];
+            let [x, y, z] = local_offset(&origin, left, up, forward);
+            position.x += x;
+            position.y += y;
+            position.z += z;
+        }
+        _ => bail!("cannot mix world and local coordinates"),
+    }
+    Ok(position)
+}
+
+/// Parses an absolute coordinate or one relative
+/// to `origin`, like `~` or `~-2.5`.
+fn parse_coordinate(arg: &str, origin: f64) -> anyhow::Result<f64> {
+    let (relative, number) = match arg.strip_prefix('~') {
+        Some(offset) => (true, offset),
+        None => (false, arg),
+    };
+    let value = if relative && number.is_empty() {
+        0.0
+    } else {
+        number
+            .parse::<f64>()
+            .with_context(|| format!("invalid coordinate '{}'", arg))?
+    };
+    Ok(if relative { origin + value } else { value })
+}
+
+/// Parses a local coordinate, like `^` or `^2`.
+fn parse_local_coordinate(arg: &str) -> anyhow::Result<f64> {
+    match arg.strip_prefix('^') {
+        Some("") => Ok(0.0),
+        Some(number) => number
+            .parse::<f64>()
+            .with_context(|| format!("invalid coordinate '{}'", arg)),
+        None => bail!("cannot mix world and local coordinates"),
+    }
+}
+
+/// Converts an offset along the left, up and forward axes
+/// of `origin`'s rotation to world coordinates, like vanilla.
+fn local_offset(origin: &Position, left: f64, up: f64, forward: f64) -> [f64; 3] {
+    let yaw = (f64::from(origin.yaw) + 90.0).to_radians();
+    let pitch = -f64::from(origin.pitch).to_radians();
+    let pitch_up = pitch + std::f64::consts::FRAC_PI_2;
+
+    let forward_axis = [
+        yaw.cos() * pitch.cos(),
+        pitch.sin(),
+        yaw.sin() * pitch.cos(),
+    ];
+    let up_axis = [
+        yaw.cos() * pitch_up.cos(),
+        pitch_up.sin(),
+        yaw.sin() * pitch_up.cos(),
+    ];
+    // The left axis is the negated cross product of the other two.
+    let left_axis = [
+        -(forward_axis[1] * up_axis[2] - forward_axis[2] * up_axis[1]),
+        -(forward_axis[2] * up_axis[0] - forward_axis[0] * up_axis[2]),
+        -(forward_axis[0] * up_axis[1] - forward_axis[1] * up_axis[0]),
+    ];
+
+    let mut offset = [0.0; 3];
+    for (i, component) in offset.iter_mut().enumerate() {
+        *component = forward_axis[i] * forward + up_axis[i] * up + left_axis[i] * left;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use base::EntityKind;
+    use quill_common::components::CustomName;
+
+    use super::*;
+    use crate::{
+        health::Health,
+        riding::{Passengers, Vehicle},
+    };
+
+    fn noop(_: &mut Game, _: Entity, _: &[&str]) -> anyhow::Result<Text> {
+        Ok(Text::of(""))
+    }
+
+    #[test]
+    fn summon_requires_permission() {
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Position::default(),));
+        let error = summon(&mut game, sender, &["zombie"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "you don't have permission to use this command"
+        );
+    }
+
+    fn summoning_game() -> Game {
+        let mut game = Game::new();
+        game.add_entity_spawn_callback(crate::entities::add_entity_components);
+        game
+    }
+
+    fn summoner(game: &mut Game) -> Entity {
+        game.ecs
+            .spawn((Permissions::new(vec![SUMMON_PERMISSION.to_owned()]),))
+    }
+
+    #[test]
+    fn summon_without_position() {
+        let mut game = summoning_game();
+        let console = summoner(&mut game);
+
+        let error = summon(&mut game, console, &["zombie", "~", "64", "0"]).unwrap_err();
+        assert_eq!(error.to_string(), "a position is required");
+        let error = summon(&mut game, console, &["zombie"]).unwrap_err();
+        assert_eq!(error.to_string(), "a position is required");
+
+        summon(&mut game, console, &["zombie", "1", "64", "-2.5"]).unwrap();
+        let (_, (_, position)) = game
+            .ecs
+            .query::<(&EntityKind, &Position)>()
+            .iter()
+            .next()
+            .unwrap();
+        assert_eq!((position.x, position.y, position.z), (1.0, 64.0, -2.5));
+    }
+
+    #[test]
+    fn summon_applies_nbt_and_spawns_riders() {
+        let mut game = summoning_game();
+        let sender = summoner(&mut game);
+        game.ecs.insert(sender, Position::default()).unwrap();
+
+        summon(
+            &mut game,
+            sender,
+            &[
+                "spider",
+                "~",
+                "~1",
+                "~",
+                "{CustomName:'\"Bob\"',",
+                "Passengers:[{id:\"minecraft:skeleton\",Health:5f}]}",
+            ],
+        )
+        .unwrap();
+
+        let (spider, _) = game
+            .ecs
+            .query::<&EntityKind>()
+            .iter()
+            .find(|(_, &kind)| kind == EntityKind::Spider)
+            .unwrap();
+        assert_eq!(&**game.ecs.get::<CustomName>(spider).unwrap(), "Bob");
+        assert_eq!(game.ecs.get::<Position>(spider).unwrap().y, 65.0);
+
+        let (skeleton, _) = game
+            .ecs
+            .query::<&EntityKind>()
+            .iter()
+            .find(|(_, &kind)| kind == EntityKind::Skeleton)
+            .unwrap();
+        assert_eq!(game.ecs.get::<Health>(skeleton).unwrap().current, 5.0);
+        assert_eq!(*game.ecs.get::<Vehicle>(skeleton).unwrap(), Vehicle(spider));
+        assert_eq!(
+            *game.ecs.get::<Passengers>(spider).unwrap(),
+            Passengers(vec![skeleton])
+        );
+    }
+
+    #[test]
+    fn local_coordinates_follow_rotation() {
+        let origin = Position {
+            x: 0.0,
+            y: 64.0,
+            z: 0.0,
+            pitch: 0.0,
+            yaw: 0.0,
+        };
+        // With a yaw of 0, forward is +Z and left is +X.
+        let position = parse_position(["^1", "^2", "^3"], Some(origin)).unwrap();
+        assert!((position.x - 1.0).abs() < 1e-6);
+        assert!((position.y - 66.0).abs() < 1e-6);
+        assert!((position.z - 3.0).abs() < 1e-6);
+
+        assert!(parse_position(["^", "~", "^"], Some(origin)).is_err());
+    }
+
+    #[test]
+    fn complete_commands_and_players() {
+        let mut game = Game::new();
+        register(&mut game);
+        game.resources
+            .get_mut::<CommandRegistry>()
+            .unwrap()
+            .register("say", noop);
+        let sender = game.ecs.spawn((Player, Name::new("Steve")));
+        game.ecs.spawn((Player, Name::new("Sam"), Vanished));
+
+        let completions = complete(&game, sender, "s").unwrap();
+        assert_eq!(completions.start, 0);
+        assert_eq!(completions.length, 1);
+        assert_eq!(completions.matches, vec!["say", "summon"]);
+
+        let completions = complete(&game, sender, "say hi s").unwrap();
+        assert_eq!(completions.start, 7);
+        assert_eq!(completions.matches, vec!["Steve"]);
+
+        game.ecs
+            .insert(
+                sender,
+                Permissions::new(vec![vanish::SEE_VANISHED_PERMISSION.to_owned()]),
+            )
+            .unwrap();
+        let completions = complete(&game, sender, "say ").unwrap();
+        assert_eq!(completions.length, 0);
+        assert_eq!(completions.matches, vec!["Sam", "Steve"]);
+
+        // Characters outside the BMP take two UTF-16 code units.
+        let completions = complete(&game, sender, "say \u{1F600} St").unwrap();
+        assert_eq!(completions.start, 7);
+        assert_eq!(completions.length, 2);
+    }
+}
@@ -0,0 +1,442 @@
+//! Conduits: frame detection, conduit power
+//! and attacks on hostile mobs in water.
+//!
+//! Conduits are tracked as they are placed
+//! and as the chunks containing them load.
+
+use std::sync::Arc;
+
+use ahash::AHashMap;
+use base::{
+    chunk::{SECTION_HEIGHT, SECTION_WIDTH},
+    BlockId, BlockPosition, Chunk, EntityKind, Position,
+};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::entities::Player;
+use uuid::Uuid;
+
+use crate::{
+    effects::{add_effect, ActiveEffect, StatusEffect},
+    events::{BlockChangeEvent, ChunkLoadEvent},
+    health, Game,
+};
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(Conduits::default());
+    systems
+        .add_system(track_conduits)
+        .add_system(track_loaded_conduits)
+        .add_system(tick_conduits)
+        .add_system(damage_conduit_targets);
+}
+
+/// Ticks between two updates of a conduit.
+pub const CONDUIT_PERIOD: u64 = 40;
+
+/// Duration of the conduit power effect given to players.
+pub const CONDUIT_POWER_DURATION: u32 = 260;
+
+/// Number of frame blocks needed for a conduit to activate.
+pub const MIN_FRAME_BLOCKS: usize = 16;
+
+/// Number of frame blocks in a complete frame. Only
+/// conduits with a complete frame attack hostile mobs.
+pub const FULL_FRAME_BLOCKS: usize = 42;
+
+/// Distance from a conduit at which hostile mobs are attacked.
+pub const ATTACK_RANGE: f64 = 8.0;
+
+/// Damage dealt to the conduit's target on each update.
+pub const ATTACK_DAMAGE: f32 = 4.0;
+
+/// Blocks a conduit frame can be built from.
+pub const FRAME_BLOCKS: [BlockKind; 4] = [
+    BlockKind::Prismarine,
+    BlockKind::PrismarineBricks,
+    BlockKind::SeaLantern,
+    BlockKind::DarkPrismarine,
+];
+
+/// Hostile entities which conduits attack.
+pub const HOSTILE_KINDS: &[EntityKind] = &[
+    EntityKind::Blaze,
+    EntityKind::CaveSpider,
+    EntityKind::Creeper,
+    EntityKind::Drowned,
+    EntityKind::ElderGuardian,
+    EntityKind::Enderman,
+    EntityKind::Endermite,
+    EntityKind::Evoker,
+    EntityKind::Ghast,
+    EntityKind::Giant,
+    EntityKind::Guardian,
+    EntityKind::Hoglin,
+    EntityKind::Husk,
+    EntityKind::MagmaCube,
+    EntityKind::Phantom,
+    EntityKind::Pillager,
+    EntityKind::Ravager,
+    EntityKind::Shulker,
+    EntityKind::Silverfish,
+    EntityKind::Skeleton,
+    EntityKind::Slime,
+    EntityKind::Spider,
+    EntityKind::Stray,
+    EntityKind::Vex,
+    EntityKind::Vindicator,
+    EntityKind::Witch,
+    EntityKind::Wither,
+    EntityKind::WitherSkeleton,
+    EntityKind::Zoglin,
+    EntityKind::Zombie,
+    EntityKind::ZombieVillager,
+    EntityKind::ZombifiedPiglin,
+];
+
+/// State of a conduit as of its last update.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConduitState {
+    /// Whether the conduit is surrounded by water
+    /// and has enough frame blocks.
+    pub active: bool,
+    pub frame_blocks: usize,
+    /// The hostile entity the conduit attacks.
+    pub target: Option<Entity>,
+}
+
+impl ConduitState {
+    /// Returns the distance within which players get conduit power.
+    pub fn range(&self) -> f64 {
+        (self.frame_blocks / 7 * 16) as f64
+    }
+}
+
+/// The conduits in the world.
+#[derive(Default)]
+pub struct Conduits {
+    conduits: AHashMap<BlockPosition, ConduitState>,
+}
+
+impl Conduits {
+    pub fn get(&self, position: BlockPosition) -> Option<ConduitState> {
+        self.conduits.get(&position).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BlockPosition, ConduitState)> + '_ {
+        self.conduits
+            .iter()
+            .map(|(&position, &state)| (position, state))
+    }
+}
+
+/// Event triggered when a conduit activates, deactivates
+/// or changes its target. Clients use this to animate the conduit.
+#[derive(Copy, Clone, Debug)]
+pub struct ConduitUpdateEvent {
+    pub position: BlockPosition,
+    pub active: bool,
+    /// UUID of the attacked entity.
+    pub target: Option<Uuid>,
+}
+
+/// Event triggered on an entity attacked by a conduit.
+#[derive(Copy, Clone, Debug)]
+pub struct ConduitAttackEvent {
+    pub conduit: BlockPosition,
+    pub damage: f32,
+}
+
+/// Returns the positions around a conduit at which
+/// frame blocks count: three rings of 16 blocks, in
+/// the planes through the conduit, 2 blocks away from it.
+pub fn frame_positions(conduit: BlockPosition) -> impl Iterator<Item = BlockPosition> {
+    (-2..=2)
+        .flat_map(|x| (-2..=2).map(move |y| (x, y)))
+        .flat_map(|(x, y)| (-2..=2).map(move |z| (x, y, z)))
+        .filter(|&(x, y, z): &(i32, i32, i32)| {
+            let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+            (x == 0 && (ay == 2 || az == 2))
+                || (y == 0 && (ax == 2 || az == 2))
+                || (z == 0 && (ax == 2 || ay == 2))
+        })
+        .map(move |(x, y, z)| BlockPosition::new(conduit.x + x, conduit.y + y, conduit.z + z))
+}
+
+/// Returns whether a block contains water.
+pub fn is_water(block: BlockId) -> bool {
+    matches!(
+        block.kind(),
+        BlockKind::Water
+            | BlockKind::BubbleColumn
+            | BlockKind::Kelp
+            | BlockKind::KelpPlant
+            | BlockKind::Seagrass
+            | BlockKind::TallSeagrass
+    ) || block.waterlogged() == Some(true)
+}
+
+/// Returns whether the feet or head of an entity at `position` are in water.
+pub fn is_in_water(game: &Game, position: Position) -> bool {
+    let mut head = position;
+    head.y += 1.0;
+    [position, head]
+        .iter()
+        .any(|position| game.block(position.block()).map_or(false, is_water))
+}
+
+/// Checks the water and frame around a conduit.
+fn check_structure(game: &Game, conduit: BlockPosition) -> (bool, usize) {
+    let surrounded_by_water = (-1..=1)
+        .flat_map(|x| (-1..=1).map(move |y| (x, y)))
+        .flat_map(|(x, y)| (-1..=1).map(move |z| (x, y, z)))
+        .all(|(x, y, z)| {
+            game.block(BlockPosition::new(
+                conduit.x + x,
+                conduit.y + y,
+                conduit.z + z,
+            ))
+            .map_or(false, is_water)
+        });
+    let frame_blocks = frame_positions(conduit)
+        .filter(|&position| {
+            game.block(position)
+                .map_or(false, |block| FRAME_BLOCKS.contains(&block.kind()))
+        })
+        .count();
+    (
+        surrounded_by_water && frame_blocks >= MIN_FRAME_BLOCKS,
+        frame_blocks,
+    )
+}
+
+/// Returns whether `entity` is a hostile entity in water
+/// within attack range of the conduit.
+fn is_valid_target(game: &Game, conduit: Position, entity: Entity) -> bool {
+    let (kind, position) = match (
+        game.ecs.get::<EntityKind>(entity),
+        game.ecs.get::<Position>(entity),
+    ) {
+        (Ok(kind), Ok(position)) => (*kind, *position),
+        _ => return false,
+    };
+    HOSTILE_KINDS.contains(&kind)
+        && (position.x - conduit.x).abs() <= ATTACK_RANGE
+        && (position.y - conduit.y).abs() <= ATTACK_RANGE
+        && (position.z - conduit.z).abs() <= ATTACK_RANGE
+        && is_in_water(game, position)
+}
+
+/// Finds the nearest hostile entity the conduit can attack.
+fn find_target(game: &Game, conduit: Position) -> Option<Entity> {
+    game.ecs
+        .query::<(&EntityKind, &Position)>()
+        .iter()
+        .filter(|&(entity, _)| is_valid_target(game, conduit, entity))
+        .map(|(entity, (_, position))| (entity, position.distance_squared_to(conduit)))
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(entity, _)| entity)
+}
+
+/// Starts tracking placed conduits and forgets broken ones.
+fn track_conduits(game: &mut Game) -> SysResult {
+    let resources = Arc::clone(&game.resources);
+    let mut conduits = resources.get_mut::<Conduits>()?;
+    for (_, event) in game.ecs.query::<&BlockChangeEvent>().iter() {
+        for position in event.iter_changed_blocks() {
+            let is_conduit = game
+                .block(position)
+                .map_or(false, |block| block.kind() == BlockKind::Conduit);
+            if is_conduit {
+                conduits.conduits.entry(position).or_default();
+            } else {
+                conduits.conduits.remove(&position);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the positions of the conduits in `chunk`.
+///
+/// Sections whose palette has no conduit are skipped.
+pub fn conduits_in(chunk: &Chunk) -> Vec<BlockPosition> {
+    let origin = chunk.position();
+    let mut conduits = Vec::new();
+    // The first section lies below the world and only holds light.
+    for (index, section) in chunk.sections().iter().enumerate() {
+        let section = match section {
+            Some(section) => section,
+            None => continue,
+        };
+        let may_contain_conduit = section.blocks().palette().map_or(true, |palette| {
+            palette
+                .as_slice()
+                .iter()
+                .any(|block| block.kind() == BlockKind::Conduit)
+        });
+        if !may_contain_conduit {
+            continue;
+        }
+
+        let base_y = (index as i32 - 1) * SECTION_HEIGHT as i32;
+        for y in 0..SECTION_HEIGHT {
+            for z in 0..SECTION_WIDTH {
+                for x in 0..SECTION_WIDTH {
+                    let is_conduit = section
+                        .block_at(x, y, z)
+                        .map_or(false, |block| block.kind() == BlockKind::Conduit);
+                    if is_conduit {
+                        conduits.push(BlockPosition::new(
+                            origin.x * SECTION_WIDTH as i32 + x as i32,
+                            base_y + y as i32,
+                            origin.z * SECTION_WIDTH as i32 + z as i32,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    conduits
+}
+
+/// Starts tracking the conduits in newly loaded chunks.
+fn track_loaded_conduits(game: &mut Game) -> SysResult {
+    let mut conduits = game.resources.get_mut::<Conduits>()?;
+    for (_, event) in game.ecs.query::<&ChunkLoadEvent>().iter() {
+        for position in conduits_in(&event.chunk.read()) {
+            conduits.conduits.entry(position).or_default();
+        }
+    }
+    Ok(())
+}
+
+/// Updates conduits, giving conduit power to nearby
+/// players and attacking hostile mobs.
+fn tick_conduits(game: &mut Game) -> SysResult {
+    if game.tick_count % CONDUIT_PERIOD != 0 {
+        return Ok(());
+    }
+
+    let resources = Arc::clone(&game.resources);
+    let mut conduits = resources.get_mut::<Conduits>()?;
+
+    let mut powered = Vec::new();
+    let mut attacks = Vec::new();
+    for (&position, state) in &mut conduits.conduits {
+        let (active, frame_blocks) = check_structure(game, position);
+        let center: Position = position.into();
+        let target = if active && frame_blocks >= FULL_FRAME_BLOCKS {
+            state
+                .target
+                .filter(|&target| is_valid_target(game, center, target))
+                .or_else(|| find_target(game, center))
+        } else {
+            None
+        };
+
+        if active != state.active || target != state.target {
+            let event = ConduitUpdateEvent {
+                position,
+                active,
+                target: target
+                    .and_then(|target| game.ecs.get::<Uuid>(target).ok().map(|uuid| *uuid)),
+            };
+            game.ecs.insert_event(event);
+        }
+        *state = ConduitState {
+            active,
+            frame_blocks,
+            target,
+        };
+
+        if !active {
+            continue;
+        }
+        let range = state.range();
+        for (player, (_, player_position)) in game.ecs.query::<(&Player, &Position)>().iter() {
+            if player_position.distance_squared_to(center) <= range * range
+                && is_in_water(game, *player_position)
+            {
+                powered.push(player);
+            }
+        }
+        if let Some(target) = target {
+            attacks.push((target, position));
+        }
+    }
+    drop(conduits);
+
+    for player in powered {
+        add_effect(
+            game,
+            player,
+            ActiveEffect {
+                ambient: true,
+                ..ActiveEffect::new(StatusEffect::ConduitPower, 0, CONDUIT_POWER_DURATION)
+            },
+        )?;
+    }
+    for (target, conduit) in attacks {
+        game.ecs.insert_entity_event(
+            target,
+            ConduitAttackEvent {
+                conduit,
+                damage: ATTACK_DAMAGE,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Deals the damage of conduit attacks.
+fn damage_conduit_targets(game: &mut Game) -> SysResult {
+    let attacks: Vec<(Entity, f32)> = game
+        .ecs
+        .query::<&ConduitAttackEvent>()
+        .iter()
+        .map(|(target, event)| (target, event.damage))
+        .collect();
+    for (target, damage) in attacks {
+        health::damage(game, target, damage)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::ChunkPosition;
+
+    use super::*;
+
+    #[test]
+    fn full_frame_has_42_blocks() {
+        let conduit = BlockPosition::new(10, 64, -3);
+        let positions: Vec<_> = frame_positions(conduit).collect();
+        assert_eq!(positions.len(), FULL_FRAME_BLOCKS);
+        assert!(positions.contains(&BlockPosition::new(12, 64, -3)));
+        assert!(positions.contains(&BlockPosition::new(12, 66, -3)));
+        assert!(!positions.contains(&BlockPosition::new(12, 66, -1)));
+        assert!(!positions.contains(&BlockPosition::new(11, 64, -3)));
+    }
+
+    #[test]
+    fn conduits_in_loaded_chunk() {
+        let mut chunk = Chunk::new(ChunkPosition::new(2, -1));
+        chunk.set_block_at(3, 70, 15, BlockId::conduit());
+        chunk.set_block_at(0, 0, 0, BlockId::stone());
+        assert_eq!(conduits_in(&chunk), vec![BlockPosition::new(35, 70, -1)]);
+    }
+
+    #[test]
+    fn range_grows_every_seven_blocks() {
+        let state = |frame_blocks| ConduitState {
+            active: true,
+            frame_blocks,
+            target: None,
+        };
+        assert_eq!(state(16).range(), 32.0);
+        assert_eq!(state(21).range(), 48.0);
+        assert_eq!(state(42).range(), 96.0);
+    }
+}
@@ -0,0 +1,454 @@
+//! Mob conversions: an entity turning into an entity
+//! of another kind after a delay, like a zombie
+//! drowning or a zombie villager being cured.
+
+use std::{ops::RangeInclusive, sync::Arc};
+
+use base::{Area, EntityKind, Gamemode, Inventory, Item, Position};
+use blocks::BlockKind;
+use ecs::{Component, Entity, EntityBuilder, SysResult, SystemExecutor};
+use quill_common::{
+    components::{CustomName, HeadYaw},
+    entity_init::EntityInit,
+};
+use rand::Rng;
+
+use crate::{
+    effects::{self, ActiveEffect, StatusEffect, StatusEffects},
+    entities::player::HotbarSlot,
+    persistent_tags::PersistentTags,
+    window::consume_one,
+    Game,
+};
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(ConversionRules::default());
+    systems
+        .add_system(start_conversions)
+        .add_system(tick_conversions);
+}
+
+/// Number of ticks a zombie or husk has to stay
+/// underwater before it converts.
+pub const DROWNING_TICKS: u32 = 600;
+
+/// Number of ticks a cured zombie villager takes to turn
+/// into a villager, picked at random from this range.
+pub const CURE_TICKS: RangeInclusive<u32> = 3600..=6000;
+
+/// Condition checked each tick for entities matching a [`ConversionRule`].
+pub type ConversionCondition = fn(&Game, Entity) -> bool;
+
+/// Component for an entity which is converting into another kind of entity.
+#[derive(Copy, Clone, Debug)]
+pub struct Converting {
+    /// The entity to convert into.
+    pub into: EntityInit,
+    /// Ticks until the conversion finishes.
+    pub remaining_ticks: u32,
+    /// If set, the conversion is cancelled once
+    /// this condition no longer holds.
+    pub condition: Option<ConversionCondition>,
+}
+
+/// Event triggered on the new entity when a conversion finishes.
+#[derive(Copy, Clone, Debug)]
+pub struct ConversionEvent {
+    /// The entity which was converted. It is
+    /// removed at the end of the tick.
+    pub from: Entity,
+}
+
+/// Converts entities of kind `from` into `into`
+/// after `condition` held for `duration` ticks.
+#[derive(Copy, Clone, Debug)]
+pub struct ConversionRule {
+    pub from: EntityKind,
+    pub into: EntityInit,
+    pub duration: u32,
+    pub condition: ConversionCondition,
+}
+
+/// Converts entities of kind `from` into `into` when a
+/// player feeds them `item` while they have `effect`.
+///
+/// The effect is removed when the conversion starts.
+#[derive(Clone, Debug)]
+pub struct CureRule {
+    pub from: EntityKind,
+    pub into: EntityInit,
+    pub item: Item,
+    pub effect: StatusEffect,
+    /// Range the conversion time in ticks is picked from.
+    pub duration: RangeInclusive<u32>,
+}
+
+/// The conversion rules checked each tick, and
+/// the cures checked when players feed entities.
+///
+/// Plugins can add rules to this resource, or start other
+/// conversions with [`start_conversion`].
+pub struct ConversionRules {
+    rules: Vec<ConversionRule>,
+    cures: Vec<CureRule>,
+}
+
+impl ConversionRules {
+    /// Creates a registry without any rules.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            cures: Vec::new(),
+        }
+    }
+
+    /// Adds a rule. Only the first rule
+    /// matching an entity's kind is used.
+    pub fn add(&mut self, rule: ConversionRule) {
+        self.rules.push(rule);
+    }
+
+    /// Returns the rule for entities of the given kind.
+    pub fn rule_for(&self, kind: EntityKind) -> Option<&ConversionRule> {
+        self.rules.iter().find(|rule| rule.from == kind)
+    }
+
+    /// Adds a cure. Only the first cure matching
+    /// an entity's kind and the fed item is used.
+    pub fn add_cure(&mut self, cure: CureRule) {
+        self.cures.push(cure);
+    }
+
+    /// Returns the cure for entities of the given kind fed `item`.
+    pub fn cure_for(&self, kind: EntityKind, item: Item) -> Option<&CureRule> {
+        self.cures
+            .iter()
+            .find(|cure| cure.from == kind && cure.item == item)
+    }
+}
+
+impl Default for ConversionRules {
+    /// Creates a registry with the vanilla drowning
+    /// rules and zombie villager cure.
+    fn default() -> Self {
+        let mut rules = Self::new();
+        rules.add(ConversionRule {
+            from: EntityKind::Zombie,
+            into: EntityInit::Drowned,
+            duration: DROWNING_TICKS,
+            condition: is_head_in_water,
+        });
+        rules.add(ConversionRule {
+            from: EntityKind::Husk,
+            into: EntityInit::Zombie,
+            duration: DROWNING_TICKS,
+            condition: is_head_in_water,
+        });
+        rules.add_cure(CureRule {
+            from: EntityKind::ZombieVillager,
+            into: EntityInit::Villager,
+            item: Item::GoldenApple,
+            effect: StatusEffect::Weakness,
+            duration: CURE_TICKS,
+        });
+        rules
+    }
+}
+
+/// Returns whether the block at the entity's head is water.
+pub fn is_head_in_water(game: &Game, entity: Entity) -> bool {
+    let mut head = match game.ecs.get::<Position>(entity) {
+        Ok(position) => *position,
+        Err(_) => return false,
+    };
+    head.y += 1.0;
+    matches!(
+        game.block(head.block()),
+        Some(block) if block.kind() == BlockKind::Water
+    )
+}
+
+/// Starts converting `entity` into `into` after `ticks` ticks,
+/// replacing any conversion already in progress.
+pub fn start_conversion(
+    game: &mut Game,
+    entity: Entity,
+    into: EntityInit,
+    ticks: u32,
+) -> SysResult {
+    game.ecs.insert(
+        entity,
+        Converting {
+            into,
+            remaining_ticks: ticks,
+            condition: None,
+        },
+    )?;
+    Ok(())
+}
+
+/// Called when `player` right-clicks `target`. Starts curing the
+/// target if the player holds the item of a matching [`CureRule`]
+/// and the target has its effect, consuming one of the items
+/// unless the player is in creative mode.
+///
+/// Returns whether a cure started, changing the player's inventory.
+pub fn on_interact(game: &mut Game, player: Entity, target: Entity) -> anyhow::Result<bool> {
+    let kind = match game.ecs.get::<EntityKind>(target) {
+        Ok(kind) => *kind,
+        Err(_) => return Ok(()),
+    };
+    if game.ecs.get::<Converting>(target).is_ok() {
+        return Ok(false);
+    }
+    let (inventory, hotbar_slot) = match (
+        game.ecs.get::<Inventory>(player),
+        game.ecs.get::<HotbarSlot>(player),
+    ) {
+        (Ok(inventory), Ok(slot)) => (inventory.new_handle(), slot.get()),
+        _ => return Ok(()),
+    };
+    let held = match inventory
+        .item(Area::Hotbar, hotbar_slot)
+        .and_then(|item| item.as_ref().map(|stack| stack.item))
+    {
+        Some(item) => item,
+        None => return Ok(()),
+    };
+
+    let cure = match game
+        .resources
+        .get::<ConversionRules>()?
+        .cure_for(kind, held)
+    {
+        Some(cure) => cure.clone(),
+        None => return Ok(()),
+    };
+    let has_effect = game
+        .ecs
+        .get::<StatusEffects>(target)
+        .map(|effects| effects.contains(cure.effect))
+        .unwrap_or(false);
+    if !has_effect {
+        return Ok(false);
+    }
+
+    let creative = matches!(
+        game.ecs.get::<Gamemode>(player).map(|gamemode| *gamemode),
+        Ok(Gamemode::Creative)
+    );
+    if !creative {
+        if let Some(mut item) = inventory.item(Area::Hotbar, hotbar_slot) {
+            consume_one(&mut item);
+        }
+    }
+
+    let ticks = rand::thread_rng().gen_range(cure.duration);
+    effects::remove_effect(game, target, cure.effect)?;
+    effects::add_effect(
+        game,
+        target,
+        ActiveEffect::new(StatusEffect::Strength, 0, ticks),
+    )?;
+    start_conversion(game, target, cure.into, ticks)?;
+    Ok(true)
+}
+
+/// Starts conversions for entities matching a rule.
+fn start_conversions(game: &mut Game) -> SysResult {
+    let resources = Arc::clone(&game.resources);
+    let rules = resources.get::<ConversionRules>()?;
+
+    let mut started = Vec::new();
+    for (entity, (&kind, converting)) in game
+        .ecs
+        .query::<(&EntityKind, Option<&Converting>)>()
+        .iter()
+    {
+        if converting.is_some() {
+            continue;
+        }
+        if let Some(rule) = rules.rule_for(kind) {
+            if (rule.condition)(game, entity) {
+                started.push((entity, *rule));
+            }
+        }
+    }
+
+    for (entity, rule) in started {
+        game.ecs.insert(
+            entity,
+            Converting {
+                into: rule.into,
+                remaining_ticks: rule.duration,
+                condition: Some(rule.condition),
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Advances conversions, cancelling those whose
+/// condition no longer holds and finishing completed ones.
+fn tick_conversions(game: &mut Game) -> SysResult {
+    let mut cancelled = Vec::new();
+    let mut finished = Vec::new();
+    for (entity, converting) in game.ecs.query::<&mut Converting>().iter() {
+        if let Some(condition) = converting.condition {
+            if !condition(game, entity) {
+                cancelled.push(entity);
+                continue;
+            }
+        }
+
+        converting.remaining_ticks = converting.remaining_ticks.saturating_sub(1);
+        if converting.remaining_ticks == 0 {
+            finished.push((entity, converting.into));
+        }
+    }
+
+    for entity in cancelled {
+        game.ecs.remove::<Converting>(entity)?;
+    }
+    for (entity, into) in finished {
+        convert(game, entity, into)?;
+    }
+    Ok(())
+}
+
+/// Replaces `entity` with a new entity of the given kind,
+/// keeping its position and rotation, custom name,
+/// equipment and tags.
+fn convert(game: &mut Game, entity: Entity, into: EntityInit) -> SysResult {
+    let position = *game.ecs.get::<Position>(entity)?;
+
+    let mut builder = game.create_entity_builder(position, into);
+    carry_over::<HeadYaw>(game, entity, &mut builder);
+    carry_over::<CustomName>(game, entity, &mut builder);
+    carry_over::<Inventory>(game, entity, &mut builder);
+    carry_over::<PersistentTags>(game, entity, &mut builder);
+    let new_entity = game.spawn_entity(builder);
+
+    game.ecs
+        .insert_entity_event(new_entity, ConversionEvent { from: entity })?;
+    game.remove_entity(entity)?;
+    Ok(())
+}
+
+/// Adds the component `T` of `entity`, if it has
+/// one, to the builder of the converted entity.
+fn carry_over<T: Component + Clone>(game: &Game, entity: Entity, builder: &mut EntityBuilder) {
+    if let Ok(component) = game.ecs.get::<T>(entity) {
+        builder.add(T::clone(&component));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::ItemStack;
+
+    use super::*;
+
+    /// Spawns a player holding two golden apples and a zombie villager.
+    fn spawn_cure(game: &mut Game) -> (Entity, Inventory, Entity) {
+        game.insert_resource(ConversionRules::default());
+        let inventory = Inventory::player();
+        *inventory.item(Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::GoldenApple, 2));
+        let player = game.ecs.spawn((
+            inventory.new_handle(),
+            HotbarSlot::default(),
+            Gamemode::Survival,
+        ));
+        let zombie_villager = game
+            .ecs
+            .spawn((EntityKind::ZombieVillager, Position::default()));
+        (player, inventory, zombie_villager)
+    }
+
+    #[test]
+    fn cures_need_weakness() {
+        let mut game = Game::new();
+        let (player, inventory, zombie_villager) = spawn_cure(&mut game);
+
+        assert!(!on_interact(&mut game, player, zombie_villager).unwrap());
+        assert!(game.ecs.get::<Converting>(zombie_villager).is_err());
+        assert_eq!(
+            *inventory.item(Area::Hotbar, 0).unwrap(),
+            Some(ItemStack::new(Item::GoldenApple, 2))
+        );
+    }
+
+    #[test]
+    fn golden_apples_cure_weakened_zombie_villagers() {
+        let mut game = Game::new();
+        let (player, inventory, zombie_villager) = spawn_cure(&mut game);
+        effects::add_effect(
+            &mut game,
+            zombie_villager,
+            ActiveEffect::new(StatusEffect::Weakness, 0, 600),
+        )
+        .unwrap();
+
+        assert!(on_interact(&mut game, player, zombie_villager).unwrap());
+        let converting = *game.ecs.get::<Converting>(zombie_villager).unwrap();
+        assert_eq!(converting.into, EntityInit::Villager);
+        assert!(CURE_TICKS.contains(&converting.remaining_ticks));
+        assert!(converting.condition.is_none());
+        assert_eq!(
+            *inventory.item(Area::Hotbar, 0).unwrap(),
+            Some(ItemStack::new(Item::GoldenApple, 1))
+        );
+        let effects = game.ecs.get::<StatusEffects>(zombie_villager).unwrap();
+        assert!(!effects.contains(StatusEffect::Weakness));
+        assert!(effects.contains(StatusEffect::Strength));
+    }
+
+    #[test]
+    fn conversions_keep_entity_data() {
+        let mut game = Game::new();
+        let mut tags = PersistentTags::default();
+        tags.insert("plugin", "boss", "true");
+        let inventory = Inventory::player();
+        *inventory.item(Area::Helmet, 0).unwrap() = Some(ItemStack::new(Item::IronHelmet, 1));
+        let position = Position {
+            x: 1.0,
+            y: 64.0,
+            z: -3.0,
+            pitch: 10.0,
+            yaw: 90.0,
+        };
+        let mut entity = game.ecs.spawn((
+            position,
+            HeadYaw(45.0),
+            CustomName::new("Bob"),
+            inventory,
+            tags.clone(),
+        ));
+
+        // Zombie to drowned and back again
+        for &into in &[EntityInit::Drowned, EntityInit::Zombie] {
+            convert(&mut game, entity, into).unwrap();
+            entity = game
+                .ecs
+                .query::<&ConversionEvent>()
+                .iter()
+                .find(|(_, event)| event.from == entity)
+                .map(|(converted, _)| converted)
+                .unwrap();
+        }
+
+        assert_eq!(*game.ecs.get::<Position>(entity).unwrap(), position);
+        assert_eq!(*game.ecs.get::<HeadYaw>(entity).unwrap(), HeadYaw(45.0));
+        assert_eq!(&**game.ecs.get::<CustomName>(entity).unwrap(), "Bob");
+        assert_eq!(*game.ecs.get::<PersistentTags>(entity).unwrap(), tags);
+        assert_eq!(
+            *game
+                .ecs
+                .get::<Inventory>(entity)
+                .unwrap()
+                .item(Area::Helmet, 0)
+                .unwrap(),
+            Some(ItemStack::new(Item::IronHelmet, 1))
+        );
+    }
+}
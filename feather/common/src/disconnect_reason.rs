@@ -0,0 +1,65 @@
+//! Messages shown on the disconnect screen.
+//!
+//! These use the vanilla translation keys,
+//! so clients show them in their own language.
+
+use base::{Text, TextComponentBuilder};
+use chrono::{DateTime, Utc};
+
+/// The message for a player kicked by an operator,
+/// followed by `reason` if one was given.
+pub fn kicked_by_operator(reason: Option<Text>) -> Text {
+    let message = translate("multiplayer.disconnect.kicked").red();
+    match reason {
+        Some(reason) => message.push_extra("\n\n").push_extra(reason),
+        None => message,
+    }
+}
+
+/// The message for a banned player, with the ban's `reason`
+/// and when it `expires`, if the ban isn't permanent.
+pub fn banned(reason: Option<Text>, expires: Option<DateTime<Utc>>) -> Text {
+    let message = match reason {
+        Some(reason) => Text::translate_with("multiplayer.disconnect.banned.reason", vec![reason]),
+        None => translate("multiplayer.disconnect.banned"),
+    }
+    .red();
+    match expires {
+        Some(expires) => message.push_extra(Text::translate_with(
+            "multiplayer.disconnect.banned.expiration",
+            vec![Text::from(expires.format("%Y-%m-%d %H:%M UTC").to_string())],
+        )),
+        None => message,
+    }
+}
+
+/// The message for players disconnected
+/// because the server is shutting down.
+pub fn server_closed() -> Text {
+    translate("multiplayer.disconnect.server_shutdown")
+}
+
+/// The message for a player who logged
+/// in again from another connection.
+pub fn duplicate_login() -> Text {
+    translate("multiplayer.disconnect.duplicate_login")
+}
+
+fn translate(key: &'static str) -> Text {
+    Text::translate_with(key, Vec::<Text>::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn ban_expiry() {
+        let expires = Utc.ymd(2021, 6, 1).and_hms(12, 30, 0);
+        let json = banned(None, Some(expires)).to_string();
+        assert!(json.contains("multiplayer.disconnect.banned.expiration"));
+        assert!(json.contains("2021-06-01 12:30 UTC"));
+    }
+}
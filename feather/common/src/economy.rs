@@ -0,0 +1,294 @@
+//! The economy service: a standard interface to player
+//! balances, so that shop, job and reward plugins can share
+//! money regardless of where balances are stored.
+//!
+//! The [`Economy`] resource holds the active [`EconomyProvider`].
+//! Code which moves money should go through [`deposit`] and
+//! [`withdraw`], which trigger [`EconomyTransactionEvent`]s.
+
+use ahash::AHashMap;
+use quill_common::{
+    economy::{Currency, EconomyError},
+    events::{EconomyTransactionEvent, TransactionKind},
+};
+use uuid::Uuid;
+
+use crate::Game;
+
+pub fn register(game: &mut Game) {
+    game.insert_resource(Economy::new(MemoryEconomy::default()));
+}
+
+/// Stores the balances of accounts, which are identified
+/// by the UUID of their player.
+///
+/// Implement this to keep balances somewhere else, e.g. in
+/// a database, and register it with [`Economy::register_provider`].
+/// Plugins register theirs through Quill.
+pub trait EconomyProvider: 'static {
+    /// Name of the provider, for logging.
+    fn name(&self) -> &str;
+
+    fn currency(&self) -> &Currency;
+
+    /// Returns the balance of `account`, in the currency's
+    /// smallest unit. Accounts without transactions have
+    /// the provider's starting balance.
+    fn balance(&self, game: &mut Game, account: Uuid) -> i64;
+
+    /// Overwrites the balance of `account`.
+    fn set_balance(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        balance: i64,
+    ) -> Result<(), EconomyError>;
+
+    /// Adds a non-negative `amount` to the balance
+    /// of `account`, returning the new balance.
+    fn deposit(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        amount: i64,
+    ) -> Result<i64, EconomyError> {
+        let balance = self
+            .balance(game, account)
+            .checked_add(amount)
+            .ok_or(EconomyError::BalanceOverflow)?;
+        self.set_balance(game, account, balance)?;
+        Ok(balance)
+    }
+
+    /// Subtracts a non-negative `amount` from the balance
+    /// of `account`, returning the new balance.
+    ///
+    /// The default implementation doesn't allow negative balances.
+    fn withdraw(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        amount: i64,
+    ) -> Result<i64, EconomyError> {
+        let balance = self
+            .balance(game, account)
+            .checked_sub(amount)
+            .filter(|&balance| balance >= 0)
+            .ok_or(EconomyError::InsufficientFunds)?;
+        self.set_balance(game, account, balance)?;
+        Ok(balance)
+    }
+}
+
+/// Resource holding the active [`EconomyProvider`].
+pub struct Economy {
+    /// `None` while the provider is in use; see [`with_provider`].
+    provider: Option<Box<dyn EconomyProvider>>,
+    currency: Currency,
+}
+
+impl Economy {
+    pub fn new(provider: impl EconomyProvider) -> Self {
+        Self {
+            currency: provider.currency().clone(),
+            provider: Some(Box::new(provider)),
+        }
+    }
+
+    /// Replaces the active provider. Balances
+    /// aren't moved to the new provider.
+    pub fn register_provider(&mut self, provider: impl EconomyProvider) {
+        log::info!("Using economy provider {}", provider.name());
+        self.currency = provider.currency().clone();
+        self.provider = Some(Box::new(provider));
+    }
+
+    pub fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    /// Formats an amount in the active currency.
+    pub fn format(&self, amount: i64) -> String {
+        self.currency().format(amount)
+    }
+}
+
+/// Provider keeping balances in memory, used until
+/// the server registers a persistent one.
+#[derive(Debug, Default)]
+pub struct MemoryEconomy {
+    currency: Currency,
+    balances: AHashMap<Uuid, i64>,
+}
+
+impl EconomyProvider for MemoryEconomy {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn balance(&self, _game: &mut Game, account: Uuid) -> i64 {
+        self.balances.get(&account).copied().unwrap_or(0)
+    }
+
+    fn set_balance(
+        &mut self,
+        _game: &mut Game,
+        account: Uuid,
+        balance: i64,
+    ) -> Result<(), EconomyError> {
+        self.balances.insert(account, balance);
+        Ok(())
+    }
+}
+
+/// Runs `f` with the active provider. The provider is detached
+/// from the [`Economy`] resource meanwhile, so that it can access
+/// the game.
+///
+/// Fails if there is no economy, or if the provider
+/// calls back into the economy.
+fn with_provider<T>(
+    game: &mut Game,
+    f: impl FnOnce(&mut dyn EconomyProvider, &mut Game) -> Result<T, EconomyError>,
+) -> Result<T, EconomyError> {
+    let mut provider = game
+        .resources
+        .get_mut::<Economy>()
+        .ok()
+        .and_then(|mut economy| economy.provider.take())
+        .ok_or(EconomyError::ProviderFailure)?;
+    let result = f(&mut *provider, game);
+
+    if let Ok(mut economy) = game.resources.get_mut::<Economy>() {
+        // Keep a provider registered by `f`.
+        if economy.provider.is_none() {
+            economy.provider = Some(provider);
+        }
+    }
+    result
+}
+
+/// Returns the balance of `account`, or 0
+/// if the provider can't be accessed.
+pub fn balance(game: &mut Game, account: Uuid) -> i64 {
+    with_provider(game, |provider, game| Ok(provider.balance(game, account))).unwrap_or(0)
+}
+
+/// Deposits `amount` into `account`, returning the new balance.
+pub fn deposit(game: &mut Game, account: Uuid, amount: i64) -> Result<i64, EconomyError> {
+    transact(game, account, amount, TransactionKind::Deposit)
+}
+
+/// Withdraws `amount` from `account`, returning the new balance.
+pub fn withdraw(game: &mut Game, account: Uuid, amount: i64) -> Result<i64, EconomyError> {
+    transact(game, account, amount, TransactionKind::Withdrawal)
+}
+
+fn transact(
+    game: &mut Game,
+    account: Uuid,
+    amount: i64,
+    kind: TransactionKind,
+) -> Result<i64, EconomyError> {
+    if amount < 0 {
+        return Err(EconomyError::NegativeAmount);
+    }
+
+    let balance = with_provider(game, |provider, game| match kind {
+        TransactionKind::Deposit => provider.deposit(game, account, amount),
+        TransactionKind::Withdrawal => provider.withdraw(game, account, amount),
+    })?;
+
+    game.ecs.insert_event(EconomyTransactionEvent {
+        account,
+        kind,
+        amount,
+        balance,
+    });
+    Ok(balance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transactions() {
+        let mut game = Game::new();
+        register(&mut game);
+        let account = Uuid::from_u128(1);
+
+        assert_eq!(deposit(&mut game, account, 500), Ok(500));
+        assert_eq!(withdraw(&mut game, account, 200), Ok(300));
+        assert_eq!(
+            withdraw(&mut game, account, 301),
+            Err(EconomyError::InsufficientFunds)
+        );
+        assert_eq!(
+            deposit(&mut game, account, -1),
+            Err(EconomyError::NegativeAmount)
+        );
+        assert_eq!(
+            deposit(&mut game, account, i64::MAX),
+            Err(EconomyError::BalanceOverflow)
+        );
+        assert_eq!(balance(&mut game, account), 300);
+
+        // Only successful transactions trigger events.
+        let events: Vec<(TransactionKind, i64)> = game
+            .ecs
+            .query::<&EconomyTransactionEvent>()
+            .iter()
+            .map(|(_, event)| (event.kind, event.balance))
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert!(events.contains(&(TransactionKind::Deposit, 500)));
+        assert!(events.contains(&(TransactionKind::Withdrawal, 300)));
+    }
+
+    /// Deposits into a second account whenever it's queried.
+    struct ReentrantEconomy(MemoryEconomy);
+
+    impl EconomyProvider for ReentrantEconomy {
+        fn name(&self) -> &str {
+            "reentrant"
+        }
+
+        fn currency(&self) -> &Currency {
+            self.0.currency()
+        }
+
+        fn balance(&self, game: &mut Game, account: Uuid) -> i64 {
+            assert_eq!(
+                deposit(game, Uuid::from_u128(2), 1),
+                Err(EconomyError::ProviderFailure)
+            );
+            self.0.balance(game, account)
+        }
+
+        fn set_balance(
+            &mut self,
+            game: &mut Game,
+            account: Uuid,
+            balance: i64,
+        ) -> Result<(), EconomyError> {
+            self.0.set_balance(game, account, balance)
+        }
+    }
+
+    #[test]
+    fn providers_cannot_reenter() {
+        let mut game = Game::new();
+        game.insert_resource(Economy::new(ReentrantEconomy(MemoryEconomy::default())));
+        let account = Uuid::from_u128(1);
+
+        assert_eq!(deposit(&mut game, account, 5), Ok(5));
+        assert_eq!(balance(&mut game, account), 5);
+        // The provider is attached again afterwards.
+        assert_eq!(deposit(&mut game, account, 5), Ok(10));
+    }
+}
@@ -0,0 +1,421 @@
+//! Status effects, like speed or poison, applied to entities.
+//!
+//! Effects are changed through [`add_effect`], [`remove_effect`]
+//! and [`clear_effects`], which trigger the corresponding events
+//! and keep the entity's potion particle metadata up to date.
+//!
+//! `/effect clear [player]` clears the effects of the sender
+//! or of the named player.
+
+use anyhow::anyhow;
+use base::{
+    metadata::{META_INDEX_LIVING_POTION_AMBIENT, META_INDEX_LIVING_POTION_COLOR},
+    EntityMetadata, Text,
+};
+use ecs::{Component, Entity, SysResult, SystemExecutor};
+use quill_common::components::Name;
+
+use crate::{
+    commands::{check_permission, CommandRegistry},
+    Game,
+};
+
+/// Permission to use `/effect`.
+pub const EFFECT_PERMISSION: &str = "feather.effect";
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.resources
+        .get_mut::<CommandRegistry>()
+        .expect("commands must be registered first")
+        .register("effect", effect_command);
+    systems.add_system(tick_effects);
+}
+
+macro_rules! status_effects {
+    ($($effect:ident = $id:literal, $color:literal;)*) => {
+        /// A kind of status effect.
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub enum StatusEffect {
+            $($effect,)*
+        }
+
+        impl StatusEffect {
+            /// Returns the protocol ID of this effect.
+            pub fn id(self) -> u8 {
+                match self {
+                    $(StatusEffect::$effect => $id,)*
+                }
+            }
+
+            pub fn from_id(id: u8) -> Option<Self> {
+                match id {
+                    $($id => Some(StatusEffect::$effect),)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the RGB color of this effect's particles.
+            pub fn color(self) -> u32 {
+                match self {
+                    $(StatusEffect::$effect => $color,)*
+                }
+            }
+        }
+    };
+}
+
+status_effects! {
+    Speed = 1, 0x7CAFC6;
+    Slowness = 2, 0x5A6C81;
+    Haste = 3, 0xD9C043;
+    MiningFatigue = 4, 0x4A4217;
+    Strength = 5, 0x932423;
+    InstantHealth = 6, 0xF82423;
+    InstantDamage = 7, 0x430A09;
+    JumpBoost = 8, 0x22FF4C;
+    Nausea = 9, 0x551D4A;
+    Regeneration = 10, 0xCD5CAB;
+    Resistance = 11, 0x99453A;
+    FireResistance = 12, 0xE49A3A;
+    WaterBreathing = 13, 0x2E5299;
+    Invisibility = 14, 0x7F8392;
+    Blindness = 15, 0x1F1F23;
+    NightVision = 16, 0x1F1FA1;
+    Hunger = 17, 0x587653;
+    Weakness = 18, 0x484D48;
+    Poison = 19, 0x4E9331;
+    Wither = 20, 0x352A27;
+    HealthBoost = 21, 0xF87D23;
+    Absorption = 22, 0x2552A5;
+    Saturation = 23, 0xF82423;
+    Glowing = 24, 0x94A061;
+    Levitation = 25, 0xCEFFFF;
+    Luck = 26, 0x339900;
+    Unluck = 27, 0xC0A44D;
+    SlowFalling = 28, 0xFFEFD1;
+    ConduitPower = 29, 0x1DC2D1;
+    DolphinsGrace = 30, 0x88A3BE;
+    BadOmen = 31, 0x0B6138;
+    HeroOfTheVillage = 32, 0x44FF44;
+}
+
+/// A status effect applied to an entity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ActiveEffect {
+    pub effect: StatusEffect,
+    /// The effect level minus one.
+    pub amplifier: u8,
+    /// Remaining duration in ticks.
+    pub duration: u32,
+    /// Whether the effect comes from a beacon or conduit.
+    /// Ambient effects have fainter particles.
+    pub ambient: bool,
+    pub show_particles: bool,
+    pub show_icon: bool,
+}
+
+impl ActiveEffect {
+    pub fn new(effect: StatusEffect, amplifier: u8, duration: u32) -> Self {
+        Self {
+            effect,
+            amplifier,
+            duration,
+            ambient: false,
+            show_particles: true,
+            show_icon: true,
+        }
+    }
+
+    /// Returns whether this effect should replace
+    /// an existing effect of the same kind.
+    fn overrides(&self, existing: &ActiveEffect) -> bool {
+        self.amplifier > existing.amplifier
+            || (self.amplifier == existing.amplifier && self.duration > existing.duration)
+    }
+}
+
+/// The status effects applied to an entity.
+#[derive(Clone, Debug, Default)]
+pub struct StatusEffects {
+    effects: Vec<ActiveEffect>,
+}
+
+impl StatusEffects {
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveEffect> + '_ {
+        self.effects.iter()
+    }
+
+    pub fn get(&self, effect: StatusEffect) -> Option<&ActiveEffect> {
+        self.effects.iter().find(|active| active.effect == effect)
+    }
+
+    pub fn contains(&self, effect: StatusEffect) -> bool {
+        self.get(effect).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Returns the color of the entity's potion particles:
+    /// the colors of all effects showing particles, weighted
+    /// by their level. Zero if there are no such effects.
+    pub fn particle_color(&self) -> u32 {
+        let (mut r, mut g, mut b, mut weight) = (0, 0, 0, 0);
+        for active in self.effects.iter().filter(|active| active.show_particles) {
+            let color = active.effect.color();
+            let level = active.amplifier as u32 + 1;
+            r += ((color >> 16) & 0xFF) * level;
+            g += ((color >> 8) & 0xFF) * level;
+            b += (color & 0xFF) * level;
+            weight += level;
+        }
+        if weight == 0 {
+            return 0;
+        }
+        ((r / weight) << 16) | ((g / weight) << 8) | (b / weight)
+    }
+
+    /// Returns whether all effects showing particles are ambient.
+    pub fn is_ambient(&self) -> bool {
+        self.effects
+            .iter()
+            .filter(|active| active.show_particles)
+            .all(|active| active.ambient)
+    }
+}
+
+/// Event triggered when effects are added to an entity,
+/// or existing effects are replaced by stronger ones.
+#[derive(Debug, Clone)]
+pub struct EffectsAddedEvent {
+    pub effects: Vec<ActiveEffect>,
+}
+
+/// Event triggered when effects are removed from
+/// an entity before they expired.
+#[derive(Debug, Clone)]
+pub struct EffectsRemovedEvent {
+    pub effects: Vec<StatusEffect>,
+}
+
+/// Event triggered when an entity's effects run out.
+#[derive(Debug, Clone)]
+pub struct EffectsExpiredEvent {
+    pub effects: Vec<StatusEffect>,
+}
+
+/// Applies an effect to an entity. An existing effect
+/// of the same kind is only replaced if the new one
+/// has a higher level or lasts longer.
+pub fn add_effect(game: &mut Game, entity: Entity, effect: ActiveEffect) -> SysResult {
+    if game.ecs.get::<StatusEffects>(entity).is_err() {
+        game.ecs.insert(entity, StatusEffects::default())?;
+    }
+
+    {
+        let mut effects = game.ecs.get_mut::<StatusEffects>(entity)?;
+        match effects
+            .effects
+            .iter_mut()
+            .find(|active| active.effect == effect.effect)
+        {
+            Some(existing) if effect.overrides(existing) => *existing = effect,
+            Some(_) => return Ok(()),
+            None => effects.effects.push(effect),
+        }
+    }
+
+    push_event(game, entity, effect, |event: &mut EffectsAddedEvent| {
+        &mut event.effects
+    })?;
+    update_metadata(game, entity)
+}
+
+/// Removes an effect from an entity, if present.
+pub fn remove_effect(game: &mut Game, entity: Entity, effect: StatusEffect) -> SysResult {
+    let removed = match game.ecs.get_mut::<StatusEffects>(entity) {
+        Ok(mut effects) => {
+            let count = effects.effects.len();
+            effects.effects.retain(|active| active.effect != effect);
+            effects.effects.len() != count
+        }
+        Err(_) => false,
+    };
+
+    if removed {
+        push_event(game, entity, effect, |event: &mut EffectsRemovedEvent| {
+            &mut event.effects
+        })?;
+        update_metadata(game, entity)?;
+    }
+    Ok(())
+}
+
+/// Removes all effects from an entity, like
+/// drinking milk or dying does.
+pub fn clear_effects(game: &mut Game, entity: Entity) -> SysResult {
+    let removed: Vec<StatusEffect> = match game.ecs.get_mut::<StatusEffects>(entity) {
+        Ok(mut effects) => effects
+            .effects
+            .drain(..)
+            .map(|active| active.effect)
+            .collect(),
+        Err(_) => return Ok(()),
+    };
+
+    for effect in removed {
+        push_event(game, entity, effect, |event: &mut EffectsRemovedEvent| {
+            &mut event.effects
+        })?;
+    }
+    update_metadata(game, entity)
+}
+
+/// `/effect clear [player]`
+fn effect_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    check_permission(game, sender, EFFECT_PERMISSION)?;
+    let target = match args {
+        ["clear"] => sender,
+        ["clear", name] => game
+            .ecs
+            .query::<&Name>()
+            .iter()
+            .find(|(_, player)| player.as_str() == *name)
+            .map(|(player, _)| player)
+            .ok_or_else(|| anyhow!("unknown player '{}'", name))?,
+        _ => return Ok(Text::of("Usage: /effect clear [player]")),
+    };
+
+    clear_effects(game, target)?;
+    Ok(Text::of("Cleared effects"))
+}
+
+/// Adds `value` to an event of type `E` on the entity,
+/// creating the event if there is none yet this tick.
+fn push_event<E, T>(
+    game: &mut Game,
+    entity: Entity,
+    value: T,
+    values: fn(&mut E) -> &mut Vec<T>,
+) -> SysResult
+where
+    E: Component + From<Vec<T>>,
+{
+    if let Ok(mut event) = game.ecs.get_mut::<E>(entity) {
+        values(&mut event).push(value);
+        return Ok(());
+    }
+    game.ecs.insert_entity_event(entity, E::from(vec![value]))?;
+    Ok(())
+}
+
+impl From<Vec<ActiveEffect>> for EffectsAddedEvent {
+    fn from(effects: Vec<ActiveEffect>) -> Self {
+        Self { effects }
+    }
+}
+
+impl From<Vec<StatusEffect>> for EffectsRemovedEvent {
+    fn from(effects: Vec<StatusEffect>) -> Self {
+        Self { effects }
+    }
+}
+
+impl From<Vec<StatusEffect>> for EffectsExpiredEvent {
+    fn from(effects: Vec<StatusEffect>) -> Self {
+        Self { effects }
+    }
+}
+
+/// Updates the potion particle color and ambient
+/// flag in the entity's metadata.
+fn update_metadata(game: &mut Game, entity: Entity) -> SysResult {
+    let (color, ambient) = match game.ecs.get::<StatusEffects>(entity) {
+        Ok(effects) => (effects.particle_color(), effects.is_ambient()),
+        Err(_) => return Ok(()),
+    };
+    if let Ok(mut metadata) = game.ecs.get_mut::<EntityMetadata>(entity) {
+        metadata.set(META_INDEX_LIVING_POTION_COLOR, color as i32);
+        metadata.set(META_INDEX_LIVING_POTION_AMBIENT, ambient);
+    }
+    Ok(())
+}
+
+/// Counts down effect durations and removes expired effects.
+fn tick_effects(game: &mut Game) -> SysResult {
+    let mut expired = Vec::new();
+    for (entity, effects) in game.ecs.query::<&mut StatusEffects>().iter() {
+        for active in &mut effects.effects {
+            active.duration = active.duration.saturating_sub(1);
+            if active.duration == 0 {
+                expired.push((entity, active.effect));
+            }
+        }
+        effects.effects.retain(|active| active.duration > 0);
+    }
+
+    for (entity, effect) in expired {
+        push_event(game, entity, effect, |event: &mut EffectsExpiredEvent| {
+            &mut event.effects
+        })?;
+        update_metadata(game, entity)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particle_color_is_weighted_by_level() {
+        let mut effects = StatusEffects::default();
+        assert_eq!(effects.particle_color(), 0);
+
+        effects
+            .effects
+            .push(ActiveEffect::new(StatusEffect::Speed, 0, 100));
+        assert_eq!(effects.particle_color(), StatusEffect::Speed.color());
+
+        effects
+            .effects
+            .push(ActiveEffect::new(StatusEffect::Levitation, 1, 100));
+        // (0x7C + 2 * 0xCE) / 3, (0xAF + 2 * 0xFF) / 3, (0xC6 + 2 * 0xFF) / 3
+        assert_eq!(effects.particle_color(), 0xB2E4EC);
+    }
+
+    #[test]
+    fn clear_effects_removes_all_effects() {
+        let mut game = Game::new();
+        let entity = game.ecs.spawn(());
+        add_effect(
+            &mut game,
+            entity,
+            ActiveEffect::new(StatusEffect::Speed, 0, 100),
+        )
+        .unwrap();
+        add_effect(
+            &mut game,
+            entity,
+            ActiveEffect::new(StatusEffect::Poison, 0, 100),
+        )
+        .unwrap();
+
+        clear_effects(&mut game, entity).unwrap();
+        assert!(game.ecs.get::<StatusEffects>(entity).unwrap().is_empty());
+        assert_eq!(
+            game.ecs.get::<EffectsRemovedEvent>(entity).unwrap().effects,
+            vec![StatusEffect::Speed, StatusEffect::Poison]
+        );
+    }
+
+    #[test]
+    fn stronger_effects_override() {
+        let weak = ActiveEffect::new(StatusEffect::Speed, 0, 100);
+        let strong = ActiveEffect::new(StatusEffect::Speed, 1, 50);
+        assert!(strong.overrides(&weak));
+        assert!(!weak.overrides(&strong));
+        assert!(ActiveEffect::new(StatusEffect::Speed, 0, 200).overrides(&weak));
+    }
+}
@@ -0,0 +1,441 @@
+//! Enchanting tables: bookshelf counting, the three
+//! offered enchantments and enchanting items.
+//!
+//! Offers are computed like vanilla does, from the player's
+//! [`EnchantmentSeed`], so the same item shows the same offers
+//! until the player enchants something.
+//!
+//! Players don't have experience yet, so the level
+//! requirement of an offer is shown but not enforced.
+
+use base::{Area, BlockPosition, Enchantment, Gamemode, Inventory, Item, ItemStack, ItemStackMeta};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult};
+use utils::JavaRandom;
+
+use crate::{
+    interactable::InteractableRegistry,
+    window::{self, BackingWindow, Window},
+    Game,
+};
+
+mod enchantments;
+
+pub use enchantments::{
+    enchantability, enchantment_info, EnchantmentInfo, EnchantmentTarget, MaxCost,
+    TABLE_ENCHANTMENTS,
+};
+
+pub fn register(game: &mut Game) {
+    game.resources
+        .get_mut::<InteractableRegistry>()
+        .expect("interactable registry not registered")
+        .register(BlockKind::EnchantingTable);
+}
+
+/// Maximum number of bookshelves that count towards an enchanting table.
+pub const MAX_BOOKSHELVES: u32 = 15;
+
+/// Number of offers shown by an enchanting table.
+pub const OFFER_COUNT: usize = 3;
+
+/// Seed used to compute a player's enchantment offers.
+/// Changes whenever the player enchants an item.
+///
+/// Stored as `XpSeed` in player data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EnchantmentSeed(pub i32);
+
+impl EnchantmentSeed {
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+}
+
+/// An enchantment offered by an enchanting table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EnchantmentOffer {
+    /// Experience level required to select this offer.
+    /// Zero if there is no offer.
+    pub cost: u32,
+    /// The enchantment shown as a hint. The item
+    /// may receive additional enchantments.
+    pub hint: Option<(&'static EnchantmentInfo, u16)>,
+}
+
+/// Component for a player who has an enchanting table open.
+#[derive(Clone, Debug)]
+pub struct OpenEnchantingTable {
+    pub position: BlockPosition,
+    /// Number of bookshelves around the table.
+    pub bookshelves: u32,
+    pub offers: [EnchantmentOffer; OFFER_COUNT],
+}
+
+/// Returns whether the block at `position` is an enchanting table.
+pub fn is_enchanting_table(game: &Game, position: BlockPosition) -> bool {
+    game.block(position)
+        .map_or(false, |block| block.kind() == BlockKind::EnchantingTable)
+}
+
+/// Counts the bookshelves around an enchanting table, which
+/// need the block between them and the table to be empty.
+pub fn count_bookshelves(game: &Game, table: BlockPosition) -> u32 {
+    let is_air = |x, y, z| {
+        game.block(BlockPosition::new(table.x + x, table.y + y, table.z + z))
+            .map_or(false, |block| block.is_air())
+    };
+    let bookshelf = |x, y, z| {
+        game.block(BlockPosition::new(table.x + x, table.y + y, table.z + z))
+            .map_or(0, |block| (block.kind() == BlockKind::Bookshelf) as u32)
+    };
+
+    let mut count = 0;
+    for z in -1..=1 {
+        for x in -1..=1 {
+            if (x == 0 && z == 0) || !is_air(x, 0, z) || !is_air(x, 1, z) {
+                continue;
+            }
+            count += bookshelf(x * 2, 0, z * 2) + bookshelf(x * 2, 1, z * 2);
+            if x != 0 && z != 0 {
+                count += bookshelf(x * 2, 0, z)
+                    + bookshelf(x * 2, 1, z)
+                    + bookshelf(x, 0, z * 2)
+                    + bookshelf(x, 1, z * 2);
+            }
+        }
+    }
+    count.min(MAX_BOOKSHELVES)
+}
+
+/// Returns whether an item can be enchanted at an enchanting table.
+pub fn is_enchantable(item: &ItemStack) -> bool {
+    let already_enchanted = item
+        .meta
+        .as_ref()
+        .map_or(false, |meta| !meta.enchantments.is_empty());
+    item.count == 1 && enchantability(item.item) > 0 && !already_enchanted
+}
+
+/// Computes the offers of an enchanting table for the given item.
+pub fn compute_offers(
+    item: &ItemStack,
+    bookshelves: u32,
+    seed: EnchantmentSeed,
+) -> [EnchantmentOffer; OFFER_COUNT] {
+    let mut offers: [EnchantmentOffer; OFFER_COUNT] = Default::default();
+    if !is_enchantable(item) {
+        return offers;
+    }
+
+    let mut random = JavaRandom::new(seed.0 as i64);
+    for (slot, offer) in offers.iter_mut().enumerate() {
+        let cost = enchantment_cost(&mut random, slot, bookshelves, item.item);
+        offer.cost = if cost < slot as i32 + 1 {
+            0
+        } else {
+            cost as u32
+        };
+    }
+
+    for (slot, offer) in offers.iter_mut().enumerate() {
+        if offer.cost == 0 {
+            continue;
+        }
+        let enchantments = select_enchantments(&mut random, item.item, slot, offer.cost, seed);
+        if !enchantments.is_empty() {
+            let index = random.next_int_bounded(enchantments.len() as i32) as usize;
+            offer.hint = Some(enchantments[index]);
+        }
+    }
+    offers
+}
+
+/// Computes the level required by the offer in `slot`.
+fn enchantment_cost(random: &mut JavaRandom, slot: usize, bookshelves: u32, item: Item) -> i32 {
+    if enchantability(item) <= 0 {
+        return 0;
+    }
+    let bookshelves = bookshelves.min(MAX_BOOKSHELVES) as i32;
+    let base = random.next_int_bounded(8)
+        + 1
+        + (bookshelves >> 1)
+        + random.next_int_bounded(bookshelves + 1);
+    match slot {
+        0 => (base / 3).max(1),
+        1 => base * 2 / 3 + 1,
+        _ => base.max(bookshelves * 2),
+    }
+}
+
+/// Selects the enchantments an item receives when the offer
+/// in `slot`, with the given cost, is chosen.
+fn select_enchantments(
+    random: &mut JavaRandom,
+    item: Item,
+    slot: usize,
+    cost: u32,
+    seed: EnchantmentSeed,
+) -> Vec<(&'static EnchantmentInfo, u16)> {
+    *random = JavaRandom::new(seed.0.wrapping_add(slot as i32) as i64);
+    let mut selected = Vec::new();
+
+    let enchantability = enchantability(item);
+    let mut cost = cost as i32
+        + 1
+        + random.next_int_bounded(enchantability / 4 + 1)
+        + random.next_int_bounded(enchantability / 4 + 1);
+    let variation = (random.next_float() + random.next_float() - 1.0) * 0.15;
+    cost = ((cost as f32 + cost as f32 * variation + 0.5).floor() as i32).max(1);
+
+    let mut available = available_enchantments(item, cost);
+    if let Some(first) = weighted_choice(random, &available) {
+        selected.push(first);
+        while random.next_int_bounded(50) <= cost {
+            let (last, _) = selected[selected.len() - 1];
+            available.retain(|(info, _)| info.is_compatible_with(last));
+            match weighted_choice(random, &available) {
+                Some(next) => selected.push(next),
+                None => break,
+            }
+            cost /= 2;
+        }
+    }
+
+    if item == Item::Book && selected.len() > 1 {
+        selected.remove(random.next_int_bounded(selected.len() as i32) as usize);
+    }
+    selected
+}
+
+/// Returns the enchantments applicable to `item`, each at the
+/// highest level whose cost range includes `cost`.
+fn available_enchantments(item: Item, cost: i32) -> Vec<(&'static EnchantmentInfo, u16)> {
+    TABLE_ENCHANTMENTS
+        .iter()
+        .filter(|info| item == Item::Book || info.target.includes(item))
+        .filter_map(|info| {
+            (1..=info.max_level)
+                .rev()
+                .find(|&level| cost >= info.min_cost(level) && cost <= info.max_cost(level))
+                .map(|level| (info, level))
+        })
+        .collect()
+}
+
+fn weighted_choice(
+    random: &mut JavaRandom,
+    enchantments: &[(&'static EnchantmentInfo, u16)],
+) -> Option<(&'static EnchantmentInfo, u16)> {
+    let total_weight: i32 = enchantments.iter().map(|(info, _)| info.weight).sum();
+    if total_weight <= 0 {
+        return None;
+    }
+    let mut remaining = random.next_int_bounded(total_weight);
+    for &(info, level) in enchantments {
+        remaining -= info.weight;
+        if remaining < 0 {
+            return Some((info, level));
+        }
+    }
+    None
+}
+
+/// Opens the enchanting table at `position` for `player`.
+pub fn open_enchanting_table(
+    game: &mut Game,
+    player: Entity,
+    position: BlockPosition,
+) -> SysResult {
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let window = Window::new(BackingWindow::Enchantment {
+        enchantment_table: Inventory::enchantment_table(),
+        player: inventory,
+    });
+    let bookshelves = count_bookshelves(game, position);
+    game.ecs.insert(player, window)?;
+    game.ecs.insert(
+        player,
+        OpenEnchantingTable {
+            position,
+            bookshelves,
+            offers: Default::default(),
+        },
+    )?;
+    Ok(())
+}
+
+/// Returns the item and lapis slots of the player's enchanting window.
+fn table_inventory(game: &Game, player: Entity) -> anyhow::Result<Inventory> {
+    match game.ecs.get::<Window>(player)?.inner() {
+        BackingWindow::Enchantment {
+            enchantment_table, ..
+        } => Ok(enchantment_table.new_handle()),
+        _ => anyhow::bail!("player has no enchanting window open"),
+    }
+}
+
+/// Recomputes the offers for the item in the player's enchanting table.
+pub fn update_offers(game: &mut Game, player: Entity) -> SysResult {
+    let table = table_inventory(game, player)?;
+    let item = table
+        .item(Area::EnchantmentItem, 0)
+        .and_then(|item| item.clone());
+    let seed = *game.ecs.get::<EnchantmentSeed>(player)?;
+
+    let mut open = game.ecs.get_mut::<OpenEnchantingTable>(player)?;
+    open.offers = match item {
+        Some(item) => compute_offers(&item, open.bookshelves, seed),
+        None => Default::default(),
+    };
+    Ok(())
+}
+
+/// Enchants the item in the player's enchanting table
+/// with the offer in `slot`, consuming lapis lazuli.
+pub fn enchant(game: &mut Game, player: Entity, slot: usize) -> SysResult {
+    let table = table_inventory(game, player)?;
+    let offer = match game
+        .ecs
+        .get::<OpenEnchantingTable>(player)?
+        .offers
+        .get(slot)
+    {
+        Some(offer) if offer.cost > 0 => offer.clone(),
+        _ => anyhow::bail!("player selected an enchantment which isn't offered"),
+    };
+    let creative = *game.ecs.get::<Gamemode>(player)? == Gamemode::Creative;
+    let lapis_cost = slot as u32 + 1;
+
+    {
+        let mut lapis = table
+            .item(Area::EnchantmentLapis, 0)
+            .expect("enchanting table has a lapis slot");
+        let lapis_count = lapis
+            .as_ref()
+            .filter(|lapis| lapis.item == Item::LapisLazuli)
+            .map_or(0, |lapis| lapis.count);
+        if lapis_count < lapis_cost && !creative {
+            anyhow::bail!("not enough lapis lazuli to enchant");
+        }
+
+        let mut item_slot = table
+            .item(Area::EnchantmentItem, 0)
+            .expect("enchanting table has an item slot");
+        let item = match item_slot.as_mut() {
+            Some(item) if is_enchantable(item) => item,
+            _ => anyhow::bail!("item in enchanting table can't be enchanted"),
+        };
+
+        let seed = *game.ecs.get::<EnchantmentSeed>(player)?;
+        let mut random = JavaRandom::new(0);
+        let enchantments = select_enchantments(&mut random, item.item, slot, offer.cost, seed);
+        if enchantments.is_empty() {
+            return Ok(());
+        }
+        apply_enchantments(item, &enchantments);
+
+        if !creative {
+            if let Some(lapis_stack) = lapis.as_mut() {
+                lapis_stack.remove(lapis_cost);
+                if lapis_stack.count == 0 {
+                    *lapis = None;
+                }
+            }
+        }
+    }
+
+    game.ecs.insert(player, EnchantmentSeed::random())?;
+    update_offers(game, player)
+}
+
+fn apply_enchantments(item: &mut ItemStack, enchantments: &[(&EnchantmentInfo, u16)]) {
+    let enchantments = enchantments.iter().map(|&(info, level)| Enchantment {
+        id: info.id.to_owned(),
+        level,
+    });
+    let meta = item.meta.get_or_insert_with(ItemStackMeta::default);
+    if item.item == Item::Book {
+        item.item = Item::EnchantedBook;
+        meta.stored_enchantments.extend(enchantments);
+    } else {
+        meta.enchantments.extend(enchantments);
+    }
+}
+
+/// Closes the player's enchanting table, returning the
+/// items in it to the player's inventory.
+pub fn close_enchanting_table(game: &mut Game, player: Entity) -> SysResult {
+    let table = table_inventory(game, player)?;
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    game.ecs.remove::<OpenEnchantingTable>(player)?;
+    game.ecs.insert(
+        player,
+        Window::new(BackingWindow::Player { player: inventory }),
+    )?;
+
+    let items: Vec<ItemStack> = [Area::EnchantmentItem, Area::EnchantmentLapis]
+        .iter()
+        .filter_map(|&area| table.item(area, 0).and_then(|mut item| item.take()))
+        .collect();
+    window::return_items(game, player, items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offers_are_deterministic() {
+        let sword = ItemStack::new(Item::DiamondSword, 1);
+        let offers = compute_offers(&sword, 15, EnchantmentSeed(1234));
+        assert_eq!(offers, compute_offers(&sword, 15, EnchantmentSeed(1234)));
+
+        for (slot, offer) in offers.iter().enumerate() {
+            assert!(offer.cost >= slot as u32 + 1);
+            let (info, level) = offer.hint.expect("offer has a hint");
+            assert!(info.target.includes(Item::DiamondSword));
+            assert!((1..=info.max_level).contains(&level));
+        }
+        assert!(offers[2].cost >= 30);
+    }
+
+    #[test]
+    fn unenchantable_items_have_no_offers() {
+        let dirt = ItemStack::new(Item::Dirt, 1);
+        assert_eq!(
+            compute_offers(&dirt, 15, EnchantmentSeed(0)),
+            <[EnchantmentOffer; OFFER_COUNT]>::default()
+        );
+
+        let mut enchanted = ItemStack::new(Item::IronPickaxe, 1);
+        apply_enchantments(
+            &mut enchanted,
+            &[(enchantment_info("minecraft:efficiency").unwrap(), 1)],
+        );
+        assert!(!is_enchantable(&enchanted));
+    }
+
+    #[test]
+    fn enchanting_books_stores_enchantments() {
+        let mut book = ItemStack::new(Item::Book, 1);
+        apply_enchantments(
+            &mut book,
+            &[(enchantment_info("minecraft:sharpness").unwrap(), 3)],
+        );
+        assert_eq!(book.item, Item::EnchantedBook);
+        let meta = book.meta.unwrap();
+        assert!(meta.enchantments.is_empty());
+        assert_eq!(meta.stored_enchantments[0].level, 3);
+    }
+
+    #[test]
+    fn enchantability_by_material() {
+        assert_eq!(enchantability(Item::GoldenChestplate), 25);
+        assert_eq!(enchantability(Item::GoldenPickaxe), 22);
+        assert_eq!(enchantability(Item::IronHelmet), 9);
+        assert_eq!(enchantability(Item::IronSword), 14);
+        assert_eq!(enchantability(Item::Book), 1);
+        assert_eq!(enchantability(Item::Stick), 0);
+    }
+}
@@ -0,0 +1,211 @@
+//! Enchantments which can be obtained from an enchanting
+//! table, and the items they apply to.
+
+use base::Item;
+
+/// Items an enchantment can be applied to at an enchanting table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EnchantmentTarget {
+    Armor,
+    ArmorHead,
+    ArmorChest,
+    ArmorFeet,
+    Weapon,
+    Digger,
+    FishingRod,
+    Trident,
+    /// Any item with durability.
+    Breakable,
+    Bow,
+    Crossbow,
+}
+
+impl EnchantmentTarget {
+    /// Returns whether this target includes `item`.
+    pub fn includes(self, item: Item) -> bool {
+        let name = item.name();
+        let is_helmet = name.ends_with("_helmet");
+        let is_chestplate = name.ends_with("_chestplate");
+        let is_boots = name.ends_with("_boots");
+        match self {
+            EnchantmentTarget::Armor => {
+                is_helmet || is_chestplate || is_boots || name.ends_with("_leggings")
+            }
+            EnchantmentTarget::ArmorHead => is_helmet,
+            EnchantmentTarget::ArmorChest => is_chestplate,
+            EnchantmentTarget::ArmorFeet => is_boots,
+            EnchantmentTarget::Weapon => name.ends_with("_sword"),
+            EnchantmentTarget::Digger => {
+                name.ends_with("_pickaxe")
+                    || name.ends_with("_axe")
+                    || name.ends_with("_shovel")
+                    || name.ends_with("_hoe")
+            }
+            EnchantmentTarget::FishingRod => item == Item::FishingRod,
+            EnchantmentTarget::Trident => item == Item::Trident,
+            EnchantmentTarget::Breakable => item.durability().is_some(),
+            EnchantmentTarget::Bow => item == Item::Bow,
+            EnchantmentTarget::Crossbow => item == Item::Crossbow,
+        }
+    }
+}
+
+/// Returns the enchantability of an item, which increases the
+/// chance of getting higher level enchantments. Items with
+/// an enchantability of zero can't be enchanted.
+pub fn enchantability(item: Item) -> i32 {
+    let name = item.name();
+    let is_armor = EnchantmentTarget::Armor.includes(item);
+    let is_tool =
+        EnchantmentTarget::Weapon.includes(item) || EnchantmentTarget::Digger.includes(item);
+    if !is_armor && !is_tool {
+        return match item {
+            Item::Book | Item::Bow | Item::Crossbow | Item::FishingRod | Item::Trident => 1,
+            _ => 0,
+        };
+    }
+
+    let material = name.split('_').next().unwrap_or_default();
+    match (material, is_armor) {
+        ("leather", true) => 15,
+        ("chainmail", true) => 12,
+        ("iron", true) => 9,
+        ("golden", true) => 25,
+        ("diamond", true) => 10,
+        ("turtle", true) => 9,
+        ("netherite", _) => 15,
+        ("wooden", false) => 15,
+        ("stone", false) => 5,
+        ("iron", false) => 14,
+        ("golden", false) => 22,
+        ("diamond", false) => 10,
+        _ => 0,
+    }
+}
+
+/// Maximum cost at which an enchantment level can be selected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MaxCost {
+    /// The maximum cost is the minimum cost plus this value.
+    Span(i32),
+    Fixed(i32),
+}
+
+/// An enchantment which can be obtained from an enchanting table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EnchantmentInfo {
+    /// Namespaced ID of the enchantment.
+    pub id: &'static str,
+    /// Numeric ID of the enchantment used by the protocol.
+    pub protocol_id: i16,
+    /// Relative chance of the enchantment being selected.
+    pub weight: i32,
+    pub max_level: u16,
+    /// The minimum cost for level 1 ...
+    pub base_cost: i32,
+    /// ... and how much it increases per level.
+    pub cost_per_level: i32,
+    pub max_cost: MaxCost,
+    pub target: EnchantmentTarget,
+}
+
+impl EnchantmentInfo {
+    pub fn min_cost(&self, level: u16) -> i32 {
+        self.base_cost + self.cost_per_level * (level as i32 - 1)
+    }
+
+    pub fn max_cost(&self, level: u16) -> i32 {
+        match self.max_cost {
+            MaxCost::Span(span) => self.min_cost(level) + span,
+            MaxCost::Fixed(cost) => cost,
+        }
+    }
+
+    /// Returns whether this enchantment can be on
+    /// the same item as `other`.
+    pub fn is_compatible_with(&self, other: &EnchantmentInfo) -> bool {
+        const EXCLUSIVE_GROUPS: &[&[&str]] = &[
+            &[
+                "minecraft:protection",
+                "minecraft:fire_protection",
+                "minecraft:blast_protection",
+                "minecraft:projectile_protection",
+            ],
+            &[
+                "minecraft:sharpness",
+                "minecraft:smite",
+                "minecraft:bane_of_arthropods",
+            ],
+            &["minecraft:silk_touch", "minecraft:fortune"],
+            &["minecraft:riptide", "minecraft:loyalty"],
+            &["minecraft:riptide", "minecraft:channeling"],
+            &["minecraft:multishot", "minecraft:piercing"],
+        ];
+        self.id != other.id
+            && !EXCLUSIVE_GROUPS
+                .iter()
+                .any(|group| group.contains(&self.id) && group.contains(&other.id))
+    }
+}
+
+macro_rules! enchantments {
+    ($($id:literal = $protocol_id:literal, $weight:literal, $max_level:literal, $base_cost:literal + $cost_per_level:literal, $max_cost:expr, $target:ident;)*) => {
+        /// Enchantments obtainable from an enchanting
+        /// table, in protocol ID order.
+        pub const TABLE_ENCHANTMENTS: &[EnchantmentInfo] = &[
+            $(EnchantmentInfo {
+                id: concat!("minecraft:", $id),
+                protocol_id: $protocol_id,
+                weight: $weight,
+                max_level: $max_level,
+                base_cost: $base_cost,
+                cost_per_level: $cost_per_level,
+                max_cost: $max_cost,
+                target: EnchantmentTarget::$target,
+            },)*
+        ];
+    };
+}
+
+use MaxCost::{Fixed, Span};
+
+enchantments! {
+    "protection" = 0, 10, 4, 1 + 11, Span(11), Armor;
+    "fire_protection" = 1, 5, 4, 10 + 8, Span(8), Armor;
+    "feather_falling" = 2, 5, 4, 5 + 6, Span(6), ArmorFeet;
+    "blast_protection" = 3, 2, 4, 5 + 8, Span(8), Armor;
+    "projectile_protection" = 4, 5, 4, 3 + 6, Span(6), Armor;
+    "respiration" = 5, 2, 3, 10 + 10, Span(30), ArmorHead;
+    "aqua_affinity" = 6, 2, 1, 1 + 0, Span(40), ArmorHead;
+    "thorns" = 7, 1, 3, 10 + 20, Span(55), ArmorChest;
+    "depth_strider" = 8, 2, 3, 10 + 10, Span(15), ArmorFeet;
+    "sharpness" = 12, 10, 5, 1 + 11, Span(20), Weapon;
+    "smite" = 13, 5, 5, 5 + 8, Span(20), Weapon;
+    "bane_of_arthropods" = 14, 5, 5, 5 + 8, Span(20), Weapon;
+    "knockback" = 15, 5, 2, 5 + 20, Span(55), Weapon;
+    "fire_aspect" = 16, 2, 2, 10 + 20, Span(55), Weapon;
+    "looting" = 17, 2, 3, 15 + 9, Span(55), Weapon;
+    "sweeping" = 18, 2, 3, 5 + 9, Span(15), Weapon;
+    "efficiency" = 19, 10, 5, 1 + 10, Span(55), Digger;
+    "silk_touch" = 20, 1, 1, 15 + 0, Span(55), Digger;
+    "unbreaking" = 21, 5, 3, 5 + 8, Span(55), Breakable;
+    "fortune" = 22, 2, 3, 15 + 9, Span(55), Digger;
+    "power" = 23, 10, 5, 1 + 10, Span(15), Bow;
+    "punch" = 24, 2, 2, 12 + 20, Span(25), Bow;
+    "flame" = 25, 2, 1, 20 + 0, Fixed(50), Bow;
+    "infinity" = 26, 1, 1, 20 + 0, Fixed(50), Bow;
+    "luck_of_the_sea" = 27, 2, 3, 15 + 9, Span(55), FishingRod;
+    "lure" = 28, 2, 3, 15 + 9, Span(55), FishingRod;
+    "loyalty" = 29, 5, 3, 12 + 7, Fixed(50), Trident;
+    "impaling" = 30, 2, 5, 1 + 8, Span(20), Trident;
+    "riptide" = 31, 2, 3, 17 + 7, Fixed(50), Trident;
+    "channeling" = 32, 1, 1, 25 + 0, Fixed(50), Trident;
+    "multishot" = 33, 2, 1, 20 + 0, Fixed(50), Crossbow;
+    "quick_charge" = 34, 5, 3, 12 + 20, Fixed(50), Crossbow;
+    "piercing" = 35, 10, 4, 1 + 10, Fixed(50), Crossbow;
+}
+
+/// Returns the table enchantment with the given namespaced ID.
+pub fn enchantment_info(id: &str) -> Option<&'static EnchantmentInfo> {
+    TABLE_ENCHANTMENTS.iter().find(|info| info.id == id)
+}
@@ -4,6 +4,7 @@
 //! It should export a `build_default(&mut EntityBuilder)` function to
 //! add default components for that entity.
 
+use base::EntityKind;
 use ecs::EntityBuilder;
 use quill_common::{components::OnGround, entity_init::EntityInit};
 use uuid::Uuid;
@@ -234,3 +235,117 @@ pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
         EntityInit::FishingBobber => fishing_bobber::build_default(builder),
     }
 }
+
+/// Returns the [`EntityInit`] which spawns an entity of the given kind.
+pub fn entity_init_for_kind(kind: EntityKind) -> EntityInit {
+    match kind {
+        EntityKind::AreaEffectCloud => EntityInit::AreaEffectCloud,
+        EntityKind::ArmorStand => EntityInit::ArmorStand,
+        EntityKind::Arrow => EntityInit::Arrow,
+        EntityKind::Bat => EntityInit::Bat,
+        EntityKind::Bee => EntityInit::Bee,
+        EntityKind::Blaze => EntityInit::Blaze,
+        EntityKind::Boat => EntityInit::Boat,
+        EntityKind::Cat => EntityInit::Cat,
+        EntityKind::CaveSpider => EntityInit::CaveSpider,
+        EntityKind::Chicken => EntityInit::Chicken,
+        EntityKind::Cod => EntityInit::Cod,
+        EntityKind::Cow => EntityInit::Cow,
+        EntityKind::Creeper => EntityInit::Creeper,
+        EntityKind::Dolphin => EntityInit::Dolphin,
+        EntityKind::Donkey => EntityInit::Donkey,
+        EntityKind::DragonFireball => EntityInit::DragonFireball,
+        EntityKind::Drowned => EntityInit::Drowned,
+        EntityKind::ElderGuardian => EntityInit::ElderGuardian,
+        EntityKind::EndCrystal => EntityInit::EndCrystal,
+        EntityKind::EnderDragon => EntityInit::EnderDragon,
+        EntityKind::Enderman => EntityInit::Enderman,
+        EntityKind::Endermite => EntityInit::Endermite,
+        EntityKind::Evoker => EntityInit::Evoker,
+        EntityKind::EvokerFangs => EntityInit::EvokerFangs,
+        EntityKind::ExperienceOrb => EntityInit::ExperienceOrb,
+        EntityKind::EyeOfEnder => EntityInit::EyeOfEnder,
+        EntityKind::FallingBlock => EntityInit::FallingBlock,
+        EntityKind::FireworkRocket => EntityInit::FireworkRocket,
+        EntityKind::Fox => EntityInit::Fox,
+        EntityKind::Ghast => EntityInit::Ghast,
+        EntityKind::Giant => EntityInit::Giant,
+        EntityKind::Guardian => EntityInit::Guardian,
+        EntityKind::Hoglin => EntityInit::Hoglin,
+        EntityKind::Horse => EntityInit::Horse,
+        EntityKind::Husk => EntityInit::Husk,
+        EntityKind::Illusioner => EntityInit::Illusioner,
+        EntityKind::IronGolem => EntityInit::IronGolem,
+        EntityKind::Item => EntityInit::Item,
+        EntityKind::ItemFrame => EntityInit::ItemFrame,
+        EntityKind::Fireball => EntityInit::Fireball,
+        EntityKind::LeashKnot => EntityInit::LeashKnot,
+        EntityKind::LightningBolt => EntityInit::LightningBolt,
+        EntityKind::Llama => EntityInit::Llama,
+        EntityKind::LlamaSpit => EntityInit::LlamaSpit,
+        EntityKind::MagmaCube => EntityInit::MagmaCube,
+        EntityKind::Minecart => EntityInit::Minecart,
+        EntityKind::ChestMinecart => EntityInit::ChestMinecart,
+        EntityKind::CommandBlockMinecart => EntityInit::CommandBlockMinecart,
+        EntityKind::FurnaceMinecart => EntityInit::FurnaceMinecart,
+        EntityKind::HopperMinecart => EntityInit::HopperMinecart,
+        EntityKind::SpawnerMinecart => EntityInit::SpawnerMinecart,
+        EntityKind::TntMinecart => EntityInit::TntMinecart,
+        EntityKind::Mule => EntityInit::Mule,
+        EntityKind::Mooshroom => EntityInit::Mooshroom,
+        EntityKind::Ocelot => EntityInit::Ocelot,
+        EntityKind::Painting => EntityInit::Painting,
+        EntityKind::Panda => EntityInit::Panda,
+        EntityKind::Parrot => EntityInit::Parrot,
+        EntityKind::Phantom => EntityInit::Phantom,
+        EntityKind::Pig => EntityInit::Pig,
+        EntityKind::Piglin => EntityInit::Piglin,
+        EntityKind::PiglinBrute => EntityInit::PiglinBrute,
+        EntityKind::Pillager => EntityInit::Pillager,
+        EntityKind::PolarBear => EntityInit::PolarBear,
+        EntityKind::Tnt => EntityInit::Tnt,
+        EntityKind::Pufferfish => EntityInit::Pufferfish,
+        EntityKind::Rabbit => EntityInit::Rabbit,
+        EntityKind::Ravager => EntityInit::Ravager,
+        EntityKind::Salmon => EntityInit::Salmon,
+        EntityKind::Sheep => EntityInit::Sheep,
+        EntityKind::Shulker => EntityInit::Shulker,
+        EntityKind::ShulkerBullet => EntityInit::ShulkerBullet,
+        EntityKind::Silverfish => EntityInit::Silverfish,
+        EntityKind::Skeleton => EntityInit::Skeleton,
+        EntityKind::SkeletonHorse => EntityInit::SkeletonHorse,
+        EntityKind::Slime => EntityInit::Slime,
+        EntityKind::SmallFireball => EntityInit::SmallFireball,
+        EntityKind::SnowGolem => EntityInit::SnowGolem,
+        EntityKind::Snowball => EntityInit::Snowball,
+        EntityKind::SpectralArrow => EntityInit::SpectralArrow,
+        EntityKind::Spider => EntityInit::Spider,
+        EntityKind::Squid => EntityInit::Squid,
+        EntityKind::Stray => EntityInit::Stray,
+        EntityKind::Strider => EntityInit::Strider,
+        EntityKind::Egg => EntityInit::Egg,
+        EntityKind::EnderPearl => EntityInit::EnderPearl,
+        EntityKind::ExperienceBottle => EntityInit::ExperienceBottle,
+        EntityKind::Potion => EntityInit::Potion,
+        EntityKind::Trident => EntityInit::Trident,
+        EntityKind::TraderLlama => EntityInit::TraderLlama,
+        EntityKind::TropicalFish => EntityInit::TropicalFish,
+        EntityKind::Turtle => EntityInit::Turtle,
+        EntityKind::Vex => EntityInit::Vex,
+        EntityKind::Villager => EntityInit::Villager,
+        EntityKind::Vindicator => EntityInit::Vindicator,
+        EntityKind::WanderingTrader => EntityInit::WanderingTrader,
+        EntityKind::Witch => EntityInit::Witch,
+        EntityKind::Wither => EntityInit::Wither,
+        EntityKind::WitherSkeleton => EntityInit::WitherSkeleton,
+        EntityKind::WitherSkull => EntityInit::WitherSkull,
+        EntityKind::Wolf => EntityInit::Wolf,
+        EntityKind::Zoglin => EntityInit::Zoglin,
+        EntityKind::Zombie => EntityInit::Zombie,
+        EntityKind::ZombieHorse => EntityInit::ZombieHorse,
+        EntityKind::ZombieVillager => EntityInit::ZombieVillager,
+        EntityKind::ZombifiedPiglin => EntityInit::ZombifiedPiglin,
+        EntityKind::Player => EntityInit::Player,
+        EntityKind::FishingBobber => EntityInit::FishingBobber,
+    }
+}
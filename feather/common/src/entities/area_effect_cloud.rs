@@ -1,4 +1,10 @@
-use base::EntityKind;
+use base::{
+    metadata::{
+        META_INDEX_AREA_EFFECT_CLOUD_COLOR, META_INDEX_AREA_EFFECT_CLOUD_IGNORE_RADIUS,
+        META_INDEX_AREA_EFFECT_CLOUD_PARTICLE, META_INDEX_AREA_EFFECT_CLOUD_RADIUS,
+    },
+    EntityKind, EntityMetadata, ParticleKind,
+};
 use ecs::EntityBuilder;
 use quill_common::entities::AreaEffectCloud;
 
@@ -6,5 +12,15 @@ pub fn build_default(builder: &mut EntityBuilder) {
     super::build_default(builder);
     builder
         .add(AreaEffectCloud)
-        .add(EntityKind::AreaEffectCloud);
+        .add(EntityKind::AreaEffectCloud)
+        .add(
+            EntityMetadata::entity_base()
+                .with(META_INDEX_AREA_EFFECT_CLOUD_RADIUS, 0.5f32)
+                .with(META_INDEX_AREA_EFFECT_CLOUD_COLOR, 0i32)
+                .with(META_INDEX_AREA_EFFECT_CLOUD_IGNORE_RADIUS, false)
+                .with(
+                    META_INDEX_AREA_EFFECT_CLOUD_PARTICLE,
+                    ParticleKind::EntityEffect,
+                ),
+        );
 }
@@ -0,0 +1,116 @@
+//! Spawns entities from their NBT data, like the
+//! data tag given to `/summon`.
+//!
+//! The supported tags are:
+//! * `CustomName`, a JSON text.
+//! * `Rotation`, the yaw and pitch of the entity.
+//! * `Health`, capped to the entity's maximum health.
+//! * `Passengers`, entities spawned riding this one,
+//!   each with an `id` and its own tags.
+//!
+//! Like in vanilla, other tags are ignored.
+
+use anyhow::{anyhow, bail, Context};
+use base::{EntityKind, Position, Text};
+use ecs::Entity;
+use nbt::Value;
+use quill_common::components::CustomName;
+
+use crate::{
+    entities::entity_init_for_kind,
+    health::{max_health, Health},
+    riding, Game,
+};
+
+/// Spawns an entity of `kind` at `position` with the
+/// tags in `nbt`, a compound, and spawns its passengers.
+pub fn spawn_with_nbt(
+    game: &mut Game,
+    kind: EntityKind,
+    mut position: Position,
+    nbt: &Value,
+) -> anyhow::Result<Entity> {
+    let tags = match nbt {
+        Value::Compound(tags) => tags,
+        _ => bail!("entity data must be a compound"),
+    };
+
+    if let Some(rotation) = tags.get("Rotation") {
+        let (yaw, pitch) = match rotation {
+            Value::List(angles) => match angles.as_slice() {
+                [yaw, pitch] => (number(yaw), number(pitch)),
+                _ => (None, None),
+            },
+            _ => (None, None),
+        };
+        position.yaw = yaw.context("Rotation must hold a yaw and a pitch")? as f32;
+        position.pitch = pitch.context("Rotation must hold a yaw and a pitch")? as f32;
+    }
+
+    let mut builder = game.create_entity_builder(position, entity_init_for_kind(kind));
+    if let Some(name) = tags.get("CustomName") {
+        let name = match name {
+            Value::String(json) => serde_json::from_str::<Text>(json)
+                .map_err(|e| anyhow!("invalid CustomName: {}", e))?,
+            _ => bail!("CustomName must be a string"),
+        };
+        builder.add(CustomName::new(&name.to_plain_string()));
+    }
+    if let Some(health) = tags.get("Health") {
+        let health = number(health).context("Health must be a number")? as f32;
+        let max = max_health(kind);
+        builder.add(Health {
+            current: health.min(max).max(0.0),
+            max,
+        });
+    }
+    let entity = game.spawn_entity(builder);
+
+    if let Some(passengers) = tags.get("Passengers") {
+        let passengers = match passengers {
+            Value::List(passengers) => passengers,
+            _ => bail!("Passengers must be a list"),
+        };
+        for passenger in passengers {
+            let kind = passenger_kind(passenger)?;
+            let passenger = spawn_with_nbt(game, kind, position, passenger)?;
+            riding::mount(game, passenger, entity)?;
+        }
+    }
+
+    Ok(entity)
+}
+
+/// Reads the `id` tag of a passenger.
+fn passenger_kind(passenger: &Value) -> anyhow::Result<EntityKind> {
+    let id = match passenger {
+        Value::Compound(tags) => match tags.get("id") {
+            Some(Value::String(id)) => id,
+            _ => bail!("passengers need an id"),
+        },
+        _ => bail!("passengers must be compounds"),
+    };
+    summonable_kind(id)
+}
+
+/// Parses the kind of a summonable entity, like
+/// `minecraft:zombie` or `zombie`.
+pub fn summonable_kind(id: &str) -> anyhow::Result<EntityKind> {
+    match EntityKind::from_name(id.trim_start_matches("minecraft:")) {
+        Some(EntityKind::Player) => bail!("players cannot be summoned"),
+        Some(kind) => Ok(kind),
+        None => bail!("unknown entity type '{}'", id),
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match *value {
+        Value::Byte(v) => Some(v.into()),
+        Value::Short(v) => Some(v.into()),
+        Value::Int(v) => Some(v.into()),
+        Value::Long(v) => Some(v as f64),
+        Value::Float(v) => Some(v.into()),
+        Value::Double(v) => Some(v),
+        _ => None,
+    }
+}
@@ -1,9 +1,10 @@
 use std::sync::Arc;
 
-use base::{Chunk, ChunkPosition};
+use base::{Chunk, ChunkPosition, Difficulty, Text};
 use parking_lot::RwLock;
+use uuid::Uuid;
 
-use crate::view::View;
+use crate::{combat_log::CombatLogAction, resource_pack::ResourcePackStatus, view::View};
 
 mod block_change;
 mod plugin_message;
@@ -15,8 +16,14 @@ pub use plugin_message::PluginMessageEvent;
 #[derive(Debug)]
 pub struct PlayerJoinEvent;
 
+/// Triggered alongside [`PlayerJoinEvent`] when a player
+/// joins the server for the first time.
+#[derive(Debug)]
+pub struct FirstJoinEvent;
+
 /// Event triggered when a player changes their `View`,
-/// meaning they crossed into a new chunk.
+/// meaning they crossed into a new chunk or changed
+/// their view distance.
 #[derive(Debug)]
 pub struct ViewUpdateEvent {
     pub old_view: View,
@@ -76,3 +83,103 @@ pub struct EntityRemoveEvent;
 /// Triggered when an entity is added into the world.
 #[derive(Debug)]
 pub struct EntityCreateEvent;
+
+/// Triggered on a player when they are about to be
+/// transferred to another server behind the proxy.
+#[derive(Debug)]
+pub struct PlayerTransferEvent {
+    /// Name of the target server in the proxy's configuration.
+    pub server: String,
+}
+
+/// Triggered on a player when they leave this server
+/// after being transferred to another one.
+///
+/// Like [`EntityRemoveEvent`], the player remains alive
+/// for one tick so systems can observe this event.
+#[derive(Debug)]
+pub struct PlayerTransferredEvent {
+    pub server: String,
+}
+
+/// Triggered on a player when they are kicked from the server.
+///
+/// The server disconnects the player with `reason` after
+/// gameplay systems have run.
+#[derive(Debug)]
+pub struct PlayerKickEvent {
+    pub reason: Text,
+}
+
+/// Triggered on a player when they vanish or reappear.
+///
+/// The server hides or shows the player to other
+/// clients accordingly. See [`crate::vanish`].
+#[derive(Debug)]
+pub struct VanishChangeEvent {
+    pub vanished: bool,
+}
+
+/// Triggered on a player when they become AFK
+/// or come back. See [`crate::afk`].
+#[derive(Debug)]
+pub struct AfkChangeEvent {
+    pub afk: bool,
+}
+
+/// Triggered when the difficulty of a world changes.
+///
+/// The server sends the new difficulty to
+/// the players in the world.
+#[derive(Debug)]
+pub struct DifficultyChangeEvent {
+    /// Name of the world.
+    pub world: String,
+    pub difficulty: Difficulty,
+}
+
+/// Triggered when regions of a world are
+/// defined or removed. See [`crate::regions`].
+#[derive(Debug)]
+pub struct RegionsChangeEvent {
+    /// Name of the world.
+    pub world: String,
+}
+
+/// Triggered on a player who disconnects during combat,
+/// before their entity is removed. See [`crate::combat_log`].
+#[derive(Debug)]
+pub struct CombatLogEvent {
+    /// What the server does about it.
+    pub action: CombatLogAction,
+}
+
+/// Triggered when a combat logger loses their items,
+/// either on disconnecting or when their stand-in is killed.
+#[derive(Debug)]
+pub struct CombatLogPunishEvent {
+    pub player: Uuid,
+}
+
+/// Triggered on a player when a resource pack should be
+/// sent to them. See [`crate::resource_pack`].
+#[derive(Debug)]
+pub struct ResourcePackSendEvent {
+    pub url: String,
+    /// SHA-1 hash of the pack, or empty.
+    pub hash: String,
+}
+
+/// Triggered on a player when their client
+/// responds to a resource pack.
+#[derive(Debug)]
+pub struct ResourcePackStatusEvent {
+    pub status: ResourcePackStatus,
+}
+
+/// Triggered on a player when they unlock recipes.
+/// See [`crate::recipe_book`].
+#[derive(Debug)]
+pub struct RecipesUnlockedEvent {
+    pub recipes: Vec<String>,
+}
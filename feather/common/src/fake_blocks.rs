@@ -0,0 +1,107 @@
+//! Fake blocks, which override blocks on the
+//! client of a single player without changing the world.
+//!
+//! They are stored in the player's [`FakeBlocks`] component.
+//! The server sends the changes made to it to the player's
+//! client, and sends the overrides again whenever the client
+//! receives their chunk again. Restoring a fake block shows
+//! the real block again.
+
+use ahash::AHashMap;
+use base::{BlockId, BlockPosition};
+
+/// Component storing the blocks overridden for a player.
+#[derive(Debug, Default)]
+pub struct FakeBlocks {
+    blocks: AHashMap<BlockPosition, BlockId>,
+    /// Positions whose override changed since the last
+    /// call to `take_changes`, in order.
+    changed: Vec<BlockPosition>,
+}
+
+impl FakeBlocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shows `block` at `pos` instead of the real block.
+    pub fn set(&mut self, pos: BlockPosition, block: BlockId) {
+        if self.blocks.insert(pos, block) != Some(block) {
+            self.changed.push(pos);
+        }
+    }
+
+    /// Shows the real block at `pos` again.
+    /// Returns `false` if the block wasn't overridden.
+    pub fn restore(&mut self, pos: BlockPosition) -> bool {
+        let restored = self.blocks.remove(&pos).is_some();
+        if restored {
+            self.changed.push(pos);
+        }
+        restored
+    }
+
+    /// Shows all the real blocks again.
+    pub fn restore_all(&mut self) {
+        self.changed.extend(self.blocks.drain().map(|(pos, _)| pos));
+    }
+
+    pub fn get(&self, pos: BlockPosition) -> Option<BlockId> {
+        self.blocks.get(&pos).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BlockPosition, BlockId)> + '_ {
+        self.blocks.iter().map(|(&pos, &block)| (pos, block))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Takes the overrides changed since the last call,
+    /// with `None` for restored blocks.
+    pub fn take_changes(&mut self) -> Vec<(BlockPosition, Option<BlockId>)> {
+        let mut changes: Vec<_> = self
+            .changed
+            .drain(..)
+            .map(|pos| (pos, self.blocks.get(&pos).copied()))
+            .collect();
+        // Keep only the last change to each position.
+        let mut seen = Vec::with_capacity(changes.len());
+        changes.reverse();
+        changes.retain(|(pos, _)| {
+            let first = !seen.contains(pos);
+            seen.push(*pos);
+            first
+        });
+        changes.reverse();
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_are_coalesced() {
+        let mut fake_blocks = FakeBlocks::new();
+        let a = BlockPosition::new(0, 64, 0);
+        let b = BlockPosition::new(1, 64, 0);
+        fake_blocks.set(a, BlockId::stone());
+        fake_blocks.set(b, BlockId::stone());
+        fake_blocks.set(a, BlockId::dirt());
+        assert!(fake_blocks.restore(b));
+        assert!(!fake_blocks.restore(b));
+
+        assert_eq!(
+            fake_blocks.take_changes(),
+            [(a, Some(BlockId::dirt())), (b, None)]
+        );
+        assert!(fake_blocks.take_changes().is_empty());
+
+        fake_blocks.restore_all();
+        assert!(fake_blocks.is_empty());
+        assert_eq!(fake_blocks.take_changes(), [(a, None)]);
+    }
+}
@@ -0,0 +1,151 @@
+//! Fake entities, which only exist on the client of a single
+//! player: holograms, NPCs and the like.
+//!
+//! They are stored in the player's [`FakeEntities`] component.
+//! The server sends the changes made to it to the player's client,
+//! and they disappear with the player when they leave.
+
+use std::collections::BTreeMap;
+
+use base::Position;
+use quill_common::fake_entity::FakeEntity;
+
+/// ID of a fake entity, unique among those of a player.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FakeEntityId(pub u32);
+
+/// A change to a player's fake entities
+/// which still needs to be sent to their client.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FakeEntityUpdate {
+    Spawn(FakeEntityId, FakeEntity),
+    Move(FakeEntityId, Position),
+    SetText(FakeEntityId, String),
+    Remove(FakeEntityId),
+}
+
+impl FakeEntityUpdate {
+    pub fn id(&self) -> FakeEntityId {
+        match self {
+            FakeEntityUpdate::Spawn(id, _)
+            | FakeEntityUpdate::Move(id, _)
+            | FakeEntityUpdate::SetText(id, _)
+            | FakeEntityUpdate::Remove(id) => *id,
+        }
+    }
+}
+
+/// Component storing the fake entities shown to a player.
+#[derive(Debug, Default)]
+pub struct FakeEntities {
+    next_id: u32,
+    entities: BTreeMap<FakeEntityId, FakeEntity>,
+    updates: Vec<FakeEntityUpdate>,
+}
+
+impl FakeEntities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, entity: FakeEntity) -> FakeEntityId {
+        let id = FakeEntityId(self.next_id);
+        self.next_id += 1;
+        self.entities.insert(id, entity.clone());
+        self.updates.push(FakeEntityUpdate::Spawn(id, entity));
+        id
+    }
+
+    /// Moves a fake entity. Returns `false` if it doesn't exist.
+    pub fn move_to(&mut self, id: FakeEntityId, position: Position) -> bool {
+        match self.entities.get_mut(&id) {
+            Some(entity) => {
+                entity.set_position(position);
+                self.updates.push(FakeEntityUpdate::Move(id, position));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets the text of a hologram. Returns `false`
+    /// if it doesn't exist or isn't a hologram.
+    pub fn set_text(&mut self, id: FakeEntityId, new_text: String) -> bool {
+        match self.entities.get_mut(&id) {
+            Some(FakeEntity::Hologram { text, .. }) => {
+                *text = new_text.clone();
+                self.updates.push(FakeEntityUpdate::SetText(id, new_text));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes a fake entity. Returns `false` if it doesn't exist.
+    pub fn remove(&mut self, id: FakeEntityId) -> bool {
+        if self.entities.remove(&id).is_none() {
+            return false;
+        }
+        // Updates to an entity the client hasn't seen yet
+        // don't need to be sent.
+        let spawned_this_tick = self
+            .updates
+            .iter()
+            .any(|update| matches!(update, FakeEntityUpdate::Spawn(spawned, _) if *spawned == id));
+        self.updates.retain(|update| update.id() != id);
+        if !spawned_this_tick {
+            self.updates.push(FakeEntityUpdate::Remove(id));
+        }
+        true
+    }
+
+    pub fn get(&self, id: FakeEntityId) -> Option<&FakeEntity> {
+        self.entities.get(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (FakeEntityId, &FakeEntity)> + '_ {
+        self.entities.iter().map(|(&id, entity)| (id, entity))
+    }
+
+    /// Takes the changes made since the last call.
+    pub fn take_updates(&mut self) -> Vec<FakeEntityUpdate> {
+        std::mem::take(&mut self.updates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    fn hologram(text: &str) -> FakeEntity {
+        FakeEntity::Hologram {
+            position: position!(0.0, 64.0, 0.0),
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn updates_are_recorded() {
+        let mut entities = FakeEntities::new();
+        let id = entities.spawn(hologram("a"));
+        entities.take_updates();
+
+        assert!(entities.set_text(id, "b".to_owned()));
+        assert!(entities.move_to(id, position!(1.0, 64.0, 0.0)));
+        assert!(entities.remove(id));
+        assert!(!entities.remove(id));
+        assert_eq!(entities.take_updates(), [FakeEntityUpdate::Remove(id)]);
+    }
+
+    #[test]
+    fn entities_removed_before_sending_are_never_sent() {
+        let mut entities = FakeEntities::new();
+        let id = entities.spawn(hologram("a"));
+        entities.set_text(id, "b".to_owned());
+        entities.remove(id);
+        assert!(entities.take_updates().is_empty());
+        assert!(entities.get(id).is_none());
+    }
+}
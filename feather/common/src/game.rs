@@ -11,6 +11,7 @@ use crate::{
     chat::{ChatKind, ChatMessage},
     chunk_entities::ChunkEntities,
     events::{BlockChangeEvent, EntityCreateEvent, EntityRemoveEvent, PlayerJoinEvent},
+    world::EntityWorld,
     ChatBox, World,
 };
 
@@ -180,6 +181,37 @@ impl Game {
         Ok(())
     }
 
+    /// Gets the world `entity` is in, as named by its
+    /// [`EntityWorld`] component.
+    ///
+    /// Gameplay code should go through this method when it
+    /// needs per-world settings like difficulty or PvP.
+    pub fn world_of(&self, entity: Entity) -> &World {
+        self.ecs
+            .get::<EntityWorld>(entity)
+            .ok()
+            .and_then(|world| self.world_named(&world.0))
+            .unwrap_or(&self.world)
+    }
+
+    /// Gets the world called `name`.
+    pub fn world_named(&self, name: &str) -> Option<&World> {
+        if self.world.name() == name {
+            Some(&self.world)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the world called `name`.
+    pub fn world_named_mut(&mut self, name: &str) -> Option<&mut World> {
+        if self.world.name() == name {
+            Some(&mut self.world)
+        } else {
+            None
+        }
+    }
+
     /// Gets the block at the given position.
     pub fn block(&self, pos: BlockPosition) -> Option<BlockId> {
         self.world.block_at(pos)
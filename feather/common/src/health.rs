@@ -0,0 +1,101 @@
+//! Health of mobs and the damage dealt to them.
+//!
+//! Mobs get their [`Health`] the first time they are damaged.
+//! [`damage`] triggers an [`EntityDamageEvent`] and removes
+//! the entity once its health runs out.
+
+use base::EntityKind;
+use ecs::{Entity, SysResult};
+
+use crate::Game;
+
+/// Health of a mob, in half hearts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    /// Returns full health for an entity of `kind`.
+    pub fn full(kind: EntityKind) -> Self {
+        let max = max_health(kind);
+        Self { current: max, max }
+    }
+}
+
+/// Event triggered when an entity takes damage.
+#[derive(Copy, Clone, Debug)]
+pub struct EntityDamageEvent {
+    pub amount: f32,
+}
+
+/// Returns the vanilla maximum health of an entity of `kind`.
+pub fn max_health(kind: EntityKind) -> f32 {
+    match kind {
+        EntityKind::Wither => 300.0,
+        EntityKind::Giant | EntityKind::Ravager => 100.0,
+        EntityKind::ElderGuardian => 80.0,
+        EntityKind::Enderman | EntityKind::Hoglin | EntityKind::Zoglin => 40.0,
+        EntityKind::Guardian | EntityKind::Shulker => 30.0,
+        EntityKind::Witch => 26.0,
+        EntityKind::Evoker | EntityKind::Vindicator => 24.0,
+        EntityKind::Spider | EntityKind::MagmaCube | EntityKind::Slime => 16.0,
+        EntityKind::Vex => 14.0,
+        EntityKind::CaveSpider => 12.0,
+        EntityKind::Ghast => 10.0,
+        EntityKind::Endermite | EntityKind::Silverfish => 8.0,
+        _ => 20.0,
+    }
+}
+
+/// Deals `amount` damage to `entity`, removing it
+/// from the world if its health runs out.
+pub fn damage(game: &mut Game, entity: Entity, amount: f32) -> SysResult {
+    if game.ecs.get::<Health>(entity).is_err() {
+        let kind = *game.ecs.get::<EntityKind>(entity)?;
+        game.ecs.insert(entity, Health::full(kind))?;
+    }
+
+    let health = {
+        let mut health = game.ecs.get_mut::<Health>(entity)?;
+        if health.current <= 0.0 {
+            // Already dead and about to be removed.
+            return Ok(());
+        }
+        health.current = (health.current - amount).max(0.0);
+        health.current
+    };
+    game.ecs
+        .insert_entity_event(entity, EntityDamageEvent { amount })?;
+    if health <= 0.0 {
+        game.remove_entity(entity)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::EntityRemoveEvent;
+
+    use super::*;
+
+    #[test]
+    fn entities_die_when_health_runs_out() {
+        let mut game = Game::new();
+        let silverfish = game.ecs.spawn((EntityKind::Silverfish,));
+
+        damage(&mut game, silverfish, 4.0).unwrap();
+        assert_eq!(
+            *game.ecs.get::<Health>(silverfish).unwrap(),
+            Health {
+                current: 4.0,
+                max: 8.0
+            }
+        );
+        assert!(game.ecs.get::<EntityDamageEvent>(silverfish).is_ok());
+
+        damage(&mut game, silverfish, 4.0).unwrap();
+        assert!(game.ecs.get::<EntityRemoveEvent>(silverfish).is_ok());
+    }
+}
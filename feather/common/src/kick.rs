@@ -0,0 +1,63 @@
+//! Kicking players from the server.
+//!
+//! [`kick_player`] triggers a [`PlayerKickEvent`], which
+//! the server answers by disconnecting the player's client.
+
+use base::Text;
+use ecs::{Entity, SysResult};
+use quill_common::components::Name;
+
+use crate::{
+    commands::{check_permission, CommandRegistry},
+    disconnect_reason,
+    events::PlayerKickEvent,
+    Game,
+};
+
+pub fn register(game: &mut Game) {
+    game.resources
+        .get_mut::<CommandRegistry>()
+        .expect("commands must be registered first")
+        .register("kickall", kickall_command);
+}
+
+/// Kicks `player` from the server, showing them `reason`.
+///
+/// See [`disconnect_reason`](crate::disconnect_reason)
+/// for the vanilla reasons.
+pub fn kick_player(game: &mut Game, player: Entity, reason: impl Into<Text>) -> SysResult {
+    game.ecs.insert_entity_event(
+        player,
+        PlayerKickEvent {
+            reason: reason.into(),
+        },
+    )?;
+    Ok(())
+}
+
+/// Permission node required for `/kickall`.
+pub const KICKALL_PERMISSION: &str = "feather.kickall";
+
+/// `/kickall [reason]`
+///
+/// Kicks every player except the sender.
+fn kickall_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    check_permission(game, sender, KICKALL_PERMISSION)?;
+    let reason = if args.is_empty() {
+        disconnect_reason::kicked_by_operator(None)
+    } else {
+        disconnect_reason::kicked_by_operator(Some(Text::from(args.join(" "))))
+    };
+
+    let players: Vec<Entity> = game
+        .ecs
+        .query::<&Name>()
+        .iter()
+        .map(|(player, _)| player)
+        .filter(|&player| player != sender)
+        .collect();
+    for &player in &players {
+        kick_player(game, player, reason.clone())?;
+    }
+    Ok(Text::of(format!("Kicked {} players", players.len())))
+}
@@ -24,23 +24,104 @@ pub mod world_source;
 pub mod world;
 pub use world::World;
 
-mod chunk_loading;
+pub mod chunk_loading;
 
 mod chunk_entities;
 
 pub mod chat;
+pub mod commands;
 pub use chat::ChatBox;
 
 pub mod entities;
+pub mod entity_nbt;
+pub mod riding;
 
 pub mod interactable;
 
+pub mod conversion;
+
+pub mod block_age;
+
+pub mod effects;
+pub mod health;
+
+pub mod beacon;
+
+pub mod conduit;
+
+pub mod enchanting;
+
+pub mod smithing;
+pub mod stonecutter;
+
+pub mod cartography;
+pub mod maps;
+
+pub mod transfer;
+
+pub mod disconnect_reason;
+pub mod kick;
+
+pub mod shutdown;
+
+pub mod permissions;
+
+pub mod vanish;
+
+pub mod fake_blocks;
+pub mod fake_entities;
+
+pub mod afk;
+
+pub mod world_settings;
+
+pub mod plugin_channels;
+
+pub mod combat_log;
+
+pub mod resource_pack;
+
+pub mod ai;
+
+pub mod persistent_tags;
+
+pub mod movement;
+
+pub mod regions;
+
+pub mod economy;
+
+pub mod recipe_book;
+
 /// Registers gameplay systems with the given `Game` and `SystemExecutor`.
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
     view::register(game, systems);
     chunk_loading::register(game, systems);
     chunk_entities::register(systems);
     interactable::register(game);
+    conversion::register(game, systems);
+    block_age::register(game, systems);
+    beacon::register(game, systems);
+    conduit::register(game, systems);
+    enchanting::register(game);
+    stonecutter::register(game);
+    smithing::register(game);
+    maps::register(game);
+    cartography::register(game);
+    commands::register(game);
+    effects::register(game, systems);
+    kick::register(game);
+    shutdown::register(game, systems);
+    vanish::register(game);
+    afk::register(game, systems);
+    world_settings::register(game);
+    plugin_channels::register(game);
+    combat_log::register(game, systems);
+    resource_pack::register(game);
+    ai::register(game, systems);
+    movement::register(systems);
+    economy::register(game);
+    recipe_book::register(game, systems);
 
     game.add_entity_spawn_callback(entities::add_entity_components);
 }
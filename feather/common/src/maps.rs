@@ -0,0 +1,172 @@
+//! The map data store: the area and pixels of each filled map,
+//! keyed by the map ID stored in the map item.
+//!
+//! The server loads the maps of the world into the [`MapStore`]
+//! and saves those added or changed since.
+
+use ahash::AHashMap;
+use base::{
+    anvil::map::{MapData, MAP_PIXELS, MAX_MAP_SCALE},
+    Item, ItemStack,
+};
+
+use crate::Game;
+
+pub fn register(game: &mut Game) {
+    game.insert_resource(MapStore::default());
+}
+
+/// Resource storing the data of every map, by ID.
+#[derive(Debug, Default)]
+pub struct MapStore {
+    maps: AHashMap<i32, MapData>,
+    /// The last map ID given out, or `None` if there are no maps.
+    last_id: Option<i32>,
+    /// Maps added or changed since the last save.
+    unsaved: Vec<i32>,
+    last_id_unsaved: bool,
+}
+
+impl MapStore {
+    /// Creates a store of maps loaded from a world,
+    /// none of which need to be saved.
+    pub fn new(maps: impl IntoIterator<Item = (i32, MapData)>, last_id: Option<i32>) -> Self {
+        let maps: AHashMap<i32, MapData> = maps.into_iter().collect();
+        // Keep giving out new IDs even if the counter is behind.
+        let last_id = maps.keys().copied().chain(last_id).max();
+        Self {
+            maps,
+            last_id,
+            unsaved: Vec::new(),
+            last_id_unsaved: false,
+        }
+    }
+
+    pub fn get(&self, id: i32) -> Option<&MapData> {
+        self.maps.get(&id)
+    }
+
+    /// Gives out a new map ID.
+    pub fn next_id(&mut self) -> i32 {
+        let id = self.last_id.map_or(0, |id| id + 1);
+        self.last_id = Some(id);
+        self.last_id_unsaved = true;
+        id
+    }
+
+    /// Stores the data of the map with the given ID.
+    pub fn insert(&mut self, id: i32, data: MapData) {
+        self.maps.insert(id, data);
+        if !self.unsaved.contains(&id) {
+            self.unsaved.push(id);
+        }
+    }
+
+    /// Takes the maps which need to be saved.
+    pub fn take_unsaved(&mut self) -> Vec<(i32, &MapData)> {
+        let maps = &self.maps;
+        self.unsaved
+            .drain(..)
+            .filter_map(|id| maps.get(&id).map(|data| (id, data)))
+            .collect()
+    }
+
+    /// Takes the last map ID given out, if it
+    /// changed since the last save.
+    pub fn take_unsaved_last_id(&mut self) -> Option<i32> {
+        if !std::mem::take(&mut self.last_id_unsaved) {
+            return None;
+        }
+        self.last_id
+    }
+}
+
+/// Gets the ID of the map shown by a filled map.
+pub fn map_id(stack: &ItemStack) -> Option<i32> {
+    if stack.item != Item::FilledMap {
+        return None;
+    }
+    stack.meta.as_ref().and_then(|meta| meta.map_id)
+}
+
+/// Sets the map shown by a filled map.
+pub fn set_map_id(stack: &mut ItemStack, id: i32) {
+    stack.meta.get_or_insert_with(Default::default).map_id = Some(id);
+}
+
+/// Returns a blank map covering twice the width of `map`,
+/// or `None` if `map` can't be zoomed out further.
+///
+/// Like in vanilla, the new center is aligned to the grid
+/// of maps at the new scale rather than kept.
+pub fn zoomed_out(map: &MapData) -> Option<MapData> {
+    if map.locked || map.scale >= MAX_MAP_SCALE {
+        return None;
+    }
+    let scale = map.scale + 1;
+    let width = 128 << scale;
+    let center = |coordinate: i32| {
+        let grid = (coordinate + 64).div_euclid(width);
+        grid * width + width / 2 - 64
+    };
+    Some(MapData {
+        scale,
+        x_center: center(map.x_center),
+        z_center: center(map.z_center),
+        locked: false,
+        colors: vec![0; MAP_PIXELS],
+        ..map.clone()
+    })
+}
+
+/// Returns a locked copy of `map`, or `None`
+/// if `map` is already locked.
+pub fn locked(map: &MapData) -> Option<MapData> {
+    if map.locked {
+        return None;
+    }
+    Some(MapData {
+        locked: true,
+        ..map.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(scale: i8, x_center: i32, z_center: i32) -> MapData {
+        MapData {
+            scale,
+            dimension: "minecraft:overworld".to_owned(),
+            x_center,
+            z_center,
+            locked: false,
+            tracking_position: true,
+            unlimited_tracking: false,
+            colors: vec![1; MAP_PIXELS],
+        }
+    }
+
+    #[test]
+    fn zooming_out_aligns_the_center() {
+        let zoomed = zoomed_out(&map(0, 192, -64)).unwrap();
+        assert_eq!(zoomed.scale, 1);
+        assert_eq!((zoomed.x_center, zoomed.z_center), (320, 64));
+        assert!(zoomed.colors.iter().all(|&color| color == 0));
+
+        assert!(zoomed_out(&map(MAX_MAP_SCALE, 0, 0)).is_none());
+        assert!(zoomed_out(&locked(&map(0, 0, 0)).unwrap()).is_none());
+    }
+
+    #[test]
+    fn new_ids_follow_loaded_maps() {
+        let mut store = MapStore::new(vec![(4, map(0, 0, 0))], Some(2));
+        assert_eq!(store.next_id(), 5);
+        store.insert(5, map(1, 0, 0));
+        assert_eq!(store.take_unsaved_last_id(), Some(5));
+        assert_eq!(store.take_unsaved_last_id(), None);
+        assert_eq!(store.take_unsaved().len(), 1);
+        assert!(store.take_unsaved().is_empty());
+    }
+}
@@ -0,0 +1,36 @@
+//! Ticks the [`MovementController`]s of entities
+//! moved by the server, like NPCs.
+
+use base::Position;
+use ecs::{SysResult, SystemExecutor};
+use quill_common::{components::HeadYaw, movement::MovementController};
+
+use crate::Game;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(tick_movement_controllers);
+}
+
+fn tick_movement_controllers(game: &mut Game) -> SysResult {
+    let mut missing_head_yaw = Vec::new();
+    for (entity, (controller, position, head_yaw)) in game
+        .ecs
+        .query::<(&mut MovementController, &mut Position, Option<&mut HeadYaw>)>()
+        .iter()
+    {
+        match head_yaw {
+            Some(head_yaw) => controller.tick(position, &mut head_yaw.0),
+            None => {
+                // The head starts out facing where the body does.
+                let mut head_yaw = position.yaw;
+                controller.tick(position, &mut head_yaw);
+                missing_head_yaw.push((entity, HeadYaw(head_yaw)));
+            }
+        }
+    }
+
+    for (entity, head_yaw) in missing_head_yaw {
+        game.ecs.insert(entity, head_yaw)?;
+    }
+    Ok(())
+}
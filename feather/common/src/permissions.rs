@@ -0,0 +1,57 @@
+//! Permission nodes granted to players.
+//!
+//! Nodes are dot-separated strings like `server.silentjoin`.
+//! A granted node ending in `*` also grants every node
+//! it prefixes, so `server.*` grants `server.silentjoin`
+//! and `*` grants everything.
+
+use ahash::AHashSet;
+
+/// Component storing the permission nodes granted to a player.
+#[derive(Clone, Debug, Default)]
+pub struct Permissions {
+    nodes: AHashSet<String>,
+}
+
+impl Permissions {
+    pub fn new(nodes: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            nodes: nodes.into_iter().collect(),
+        }
+    }
+
+    /// Determines whether `node` is granted.
+    pub fn has(&self, node: &str) -> bool {
+        self.nodes
+            .iter()
+            .any(|granted| match granted.strip_suffix('*') {
+                Some(prefix) => node.starts_with(prefix),
+                None => granted == node,
+            })
+    }
+
+    pub fn grant(&mut self, node: impl Into<String>) {
+        self.nodes.insert(node.into());
+    }
+
+    pub fn revoke(&mut self, node: &str) {
+        self.nodes.remove(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        let permissions = Permissions::new(vec!["server.*".to_owned(), "chat.color".to_owned()]);
+        assert!(permissions.has("server.silentjoin"));
+        assert!(permissions.has("chat.color"));
+        assert!(!permissions.has("chat.colors"));
+        assert!(!permissions.has("world.edit"));
+
+        assert!(Permissions::new(vec!["*".to_owned()]).has("world.edit"));
+        assert!(!Permissions::default().has("server.silentjoin"));
+    }
+}
@@ -0,0 +1,64 @@
+//! Key-value tags plugins attach to entities.
+//!
+//! Tags are stored on the entity rather than in plugin memory,
+//! so they outlive the plugin instance which set them, e.g.
+//! across plugin reloads. Keys are namespaced with the plugin's
+//! identifier so plugins can't overwrite each other's tags.
+
+use std::collections::BTreeMap;
+
+/// Component storing an entity's tags.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PersistentTags(BTreeMap<String, String>);
+
+impl PersistentTags {
+    pub fn get(&self, namespace: &str, key: &str) -> Option<&str> {
+        self.0.get(&namespaced(namespace, key)).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, namespace: &str, key: &str, value: impl Into<String>) {
+        self.0.insert(namespaced(namespace, key), value.into());
+    }
+
+    pub fn remove(&mut self, namespace: &str, key: &str) -> Option<String> {
+        self.0.remove(&namespaced(namespace, key))
+    }
+
+    /// Iterates over the tags as `(namespace:key, value)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn namespaced(namespace: &str, key: &str) -> String {
+    format!("{}:{}", namespace, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_namespaced() {
+        let mut tags = PersistentTags::default();
+        tags.insert("boss_plugin", "phase", "2");
+        tags.insert("other_plugin", "phase", "idle");
+
+        assert_eq!(tags.get("boss_plugin", "phase"), Some("2"));
+        assert_eq!(
+            tags.remove("other_plugin", "phase").as_deref(),
+            Some("idle")
+        );
+        assert_eq!(tags.get("other_plugin", "phase"), None);
+        assert_eq!(
+            tags.iter().collect::<Vec<_>>(),
+            [("boss_plugin:phase", "2")]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Plugin messaging channels.
+//!
+//! Plugin messages (custom payloads) carry data on named
+//! channels like `minecraft:brand` or `bungeecord:main`.
+//! Subsystems subscribe to the channels they handle in the
+//! [`PluginChannels`] resource, and received messages are
+//! passed to every subscriber of their channel.
+//!
+//! Clients announce the channels they listen on with
+//! `minecraft:register` and `minecraft:unregister` messages.
+//! These are tracked in the [`RegisteredChannels`] component,
+//! up to [`MAX_REGISTERED_CHANNELS`] per player.
+
+use ahash::{AHashMap, AHashSet};
+use ecs::{Entity, SysResult};
+
+use crate::{events::PluginMessageEvent, Game};
+
+/// Channel on which a client registers the channels it listens on.
+pub const REGISTER_CHANNEL: &str = "minecraft:register";
+/// Channel on which a client unregisters channels.
+pub const UNREGISTER_CHANNEL: &str = "minecraft:unregister";
+
+/// Maximum number of channels a client may register.
+/// Further registrations are ignored, so a client can't
+/// grow its set of channels without bound.
+pub const MAX_REGISTERED_CHANNELS: usize = 128;
+
+/// Handles a plugin message received from a player.
+pub type ChannelHandler = fn(&mut Game, Entity, &[u8]) -> SysResult;
+
+pub fn register(game: &mut Game) {
+    game.insert_resource(PluginChannels::new());
+}
+
+/// Resource mapping channels to their subscribers.
+#[derive(Default)]
+pub struct PluginChannels {
+    handlers: AHashMap<String, Vec<ChannelHandler>>,
+}
+
+impl PluginChannels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `handler` for each plugin message received on `channel`.
+    pub fn subscribe(&mut self, channel: impl Into<String>, handler: ChannelHandler) {
+        self.handlers
+            .entry(channel.into())
+            .or_default()
+            .push(handler);
+    }
+
+    pub fn is_subscribed(&self, channel: &str) -> bool {
+        self.handlers.contains_key(channel)
+    }
+
+    /// Returns the channels with at least one subscriber,
+    /// which the server registers with clients.
+    pub fn channels(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    fn handlers(&self, channel: &str) -> Vec<ChannelHandler> {
+        self.handlers.get(channel).cloned().unwrap_or_default()
+    }
+}
+
+/// Component storing the channels a player's client listens on.
+#[derive(Clone, Debug, Default)]
+pub struct RegisteredChannels {
+    channels: AHashSet<String>,
+}
+
+impl RegisteredChannels {
+    /// Registers `channel`, returning `false` if
+    /// the limit of registered channels was reached.
+    fn register(&mut self, channel: String) -> bool {
+        if self.channels.len() >= MAX_REGISTERED_CHANNELS && !self.channels.contains(&channel) {
+            return false;
+        }
+        self.channels.insert(channel);
+        true
+    }
+
+    pub fn contains(&self, channel: &str) -> bool {
+        self.channels.contains(channel)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.channels.iter().map(String::as_str)
+    }
+}
+
+/// Component storing the brand a player's client sent
+/// on `minecraft:brand`, like `vanilla` or `fabric`.
+#[derive(Clone, Debug)]
+pub struct ClientBrand(pub String);
+
+/// Encodes channel names as the payload of a
+/// `minecraft:register` or `minecraft:unregister` message.
+pub fn encode_channel_list<'a>(channels: impl IntoIterator<Item = &'a str>) -> Vec<u8> {
+    channels
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join("\0")
+        .into_bytes()
+}
+
+/// Decodes the channel names in the payload of a
+/// `minecraft:register` or `minecraft:unregister` message.
+pub fn decode_channel_list(data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    data.split(|&byte| byte == 0)
+        .filter(|channel| !channel.is_empty())
+        .map(|channel| String::from_utf8_lossy(channel).into_owned())
+}
+
+/// Handles a plugin message sent by `player`.
+pub fn handle_message(game: &mut Game, player: Entity, channel: &str, data: &[u8]) -> SysResult {
+    match channel {
+        REGISTER_CHANNEL => {
+            let mut registered = game.ecs.get_mut::<RegisteredChannels>(player)?;
+            for channel in decode_channel_list(data) {
+                if !registered.register(channel) {
+                    log::debug!(
+                        "Ignoring channels registered past the limit of {}",
+                        MAX_REGISTERED_CHANNELS
+                    );
+                    break;
+                }
+            }
+            return Ok(());
+        }
+        UNREGISTER_CHANNEL => {
+            let mut registered = game.ecs.get_mut::<RegisteredChannels>(player)?;
+            for channel in decode_channel_list(data) {
+                registered.channels.remove(&channel);
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let handlers = game.resources.get::<PluginChannels>()?.handlers(channel);
+    if handlers.is_empty() {
+        log::trace!("Ignoring plugin message on unknown channel {}", channel);
+    }
+    for handler in handlers {
+        handler(game, player, data)?;
+    }
+    Ok(())
+}
+
+/// Sends a plugin message to `player`.
+pub fn send_message(
+    game: &mut Game,
+    player: Entity,
+    channel: impl Into<String>,
+    data: impl Into<Vec<u8>>,
+) -> SysResult {
+    game.ecs.insert_entity_event(
+        player,
+        PluginMessageEvent {
+            channel: channel.into(),
+            data: data.into(),
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_lists() {
+        let encoded = encode_channel_list(vec!["bungeecord:main", "feather:test"]);
+        assert_eq!(encoded, b"bungeecord:main\0feather:test");
+        let decoded: Vec<String> = decode_channel_list(&encoded).collect();
+        assert_eq!(decoded, vec!["bungeecord:main", "feather:test"]);
+        assert_eq!(decode_channel_list(b"\0").count(), 0);
+    }
+
+    #[test]
+    fn register_and_dispatch() {
+        fn handler(game: &mut Game, player: Entity, data: &[u8]) -> SysResult {
+            game.ecs.insert(player, data.to_vec())?;
+            Ok(())
+        }
+
+        let mut game = Game::new();
+        register(&mut game);
+        game.resources
+            .get_mut::<PluginChannels>()
+            .unwrap()
+            .subscribe("feather:test", handler);
+        let player = game.ecs.spawn((RegisteredChannels::default(),));
+
+        handle_message(&mut game, player, REGISTER_CHANNEL, b"a:b\0c:d").unwrap();
+        handle_message(&mut game, player, UNREGISTER_CHANNEL, b"a:b").unwrap();
+        let registered = game.ecs.get::<RegisteredChannels>(player).unwrap();
+        assert!(!registered.contains("a:b"));
+        assert!(registered.contains("c:d"));
+        drop(registered);
+
+        handle_message(&mut game, player, "feather:test", &[1, 2]).unwrap();
+        assert_eq!(*game.ecs.get::<Vec<u8>>(player).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn registered_channels_are_capped() {
+        let mut game = Game::new();
+        register(&mut game);
+        let player = game.ecs.spawn((RegisteredChannels::default(),));
+
+        let channels: Vec<String> = (0..MAX_REGISTERED_CHANNELS + 10)
+            .map(|i| format!("feather:channel{}", i))
+            .collect();
+        let data = encode_channel_list(channels.iter().map(String::as_str));
+        handle_message(&mut game, player, REGISTER_CHANNEL, &data).unwrap();
+        let registered = game.ecs.get::<RegisteredChannels>(player).unwrap();
+        assert_eq!(registered.iter().count(), MAX_REGISTERED_CHANNELS);
+    }
+}
@@ -0,0 +1,269 @@
+//! Recipe books: the recipes each player knows and the state
+//! of their crafting, furnace, blast furnace and smoker books.
+//!
+//! Recipes are unlocked once a player holds one of their
+//! ingredients, as listed in the [`RecipeUnlocks`] resource.
+//! Newly unlocked recipes trigger a [`RecipesUnlockedEvent`].
+//!
+//! The book is stored as `recipeBook` in player data.
+
+use std::collections::BTreeSet;
+
+use ahash::AHashMap;
+use base::{anvil::player::RecipeBookData, Inventory, Item};
+use ecs::{Entity, SysResult, SystemExecutor};
+
+use crate::{events::RecipesUnlockedEvent, Game};
+
+/// Ticks between checks for new ingredients in inventories.
+const UNLOCK_INTERVAL: u64 = 10;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(RecipeUnlocks::default());
+    systems.add_system(unlock_recipes);
+}
+
+/// One of the recipe books of a player.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RecipeBookKind {
+    Crafting,
+    Furnace,
+    BlastFurnace,
+    Smoker,
+}
+
+impl RecipeBookKind {
+    pub const ALL: [RecipeBookKind; 4] = [
+        RecipeBookKind::Crafting,
+        RecipeBookKind::Furnace,
+        RecipeBookKind::BlastFurnace,
+        RecipeBookKind::Smoker,
+    ];
+
+    /// Gets a book from its protocol ID.
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.get(id as usize).copied()
+    }
+
+    pub fn id(self) -> usize {
+        self as usize
+    }
+}
+
+/// Whether a recipe book is open and only shows craftable recipes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BookState {
+    pub open: bool,
+    pub filter_active: bool,
+}
+
+/// Component storing the recipes known by a player.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecipeBook {
+    books: [BookState; 4],
+    known: BTreeSet<String>,
+    /// Known recipes the player hasn't looked at yet.
+    highlighted: BTreeSet<String>,
+}
+
+impl RecipeBook {
+    pub fn from_data(data: &RecipeBookData) -> Self {
+        let state = |open, filter_active| BookState {
+            open,
+            filter_active,
+        };
+        Self {
+            books: [
+                state(data.is_gui_open, data.is_filtering_craftable),
+                state(
+                    data.is_furnace_gui_open,
+                    data.is_furnace_filtering_craftable,
+                ),
+                state(
+                    data.is_blasting_furnace_gui_open,
+                    data.is_blasting_furnace_filtering_craftable,
+                ),
+                state(data.is_smoker_gui_open, data.is_smoker_filtering_craftable),
+            ],
+            known: data.recipes.iter().cloned().collect(),
+            highlighted: data
+                .to_be_displayed
+                .iter()
+                .filter(|recipe| data.recipes.contains(recipe))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn to_data(&self) -> RecipeBookData {
+        let [crafting, furnace, blast_furnace, smoker] = self.books;
+        RecipeBookData {
+            recipes: self.known.iter().cloned().collect(),
+            to_be_displayed: self.highlighted.iter().cloned().collect(),
+            is_gui_open: crafting.open,
+            is_filtering_craftable: crafting.filter_active,
+            is_furnace_gui_open: furnace.open,
+            is_furnace_filtering_craftable: furnace.filter_active,
+            is_blasting_furnace_gui_open: blast_furnace.open,
+            is_blasting_furnace_filtering_craftable: blast_furnace.filter_active,
+            is_smoker_gui_open: smoker.open,
+            is_smoker_filtering_craftable: smoker.filter_active,
+        }
+    }
+
+    /// Gets the states of all books, in protocol ID order.
+    pub fn books(&self) -> [BookState; 4] {
+        self.books
+    }
+
+    pub fn book(&self, kind: RecipeBookKind) -> BookState {
+        self.books[kind.id()]
+    }
+
+    pub fn set_book(&mut self, kind: RecipeBookKind, state: BookState) {
+        self.books[kind.id()] = state;
+    }
+
+    pub fn knows(&self, recipe: &str) -> bool {
+        self.known.contains(recipe)
+    }
+
+    pub fn known(&self) -> impl Iterator<Item = &str> {
+        self.known.iter().map(String::as_str)
+    }
+
+    pub fn highlighted(&self) -> impl Iterator<Item = &str> {
+        self.highlighted.iter().map(String::as_str)
+    }
+
+    /// Adds a recipe, highlighting it as new.
+    /// Returns `false` if it was already known.
+    pub fn unlock(&mut self, recipe: &str) -> bool {
+        if self.knows(recipe) {
+            return false;
+        }
+        self.known.insert(recipe.to_owned());
+        self.highlighted.insert(recipe.to_owned());
+        true
+    }
+
+    /// Stops highlighting a recipe once the player has seen it.
+    pub fn mark_seen(&mut self, recipe: &str) {
+        self.highlighted.remove(recipe);
+    }
+}
+
+/// Resource listing the recipes unlocked by holding each item.
+#[derive(Debug, Default)]
+pub struct RecipeUnlocks {
+    by_ingredient: AHashMap<Item, Vec<String>>,
+}
+
+impl RecipeUnlocks {
+    /// Makes holding any of `ingredients` unlock `recipe`.
+    pub fn add(&mut self, recipe: &str, ingredients: impl IntoIterator<Item = Item>) {
+        for ingredient in ingredients {
+            let recipes = self.by_ingredient.entry(ingredient).or_default();
+            if !recipes.iter().any(|r| r == recipe) {
+                recipes.push(recipe.to_owned());
+            }
+        }
+    }
+
+    /// Gets the recipes unlocked by holding `item`.
+    pub fn recipes_for(&self, item: Item) -> &[String] {
+        self.by_ingredient
+            .get(&item)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_ingredient.is_empty()
+    }
+}
+
+fn unlock_recipes(game: &mut Game) -> SysResult {
+    if game.tick_count % UNLOCK_INTERVAL != 0 {
+        return Ok(());
+    }
+    let unlocks = game.resources.get::<RecipeUnlocks>()?;
+    if unlocks.is_empty() {
+        return Ok(());
+    }
+
+    let mut unlocked: Vec<(Entity, Vec<String>)> = Vec::new();
+    for (player, (inventory, book)) in game.ecs.query::<(&Inventory, &mut RecipeBook)>().iter() {
+        let mut recipes = Vec::new();
+        for stack in inventory.to_vec().into_iter().flatten() {
+            for recipe in unlocks.recipes_for(stack.item) {
+                if book.unlock(recipe) {
+                    recipes.push(recipe.clone());
+                }
+            }
+        }
+        if !recipes.is_empty() {
+            unlocked.push((player, recipes));
+        }
+    }
+    drop(unlocks);
+
+    for (player, recipes) in unlocked {
+        game.ecs
+            .insert_entity_event(player, RecipesUnlockedEvent { recipes })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::ItemStack;
+
+    use super::*;
+
+    #[test]
+    fn holding_an_ingredient_unlocks_its_recipes() {
+        let mut game = Game::new();
+        game.insert_resource(RecipeUnlocks::default());
+        game.resources
+            .get_mut::<RecipeUnlocks>()
+            .unwrap()
+            .add("minecraft:torch", vec![Item::Stick, Item::Coal]);
+
+        let inventory = Inventory::player();
+        *inventory.item(base::Area::Hotbar, 0).unwrap() = Some(ItemStack::new(Item::Coal, 2));
+        let player = game.ecs.spawn((inventory, RecipeBook::default()));
+
+        unlock_recipes(&mut game).unwrap();
+        let events: Vec<Vec<String>> = game
+            .ecs
+            .query::<&RecipesUnlockedEvent>()
+            .iter()
+            .map(|(_, event)| event.recipes.clone())
+            .collect();
+        assert_eq!(events, vec![vec!["minecraft:torch".to_owned()]]);
+
+        let book = game.ecs.get::<RecipeBook>(player).unwrap();
+        assert!(book.knows("minecraft:torch"));
+        assert_eq!(book.highlighted().collect::<Vec<_>>(), ["minecraft:torch"]);
+    }
+
+    #[test]
+    fn book_survives_player_data() {
+        let mut book = RecipeBook::default();
+        book.unlock("minecraft:torch");
+        book.unlock("minecraft:stick");
+        book.mark_seen("minecraft:stick");
+        book.set_book(
+            RecipeBookKind::Smoker,
+            BookState {
+                open: true,
+                filter_active: true,
+            },
+        );
+
+        let data = book.to_data();
+        assert!(data.is_smoker_gui_open && !data.is_gui_open);
+        assert_eq!(RecipeBook::from_data(&data), book);
+    }
+}
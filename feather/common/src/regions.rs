@@ -0,0 +1,298 @@
+//! Protected regions: the server side of claims.
+//!
+//! Each [`World`] stores its [`Regions`]. Plugins define
+//! regions and their flags, and gameplay code asks this module
+//! whether an action is allowed, e.g. with [`can_build`] before
+//! a player breaks a block. Players with [`BYPASS_PERMISSION`]
+//! ignore the build and interact flags.
+
+use std::collections::BTreeMap;
+
+use ahash::AHashMap;
+use anyhow::anyhow;
+use base::{BlockPosition, ChunkPosition, Position};
+use ecs::Entity;
+use quill_common::region::{Region, RegionFlag};
+use uuid::Uuid;
+
+use crate::{events::RegionsChangeEvent, permissions::Permissions, Game, World};
+
+/// Permission to build and interact in any region.
+pub const BYPASS_PERMISSION: &str = "server.regions.bypass";
+
+/// Regions covering more chunks than this aren't added to
+/// the chunk index, but checked for every position instead.
+const MAX_INDEXED_CHUNKS: usize = 1024;
+
+/// The regions of a world, indexed by the chunks they cover.
+#[derive(Debug, Default)]
+pub struct Regions {
+    regions: BTreeMap<String, Region>,
+    /// Names of the regions covering each chunk.
+    chunks: AHashMap<ChunkPosition, Vec<String>>,
+    /// Names of the regions too large for `chunks`.
+    large: Vec<String>,
+}
+
+impl Regions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a region, replacing the region with the same name.
+    pub fn insert(&mut self, region: Region) -> Option<Region> {
+        let old = self.remove(&region.name);
+        if let Some(chunks) = indexed_chunks(&region) {
+            for chunk in chunks {
+                self.chunks
+                    .entry(chunk)
+                    .or_default()
+                    .push(region.name.clone());
+            }
+        } else {
+            self.large.push(region.name.clone());
+        }
+        self.regions.insert(region.name.clone(), region);
+        old
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Region> {
+        let region = self.regions.remove(name)?;
+        if let Some(chunks) = indexed_chunks(&region) {
+            for chunk in chunks {
+                if let Some(names) = self.chunks.get_mut(&chunk) {
+                    names.retain(|n| n != name);
+                    if names.is_empty() {
+                        self.chunks.remove(&chunk);
+                    }
+                }
+            }
+        } else {
+            self.large.retain(|n| n != name);
+        }
+        Some(region)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Region> {
+        self.regions.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Region> + '_ {
+        self.regions.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Iterates over the regions containing `pos`.
+    pub fn at(&self, pos: BlockPosition) -> impl Iterator<Item = &Region> + '_ {
+        let chunk = ChunkPosition::from(pos);
+        self.chunks
+            .get(&chunk)
+            .into_iter()
+            .flatten()
+            .chain(&self.large)
+            .map(move |name| &self.regions[name])
+            .filter(move |region| region.contains(pos))
+    }
+
+    /// Determines whether `flag` is allowed at `pos` for `player`.
+    ///
+    /// The region with the highest priority which sets the
+    /// flag decides; between equal priorities, denying wins.
+    /// Members of a region are exempt from the
+    /// [`Build`](RegionFlag::Build) and
+    /// [`Interact`](RegionFlag::Interact) flags it sets.
+    pub fn allows(&self, pos: BlockPosition, flag: RegionFlag, player: Option<Uuid>) -> bool {
+        let exempt = |region: &Region| {
+            matches!(flag, RegionFlag::Build | RegionFlag::Interact)
+                && player.map_or(false, |player| region.members.contains(&player))
+        };
+        self.at(pos)
+            .filter_map(|region| {
+                let allowed = region.flag(flag)? || exempt(region);
+                Some((region.priority, allowed))
+            })
+            // `false` sorts before `true`.
+            .max_by_key(|&(priority, allowed)| (priority, !allowed))
+            .map_or(true, |(_, allowed)| allowed)
+    }
+}
+
+/// Returns the chunks `region` covers, or `None`
+/// if they're too many to index.
+fn indexed_chunks(region: &Region) -> Option<Vec<ChunkPosition>> {
+    let (min, max) = match region.shape.bounds() {
+        Some(bounds) => bounds,
+        None => return Some(Vec::new()),
+    };
+    let (min, max) = (ChunkPosition::from(min), ChunkPosition::from(max));
+    let count = (max.x - min.x + 1) as usize * (max.z - min.z + 1) as usize;
+    if count > MAX_INDEXED_CHUNKS {
+        return None;
+    }
+    Some(
+        (min.x..=max.x)
+            .flat_map(|x| (min.z..=max.z).map(move |z| ChunkPosition::new(x, z)))
+            .collect(),
+    )
+}
+
+/// Determines whether `player` may break
+/// or place the block at `pos`.
+pub fn can_build(game: &Game, player: Entity, pos: BlockPosition) -> bool {
+    player_allowed(game, player, pos, RegionFlag::Build)
+}
+
+/// Determines whether `player` may use the block at
+/// `pos` or interact with an entity standing there.
+pub fn can_interact(game: &Game, player: Entity, pos: BlockPosition) -> bool {
+    player_allowed(game, player, pos, RegionFlag::Interact)
+}
+
+fn player_allowed(game: &Game, player: Entity, pos: BlockPosition, flag: RegionFlag) -> bool {
+    let bypass = game
+        .ecs
+        .get::<Permissions>(player)
+        .map_or(false, |permissions| permissions.has(BYPASS_PERMISSION));
+    let uuid = game.ecs.get::<Uuid>(player).ok().map(|uuid| *uuid);
+    bypass || game.world_of(player).regions().allows(pos, flag, uuid)
+}
+
+/// Determines whether the regions where `attacker`
+/// and `target` stand allow PvP.
+pub fn pvp_allowed(game: &Game, attacker: Entity, target: Entity) -> bool {
+    let regions = game.world_of(attacker).regions();
+    [attacker, target].iter().all(|&player| {
+        game.ecs.get::<Position>(player).map_or(true, |position| {
+            regions.allows(position.block(), RegionFlag::Pvp, None)
+        })
+    })
+}
+
+/// Determines whether mobs may spawn naturally at `pos`.
+pub fn mob_spawning_allowed(world: &World, pos: BlockPosition) -> bool {
+    world.regions().allows(pos, RegionFlag::MobSpawning, None)
+}
+
+/// Adds a region to the world called `world`, replacing
+/// the region with the same name.
+pub fn define_region(
+    game: &mut Game,
+    world: &str,
+    region: Region,
+) -> anyhow::Result<Option<Region>> {
+    let old = world_named(game, world)?.regions_mut().insert(region);
+    game.ecs.insert_event(RegionsChangeEvent {
+        world: world.to_owned(),
+    });
+    Ok(old)
+}
+
+/// Removes the region called `name` from the world called `world`.
+pub fn remove_region(game: &mut Game, world: &str, name: &str) -> anyhow::Result<Option<Region>> {
+    let removed = world_named(game, world)?.regions_mut().remove(name);
+    if removed.is_some() {
+        game.ecs.insert_event(RegionsChangeEvent {
+            world: world.to_owned(),
+        });
+    }
+    Ok(removed)
+}
+
+fn world_named<'a>(game: &'a mut Game, world: &str) -> anyhow::Result<&'a mut World> {
+    game.world_named_mut(world)
+        .ok_or_else(|| anyhow!("unknown world '{}'", world))
+}
+
+#[cfg(test)]
+mod tests {
+    use quill_common::region::RegionShape;
+
+    use super::*;
+
+    fn cuboid(name: &str, a: (i32, i32, i32), b: (i32, i32, i32)) -> Region {
+        Region::new(
+            name,
+            RegionShape::cuboid(
+                BlockPosition::new(a.0, a.1, a.2),
+                BlockPosition::new(b.0, b.1, b.2),
+            ),
+        )
+    }
+
+    #[test]
+    fn highest_priority_decides() {
+        let mut regions = Regions::new();
+        regions.insert(
+            cuboid("spawn", (-100, 0, -100), (100, 255, 100)).with_flag(RegionFlag::Build, false),
+        );
+        regions.insert(
+            cuboid("market", (0, 0, 0), (20, 255, 20))
+                .with_priority(1)
+                .with_flag(RegionFlag::Build, true),
+        );
+
+        let inside_market = BlockPosition::new(10, 64, 10);
+        let outside_market = BlockPosition::new(-10, 64, 10);
+        let outside_spawn = BlockPosition::new(500, 64, 10);
+        assert!(regions.allows(inside_market, RegionFlag::Build, None));
+        assert!(!regions.allows(outside_market, RegionFlag::Build, None));
+        assert!(regions.allows(outside_spawn, RegionFlag::Build, None));
+        // Flags no region sets are allowed.
+        assert!(regions.allows(outside_market, RegionFlag::Pvp, None));
+    }
+
+    #[test]
+    fn denying_wins_ties() {
+        let mut regions = Regions::new();
+        regions.insert(cuboid("a", (0, 0, 0), (10, 10, 10)).with_flag(RegionFlag::Pvp, true));
+        regions.insert(cuboid("b", (5, 0, 5), (15, 10, 15)).with_flag(RegionFlag::Pvp, false));
+        assert!(regions.allows(BlockPosition::new(2, 5, 2), RegionFlag::Pvp, None));
+        assert!(!regions.allows(BlockPosition::new(7, 5, 7), RegionFlag::Pvp, None));
+    }
+
+    #[test]
+    fn members_may_build() {
+        let owner = Uuid::from_u128(1);
+        let mut regions = Regions::new();
+        regions.insert(
+            cuboid("claim", (0, 0, 0), (15, 255, 15))
+                .with_flag(RegionFlag::Build, false)
+                .with_member(owner),
+        );
+        let pos = BlockPosition::new(3, 64, 3);
+        assert!(regions.allows(pos, RegionFlag::Build, Some(owner)));
+        assert!(!regions.allows(pos, RegionFlag::Build, Some(Uuid::from_u128(2))));
+    }
+
+    #[test]
+    fn large_regions_and_removal() {
+        let mut regions = Regions::new();
+        regions.insert(
+            cuboid("world", (-100_000, 0, -100_000), (100_000, 255, 100_000))
+                .with_flag(RegionFlag::MobSpawning, false),
+        );
+        regions
+            .insert(cuboid("small", (0, 0, 0), (40, 255, 40)).with_flag(RegionFlag::Build, false));
+        let pos = BlockPosition::new(20, 64, 20);
+        assert_eq!(regions.at(pos).count(), 2);
+
+        regions.remove("small");
+        regions.remove("world");
+        assert_eq!(regions.at(pos).count(), 0);
+        assert!(regions.chunks.is_empty());
+        assert!(regions.large.is_empty());
+    }
+
+    #[test]
+    fn replacing_reindexes() {
+        let mut regions = Regions::new();
+        regions.insert(cuboid("claim", (0, 0, 0), (15, 255, 15)));
+        let old = regions.insert(cuboid("claim", (100, 0, 100), (115, 255, 115)));
+        assert!(old.is_some());
+        assert_eq!(regions.at(BlockPosition::new(5, 64, 5)).count(), 0);
+        assert_eq!(regions.at(BlockPosition::new(105, 64, 105)).count(), 1);
+    }
+}
@@ -0,0 +1,136 @@
+//! Server resource packs.
+//!
+//! [`send_resource_pack`] triggers a [`ResourcePackSendEvent`],
+//! which the server answers by sending the pack to the player's
+//! client. The client's responses are passed to [`on_status`],
+//! which triggers a [`ResourcePackStatusEvent`] and kicks players
+//! who decline a required pack.
+
+use ecs::{Entity, SysResult};
+
+use crate::{
+    events::{ResourcePackSendEvent, ResourcePackStatusEvent},
+    kick, Game,
+};
+
+pub fn register(game: &mut Game) {
+    game.insert_resource(ResourcePackPolicy::default());
+}
+
+/// Response of a client to a resource pack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResourcePackStatus {
+    /// The pack was downloaded and applied.
+    Loaded,
+    Declined,
+    FailedDownload,
+    /// The player accepted the pack; the download is starting.
+    Accepted,
+}
+
+impl ResourcePackStatus {
+    /// Gets a status from its protocol ID.
+    pub fn from_id(id: i32) -> Option<Self> {
+        Some(match id {
+            0 => ResourcePackStatus::Loaded,
+            1 => ResourcePackStatus::Declined,
+            2 => ResourcePackStatus::FailedDownload,
+            3 => ResourcePackStatus::Accepted,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether the player won't be using the pack.
+    pub fn is_rejection(self) -> bool {
+        matches!(
+            self,
+            ResourcePackStatus::Declined | ResourcePackStatus::FailedDownload
+        )
+    }
+}
+
+/// Resource deciding what happens to players
+/// who don't use the server resource pack.
+#[derive(Debug, Default)]
+pub struct ResourcePackPolicy {
+    /// Message players are kicked with if they decline or
+    /// fail to download a pack, or `None` to let them play.
+    kick_message: Option<String>,
+}
+
+impl ResourcePackPolicy {
+    /// Requires players to use resource packs
+    /// sent to them, kicking them with `message` otherwise.
+    pub fn require(&mut self, message: impl Into<String>) {
+        self.kick_message = Some(message.into());
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.kick_message.is_some()
+    }
+}
+
+/// Sends the resource pack at `url` to `player`. `hash` is the
+/// SHA-1 of the pack as 40 hexadecimal digits, or empty.
+pub fn send_resource_pack(
+    game: &mut Game,
+    player: Entity,
+    url: impl Into<String>,
+    hash: impl Into<String>,
+) -> SysResult {
+    game.ecs.insert_entity_event(
+        player,
+        ResourcePackSendEvent {
+            url: url.into(),
+            hash: hash.into(),
+        },
+    )?;
+    Ok(())
+}
+
+/// Called when `player`'s client responds to a resource pack.
+pub fn on_status(game: &mut Game, player: Entity, status: ResourcePackStatus) -> SysResult {
+    game.ecs
+        .insert_entity_event(player, ResourcePackStatusEvent { status })?;
+
+    if status.is_rejection() {
+        let kick_message = game
+            .resources
+            .get::<ResourcePackPolicy>()?
+            .kick_message
+            .clone();
+        if let Some(message) = kick_message {
+            kick::kick_player(game, player, message)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::PlayerKickEvent;
+    use base::Text;
+
+    #[test]
+    fn required_pack_kicks_on_rejection() {
+        let mut game = Game::new();
+        register(&mut game);
+        let player = game.ecs.spawn(());
+
+        on_status(&mut game, player, ResourcePackStatus::Declined).unwrap();
+        assert!(game.ecs.get::<PlayerKickEvent>(player).is_err());
+
+        game.resources
+            .get_mut::<ResourcePackPolicy>()
+            .unwrap()
+            .require("This server requires its resource pack");
+        on_status(&mut game, player, ResourcePackStatus::Accepted).unwrap();
+        assert!(game.ecs.get::<PlayerKickEvent>(player).is_err());
+        on_status(&mut game, player, ResourcePackStatus::FailedDownload).unwrap();
+        assert_eq!(
+            game.ecs.get::<PlayerKickEvent>(player).unwrap().reason,
+            Text::from("This server requires its resource pack")
+        );
+    }
+}
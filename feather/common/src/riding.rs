@@ -0,0 +1,34 @@
+//! Entities riding other entities, like a skeleton
+//! riding a spider.
+//!
+//! A rider has a [`Vehicle`] component pointing at the
+//! entity it rides, which lists its riders in [`Passengers`].
+
+use ecs::{Entity, SysResult};
+
+use crate::Game;
+
+/// Component of an entity riding another entity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Vehicle(pub Entity);
+
+/// Component listing the entities riding an
+/// entity, in the order they mounted it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Passengers(pub Vec<Entity>);
+
+/// Makes `passenger` ride `vehicle`.
+pub fn mount(game: &mut Game, passenger: Entity, vehicle: Entity) -> SysResult {
+    game.ecs.insert(passenger, Vehicle(vehicle))?;
+    let mounted = match game.ecs.get_mut::<Passengers>(vehicle) {
+        Ok(mut passengers) => {
+            passengers.0.push(passenger);
+            true
+        }
+        Err(_) => false,
+    };
+    if !mounted {
+        game.ecs.insert(vehicle, Passengers(vec![passenger]))?;
+    }
+    Ok(())
+}
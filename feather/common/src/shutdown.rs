@@ -0,0 +1,286 @@
+//! Stopping and restarting the server from within the game.
+//!
+//! `/stop` stops the server right away, and `/restart in <duration>`
+//! schedules a restart, broadcasting warnings as it approaches.
+//! The [`Shutdown`] resource records the request; the server's
+//! main loop checks it after each tick and then shuts down gracefully.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use base::Text;
+use ecs::{Entity, SysResult, SystemExecutor};
+
+use crate::{
+    chat::ChatKind,
+    commands::{check_permission, CommandRegistry},
+    Game,
+};
+
+/// Why the server is stopping.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Stop,
+    Restart,
+}
+
+/// Resource controlling when the server stops.
+#[derive(Debug)]
+pub struct Shutdown {
+    requested: Option<StopReason>,
+    scheduled_restart: Option<ScheduledRestart>,
+    /// Time before a scheduled restart at which to
+    /// broadcast a warning, longest first.
+    warnings: Vec<Duration>,
+}
+
+#[derive(Debug)]
+struct ScheduledRestart {
+    at: Instant,
+    /// Index into `warnings` of the next warning to broadcast.
+    next_warning: usize,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new(
+            [300, 60, 30, 10, 5, 4, 3, 2, 1]
+                .iter()
+                .map(|&secs| Duration::from_secs(secs))
+                .collect(),
+        )
+    }
+}
+
+impl Shutdown {
+    pub fn new(warnings: Vec<Duration>) -> Self {
+        let mut this = Self {
+            requested: None,
+            scheduled_restart: None,
+            warnings: Vec::new(),
+        };
+        this.set_warnings(warnings);
+        this
+    }
+
+    /// Sets the times before a scheduled restart at which
+    /// a warning is broadcast.
+    pub fn set_warnings(&mut self, mut warnings: Vec<Duration>) {
+        warnings.sort_unstable_by(|a, b| b.cmp(a));
+        warnings.dedup();
+        self.warnings = warnings;
+    }
+
+    /// Asks the server to stop after the current tick.
+    pub fn request(&mut self, reason: StopReason) {
+        self.requested.get_or_insert(reason);
+    }
+
+    /// Returns why the server should stop, if it should.
+    pub fn requested(&self) -> Option<StopReason> {
+        self.requested
+    }
+
+    /// Schedules a restart `delay` after `now`,
+    /// replacing any restart already scheduled.
+    pub fn schedule_restart(&mut self, now: Instant, delay: Duration) {
+        // Skip warnings for times already passed.
+        let next_warning = self
+            .warnings
+            .iter()
+            .position(|&warning| warning < delay)
+            .unwrap_or_else(|| self.warnings.len());
+        self.scheduled_restart = Some(ScheduledRestart {
+            at: now + delay,
+            next_warning,
+        });
+    }
+
+    /// Cancels the scheduled restart. Returns whether one was scheduled.
+    pub fn cancel_restart(&mut self) -> bool {
+        self.scheduled_restart.take().is_some()
+    }
+
+    /// Time left until the scheduled restart, if any.
+    pub fn time_until_restart(&self, now: Instant) -> Option<Duration> {
+        self.scheduled_restart
+            .as_ref()
+            .map(|restart| restart.at.saturating_duration_since(now))
+    }
+
+    /// Advances the scheduled restart. Returns the time
+    /// remaining if a warning should be broadcast.
+    fn poll(&mut self, now: Instant) -> Option<Duration> {
+        let restart = self.scheduled_restart.as_mut()?;
+        let remaining = restart.at.saturating_duration_since(now);
+        if remaining == Duration::from_secs(0) {
+            self.scheduled_restart = None;
+            self.request(StopReason::Restart);
+            return None;
+        }
+
+        let mut warning = None;
+        while let Some(&next) = self.warnings.get(restart.next_warning) {
+            if remaining > next {
+                break;
+            }
+            warning = Some(next);
+            restart.next_warning += 1;
+        }
+        warning
+    }
+}
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(Shutdown::default());
+    let mut commands = game
+        .resources
+        .get_mut::<CommandRegistry>()
+        .expect("commands must be registered first");
+    commands.register("stop", stop_command);
+    commands.register("restart", restart_command);
+    systems.add_system(tick_scheduled_restart);
+}
+
+/// Permission node required for `/stop`.
+pub const STOP_PERMISSION: &str = "feather.stop";
+
+/// Permission node required for `/restart`.
+pub const RESTART_PERMISSION: &str = "feather.restart";
+
+/// `/stop`
+fn stop_command(game: &mut Game, sender: Entity, _args: &[&str]) -> anyhow::Result<Text> {
+    check_permission(game, sender, STOP_PERMISSION)?;
+    game.resources
+        .get_mut::<Shutdown>()?
+        .request(StopReason::Stop);
+    Ok(Text::translate_with(
+        "commands.stop.stopping",
+        Vec::<Text>::new(),
+    ))
+}
+
+/// `/restart in <duration>`, `/restart now`, or `/restart cancel`
+fn restart_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    check_permission(game, sender, RESTART_PERMISSION)?;
+    let mut shutdown = game.resources.get_mut::<Shutdown>()?;
+    match args {
+        ["in", delay] => {
+            let delay = parse_duration(delay)?;
+            shutdown.schedule_restart(Instant::now(), delay);
+            game.broadcast_chat(
+                ChatKind::System,
+                format!("The server will restart in {}", format_duration(delay)),
+            );
+            Ok(Text::of("Restart scheduled"))
+        }
+        ["now"] => {
+            shutdown.request(StopReason::Restart);
+            Ok(Text::of("Restarting the server"))
+        }
+        ["cancel"] => {
+            if !shutdown.cancel_restart() {
+                bail!("no restart is scheduled");
+            }
+            game.broadcast_chat(ChatKind::System, "The scheduled restart was cancelled");
+            Ok(Text::of("Restart cancelled"))
+        }
+        _ => bail!("usage: /restart in <duration> | now | cancel"),
+    }
+}
+
+fn tick_scheduled_restart(game: &mut Game) -> SysResult {
+    let warning = game.resources.get_mut::<Shutdown>()?.poll(Instant::now());
+    if let Some(remaining) = warning {
+        game.broadcast_chat(
+            ChatKind::System,
+            format!("The server will restart in {}", format_duration(remaining)),
+        );
+    }
+    Ok(())
+}
+
+/// Parses a duration like `90`, `90s`, `5m`, or `1h`.
+/// Plain numbers are seconds.
+pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid duration '{}'", s))?;
+    let secs = match unit {
+        "s" => number,
+        "m" => number * 60,
+        "h" => number * 60 * 60,
+        _ => bail!("invalid duration unit '{}': expected s, m, or h", unit),
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (amount, unit) = if secs >= 3600 && secs % 3600 == 0 {
+        (secs / 3600, "hour")
+    } else if secs >= 60 && secs % 60 == 0 {
+        (secs / 60, "minute")
+    } else {
+        (secs, "second")
+    };
+    format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn warns_then_restarts() {
+        let mut shutdown = Shutdown::new(vec![secs(10), secs(60), secs(5)]);
+        let start = Instant::now();
+        shutdown.schedule_restart(start, secs(30));
+
+        assert_eq!(shutdown.poll(start), None);
+        assert_eq!(shutdown.poll(start + secs(20)), Some(secs(10)));
+        assert_eq!(shutdown.poll(start + secs(21)), None);
+        // Warnings missed between polls are merged.
+        assert_eq!(shutdown.poll(start + secs(29)), Some(secs(5)));
+        assert_eq!(shutdown.requested(), None);
+
+        assert_eq!(shutdown.poll(start + secs(30)), None);
+        assert_eq!(shutdown.requested(), Some(StopReason::Restart));
+        assert_eq!(shutdown.time_until_restart(start), None);
+    }
+
+    #[test]
+    fn cancel_restart() {
+        let mut shutdown = Shutdown::default();
+        let start = Instant::now();
+        shutdown.schedule_restart(start, secs(1));
+        assert!(shutdown.cancel_restart());
+        assert_eq!(shutdown.poll(start + secs(2)), None);
+        assert_eq!(shutdown.requested(), None);
+        assert!(!shutdown.cancel_restart());
+    }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("90").unwrap(), secs(90));
+        assert_eq!(parse_duration("5m").unwrap(), secs(300));
+        assert_eq!(parse_duration("1h").unwrap(), secs(3600));
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(format_duration(secs(1)), "1 second");
+        assert_eq!(format_duration(secs(120)), "2 minutes");
+        assert_eq!(format_duration(secs(90)), "90 seconds");
+    }
+}
@@ -0,0 +1,196 @@
+//! Smithing tables: upgrading diamond gear to netherite.
+//!
+//! The upgraded item keeps the damage, name and enchantments
+//! of the base item. Taking it consumes the base item and
+//! one netherite ingot.
+
+use base::{Area, BlockPosition, Inventory, Item, ItemStack};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult};
+
+use crate::{
+    interactable::InteractableRegistry,
+    window::{self, BackingWindow, Window},
+    Game,
+};
+
+pub fn register(game: &mut Game) {
+    game.resources
+        .get_mut::<InteractableRegistry>()
+        .expect("interactable registry not registered")
+        .register(BlockKind::SmithingTable);
+}
+
+/// A smithing recipe, combining a base item
+/// and an addition into an upgraded item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SmithingRecipe {
+    pub base: Item,
+    pub addition: Item,
+    pub result: Item,
+}
+
+impl SmithingRecipe {
+    /// Gets the ID of this recipe, named like vanilla's.
+    pub fn id(&self) -> String {
+        format!("minecraft:{}_smithing", self.result.name())
+    }
+
+    /// Upgrades `base`, keeping its damage and metadata.
+    pub fn upgrade(&self, base: &ItemStack) -> ItemStack {
+        ItemStack {
+            item: self.result,
+            count: 1,
+            ..base.clone()
+        }
+    }
+}
+
+const fn netherite_upgrade(base: Item, result: Item) -> SmithingRecipe {
+    SmithingRecipe {
+        base,
+        addition: Item::NetheriteIngot,
+        result,
+    }
+}
+
+/// The smithing recipes of vanilla 1.16.
+pub static SMITHING_RECIPES: &[SmithingRecipe] = &[
+    netherite_upgrade(Item::DiamondSword, Item::NetheriteSword),
+    netherite_upgrade(Item::DiamondShovel, Item::NetheriteShovel),
+    netherite_upgrade(Item::DiamondPickaxe, Item::NetheritePickaxe),
+    netherite_upgrade(Item::DiamondAxe, Item::NetheriteAxe),
+    netherite_upgrade(Item::DiamondHoe, Item::NetheriteHoe),
+    netherite_upgrade(Item::DiamondHelmet, Item::NetheriteHelmet),
+    netherite_upgrade(Item::DiamondChestplate, Item::NetheriteChestplate),
+    netherite_upgrade(Item::DiamondLeggings, Item::NetheriteLeggings),
+    netherite_upgrade(Item::DiamondBoots, Item::NetheriteBoots),
+];
+
+/// Finds the recipe combining `base` and `addition`.
+pub fn find_recipe(base: Item, addition: Item) -> Option<&'static SmithingRecipe> {
+    SMITHING_RECIPES
+        .iter()
+        .find(|recipe| recipe.base == base && recipe.addition == addition)
+}
+
+/// Component for a player who has a smithing table open.
+#[derive(Clone, Debug)]
+pub struct OpenSmithingTable {
+    pub position: BlockPosition,
+    /// Result put in the output slot, until the player takes it.
+    output: Option<ItemStack>,
+}
+
+/// Returns whether the block at `position` is a smithing table.
+pub fn is_smithing_table(game: &Game, position: BlockPosition) -> bool {
+    game.block(position)
+        .map_or(false, |block| block.kind() == BlockKind::SmithingTable)
+}
+
+/// Opens the smithing table at `position` for `player`.
+pub fn open_smithing_table(game: &mut Game, player: Entity, position: BlockPosition) -> SysResult {
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let window = Window::new(BackingWindow::Smithing {
+        smithing_table: Inventory::smithing_table(),
+        player: inventory,
+    });
+    game.ecs.insert(player, window)?;
+    game.ecs.insert(
+        player,
+        OpenSmithingTable {
+            position,
+            output: None,
+        },
+    )?;
+    Ok(())
+}
+
+/// Returns the slots of the player's smithing window.
+fn table_inventory(game: &Game, player: Entity) -> anyhow::Result<Inventory> {
+    match game.ecs.get::<Window>(player)?.inner() {
+        BackingWindow::Smithing { smithing_table, .. } => Ok(smithing_table.new_handle()),
+        _ => anyhow::bail!("player has no smithing window open"),
+    }
+}
+
+/// Updates the player's smithing table after they clicked
+/// in its window, consuming the inputs if they took the
+/// result and filling the output slot again.
+pub fn update(game: &mut Game, player: Entity) -> SysResult {
+    let table = table_inventory(game, player)?;
+    let mut open = game.ecs.get_mut::<OpenSmithingTable>(player)?;
+    let mut base = table
+        .item(Area::SmithingBase, 0)
+        .expect("smithing table has a base slot");
+    let mut addition = table
+        .item(Area::SmithingAddition, 0)
+        .expect("smithing table has an addition slot");
+    let mut output = table
+        .item(Area::SmithingOutput, 0)
+        .expect("smithing table has an output slot");
+
+    if open.output.is_some() && *output != open.output {
+        window::consume_one(&mut base);
+        window::consume_one(&mut addition);
+        open.output = None;
+    }
+
+    let result = match (base.as_ref(), addition.as_ref()) {
+        (Some(base), Some(addition)) => {
+            find_recipe(base.item, addition.item).map(|recipe| recipe.upgrade(base))
+        }
+        _ => None,
+    };
+    // Leave items the player put in the output slot.
+    if *output == open.output || output.is_none() {
+        *output = result.clone();
+        open.output = result;
+    }
+    Ok(())
+}
+
+/// Closes the player's smithing table, returning the
+/// items in it to the player's inventory.
+pub fn close_smithing_table(game: &mut Game, player: Entity) -> SysResult {
+    let table = table_inventory(game, player)?;
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let open = game.ecs.remove::<OpenSmithingTable>(player)?;
+    game.ecs.insert(
+        player,
+        Window::new(BackingWindow::Player { player: inventory }),
+    )?;
+
+    let mut items: Vec<ItemStack> = [Area::SmithingBase, Area::SmithingAddition]
+        .iter()
+        .filter_map(|&area| table.item(area, 0).and_then(|mut item| item.take()))
+        .collect();
+    let output = table
+        .item(Area::SmithingOutput, 0)
+        .and_then(|mut item| item.take());
+    if output != open.output {
+        items.extend(output);
+    }
+    window::return_items(game, player, items)
+}
+
+#[cfg(test)]
+mod tests {
+    use base::ItemStackMeta;
+
+    use super::*;
+
+    #[test]
+    fn upgrading_keeps_damage_and_meta() {
+        let mut sword = ItemStack::new(Item::DiamondSword, 1);
+        sword.damage = Some(120);
+        sword.meta = Some(ItemStackMeta::default());
+
+        let recipe = find_recipe(Item::DiamondSword, Item::NetheriteIngot).unwrap();
+        let upgraded = recipe.upgrade(&sword);
+        assert_eq!(upgraded.item, Item::NetheriteSword);
+        assert_eq!(upgraded.damage, Some(120));
+        assert_eq!(upgraded.meta, sword.meta);
+        assert!(find_recipe(Item::IronSword, Item::NetheriteIngot).is_none());
+    }
+}
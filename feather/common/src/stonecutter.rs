@@ -0,0 +1,253 @@
+//! Stonecutters: cutting blocks into slabs, stairs,
+//! walls and the other shapes made from them.
+//!
+//! The client lists the recipes for the item in the input slot
+//! and sends the index of the one the player selects, so the
+//! server lists them in the same order, see [`recipes_for`].
+//! Taking the result consumes one input item.
+
+use base::{Area, BlockPosition, Inventory, Item, ItemStack};
+use blocks::BlockKind;
+use ecs::{Entity, SysResult};
+
+use crate::{
+    interactable::InteractableRegistry,
+    window::{self, BackingWindow, Window},
+    Game,
+};
+
+mod recipes;
+
+pub use recipes::STONECUTTING_RECIPES;
+
+pub fn register(game: &mut Game) {
+    game.resources
+        .get_mut::<InteractableRegistry>()
+        .expect("interactable registry not registered")
+        .register(BlockKind::Stonecutter);
+}
+
+/// A stonecutter recipe, turning one item into `count` of another.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StonecuttingRecipe {
+    pub ingredient: Item,
+    pub result: Item,
+    pub count: u32,
+}
+
+impl StonecuttingRecipe {
+    /// Gets the ID of this recipe, named like vanilla's.
+    pub fn id(&self) -> String {
+        format!(
+            "minecraft:{}_from_{}_stonecutting",
+            self.result.name(),
+            self.ingredient.name()
+        )
+    }
+
+    pub fn result(&self) -> ItemStack {
+        ItemStack::new(self.result, self.count)
+    }
+}
+
+/// Returns the recipes cutting `item`, in the order the client lists them.
+///
+/// The client sorts them by the translation key of their result.
+/// All results are blocks, whose keys only differ by name.
+pub fn recipes_for(item: Item) -> Vec<&'static StonecuttingRecipe> {
+    let mut recipes: Vec<_> = STONECUTTING_RECIPES
+        .iter()
+        .filter(|recipe| recipe.ingredient == item)
+        .collect();
+    recipes.sort_by_key(|recipe| recipe.result.name());
+    recipes
+}
+
+/// Component for a player who has a stonecutter open.
+#[derive(Clone, Debug)]
+pub struct OpenStonecutter {
+    pub position: BlockPosition,
+    /// Index of the selected recipe in the list of [`recipes_for`]
+    /// the input item, or `None` if no recipe is selected.
+    pub selected: Option<usize>,
+    /// Item the recipe list was made for.
+    input: Option<Item>,
+    /// Result put in the output slot, until the player takes it.
+    output: Option<ItemStack>,
+}
+
+/// Returns whether the block at `position` is a stonecutter.
+pub fn is_stonecutter(game: &Game, position: BlockPosition) -> bool {
+    game.block(position)
+        .map_or(false, |block| block.kind() == BlockKind::Stonecutter)
+}
+
+/// Opens the stonecutter at `position` for `player`.
+pub fn open_stonecutter(game: &mut Game, player: Entity, position: BlockPosition) -> SysResult {
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let window = Window::new(BackingWindow::Stonecutter {
+        stonecutter: Inventory::stonecutter(),
+        player: inventory,
+    });
+    game.ecs.insert(player, window)?;
+    game.ecs.insert(
+        player,
+        OpenStonecutter {
+            position,
+            selected: None,
+            input: None,
+            output: None,
+        },
+    )?;
+    Ok(())
+}
+
+/// Returns the input and output slots of the player's stonecutter window.
+fn cutter_inventory(game: &Game, player: Entity) -> anyhow::Result<Inventory> {
+    match game.ecs.get::<Window>(player)?.inner() {
+        BackingWindow::Stonecutter { stonecutter, .. } => Ok(stonecutter.new_handle()),
+        _ => anyhow::bail!("player has no stonecutter window open"),
+    }
+}
+
+/// Selects the recipe at `index` in the list for the input item.
+pub fn select_recipe(game: &mut Game, player: Entity, index: usize) -> SysResult {
+    let cutter = cutter_inventory(game, player)?;
+    let input = cutter
+        .item(Area::StonecutterInput, 0)
+        .and_then(|item| item.clone());
+    let recipes = input.map_or_else(Vec::new, |input| recipes_for(input.item));
+    if index >= recipes.len() {
+        anyhow::bail!("player selected a stonecutter recipe which isn't listed");
+    }
+
+    game.ecs.get_mut::<OpenStonecutter>(player)?.selected = Some(index);
+    update(game, player)
+}
+
+/// Updates the player's stonecutter after they clicked
+/// in its window, consuming an input item if they took
+/// the result and filling the output slot again.
+pub fn update(game: &mut Game, player: Entity) -> SysResult {
+    let cutter = cutter_inventory(game, player)?;
+    let mut open = game.ecs.get_mut::<OpenStonecutter>(player)?;
+    let mut input = cutter
+        .item(Area::StonecutterInput, 0)
+        .expect("stonecutter has an input slot");
+    let mut output = cutter
+        .item(Area::StonecutterOutput, 0)
+        .expect("stonecutter has an output slot");
+
+    if open.output.is_some() && *output != open.output {
+        window::consume_one(&mut input);
+        open.output = None;
+    }
+
+    let input_item = input.as_ref().map(|stack| stack.item);
+    if input_item != open.input {
+        open.input = input_item;
+        open.selected = None;
+    }
+
+    let result = match (input_item, open.selected) {
+        (Some(item), Some(selected)) => recipes_for(item)
+            .get(selected)
+            .map(|recipe| recipe.result()),
+        _ => None,
+    };
+    // Leave items the player put in the output slot.
+    if *output == open.output || output.is_none() {
+        *output = result.clone();
+        open.output = result;
+    }
+    Ok(())
+}
+
+/// Closes the player's stonecutter, returning the
+/// input item to the player's inventory.
+pub fn close_stonecutter(game: &mut Game, player: Entity) -> SysResult {
+    let cutter = cutter_inventory(game, player)?;
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let open = game.ecs.remove::<OpenStonecutter>(player)?;
+    game.ecs.insert(
+        player,
+        Window::new(BackingWindow::Player { player: inventory }),
+    )?;
+
+    let mut items = Vec::new();
+    items.extend(
+        cutter
+            .item(Area::StonecutterInput, 0)
+            .and_then(|mut item| item.take()),
+    );
+    let output = cutter
+        .item(Area::StonecutterOutput, 0)
+        .and_then(|mut item| item.take());
+    if output != open.output {
+        items.extend(output);
+    }
+    window::return_items(game, player, items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recipes_are_listed_like_the_client() {
+        let results: Vec<Item> = recipes_for(Item::Stone)
+            .iter()
+            .map(|recipe| recipe.result)
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                Item::ChiseledStoneBricks,
+                Item::StoneBrickSlab,
+                Item::StoneBrickStairs,
+                Item::StoneBrickWall,
+                Item::StoneBricks,
+                Item::StoneSlab,
+                Item::StoneStairs,
+            ]
+        );
+        assert!(recipes_for(Item::Dirt).is_empty());
+    }
+
+    #[test]
+    fn taking_the_result_consumes_an_input() {
+        let mut game = Game::new();
+        let player = game.ecs.spawn((Inventory::player(),));
+        open_stonecutter(&mut game, player, BlockPosition::new(0, 64, 0)).unwrap();
+        let cutter = cutter_inventory(&game, player).unwrap();
+        *cutter.item(Area::StonecutterInput, 0).unwrap() = Some(ItemStack::new(Item::Stone, 2));
+
+        update(&mut game, player).unwrap();
+        select_recipe(&mut game, player, 5).unwrap();
+        assert_eq!(
+            *cutter.item(Area::StonecutterOutput, 0).unwrap(),
+            Some(ItemStack::new(Item::StoneSlab, 2))
+        );
+
+        cutter.item(Area::StonecutterOutput, 0).unwrap().take();
+        update(&mut game, player).unwrap();
+        assert_eq!(
+            *cutter.item(Area::StonecutterInput, 0).unwrap(),
+            Some(ItemStack::new(Item::Stone, 1))
+        );
+        assert_eq!(
+            *cutter.item(Area::StonecutterOutput, 0).unwrap(),
+            Some(ItemStack::new(Item::StoneSlab, 2))
+        );
+
+        // A different input clears the selection.
+        *cutter.item(Area::StonecutterInput, 0).unwrap() =
+            Some(ItemStack::new(Item::Cobblestone, 1));
+        update(&mut game, player).unwrap();
+        assert_eq!(
+            game.ecs.get::<OpenStonecutter>(player).unwrap().selected,
+            None
+        );
+        assert_eq!(*cutter.item(Area::StonecutterOutput, 0).unwrap(), None);
+    }
+}
@@ -0,0 +1,163 @@
+use base::Item;
+
+use super::StonecuttingRecipe;
+
+const fn recipe(ingredient: Item, result: Item, count: u32) -> StonecuttingRecipe {
+    StonecuttingRecipe {
+        ingredient,
+        result,
+        count,
+    }
+}
+
+/// The stonecutter recipes of vanilla 1.16.
+pub static STONECUTTING_RECIPES: &[StonecuttingRecipe] = &[
+    recipe(Item::Stone, Item::StoneSlab, 2),
+    recipe(Item::Stone, Item::StoneStairs, 1),
+    recipe(Item::Stone, Item::StoneBricks, 1),
+    recipe(Item::Stone, Item::StoneBrickSlab, 2),
+    recipe(Item::Stone, Item::StoneBrickStairs, 1),
+    recipe(Item::Stone, Item::StoneBrickWall, 1),
+    recipe(Item::Stone, Item::ChiseledStoneBricks, 1),
+    recipe(Item::StoneBricks, Item::StoneBrickSlab, 2),
+    recipe(Item::StoneBricks, Item::StoneBrickStairs, 1),
+    recipe(Item::StoneBricks, Item::StoneBrickWall, 1),
+    recipe(Item::StoneBricks, Item::ChiseledStoneBricks, 1),
+    recipe(Item::Cobblestone, Item::CobblestoneSlab, 2),
+    recipe(Item::Cobblestone, Item::CobblestoneStairs, 1),
+    recipe(Item::Cobblestone, Item::CobblestoneWall, 1),
+    recipe(Item::MossyCobblestone, Item::MossyCobblestoneSlab, 2),
+    recipe(Item::MossyCobblestone, Item::MossyCobblestoneStairs, 1),
+    recipe(Item::MossyCobblestone, Item::MossyCobblestoneWall, 1),
+    recipe(Item::MossyStoneBricks, Item::MossyStoneBrickSlab, 2),
+    recipe(Item::MossyStoneBricks, Item::MossyStoneBrickStairs, 1),
+    recipe(Item::MossyStoneBricks, Item::MossyStoneBrickWall, 1),
+    recipe(Item::SmoothStone, Item::SmoothStoneSlab, 2),
+    recipe(Item::Andesite, Item::AndesiteSlab, 2),
+    recipe(Item::Andesite, Item::AndesiteStairs, 1),
+    recipe(Item::Andesite, Item::AndesiteWall, 1),
+    recipe(Item::Andesite, Item::PolishedAndesite, 1),
+    recipe(Item::Andesite, Item::PolishedAndesiteSlab, 2),
+    recipe(Item::Andesite, Item::PolishedAndesiteStairs, 1),
+    recipe(Item::PolishedAndesite, Item::PolishedAndesiteSlab, 2),
+    recipe(Item::PolishedAndesite, Item::PolishedAndesiteStairs, 1),
+    recipe(Item::Diorite, Item::DioriteSlab, 2),
+    recipe(Item::Diorite, Item::DioriteStairs, 1),
+    recipe(Item::Diorite, Item::DioriteWall, 1),
+    recipe(Item::Diorite, Item::PolishedDiorite, 1),
+    recipe(Item::Diorite, Item::PolishedDioriteSlab, 2),
+    recipe(Item::Diorite, Item::PolishedDioriteStairs, 1),
+    recipe(Item::PolishedDiorite, Item::PolishedDioriteSlab, 2),
+    recipe(Item::PolishedDiorite, Item::PolishedDioriteStairs, 1),
+    recipe(Item::Granite, Item::GraniteSlab, 2),
+    recipe(Item::Granite, Item::GraniteStairs, 1),
+    recipe(Item::Granite, Item::GraniteWall, 1),
+    recipe(Item::Granite, Item::PolishedGranite, 1),
+    recipe(Item::Granite, Item::PolishedGraniteSlab, 2),
+    recipe(Item::Granite, Item::PolishedGraniteStairs, 1),
+    recipe(Item::PolishedGranite, Item::PolishedGraniteSlab, 2),
+    recipe(Item::PolishedGranite, Item::PolishedGraniteStairs, 1),
+    recipe(Item::Sandstone, Item::SandstoneSlab, 2),
+    recipe(Item::Sandstone, Item::SandstoneStairs, 1),
+    recipe(Item::Sandstone, Item::SandstoneWall, 1),
+    recipe(Item::Sandstone, Item::CutSandstone, 1),
+    recipe(Item::Sandstone, Item::CutSandstoneSlab, 2),
+    recipe(Item::Sandstone, Item::ChiseledSandstone, 1),
+    recipe(Item::CutSandstone, Item::CutSandstoneSlab, 2),
+    recipe(Item::SmoothSandstone, Item::SmoothSandstoneSlab, 2),
+    recipe(Item::SmoothSandstone, Item::SmoothSandstoneStairs, 1),
+    recipe(Item::RedSandstone, Item::RedSandstoneSlab, 2),
+    recipe(Item::RedSandstone, Item::RedSandstoneStairs, 1),
+    recipe(Item::RedSandstone, Item::RedSandstoneWall, 1),
+    recipe(Item::RedSandstone, Item::CutRedSandstone, 1),
+    recipe(Item::RedSandstone, Item::CutRedSandstoneSlab, 2),
+    recipe(Item::RedSandstone, Item::ChiseledRedSandstone, 1),
+    recipe(Item::CutRedSandstone, Item::CutRedSandstoneSlab, 2),
+    recipe(Item::SmoothRedSandstone, Item::SmoothRedSandstoneSlab, 2),
+    recipe(Item::SmoothRedSandstone, Item::SmoothRedSandstoneStairs, 1),
+    recipe(Item::Bricks, Item::BrickSlab, 2),
+    recipe(Item::Bricks, Item::BrickStairs, 1),
+    recipe(Item::Bricks, Item::BrickWall, 1),
+    recipe(Item::NetherBricks, Item::NetherBrickSlab, 2),
+    recipe(Item::NetherBricks, Item::NetherBrickStairs, 1),
+    recipe(Item::NetherBricks, Item::NetherBrickWall, 1),
+    recipe(Item::RedNetherBricks, Item::RedNetherBrickSlab, 2),
+    recipe(Item::RedNetherBricks, Item::RedNetherBrickStairs, 1),
+    recipe(Item::RedNetherBricks, Item::RedNetherBrickWall, 1),
+    recipe(Item::Prismarine, Item::PrismarineSlab, 2),
+    recipe(Item::Prismarine, Item::PrismarineStairs, 1),
+    recipe(Item::Prismarine, Item::PrismarineWall, 1),
+    recipe(Item::PrismarineBricks, Item::PrismarineBrickSlab, 2),
+    recipe(Item::PrismarineBricks, Item::PrismarineBrickStairs, 1),
+    recipe(Item::DarkPrismarine, Item::DarkPrismarineSlab, 2),
+    recipe(Item::DarkPrismarine, Item::DarkPrismarineStairs, 1),
+    recipe(Item::PurpurBlock, Item::PurpurSlab, 2),
+    recipe(Item::PurpurBlock, Item::PurpurStairs, 1),
+    recipe(Item::PurpurBlock, Item::PurpurPillar, 1),
+    recipe(Item::QuartzBlock, Item::QuartzSlab, 2),
+    recipe(Item::QuartzBlock, Item::QuartzStairs, 1),
+    recipe(Item::QuartzBlock, Item::QuartzPillar, 1),
+    recipe(Item::QuartzBlock, Item::ChiseledQuartzBlock, 1),
+    recipe(Item::QuartzBlock, Item::QuartzBricks, 1),
+    recipe(Item::SmoothQuartz, Item::SmoothQuartzSlab, 2),
+    recipe(Item::SmoothQuartz, Item::SmoothQuartzStairs, 1),
+    recipe(Item::EndStone, Item::EndStoneBricks, 1),
+    recipe(Item::EndStone, Item::EndStoneBrickSlab, 2),
+    recipe(Item::EndStone, Item::EndStoneBrickStairs, 1),
+    recipe(Item::EndStone, Item::EndStoneBrickWall, 1),
+    recipe(Item::EndStoneBricks, Item::EndStoneBrickSlab, 2),
+    recipe(Item::EndStoneBricks, Item::EndStoneBrickStairs, 1),
+    recipe(Item::EndStoneBricks, Item::EndStoneBrickWall, 1),
+    recipe(Item::Blackstone, Item::BlackstoneSlab, 2),
+    recipe(Item::Blackstone, Item::BlackstoneStairs, 1),
+    recipe(Item::Blackstone, Item::BlackstoneWall, 1),
+    recipe(Item::Blackstone, Item::PolishedBlackstone, 1),
+    recipe(Item::Blackstone, Item::PolishedBlackstoneSlab, 2),
+    recipe(Item::Blackstone, Item::PolishedBlackstoneStairs, 1),
+    recipe(Item::Blackstone, Item::PolishedBlackstoneWall, 1),
+    recipe(Item::Blackstone, Item::PolishedBlackstoneBricks, 1),
+    recipe(Item::Blackstone, Item::PolishedBlackstoneBrickSlab, 2),
+    recipe(Item::Blackstone, Item::PolishedBlackstoneBrickStairs, 1),
+    recipe(Item::Blackstone, Item::PolishedBlackstoneBrickWall, 1),
+    recipe(Item::Blackstone, Item::ChiseledPolishedBlackstone, 1),
+    recipe(Item::PolishedBlackstone, Item::PolishedBlackstoneSlab, 2),
+    recipe(Item::PolishedBlackstone, Item::PolishedBlackstoneStairs, 1),
+    recipe(Item::PolishedBlackstone, Item::PolishedBlackstoneWall, 1),
+    recipe(Item::PolishedBlackstone, Item::PolishedBlackstoneBricks, 1),
+    recipe(
+        Item::PolishedBlackstone,
+        Item::PolishedBlackstoneBrickSlab,
+        2,
+    ),
+    recipe(
+        Item::PolishedBlackstone,
+        Item::PolishedBlackstoneBrickStairs,
+        1,
+    ),
+    recipe(
+        Item::PolishedBlackstone,
+        Item::PolishedBlackstoneBrickWall,
+        1,
+    ),
+    recipe(
+        Item::PolishedBlackstone,
+        Item::ChiseledPolishedBlackstone,
+        1,
+    ),
+    recipe(
+        Item::PolishedBlackstoneBricks,
+        Item::PolishedBlackstoneBrickSlab,
+        2,
+    ),
+    recipe(
+        Item::PolishedBlackstoneBricks,
+        Item::PolishedBlackstoneBrickStairs,
+        1,
+    ),
+    recipe(
+        Item::PolishedBlackstoneBricks,
+        Item::PolishedBlackstoneBrickWall,
+        1,
+    ),
+    recipe(Item::Basalt, Item::PolishedBasalt, 1),
+];
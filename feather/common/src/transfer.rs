@@ -0,0 +1,70 @@
+//! Transferring players to other servers behind the same proxy.
+//!
+//! Feather can't move a player to another server itself;
+//! instead it asks the proxy (BungeeCord or Velocity) to
+//! connect the player elsewhere. [`transfer_player`] triggers
+//! a [`PlayerTransferEvent`], which the server answers by
+//! messaging the proxy. When the player then leaves, a
+//! [`PlayerTransferredEvent`](crate::events::PlayerTransferredEvent)
+//! is triggered.
+
+use ecs::{Entity, SysResult};
+
+use crate::{events::PlayerTransferEvent, Game};
+
+/// Number of ticks the proxy gets to move a player
+/// before the transfer is considered failed.
+pub const TRANSFER_TIMEOUT_TICKS: u64 = 20 * 10;
+
+/// Marks a player whose transfer was requested but
+/// who hasn't left the server yet.
+///
+/// Removed if the player is still online
+/// [`TRANSFER_TIMEOUT_TICKS`] after the request, so a
+/// later ordinary leave isn't mistaken for a transfer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingTransfer {
+    pub server: String,
+    /// Tick on which the transfer was requested.
+    pub requested_at: u64,
+}
+
+impl PendingTransfer {
+    /// Returns whether the proxy has had enough time
+    /// to move the player by tick `now`.
+    pub fn is_expired(&self, now: u64) -> bool {
+        now.saturating_sub(self.requested_at) >= TRANSFER_TIMEOUT_TICKS
+    }
+}
+
+/// Requests that the proxy transfer `player` to `server`.
+///
+/// Replaces any transfer already pending for the player.
+pub fn transfer_player(game: &mut Game, player: Entity, server: impl Into<String>) -> SysResult {
+    let server = server.into();
+    game.ecs.insert(
+        player,
+        PendingTransfer {
+            server: server.clone(),
+            requested_at: game.tick_count,
+        },
+    )?;
+    game.ecs
+        .insert_entity_event(player, PlayerTransferEvent { server })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_transfer_expires() {
+        let pending = PendingTransfer {
+            server: "lobby".to_owned(),
+            requested_at: 100,
+        };
+        assert!(!pending.is_expired(100 + TRANSFER_TIMEOUT_TICKS - 1));
+        assert!(pending.is_expired(100 + TRANSFER_TIMEOUT_TICKS));
+    }
+}
@@ -0,0 +1,70 @@
+//! Vanishing: hiding players from other players.
+//!
+//! `/vanish` toggles the [`Vanished`] component on the sender
+//! and triggers a [`VanishChangeEvent`]. The server then keeps
+//! the player out of other clients' entity lists and tablists,
+//! except for players allowed to see vanished players,
+//! and silences their join and quit messages.
+
+use base::Text;
+use ecs::{Entity, SysResult};
+
+use crate::{commands::CommandRegistry, events::VanishChangeEvent, permissions::Permissions, Game};
+
+/// Permission to use `/vanish`.
+pub const VANISH_PERMISSION: &str = "server.vanish";
+
+/// Permission to see vanished players.
+pub const SEE_VANISHED_PERMISSION: &str = "server.vanish.see";
+
+/// Marker component for vanished players.
+#[derive(Copy, Clone, Debug)]
+pub struct Vanished;
+
+pub fn register(game: &mut Game) {
+    game.resources
+        .get_mut::<CommandRegistry>()
+        .expect("commands must be registered first")
+        .register("vanish", vanish_command);
+}
+
+/// Determines whether a player with `permissions`
+/// sees vanished players.
+pub fn sees_vanished(permissions: &Permissions) -> bool {
+    permissions.has(SEE_VANISHED_PERMISSION)
+}
+
+/// Vanishes or reveals `player`.
+pub fn set_vanished(game: &mut Game, player: Entity, vanished: bool) -> SysResult {
+    if game.ecs.get::<Vanished>(player).is_ok() == vanished {
+        return Ok(());
+    }
+    if vanished {
+        game.ecs.insert(player, Vanished)?;
+    } else {
+        game.ecs.remove::<Vanished>(player)?;
+    }
+    game.ecs
+        .insert_entity_event(player, VanishChangeEvent { vanished })?;
+    Ok(())
+}
+
+/// `/vanish`
+fn vanish_command(game: &mut Game, sender: Entity, _args: &[&str]) -> anyhow::Result<Text> {
+    let allowed = game
+        .ecs
+        .get::<Permissions>(sender)
+        .map(|permissions| permissions.has(VANISH_PERMISSION))
+        .unwrap_or(false);
+    if !allowed {
+        anyhow::bail!("you don't have permission to vanish");
+    }
+
+    let vanished = game.ecs.get::<Vanished>(sender).is_err();
+    set_vanished(game, sender, vanished)?;
+    Ok(Text::of(if vanished {
+        "You are now vanished"
+    } else {
+        "You are no longer vanished"
+    }))
+}
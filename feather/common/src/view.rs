@@ -1,6 +1,6 @@
 use ahash::AHashSet;
 use base::{ChunkPosition, Position};
-use ecs::{SysResult, SystemExecutor};
+use ecs::{Entity, SysResult, SystemExecutor};
 use itertools::Either;
 use quill_common::components::Name;
 
@@ -54,6 +54,34 @@ fn update_view_on_join(game: &mut Game) -> SysResult {
     Ok(())
 }
 
+/// Changes the view distance of a player.
+///
+/// Triggers a [`ViewUpdateEvent`] containing only the chunks
+/// entering or leaving the view, so the chunks the player
+/// already has are neither reloaded nor resent.
+pub fn set_view_distance(game: &mut Game, player: Entity, view_distance: u32) -> SysResult {
+    let (old_view, new_view) = {
+        let mut view = game.ecs.get_mut::<View>(player)?;
+        if view.view_distance() == view_distance {
+            return Ok(());
+        }
+        let current = *view;
+        view.set_view_distance(view_distance);
+
+        // If the view already changed this tick, the event
+        // needs to cover both changes.
+        let old_view = match game.ecs.get::<ViewUpdateEvent>(player) {
+            Ok(event) => event.old_view,
+            Err(_) => current,
+        };
+        (old_view, *view)
+    };
+
+    game.ecs
+        .insert_entity_event(player, ViewUpdateEvent::new(old_view, new_view))?;
+    Ok(())
+}
+
 /// The view of a player, representing the set of chunks
 /// within their view distance.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -1,12 +1,15 @@
 use std::mem;
 
 use anyhow::{anyhow, bail};
-use base::{Area, Item, ItemStack};
+use base::{Area, Inventory, Item, ItemStack, Position};
 
-use ecs::SysResult;
+use ecs::{Entity, SysResult};
 pub use generated::Window as BackingWindow;
 use generated::WindowError;
 use parking_lot::MutexGuard;
+use quill_common::entity_init::EntityInit;
+
+use crate::Game;
 
 /// A player's window. Wraps one or more inventories and handles
 /// conversion between protocol and slot indices.
@@ -207,6 +210,59 @@ impl Window {
     }
 }
 
+/// Gives the items left in a closed window back to `player`,
+/// dropping those which don't fit in their inventory.
+pub fn return_items(
+    game: &mut Game,
+    player: Entity,
+    items: impl IntoIterator<Item = ItemStack>,
+) -> SysResult {
+    let inventory = game.ecs.get::<Inventory>(player)?.new_handle();
+    let position = *game.ecs.get::<Position>(player)?;
+    for item in items {
+        if let Some(leftover) = return_to_inventory(&inventory, item) {
+            let mut builder = game.create_entity_builder(position, EntityInit::Item);
+            builder.add(leftover);
+            game.spawn_entity(builder);
+        }
+    }
+    Ok(())
+}
+
+/// Removes one item from the stack in `slot`,
+/// emptying the slot if it was the last one.
+pub fn consume_one(slot: &mut Option<ItemStack>) {
+    if let Some(stack) = slot.as_mut() {
+        stack.remove(1);
+        if stack.count == 0 {
+            *slot = None;
+        }
+    }
+}
+
+/// Adds an item to the hotbar or storage of a player's
+/// inventory. Returns the items which didn't fit.
+fn return_to_inventory(inventory: &Inventory, mut item: ItemStack) -> Option<ItemStack> {
+    for &area in &[Area::Hotbar, Area::Storage] {
+        let mut slot = 0;
+        while let Some(mut stack) = inventory.item(area, slot) {
+            match stack.as_mut() {
+                Some(stack) if stack.has_same_type(&item) => stack.merge_with(&mut item),
+                Some(_) => {}
+                None => {
+                    *stack = Some(item);
+                    return None;
+                }
+            }
+            if item.count == 0 {
+                return None;
+            }
+            slot += 1;
+        }
+    }
+    Some(item)
+}
+
 /// Determines whether the given area will accept the given item
 /// for shift-click transfer.
 fn will_accept(area: Area, stack: &ItemStack) -> bool {
@@ -285,8 +341,8 @@ fn will_accept(area: Area, stack: &ItemStack) -> bool {
                 | Item::DiamondHorseArmor
         ),
         Area::LlamaCarpet => true,
-        Area::CartographyMap => matches!(stack.item(), Item::Map | Item::FilledMap),
-        Area::CartographyPaper => stack.item() == Item::Paper,
+        Area::CartographyMap => stack.item() == Item::FilledMap,
+        Area::CartographyPaper => matches!(stack.item(), Item::Paper | Item::Map | Item::GlassPane),
         Area::CartographyOutput => false,
         Area::GrindstoneInput1 => true,
         Area::GrindstoneInput2 => true,
@@ -298,6 +354,9 @@ fn will_accept(area: Area, stack: &ItemStack) -> bool {
         Area::LoomOutput => false,
         Area::StonecutterInput => true,
         Area::StonecutterOutput => false,
+        Area::SmithingBase => true,
+        Area::SmithingAddition => stack.item() == Item::NetheriteIngot,
+        Area::SmithingOutput => false,
     }
 }
 
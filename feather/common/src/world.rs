@@ -1,35 +1,47 @@
-use ahash::{AHashMap, AHashSet};
+use ahash::AHashMap;
 use base::{BlockPosition, Chunk, ChunkPosition, CHUNK_HEIGHT};
 use blocks::BlockId;
 use ecs::Ecs;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::sync::Arc;
 
-use crate::{
-    events::ChunkLoadEvent,
-    world_source::{null::NullWorldSource, ChunkLoadResult, WorldSource},
-};
+use crate::{regions::Regions, world_settings::WorldSettings, world_source::WorldSource};
+
+mod chunk_manager;
+
+pub use chunk_manager::ChunkManager;
+
+/// Name of a world created without one.
+pub const DEFAULT_WORLD_NAME: &str = "world";
+
+/// Component naming the world an entity is in.
+///
+/// Entities without it, or naming a world which
+/// isn't loaded, are in the game's main world.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntityWorld(pub String);
 
 /// Stores all blocks and chunks in a world,
 /// along with global world data like weather, time,
-/// and the [`WorldSource`](crate::world_source::WorldSource).
+/// gameplay settings, and the
+/// [`WorldSource`](crate::world_source::WorldSource).
 ///
 /// NB: _not_ what most Rust ECSs call "world."
 /// This does not store entities; it only contains blocks.
 pub struct World {
-    chunk_map: ChunkMap,
-    world_source: Box<dyn WorldSource>,
-    loading_chunks: AHashSet<ChunkPosition>,
-    canceled_chunk_loads: AHashSet<ChunkPosition>,
+    name: String,
+    settings: WorldSettings,
+    regions: Regions,
+    chunks: ChunkManager,
 }
 
 impl Default for World {
     fn default() -> Self {
         Self {
-            chunk_map: ChunkMap::new(),
-            world_source: Box::new(NullWorldSource::default()),
-            loading_chunks: AHashSet::new(),
-            canceled_chunk_loads: AHashSet::new(),
+            name: DEFAULT_WORLD_NAME.to_owned(),
+            settings: WorldSettings::default(),
+            regions: Regions::new(),
+            chunks: ChunkManager::default(),
         }
     }
 }
@@ -42,67 +54,88 @@ impl World {
     /// Creates a `World` from a `WorldSource` for loading chunks.
     pub fn with_source(world_source: impl WorldSource + 'static) -> Self {
         Self {
-            world_source: Box::new(world_source),
+            chunks: ChunkManager::new(world_source),
             ..Default::default()
         }
     }
 
+    /// Gets the name of this world, used to
+    /// refer to it in commands.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Gets the difficulty, PvP and game rule settings of this world.
+    pub fn settings(&self) -> &WorldSettings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut WorldSettings {
+        &mut self.settings
+    }
+
+    /// Gets the protected regions of this world.
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
+
+    /// Prefer [`regions::define_region`](crate::regions::define_region)
+    /// and [`regions::remove_region`](crate::regions::remove_region),
+    /// which let the server save the changes.
+    pub fn regions_mut(&mut self) -> &mut Regions {
+        &mut self.regions
+    }
+
+    /// Gets the manager loading and saving this world's chunks.
+    pub fn chunk_manager(&self) -> &ChunkManager {
+        &self.chunks
+    }
+
+    pub fn chunk_manager_mut(&mut self) -> &mut ChunkManager {
+        &mut self.chunks
+    }
+
     /// Queues the given chunk to be loaded.
     pub fn queue_chunk_load(&mut self, pos: ChunkPosition) {
-        self.loading_chunks.insert(pos);
-        self.world_source.queue_load(pos);
+        self.chunks.queue_load(pos);
     }
 
     /// Loads any chunks that have been loaded asynchronously
     /// after a call to [`queue_chunk_load`].
     pub fn load_chunks(&mut self, ecs: &mut Ecs) {
-        while let Some(loaded) = self.world_source.poll_loaded_chunk() {
-            self.loading_chunks.remove(&loaded.pos);
-            if self.canceled_chunk_loads.remove(&loaded.pos) {
-                continue;
-            }
-
-            let chunk = match loaded.result {
-                ChunkLoadResult::Missing => {
-                    log::debug!(
-                        "Chunk {:?} is missing; using default empty chunk",
-                        loaded.pos
-                    );
-                    Chunk::new(loaded.pos)
-                }
-                ChunkLoadResult::Error(e) => {
-                    log::error!("Failed to load chunk {:?}: {:?}", loaded.pos, e);
-                    continue;
-                }
-                ChunkLoadResult::Loaded { chunk } => chunk,
-            };
-            self.chunk_map.insert_chunk(chunk);
-            ecs.insert_event(ChunkLoadEvent {
-                chunk: Arc::clone(&self.chunk_map.0[&loaded.pos]),
-                position: loaded.pos,
-            });
-            log::trace!("Loaded chunk {:?}", loaded.pos);
-        }
+        self.chunks.poll_loaded(ecs);
     }
 
-    /// Unloads the given chunk.
+    /// Unloads the given chunk, saving it
+    /// to the world source if it was changed.
     pub fn unload_chunk(&mut self, pos: ChunkPosition) {
-        self.chunk_map.remove_chunk(pos);
-        if self.is_chunk_loading(pos) {
-            self.canceled_chunk_loads.insert(pos);
-        }
+        self.chunks.unload(pos);
+    }
+
+    /// Marks a loaded chunk as changed, so that
+    /// it's saved when unloaded.
+    pub fn mark_chunk_changed(&mut self, pos: ChunkPosition) {
+        self.chunks.mark_changed(pos);
+    }
 
-        log::trace!("Unloaded chunk {:?}", pos);
+    /// Saves all changed chunks to the world source, blocking
+    /// until they are written. Returns the number of chunks saved.
+    pub fn save_chunks(&mut self) -> usize {
+        self.chunks.save_all()
     }
 
     /// Returns whether the given chunk is loaded.
     pub fn is_chunk_loaded(&self, pos: ChunkPosition) -> bool {
-        self.chunk_map.0.contains_key(&pos)
+        self.chunks.is_loaded(pos)
     }
 
     /// Returns whether the given chunk is queued to be loaded.
     pub fn is_chunk_loading(&self, pos: ChunkPosition) -> bool {
-        self.loading_chunks.contains(&pos)
+        self.chunks.is_loading(pos)
     }
 
     /// Sets the block at the given position.
@@ -112,7 +145,7 @@ impl World {
     /// are out of bounds and thus no operation
     /// was performed.
     pub fn set_block_at(&self, pos: BlockPosition, block: BlockId) -> bool {
-        self.chunk_map.set_block_at(pos, block)
+        self.chunks.chunk_map().set_block_at(pos, block)
     }
 
     /// Retrieves the block at the specified
@@ -120,17 +153,17 @@ impl World {
     /// exists is not loaded or the coordinates
     /// are out of bounds, `None` is returned.
     pub fn block_at(&self, pos: BlockPosition) -> Option<BlockId> {
-        self.chunk_map.block_at(pos)
+        self.chunks.chunk_map().block_at(pos)
     }
 
     /// Returns the chunk map.
     pub fn chunk_map(&self) -> &ChunkMap {
-        &self.chunk_map
+        self.chunks.chunk_map()
     }
 
     /// Mutably gets the chunk map.
     pub fn chunk_map_mut(&mut self) -> &mut ChunkMap {
-        &mut self.chunk_map
+        self.chunks.chunk_map_mut()
     }
 }
 
@@ -236,4 +269,37 @@ mod tests {
         assert!(world.block_at(BlockPosition::new(0, -1, 0)).is_none());
         assert!(world.block_at(BlockPosition::new(0, 0, 0)).is_some());
     }
+
+    /// Source recording the chunks it's asked to save.
+    struct SavingSource(Arc<RwLock<Vec<ChunkPosition>>>);
+
+    impl WorldSource for SavingSource {
+        fn queue_load(&mut self, _pos: ChunkPosition) {}
+
+        fn poll_loaded_chunk(&mut self) -> Option<crate::world_source::LoadedChunk> {
+            None
+        }
+
+        fn queue_save(&mut self, chunk: Chunk) {
+            self.0.write().push(chunk.position());
+        }
+    }
+
+    #[test]
+    fn changed_chunks_are_saved() {
+        let saved = Arc::new(RwLock::new(Vec::new()));
+        let mut world = World::with_source(SavingSource(Arc::clone(&saved)));
+        let (changed, unchanged) = (ChunkPosition::new(0, 0), ChunkPosition::new(1, 0));
+        world.chunk_map_mut().insert_chunk(Chunk::new(changed));
+        world.chunk_map_mut().insert_chunk(Chunk::new(unchanged));
+
+        world.mark_chunk_changed(changed);
+        world.unload_chunk(changed);
+        world.unload_chunk(unchanged);
+        assert_eq!(*saved.read(), vec![changed]);
+
+        // Unloaded chunks can't be marked.
+        world.mark_chunk_changed(changed);
+        assert_eq!(world.save_chunks(), 0);
+    }
 }
@@ -0,0 +1,273 @@
+//! Loading, unloading and saving of a world's chunks.
+
+use std::sync::Arc;
+
+use ahash::AHashSet;
+use base::{Chunk, ChunkPosition};
+use ecs::Ecs;
+
+use super::ChunkMap;
+use crate::{
+    events::ChunkLoadEvent,
+    world_source::{null::NullWorldSource, ChunkLoadResult, WorldSource},
+};
+
+/// Owns the loaded chunks of a world and services load requests
+/// through a [`WorldSource`], which loads chunks off the main thread.
+///
+/// Requests for chunks already loaded or loading are ignored, and
+/// unloading a chunk which is still loading cancels its request.
+pub struct ChunkManager {
+    chunk_map: ChunkMap,
+    source: Box<dyn WorldSource>,
+    /// Chunks requested from the source which haven't
+    /// arrived yet. Chunks which arrive without being
+    /// in this set were canceled and are dropped.
+    loading: AHashSet<ChunkPosition>,
+    /// Loaded chunks with blocks changed since they were
+    /// loaded, which are saved when unloaded.
+    changed: AHashSet<ChunkPosition>,
+    /// Chunks which failed to load and were replaced with empty
+    /// chunks. They're never saved, so the stored chunk is kept.
+    failed: AHashSet<ChunkPosition>,
+    /// Whether changed chunks are saved at all.
+    saving: bool,
+}
+
+impl Default for ChunkManager {
+    fn default() -> Self {
+        Self::new(NullWorldSource::default())
+    }
+}
+
+impl ChunkManager {
+    pub fn new(source: impl WorldSource) -> Self {
+        Self {
+            chunk_map: ChunkMap::new(),
+            source: Box::new(source),
+            loading: AHashSet::new(),
+            changed: AHashSet::new(),
+            failed: AHashSet::new(),
+            saving: true,
+        }
+    }
+
+    /// Sets whether changed chunks are saved to the source. When
+    /// disabled, changes are lost once their chunk is unloaded.
+    pub fn set_saving(&mut self, saving: bool) {
+        self.saving = saving;
+        if !saving {
+            self.changed.clear();
+        }
+    }
+
+    /// Whether changed chunks are saved to the source.
+    pub fn is_saving(&self) -> bool {
+        self.saving
+    }
+
+    /// Queues the given chunk to be loaded,
+    /// unless it's already loaded or loading.
+    pub fn queue_load(&mut self, pos: ChunkPosition) {
+        if self.is_loaded(pos) || !self.loading.insert(pos) {
+            return;
+        }
+        self.source.queue_load(pos);
+    }
+
+    /// Cancels loading the given chunk.
+    pub fn cancel_load(&mut self, pos: ChunkPosition) {
+        if self.loading.remove(&pos) {
+            self.source.cancel_load(pos);
+        }
+    }
+
+    /// Adds the chunks loaded since the last call to the chunk map,
+    /// triggering a [`ChunkLoadEvent`] for each.
+    ///
+    /// Chunks which fail to load are replaced with empty chunks, so
+    /// that players waiting for them can still join, but aren't saved.
+    pub fn poll_loaded(&mut self, ecs: &mut Ecs) {
+        while let Some(loaded) = self.source.poll_loaded_chunk() {
+            if !self.loading.remove(&loaded.pos) {
+                continue;
+            }
+
+            let chunk = match loaded.result {
+                ChunkLoadResult::Missing => {
+                    log::debug!(
+                        "Chunk {:?} is missing; using default empty chunk",
+                        loaded.pos
+                    );
+                    Chunk::new(loaded.pos)
+                }
+                ChunkLoadResult::Error(e) => {
+                    log::error!(
+                        "Failed to load chunk {:?}; using an empty chunk which won't be saved: {:?}",
+                        loaded.pos,
+                        e
+                    );
+                    self.failed.insert(loaded.pos);
+                    Chunk::new(loaded.pos)
+                }
+                ChunkLoadResult::Loaded { chunk } => chunk,
+            };
+            self.chunk_map.insert_chunk(chunk);
+            ecs.insert_event(ChunkLoadEvent {
+                chunk: Arc::clone(&self.chunk_map.0[&loaded.pos]),
+                position: loaded.pos,
+            });
+            log::trace!("Loaded chunk {:?}", loaded.pos);
+        }
+    }
+
+    /// Unloads the given chunk, saving it if it was changed,
+    /// or cancels loading it if it's still loading.
+    pub fn unload(&mut self, pos: ChunkPosition) {
+        self.failed.remove(&pos);
+        if self.changed.remove(&pos) {
+            if let Some(chunk) = self.chunk_map.0.get(&pos) {
+                self.source.queue_save(chunk.read().clone());
+            }
+        }
+        self.chunk_map.remove_chunk(pos);
+        self.cancel_load(pos);
+
+        log::trace!("Unloaded chunk {:?}", pos);
+    }
+
+    /// Marks a loaded chunk as changed, so that
+    /// it's saved when unloaded. Chunks which
+    /// failed to load are never saved.
+    pub fn mark_changed(&mut self, pos: ChunkPosition) {
+        if self.saving && self.is_loaded(pos) && !self.failed.contains(&pos) {
+            self.changed.insert(pos);
+        }
+    }
+
+    /// Saves all changed chunks, blocking until they
+    /// are written. Returns the number of chunks saved.
+    pub fn save_all(&mut self) -> usize {
+        let mut saved = 0;
+        for pos in self.changed.drain() {
+            if let Some(chunk) = self.chunk_map.0.get(&pos) {
+                self.source.queue_save(chunk.read().clone());
+                saved += 1;
+            }
+        }
+        self.source.flush();
+        saved
+    }
+
+    pub fn is_loaded(&self, pos: ChunkPosition) -> bool {
+        self.chunk_map.0.contains_key(&pos)
+    }
+
+    pub fn is_loading(&self, pos: ChunkPosition) -> bool {
+        self.loading.contains(&pos)
+    }
+
+    /// Gets the number of loaded chunks.
+    pub fn loaded_count(&self) -> usize {
+        self.chunk_map.0.len()
+    }
+
+    pub fn chunk_map(&self) -> &ChunkMap {
+        &self.chunk_map
+    }
+
+    pub fn chunk_map_mut(&mut self) -> &mut ChunkMap {
+        &mut self.chunk_map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::world_source::LoadedChunk;
+
+    /// Source which loads chunks when told to,
+    /// recording the requests it receives.
+    #[derive(Clone, Default)]
+    struct ManualSource {
+        requests: Arc<Mutex<Vec<ChunkPosition>>>,
+        canceled: Arc<Mutex<Vec<ChunkPosition>>>,
+        loaded: Arc<Mutex<Vec<ChunkPosition>>>,
+        failed: Arc<Mutex<Vec<ChunkPosition>>>,
+        saved: Arc<Mutex<Vec<ChunkPosition>>>,
+    }
+
+    impl WorldSource for ManualSource {
+        fn queue_load(&mut self, pos: ChunkPosition) {
+            self.requests.lock().push(pos);
+        }
+
+        fn cancel_load(&mut self, pos: ChunkPosition) {
+            self.canceled.lock().push(pos);
+        }
+
+        fn poll_loaded_chunk(&mut self) -> Option<LoadedChunk> {
+            if let Some(pos) = self.failed.lock().pop() {
+                return Some(LoadedChunk {
+                    pos,
+                    result: ChunkLoadResult::Error(anyhow::anyhow!("corrupt chunk")),
+                });
+            }
+            self.loaded.lock().pop().map(|pos| LoadedChunk {
+                pos,
+                result: ChunkLoadResult::Missing,
+            })
+        }
+
+        fn queue_save(&mut self, chunk: Chunk) {
+            self.saved.lock().push(chunk.position());
+        }
+    }
+
+    #[test]
+    fn requests_are_deduplicated_and_canceled() {
+        let source = ManualSource::default();
+        let mut chunks = ChunkManager::new(source.clone());
+        let mut ecs = Ecs::new();
+        let (kept, left) = (ChunkPosition::new(0, 0), ChunkPosition::new(5, 5));
+
+        chunks.queue_load(kept);
+        chunks.queue_load(kept);
+        chunks.queue_load(left);
+        assert_eq!(*source.requests.lock(), vec![kept, left]);
+
+        // The player moved away before the chunk arrived.
+        chunks.unload(left);
+        assert_eq!(*source.canceled.lock(), vec![left]);
+
+        source.loaded.lock().extend([kept, left].iter().copied());
+        chunks.poll_loaded(&mut ecs);
+        assert!(chunks.is_loaded(kept));
+        assert!(!chunks.is_loaded(left));
+        assert!(!chunks.is_loading(left));
+
+        chunks.queue_load(kept);
+        assert_eq!(source.requests.lock().len(), 2);
+    }
+
+    #[test]
+    fn failed_chunks_are_empty_and_never_saved() {
+        let source = ManualSource::default();
+        let mut chunks = ChunkManager::new(source.clone());
+        let mut ecs = Ecs::new();
+        let pos = ChunkPosition::new(0, 0);
+
+        chunks.queue_load(pos);
+        source.failed.lock().push(pos);
+        chunks.poll_loaded(&mut ecs);
+        assert!(chunks.is_loaded(pos));
+        assert!(!chunks.is_loading(pos));
+
+        chunks.mark_changed(pos);
+        assert_eq!(chunks.save_all(), 0);
+        chunks.unload(pos);
+        assert!(source.saved.lock().is_empty());
+    }
+}
@@ -0,0 +1,297 @@
+//! Per-world gameplay settings: difficulty, PvP, and game rules.
+//!
+//! Each [`World`](crate::World) stores its own [`WorldSettings`].
+//! Gameplay code looks them up through [`Game::world_of`] so
+//! that entities follow the rules of the world they're in.
+//!
+//! `/difficulty`, `/pvp` and `/gamerule` query or change the
+//! settings. Each takes an optional world name as its first
+//! argument and otherwise targets the sender's world.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail};
+use base::{anti_xray::AntiXray, BlockPosition, Difficulty, GameRules, Position, Text};
+use ecs::Entity;
+use quill_common::entities::Player;
+
+use crate::{
+    commands::CommandRegistry, events::DifficultyChangeEvent, permissions::Permissions, regions,
+    Game, World,
+};
+
+/// Permission to change world settings with commands.
+pub const WORLD_SETTINGS_PERMISSION: &str = "server.worldsettings";
+
+/// Gameplay settings of a world.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorldSettings {
+    pub difficulty: Difficulty,
+    /// Whether players may attack each other.
+    pub pvp: bool,
+    pub game_rules: GameRules,
+    /// Obfuscation of ores in chunks sent to players,
+    /// or `None` if anti-xray is disabled.
+    pub anti_xray: Option<Arc<AntiXray>>,
+}
+
+impl Default for WorldSettings {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::default(),
+            pvp: true,
+            game_rules: GameRules::default(),
+            anti_xray: None,
+        }
+    }
+}
+
+pub fn register(game: &mut Game) {
+    let mut commands = game
+        .resources
+        .get_mut::<CommandRegistry>()
+        .expect("commands must be registered first");
+    commands.register("difficulty", difficulty_command);
+    commands.register("pvp", pvp_command);
+    commands.register("gamerule", gamerule_command);
+}
+
+/// Determines whether `attacker` may attack `target` under the
+/// PvP setting of the attacker's world and its protected regions.
+///
+/// Players may only attack other entities, like animals, armor
+/// stands and item frames, where they may build.
+pub fn can_attack(game: &Game, attacker: Entity, target: Entity) -> bool {
+    if game.ecs.get::<Player>(attacker).is_err() {
+        return true;
+    }
+    if game.ecs.get::<Player>(target).is_ok() {
+        return game.world_of(attacker).settings().pvp
+            && regions::pvp_allowed(game, attacker, target);
+    }
+    match game.ecs.get::<Position>(target) {
+        Ok(position) => regions::can_build(game, attacker, position.block()),
+        Err(_) => true,
+    }
+}
+
+/// Determines whether a mob may spawn naturally at `pos` in `world`,
+/// following the `doMobSpawning` game rule, the difficulty (hostile
+/// mobs don't spawn on peaceful) and the world's protected regions.
+///
+/// Mobs summoned on purpose, e.g. with `/summon`, ignore these settings.
+pub fn mob_spawning_allowed(world: &World, pos: BlockPosition, hostile: bool) -> bool {
+    let settings = world.settings();
+    settings.game_rules.do_mob_spawning
+        && !(hostile && settings.difficulty == Difficulty::Peaceful)
+        && regions::mob_spawning_allowed(world, pos)
+}
+
+/// Sets the difficulty of the world called `world`.
+pub fn set_difficulty(game: &mut Game, world: &str, difficulty: Difficulty) -> anyhow::Result<()> {
+    let settings = game
+        .world_named_mut(world)
+        .ok_or_else(|| anyhow!("unknown world '{}'", world))?
+        .settings_mut();
+    if settings.difficulty == difficulty {
+        return Ok(());
+    }
+    settings.difficulty = difficulty;
+    game.ecs.insert_event(DifficultyChangeEvent {
+        world: world.to_owned(),
+        difficulty,
+    });
+    Ok(())
+}
+
+/// Splits an optional leading world name off `args`,
+/// defaulting to the world `sender` is in.
+fn target_world<'a, 'b>(
+    game: &mut Game,
+    sender: Entity,
+    args: &'a [&'b str],
+) -> (String, &'a [&'b str]) {
+    match args.split_first() {
+        Some((first, rest)) if game.world_named_mut(first).is_some() => ((*first).to_owned(), rest),
+        _ => (game.world_of(sender).name().to_owned(), args),
+    }
+}
+
+fn settings_of<'a>(game: &'a mut Game, world: &str) -> anyhow::Result<&'a mut WorldSettings> {
+    game.world_named_mut(world)
+        .map(|world| world.settings_mut())
+        .ok_or_else(|| anyhow!("unknown world '{}'", world))
+}
+
+fn check_permission(game: &Game, sender: Entity) -> anyhow::Result<()> {
+    let allowed = game
+        .ecs
+        .get::<Permissions>(sender)
+        .map(|permissions| permissions.has(WORLD_SETTINGS_PERMISSION))
+        .unwrap_or(false);
+    if !allowed {
+        bail!("you don't have permission to change world settings");
+    }
+    Ok(())
+}
+
+/// `/difficulty [<world>] [<difficulty>]`
+fn difficulty_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    let (world, args) = target_world(game, sender, args);
+    match args {
+        [] => {
+            let difficulty = settings_of(game, &world)?.difficulty;
+            Ok(Text::of(format!(
+                "The difficulty of {} is {}",
+                world,
+                difficulty.name()
+            )))
+        }
+        [difficulty] => {
+            check_permission(game, sender)?;
+            let difficulty: Difficulty = difficulty
+                .parse()
+                .map_err(|_| anyhow!("unknown difficulty '{}'", difficulty))?;
+            set_difficulty(game, &world, difficulty)?;
+            Ok(Text::of(format!(
+                "The difficulty of {} has been set to {}",
+                world,
+                difficulty.name()
+            )))
+        }
+        _ => bail!("usage: /difficulty [<world>] [<difficulty>]"),
+    }
+}
+
+/// `/pvp [<world>] [on|off]`
+fn pvp_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    let (world, args) = target_world(game, sender, args);
+    let pvp = match args {
+        [] => settings_of(game, &world)?.pvp,
+        [value] => {
+            check_permission(game, sender)?;
+            let pvp = match *value {
+                "on" | "true" => true,
+                "off" | "false" => false,
+                _ => bail!("expected 'on' or 'off'"),
+            };
+            settings_of(game, &world)?.pvp = pvp;
+            pvp
+        }
+        _ => bail!("usage: /pvp [<world>] [on|off]"),
+    };
+    Ok(Text::of(format!(
+        "PvP is {} in {}",
+        if pvp { "enabled" } else { "disabled" },
+        world
+    )))
+}
+
+/// `/gamerule [<world>] <rule> [<value>]`
+fn gamerule_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    let (world, args) = target_world(game, sender, args);
+    match args {
+        [rule] => {
+            let value = settings_of(game, &world)?
+                .game_rules
+                .get(rule)
+                .ok_or_else(|| anyhow!("unknown game rule '{}'", rule))?;
+            Ok(Text::of(format!(
+                "Gamerule {} is currently set to: {} in {}",
+                rule, value, world
+            )))
+        }
+        [rule, value] => {
+            check_permission(game, sender)?;
+            settings_of(game, &world)?.game_rules.set(rule, value)?;
+            Ok(Text::of(format!(
+                "Gamerule {} is now set to: {} in {}",
+                rule, value, world
+            )))
+        }
+        _ => bail!("usage: /gamerule [<world>] <rule> [<value>]"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::EntityWorld;
+
+    use super::*;
+
+    #[test]
+    fn target_world_is_optional() {
+        let mut game = Game::new();
+        game.world.set_name("lobby");
+        let sender = game.ecs.spawn(());
+
+        let (world, args) = target_world(&mut game, sender, &["lobby", "hard"]);
+        assert_eq!(world, "lobby");
+        assert_eq!(args, ["hard"]);
+
+        let (world, args) = target_world(&mut game, sender, &["hard"]);
+        assert_eq!(world, "lobby");
+        assert_eq!(args, ["hard"]);
+    }
+
+    #[test]
+    fn changing_difficulty_requires_permission() {
+        let mut game = Game::new();
+        let sender = game.ecs.spawn((Permissions::default(),));
+        assert!(difficulty_command(&mut game, sender, &["hard"]).is_err());
+        assert_eq!(game.world.settings().difficulty, Difficulty::Normal);
+
+        game.ecs
+            .get_mut::<Permissions>(sender)
+            .unwrap()
+            .grant(WORLD_SETTINGS_PERMISSION);
+        difficulty_command(&mut game, sender, &["world", "hard"]).unwrap();
+        assert_eq!(game.world.settings().difficulty, Difficulty::Hard);
+    }
+
+    #[test]
+    fn attacking_entities_requires_build() {
+        use base::position;
+        use quill_common::region::{Region, RegionFlag, RegionShape};
+
+        let mut game = Game::new();
+        game.world.regions_mut().insert(
+            Region::new(
+                "spawn",
+                RegionShape::cuboid(BlockPosition::new(0, 0, 0), BlockPosition::new(15, 255, 15)),
+            )
+            .with_flag(RegionFlag::Build, false),
+        );
+        let player = game.ecs.spawn((Player, Permissions::default()));
+        let inside = game.ecs.spawn((position!(8.0, 64.0, 8.0),));
+        let outside = game.ecs.spawn((position!(32.0, 64.0, 8.0),));
+        assert!(!can_attack(&game, player, inside));
+        assert!(can_attack(&game, player, outside));
+        // Mobs aren't bound by regions.
+        assert!(can_attack(&game, inside, outside));
+    }
+
+    #[test]
+    fn world_of_follows_entity_world() {
+        let mut game = Game::new();
+        game.world.set_name("lobby");
+        let in_lobby = game.ecs.spawn((EntityWorld("lobby".to_owned()),));
+        let unknown = game.ecs.spawn((EntityWorld("nether".to_owned()),));
+        assert_eq!(game.world_of(in_lobby).name(), "lobby");
+        assert_eq!(game.world_of(unknown).name(), "lobby");
+    }
+
+    #[test]
+    fn mob_spawning_follows_settings() {
+        let mut world = World::new();
+        let pos = BlockPosition::new(0, 64, 0);
+        assert!(mob_spawning_allowed(&world, pos, true));
+
+        world.settings_mut().difficulty = Difficulty::Peaceful;
+        assert!(!mob_spawning_allowed(&world, pos, true));
+        assert!(mob_spawning_allowed(&world, pos, false));
+
+        world.settings_mut().game_rules.do_mob_spawning = false;
+        assert!(!mob_spawning_allowed(&world, pos, false));
+    }
+}
@@ -35,6 +35,18 @@ pub trait WorldSource: 'static {
     /// same order they were queued for loading.
     fn poll_loaded_chunk(&mut self) -> Option<LoadedChunk>;
 
+    /// Tells the source a queued chunk is no longer needed.
+    /// Sources may skip loading it, but may also still
+    /// return it from `poll_loaded_chunk`.
+    fn cancel_load(&mut self, _pos: ChunkPosition) {}
+
+    /// Enqueues a chunk to be saved. Sources
+    /// which can't store chunks ignore it.
+    fn queue_save(&mut self, _chunk: Chunk) {}
+
+    /// Blocks until all chunks queued for saving are written.
+    fn flush(&mut self) {}
+
     /// Creates a `WorldSource` that falls back to `fallback`
     /// if chunks in `self` are missing or corrupt.
     fn with_fallback(self, fallback: impl WorldSource) -> FallbackWorldSource
@@ -81,4 +93,17 @@ impl WorldSource for FallbackWorldSource {
             .flatten()
             .or_else(|| self.fallback.poll_loaded_chunk())
     }
+
+    fn cancel_load(&mut self, pos: ChunkPosition) {
+        self.first.cancel_load(pos);
+        self.fallback.cancel_load(pos);
+    }
+
+    fn queue_save(&mut self, chunk: Chunk) {
+        self.first.queue_save(chunk);
+    }
+
+    fn flush(&mut self) {
+        self.first.flush();
+    }
 }
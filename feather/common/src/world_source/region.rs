@@ -1,53 +1,135 @@
 use std::{
-    collections::hash_map::Entry,
+    collections::hash_map::{DefaultHasher, Entry},
+    fs,
+    hash::{Hash, Hasher},
+    panic::{self, AssertUnwindSafe},
     path::PathBuf,
-    time::{Duration, Instant},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
+use anyhow::{anyhow, bail};
 use base::{
     anvil::region::{RegionHandle, RegionPosition},
-    ChunkPosition,
+    Chunk, ChunkPosition,
 };
 use flume::{Receiver, Sender};
+use parking_lot::Mutex;
+use utils::panic_message;
 
 use super::{ChunkLoadResult, LoadedChunk, WorldSource};
 
-/// World source loading from a vanilla (Anvil) world.
+/// Number of threads loading and saving chunks by default.
+pub const DEFAULT_WORKERS: usize = 4;
+
+/// World source loading from and saving to a vanilla (Anvil) world.
+///
+/// Chunks are handled by a pool of worker threads. Each region
+/// file belongs to one worker, so that writes to a region
+/// never race and each file is only opened once.
 pub struct RegionWorldSource {
-    request_sender: Sender<ChunkPosition>,
+    workers: Vec<Sender<Request>>,
     result_receiver: Receiver<LoadedChunk>,
+    /// Chunks which no longer need to be loaded.
+    canceled: Arc<Mutex<AHashSet<ChunkPosition>>>,
 }
 
 impl RegionWorldSource {
     pub fn new(world_dir: impl Into<PathBuf>) -> Self {
-        let (request_sender, request_receiver) = flume::unbounded();
-        let (worker, result_receiver) = Worker::new(world_dir.into(), request_receiver);
+        Self::with_workers(world_dir, DEFAULT_WORKERS)
+    }
 
-        worker.start();
+    /// Creates a source with `count` worker threads.
+    pub fn with_workers(world_dir: impl Into<PathBuf>, count: usize) -> Self {
+        let world_dir = world_dir.into();
+        // Unbounded so that workers never wait on the main
+        // thread while it waits for them in `flush`.
+        let (result_sender, result_receiver) = flume::unbounded();
+        let canceled = Arc::new(Mutex::new(AHashSet::new()));
+
+        let workers = (0..count.max(1))
+            .map(|index| {
+                let (request_sender, request_receiver) = flume::unbounded();
+                Worker::new(
+                    world_dir.clone(),
+                    request_receiver,
+                    result_sender.clone(),
+                    Arc::clone(&canceled),
+                )
+                .start(index);
+                request_sender
+            })
+            .collect();
 
         Self {
-            request_sender,
+            workers,
             result_receiver,
+            canceled,
         }
     }
+
+    /// Sends a request to the worker owning the region of `pos`.
+    fn send(&self, pos: ChunkPosition, request: Request) {
+        let mut hasher = DefaultHasher::new();
+        RegionPosition::from_chunk(pos).hash(&mut hasher);
+        let worker = hasher.finish() as usize % self.workers.len();
+        self.workers[worker]
+            .send(request)
+            .expect("chunk worker panicked");
+    }
 }
 
 impl WorldSource for RegionWorldSource {
     fn queue_load(&mut self, pos: ChunkPosition) {
-        self.request_sender
-            .send(pos)
-            .expect("chunk worker panicked");
+        self.canceled.lock().remove(&pos);
+        self.send(pos, Request::Load(pos));
     }
 
     fn poll_loaded_chunk(&mut self) -> Option<super::LoadedChunk> {
         self.result_receiver.try_recv().ok()
     }
+
+    fn cancel_load(&mut self, pos: ChunkPosition) {
+        self.canceled.lock().insert(pos);
+    }
+
+    fn queue_save(&mut self, chunk: Chunk) {
+        self.send(chunk.position(), Request::Save(chunk));
+    }
+
+    fn flush(&mut self) {
+        let done: Vec<_> = self
+            .workers
+            .iter()
+            .map(|worker| {
+                let (done_tx, done_rx) = flume::bounded(1);
+                worker
+                    .send(Request::Flush(done_tx))
+                    .expect("chunk worker panicked");
+                done_rx
+            })
+            .collect();
+        for done_rx in done {
+            let _ = done_rx.recv();
+        }
+    }
+}
+
+enum Request {
+    Load(ChunkPosition),
+    Save(Chunk),
+    /// Notifies the sender once previous requests are handled.
+    Flush(Sender<()>),
 }
 
 /// Duration to keep a region file open when not in use.
 const CACHE_TIME: Duration = Duration::from_secs(60);
 
+/// Directory within the world directory where
+/// corrupt chunks are moved to.
+pub const QUARANTINE_DIR: &str = "corrupted";
+
 struct OpenRegionFile {
     handle: RegionHandle,
     last_used: Instant,
@@ -67,8 +149,9 @@ impl OpenRegionFile {
 }
 
 struct Worker {
-    request_receiver: Receiver<ChunkPosition>,
+    request_receiver: Receiver<Request>,
     result_sender: Sender<LoadedChunk>,
+    canceled: Arc<Mutex<AHashSet<ChunkPosition>>>,
     world_dir: PathBuf,
     region_files: AHashMap<RegionPosition, OpenRegionFile>,
     last_cache_update: Instant,
@@ -77,36 +160,39 @@ struct Worker {
 impl Worker {
     pub fn new(
         world_dir: PathBuf,
-        request_receiver: Receiver<ChunkPosition>,
-    ) -> (Self, Receiver<LoadedChunk>) {
-        let (result_sender, result_receiver) = flume::bounded(256);
-        (
-            Self {
-                request_receiver,
-                result_sender,
-                world_dir,
-                region_files: AHashMap::new(),
-                last_cache_update: Instant::now(),
-            },
-            result_receiver,
-        )
+        request_receiver: Receiver<Request>,
+        result_sender: Sender<LoadedChunk>,
+        canceled: Arc<Mutex<AHashSet<ChunkPosition>>>,
+    ) -> Self {
+        Self {
+            request_receiver,
+            result_sender,
+            canceled,
+            world_dir,
+            region_files: AHashMap::new(),
+            last_cache_update: Instant::now(),
+        }
     }
 
-    pub fn start(self) {
+    pub fn start(self, index: usize) {
         std::thread::Builder::new()
-            .name("chunk_worker".to_owned())
+            .name(format!("chunk_worker_{}", index))
             .spawn(move || self.run())
             .expect("failed to create chunk worker thread");
     }
 
     fn run(mut self) {
-        log::info!("Chunk worker started");
+        log::debug!("Chunk worker started");
         loop {
             match self.request_receiver.recv_timeout(Duration::from_secs(30)) {
-                Ok(pos) => self.load_chunk(pos),
+                Ok(Request::Load(pos)) => self.load_chunk(pos),
+                Ok(Request::Save(chunk)) => self.save_chunk(&chunk),
+                Ok(Request::Flush(done)) => {
+                    let _ = done.send(());
+                }
                 Err(flume::RecvTimeoutError::Timeout) => (),
                 Err(flume::RecvTimeoutError::Disconnected) => {
-                    log::info!("Chunk worker shutting down");
+                    log::debug!("Chunk worker shutting down");
                     return;
                 }
             }
@@ -115,6 +201,9 @@ impl Worker {
     }
 
     fn load_chunk(&mut self, pos: ChunkPosition) {
+        if self.canceled.lock().remove(&pos) {
+            return;
+        }
         let result = self.get_chunk_load_result(pos);
         let _ = self.result_sender.send(LoadedChunk { pos, result });
     }
@@ -126,14 +215,112 @@ impl Worker {
             None => return ChunkLoadResult::Missing,
         };
 
-        let chunk = match file.handle.load_chunk(pos) {
-            Ok((chunk, _, _)) => chunk,
-            Err(e) => return ChunkLoadResult::Error(e.into()),
+        file.last_used = Instant::now();
+
+        // Corrupt data may also cause a panic while decoding.
+        // This must not take down the worker.
+        let handle = &mut file.handle;
+        let error = match panic::catch_unwind(AssertUnwindSafe(|| handle.load_chunk(pos))) {
+            Ok(Ok((chunk, _, _))) => return ChunkLoadResult::Loaded { chunk },
+            Ok(Err(e)) => {
+                if e.is_corruption() {
+                    self.quarantine_chunk(pos, &e.to_string());
+                }
+                anyhow::Error::new(e)
+            }
+            Err(payload) => {
+                let message = panic_message(&*payload);
+                self.quarantine_chunk(pos, &message);
+                anyhow!("panicked while loading chunk: {}", message)
+            }
         };
+        ChunkLoadResult::Error(error.context(format!("failed to read {}", region.file_name())))
+    }
 
+    fn save_chunk(&mut self, chunk: &Chunk) {
+        let pos = chunk.position();
+        if let Err(e) = self.try_save_chunk(chunk) {
+            log::error!("Failed to save chunk ({}, {}): {:?}", pos.x, pos.z, e);
+        }
+    }
+
+    fn try_save_chunk(&mut self, chunk: &Chunk) -> anyhow::Result<()> {
+        let pos = chunk.position();
+        let region = RegionPosition::from_chunk(pos);
+        if self.region_file_handle(region).is_none() {
+            // Never overwrite a region file which exists but can't be read.
+            if self
+                .world_dir
+                .join("region")
+                .join(region.file_name())
+                .exists()
+            {
+                bail!("{} could not be opened", region.file_name());
+            }
+            let handle = base::anvil::region::create_region(&self.world_dir, region)?;
+            self.region_files
+                .insert(region, OpenRegionFile::new(handle));
+        }
+        let file = self
+            .region_files
+            .get_mut(&region)
+            .expect("region file was just opened");
         file.last_used = Instant::now();
 
-        ChunkLoadResult::Loaded { chunk }
+        // Entities and block entities aren't kept with loaded
+        // chunks yet, so carry over the ones already stored.
+        let handle = &mut file.handle;
+        let (entities, block_entities) =
+            match panic::catch_unwind(AssertUnwindSafe(|| handle.load_chunk(pos))) {
+                Ok(Ok((_, entities, block_entities))) => (entities, block_entities),
+                _ => Default::default(),
+            };
+        handle.save_chunk(chunk, &entities, &block_entities)?;
+        Ok(())
+    }
+
+    /// Moves the data of a corrupt chunk out of its region file
+    /// into the quarantine directory. The chunk is then missing
+    /// from the world, so it is regenerated by the fallback source.
+    fn quarantine_chunk(&mut self, pos: ChunkPosition, error: &str) {
+        let region = RegionPosition::from_chunk(pos);
+        let dir = self.world_dir.join(QUARANTINE_DIR);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        let path = dir.join(format!("c.{}.{}.{}.mcc", pos.x, pos.z, timestamp));
+
+        let result = match self.region_file_handle(region) {
+            Some(file) => (|| -> anyhow::Result<usize> {
+                let data = file.handle.read_raw_chunk(pos)?;
+                fs::create_dir_all(&dir)?;
+                fs::write(&path, &data)?;
+                file.handle.remove_chunk(pos)?;
+                Ok(data.len())
+            })(),
+            None => Err(anyhow!("region file could not be opened")),
+        };
+
+        match result {
+            Ok(size) => log::error!(
+                "Quarantined corrupt chunk: chunk=({}, {}) region={} error=\"{}\" size={} moved_to={}",
+                pos.x,
+                pos.z,
+                region.file_name(),
+                error,
+                size,
+                path.display()
+            ),
+            Err(e) => log::error!(
+                "Failed to quarantine corrupt chunk: chunk=({}, {}) region={} error=\"{}\" cause=\"{}\"",
+                pos.x,
+                pos.z,
+                region.file_name(),
+                error,
+                e
+            ),
+        }
     }
 
     fn region_file_handle(&mut self, region: RegionPosition) -> Option<&mut OpenRegionFile> {
@@ -168,3 +355,57 @@ impl Worker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Seek, SeekFrom, Write};
+
+    use base::Chunk;
+
+    use super::*;
+
+    #[test]
+    fn corrupt_chunks_are_quarantined() {
+        let dir =
+            std::env::temp_dir().join(format!("feather-quarantine-test-{}", std::process::id()));
+        let pos = ChunkPosition::new(1, 2);
+        let mut region =
+            base::anvil::region::create_region(&dir, RegionPosition::from_chunk(pos)).unwrap();
+        region.save_chunk(&Chunk::new(pos), &[], &[]).unwrap();
+        drop(region);
+
+        // Overwrite the compression type of the chunk, which is stored
+        // right after the header (two 4 KiB sectors) and the chunk length.
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .open(
+                dir.join("region")
+                    .join(RegionPosition::from_chunk(pos).file_name()),
+            )
+            .unwrap();
+        file.seek(SeekFrom::Start(2 * 4096 + 4)).unwrap();
+        file.write_all(&[9]).unwrap();
+        drop(file);
+
+        let (_sender, receiver) = flume::unbounded();
+        let (result_sender, _) = flume::unbounded();
+        let mut worker = Worker::new(dir.clone(), receiver, result_sender, Default::default());
+        assert!(matches!(
+            worker.get_chunk_load_result(pos),
+            ChunkLoadResult::Error(_)
+        ));
+        assert_eq!(fs::read_dir(dir.join(QUARANTINE_DIR)).unwrap().count(), 1);
+        assert!(matches!(
+            worker
+                .region_files
+                .values_mut()
+                .next()
+                .unwrap()
+                .handle
+                .load_chunk(pos),
+            Err(base::anvil::region::Error::ChunkNotExist)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -13,11 +13,7 @@ use smartstring::{LazyCompact, SmartString};
 mod vanilla;
 pub use vanilla::download_vanilla_assets;
 
-mod id;
-pub use id::NamespacedId;
-
-/// The default namespace for resource locations (NamespacedIds).
-pub const DEFAULT_NAMESPACE: &str = "minecraft";
+pub use base::id::{NamespacedId, DEFAULT_NAMESPACE};
 
 /// The pack.mcmeta file at the root of a datapack.
 ///
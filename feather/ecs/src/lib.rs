@@ -16,7 +16,7 @@ use hecs::{Component, DynamicBundle, Fetch, Query, World};
 
 #[doc(inline)]
 pub use hecs::{
-    BuiltEntity, ComponentError, DynamicQuery, DynamicQueryTypes, Entity, EntityBuilder,
+    BuiltEntity, Component, ComponentError, DynamicQuery, DynamicQueryTypes, Entity, EntityBuilder,
     MissingComponent, NoSuchEntity, QueryBorrow, Ref, RefMut,
 };
 
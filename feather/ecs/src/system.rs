@@ -109,6 +109,12 @@ impl<Input> SystemExecutor<Input> {
         self.systems.push(system);
     }
 
+    /// Removes all systems for which `keep`
+    /// returns `false` when given the system's name.
+    pub fn retain_systems(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.systems.retain(|system| keep(&system.name));
+    }
+
     /// Begins a group with the provided group state type.
     ///
     /// The group state must be added to the `resources`.
@@ -40,3 +40,19 @@ fn systems_are_executed_in_order() {
     executor.run(&mut input);
     assert_eq!(input.x, 110);
 }
+
+#[test]
+fn retained_systems_keep_running() {
+    let mut executor = SystemExecutor::new();
+    executor.add_system_with_name(system1, "first");
+    executor.add_system_with_name(system2, "second");
+    executor.retain_systems(|name| name != "second");
+    assert_eq!(executor.system_names().collect::<Vec<_>>(), vec!["first"]);
+
+    let mut input = Input {
+        x: 1,
+        ecs: Ecs::new(),
+    };
+    executor.run(&mut input);
+    assert_eq!(input.x, 11);
+}
@@ -8,6 +8,7 @@ internal_ids = {}
 names = {}
 display_names = {}
 bboxes = {}
+living = {}
 
 for entity in data:
     variant = common.camel_case(entity['name'])
@@ -16,6 +17,10 @@ for entity in data:
     internal_ids[variant] = entity['internalId']
     names[variant] = entity['name']
     display_names[variant] = entity['displayName']
+    # Living entities are spawned with the Spawn Living Entity packet,
+    # others with Spawn Entity. Armor stands are living entities
+    # even though they aren't mobs.
+    living[variant] = entity['type'] in ("mob", "player") or entity['name'] == "armor_stand"
 
     width = entity['width']
     height = entity['height']
@@ -27,5 +32,6 @@ output += common.generate_enum_property("EntityKind", "internal_id", "u32", inte
 output += common.generate_enum_property("EntityKind", "name", "&str", names, True, "&'static str")
 output += common.generate_enum_property("EntityKind", "display_name", "&str", display_names, True, "&'static str")
 output += common.generate_enum_property("EntityKind", "bounding_box", "vek::Aabb<f64>", bboxes)
+output += common.generate_enum_property("EntityKind", "is_living", "bool", living)
 
 common.output("src/entity.rs", output)
@@ -0,0 +1,176 @@
+# Generates packet structs and packet ID registries from
+# the minecraft-data protocol definition.
+#
+# Packets with fields whose types can't be expressed with
+# `#[derive(Packet)]` must be defined by hand in the `client`
+# and `server` packet modules; the generated module re-exports
+# those definitions.
+
+import common
+from glob import glob
+from re import findall, sub
+
+VERSION = "1.16.2"
+
+# State name in minecraft-data => state name in Feather
+STATES = {
+    "handshaking": "Handshake",
+    "status": "Status",
+    "login": "Login",
+    "play": "Play",
+}
+
+# Direction in minecraft-data => module and packet enum prefix
+DIRECTIONS = {
+    "toServer": "Client",
+    "toClient": "Server",
+}
+
+# minecraft-data type => Feather wire type
+TYPES = {
+    "varint": "VarInt",
+    "varlong": "VarLong",
+    "string": "String",
+    "UUID": "Uuid",
+    "bool": "bool",
+    "i8": "i8",
+    "u8": "u8",
+    "i16": "i16",
+    "u16": "u16",
+    "i32": "i32",
+    "i64": "i64",
+    "f32": "f32",
+    "f64": "f64",
+    "position": "BlockPosition",
+    "slot": "Slot",
+    "nbt": "Nbt<Blob>",
+    "restBuffer": "LengthInferredVecU8",
+    "entityMetadata": "EntityMetadata",
+}
+
+# Directory containing the hand-written packet modules
+PACKETS_PATH = "../protocol/src/packets"
+
+# Field names which are Rust keywords
+RENAMES = {
+    "type": "kind",
+    "move": "movement",
+}
+
+
+def snake_case(string):
+    return sub(r'(?<!^)(?=[A-Z])', '_', string).lower()
+
+
+# Converts a minecraft-data type to a Feather wire type,
+# or returns None if it isn't supported.
+def field_type(typ):
+    if isinstance(typ, str):
+        return TYPES.get(typ)
+
+    kind, options = typ
+    if kind == "mapper":
+        return field_type(options["type"])
+    elif kind == "pstring" and options.get("countType") == "varint":
+        return "String"
+    elif kind == "option":
+        inner = field_type(options)
+        if inner is not None:
+            return f"Option<{inner}>"
+    elif kind == "buffer" and options.get("countType") == "varint":
+        return "LengthPrefixedVec<u8>"
+    elif kind == "array" and options.get("countType") == "varint":
+        inner = field_type(options["type"])
+        if inner is not None:
+            return f"LengthPrefixedVec<{inner}>"
+    return None
+
+
+# Wire types which are stored as another type in packet structs
+# => (field type, `#[packet(...)]` encoding)
+ENCODINGS = {
+    "VarInt": ("i32", "varint"),
+    "VarLong": ("i64", "varlong"),
+    "LengthInferredVecU8": ("Vec<u8>", "length_inferred"),
+}
+
+
+# Generates a struct field for a wire type.
+def generate_field(name, typ):
+    if typ.startswith("LengthPrefixedVec<"):
+        inner = typ[len("LengthPrefixedVec<"):-1]
+        field_type, encoding = f"Vec<{inner}>", "length_prefixed"
+    else:
+        field_type, encoding = ENCODINGS.get(typ, (typ, None))
+
+    attribute = f"#[packet({encoding})]" if encoding is not None else ""
+    return f"{attribute} pub {name}: {field_type},"
+
+
+# Finds the names of the packets defined by hand in a packet module.
+def hand_written_packets(module):
+    names = set()
+    for path in glob(f"{PACKETS_PATH}/{module}/**/*.rs", recursive=True):
+        names.update(findall(r"pub struct (\w+)", open(path).read()))
+    return names
+
+
+def generate_packet(name, definition, module, hand_written):
+    fields = ""
+    for field in definition[1]:
+        typ = field_type(field["type"])
+        if typ is None or "name" not in field:
+            if name not in hand_written:
+                raise Exception(f"{module} packet {name} must be defined by hand")
+            return f"pub use crate::packets::{module}::{name};"
+        field_name = snake_case(field["name"])
+        field_name = RENAMES.get(field_name, field_name)
+        fields += generate_field(field_name, typ)
+
+    return f"""
+        #[derive(Debug, Clone, Packet)]
+        pub struct {name} {{ {fields} }}
+    """
+
+
+def generate_direction(direction, prefix):
+    module = prefix.lower()
+    hand_written = hand_written_packets(module)
+    packets = ""
+    registries = ""
+    for state, state_name in STATES.items():
+        types = data[state][direction]["types"]
+        mappings = types["packet"][1][0]["type"][1]["mappings"]
+        if len(mappings) == 0:
+            continue
+
+        variants = ""
+        for id, name in mappings.items():
+            struct = common.camel_case(name)
+            packets += generate_packet(struct, types[f"packet_{name}"], module, hand_written)
+            variants += f"{id} = {struct},"
+
+        registries += f"""
+            packet_enum!({prefix}{state_name}Packet in {state_name} {{
+                {variants}
+            }});
+        """
+
+    return f"""
+        pub mod {module} {{
+            use super::*;
+
+            {packets}
+
+            {registries}
+        }}
+    """
+
+
+data = common.load_minecraft_json("protocol.json", VERSION)
+
+output = "use super::*;"
+for direction, prefix in DIRECTIONS.items():
+    output += generate_direction(direction, prefix)
+
+common.output("../protocol/src/packets/generated.rs", output)
@@ -4,6 +4,7 @@ generators=(
   "entity"
   "inventory"
   "item"
+  "packets"
   "particle"
   "simplified_block"
 )
@@ -1481,3 +1481,119 @@ impl EntityKind {
         }
     }
 }
+#[allow(clippy::all)]
+impl EntityKind {
+    /// Returns the `is_living` property of this `EntityKind`.
+    pub fn is_living(&self) -> bool {
+        match self {
+            EntityKind::AreaEffectCloud => false,
+            EntityKind::ArmorStand => true,
+            EntityKind::Arrow => false,
+            EntityKind::Bat => true,
+            EntityKind::Bee => true,
+            EntityKind::Blaze => true,
+            EntityKind::Boat => false,
+            EntityKind::Cat => true,
+            EntityKind::CaveSpider => true,
+            EntityKind::Chicken => true,
+            EntityKind::Cod => true,
+            EntityKind::Cow => true,
+            EntityKind::Creeper => true,
+            EntityKind::Dolphin => true,
+            EntityKind::Donkey => true,
+            EntityKind::DragonFireball => false,
+            EntityKind::Drowned => true,
+            EntityKind::ElderGuardian => true,
+            EntityKind::EndCrystal => false,
+            EntityKind::EnderDragon => true,
+            EntityKind::Enderman => true,
+            EntityKind::Endermite => true,
+            EntityKind::Evoker => true,
+            EntityKind::EvokerFangs => false,
+            EntityKind::ExperienceOrb => false,
+            EntityKind::EyeOfEnder => false,
+            EntityKind::FallingBlock => false,
+            EntityKind::FireworkRocket => false,
+            EntityKind::Fox => true,
+            EntityKind::Ghast => true,
+            EntityKind::Giant => true,
+            EntityKind::Guardian => true,
+            EntityKind::Hoglin => true,
+            EntityKind::Horse => true,
+            EntityKind::Husk => true,
+            EntityKind::Illusioner => true,
+            EntityKind::IronGolem => true,
+            EntityKind::Item => false,
+            EntityKind::ItemFrame => false,
+            EntityKind::Fireball => false,
+            EntityKind::LeashKnot => false,
+            EntityKind::LightningBolt => false,
+            EntityKind::Llama => true,
+            EntityKind::LlamaSpit => false,
+            EntityKind::MagmaCube => true,
+            EntityKind::Minecart => false,
+            EntityKind::ChestMinecart => false,
+            EntityKind::CommandBlockMinecart => false,
+            EntityKind::FurnaceMinecart => false,
+            EntityKind::HopperMinecart => false,
+            EntityKind::SpawnerMinecart => false,
+            EntityKind::TntMinecart => false,
+            EntityKind::Mule => true,
+            EntityKind::Mooshroom => true,
+            EntityKind::Ocelot => true,
+            EntityKind::Painting => false,
+            EntityKind::Panda => true,
+            EntityKind::Parrot => true,
+            EntityKind::Phantom => true,
+            EntityKind::Pig => true,
+            EntityKind::Piglin => true,
+            EntityKind::PiglinBrute => true,
+            EntityKind::Pillager => true,
+            EntityKind::PolarBear => true,
+            EntityKind::Tnt => false,
+            EntityKind::Pufferfish => true,
+            EntityKind::Rabbit => true,
+            EntityKind::Ravager => true,
+            EntityKind::Salmon => true,
+            EntityKind::Sheep => true,
+            EntityKind::Shulker => true,
+            EntityKind::ShulkerBullet => false,
+            EntityKind::Silverfish => true,
+            EntityKind::Skeleton => true,
+            EntityKind::SkeletonHorse => true,
+            EntityKind::Slime => true,
+            EntityKind::SmallFireball => false,
+            EntityKind::SnowGolem => true,
+            EntityKind::Snowball => false,
+            EntityKind::SpectralArrow => false,
+            EntityKind::Spider => true,
+            EntityKind::Squid => true,
+            EntityKind::Stray => true,
+            EntityKind::Strider => true,
+            EntityKind::Egg => false,
+            EntityKind::EnderPearl => false,
+            EntityKind::ExperienceBottle => false,
+            EntityKind::Potion => false,
+            EntityKind::Trident => false,
+            EntityKind::TraderLlama => true,
+            EntityKind::TropicalFish => true,
+            EntityKind::Turtle => true,
+            EntityKind::Vex => true,
+            EntityKind::Villager => true,
+            EntityKind::Vindicator => true,
+            EntityKind::WanderingTrader => true,
+            EntityKind::Witch => true,
+            EntityKind::Wither => true,
+            EntityKind::WitherSkeleton => true,
+            EntityKind::WitherSkull => false,
+            EntityKind::Wolf => true,
+            EntityKind::Zoglin => true,
+            EntityKind::Zombie => true,
+            EntityKind::ZombieHorse => true,
+            EntityKind::ZombieVillager => true,
+            EntityKind::ZombifiedPiglin => true,
+            EntityKind::Player => true,
+            EntityKind::FishingBobber => false,
+        }
+    }
+}
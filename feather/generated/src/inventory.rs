@@ -41,6 +41,9 @@ pub enum Area {
     LoomOutput,
     StonecutterInput,
     StonecutterOutput,
+    SmithingBase,
+    SmithingAddition,
+    SmithingOutput,
 }
 #[derive(Debug, Clone)]
 pub enum Window {
@@ -136,6 +139,10 @@ pub enum Window {
         stonecutter: crate::Inventory,
         player: crate::Inventory,
     },
+    Smithing {
+        smithing_table: crate::Inventory,
+        player: crate::Inventory,
+    },
 }
 impl Window {
     #[allow(unused_comparisons)]
@@ -665,6 +672,34 @@ impl Window {
                     None
                 }
             }
+            Window::Smithing {
+                smithing_table,
+                player,
+            } => {
+                if index >= 0 && index < 1 {
+                    let area = Area::SmithingBase;
+                    let slot = index - 0;
+                    Some((smithing_table, area, slot))
+                } else if index >= 1 && index < 2 {
+                    let area = Area::SmithingAddition;
+                    let slot = index - 1;
+                    Some((smithing_table, area, slot))
+                } else if index >= 2 && index < 3 {
+                    let area = Area::SmithingOutput;
+                    let slot = index - 2;
+                    Some((smithing_table, area, slot))
+                } else if index >= 3 && index < 30 {
+                    let area = Area::Storage;
+                    let slot = index - 3;
+                    Some((player, area, slot))
+                } else if index >= 30 && index < 39 {
+                    let area = Area::Hotbar;
+                    let slot = index - 30;
+                    Some((player, area, slot))
+                } else {
+                    None
+                }
+            }
         }
     }
     pub fn slot_to_index(
@@ -1006,6 +1041,24 @@ impl Window {
                     None
                 }
             }
+            Window::Smithing {
+                smithing_table,
+                player,
+            } => {
+                if area == Area::SmithingBase && smithing_table.ptr_eq(inventory) {
+                    Some(slot + 0)
+                } else if area == Area::SmithingAddition && smithing_table.ptr_eq(inventory) {
+                    Some(slot + 1)
+                } else if area == Area::SmithingOutput && smithing_table.ptr_eq(inventory) {
+                    Some(slot + 2)
+                } else if area == Area::Storage && player.ptr_eq(inventory) {
+                    Some(slot + 3)
+                } else if area == Area::Hotbar && player.ptr_eq(inventory) {
+                    Some(slot + 30)
+                } else {
+                    None
+                }
+            }
         }
     }
 }
@@ -1038,6 +1091,7 @@ impl Window {
             Window::Lectern { .. } => "lectern",
             Window::Loom { .. } => "loom",
             Window::Stonecutter { .. } => "stonecutter",
+            Window::Smithing { .. } => "smithing",
         }
     }
 }
@@ -1065,6 +1119,27 @@ pub enum InventoryBacking<T> {
         furnace_fuel: [T; 1],
         furnace_output: [T; 1],
     },
+    EnchantmentTable {
+        enchantment_item: [T; 1],
+        enchantment_lapis: [T; 1],
+    },
+    Stonecutter {
+        stonecutter_input: [T; 1],
+        stonecutter_output: [T; 1],
+    },
+    SmithingTable {
+        smithing_base: [T; 1],
+        smithing_addition: [T; 1],
+        smithing_output: [T; 1],
+    },
+    CartographyTable {
+        cartography_map: [T; 1],
+        cartography_paper: [T; 1],
+        cartography_output: [T; 1],
+    },
+    Beacon {
+        beacon_payment: [T; 1],
+    },
 }
 impl<T> InventoryBacking<T> {
     pub fn area_slice(&self, area: Area) -> Option<&[T]> {
@@ -1111,6 +1186,46 @@ impl<T> InventoryBacking<T> {
                 Area::FurnaceOutput => Some(furnace_output.as_ref()),
                 _ => None,
             },
+            InventoryBacking::EnchantmentTable {
+                enchantment_item,
+                enchantment_lapis,
+            } => match area {
+                Area::EnchantmentItem => Some(enchantment_item.as_ref()),
+                Area::EnchantmentLapis => Some(enchantment_lapis.as_ref()),
+                _ => None,
+            },
+            InventoryBacking::Stonecutter {
+                stonecutter_input,
+                stonecutter_output,
+            } => match area {
+                Area::StonecutterInput => Some(stonecutter_input.as_ref()),
+                Area::StonecutterOutput => Some(stonecutter_output.as_ref()),
+                _ => None,
+            },
+            InventoryBacking::SmithingTable {
+                smithing_base,
+                smithing_addition,
+                smithing_output,
+            } => match area {
+                Area::SmithingBase => Some(smithing_base.as_ref()),
+                Area::SmithingAddition => Some(smithing_addition.as_ref()),
+                Area::SmithingOutput => Some(smithing_output.as_ref()),
+                _ => None,
+            },
+            InventoryBacking::CartographyTable {
+                cartography_map,
+                cartography_paper,
+                cartography_output,
+            } => match area {
+                Area::CartographyMap => Some(cartography_map.as_ref()),
+                Area::CartographyPaper => Some(cartography_paper.as_ref()),
+                Area::CartographyOutput => Some(cartography_output.as_ref()),
+                _ => None,
+            },
+            InventoryBacking::Beacon { beacon_payment } => match area {
+                Area::BeaconPayment => Some(beacon_payment.as_ref()),
+                _ => None,
+            },
         }
     }
     pub fn areas(&self) -> &'static [Area] {
@@ -1144,6 +1259,34 @@ impl<T> InventoryBacking<T> {
                 ];
                 &AREAS
             }
+            InventoryBacking::EnchantmentTable { .. } => {
+                static AREAS: [Area; 2] = [Area::EnchantmentItem, Area::EnchantmentLapis];
+                &AREAS
+            }
+            InventoryBacking::Stonecutter { .. } => {
+                static AREAS: [Area; 2] = [Area::StonecutterInput, Area::StonecutterOutput];
+                &AREAS
+            }
+            InventoryBacking::SmithingTable { .. } => {
+                static AREAS: [Area; 3] = [
+                    Area::SmithingBase,
+                    Area::SmithingAddition,
+                    Area::SmithingOutput,
+                ];
+                &AREAS
+            }
+            InventoryBacking::CartographyTable { .. } => {
+                static AREAS: [Area; 3] = [
+                    Area::CartographyMap,
+                    Area::CartographyPaper,
+                    Area::CartographyOutput,
+                ];
+                &AREAS
+            }
+            InventoryBacking::Beacon { .. } => {
+                static AREAS: [Area; 1] = [Area::BeaconPayment];
+                &AREAS
+            }
         }
     }
     pub fn player() -> Self
@@ -1188,6 +1331,52 @@ impl<T> InventoryBacking<T> {
             furnace_output: Default::default(),
         }
     }
+    pub fn enchantment_table() -> Self
+    where
+        T: Default,
+    {
+        InventoryBacking::EnchantmentTable {
+            enchantment_item: Default::default(),
+            enchantment_lapis: Default::default(),
+        }
+    }
+    pub fn stonecutter() -> Self
+    where
+        T: Default,
+    {
+        InventoryBacking::Stonecutter {
+            stonecutter_input: Default::default(),
+            stonecutter_output: Default::default(),
+        }
+    }
+    pub fn smithing_table() -> Self
+    where
+        T: Default,
+    {
+        InventoryBacking::SmithingTable {
+            smithing_base: Default::default(),
+            smithing_addition: Default::default(),
+            smithing_output: Default::default(),
+        }
+    }
+    pub fn cartography_table() -> Self
+    where
+        T: Default,
+    {
+        InventoryBacking::CartographyTable {
+            cartography_map: Default::default(),
+            cartography_paper: Default::default(),
+            cartography_output: Default::default(),
+        }
+    }
+    pub fn beacon() -> Self
+    where
+        T: Default,
+    {
+        InventoryBacking::Beacon {
+            beacon_payment: Default::default(),
+        }
+    }
 }
 impl crate::Inventory {
     pub fn player() -> Self {
@@ -1210,4 +1399,29 @@ impl crate::Inventory {
             backing: std::sync::Arc::new(InventoryBacking::furnace()),
         }
     }
+    pub fn enchantment_table() -> Self {
+        Self {
+            backing: std::sync::Arc::new(InventoryBacking::enchantment_table()),
+        }
+    }
+    pub fn stonecutter() -> Self {
+        Self {
+            backing: std::sync::Arc::new(InventoryBacking::stonecutter()),
+        }
+    }
+    pub fn smithing_table() -> Self {
+        Self {
+            backing: std::sync::Arc::new(InventoryBacking::smithing_table()),
+        }
+    }
+    pub fn cartography_table() -> Self {
+        Self {
+            backing: std::sync::Arc::new(InventoryBacking::cartography_table()),
+        }
+    }
+    pub fn beacon() -> Self {
+        Self {
+            backing: std::sync::Arc::new(InventoryBacking::beacon()),
+        }
+    }
 }
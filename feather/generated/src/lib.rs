@@ -31,6 +31,32 @@ pub struct ItemStack {
 
     /// Damage to the item, if it's damageable.
     pub damage: Option<u32>,
+
+    /// Display name, lore and enchantments of the item.
+    pub meta: Option<ItemStackMeta>,
+}
+
+/// Additional data attached to an `ItemStack`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemStackMeta {
+    /// Custom name of the item, as a JSON text component.
+    pub display_name: Option<String>,
+    /// Lines of lore, each a JSON text component.
+    pub lore: Vec<String>,
+    pub enchantments: Vec<Enchantment>,
+    /// Enchantments stored in an enchanted book, which
+    /// can be applied to other items.
+    pub stored_enchantments: Vec<Enchantment>,
+    /// ID of the map shown by a filled map.
+    pub map_id: Option<i32>,
+}
+
+/// An enchantment applied to an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enchantment {
+    /// Namespaced ID of the enchantment, e.g. `minecraft:sharpness`.
+    pub id: String,
+    pub level: u16,
 }
 
 impl ItemStack {
@@ -40,6 +66,7 @@ impl ItemStack {
             item,
             count,
             damage: item.durability().map(|_| 0),
+            meta: None,
         }
     }
 
@@ -47,7 +74,7 @@ impl ItemStack {
     /// the same type as (but not necessarily the same
     /// amount as) `self`.
     pub fn has_same_type(&self, other: &ItemStack) -> bool {
-        other.item == self.item && other.damage == self.damage
+        other.item == self.item && other.damage == self.damage && other.meta == self.meta
     }
 
     /// Returns the item type for this `ItemStack`.
@@ -5,19 +5,23 @@ use std::{
     mem::size_of,
     panic::AssertUnwindSafe,
     ptr::NonNull,
-    sync::atomic::{AtomicBool, Ordering},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
+use ahash::AHashMap;
 use anyhow::anyhow;
 use bytemuck::{Pod, Zeroable};
 use feather_common::Game;
 use feather_ecs::EntityBuilder;
-use quill_common::Component;
+use quill_common::{entity_init::EntityInit, events::EventPriority, Component};
 use serde::de::DeserializeOwned;
 use vec_arena::Arena;
 use wasmer::{FromToNativeWasmType, Instance};
 
-use crate::{host_function::WasmHostFunction, thread_pinned::ThreadPinned, PluginId};
+use crate::{
+    goal::DroppedGoals, host_function::WasmHostFunction, quota::PluginQuotas,
+    thread_pinned::ThreadPinned, PluginId,
+};
 
 mod native;
 mod wasm;
@@ -179,30 +183,84 @@ pub struct PluginContext {
     /// ID of the plugin.
     id: PluginId,
 
+    /// Identifier from the plugin's metadata.
+    identifier: String,
+
+    /// Resource limits for the plugin.
+    quotas: PluginQuotas,
+
+    /// Number of systems the plugin has registered.
+    registered_systems: AtomicUsize,
+
+    /// Number of scheduled tasks the plugin has registered.
+    registered_tasks: AtomicUsize,
+
     /// Active entity builders for the plugin.
     pub entity_builders: ThreadPinned<Arena<EntityBuilder>>,
+
+    /// State handed off between instances
+    /// of the plugin when it is reloaded.
+    handed_off_state: ThreadPinned<Option<Vec<u8>>>,
+
+    /// Event handlers registered by the plugin.
+    event_handlers: ThreadPinned<Vec<EventHandler>>,
+
+    /// Vanilla types of the custom entity types
+    /// registered by the plugin, keyed by namespaced name.
+    custom_entities: ThreadPinned<AHashMap<String, EntityInit>>,
+
+    /// Goals of the plugin which were removed
+    /// and still need to be freed by the plugin.
+    dropped_goals: DroppedGoals,
+}
+
+/// An event handler registered by a plugin.
+///
+/// Invoked like a system, but by the event handler dispatch
+/// system rather than the system executor.
+#[derive(Clone)]
+pub struct EventHandler {
+    pub name: String,
+    pub priority: EventPriority,
+    pub data: PluginPtrMut<u8>,
 }
 
 impl PluginContext {
     /// Creates a new WASM plugin context.
-    pub fn new_wasm(id: PluginId) -> Self {
+    pub fn new_wasm(id: PluginId, identifier: String, quotas: PluginQuotas) -> Self {
         Self {
             inner: Inner::Wasm(ThreadPinned::new(wasm::WasmPluginContext::new())),
             invoking_on_main_thread: AtomicBool::new(false),
             game: ThreadPinned::new(None),
             id,
+            identifier,
+            quotas,
+            registered_systems: AtomicUsize::new(0),
+            registered_tasks: AtomicUsize::new(0),
             entity_builders: ThreadPinned::new(Arena::new()),
+            handed_off_state: ThreadPinned::new(None),
+            event_handlers: ThreadPinned::new(Vec::new()),
+            custom_entities: ThreadPinned::new(AHashMap::new()),
+            dropped_goals: DroppedGoals::default(),
         }
     }
 
     /// Creates a new native plugin context.
-    pub fn new_native(id: PluginId) -> Self {
+    pub fn new_native(id: PluginId, identifier: String, quotas: PluginQuotas) -> Self {
         Self {
             inner: Inner::Native(native::NativePluginContext::new()),
             invoking_on_main_thread: AtomicBool::new(false),
             game: ThreadPinned::new(None),
             id,
+            identifier,
+            quotas,
+            registered_systems: AtomicUsize::new(0),
+            registered_tasks: AtomicUsize::new(0),
             entity_builders: ThreadPinned::new(Arena::new()),
+            handed_off_state: ThreadPinned::new(None),
+            event_handlers: ThreadPinned::new(Vec::new()),
+            custom_entities: ThreadPinned::new(AHashMap::new()),
+            dropped_goals: DroppedGoals::default(),
         }
     }
 
@@ -237,6 +295,11 @@ impl PluginContext {
         result.unwrap()
     }
 
+    /// Returns whether the plugin is currently being invoked.
+    pub fn is_entered(&self) -> bool {
+        self.invoking_on_main_thread.load(Ordering::SeqCst)
+    }
+
     /// Gets a mutable reference to the `Game`.
     ///
     /// # Panics
@@ -256,11 +319,117 @@ impl PluginContext {
         })
     }
 
+    /// Gets the goals of the plugin which were
+    /// removed and still need to be freed.
+    pub fn dropped_goals(&self) -> DroppedGoals {
+        self.dropped_goals.clone()
+    }
+
     /// Gets the plugin ID.
     pub fn plugin_id(&self) -> PluginId {
         self.id
     }
 
+    /// Gets the identifier from the plugin's metadata.
+    pub fn identifier(&self) -> &str {
+        &self.identifier
+    }
+
+    /// Gets the prefix of the names of systems
+    /// registered by the plugin.
+    ///
+    /// Includes the plugin ID, so the systems of an instance
+    /// being reloaded can be told apart from its replacement's.
+    pub fn system_name_prefix(&self) -> String {
+        format!("{}#{}/", self.identifier, self.id.0)
+    }
+
+    /// Stores state to hand off to the next
+    /// instance of the plugin.
+    pub fn store_state(&self, state: Vec<u8>) {
+        *self.handed_off_state.borrow_mut() = Some(state);
+    }
+
+    /// Takes the state handed off by the previous
+    /// instance of the plugin.
+    pub fn take_state(&self) -> Option<Vec<u8>> {
+        self.handed_off_state.borrow_mut().take()
+    }
+
+    /// Registers a custom entity type, returning its namespaced name.
+    pub fn register_custom_entity(&self, name: &str, disguise: EntityInit) -> String {
+        let kind = format!("{}:{}", self.identifier, name);
+        self.custom_entities
+            .borrow_mut()
+            .insert(kind.clone(), disguise);
+        kind
+    }
+
+    /// Gets the vanilla type of a custom entity type
+    /// registered by the plugin.
+    pub fn custom_entity_disguise(&self, kind: &str) -> Option<EntityInit> {
+        self.custom_entities.borrow().get(kind).copied()
+    }
+
+    pub fn add_event_handler(&self, handler: EventHandler) {
+        self.event_handlers.borrow_mut().push(handler);
+    }
+
+    /// Gets the event handlers registered by the plugin,
+    /// in registration order.
+    pub fn event_handlers(&self) -> Ref<Vec<EventHandler>> {
+        self.event_handlers.borrow()
+    }
+
+    /// Gets the resource limits for the plugin.
+    pub fn quotas(&self) -> &PluginQuotas {
+        &self.quotas
+    }
+
+    /// Counts a newly registered system against the
+    /// plugin's quota.
+    ///
+    /// Returns an error if the plugin has already
+    /// registered its maximum number of systems.
+    pub fn count_system(&self) -> anyhow::Result<usize> {
+        let count = self.registered_systems.fetch_add(1, Ordering::SeqCst) + 1;
+        if count > self.quotas.max_systems {
+            self.registered_systems.fetch_sub(1, Ordering::SeqCst);
+            return Err(anyhow!(
+                "plugin exceeded its quota of {} systems",
+                self.quotas.max_systems
+            ));
+        }
+        Ok(count)
+    }
+
+    /// Gets the number of systems the plugin has registered.
+    pub fn registered_systems(&self) -> usize {
+        self.registered_systems.load(Ordering::SeqCst)
+    }
+
+    /// Counts a newly scheduled task against the
+    /// plugin's quota.
+    ///
+    /// Returns an error if the plugin has already
+    /// scheduled its maximum number of tasks.
+    pub fn count_task(&self) -> anyhow::Result<usize> {
+        let count = self.registered_tasks.fetch_add(1, Ordering::SeqCst) + 1;
+        if count > self.quotas.max_tasks {
+            self.registered_tasks.fetch_sub(1, Ordering::SeqCst);
+            return Err(anyhow!(
+                "plugin exceeded its quota of {} scheduled tasks",
+                self.quotas.max_tasks
+            ));
+        }
+        Ok(count)
+    }
+
+    /// Gets the number of scheduled tasks the plugin has registered.
+    pub fn registered_tasks(&self) -> usize {
+        self.registered_tasks.load(Ordering::SeqCst)
+    }
+
     /// Accesses a byte slice in the plugin's memory space.
     ///
     /// # Safety
@@ -0,0 +1,98 @@
+//! Economy providers implemented by plugins.
+
+use std::{cell::RefCell, rc::Rc};
+
+use feather_common::{economy::EconomyProvider, Game};
+use feather_ecs::HasResources;
+use quill_common::economy::{Currency, EconomyError, EconomyOp};
+use uuid::Uuid;
+
+use crate::{PluginId, PluginManager};
+
+/// A provider whose operations are invoked through
+/// the plugin's `quill_run_economy` export.
+///
+/// Operations fail with [`EconomyError::ProviderFailure`]
+/// once the plugin is unloaded.
+pub struct PluginEconomy {
+    plugin: PluginId,
+    name: String,
+    currency: Currency,
+}
+
+impl PluginEconomy {
+    pub fn new(plugin: PluginId, name: String, currency: Currency) -> Self {
+        Self {
+            plugin,
+            name,
+            currency,
+        }
+    }
+
+    fn run(
+        &self,
+        game: &mut Game,
+        op: EconomyOp,
+        account: Uuid,
+        amount: i64,
+    ) -> Result<i64, EconomyError> {
+        let result = game
+            .resources
+            .get::<Rc<RefCell<PluginManager>>>()
+            .map(|manager| Rc::clone(&*manager))
+            .map_err(anyhow::Error::from)
+            .and_then(|manager| {
+                let manager = manager.borrow();
+                match manager.plugin(self.plugin) {
+                    Some(plugin) => plugin.run_economy(game, op, account, amount),
+                    None => anyhow::bail!("the plugin was unloaded"),
+                }
+            });
+        result.unwrap_or_else(|e| {
+            log::error!("Economy provider {} failed: {:?}", self.name, e);
+            Err(EconomyError::ProviderFailure)
+        })
+    }
+}
+
+impl EconomyProvider for PluginEconomy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn balance(&self, game: &mut Game, account: Uuid) -> i64 {
+        self.run(game, EconomyOp::Balance, account, 0).unwrap_or(0)
+    }
+
+    fn set_balance(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        balance: i64,
+    ) -> Result<(), EconomyError> {
+        self.run(game, EconomyOp::SetBalance, account, balance)
+            .map(drop)
+    }
+
+    fn deposit(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        amount: i64,
+    ) -> Result<i64, EconomyError> {
+        self.run(game, EconomyOp::Deposit, account, amount)
+    }
+
+    fn withdraw(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        amount: i64,
+    ) -> Result<i64, EconomyError> {
+        self.run(game, EconomyOp::Withdraw, account, amount)
+    }
+}
@@ -0,0 +1,103 @@
+//! Runs the event handlers registered by plugins.
+//!
+//! Unlike systems, which run in the order plugins registered
+//! them, the event handlers of all plugins run in a single
+//! pass ordered by their `EventPriority`.
+
+use std::{cell::RefCell, rc::Rc};
+
+use feather_common::Game;
+use feather_ecs::{HasResources, SysResult};
+
+use crate::{context::EventHandler, PluginId, PluginManager};
+
+/// Name of the system which runs all event handlers.
+pub const SYSTEM_NAME: &str = "plugin_event_handlers";
+
+/// Runs every event handler of every plugin.
+pub fn run_event_handlers(game: &mut Game) -> SysResult {
+    let manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+    let manager = manager.borrow();
+
+    let mut handlers = Vec::new();
+    for plugin in manager.plugins() {
+        let id = plugin.context().plugin_id();
+        handlers.extend(
+            plugin
+                .context()
+                .event_handlers()
+                .iter()
+                .map(|handler| (id, handler.clone())),
+        );
+    }
+    sort_handlers(&mut handlers);
+
+    for (id, handler) in handlers {
+        let plugin = match manager.plugin(id) {
+            Some(plugin) => plugin,
+            None => continue,
+        };
+        // One failing handler shouldn't keep
+        // the others from observing the event.
+        if let Err(e) = plugin.run_system(game, handler.data) {
+            log::error!(
+                "Event handler {} of plugin {} failed: {:?}",
+                handler.name,
+                plugin.metadata().name,
+                e
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Sorts handlers by priority. The sort is stable, so handlers
+/// with the same priority keep plugin load order, then
+/// registration order.
+fn sort_handlers(handlers: &mut [(PluginId, EventHandler)]) {
+    handlers.sort_by_key(|(_, handler)| handler.priority);
+}
+
+#[cfg(test)]
+mod tests {
+    use quill_common::events::EventPriority;
+
+    use super::*;
+    use crate::context::PluginPtrMut;
+
+    fn handler(name: &str, priority: EventPriority) -> EventHandler {
+        EventHandler {
+            name: name.to_owned(),
+            priority,
+            data: unsafe { PluginPtrMut::null() },
+        }
+    }
+
+    #[test]
+    fn handlers_run_by_priority() {
+        let mut handlers = vec![
+            (PluginId(0), handler("monitor", EventPriority::Monitor)),
+            (PluginId(0), handler("first_normal", EventPriority::Normal)),
+            (PluginId(0), handler("highest", EventPriority::Highest)),
+            (PluginId(1), handler("lowest", EventPriority::Lowest)),
+            (PluginId(1), handler("second_normal", EventPriority::Normal)),
+        ];
+        sort_handlers(&mut handlers);
+
+        let names: Vec<&str> = handlers
+            .iter()
+            .map(|(_, handler)| handler.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "lowest",
+                "first_normal",
+                "second_normal",
+                "highest",
+                "monitor"
+            ]
+        );
+    }
+}
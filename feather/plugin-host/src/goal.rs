@@ -0,0 +1,141 @@
+//! Mob AI goals implemented by plugins.
+
+use std::{
+    cell::RefCell,
+    marker::PhantomData,
+    mem,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use feather_common::{ai::Goal, Game};
+use feather_ecs::{Entity, HasResources, SysResult};
+use quill_common::goals::GoalOp;
+
+use crate::{context::PluginPtrMut, PluginId, PluginManager};
+
+/// A goal whose callbacks are invoked through
+/// the plugin's `quill_run_goal` export.
+///
+/// Its name is prefixed with the plugin's identifier,
+/// so the goal can be removed when the plugin is reloaded.
+pub struct PluginGoal {
+    plugin: PluginId,
+    data: PluginPtrMut<u8>,
+    dropped: DroppedGoals,
+}
+
+// SAFETY: `data` is never dereferenced by the host;
+// it's only passed back to the plugin on the main thread.
+unsafe impl Send for PluginGoal {}
+unsafe impl Sync for PluginGoal {}
+
+impl PluginGoal {
+    pub fn new(plugin: PluginId, data: PluginPtrMut<u8>, dropped: DroppedGoals) -> Self {
+        Self {
+            plugin,
+            data,
+            dropped,
+        }
+    }
+
+    fn run(&self, game: &mut Game, entity: Entity, op: GoalOp) -> anyhow::Result<u32> {
+        let manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+        let manager = manager.borrow();
+        match manager.plugin(self.plugin) {
+            Some(plugin) => plugin.run_goal(game, self.data, entity, op),
+            None => Ok(0),
+        }
+    }
+}
+
+impl Goal for PluginGoal {
+    fn can_start(&mut self, game: &mut Game, entity: Entity) -> SysResult<bool> {
+        Ok(self.run(game, entity, GoalOp::CanStart)? != 0)
+    }
+
+    fn should_continue(&mut self, game: &mut Game, entity: Entity) -> SysResult<bool> {
+        Ok(self.run(game, entity, GoalOp::ShouldContinue)? != 0)
+    }
+
+    fn start(&mut self, game: &mut Game, entity: Entity) -> SysResult {
+        self.run(game, entity, GoalOp::Start).map(drop)
+    }
+
+    fn tick(&mut self, game: &mut Game, entity: Entity) -> SysResult {
+        self.run(game, entity, GoalOp::Tick).map(drop)
+    }
+
+    fn stop(&mut self, game: &mut Game, entity: Entity) -> SysResult {
+        self.run(game, entity, GoalOp::Stop).map(drop)
+    }
+}
+
+impl Drop for PluginGoal {
+    fn drop(&mut self) {
+        self.dropped.push(self.data);
+    }
+}
+
+/// Data pointers of a plugin's goals which were removed.
+///
+/// Goals can be dropped while the plugin is running, so
+/// the plugin frees them later, in [`free_dropped_goals`].
+#[derive(Clone, Default)]
+pub struct DroppedGoals(Arc<Mutex<Vec<u64>>>);
+
+impl DroppedGoals {
+    fn push(&self, data: PluginPtrMut<u8>) {
+        self.0.lock().unwrap().push(data.ptr);
+    }
+
+    /// Takes the data pointers of the goals dropped so far.
+    pub fn take(&self) -> Vec<PluginPtrMut<u8>> {
+        mem::take(&mut *self.0.lock().unwrap())
+            .into_iter()
+            .map(|ptr| PluginPtrMut {
+                ptr,
+                _marker: PhantomData,
+            })
+            .collect()
+    }
+}
+
+/// Lets each plugin free the goals dropped since the last tick.
+///
+/// The goals of an unloaded plugin instance are never freed,
+/// since its memory is discarded with it.
+pub fn free_dropped_goals(game: &mut Game) -> SysResult {
+    let manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+    let manager = manager.borrow();
+    for plugin in manager.plugins() {
+        if let Err(e) = plugin.free_dropped_goals(game) {
+            log::error!(
+                "Plugin {} failed to free its goals: {:?}",
+                plugin.metadata().name,
+                e
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropped_goals_are_queued() {
+        let dropped = DroppedGoals::default();
+        let data = |ptr| PluginPtrMut {
+            ptr,
+            _marker: PhantomData,
+        };
+        drop(PluginGoal::new(PluginId(0), data(8), dropped.clone()));
+        drop(PluginGoal::new(PluginId(0), data(16), dropped.clone()));
+
+        let freed: Vec<u64> = dropped.take().iter().map(|data| data.ptr).collect();
+        assert_eq!(freed, vec![8, 16]);
+        assert!(dropped.take().is_empty());
+    }
+}
@@ -10,10 +10,16 @@ use crate::host_function::{NativeHostFunction, WasmHostFunction};
 
 mod block;
 mod component;
+mod custom_entity;
+mod economy;
 mod entity;
 mod entity_builder;
+mod fake_entity;
+mod goal;
 mod plugin_message;
+mod plugin_state;
 mod query;
+mod region;
 mod system;
 
 macro_rules! host_calls {
@@ -47,14 +53,22 @@ macro_rules! host_calls {
 
 use block::*;
 use component::*;
+use custom_entity::*;
+use economy::*;
 use entity::*;
 use entity_builder::*;
+use fake_entity::*;
+use goal::*;
 use plugin_message::*;
+use plugin_state::*;
 use query::*;
+use region::*;
 use system::*;
 
 host_calls! {
     "register_system" => register_system,
+    "register_task" => register_task,
+    "register_event_handler" => register_event_handler,
     "entity_get_component" => entity_get_component,
     "entity_set_component" => entity_set_component,
     "entity_builder_new_empty" => entity_builder_new_empty,
@@ -65,8 +79,34 @@ host_calls! {
     "entity_exists" => entity_exists,
     "entity_send_message" => entity_send_message,
     "entity_send_title" => entity_send_title,
+    "entity_goals" => entity_goals,
+    "entity_remove_goal" => entity_remove_goal,
+    "entity_add_goal" => entity_add_goal,
+    "register_custom_entity" => register_custom_entity,
+    "entity_builder_new_custom" => entity_builder_new_custom,
+    "entity_set_metadata" => entity_set_metadata,
+    "entity_get_tag" => entity_get_tag,
+    "entity_set_tag" => entity_set_tag,
+    "entity_remove_tag" => entity_remove_tag,
+    "entity_spawn_fake" => entity_spawn_fake,
+    "fake_entity_move" => fake_entity_move,
+    "fake_entity_set_text" => fake_entity_set_text,
+    "fake_entity_remove" => fake_entity_remove,
     "block_get" => block_get,
     "block_set" => block_set,
     "block_fill_chunk_section" => block_fill_chunk_section,
+    "entity_set_fake_block" => entity_set_fake_block,
+    "entity_restore_fake_block" => entity_restore_fake_block,
+    "entity_restore_fake_blocks" => entity_restore_fake_blocks,
     "plugin_message_send" => plugin_message_send,
+    "plugin_state_store" => plugin_state_store,
+    "plugin_state_take" => plugin_state_take,
+    "region_define" => region_define,
+    "region_remove" => region_remove,
+    "world_mob_spawning_allowed" => world_mob_spawning_allowed,
+    "economy_balance" => economy_balance,
+    "economy_deposit" => economy_deposit,
+    "economy_withdraw" => economy_withdraw,
+    "economy_currency" => economy_currency,
+    "economy_register_provider" => economy_register_provider,
 }
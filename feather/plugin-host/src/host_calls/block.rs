@@ -1,6 +1,9 @@
+use anyhow::Context;
 use feather_base::{BlockId, BlockPosition, ChunkPosition};
+use feather_common::fake_blocks::FakeBlocks;
+use feather_ecs::Entity;
 use feather_plugin_host_macros::host_function;
-use quill_common::block::BlockGetResult;
+use quill_common::{block::BlockGetResult, entities::Player};
 
 use crate::context::PluginContext;
 
@@ -38,3 +41,62 @@ pub fn block_fill_chunk_section(
         .fill_chunk_section(chunk_pos, section_y as usize, block);
     Ok(was_successful as u32)
 }
+
+#[host_function]
+pub fn entity_set_fake_block(
+    cx: &PluginContext,
+    player: u64,
+    x: i32,
+    y: i32,
+    z: i32,
+    block_id: u16,
+) -> anyhow::Result<()> {
+    let pos = BlockPosition::new(x, y, z);
+    let block = BlockId::from_vanilla_id(block_id);
+
+    let player = Entity::from_bits(player);
+    let mut game = cx.game_mut();
+    game.ecs
+        .get::<Player>(player)
+        .context("fake blocks can only be shown to players")?;
+    let existing = game.ecs.get_mut::<FakeBlocks>(player);
+    if let Ok(mut fake_blocks) = existing {
+        fake_blocks.set(pos, block);
+    } else {
+        drop(existing);
+        let mut fake_blocks = FakeBlocks::new();
+        fake_blocks.set(pos, block);
+        game.ecs.insert(player, fake_blocks)?;
+    }
+
+    Ok(())
+}
+
+#[host_function]
+pub fn entity_restore_fake_block(
+    cx: &PluginContext,
+    player: u64,
+    x: i32,
+    y: i32,
+    z: i32,
+) -> anyhow::Result<u32> {
+    let pos = BlockPosition::new(x, y, z);
+    let restored = cx
+        .game_mut()
+        .ecs
+        .get_mut::<FakeBlocks>(Entity::from_bits(player))
+        .map_or(false, |mut fake_blocks| fake_blocks.restore(pos));
+    Ok(restored as u32)
+}
+
+#[host_function]
+pub fn entity_restore_fake_blocks(cx: &PluginContext, player: u64) -> anyhow::Result<()> {
+    if let Ok(mut fake_blocks) = cx
+        .game_mut()
+        .ecs
+        .get_mut::<FakeBlocks>(Entity::from_bits(player))
+    {
+        fake_blocks.restore_all();
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use anyhow::{bail, Context};
+use feather_base::{metadata::MetaEntry, EntityMetadata, Position};
+use feather_common::persistent_tags::PersistentTags;
+use feather_ecs::Entity;
+use feather_plugin_host_macros::host_function;
+use quill_common::{components::CustomEntityKind, metadata::MetadataValue};
+
+use crate::context::{PluginContext, PluginPtr, PluginPtrMut};
+
+#[host_function]
+pub fn register_custom_entity(
+    cx: &PluginContext,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+    disguise_ptr: PluginPtr<u8>,
+    disguise_len: u32,
+    kind_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    kind_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    if name.is_empty() || name.contains(':') {
+        bail!("invalid custom entity name '{}'", name);
+    }
+    let disguise = cx.read_bincode(disguise_ptr, disguise_len)?;
+    let kind = cx.register_custom_entity(&name, disguise);
+
+    let kind_ptr = cx.bump_allocate_and_write_bytes(kind.as_bytes())?;
+    cx.write_pod(kind_ptr_ptr, kind_ptr)?;
+    cx.write_pod(kind_len_ptr, kind.len() as u32)?;
+
+    Ok(())
+}
+
+#[host_function]
+pub fn entity_builder_new_custom(
+    cx: &PluginContext,
+    position: PluginPtr<Position>,
+    kind_ptr: PluginPtr<u8>,
+    kind_len: u32,
+) -> anyhow::Result<u32> {
+    let position = cx.read_pod(position)?;
+    let kind = cx.read_string(kind_ptr, kind_len)?;
+    // Plugins can only spawn their own custom entities.
+    let disguise = cx
+        .custom_entity_disguise(&kind)
+        .with_context(|| format!("custom entity type '{}' is not registered", kind))?;
+
+    let mut builder = cx.game_mut().create_entity_builder(position, disguise);
+    builder.add(CustomEntityKind(kind));
+    let id = cx.entity_builders.borrow_mut().insert(builder);
+
+    if id > u32::MAX as usize {
+        bail!("created too many entity builders");
+    }
+
+    Ok(id as u32)
+}
+
+#[host_function]
+pub fn entity_set_metadata(
+    cx: &PluginContext,
+    entity: u64,
+    index: u32,
+    value_ptr: PluginPtr<u8>,
+    value_len: u32,
+) -> anyhow::Result<()> {
+    if index > u8::MAX as u32 {
+        bail!("metadata index {} out of range", index);
+    }
+    let value: MetadataValue = cx.read_bincode(value_ptr, value_len)?;
+    let entry = match value {
+        MetadataValue::Byte(value) => MetaEntry::Byte(value),
+        MetadataValue::VarInt(value) => MetaEntry::VarInt(value),
+        MetadataValue::Float(value) => MetaEntry::Float(value),
+        MetadataValue::String(value) => MetaEntry::String(value),
+        MetadataValue::Chat(value) => MetaEntry::Chat(value),
+        MetadataValue::OptChat(value) => MetaEntry::OptChat(value),
+        MetadataValue::Boolean(value) => MetaEntry::Boolean(value),
+        MetadataValue::Pose(value) => MetaEntry::Pose(value),
+    };
+
+    let entity = Entity::from_bits(entity);
+    let mut game = cx.game_mut();
+    let existing = game.ecs.get_mut::<EntityMetadata>(entity);
+    if let Ok(mut metadata) = existing {
+        metadata.set(index as u8, entry);
+    } else {
+        drop(existing);
+        let metadata = EntityMetadata::entity_base().with(index as u8, entry);
+        let _ = game.ecs.insert(entity, metadata);
+    }
+
+    Ok(())
+}
+
+#[host_function]
+pub fn entity_get_tag(
+    cx: &PluginContext,
+    entity: u64,
+    key_ptr: PluginPtr<u8>,
+    key_len: u32,
+    value_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    value_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let key = cx.read_string(key_ptr, key_len)?;
+    let value = cx
+        .game_mut()
+        .ecs
+        .get::<PersistentTags>(Entity::from_bits(entity))
+        .ok()
+        .and_then(|tags| tags.get(cx.identifier(), &key).map(str::to_owned));
+
+    let (value_ptr, value_len) = match value {
+        Some(value) => (
+            cx.bump_allocate_and_write_bytes(value.as_bytes())?,
+            value.len() as u32,
+        ),
+        None => (unsafe { PluginPtrMut::null() }, 0),
+    };
+    cx.write_pod(value_ptr_ptr, value_ptr)?;
+    cx.write_pod(value_len_ptr, value_len)?;
+
+    Ok(())
+}
+
+#[host_function]
+pub fn entity_set_tag(
+    cx: &PluginContext,
+    entity: u64,
+    key_ptr: PluginPtr<u8>,
+    key_len: u32,
+    value_ptr: PluginPtr<u8>,
+    value_len: u32,
+) -> anyhow::Result<()> {
+    let key = cx.read_string(key_ptr, key_len)?;
+    let value = cx.read_string(value_ptr, value_len)?;
+
+    let entity = Entity::from_bits(entity);
+    let mut game = cx.game_mut();
+    let existing = game.ecs.get_mut::<PersistentTags>(entity);
+    if let Ok(mut tags) = existing {
+        tags.insert(cx.identifier(), &key, value);
+    } else {
+        drop(existing);
+        let mut tags = PersistentTags::default();
+        tags.insert(cx.identifier(), &key, value);
+        let _ = game.ecs.insert(entity, tags);
+    }
+
+    Ok(())
+}
+
+#[host_function]
+pub fn entity_remove_tag(
+    cx: &PluginContext,
+    entity: u64,
+    key_ptr: PluginPtr<u8>,
+    key_len: u32,
+) -> anyhow::Result<()> {
+    let key = cx.read_string(key_ptr, key_len)?;
+    if let Ok(mut tags) = cx
+        .game_mut()
+        .ecs
+        .get_mut::<PersistentTags>(Entity::from_bits(entity))
+    {
+        tags.remove(cx.identifier(), &key);
+    }
+    Ok(())
+}
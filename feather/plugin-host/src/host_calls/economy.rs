@@ -0,0 +1,113 @@
+use feather_common::economy::{self, Economy};
+use feather_plugin_host_macros::host_function;
+use quill_common::economy::{Currency, EconomyError};
+use uuid::Uuid;
+
+use crate::{
+    context::{PluginContext, PluginPtr, PluginPtrMut},
+    economy::PluginEconomy,
+};
+
+fn account(high: u64, low: u64) -> Uuid {
+    Uuid::from_u128((high as u128) << 64 | low as u128)
+}
+
+#[host_function]
+pub fn economy_balance(
+    cx: &PluginContext,
+    account_high: u64,
+    account_low: u64,
+) -> anyhow::Result<i64> {
+    Ok(economy::balance(
+        &mut cx.game_mut(),
+        account(account_high, account_low),
+    ))
+}
+
+#[host_function]
+pub fn economy_deposit(
+    cx: &PluginContext,
+    account_high: u64,
+    account_low: u64,
+    amount: i64,
+    balance_ptr: PluginPtrMut<i64>,
+) -> anyhow::Result<u32> {
+    let result = economy::deposit(
+        &mut cx.game_mut(),
+        account(account_high, account_low),
+        amount,
+    );
+    write_result(cx, result, balance_ptr)
+}
+
+#[host_function]
+pub fn economy_withdraw(
+    cx: &PluginContext,
+    account_high: u64,
+    account_low: u64,
+    amount: i64,
+    balance_ptr: PluginPtrMut<i64>,
+) -> anyhow::Result<u32> {
+    let result = economy::withdraw(
+        &mut cx.game_mut(),
+        account(account_high, account_low),
+        amount,
+    );
+    write_result(cx, result, balance_ptr)
+}
+
+/// Writes the new balance on success and returns
+/// 0, or returns the error code on failure.
+fn write_result(
+    cx: &PluginContext,
+    result: Result<i64, EconomyError>,
+    balance_ptr: PluginPtrMut<i64>,
+) -> anyhow::Result<u32> {
+    match result {
+        Ok(balance) => {
+            cx.write_pod(balance_ptr, balance)?;
+            Ok(0)
+        }
+        Err(e) => Ok(e.to_u32()),
+    }
+}
+
+#[host_function]
+pub fn economy_currency(
+    cx: &PluginContext,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let currency = cx.game_mut().resources.get::<Economy>()?.currency().clone();
+    let bytes = bincode::serialize(&currency)?;
+    let bytes_ptr = cx.bump_allocate_and_write_bytes(&bytes)?;
+
+    cx.write_pod(bytes_ptr_ptr, bytes_ptr)?;
+    cx.write_pod(bytes_len_ptr, bytes.len() as u32)?;
+
+    Ok(())
+}
+
+#[host_function]
+pub fn economy_register_provider(
+    cx: &PluginContext,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+    currency_ptr: PluginPtr<u8>,
+    currency_len: u32,
+) -> anyhow::Result<()> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    let currency: Currency = cx.read_bincode(currency_ptr, currency_len)?;
+    anyhow::ensure!(
+        currency.fractional_digits <= Currency::MAX_FRACTIONAL_DIGITS,
+        "currency has more than {} fractional digits",
+        Currency::MAX_FRACTIONAL_DIGITS
+    );
+
+    let provider = PluginEconomy::new(cx.plugin_id(), name, currency);
+    cx.game_mut()
+        .resources
+        .get_mut::<Economy>()?
+        .register_provider(provider);
+    Ok(())
+}
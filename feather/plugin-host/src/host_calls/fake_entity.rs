@@ -0,0 +1,97 @@
+use anyhow::{bail, Context};
+use feather_base::Position;
+use feather_common::fake_entities::{FakeEntities, FakeEntityId};
+use feather_ecs::Entity;
+use feather_plugin_host_macros::host_function;
+use quill_common::{entities::Player, fake_entity::FakeEntity};
+
+use crate::context::{PluginContext, PluginPtr};
+
+/// Maximum length of a name in the tablist.
+const MAX_NPC_NAME_LENGTH: usize = 16;
+
+#[host_function]
+pub fn entity_spawn_fake(
+    cx: &PluginContext,
+    player: u64,
+    entity_ptr: PluginPtr<u8>,
+    entity_len: u32,
+) -> anyhow::Result<u32> {
+    let entity: FakeEntity = cx.read_bincode(entity_ptr, entity_len)?;
+    if let FakeEntity::Npc { name, .. } = &entity {
+        if name.chars().count() > MAX_NPC_NAME_LENGTH {
+            bail!(
+                "NPC name '{}' is longer than {} characters",
+                name,
+                MAX_NPC_NAME_LENGTH
+            );
+        }
+    }
+
+    let player = Entity::from_bits(player);
+    let mut game = cx.game_mut();
+    game.ecs
+        .get::<Player>(player)
+        .context("fake entities can only be shown to players")?;
+    let existing = game.ecs.get_mut::<FakeEntities>(player);
+    let id = if let Ok(mut fake_entities) = existing {
+        fake_entities.spawn(entity)
+    } else {
+        drop(existing);
+        let mut fake_entities = FakeEntities::new();
+        let id = fake_entities.spawn(entity);
+        game.ecs.insert(player, fake_entities)?;
+        id
+    };
+
+    Ok(id.0)
+}
+
+#[host_function]
+pub fn fake_entity_move(
+    cx: &PluginContext,
+    player: u64,
+    id: u32,
+    position: PluginPtr<Position>,
+) -> anyhow::Result<u32> {
+    let position = cx.read_pod(position)?;
+    let moved = cx
+        .game_mut()
+        .ecs
+        .get_mut::<FakeEntities>(Entity::from_bits(player))
+        .map_or(false, |mut fake_entities| {
+            fake_entities.move_to(FakeEntityId(id), position)
+        });
+    Ok(moved as u32)
+}
+
+#[host_function]
+pub fn fake_entity_set_text(
+    cx: &PluginContext,
+    player: u64,
+    id: u32,
+    text_ptr: PluginPtr<u8>,
+    text_len: u32,
+) -> anyhow::Result<u32> {
+    let text = cx.read_string(text_ptr, text_len)?;
+    let was_set = cx
+        .game_mut()
+        .ecs
+        .get_mut::<FakeEntities>(Entity::from_bits(player))
+        .map_or(false, |mut fake_entities| {
+            fake_entities.set_text(FakeEntityId(id), text)
+        });
+    Ok(was_set as u32)
+}
+
+#[host_function]
+pub fn fake_entity_remove(cx: &PluginContext, player: u64, id: u32) -> anyhow::Result<u32> {
+    let removed = cx
+        .game_mut()
+        .ecs
+        .get_mut::<FakeEntities>(Entity::from_bits(player))
+        .map_or(false, |mut fake_entities| {
+            fake_entities.remove(FakeEntityId(id))
+        });
+    Ok(removed as u32)
+}
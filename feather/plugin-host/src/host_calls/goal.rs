@@ -0,0 +1,80 @@
+use feather_common::ai::Goals;
+use feather_ecs::Entity;
+use feather_plugin_host_macros::host_function;
+use quill_common::goals::GoalControls;
+
+use crate::{
+    context::{PluginContext, PluginPtr, PluginPtrMut},
+    goal::PluginGoal,
+};
+
+#[host_function]
+pub fn entity_goals(
+    cx: &PluginContext,
+    entity: u64,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let infos = cx
+        .game_mut()
+        .ecs
+        .get::<Goals>(Entity::from_bits(entity))
+        .map(|goals| goals.infos())
+        .unwrap_or_default();
+    let bytes = bincode::serialize(&infos)?;
+    let bytes_ptr = cx.bump_allocate_and_write_bytes(&bytes)?;
+
+    cx.write_pod(bytes_ptr_ptr, bytes_ptr)?;
+    cx.write_pod(bytes_len_ptr, bytes.len() as u32)?;
+
+    Ok(())
+}
+
+#[host_function]
+pub fn entity_remove_goal(
+    cx: &PluginContext,
+    entity: u64,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+) -> anyhow::Result<u32> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    let removed = cx
+        .game_mut()
+        .ecs
+        .get_mut::<Goals>(Entity::from_bits(entity))
+        .map(|mut goals| goals.remove(&name))
+        .unwrap_or(0);
+    Ok(removed as u32)
+}
+
+#[host_function]
+pub fn entity_add_goal(
+    cx: &PluginContext,
+    entity: u64,
+    goal_data: PluginPtrMut<u8>,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+    priority: u32,
+    controls: u32,
+) -> anyhow::Result<()> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    // Prefix the name so the plugin's goals
+    // can be removed when it is reloaded.
+    let name = format!("{}{}", cx.system_name_prefix(), name);
+    let goal = PluginGoal::new(cx.plugin_id(), goal_data, cx.dropped_goals());
+    let controls = GoalControls::from_bits(controls);
+
+    let entity = Entity::from_bits(entity);
+    let mut game = cx.game_mut();
+    let existing = game.ecs.get_mut::<Goals>(entity);
+    if let Ok(mut goals) = existing {
+        goals.insert(name, priority, controls, goal);
+    } else {
+        drop(existing);
+        let mut goals = Goals::new();
+        goals.insert(name, priority, controls, goal);
+        let _ = game.ecs.insert(entity, goals);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use feather_plugin_host_macros::host_function;
+
+use crate::context::{PluginContext, PluginPtr, PluginPtrMut};
+
+#[host_function]
+pub fn plugin_state_store(
+    cx: &PluginContext,
+    bytes_ptr: PluginPtr<u8>,
+    bytes_len: u32,
+) -> anyhow::Result<()> {
+    let bytes = cx.read_bytes(bytes_ptr, bytes_len)?;
+    cx.store_state(bytes);
+    Ok(())
+}
+
+#[host_function]
+pub fn plugin_state_take(
+    cx: &PluginContext,
+    bytes_ptr_ptr: PluginPtrMut<PluginPtrMut<u8>>,
+    bytes_len_ptr: PluginPtrMut<u32>,
+) -> anyhow::Result<()> {
+    let (bytes_ptr, bytes_len) = match cx.take_state() {
+        Some(bytes) => (
+            cx.bump_allocate_and_write_bytes(&bytes)?,
+            bytes.len() as u32,
+        ),
+        None => (unsafe { PluginPtrMut::null() }, 0),
+    };
+
+    cx.write_pod(bytes_ptr_ptr, bytes_ptr)?;
+    cx.write_pod(bytes_len_ptr, bytes_len)?;
+
+    Ok(())
+}
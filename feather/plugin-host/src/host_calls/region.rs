@@ -0,0 +1,46 @@
+use feather_base::BlockPosition;
+use feather_common::{regions, world_settings};
+use feather_plugin_host_macros::host_function;
+use quill_common::region::Region;
+
+use crate::context::{PluginContext, PluginPtr};
+
+#[host_function]
+pub fn region_define(
+    cx: &PluginContext,
+    region_ptr: PluginPtr<u8>,
+    region_len: u32,
+) -> anyhow::Result<()> {
+    let region: Region = cx.read_bincode(region_ptr, region_len)?;
+    let mut game = cx.game_mut();
+    let world = game.world.name().to_owned();
+    regions::define_region(&mut game, &world, region)?;
+    Ok(())
+}
+
+#[host_function]
+pub fn region_remove(
+    cx: &PluginContext,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+) -> anyhow::Result<u32> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    let mut game = cx.game_mut();
+    let world = game.world.name().to_owned();
+    let removed = regions::remove_region(&mut game, &world, &name)?;
+    Ok(removed.is_some() as u32)
+}
+
+#[host_function]
+pub fn world_mob_spawning_allowed(
+    cx: &PluginContext,
+    x: i32,
+    y: i32,
+    z: i32,
+    hostile: u32,
+) -> anyhow::Result<u32> {
+    let pos = BlockPosition::new(x, y, z);
+    let game = cx.game_mut();
+    let allowed = world_settings::mob_spawning_allowed(&game.world, pos, hostile != 0);
+    Ok(allowed as u32)
+}
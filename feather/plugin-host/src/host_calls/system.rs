@@ -5,9 +5,10 @@ use std::{cell::RefCell, rc::Rc};
 use feather_common::Game;
 use feather_ecs::{HasResources, SysResult};
 use feather_plugin_host_macros::host_function;
+use quill_common::events::EventPriority;
 
 use crate::{
-    context::{PluginContext, PluginPtr, PluginPtrMut},
+    context::{EventHandler, PluginContext, PluginPtr, PluginPtrMut},
     PluginId, PluginManager,
 };
 
@@ -19,6 +20,11 @@ pub fn register_system(
     name_len: u32,
 ) -> anyhow::Result<()> {
     let name = cx.read_string(name_ptr, name_len)?;
+    cx.count_system()?;
+
+    // Prefix the name so the plugin's systems
+    // can be removed when it is reloaded.
+    let name = format!("{}{}", cx.system_name_prefix(), name);
 
     let game = cx.game_mut();
     game.system_executor
@@ -28,6 +34,62 @@ pub fn register_system(
     Ok(())
 }
 
+#[host_function]
+pub fn register_task(
+    cx: &PluginContext,
+    data_ptr: PluginPtrMut<u8>,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+    period: u32,
+) -> anyhow::Result<()> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    if period == 0 {
+        anyhow::bail!("task {} has a period of zero ticks", name);
+    }
+    cx.count_task()?;
+
+    // Tasks are systems which skip most ticks, so they
+    // share the prefix and are removed on reload too.
+    let name = format!("{}{}", cx.system_name_prefix(), name);
+
+    let game = cx.game_mut();
+    let mut system = plugin_system(cx.plugin_id(), data_ptr);
+    game.system_executor.borrow_mut().add_system_with_name(
+        move |game: &mut Game| {
+            if game.tick_count % u64::from(period) != 0 {
+                return Ok(());
+            }
+            system(game)
+        },
+        &name,
+    );
+
+    Ok(())
+}
+
+#[host_function]
+pub fn register_event_handler(
+    cx: &PluginContext,
+    data_ptr: PluginPtrMut<u8>,
+    name_ptr: PluginPtr<u8>,
+    name_len: u32,
+    priority: u32,
+) -> anyhow::Result<()> {
+    let name = cx.read_string(name_ptr, name_len)?;
+    let priority = EventPriority::from_u32(priority)
+        .ok_or_else(|| anyhow::anyhow!("invalid event priority {}", priority))?;
+    // Event handlers count towards the system quota.
+    cx.count_system()?;
+
+    cx.add_event_handler(EventHandler {
+        name,
+        priority,
+        data: data_ptr,
+    });
+
+    Ok(())
+}
+
 fn plugin_system(id: PluginId, data_ptr: PluginPtrMut<u8>) -> impl FnMut(&mut Game) -> SysResult {
     move |game: &mut Game| {
         let plugin_manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
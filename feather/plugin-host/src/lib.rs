@@ -6,30 +6,46 @@
 #![allow(warnings)] // TEMP
 
 use std::{
-    fs,
+    cell::RefCell,
+    fmt::Write,
+    fs, mem,
     path::Path,
+    rc::Rc,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
 use ahash::AHashMap;
-use anyhow::Context;
+use anyhow::{anyhow, bail, Context};
 use env::PluginEnv;
-use feather_common::Game;
+use feather_base::Text;
+use feather_common::{
+    ai::Goals,
+    chat::{ChatKind, ChatMessage},
+    commands::{check_permission, CommandRegistry},
+    Game,
+};
+use feather_ecs::Entity;
 use plugin::Plugin;
 use quill_plugin_format::{PluginFile, PluginMetadata};
+use tunables::LimitingTunables;
 use vec_arena::Arena;
 use wasmer::{
-    ChainableNamedResolver, CompilerConfig, ExportError, Features, Function, ImportObject,
-    Instance, Module, Store, JIT,
+    BaseTunables, ChainableNamedResolver, CompilerConfig, ExportError, Features, Function,
+    ImportObject, Instance, Module, Pages, Store, Target, JIT, WASM_PAGE_SIZE,
 };
 use wasmer_wasi::{WasiEnv, WasiState, WasiVersion};
 
 mod context;
+mod economy;
 mod env;
+mod event_handlers;
+mod goal;
 mod host_calls;
 mod host_function;
 mod plugin;
+mod quota;
 mod thread_pinned;
+mod tunables;
 mod wasm_ptr_ext;
 
 /// Features enabled for WASM plugins
@@ -45,6 +61,8 @@ const WASM_FEATURES: Features = Features {
     memory64: false,
 };
 
+pub use quota::PluginQuotas;
+
 /// Unique ID of a plugin.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PluginId(usize);
@@ -54,6 +72,12 @@ pub struct PluginManager {
     plugins: Arena<Plugin>,
 
     store: wasmer::Store,
+
+    quotas: PluginQuotas,
+
+    /// Plugins to reload at the end of the tick,
+    /// with the entity which requested each reload.
+    pending_reloads: Vec<(String, Entity)>,
 }
 
 impl Default for PluginManager {
@@ -63,16 +87,31 @@ impl Default for PluginManager {
 }
 
 impl PluginManager {
-    /// Creates a plugin manager with no plugins.
+    /// Creates a plugin manager with no plugins
+    /// and the default quotas.
     pub fn new() -> Self {
+        Self::with_quotas(PluginQuotas::default())
+    }
+
+    /// Creates a plugin manager with no plugins.
+    /// Plugins loaded later are limited by `quotas`.
+    pub fn with_quotas(quotas: PluginQuotas) -> Self {
         let compiler_config = compiler_config();
         let engine_config = JIT::new(compiler_config).features(WASM_FEATURES);
         let engine = engine_config.engine();
-        let store = Store::new(&engine);
+        // Memories are capped when they are created, so
+        // a plugin can't grow past `max_wasm_memory`.
+        let tunables = LimitingTunables::new(
+            BaseTunables::for_target(&Target::default()),
+            Pages((quotas.max_wasm_memory / WASM_PAGE_SIZE) as u32),
+        );
+        let store = Store::new_with_tunables(&engine, tunables);
 
         Self {
             plugins: Arena::new(),
             store,
+            quotas,
+            pending_reloads: Vec::new(),
         }
     }
 
@@ -93,15 +132,31 @@ impl PluginManager {
                 continue;
             }
 
-            let bytes = fs::read(entry.path())?;
-            self.load(game, &bytes).with_context(|| {
-                format!("failed to load plugin from {}", entry.path().display())
-            })?;
+            self.load_file(game, entry.path())?;
         }
 
         Ok(())
     }
 
+    /// Loads and enables a plugin from the given file.
+    ///
+    /// The plugin can later be reloaded from the same file.
+    pub fn load_file(
+        &mut self,
+        game: &mut Game,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<PluginId> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let id = self
+            .load(game, &bytes)
+            .with_context(|| format!("failed to load plugin from {}", path.display()))?;
+        if let Some(plugin) = self.plugin_mut(id) {
+            plugin.set_source(path);
+        }
+        Ok(id)
+    }
+
     /// Loads and enables a plugin from the given plugin file bytes.
     ///
     /// Returns the ID of the loaded plugin.
@@ -129,6 +184,211 @@ impl PluginManager {
     pub fn plugin_mut(&mut self, id: PluginId) -> Option<&mut Plugin> {
         self.plugins.get_mut(id.0)
     }
+
+    /// Finds a plugin by its name or identifier.
+    pub fn find(&self, name: &str) -> Option<PluginId> {
+        self.plugins
+            .iter()
+            .find(|(_, plugin)| {
+                plugin.metadata().name == name || plugin.metadata().identifier == name
+            })
+            .map(|(index, _)| PluginId(index))
+    }
+
+    /// Reloads a plugin from the file it was loaded from.
+    ///
+    /// The new instance is loaded and enabled before the running
+    /// instance is removed, so a broken artifact or a failing
+    /// `enable` leaves the old instance's systems in place. State
+    /// handed off by the old instance while disabling is passed
+    /// to the new instance.
+    ///
+    /// Returns the ID of the new instance.
+    ///
+    /// Must not be called while systems are running; use
+    /// [`request_reload`](Self::request_reload) instead.
+    pub fn reload(&mut self, game: &mut Game, name: &str) -> anyhow::Result<PluginId> {
+        let old_id = self
+            .find(name)
+            .ok_or_else(|| anyhow!("no plugin named '{}'", name))?;
+        let source = self
+            .plugin(old_id)
+            .and_then(Plugin::source)
+            .map(Path::to_owned)
+            .ok_or_else(|| anyhow!("plugin '{}' was not loaded from a file", name))?;
+
+        let bytes = fs::read(&source)?;
+        let file = PluginFile::decode(&bytes).context("malformed plugin file")?;
+        let id = PluginId(self.plugins.next_vacant());
+        let mut plugin = Plugin::load(self, &file, id)?;
+        plugin.set_source(source);
+
+        let old = self.plugin(old_id).expect("plugin was found");
+        if let Err(e) = old.disable(game) {
+            log::error!("Plugin {} failed to disable: {:?}", old.metadata().name, e);
+        }
+        if let Some(state) = old.context().take_state() {
+            plugin.context().store_state(state);
+        }
+
+        if let Err(e) = plugin.enable(game) {
+            // Only the new instance's systems are removed;
+            // the old instance keeps running.
+            remove_systems(game, &plugin);
+            return Err(e.context("failed to enable reloaded plugin"));
+        }
+
+        let new_id = PluginId(self.plugins.insert(plugin));
+        debug_assert_eq!(new_id, id);
+
+        let old = self.plugins.remove(old_id.0).expect("plugin was found");
+        remove_systems(game, &old);
+        Ok(id)
+    }
+
+    /// Queues a plugin to be reloaded once
+    /// the current tick's systems have run.
+    pub fn request_reload(&mut self, name: &str, requested_by: Entity) {
+        self.pending_reloads.push((name.to_owned(), requested_by));
+    }
+
+    /// Iterates over all loaded plugins.
+    pub fn plugins(&self) -> impl Iterator<Item = &Plugin> + '_ {
+        self.plugins.iter().map(|(_, plugin)| plugin)
+    }
+
+    /// Formats the time each plugin spends per tick,
+    /// along with its other resource usage.
+    pub fn timings_report(&self) -> String {
+        let mut report = String::from("Plugin timings (last 5 seconds):");
+        for plugin in self.plugins() {
+            let usage = plugin.usage();
+            let _ = write!(
+                report,
+                "\n{}: avg {:.2}ms, max {:.2}ms, {}/{} systems, {}/{} tasks",
+                plugin.metadata().name,
+                usage.average_tick_time().as_secs_f64() * 1000.,
+                usage.max_tick_time().as_secs_f64() * 1000.,
+                plugin.registered_systems(),
+                self.quotas.max_systems,
+                plugin.registered_tasks(),
+                self.quotas.max_tasks,
+            );
+            if let Some(memory) = plugin.memory_usage() {
+                let _ = write!(
+                    report,
+                    ", {}/{} KiB memory",
+                    memory / 1024,
+                    self.quotas.max_wasm_memory / 1024
+                );
+            }
+            if let Some(reason) = usage.suspended() {
+                let _ = write!(report, " [suspended: {}]", reason);
+            }
+        }
+        report
+    }
+}
+
+fn remove_systems(game: &mut Game, plugin: &Plugin) {
+    let prefix = plugin.context().system_name_prefix();
+    game.system_executor
+        .borrow_mut()
+        .retain_systems(|name| !name.starts_with(&prefix));
+
+    // Goals point into the old instance's memory.
+    for (_, goals) in game.ecs.query::<&mut Goals>().iter() {
+        goals.retain(|name| !name.starts_with(&prefix));
+    }
+}
+
+/// Performs plugin reloads requested during the tick.
+///
+/// Must be called after all systems have run.
+pub fn process_pending_reloads(game: &mut Game) -> anyhow::Result<()> {
+    let manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+    let mut manager = manager.borrow_mut();
+    for (name, requested_by) in mem::take(&mut manager.pending_reloads) {
+        let feedback = match manager.reload(game, &name) {
+            Ok(_) => format!("Reloaded plugin {}", name),
+            Err(e) => {
+                log::error!("Failed to reload plugin {}: {:?}", name, e);
+                format!("Failed to reload plugin {}: {}", name, e)
+            }
+        };
+        if game.ecs.entity(requested_by).is_ok() {
+            game.send_message(
+                requested_by,
+                ChatMessage::new(ChatKind::System, Text::of(feedback)),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Registers the `/plugins` and `/plugin` commands
+/// and the system which runs plugin event handlers.
+pub fn register(game: &mut Game) -> anyhow::Result<()> {
+    {
+        let mut commands = game.resources.get_mut::<CommandRegistry>()?;
+        commands.register("plugins", plugins_command);
+        commands.register("plugin", plugin_command);
+    }
+    game.system_executor.borrow_mut().add_system_with_name(
+        event_handlers::run_event_handlers,
+        event_handlers::SYSTEM_NAME,
+    );
+    game.system_executor
+        .borrow_mut()
+        .add_system(goal::free_dropped_goals);
+    Ok(())
+}
+
+/// Permission node required for `/plugin reload`.
+pub const RELOAD_PERMISSION: &str = "feather.plugins.reload";
+
+/// `/plugin reload <name>`
+fn plugin_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    let manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+    let mut manager = manager.borrow_mut();
+    match args {
+        ["reload", name] => {
+            check_permission(game, sender, RELOAD_PERMISSION)?;
+            if manager.find(name).is_none() {
+                bail!("no plugin named '{}'", name);
+            }
+            manager.request_reload(name, sender);
+            Ok(Text::of(format!("Reloading plugin {}...", name)))
+        }
+        _ => bail!("usage: /plugin reload <name>"),
+    }
+}
+
+/// Permission node required for `/plugins timings`.
+pub const TIMINGS_PERMISSION: &str = "feather.plugins.timings";
+
+/// `/plugins [timings]`
+fn plugins_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    let manager = Rc::clone(&*game.resources.get::<Rc<RefCell<PluginManager>>>()?);
+    let manager = manager.borrow();
+    match args {
+        [] => {
+            let names: Vec<&str> = manager
+                .plugins()
+                .map(|plugin| plugin.metadata().name.as_str())
+                .collect();
+            Ok(Text::of(format!(
+                "Plugins ({}): {}",
+                names.len(),
+                names.join(", ")
+            )))
+        }
+        ["timings"] => {
+            check_permission(game, sender, TIMINGS_PERMISSION)?;
+            Ok(Text::of(manager.timings_report()))
+        }
+        _ => bail!("usage: /plugins [timings]"),
+    }
 }
 
 #[cfg(all(feature = "cranelift", not(feature = "llvm")))]
@@ -1,11 +1,25 @@
-use std::sync::Arc;
+use std::{
+    alloc::Layout,
+    cell::RefCell,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 
 use anyhow::bail;
 use feather_common::Game;
+use feather_ecs::Entity;
+use quill_common::{
+    economy::{EconomyError, EconomyOp},
+    goals::GoalOp,
+};
 use quill_plugin_format::{PluginFile, PluginMetadata, PluginTarget, Triple};
+use uuid::Uuid;
 
 use crate::{
-    context::{PluginContext, PluginPtrMut},
+    context::{PluginContext, PluginPtr, PluginPtrMut},
+    quota::{PluginQuotas, PluginUsage, TickVerdict},
     PluginId, PluginManager,
 };
 
@@ -16,6 +30,9 @@ pub struct Plugin {
     inner: Inner,
     context: Arc<PluginContext>,
     metadata: PluginMetadata,
+    usage: RefCell<PluginUsage>,
+    /// The file the plugin was loaded from, used to reload it.
+    source: Option<PathBuf>,
 }
 
 impl Plugin {
@@ -36,7 +53,11 @@ impl Plugin {
 
         let (inner, context) = match &file.metadata().target {
             PluginTarget::Wasm => {
-                let context = Arc::new(PluginContext::new_wasm(id));
+                let context = Arc::new(PluginContext::new_wasm(
+                    id,
+                    file.metadata().identifier.clone(),
+                    manager.quotas,
+                ));
                 let plugin =
                     wasm::WasmPlugin::load(manager, &context, file.module(), file.metadata())?;
                 (Inner::Wasm(plugin), context)
@@ -50,7 +71,11 @@ impl Plugin {
                     );
                 }
                 let plugin = native::NativePlugin::load(file.module())?;
-                let context = PluginContext::new_native(id);
+                let context = PluginContext::new_native(
+                    id,
+                    file.metadata().identifier.clone(),
+                    manager.quotas,
+                );
                 (Inner::Native(plugin), Arc::new(context))
             }
         };
@@ -59,9 +84,52 @@ impl Plugin {
             inner,
             context,
             metadata: file.metadata().clone(),
+            usage: RefCell::new(PluginUsage::new()),
+            source: None,
         })
     }
 
+    /// Gets the file the plugin was loaded from, if any.
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    pub fn set_source(&mut self, source: impl Into<PathBuf>) {
+        self.source = Some(source.into());
+    }
+
+    pub(crate) fn context(&self) -> &PluginContext {
+        &self.context
+    }
+
+    pub fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
+    /// Gets the plugin's recorded resource usage.
+    pub fn usage(&self) -> std::cell::Ref<PluginUsage> {
+        self.usage.borrow()
+    }
+
+    /// Gets the number of systems the plugin has registered.
+    pub fn registered_systems(&self) -> usize {
+        self.context.registered_systems()
+    }
+
+    /// Gets the number of scheduled tasks the plugin has registered.
+    pub fn registered_tasks(&self) -> usize {
+        self.context.registered_tasks()
+    }
+
+    /// Gets the size in bytes of the plugin's linear memory,
+    /// or `None` for native plugins.
+    pub fn memory_usage(&self) -> Option<usize> {
+        match &self.inner {
+            Inner::Wasm(w) => w.memory_usage().ok(),
+            Inner::Native(_) => None,
+        }
+    }
+
     /// Enables the plugin.
     ///
     /// # Panics
@@ -81,19 +149,173 @@ impl Plugin {
         Ok(())
     }
 
+    /// Disables the plugin, giving it a chance to
+    /// hand off state to its next instance.
+    pub fn disable(&self, game: &mut Game) -> anyhow::Result<()> {
+        self.context.enter(game, || match &self.inner {
+            Inner::Wasm(w) => w.disable(),
+            Inner::Native(n) => {
+                n.disable();
+                Ok(())
+            }
+        })?;
+
+        log::info!("Disabled plugin {}", self.metadata.name);
+        Ok(())
+    }
+
     /// Runs a plugin system.
     ///
     /// `data` must be the data pointer passed
     /// to the `register_system` host call.
+    ///
+    /// Does nothing if the plugin has been suspended
+    /// for exceeding its quotas.
     pub fn run_system(&self, game: &mut Game, data: PluginPtrMut<u8>) -> anyhow::Result<()> {
-        self.context.enter(game, || match &self.inner {
+        if self.usage.borrow().suspended().is_some() {
+            return Ok(());
+        }
+
+        let tick = game.tick_count;
+        let start = Instant::now();
+        let result = self.context.enter(game, || match &self.inner {
             Inner::Wasm(w) => w.run_system(data),
             Inner::Native(n) => {
                 n.run_system(data);
                 Ok(())
             }
+        });
+        self.check_quotas(tick, start.elapsed());
+
+        result
+    }
+
+    /// Invokes a callback of a plugin goal.
+    ///
+    /// `data` must be the data pointer passed
+    /// to the `entity_add_goal` host call.
+    ///
+    /// Returns 0 if the plugin has been suspended.
+    pub fn run_goal(
+        &self,
+        game: &mut Game,
+        data: PluginPtrMut<u8>,
+        entity: Entity,
+        op: GoalOp,
+    ) -> anyhow::Result<u32> {
+        if self.usage.borrow().suspended().is_some() {
+            return Ok(0);
+        }
+
+        let tick = game.tick_count;
+        let start = Instant::now();
+        let result = self.context.enter(game, || match &self.inner {
+            Inner::Wasm(w) => w.run_goal(data, entity.to_bits(), op as u32),
+            Inner::Native(n) => n.run_goal(data, entity.to_bits(), op as u32),
+        });
+        self.check_quotas(tick, start.elapsed());
+
+        result
+    }
+
+    /// Invokes an operation of the plugin's economy provider.
+    ///
+    /// Fails if the plugin has been suspended, or if it's already
+    /// running, e.g. because the provider made a transaction itself.
+    pub fn run_economy(
+        &self,
+        game: &mut Game,
+        op: EconomyOp,
+        account: Uuid,
+        amount: i64,
+    ) -> anyhow::Result<Result<i64, EconomyError>> {
+        if self.usage.borrow().suspended().is_some() {
+            bail!("plugin is suspended");
+        }
+        if self.context.is_entered() {
+            bail!("plugin called its own economy provider");
+        }
+
+        let account = account.as_u128();
+        let (account_high, account_low) = ((account >> 64) as u64, account as u64);
+        let tick = game.tick_count;
+        let start = Instant::now();
+        let result = self.context.enter(game, || -> anyhow::Result<_> {
+            // SAFETY: the layout is that of an i64.
+            let balance_ptr: PluginPtrMut<i64> =
+                unsafe { self.context.bump_allocate(Layout::new::<i64>())?.cast() };
+            let code = match &self.inner {
+                Inner::Wasm(w) => {
+                    w.run_economy(op as u32, account_high, account_low, amount, balance_ptr)?
+                }
+                Inner::Native(n) => {
+                    n.run_economy(op as u32, account_high, account_low, amount, balance_ptr)?
+                }
+            };
+            match code {
+                0 => {
+                    let balance_ptr = PluginPtr {
+                        ptr: balance_ptr.ptr,
+                        _marker: PhantomData,
+                    };
+                    Ok(Ok(self.context.read_pod(balance_ptr)?))
+                }
+                code => EconomyError::from_u32(code)
+                    .map(Err)
+                    .ok_or_else(|| anyhow::anyhow!("invalid economy error {}", code)),
+            }
+        });
+        self.check_quotas(tick, start.elapsed());
+
+        result
+    }
+
+    /// Frees the data of the plugin's goals
+    /// which were removed since the last call.
+    ///
+    /// Does nothing if the plugin has been suspended.
+    pub fn free_dropped_goals(&self, game: &mut Game) -> anyhow::Result<()> {
+        if self.usage.borrow().suspended().is_some() {
+            return Ok(());
+        }
+        let dropped = self.context.dropped_goals().take();
+        if dropped.is_empty() {
+            return Ok(());
+        }
+
+        self.context.enter(game, || {
+            for data in dropped {
+                match &self.inner {
+                    Inner::Wasm(w) => w.run_goal(data, 0, GoalOp::Drop as u32)?,
+                    Inner::Native(n) => n.run_goal(data, 0, GoalOp::Drop as u32)?,
+                };
+            }
+            Ok(())
         })
     }
+
+    fn check_quotas(&self, tick: u64, time: std::time::Duration) {
+        let quotas = self.context.quotas();
+        let mut usage = self.usage.borrow_mut();
+        match usage.record(tick, time, quotas) {
+            TickVerdict::WithinBudget => {}
+            TickVerdict::Overrun { time, streak } => log::warn!(
+                "Plugin {} took {:.2}ms in one tick (budget {:.2}ms, {} in a row)",
+                self.metadata.name,
+                time.as_secs_f64() * 1000.,
+                quotas.tick_time_budget.as_secs_f64() * 1000.,
+                streak
+            ),
+            TickVerdict::Suspend { streak, .. } => usage.suspend(format!(
+                "exceeded its tick time budget {} ticks in a row",
+                streak
+            )),
+        }
+
+        if let Some(reason) = usage.suspended() {
+            log::error!("Suspending plugin {}: {}", self.metadata.name, reason);
+        }
+    }
 }
 
 enum Inner {
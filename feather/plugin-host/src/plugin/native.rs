@@ -22,13 +22,36 @@ pub struct NativePlugin {
     /// 3. Length of bincode-encoded vtable
     enable: unsafe extern "C" fn(*const u8, *const u8, usize),
 
+    /// The plugin's exported quill_disable function, if any.
+    disable: Option<unsafe extern "C" fn()>,
+
     /// The plugin's exported quill_run_system function.
     ///
     /// Parameters:
     /// 1. Plugin data pointer for this system
     run_system: unsafe extern "C" fn(*mut u8),
+
+    /// The plugin's exported quill_run_goal function, if any.
+    ///
+    /// Parameters:
+    /// 1. Plugin data pointer for the goal
+    /// 2. The entity the goal belongs to
+    /// 3. The `GoalOp` to perform
+    run_goal: Option<unsafe extern "C" fn(*mut u8, u64, u32) -> u32>,
+
+    /// The plugin's exported quill_run_economy function, if any.
+    ///
+    /// Parameters:
+    /// 1. The `EconomyOp` to perform
+    /// 2. Most significant bits of the account's UUID
+    /// 3. Least significant bits of the account's UUID
+    /// 4. The amount or balance of the operation
+    /// 5. Pointer receiving the balance on success
+    run_economy: Option<RunEconomy>,
 }
 
+type RunEconomy = unsafe extern "C" fn(u32, u64, u64, i64, *mut i64) -> u32;
+
 impl NativePlugin {
     pub fn load(module: &[u8]) -> anyhow::Result<Self> {
         // Libraries have to be loaded from files, so
@@ -50,17 +73,38 @@ impl NativePlugin {
                 .get("quill_setup".as_bytes())
                 .context("plugin is missing quill_setup export")?
         };
+        let disable = unsafe {
+            library
+                .get::<unsafe extern "C" fn()>("quill_disable".as_bytes())
+                .ok()
+                .map(|disable| *disable)
+        };
         let run_system = unsafe {
             *library
                 .get("quill_run_system".as_bytes())
                 .context("plugin is missing quill_run_system export")?
         };
+        let run_goal = unsafe {
+            library
+                .get::<unsafe extern "C" fn(*mut u8, u64, u32) -> u32>("quill_run_goal".as_bytes())
+                .ok()
+                .map(|run_goal| *run_goal)
+        };
+        let run_economy = unsafe {
+            library
+                .get::<RunEconomy>("quill_run_economy".as_bytes())
+                .ok()
+                .map(|run_economy| *run_economy)
+        };
 
         Ok(Self {
             tempfile: path,
             library,
             enable,
+            disable,
             run_system,
+            run_goal,
+            run_economy,
         })
     }
 
@@ -80,6 +124,13 @@ impl NativePlugin {
         }
     }
 
+    pub fn disable(&self) {
+        if let Some(disable) = self.disable {
+            // SAFETY: we assume the plugin is sound.
+            unsafe { disable() }
+        }
+    }
+
     fn generate_vtable(&self) -> Vec<u8> {
         let vtable = crate::host_calls::generate_vtable();
         bincode::serialize(&vtable).expect("can't serialize vtable")
@@ -89,4 +140,35 @@ impl NativePlugin {
         // SAFETY: we assume the plugin is sound.
         unsafe { (self.run_system)(data.as_native()) }
     }
+
+    pub fn run_goal(&self, data: PluginPtrMut<u8>, entity: u64, op: u32) -> anyhow::Result<u32> {
+        let run_goal = self
+            .run_goal
+            .context("plugin is missing quill_run_goal export")?;
+        // SAFETY: we assume the plugin is sound.
+        Ok(unsafe { run_goal(data.as_native(), entity, op) })
+    }
+
+    pub fn run_economy(
+        &self,
+        op: u32,
+        account_high: u64,
+        account_low: u64,
+        amount: i64,
+        balance_ptr: PluginPtrMut<i64>,
+    ) -> anyhow::Result<u32> {
+        let run_economy = self
+            .run_economy
+            .context("plugin is missing quill_run_economy export")?;
+        // SAFETY: we assume the plugin is sound.
+        Ok(unsafe {
+            run_economy(
+                op,
+                account_high,
+                account_low,
+                amount,
+                balance_ptr.as_native(),
+            )
+        })
+    }
 }
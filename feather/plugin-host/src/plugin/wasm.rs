@@ -20,8 +20,25 @@ pub struct WasmPlugin {
     /// Exported function to enable the plugin.
     enable: Function,
 
+    /// Exported function to disable the plugin.
+    /// Missing for plugins built against older
+    /// versions of Quill.
+    disable: Option<Function>,
+
     /// Exported function to run a system given its data pointer.
     run_system: NativeFunc<u32>,
+
+    /// Exported function to invoke a goal callback given
+    /// the goal's data pointer, its entity and the `GoalOp`.
+    /// Missing for plugins built against older
+    /// versions of Quill.
+    run_goal: Option<NativeFunc<(u32, u64, u32), u32>>,
+
+    /// Exported function to invoke an operation of the plugin's
+    /// economy provider given the `EconomyOp`, the account, the
+    /// amount and a pointer receiving the balance.
+    /// Missing for plugins without an economy provider.
+    run_economy: Option<NativeFunc<(u32, u64, u64, i64, u32), u32>>,
 }
 
 impl WasmPlugin {
@@ -46,12 +63,28 @@ impl WasmPlugin {
             .get_function("quill_run_system")?
             .native()?
             .clone();
+        let run_goal = instance
+            .exports
+            .get_function("quill_run_goal")
+            .ok()
+            .map(|run_goal| run_goal.native())
+            .transpose()?;
+        let run_economy = instance
+            .exports
+            .get_function("quill_run_economy")
+            .ok()
+            .map(|run_economy| run_economy.native())
+            .transpose()?;
         let enable = instance.exports.get_function("quill_setup")?.clone();
+        let disable = instance.exports.get_function("quill_disable").ok().cloned();
 
         Ok(Self {
             instance,
             run_system,
+            run_goal,
+            run_economy,
             enable,
+            disable,
         })
     }
 
@@ -60,10 +93,55 @@ impl WasmPlugin {
         Ok(())
     }
 
+    pub fn disable(&self) -> anyhow::Result<()> {
+        if let Some(disable) = &self.disable {
+            disable.call(&[])?;
+        }
+        Ok(())
+    }
+
     pub fn run_system(&self, data_ptr: PluginPtrMut<u8>) -> anyhow::Result<()> {
         self.run_system.call(data_ptr.ptr as u32)?;
         Ok(())
     }
+
+    pub fn run_goal(
+        &self,
+        data_ptr: PluginPtrMut<u8>,
+        entity: u64,
+        op: u32,
+    ) -> anyhow::Result<u32> {
+        match &self.run_goal {
+            Some(run_goal) => Ok(run_goal.call(data_ptr.ptr as u32, entity, op)?),
+            None => anyhow::bail!("plugin is missing quill_run_goal export"),
+        }
+    }
+
+    pub fn run_economy(
+        &self,
+        op: u32,
+        account_high: u64,
+        account_low: u64,
+        amount: i64,
+        balance_ptr: PluginPtrMut<i64>,
+    ) -> anyhow::Result<u32> {
+        match &self.run_economy {
+            Some(run_economy) => Ok(run_economy.call(
+                op,
+                account_high,
+                account_low,
+                amount,
+                balance_ptr.ptr as u32,
+            )?),
+            None => anyhow::bail!("plugin is missing quill_run_economy export"),
+        }
+    }
+
+    /// Gets the current size of the instance's linear memory in bytes.
+    pub fn memory_usage(&self) -> anyhow::Result<usize> {
+        let memory = self.instance.exports.get_memory("memory")?;
+        Ok(memory.size().bytes().0)
+    }
 }
 
 fn generate_wasi_import_object(store: &Store, plugin_name: &str) -> anyhow::Result<ImportObject> {
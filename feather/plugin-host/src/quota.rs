@@ -0,0 +1,188 @@
+//! Per-plugin resource quotas and usage tracking.
+//!
+//! Each plugin is limited in how many systems and scheduled
+//! tasks it may register, how long its systems may run each
+//! tick, and (for WebAssembly plugins) how much linear memory
+//! its instance may grow to. Registrations over the limit fail,
+//! and memory growth past the limit fails inside the plugin.
+//! A plugin which keeps exceeding its tick time budget is
+//! suspended: its systems stop running until the server restarts.
+
+use std::{collections::VecDeque, time::Duration};
+
+/// Number of ticks over which average tick times are computed.
+const TIMINGS_WINDOW: usize = 100;
+
+/// Resource limits applied to every plugin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PluginQuotas {
+    /// Maximum number of systems a plugin may register.
+    pub max_systems: usize,
+    /// Maximum number of scheduled tasks a plugin may register.
+    pub max_tasks: usize,
+    /// Time a plugin's systems may spend per tick
+    /// before a warning is logged.
+    pub tick_time_budget: Duration,
+    /// If set, a plugin which exceeds `tick_time_budget`
+    /// this many ticks in a row is suspended.
+    pub suspend_after_overruns: Option<u32>,
+    /// Maximum size in bytes of a WebAssembly
+    /// plugin's linear memory.
+    pub max_wasm_memory: usize,
+}
+
+impl Default for PluginQuotas {
+    fn default() -> Self {
+        Self {
+            max_systems: 64,
+            max_tasks: 64,
+            tick_time_budget: Duration::from_millis(10),
+            suspend_after_overruns: None,
+            max_wasm_memory: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// Result of closing out a tick in [`PluginUsage::record`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TickVerdict {
+    /// The plugin stayed within its tick time budget.
+    WithinBudget,
+    /// The plugin exceeded its budget in the tick that just ended.
+    Overrun { time: Duration, streak: u32 },
+    /// The plugin exceeded its budget too many ticks
+    /// in a row and should be suspended.
+    Suspend { time: Duration, streak: u32 },
+}
+
+/// Tracks the time a plugin spends executing.
+#[derive(Debug, Default)]
+pub struct PluginUsage {
+    /// The tick currently being accumulated.
+    current_tick: Option<u64>,
+    /// Time spent so far in `current_tick`.
+    current_time: Duration,
+    /// Total time of the most recent finished ticks.
+    recent: VecDeque<Duration>,
+    /// Number of consecutive ticks over the budget.
+    overrun_streak: u32,
+    suspended: Option<String>,
+}
+
+impl PluginUsage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the plugin ran for `time` during `tick`.
+    ///
+    /// When `tick` is later than the tick being accumulated,
+    /// that earlier tick is finished and checked against `quotas`.
+    pub fn record(&mut self, tick: u64, time: Duration, quotas: &PluginQuotas) -> TickVerdict {
+        let verdict = match self.current_tick {
+            Some(current) if current != tick => self.finish_tick(quotas),
+            _ => TickVerdict::WithinBudget,
+        };
+        self.current_tick = Some(tick);
+        self.current_time += time;
+        verdict
+    }
+
+    fn finish_tick(&mut self, quotas: &PluginQuotas) -> TickVerdict {
+        let time = std::mem::take(&mut self.current_time);
+        if self.recent.len() == TIMINGS_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(time);
+
+        if time <= quotas.tick_time_budget {
+            self.overrun_streak = 0;
+            return TickVerdict::WithinBudget;
+        }
+
+        self.overrun_streak += 1;
+        let streak = self.overrun_streak;
+        match quotas.suspend_after_overruns {
+            Some(max) if streak >= max => TickVerdict::Suspend { time, streak },
+            _ => TickVerdict::Overrun { time, streak },
+        }
+    }
+
+    /// Average time per tick over the last few seconds.
+    pub fn average_tick_time(&self) -> Duration {
+        if self.recent.is_empty() {
+            return Duration::default();
+        }
+        self.recent.iter().sum::<Duration>() / self.recent.len() as u32
+    }
+
+    /// Longest tick time over the last few seconds.
+    pub fn max_tick_time(&self) -> Duration {
+        self.recent.iter().copied().max().unwrap_or_default()
+    }
+
+    /// Marks the plugin as suspended for the given reason.
+    pub fn suspend(&mut self, reason: impl Into<String>) {
+        self.suspended = Some(reason.into());
+    }
+
+    /// Returns why the plugin was suspended, if it is.
+    pub fn suspended(&self) -> Option<&str> {
+        self.suspended.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quotas() -> PluginQuotas {
+        PluginQuotas {
+            tick_time_budget: Duration::from_millis(5),
+            suspend_after_overruns: Some(2),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accumulates_within_a_tick() {
+        let quotas = quotas();
+        let mut usage = PluginUsage::new();
+        usage.record(0, Duration::from_millis(3), &quotas);
+        usage.record(0, Duration::from_millis(3), &quotas);
+        assert_eq!(
+            usage.record(1, Duration::from_millis(1), &quotas),
+            TickVerdict::Overrun {
+                time: Duration::from_millis(6),
+                streak: 1
+            }
+        );
+        assert_eq!(usage.average_tick_time(), Duration::from_millis(6));
+    }
+
+    #[test]
+    fn suspends_after_consecutive_overruns() {
+        let quotas = quotas();
+        let mut usage = PluginUsage::new();
+        usage.record(0, Duration::from_millis(6), &quotas);
+        usage.record(1, Duration::from_millis(6), &quotas);
+        assert!(matches!(
+            usage.record(2, Duration::default(), &quotas),
+            TickVerdict::Suspend { streak: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn streak_resets_within_budget() {
+        let quotas = quotas();
+        let mut usage = PluginUsage::new();
+        usage.record(0, Duration::from_millis(6), &quotas);
+        usage.record(1, Duration::from_millis(1), &quotas);
+        usage.record(2, Duration::from_millis(6), &quotas);
+        assert!(matches!(
+            usage.record(3, Duration::default(), &quotas),
+            TickVerdict::Overrun { streak: 1, .. }
+        ));
+        assert_eq!(usage.max_tick_time(), Duration::from_millis(6));
+    }
+}
@@ -0,0 +1,128 @@
+//! Wasmer tunables which cap the linear memory of plugin instances.
+
+use std::{ptr::NonNull, sync::Arc};
+
+use wasmer::{
+    vm::{self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition},
+    MemoryType, Pages, TableType, Tunables,
+};
+
+/// Wraps another set of tunables, limiting every memory
+/// created through them to at most `limit` pages.
+///
+/// Memories which don't declare a maximum, or declare one
+/// above the limit, have their maximum lowered to the limit.
+/// `memory.grow` past it then fails inside the plugin
+/// instead of taking memory from the server.
+pub struct LimitingTunables<T> {
+    limit: Pages,
+    base: T,
+}
+
+impl<T: Tunables> LimitingTunables<T> {
+    pub fn new(base: T, limit: Pages) -> Self {
+        Self { limit, base }
+    }
+
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = *requested;
+        adjusted.maximum = Some(match requested.maximum {
+            Some(maximum) => maximum.min(self.limit),
+            None => self.limit,
+        });
+        adjusted
+    }
+
+    fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        if ty.minimum > self.limit {
+            return Err(MemoryError::Generic(format!(
+                "plugin requires {} pages of memory (limit {})",
+                ty.minimum.0, self.limit.0
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<T: Tunables> Tunables for LimitingTunables<T> {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(&self.adjust_memory(memory))
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base.create_host_memory(&adjusted, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base
+            .create_vm_memory(&adjusted, style, vm_definition_location)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmer::{BaseTunables, Target};
+
+    use super::*;
+
+    fn tunables() -> LimitingTunables<BaseTunables> {
+        LimitingTunables::new(BaseTunables::for_target(&Target::default()), Pages(16))
+    }
+
+    #[test]
+    fn caps_unbounded_memory() {
+        let adjusted = tunables().adjust_memory(&MemoryType::new(1, None, false));
+        assert_eq!(adjusted.maximum, Some(Pages(16)));
+    }
+
+    #[test]
+    fn lowers_maximum_above_limit() {
+        let tunables = tunables();
+        let adjusted = tunables.adjust_memory(&MemoryType::new(1, Some(1024), false));
+        assert_eq!(adjusted.maximum, Some(Pages(16)));
+        let adjusted = tunables.adjust_memory(&MemoryType::new(1, Some(8), false));
+        assert_eq!(adjusted.maximum, Some(Pages(8)));
+    }
+
+    #[test]
+    fn rejects_minimum_above_limit() {
+        let tunables = tunables();
+        assert!(tunables
+            .validate_memory(&MemoryType::new(32, None, false))
+            .is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Pings a server and prints its status response,
+//! the JSON shown in the multiplayer server list.
+//!
+//! Usage: `cargo run -p feather-protocol --example status_ping -- <host> [<port>]`
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+};
+
+use anyhow::bail;
+use feather_protocol::{
+    packets::client::{Handshake, HandshakeState, Request},
+    ClientHandshakePacket, ClientStatusPacket, MinecraftCodec, ProtocolVersion, ServerStatusPacket,
+};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let host = args.next().unwrap_or_else(|| "localhost".to_owned());
+    let port = match args.next() {
+        Some(port) => port.parse()?,
+        None => 25565,
+    };
+
+    let mut stream = TcpStream::connect((host.as_str(), port))?;
+    let mut codec = MinecraftCodec::new();
+
+    let mut buffer = Vec::new();
+    codec.encode(
+        &ClientHandshakePacket::Handshake(Handshake {
+            protocol_version: ProtocolVersion::LATEST.protocol_number(),
+            server_address: host,
+            server_port: port,
+            next_state: HandshakeState::Status,
+        }),
+        &mut buffer,
+    );
+    codec.encode(&ClientStatusPacket::Request(Request {}), &mut buffer);
+    stream.write_all(&buffer)?;
+
+    let mut received = [0; 4096];
+    loop {
+        if let Some(packet) = codec.next_packet::<ServerStatusPacket>()? {
+            match packet {
+                ServerStatusPacket::Response(response) => {
+                    println!("{}", response.response);
+                    return Ok(());
+                }
+                packet => bail!("unexpected packet {:?}", packet),
+            }
+        }
+
+        let read = stream.read(&mut received)?;
+        if read == 0 {
+            bail!("server closed the connection");
+        }
+        codec.accept(&received[..read]);
+    }
+}
@@ -0,0 +1,273 @@
+//! Derive macro for packet serialization in `feather-protocol`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    spanned::Spanned, Data, DeriveInput, Field, Fields, GenericArgument, Lit, LitInt, Meta,
+    NestedMeta, PathArguments, Type,
+};
+
+/// Implements `Readable` and `Writeable` for a struct with named fields.
+///
+/// Fields are read and written in declaration order, using
+/// their own `Readable` and `Writeable` implementations.
+/// A field can be annotated with `#[packet(...)]` to select
+/// the wire encoding when it differs from the field type:
+///
+/// * `varint`: an `i32` encoded as a `VarInt`.
+/// * `varlong`: an `i64` encoded as a `VarLong`.
+/// * `angle`: an `f32` in degrees encoded as an `Angle`.
+/// * `length_prefixed`: a `Vec<T>` prefixed with its length as a `VarInt`.
+/// * `short_prefixed`: a `Vec<T>` prefixed with its length as an `i16`.
+/// * `length_inferred`: a `Vec<u8>` spanning the rest of the packet.
+/// * `nbt`: a serde type encoded as NBT.
+/// * `max_length = N`: a `String` of at most `N` characters,
+///   rather than the default maximum.
+///
+/// The generated code refers to `crate::`, so the macro
+/// can only be used inside `feather-protocol`.
+#[proc_macro_derive(Packet, attributes(packet))]
+pub fn derive_packet(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: DeriveInput = syn::parse_macro_input!(input);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            Fields::Unit => {
+                return Ok(expand_impls(&input, quote! { Ok(Self) }, quote! {}));
+            }
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "#[derive(Packet)] requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "#[derive(Packet)] only supports structs; use `def_enum!` for enums",
+            ))
+        }
+    };
+
+    let mut reads = Vec::new();
+    let mut writes = Vec::new();
+    let mut field_idents = Vec::new();
+    for field in fields {
+        let field_ident = field.ident.as_ref().expect("named field");
+        let encoding = Encoding::of(field)?;
+        let context = format!(
+            "failed to read field `{}` of packet `{}`",
+            field_ident, ident
+        );
+
+        let ty = &field.ty;
+        let read = encoding.read(ty)?;
+        reads.push(quote! {
+            let #field_ident: #ty = #read.context(#context)?;
+        });
+        let write = encoding.write(quote! { self.#field_ident });
+        writes.push(quote! {
+            crate::Writeable::write(&#write, buffer, version);
+        });
+        field_idents.push(field_ident);
+    }
+
+    let read_body = quote! {
+        use anyhow::Context as _;
+        #(#reads)*
+        Ok(Self {
+            #(#field_idents,)*
+        })
+    };
+    let write_body = quote! {
+        #(#writes)*
+    };
+    Ok(expand_impls(&input, read_body, write_body))
+}
+
+fn expand_impls(
+    input: &DeriveInput,
+    read_body: TokenStream,
+    write_body: TokenStream,
+) -> TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        #[allow(unused_variables)]
+        impl #impl_generics crate::Readable for #ident #ty_generics #where_clause {
+            fn read(buffer: &mut ::std::io::Cursor<&[u8]>, version: crate::ProtocolVersion) -> anyhow::Result<Self>
+            where
+                Self: Sized,
+            {
+                #read_body
+            }
+        }
+
+        #[allow(unused_variables)]
+        impl #impl_generics crate::Writeable for #ident #ty_generics #where_clause {
+            fn write(&self, buffer: &mut Vec<u8>, version: crate::ProtocolVersion) {
+                #write_body
+            }
+        }
+    }
+}
+
+/// How a field is encoded on the wire.
+enum Encoding {
+    /// The field type's own `Readable`/`Writeable` implementation.
+    Plain,
+    VarInt,
+    VarLong,
+    Angle,
+    LengthPrefixed,
+    ShortPrefixed,
+    LengthInferred,
+    Nbt,
+    LimitedString(LitInt),
+}
+
+impl Encoding {
+    /// Parses the `#[packet(...)]` attribute of a field.
+    fn of(field: &Field) -> syn::Result<Self> {
+        let mut encoding = Encoding::Plain;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("packet"))
+        {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(syn::Error::new(meta.span(), "expected #[packet(...)]")),
+            };
+            for nested in list.nested {
+                let path = match nested {
+                    NestedMeta::Meta(Meta::Path(path)) => path,
+                    NestedMeta::Meta(Meta::NameValue(name_value))
+                        if name_value.path.is_ident("max_length") =>
+                    {
+                        encoding = match name_value.lit {
+                            Lit::Int(max_length) => Encoding::LimitedString(max_length),
+                            lit => {
+                                return Err(syn::Error::new(
+                                    lit.span(),
+                                    "expected a maximum length in characters",
+                                ))
+                            }
+                        };
+                        continue;
+                    }
+                    nested => {
+                        return Err(syn::Error::new(nested.span(), "expected an encoding name"))
+                    }
+                };
+                let name = path
+                    .get_ident()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                encoding = match name.as_str() {
+                    "varint" => Encoding::VarInt,
+                    "varlong" => Encoding::VarLong,
+                    "angle" => Encoding::Angle,
+                    "length_prefixed" => Encoding::LengthPrefixed,
+                    "short_prefixed" => Encoding::ShortPrefixed,
+                    "length_inferred" => Encoding::LengthInferred,
+                    "nbt" => Encoding::Nbt,
+                    _ => {
+                        return Err(syn::Error::new(
+                            path.span(),
+                            format!("unknown packet field encoding `{}`", name),
+                        ))
+                    }
+                };
+            }
+        }
+        Ok(encoding)
+    }
+
+    /// Expression reading a field of type `ty`,
+    /// evaluating to `anyhow::Result<ty>`.
+    fn read(&self, ty: &Type) -> syn::Result<TokenStream> {
+        Ok(match self {
+            Encoding::Plain => quote! { <#ty as crate::Readable>::read(buffer, version) },
+            Encoding::VarInt => quote! {
+                <crate::VarInt as crate::Readable>::read(buffer, version).map(|v| v.0 as _)
+            },
+            Encoding::VarLong => quote! {
+                <crate::VarLong as crate::Readable>::read(buffer, version).map(|v| v.0 as _)
+            },
+            Encoding::Angle => quote! {
+                <crate::io::Angle as crate::Readable>::read(buffer, version).map(|a| a.0)
+            },
+            Encoding::LengthPrefixed => {
+                let inner = vec_element(ty)?;
+                quote! {
+                    <crate::io::LengthPrefixedVec<#inner> as crate::Readable>::read(buffer, version)
+                        .map(Vec::from)
+                }
+            }
+            Encoding::ShortPrefixed => {
+                let inner = vec_element(ty)?;
+                quote! {
+                    <crate::io::ShortPrefixedVec<#inner> as crate::Readable>::read(buffer, version)
+                        .map(Vec::from)
+                }
+            }
+            Encoding::LengthInferred => quote! {
+                <crate::io::LengthInferredVecU8 as crate::Readable>::read(buffer, version)
+                    .map(Vec::from)
+            },
+            Encoding::Nbt => quote! {
+                <crate::Nbt<#ty> as crate::Readable>::read(buffer, version).map(|nbt| nbt.0)
+            },
+            Encoding::LimitedString(max_length) => quote! {
+                crate::io::try_get_limited_string(buffer, version, #max_length).map(str::to_owned)
+            },
+        })
+    }
+
+    /// Expression converting `value` to a `Writeable`.
+    fn write(&self, value: TokenStream) -> TokenStream {
+        match self {
+            Encoding::Plain | Encoding::LimitedString(_) => value,
+            Encoding::VarInt => quote! { crate::VarInt(#value as i32) },
+            Encoding::VarLong => quote! { crate::VarLong(#value as i64) },
+            Encoding::Angle => quote! { crate::io::Angle(#value) },
+            Encoding::LengthPrefixed => {
+                quote! { crate::io::LengthPrefixedVec::from(#value.as_slice()) }
+            }
+            Encoding::ShortPrefixed => {
+                quote! { crate::io::ShortPrefixedVec::from(#value.as_slice()) }
+            }
+            Encoding::LengthInferred => {
+                quote! { crate::io::LengthInferredVecU8::from(#value.as_slice()) }
+            }
+            Encoding::Nbt => quote! { crate::Nbt(&#value) },
+        }
+    }
+}
+
+/// Gets `T` from a field of type `Vec<T>`.
+fn vec_element(ty: &Type) -> syn::Result<&Type> {
+    if let Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                if let Some(GenericArgument::Type(inner)) = args.args.first() {
+                    return Ok(inner);
+                }
+            }
+        }
+    }
+    Err(syn::Error::new(
+        ty.span(),
+        "length-prefixed fields must have type `Vec<T>`",
+    ))
+}
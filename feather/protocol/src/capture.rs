@@ -0,0 +1,303 @@
+//! Packet captures: recordings of the packets sent
+//! over a connection, which can be replayed through
+//! the decoder to reproduce protocol bugs in tests.
+//!
+//! A capture starts with [`MAGIC`] and a format version byte,
+//! followed by one record per packet:
+//! * direction: `u8` (0 = serverbound, 1 = clientbound)
+//! * protocol state: `u8` (0 = handshake, 1 = status, 2 = login, 3 = play)
+//! * milliseconds since the capture started: big-endian `u32`
+//! * length of the packet: big-endian `u32`
+//! * the packet ID and fields, uncompressed and decrypted.
+
+use std::{
+    io::{self, Cursor, Read, Write},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{
+    ClientHandshakePacket, ClientLoginPacket, ClientPacket, ClientPlayPacket, ClientStatusPacket,
+    ProtocolState, ProtocolVersion, Readable, ServerLoginPacket, ServerPacket, ServerPlayPacket,
+    ServerStatusPacket,
+};
+
+/// Bytes at the start of every capture.
+pub const MAGIC: &[u8; 4] = b"FCAP";
+const FORMAT_VERSION: u8 = 1;
+
+/// Largest packet accepted when reading a capture.
+const MAX_PACKET_LENGTH: u32 = 2 * 1024 * 1024;
+
+/// Direction in which a packet was sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent by the client.
+    Serverbound,
+    /// Sent by the server.
+    Clientbound,
+}
+
+/// A packet stored in a capture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedPacket {
+    pub direction: Direction,
+    pub state: ProtocolState,
+    /// Time between the start of the capture and the packet.
+    pub elapsed: Duration,
+    /// The packet ID followed by the packet's fields.
+    pub data: Vec<u8>,
+}
+
+/// Writes packets to a capture.
+pub struct CaptureWriter<W> {
+    writer: W,
+    start: Instant,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Starts a capture, writing its header to `writer`.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_u8(FORMAT_VERSION)?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// Records a packet. `data` is the packet ID followed by the packet's fields.
+    pub fn record(
+        &mut self,
+        direction: Direction,
+        state: ProtocolState,
+        data: &[u8],
+    ) -> io::Result<()> {
+        self.writer.write_u8(match direction {
+            Direction::Serverbound => 0,
+            Direction::Clientbound => 1,
+        })?;
+        self.writer.write_u8(match state {
+            ProtocolState::Handshake => 0,
+            ProtocolState::Status => 1,
+            ProtocolState::Login => 2,
+            ProtocolState::Play => 3,
+        })?;
+        let elapsed = self.start.elapsed().as_millis().min(u32::MAX as u128) as u32;
+        self.writer.write_u32::<BigEndian>(elapsed)?;
+        self.writer.write_u32::<BigEndian>(data.len() as u32)?;
+        self.writer.write_all(data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Reads the packets in a capture.
+pub struct CaptureReader<R> {
+    reader: R,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Reads the header of a capture.
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("not a packet capture");
+        }
+        let format_version = reader.read_u8()?;
+        if format_version != FORMAT_VERSION {
+            bail!("unsupported capture format version {}", format_version);
+        }
+        Ok(Self { reader })
+    }
+
+    /// Reads the next packet, or returns `None` at the end of the capture.
+    pub fn next_packet(&mut self) -> anyhow::Result<Option<CapturedPacket>> {
+        let direction = match self.reader.read_u8() {
+            Ok(0) => Direction::Serverbound,
+            Ok(1) => Direction::Clientbound,
+            Ok(direction) => bail!("invalid packet direction {}", direction),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let state = match self.reader.read_u8()? {
+            0 => ProtocolState::Handshake,
+            1 => ProtocolState::Status,
+            2 => ProtocolState::Login,
+            3 => ProtocolState::Play,
+            state => bail!("invalid protocol state {}", state),
+        };
+        let elapsed = Duration::from_millis(self.reader.read_u32::<BigEndian>()?.into());
+        let length = self.reader.read_u32::<BigEndian>()?;
+        if length > MAX_PACKET_LENGTH {
+            bail!("packet of {} bytes is too large", length);
+        }
+        let mut data = vec![0; length as usize];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some(CapturedPacket {
+            direction,
+            state,
+            elapsed,
+            data,
+        }))
+    }
+}
+
+/// A packet decoded from a capture.
+#[derive(Debug, Clone)]
+pub enum ReplayedPacket {
+    Client(ClientPacket),
+    Server(ServerPacket),
+}
+
+/// Feeds the packets of a capture back through the decoder.
+///
+/// The protocol version is taken from the client's Handshake
+/// packet, like the server does.
+pub struct Replay<R> {
+    reader: CaptureReader<R>,
+    version: ProtocolVersion,
+}
+
+impl<R: Read> Replay<R> {
+    pub fn new(reader: CaptureReader<R>) -> Self {
+        Self {
+            reader,
+            version: ProtocolVersion::LATEST,
+        }
+    }
+
+    /// Decodes the next packet in the capture, or returns `None`
+    /// at its end. Fails if a packet doesn't decode or if bytes
+    /// are left over after decoding it.
+    pub fn next_packet(&mut self) -> anyhow::Result<Option<(CapturedPacket, ReplayedPacket)>> {
+        let captured = match self.reader.next_packet()? {
+            Some(captured) => captured,
+            None => return Ok(None),
+        };
+        let packet = decode(&captured, self.version)?;
+
+        if let ReplayedPacket::Client(ClientPacket::Handshake(ClientHandshakePacket::Handshake(
+            handshake,
+        ))) = &packet
+        {
+            if let Some(version) = ProtocolVersion::from_protocol_number(handshake.protocol_version)
+            {
+                self.version = version;
+            }
+        }
+        Ok(Some((captured, packet)))
+    }
+}
+
+fn decode(captured: &CapturedPacket, version: ProtocolVersion) -> anyhow::Result<ReplayedPacket> {
+    let mut cursor = Cursor::new(captured.data.as_slice());
+    let packet = match (captured.direction, captured.state) {
+        (Direction::Serverbound, ProtocolState::Handshake) => {
+            ReplayedPacket::Client(ClientHandshakePacket::read(&mut cursor, version)?.into())
+        }
+        (Direction::Serverbound, ProtocolState::Status) => {
+            ReplayedPacket::Client(ClientStatusPacket::read(&mut cursor, version)?.into())
+        }
+        (Direction::Serverbound, ProtocolState::Login) => {
+            ReplayedPacket::Client(ClientLoginPacket::read(&mut cursor, version)?.into())
+        }
+        (Direction::Serverbound, ProtocolState::Play) => {
+            ReplayedPacket::Client(ClientPlayPacket::read(&mut cursor, version)?.into())
+        }
+        (Direction::Clientbound, ProtocolState::Handshake) => {
+            bail!("server sent a packet during the handshake")
+        }
+        (Direction::Clientbound, ProtocolState::Status) => {
+            ReplayedPacket::Server(ServerStatusPacket::read(&mut cursor, version)?.into())
+        }
+        (Direction::Clientbound, ProtocolState::Login) => {
+            ReplayedPacket::Server(ServerLoginPacket::read(&mut cursor, version)?.into())
+        }
+        (Direction::Clientbound, ProtocolState::Play) => {
+            ReplayedPacket::Server(ServerPlayPacket::read(&mut cursor, version)?.into())
+        }
+    };
+
+    let left_over = captured.data.len() - cursor.position() as usize;
+    if left_over != 0 {
+        return Err(anyhow!(
+            "{} bytes left over after decoding {:?}",
+            left_over,
+            packet
+        ));
+    }
+    Ok(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        packets::client::{Handshake, HandshakeState, LoginStart},
+        Writeable,
+    };
+
+    fn encode(packet: &impl Writeable) -> Vec<u8> {
+        let mut data = Vec::new();
+        packet.write(&mut data, ProtocolVersion::LATEST);
+        data
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let handshake = ClientHandshakePacket::Handshake(Handshake {
+            protocol_version: ProtocolVersion::V1_16_2.protocol_number(),
+            server_address: "localhost".to_owned(),
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        });
+        let login_start = ClientLoginPacket::LoginStart(LoginStart {
+            name: "Notch".to_owned(),
+        });
+
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer
+            .record(
+                Direction::Serverbound,
+                ProtocolState::Handshake,
+                &encode(&handshake),
+            )
+            .unwrap();
+        writer
+            .record(
+                Direction::Serverbound,
+                ProtocolState::Login,
+                &encode(&login_start),
+            )
+            .unwrap();
+        let mut trailing = encode(&login_start);
+        trailing.push(0);
+        writer
+            .record(Direction::Serverbound, ProtocolState::Login, &trailing)
+            .unwrap();
+
+        let mut replay = Replay::new(CaptureReader::new(writer.writer.as_slice()).unwrap());
+        replay.next_packet().unwrap().unwrap();
+        assert_eq!(replay.version, ProtocolVersion::V1_16_2);
+        match replay.next_packet().unwrap().unwrap().1 {
+            ReplayedPacket::Client(ClientPacket::Login(ClientLoginPacket::LoginStart(packet))) => {
+                assert_eq!(packet.name, "Notch")
+            }
+            packet => panic!("replayed wrong packet: {:?}", packet),
+        }
+        assert!(replay.next_packet().is_err());
+        assert!(replay.next_packet().unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(CaptureReader::new(&b"PNG\x0d\x0a"[..]).is_err());
+    }
+}
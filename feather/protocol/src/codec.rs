@@ -1,6 +1,11 @@
-use crate::{io::VarInt, ProtocolVersion, Readable, Writeable};
+use crate::{
+    framing::{self, FrameError},
+    io::VarInt,
+    translation::Translation,
+    ProtocolVersion, Readable, Writeable,
+};
 use aes::Aes128;
-use bytes::BytesMut;
+use bytes::{Buf, Bytes, BytesMut};
 use cfb8::{
     stream_cipher::{NewStreamCipher, StreamCipher},
     Cfb8,
@@ -9,22 +14,35 @@ use flate2::{
     bufread::{ZlibDecoder, ZlibEncoder},
     Compression,
 };
-use std::io::{Cursor, Read};
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    io::{Cursor, Read},
+};
 
 type AesCfb8 = Cfb8<Aes128>;
 pub type CompressionThreshold = usize;
 
+/// Largest uncompressed packet length accepted, as in vanilla.
+const MAX_DATA_LENGTH: usize = 2 * 1024 * 1024;
+
 /// An encryption key for use with AES-CFB8.
 pub type CryptKey = [u8; 16];
 
 /// State to serialize and deserialize packets from a byte stream.
-#[derive(Default)]
 pub struct MinecraftCodec {
     /// If encryption is enabled, then this is the cryptor state.
     cryptor: Option<AesCfb8>,
     crypt_key: Option<CryptKey>,
     /// If compression is enabled, then this is the compression threshold.
     compression: Option<CompressionThreshold>,
+    /// zlib level used to compress packets.
+    compression_level: Compression,
+    /// Protocol version used to encode and decode packets.
+    version: ProtocolVersion,
+    /// If the client's version is not supported natively,
+    /// then this translates packets to and from it.
+    translation: Option<Translation>,
 
     /// A buffer of received bytes.
     received_buf: BytesMut,
@@ -32,6 +50,25 @@ pub struct MinecraftCodec {
     staging_buf: Vec<u8>,
     /// Another auxilary buffer.
     compression_target: Vec<u8>,
+    /// Translated packet bodies which haven't been returned yet.
+    translated_frames: VecDeque<Bytes>,
+}
+
+impl Default for MinecraftCodec {
+    fn default() -> Self {
+        Self {
+            cryptor: None,
+            crypt_key: None,
+            compression: None,
+            compression_level: Compression::default(),
+            version: ProtocolVersion::LATEST,
+            translation: None,
+            received_buf: BytesMut::new(),
+            staging_buf: Vec::new(),
+            compression_target: Vec::new(),
+            translated_frames: VecDeque::new(),
+        }
+    }
 }
 
 impl MinecraftCodec {
@@ -46,13 +83,40 @@ impl MinecraftCodec {
         self.crypt_key = Some(key);
     }
 
+    /// Sets the protocol version used to encode and decode packets.
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.version = version;
+    }
+
+    /// Gets the protocol version used to encode and decode packets.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Starts translating packets if the protocol version
+    /// is not supported natively. Returns whether packets
+    /// are translated.
+    ///
+    /// Must be called once the connection enters the Play state,
+    /// since only Play packets can be translated.
+    pub fn enable_translation(&mut self) -> bool {
+        self.translation = Translation::for_version(self.version);
+        self.translation.is_some()
+    }
+
     /// Enables compression with the provided compression threshold.
     pub fn enable_compression(&mut self, threshold: CompressionThreshold) {
         self.compression = Some(threshold);
     }
 
-    /// Gets another `MinecraftCodec` with the same compression and encryption
-    /// parameters.
+    /// Sets the zlib level used to compress packets, from
+    /// 0 (fastest) to 9 (smallest). Higher levels are clamped to 9.
+    pub fn set_compression_level(&mut self, level: u32) {
+        self.compression_level = Compression::new(level.min(9));
+    }
+
+    /// Gets another `MinecraftCodec` with the same compression, encryption,
+    /// and protocol version parameters.
     pub fn clone_with_settings(&self) -> MinecraftCodec {
         MinecraftCodec {
             cryptor: self
@@ -60,15 +124,59 @@ impl MinecraftCodec {
                 .map(|key| AesCfb8::new_var(&key, &key).expect("key size is invalid")),
             crypt_key: self.crypt_key,
             compression: self.compression,
+            compression_level: self.compression_level,
+            version: self.version,
+            translation: self.translation.clone(),
             received_buf: BytesMut::new(),
             staging_buf: Vec::new(),
             compression_target: Vec::new(),
+            translated_frames: VecDeque::new(),
         }
     }
 
+    /// Gets the compression threshold, if compression is enabled.
+    pub fn compression(&self) -> Option<CompressionThreshold> {
+        self.compression
+    }
+
     /// Writes a packet into the provided writer.
     pub fn encode(&mut self, packet: &impl Writeable, output: &mut Vec<u8>) {
-        packet.write(&mut self.staging_buf, ProtocolVersion::V1_16_2);
+        let start = output.len();
+        self.encode_frame(packet, output);
+        self.encrypt(&mut output[start..]);
+    }
+
+    /// Writes a packet's frame, i.e. its length-prefixed and
+    /// possibly compressed bytes, without encrypting it.
+    ///
+    /// A frame can be written to any connection with the same
+    /// protocol version and compression settings using [`write_frame`](Self::write_frame),
+    /// so packets sent to many clients only need to be encoded once.
+    pub fn encode_frame(&mut self, packet: &impl Writeable, output: &mut Vec<u8>) {
+        packet.write(&mut self.staging_buf, self.version);
+        self.finish_frame(output);
+    }
+
+    /// Like [`encode`](Self::encode), but for a packet
+    /// already written with [`Writeable::write`].
+    pub fn encode_data(&mut self, data: &[u8], output: &mut Vec<u8>) {
+        let start = output.len();
+        self.staging_buf.extend_from_slice(data);
+        self.finish_frame(output);
+        self.encrypt(&mut output[start..]);
+    }
+
+    /// Frames the packet in the staging buffer.
+    fn finish_frame(&mut self, output: &mut Vec<u8>) {
+        if let Some(translation) = &mut self.translation {
+            // Packets written by the server are always valid
+            // in the native format.
+            translation
+                .clientbound(&self.staging_buf, &mut self.compression_target)
+                .expect("failed to translate packet");
+            std::mem::swap(&mut self.staging_buf, &mut self.compression_target);
+            self.compression_target.clear();
+        }
 
         if let Some(threshold) = self.compression {
             self.encode_compressed(output, threshold);
@@ -76,14 +184,24 @@ impl MinecraftCodec {
             self.encode_uncompressed(output);
         }
 
+        self.staging_buf.clear();
+    }
+
+    /// Writes a frame produced by [`encode_frame`](Self::encode_frame).
+    pub fn write_frame(&mut self, frame: &[u8], output: &mut Vec<u8>) {
+        let start = output.len();
+        output.extend_from_slice(frame);
+        self.encrypt(&mut output[start..]);
+    }
+
+    fn encrypt(&mut self, bytes: &mut [u8]) {
         if let Some(cryptor) = &mut self.cryptor {
-            cryptor.encrypt(output);
+            cryptor.encrypt(bytes);
         }
-
-        self.staging_buf.clear();
     }
 
     fn encode_compressed(&mut self, output: &mut Vec<u8>, threshold: CompressionThreshold) {
+        let version = self.version;
         let (data_length, data) = if self.staging_buf.len() >= threshold {
             self.data_compressed()
         } else {
@@ -98,15 +216,15 @@ impl MinecraftCodec {
             .unwrap();
 
         let packet_length = data_length_bytes.position() as usize + data.len();
-        VarInt(packet_length as i32).write(output, ProtocolVersion::V1_16_2);
-        VarInt(data_length as i32).write(output, ProtocolVersion::V1_16_2);
+        VarInt(packet_length as i32).write(output, version);
+        VarInt(data_length as i32).write(output, version);
         output.extend_from_slice(data);
 
         self.compression_target.clear();
     }
 
     fn data_compressed(&mut self) -> (usize, &[u8]) {
-        let mut encoder = ZlibEncoder::new(self.staging_buf.as_slice(), Compression::default());
+        let mut encoder = ZlibEncoder::new(self.staging_buf.as_slice(), self.compression_level);
         encoder
             .read_to_end(&mut self.compression_target)
             .expect("compression failed");
@@ -121,7 +239,7 @@ impl MinecraftCodec {
         // TODO: we should probably be able to determine the length without writing the packet,
         // which could remove an unnecessary copy.
         let length = self.staging_buf.len() as i32;
-        VarInt(length).write(output, ProtocolVersion::V1_16_2);
+        VarInt(length).write(output, self.version);
         output.extend_from_slice(&self.staging_buf);
     }
 
@@ -142,40 +260,222 @@ impl MinecraftCodec {
     where
         T: Readable,
     {
-        let mut cursor = Cursor::new(&self.received_buf[..]);
-        let packet = if let Ok(length) = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2) {
-            let length_field_length = cursor.position() as usize;
-
-            if self.received_buf.len() - length_field_length >= length.0 as usize {
-                cursor = Cursor::new(
-                    &self.received_buf
-                        [length_field_length..length_field_length + length.0 as usize],
-                );
-
-                if self.compression.is_some() {
-                    let data_length = VarInt::read(&mut cursor, ProtocolVersion::V1_16_2)?;
-                    if data_length.0 != 0 {
-                        let mut decoder =
-                            ZlibDecoder::new(&cursor.get_ref()[cursor.position() as usize..]);
-                        decoder.read_to_end(&mut self.compression_target)?;
-                        cursor = Cursor::new(&self.compression_target);
-                    }
-                }
+        match self.next_frame()? {
+            Some(frame) => {
+                let mut cursor = Cursor::new(&frame[..]);
+                T::read(&mut cursor, self.version).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
 
-                let packet = T::read(&mut cursor, ProtocolVersion::V1_16_2)?;
+    /// Gets the body of the next packet that was received, if any,
+    /// with the length prefix removed and decompressed.
+    ///
+    /// Uncompressed bodies share the receive buffer's allocation
+    /// instead of being copied. Reading a packet from the body
+    /// still copies its strings and byte arrays.
+    pub fn next_frame(&mut self) -> anyhow::Result<Option<Bytes>> {
+        loop {
+            if let Some(frame) = self.translated_frames.pop_front() {
+                return Ok(Some(frame));
+            }
+            let frame = match self.next_received_frame()? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            match &self.translation {
+                Some(translation) => translation.serverbound(frame, &mut self.translated_frames)?,
+                None => return Ok(Some(frame)),
+            }
+        }
+    }
 
-                let bytes_read = cursor.position() as usize + length_field_length;
-                self.received_buf = self.received_buf.split_off(bytes_read);
+    fn next_received_frame(&mut self) -> anyhow::Result<Option<Bytes>> {
+        let mut frame = match framing::split_frame(&mut self.received_buf)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
 
-                self.compression_target.clear();
-                Some(packet)
+        if self.compression.is_some() {
+            let mut cursor = Cursor::new(&frame[..]);
+            let data_length = VarInt::read(&mut cursor, self.version)?.0;
+            let data_start = cursor.position() as usize;
+            if data_length != 0 {
+                let data_length = usize::try_from(data_length)
+                    .ok()
+                    .filter(|&data_length| data_length <= MAX_DATA_LENGTH)
+                    .ok_or(FrameError::InvalidDataLength(data_length))?;
+                let mut data = Vec::with_capacity(data_length);
+                ZlibDecoder::new(&frame[data_start..])
+                    .take(data_length as u64 + 1)
+                    .read_to_end(&mut data)
+                    .map_err(FrameError::Decompression)?;
+                if data.len() != data_length {
+                    return Err(FrameError::DataLengthMismatch {
+                        expected: data_length,
+                        actual: data.len(),
+                    }
+                    .into());
+                }
+                frame = Bytes::from(data);
             } else {
-                None
+                frame.advance(data_start);
             }
-        } else {
-            None
+        }
+
+        Ok(Some(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(compression: Option<CompressionThreshold>) {
+        let mut encoder = MinecraftCodec::new();
+        let mut decoder = MinecraftCodec::new();
+        if let Some(threshold) = compression {
+            encoder.enable_compression(threshold);
+            decoder.enable_compression(threshold);
+        }
+
+        let long = "feather".repeat(20);
+        let mut bytes = Vec::new();
+        encoder.encode(&"short".to_owned(), &mut bytes);
+        encoder.encode(&long, &mut bytes);
+
+        // Feed the packets in two halves to exercise partial frames.
+        let (first, second) = bytes.split_at(bytes.len() / 2);
+        decoder.accept(first);
+        assert_eq!(
+            decoder.next_packet::<String>().unwrap().as_deref(),
+            Some("short")
+        );
+        assert_eq!(decoder.next_packet::<String>().unwrap(), None);
+        decoder.accept(second);
+        assert_eq!(decoder.next_packet::<String>().unwrap(), Some(long));
+        assert_eq!(decoder.next_packet::<String>().unwrap(), None);
+    }
+
+    #[test]
+    fn uncompressed_round_trip() {
+        round_trip(None);
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        round_trip(Some(64));
+    }
+
+    #[test]
+    fn compression_levels() {
+        let packet = "feather".repeat(100);
+        let encode = |level| {
+            let mut codec = MinecraftCodec::new();
+            codec.enable_compression(64);
+            codec.set_compression_level(level);
+            let mut bytes = Vec::new();
+            codec.encode(&packet, &mut bytes);
+            bytes
         };
+        let fastest = encode(0);
+        let smallest = encode(9);
+        assert!(smallest.len() < fastest.len());
+
+        let mut decoder = MinecraftCodec::new();
+        decoder.enable_compression(64);
+        decoder.accept(&fastest);
+        assert_eq!(decoder.next_packet::<String>().unwrap(), Some(packet));
+    }
+
+    #[test]
+    fn encoded_data_matches_packets() {
+        let packet = "feather".repeat(20);
+        let mut data = Vec::new();
+        packet.write(&mut data, ProtocolVersion::LATEST);
+
+        let mut codec = MinecraftCodec::new();
+        codec.enable_compression(64);
+        let (mut from_packet, mut from_data) = (Vec::new(), Vec::new());
+        codec.encode(&packet, &mut from_packet);
+        codec.encode_data(&data, &mut from_data);
+        assert_eq!(from_packet, from_data);
+    }
+
+    #[test]
+    fn shared_frames() {
+        let key = [7; 16];
+        let mut encoder = MinecraftCodec::new();
+        encoder.enable_compression(64);
+        encoder.enable_encryption(key);
+        let mut decoder = encoder.clone_with_settings();
+
+        let long = "feather".repeat(20);
+        let mut frame = Vec::new();
+        encoder.encode_frame(&long, &mut frame);
+
+        // Encryption only applies to the newly written bytes.
+        let mut bytes = Vec::new();
+        encoder.encode(&"short".to_owned(), &mut bytes);
+        encoder.write_frame(&frame, &mut bytes);
+        encoder.write_frame(&frame, &mut bytes);
 
-        Ok(packet)
+        decoder.accept(&bytes);
+        assert_eq!(
+            decoder.next_packet::<String>().unwrap().as_deref(),
+            Some("short")
+        );
+        assert_eq!(
+            decoder.next_packet::<String>().unwrap().as_ref(),
+            Some(&long)
+        );
+        assert_eq!(decoder.next_packet::<String>().unwrap(), Some(long));
+        assert_eq!(decoder.next_packet::<String>().unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_lengths() {
+        let mut codec = MinecraftCodec::new();
+        codec.accept(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        let error = codec.next_frame().unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<FrameError>(),
+            Some(FrameError::TooLong)
+        ));
+
+        let mut codec = MinecraftCodec::new();
+        codec.enable_compression(64);
+        // Claims to decompress to 64 MiB.
+        codec.accept(&[5, 0x80, 0x80, 0x80, 0x20, 0]);
+        let error = codec.next_frame().unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<FrameError>(),
+            Some(FrameError::InvalidDataLength(0x0400_0000))
+        ));
+    }
+
+    #[test]
+    fn translated_round_trip() {
+        use crate::{packets::server::KeepAlive, ClientPlayPacket, ServerPlayPacket};
+
+        let mut codec = MinecraftCodec::new();
+        codec.set_version(ProtocolVersion::V1_16_1);
+        assert!(codec.enable_translation());
+
+        // Keep Alive moved from 0x1F to 0x20.
+        let mut bytes = Vec::new();
+        codec.encode(
+            &ServerPlayPacket::KeepAlive(KeepAlive { id: 5 }),
+            &mut bytes,
+        );
+        assert_eq!(&bytes[..2], &[9, 0x20]);
+
+        // Use Item moved from 0x2E to 0x2F.
+        codec.accept(&[2, 0x2E, 1]);
+        match codec.next_packet::<ClientPlayPacket>().unwrap() {
+            Some(ClientPlayPacket::UseItem(use_item)) => assert_eq!(use_item.hand, 1),
+            packet => panic!("unexpected packet {:?}", packet),
+        }
     }
 }
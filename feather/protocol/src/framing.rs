@@ -0,0 +1,107 @@
+//! Splitting a byte stream into frames.
+//!
+//! Each packet is sent as a frame: its length as a VarInt,
+//! followed by that many bytes. A frame may arrive over
+//! several reads, so [`split_frame`] leaves incomplete
+//! frames in the buffer until the rest is received.
+
+use bytes::{Buf, Bytes, BytesMut};
+use std::io;
+use thiserror::Error;
+
+/// Largest frame length accepted, as in vanilla:
+/// the largest which fits in a three-byte VarInt.
+pub const MAX_FRAME_LENGTH: usize = (1 << 21) - 1;
+
+/// Length of the VarInt holding [`MAX_FRAME_LENGTH`].
+const MAX_HEADER_LENGTH: usize = 3;
+
+/// Reads the length prefix at the start of `buf`.
+///
+/// Returns the length of the prefix and of the frame
+/// following it, or `None` if the prefix is incomplete.
+pub fn read_header(buf: &[u8]) -> Result<Option<(usize, usize)>, FrameError> {
+    let mut length = 0;
+    for (i, &byte) in buf.iter().take(MAX_HEADER_LENGTH).enumerate() {
+        length |= usize::from(byte & 0b0111_1111) << (7 * i);
+        if byte & 0b1000_0000 == 0 {
+            return Ok(Some((i + 1, length)));
+        }
+    }
+
+    if buf.len() >= MAX_HEADER_LENGTH {
+        Err(FrameError::TooLong)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Removes the next frame from the start of `buf`, without
+/// its length prefix. Returns `None` if the frame hasn't
+/// been fully received yet.
+pub fn split_frame(buf: &mut BytesMut) -> Result<Option<Bytes>, FrameError> {
+    let (header_length, length) = match read_header(buf)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let frame_end = header_length + length;
+    if buf.len() < frame_end {
+        // Make room for the rest of the frame at once.
+        buf.reserve(frame_end - buf.len());
+        return Ok(None);
+    }
+
+    buf.advance(header_length);
+    Ok(Some(buf.split_to(length).freeze()))
+}
+
+/// An error which occurred while splitting
+/// received bytes into packets.
+#[derive(Debug, Error)]
+pub enum FrameError {
+    #[error("packet is longer than the maximum of {} bytes", MAX_FRAME_LENGTH)]
+    TooLong,
+    #[error("invalid uncompressed packet length {0}")]
+    InvalidDataLength(i32),
+    #[error("failed to decompress packet")]
+    Decompression(#[source] io::Error),
+    #[error("packet decompressed to {actual} bytes instead of {expected}")]
+    DataLengthMismatch { expected: usize, actual: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_split_once_complete() {
+        let mut buf = BytesMut::new();
+        // A 300 byte frame, whose length takes two bytes.
+        buf.extend_from_slice(&[0b1010_1100]);
+        assert!(split_frame(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(&[0b0000_0010]);
+        buf.extend_from_slice(&[1; 299]);
+        assert!(split_frame(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(&[1, 5]);
+
+        let frame = split_frame(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.len(), 300);
+        assert_eq!(&buf[..], &[5]);
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        assert_eq!(
+            read_header(&[0xFF, 0xFF, 0x7F]).unwrap(),
+            Some((3, MAX_FRAME_LENGTH))
+        );
+        assert!(matches!(
+            read_header(&[0xFF, 0xFF, 0xFF]),
+            Err(FrameError::TooLong)
+        ));
+        assert!(matches!(
+            split_frame(&mut BytesMut::from(&[0x80, 0x80, 0x80, 0x01][..])),
+            Err(FrameError::TooLong)
+        ));
+    }
+}
@@ -2,22 +2,25 @@
 
 use crate::{ProtocolVersion, Slot};
 use anyhow::{anyhow, bail, Context};
-use base::{
-    anvil::entity::ItemNbt, metadata::MetaEntry, BlockId, BlockPosition, Direction, EntityMetadata,
-    Gamemode, Item, ItemStack,
-};
+use blocks::BlockId;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use generated::{Item, ItemStack};
+use libcraft_blocks::BlockState;
+use libcraft_core::{BlockPosition, Gamemode};
+use libcraft_particles::ParticleKind;
+use libcraft_text::Text;
 use num_traits::{FromPrimitive, ToPrimitive};
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
     borrow::Cow,
-    collections::BTreeMap,
     convert::{TryFrom, TryInto},
     io::{self, Cursor, Read, Write},
     iter,
     num::TryFromIntError,
+    str::Utf8Error,
 };
 use thiserror::Error;
+use types::{item_nbt::ItemNbt, metadata::MetaEntry, Direction, EntityMetadata, NamespacedId};
 use uuid::Uuid;
 
 /// Trait implemented for types which can be read
@@ -50,6 +53,10 @@ where
 pub enum Error {
     #[error("unexpected end of input: failed to read value of type `{0}`")]
     UnexpectedEof(&'static str),
+    #[error("string contained invalid UTF-8")]
+    InvalidUtf8(#[source] Utf8Error),
+    #[error("string of {length} characters exceeds maximum length of {max}")]
+    StringTooLong { length: usize, max: usize },
 }
 
 macro_rules! integer_impl {
@@ -251,7 +258,7 @@ impl Readable for VarLong {
 
             if num_read > 10 {
                 bail!(
-                    "VarInt too long (max length: 5, value read so far: {})",
+                    "VarLong too long (max length: 10, value read so far: {})",
                     result
                 );
             }
@@ -275,6 +282,14 @@ impl From<i64> for VarLong {
     }
 }
 
+impl VarLong {
+    /// Returns the number of bytes needed to encode this value.
+    pub fn needed_bytes(&self) -> usize {
+        let bits = 64 - (self.0 as u64).leading_zeros() as usize;
+        ((bits + 6) / 7).max(1)
+    }
+}
+
 impl Writeable for VarLong {
     fn write(&self, buffer: &mut Vec<u8>, _version: ProtocolVersion) {
         let mut x = self.0 as u64;
@@ -299,34 +314,77 @@ impl Readable for String {
     where
         Self: Sized,
     {
-        // Length is encoded as VarInt.
-        // Following `length` bytes are the UTF8-encoded
-        // string.
+        try_get_string(buffer, version).map(str::to_owned)
+    }
+}
+
+/// Maximum length of strings without a lower limit,
+/// in characters.
+pub const MAX_STRING_LENGTH: usize = 32767;
+
+/// Reads a string as a slice of the buffer.
+///
+/// [`Readable`] for `String` copies the string out of
+/// this slice, so decoded packets still own their strings.
+///
+/// Length is encoded as VarInt. Following `length`
+/// bytes are the UTF8-encoded string.
+pub fn try_get_string<'a>(
+    buffer: &mut Cursor<&'a [u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<&'a str> {
+    try_get_limited_string(buffer, version, MAX_STRING_LENGTH)
+}
 
-        let length = VarInt::read(buffer, version)
-            .context("failed to read string length")?
-            .0 as usize;
+/// Reads a string of at most `max_length` characters
+/// as a slice of the buffer.
+///
+/// Like in vanilla, characters are counted as UTF-16 code units,
+/// so characters outside the Basic Multilingual Plane count twice.
+pub fn try_get_limited_string<'a>(
+    buffer: &mut Cursor<&'a [u8]>,
+    version: ProtocolVersion,
+    max_length: usize,
+) -> anyhow::Result<&'a str> {
+    let length = VarInt::read(buffer, version).context("failed to read string length")?;
+    let length =
+        usize::try_from(length.0).map_err(|_| anyhow!("negative string length {}", length.0))?;
+
+    // A character takes at most 4 bytes.
+    let max_bytes = max_length * 4;
+    if length > max_bytes {
+        bail!(
+            "string length {} bytes exceeds maximum allowed length of {} bytes",
+            length,
+            max_bytes
+        );
+    }
 
-        // TODO: support custom length limits
-        // Current max length is max value of a signed 16-bit int.
-        let max_length = std::i16::MAX as usize;
+    let bytes = try_get_bytes(buffer, length).map_err(|_| Error::UnexpectedEof("String"))?;
+    let string = std::str::from_utf8(bytes).map_err(Error::InvalidUtf8)?;
+    // Strings have at least as many bytes as UTF-16 code units.
+    if length > max_length {
+        let length = string.encode_utf16().count();
         if length > max_length {
-            bail!(
-                "string length {} exceeds maximum allowed length of {}",
+            return Err(Error::StringTooLong {
                 length,
-                max_length
-            );
+                max: max_length,
+            }
+            .into());
         }
+    }
+    Ok(string)
+}
 
-        // Read string into buffer.
-        let mut temp = vec![0u8; length];
-        buffer
-            .read_exact(&mut temp)
-            .map_err(|_| Error::UnexpectedEof("String"))?;
-        let s = std::str::from_utf8(&temp).context("string contained invalid UTF8")?;
-
-        Ok(s.to_owned())
+/// Reads `length` bytes as a slice of the buffer.
+pub fn try_get_bytes<'a>(buffer: &mut Cursor<&'a [u8]>, length: usize) -> Result<&'a [u8], Error> {
+    let data: &'a [u8] = *buffer.get_ref();
+    let start = (buffer.position() as usize).min(data.len());
+    if data.len() - start < length {
+        return Err(Error::UnexpectedEof("bytes"));
     }
+    buffer.set_position((start + length) as u64);
+    Ok(&data[start..start + length])
 }
 
 impl Writeable for String {
@@ -336,6 +394,40 @@ impl Writeable for String {
     }
 }
 
+impl Readable for Text {
+    fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let json = String::read(buffer, version)?;
+        serde_json::from_str(&json).map_err(anyhow::Error::from)
+    }
+}
+
+impl Writeable for Text {
+    fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) {
+        self.to_string().write(buffer, version);
+    }
+}
+
+impl Readable for NamespacedId {
+    fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let string = String::read(buffer, version)?;
+        string
+            .parse()
+            .with_context(|| format!("invalid namespaced ID '{}'", string))
+    }
+}
+
+impl Writeable for NamespacedId {
+    fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) {
+        self.to_string().write(buffer, version);
+    }
+}
+
 impl Readable for bool {
     fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
     where
@@ -581,23 +673,21 @@ impl Readable for Slot {
 
         if present {
             let item_id = VarInt::read(buffer, version)?.0;
-            let count = u8::read(buffer, version)? as u32;
+            let count = u8::read(buffer, version)?;
 
-            // Read NBT, but make sure to reset the buffer position if it's missing.
+            // A single TAG_End byte means the item has no NBT.
             let position = buffer.position();
-            let tags: Option<ItemNbt> = Nbt::read(buffer, version).ok().map(|nbt| nbt.0);
-            if tags.is_none() {
-                buffer.set_position(position + 1); // account for TAG_End, which is 1 byte
-            }
+            let tags = if u8::read(buffer, version)? == 0 {
+                None
+            } else {
+                buffer.set_position(position);
+                Some(Nbt::<ItemNbt>::read(buffer, version)?.0)
+            };
 
             let item = Item::from_id(item_id.try_into()?)
                 .ok_or_else(|| anyhow!("unknown item ID {}", item_id))?;
 
-            Ok(Some(ItemStack {
-                item,
-                count,
-                damage: tags.map(|t| t.damage).flatten().map(|d| d as u32),
-            }))
+            Ok(Some(ItemNbt::item_stack(&tags, item, count)))
         } else {
             Ok(None)
         }
@@ -614,7 +704,6 @@ impl Writeable for Slot {
 
             let tags: ItemNbt = stack.into();
             if tags != ItemNbt::default() {
-                dbg!();
                 Nbt(tags).write(buffer, version);
             } else {
                 0u8.write(buffer, version); // TAG_End
@@ -628,7 +717,7 @@ impl Readable for EntityMetadata {
     where
         Self: Sized,
     {
-        let mut values = BTreeMap::new();
+        let mut metadata = EntityMetadata::new();
 
         loop {
             let index = u8::read(buffer, version)?;
@@ -638,10 +727,10 @@ impl Readable for EntityMetadata {
             }
 
             let entry = read_meta_entry(buffer, version)?;
-            values.insert(index, entry);
+            metadata.values.insert(index, entry);
         }
 
-        Ok(EntityMetadata { values })
+        Ok(metadata)
     }
 }
 
@@ -684,19 +773,15 @@ fn read_meta_entry(
         } else {
             None
         }),
-        13 => MetaEntry::OptBlockId(if bool::read(buffer, version)? {
-            Some(VarInt::read(buffer, version)?.0)
-        } else {
-            None
-        }),
+        13 => MetaEntry::OptBlockId(read_opt_block_id(buffer, version)?),
         14 => MetaEntry::Nbt(Nbt::read(buffer, version)?.0),
-        15 => MetaEntry::Particle,
-        16 => MetaEntry::VillagerData,
-        17 => MetaEntry::OptVarInt(if bool::read(buffer, version)? {
-            Some(VarInt::read(buffer, version)?.0)
-        } else {
-            None
-        }),
+        15 => MetaEntry::Particle(read_particle(buffer, version)?),
+        16 => MetaEntry::VillagerData(
+            VarInt::read(buffer, version)?.0,
+            VarInt::read(buffer, version)?.0,
+            VarInt::read(buffer, version)?.0,
+        ),
+        17 => MetaEntry::OptVarInt(read_opt_var_int(buffer, version)?),
         18 => MetaEntry::Pose(VarInt::read(buffer, version)?.0),
         x => bail!("invalid entity metadata entry ID {}", x),
     })
@@ -757,28 +842,104 @@ fn write_meta_entry(entry: &MetaEntry, buffer: &mut Vec<u8>, version: ProtocolVe
                 false.write(buffer, version);
             }
         }
-        MetaEntry::OptBlockId(ox) => {
-            if let Some(x) = ox {
-                VarInt(*x).write(buffer, version);
-            } else {
-                VarInt(0).write(buffer, version); // No value implies air
-            }
-        }
+        MetaEntry::OptBlockId(ox) => VarInt(ox.unwrap_or(0)).write(buffer, version),
         MetaEntry::Nbt(val) => Nbt(val).write(buffer, version),
-        MetaEntry::Particle => unimplemented!("entity metadata with particles"),
-        MetaEntry::VillagerData => unimplemented!("entity metadata with villager data"),
-        MetaEntry::OptVarInt(ox) => {
-            if let Some(x) = ox {
-                true.write(buffer, version);
-                x.write(buffer, version);
-            } else {
-                false.write(buffer, version);
-            }
+        MetaEntry::Particle(kind) => write_particle(kind, buffer, version),
+        MetaEntry::VillagerData(kind, profession, level) => {
+            VarInt(*kind).write(buffer, version);
+            VarInt(*profession).write(buffer, version);
+            VarInt(*level).write(buffer, version);
         }
+        MetaEntry::OptVarInt(ox) => write_opt_var_int(*ox, buffer, version),
         MetaEntry::Pose(x) => VarInt(x.to_i32().unwrap()).write(buffer, version),
     }
 }
 
+/// Reads an optional `VarInt` encoded as `0` for `None`
+/// and `value + 1` otherwise.
+fn read_opt_var_int(
+    buffer: &mut Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<Option<i32>> {
+    let value = VarInt::read(buffer, version)?.0;
+    Ok(if value == 0 { None } else { Some(value - 1) })
+}
+
+fn write_opt_var_int(value: Option<i32>, buffer: &mut Vec<u8>, version: ProtocolVersion) {
+    VarInt(value.map(|x| x + 1).unwrap_or(0)).write(buffer, version);
+}
+
+/// Reads an optional block state ID. Unlike other optional
+/// `VarInt`s, it's written as is, with `0` (air) for `None`.
+fn read_opt_block_id(
+    buffer: &mut Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<Option<i32>> {
+    let value = VarInt::read(buffer, version)?.0;
+    Ok(if value == 0 { None } else { Some(value) })
+}
+
+/// Reads a particle ID followed by the particle's data.
+fn read_particle(
+    buffer: &mut Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<ParticleKind> {
+    let id = VarInt::read(buffer, version)?.0;
+    let mut kind = ParticleKind::from_id(id.try_into()?)
+        .ok_or_else(|| anyhow!("invalid particle ID {}", id))?;
+    match &mut kind {
+        ParticleKind::Dust {
+            red,
+            green,
+            blue,
+            scale,
+        } => {
+            *red = f32::read(buffer, version)?;
+            *green = f32::read(buffer, version)?;
+            *blue = f32::read(buffer, version)?;
+            *scale = f32::read(buffer, version)?;
+        }
+        ParticleKind::Block(block_state) | ParticleKind::FallingDust(block_state) => {
+            let state = VarInt::read(buffer, version)?.0;
+            *block_state = BlockState::from_id(state.try_into()?)
+                .ok_or_else(|| anyhow!("invalid block state ID {}", state))?;
+        }
+        ParticleKind::Item(item) => {
+            *item = Slot::read(buffer, version)?
+                .and_then(|stack| libcraft_items::Item::from_id(stack.item.id()));
+        }
+        _ => {}
+    }
+    Ok(kind)
+}
+
+fn write_particle(kind: &ParticleKind, buffer: &mut Vec<u8>, version: ProtocolVersion) {
+    VarInt(kind.id() as i32).write(buffer, version);
+    match kind {
+        ParticleKind::Dust {
+            red,
+            green,
+            blue,
+            scale,
+        } => {
+            red.write(buffer, version);
+            green.write(buffer, version);
+            blue.write(buffer, version);
+            scale.write(buffer, version);
+        }
+        ParticleKind::Block(block_state) | ParticleKind::FallingDust(block_state) => {
+            VarInt(block_state.id() as i32).write(buffer, version);
+        }
+        ParticleKind::Item(item) => {
+            let stack = item
+                .and_then(|item| Item::from_id(item.id()))
+                .map(|item| ItemStack::new(item, 1));
+            stack.write(buffer, version);
+        }
+        _ => {}
+    }
+}
+
 impl Readable for Uuid {
     fn read(buffer: &mut Cursor<&[u8]>, _version: ProtocolVersion) -> anyhow::Result<Self>
     where
@@ -846,11 +1007,42 @@ impl Readable for Angle {
 
 impl Writeable for Angle {
     fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) {
-        let val = (self.0 / 360.0 * 256.0).round() as u8;
+        // Casting through i32 wraps negative and
+        // out-of-range angles into 0..256 steps.
+        let val = (self.0 / 360.0 * 256.0).round() as i32 as u8;
         val.write(buffer, version);
     }
 }
 
+/// A change in one coordinate of an entity's position,
+/// in 1/4096ths of a block, as sent in the relative
+/// entity movement packets.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PositionDelta(pub i16);
+
+impl PositionDelta {
+    /// Computes the delta for a coordinate moving from `old` to `new`.
+    ///
+    /// Returns `None` if the change is too large to encode
+    /// (8 blocks or more), in which case the entity needs to be
+    /// teleported instead.
+    pub fn between(old: f64, new: f64) -> Option<Self> {
+        let delta = (new * 4096.0).floor() as i64 - (old * 4096.0).floor() as i64;
+        i16::try_from(delta).ok().map(PositionDelta)
+    }
+
+    /// Returns the delta in blocks.
+    pub fn blocks(self) -> f64 {
+        self.0 as f64 / 4096.0
+    }
+}
+
+impl From<PositionDelta> for i16 {
+    fn from(delta: PositionDelta) -> Self {
+        delta.0
+    }
+}
+
 impl Readable for BlockId {
     fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
     where
@@ -896,3 +1088,250 @@ impl Writeable for Gamemode {
         (id as u8).write(buffer, version);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_var_long(value: i64) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        VarLong(value).write(&mut buffer, ProtocolVersion::LATEST);
+        buffer
+    }
+
+    #[test]
+    fn read_string_slices() {
+        let mut buffer = Vec::new();
+        "feather"
+            .to_owned()
+            .write(&mut buffer, ProtocolVersion::LATEST);
+        buffer.push(42);
+
+        let mut cursor = Cursor::new(&buffer[..]);
+        let string = try_get_string(&mut cursor, ProtocolVersion::LATEST).unwrap();
+        assert_eq!(string, "feather");
+        assert_eq!(string.as_ptr(), buffer[1..].as_ptr());
+        assert_eq!(u8::read(&mut cursor, ProtocolVersion::LATEST).unwrap(), 42);
+        assert!(try_get_bytes(&mut cursor, 1).is_err());
+    }
+
+    #[test]
+    fn string_limits_count_characters() {
+        let read = |string: &str, max_length| {
+            let mut buffer = Vec::new();
+            string
+                .to_owned()
+                .write(&mut buffer, ProtocolVersion::LATEST);
+            let mut cursor = Cursor::new(&buffer[..]);
+            try_get_limited_string(&mut cursor, ProtocolVersion::LATEST, max_length)
+                .map(str::to_owned)
+        };
+
+        // Six bytes, but three characters.
+        assert_eq!(read("äöü", 3).unwrap(), "äöü");
+        // Outside the BMP, so two UTF-16 code units.
+        assert_eq!(read("🦀", 2).unwrap(), "🦀");
+        let error = read("feathers", 7).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::StringTooLong { length: 8, max: 7 })
+        ));
+        assert!(read(&"a".repeat(29), 7).is_err());
+    }
+
+    #[test]
+    fn invalid_utf8_is_rejected() {
+        let buffer = [2, 0xC3, 0x28];
+        let mut cursor = Cursor::new(&buffer[..]);
+        let error = try_get_string(&mut cursor, ProtocolVersion::LATEST).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn var_long_round_trip() {
+        for &value in &[
+            0,
+            1,
+            127,
+            128,
+            255,
+            2147483647,
+            -1,
+            -2147483648,
+            i64::MAX,
+            i64::MIN,
+        ] {
+            let bytes = write_var_long(value);
+            assert_eq!(bytes.len(), VarLong(value).needed_bytes());
+
+            let mut cursor = Cursor::new(&bytes[..]);
+            let read = VarLong::read(&mut cursor, ProtocolVersion::LATEST).unwrap();
+            assert_eq!(read.0, value);
+            assert_eq!(cursor.position() as usize, bytes.len());
+        }
+    }
+
+    #[test]
+    fn var_long_encoding() {
+        assert_eq!(write_var_long(0), [0x00]);
+        assert_eq!(write_var_long(128), [0x80, 0x01]);
+        assert_eq!(
+            write_var_long(-1),
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let text = Text::translate_with("chat.type.text", vec!["Notch", "hello"]);
+        let mut buffer = Vec::new();
+        text.write(&mut buffer, ProtocolVersion::LATEST);
+
+        let read = Text::read(&mut Cursor::new(&buffer[..]), ProtocolVersion::LATEST).unwrap();
+        assert_eq!(read, text);
+    }
+
+    #[test]
+    fn namespaced_id_round_trip() {
+        let id: NamespacedId = "feather:stone".parse().unwrap();
+        let mut buffer = Vec::new();
+        id.write(&mut buffer, ProtocolVersion::LATEST);
+        assert_eq!(&buffer[1..], b"feather:stone");
+
+        let read =
+            NamespacedId::read(&mut Cursor::new(&buffer[..]), ProtocolVersion::LATEST).unwrap();
+        assert_eq!(read, id);
+    }
+
+    #[test]
+    fn invalid_namespaced_id() {
+        let mut buffer = Vec::new();
+        "no spaces:allowed"
+            .to_owned()
+            .write(&mut buffer, ProtocolVersion::LATEST);
+        assert!(
+            NamespacedId::read(&mut Cursor::new(&buffer[..]), ProtocolVersion::LATEST).is_err()
+        );
+    }
+
+    #[test]
+    fn var_long_too_long() {
+        let bytes = [0xff; 11];
+        assert!(VarLong::read(&mut Cursor::new(&bytes[..]), ProtocolVersion::LATEST).is_err());
+    }
+
+    fn slot_round_trip(slot: Slot) {
+        let mut buffer = Vec::new();
+        slot.write(&mut buffer, ProtocolVersion::LATEST);
+
+        let mut cursor = Cursor::new(&buffer[..]);
+        let read = Slot::read(&mut cursor, ProtocolVersion::LATEST).unwrap();
+        assert_eq!(read, slot);
+        assert_eq!(cursor.position() as usize, buffer.len());
+    }
+
+    #[test]
+    fn slot_without_nbt() {
+        slot_round_trip(None);
+        slot_round_trip(Some(ItemStack::new(Item::Stone, 64)));
+    }
+
+    #[test]
+    fn slot_with_nbt() {
+        let mut stack = ItemStack::new(Item::DiamondSword, 1);
+        stack.damage = Some(12);
+        stack.meta = Some(generated::ItemStackMeta {
+            display_name: Some(r#"{"text":"Excalibur"}"#.to_owned()),
+            lore: vec![r#"{"text":"Pulled from a stone"}"#.to_owned()],
+            enchantments: vec![generated::Enchantment {
+                id: "minecraft:sharpness".to_owned(),
+                level: 5,
+            }],
+            ..Default::default()
+        });
+        slot_round_trip(Some(stack));
+    }
+
+    #[test]
+    fn metadata_round_trip() {
+        let metadata = EntityMetadata::new().with_many(&[
+            (0, MetaEntry::Byte(0x20)),
+            (
+                2,
+                MetaEntry::OptChat(Some(r#"{"text":"Steve"}"#.to_owned())),
+            ),
+            (
+                7,
+                MetaEntry::OptPosition(Some(BlockPosition::new(1, -2, 3))),
+            ),
+            (8, MetaEntry::Direction(Direction::East)),
+            (9, MetaEntry::OptBlockId(None)),
+            (10, MetaEntry::OptVarInt(Some(0))),
+            (14, MetaEntry::OptBlockId(Some(9))),
+            (
+                11,
+                MetaEntry::Particle(ParticleKind::Dust {
+                    red: 1.0,
+                    green: 0.5,
+                    blue: 0.0,
+                    scale: 2.0,
+                }),
+            ),
+            (12, MetaEntry::VillagerData(2, 5, 1)),
+            (
+                13,
+                MetaEntry::Particle(ParticleKind::Item(Some(libcraft_items::Item::Apple))),
+            ),
+        ]);
+        let mut buffer = Vec::new();
+        metadata.write(&mut buffer, ProtocolVersion::LATEST);
+
+        let mut cursor = Cursor::new(&buffer[..]);
+        let read = EntityMetadata::read(&mut cursor, ProtocolVersion::LATEST).unwrap();
+        assert_eq!(read.values, metadata.values);
+        assert_eq!(cursor.position() as usize, buffer.len());
+    }
+
+    #[test]
+    fn opt_var_int_encoding() {
+        let mut buffer = Vec::new();
+        write_opt_var_int(None, &mut buffer, ProtocolVersion::LATEST);
+        write_opt_var_int(Some(0), &mut buffer, ProtocolVersion::LATEST);
+        assert_eq!(buffer, [0, 1]);
+    }
+
+    #[test]
+    fn opt_block_id_encoding() {
+        let metadata = EntityMetadata::new().with_many(&[
+            (0, MetaEntry::OptBlockId(None)),
+            (1, MetaEntry::OptBlockId(Some(9))),
+        ]);
+        let mut buffer = Vec::new();
+        metadata.write(&mut buffer, ProtocolVersion::LATEST);
+        // Index, type, then the block state ID as is
+        assert_eq!(buffer, [0, 13, 0, 1, 13, 9, 0xFF]);
+    }
+
+    #[test]
+    fn angle_wraps() {
+        let mut buffer = Vec::new();
+        Angle(-90.0).write(&mut buffer, ProtocolVersion::LATEST);
+        Angle(360.0).write(&mut buffer, ProtocolVersion::LATEST);
+        Angle(450.0).write(&mut buffer, ProtocolVersion::LATEST);
+        assert_eq!(buffer, [192, 0, 64]);
+    }
+
+    #[test]
+    fn position_delta() {
+        assert_eq!(PositionDelta::between(0.0, 1.0), Some(PositionDelta(4096)));
+        assert_eq!(
+            PositionDelta::between(10.5, 10.25),
+            Some(PositionDelta(-1024))
+        );
+        assert_eq!(PositionDelta::between(0.0, 8.0), None);
+        assert_eq!(PositionDelta(-2048).blocks(), -0.5);
+    }
+}
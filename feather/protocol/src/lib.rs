@@ -1,14 +1,40 @@
+//! An implementation of the Minecraft: Java Edition protocol,
+//! versions 1.16.1 through 1.16.5.
+//!
+//! This crate only depends on the data types in libcraft and
+//! `feather-types`, not on `feather-base` or the server, so bots,
+//! proxies and other tools can use it to talk to Minecraft clients
+//! and servers:
+//! * [`packets`] defines the packets, grouped by direction and
+//! protocol state into enums like [`ClientPlayPacket`].
+//! * [`MinecraftCodec`] splits a byte stream into packets and
+//! handles compression and encryption, using [`framing`]
+//! to find where each packet ends.
+//! * [`io`] contains the types packets are made of, like [`VarInt`],
+//! through the [`Readable`] and [`Writeable`] traits.
+//! * [`capture`] records packet streams and replays them.
+//! * [`translation`] converts packets to and from
+//! 1.16.1, which is not supported natively.
+//!
+//! See `examples/status_ping.rs` for a minimal client.
+
 use anyhow::anyhow;
-use base::ItemStack;
+use generated::ItemStack;
 
+pub mod capture;
 pub mod codec;
+pub mod framing;
 pub mod io;
 pub mod packets;
+pub mod translation;
 
 #[doc(inline)]
 pub use codec::MinecraftCodec;
 pub use io::Nbt;
-pub use io::{Readable, VarInt, VarLong, Writeable};
+pub use io::{
+    try_get_bytes, try_get_limited_string, try_get_string, PositionDelta, Readable, VarInt,
+    VarLong, Writeable,
+};
 #[doc(inline)]
 pub use packets::{
     client::{ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ClientStatusPacket},
@@ -19,9 +45,47 @@ pub use packets::{
 pub type Slot = Option<ItemStack>;
 
 /// A protocol version.
+///
+/// Each connection's codec is set to the version the
+/// client announced in its handshake. `Readable` and `Writeable`
+/// implementations receive the version and can branch on it
+/// where the wire format differs between versions.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum ProtocolVersion {
+    /// Supported through [`translation`].
+    V1_16_1,
     V1_16_2,
+    V1_16_3,
+    /// Also used by 1.16.5.
+    V1_16_4,
+}
+
+impl ProtocolVersion {
+    /// The newest supported protocol version.
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V1_16_4;
+
+    /// Gets the version with the given protocol number,
+    /// as sent in the Handshake packet. Returns `None`
+    /// if the version is not supported.
+    pub fn from_protocol_number(number: i32) -> Option<Self> {
+        match number {
+            736 => Some(ProtocolVersion::V1_16_1),
+            751 => Some(ProtocolVersion::V1_16_2),
+            753 => Some(ProtocolVersion::V1_16_3),
+            754 => Some(ProtocolVersion::V1_16_4),
+            _ => None,
+        }
+    }
+
+    /// Gets the protocol number of this version.
+    pub const fn protocol_number(self) -> i32 {
+        match self {
+            ProtocolVersion::V1_16_1 => 736,
+            ProtocolVersion::V1_16_2 => 751,
+            ProtocolVersion::V1_16_3 => 753,
+            ProtocolVersion::V1_16_4 => 754,
+        }
+    }
 }
 
 /// A protocol state.
@@ -33,6 +97,19 @@ pub enum ProtocolState {
     Play,
 }
 
+/// Implemented for packet enums, such as [`ClientLoginPacket`],
+/// whose packets are only sent in one protocol state.
+pub trait StatePacket {
+    const STATE: ProtocolState;
+}
+
+impl<'a, T> StatePacket for &'a T
+where
+    T: StatePacket,
+{
+    const STATE: ProtocolState = T::STATE;
+}
+
 /// Reads an arbitrary packet sent by a client based on a dynamically-updated
 /// protocol state. As opposed to `MinecraftCodec`, this struct does not type-encode
 /// the current protocol state using generics.
@@ -62,6 +139,10 @@ impl ClientPacketCodec {
         self.state = state
     }
 
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.codec.set_version(version)
+    }
+
     /// Decodes a `ClientPacket` using the provided data.
     pub fn decode(&mut self, data: &[u8]) -> anyhow::Result<Option<ClientPacket>> {
         self.codec.accept(data);
@@ -120,6 +201,10 @@ impl ServerPacketCodec {
         self.state = state
     }
 
+    pub fn set_version(&mut self, version: ProtocolVersion) {
+        self.codec.set_version(version)
+    }
+
     /// Decodes a `ServerPacket` using the provided data.
     pub fn decode(&mut self, data: &[u8]) -> anyhow::Result<Option<ServerPacket>> {
         self.codec.accept(data);
@@ -208,3 +293,30 @@ impl From<ServerPlayPacket> for ServerPacket {
         ServerPacket::Play(packet)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_numbers_round_trip() {
+        for &version in &[
+            ProtocolVersion::V1_16_1,
+            ProtocolVersion::V1_16_2,
+            ProtocolVersion::V1_16_3,
+            ProtocolVersion::V1_16_4,
+        ] {
+            assert_eq!(
+                ProtocolVersion::from_protocol_number(version.protocol_number()),
+                Some(version)
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported_protocol_number() {
+        assert_eq!(ProtocolVersion::from_protocol_number(404), None);
+        assert_eq!(ProtocolVersion::from_protocol_number(735), None);
+        assert_eq!(ProtocolVersion::from_protocol_number(755), None);
+    }
+}
@@ -40,57 +40,6 @@ macro_rules! user_type_convert_to_writeable {
     };
 }
 
-macro_rules! packets {
-    (
-        $(
-            $packet:ident {
-                $(
-                    $field:ident $typ:ident $(<$generics:ident>)?
-                );* $(;)?
-            } $(,)?
-        )*
-    ) => {
-        $(
-            #[derive(Debug, Clone)]
-            pub struct $packet {
-                $(
-                    pub $field: user_type!($typ $(<$generics>)?),
-                )*
-            }
-
-            #[allow(unused_imports, unused_variables)]
-            impl crate::Readable for $packet {
-                fn read(buffer: &mut ::std::io::Cursor<&[u8]>, version: crate::ProtocolVersion) -> anyhow::Result<Self>
-                where
-                    Self: Sized
-                {
-                    use anyhow::Context as _;
-                    $(
-                        let $field = <$typ $(<$generics>)?>::read(buffer, version)
-                            .context(concat!("failed to read field `", stringify!($field), "` of packet `", stringify!($packet), "`"))?
-                            .into();
-                    )*
-
-                    Ok(Self {
-                        $(
-                            $field,
-                        )*
-                    })
-                }
-            }
-
-            #[allow(unused_variables)]
-            impl crate::Writeable for $packet {
-                fn write(&self, buffer: &mut Vec<u8>, version: crate::ProtocolVersion) {
-                    $(
-                        user_type_convert_to_writeable!($typ $(<$generics>)?, &self.$field).write(buffer, version);
-                    )*
-                }
-            }
-        )*
-    };
-}
-
 macro_rules! discriminant_to_literal {
     (String, $discriminant:expr) => {
         &*$discriminant
@@ -195,7 +144,7 @@ macro_rules! def_enum {
 
 macro_rules! packet_enum {
     (
-        $ident:ident {
+        $ident:ident in $state:ident {
             $($id:literal = $packet:ident),* $(,)?
         }
     ) => {
@@ -206,6 +155,10 @@ macro_rules! packet_enum {
             )*
         }
 
+        impl crate::StatePacket for $ident {
+            const STATE: crate::ProtocolState = crate::ProtocolState::$state;
+        }
+
         impl $ident {
             /// Returns the packet ID of this packet.
             pub fn id(&self) -> u32 {
@@ -283,7 +236,10 @@ pub trait VariantOf<Enum> {
 
 use crate::io::{Angle, LengthInferredVecU8, LengthPrefixedVec, Nbt, ShortPrefixedVec, VarInt};
 use crate::Slot;
-use base::{BlockId, BlockPosition};
+use blocks::BlockId;
+use feather_protocol_macros::Packet;
+use libcraft_core::BlockPosition;
+use libcraft_text::Text;
 use nbt::Blob;
 use uuid::Uuid;
 
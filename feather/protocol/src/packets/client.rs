@@ -12,22 +12,22 @@ pub use login::*;
 pub use play::*;
 pub use status::*;
 
-packet_enum!(ClientHandshakePacket {
+packet_enum!(ClientHandshakePacket in Handshake {
     0x00 = Handshake,
 });
 
-packet_enum!(ClientStatusPacket {
+packet_enum!(ClientStatusPacket in Status {
     0x00 = Request,
     0x01 = Ping,
 });
 
-packet_enum!(ClientLoginPacket {
+packet_enum!(ClientLoginPacket in Login {
     0x00 = LoginStart,
     0x01 = EncryptionResponse,
     0x02 = LoginPluginResponse,
 });
 
-packet_enum!(ClientPlayPacket {
+packet_enum!(ClientPlayPacket in Play {
     0x00 = TeleportConfirm,
     0x01 = QueryBlockNbt,
     0x02 = SetDifficulty,
@@ -7,11 +7,14 @@ def_enum! {
     }
 }
 
-packets! {
-    Handshake {
-        protocol_version VarInt;
-        server_address String;
-        server_port u16;
-        next_state HandshakeState;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Handshake {
+    #[packet(varint)]
+    pub protocol_version: i32,
+    /// Proxies using BungeeCord IP forwarding append the player's
+    /// address, UUID and signed profile, so this is much longer
+    /// than the 255 characters vanilla clients send.
+    pub server_address: String,
+    pub server_port: u16,
+    pub next_state: HandshakeState,
 }
@@ -1,18 +1,65 @@
 use super::*;
 
-packets! {
-    LoginStart {
-        name String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct LoginStart {
+    #[packet(max_length = 16)]
+    pub name: String,
+}
 
-    EncryptionResponse {
-        shared_secret LengthPrefixedVec<u8>;
-        verify_token LengthPrefixedVec<u8>;
+#[derive(Debug, Clone, Packet)]
+pub struct EncryptionResponse {
+    #[packet(length_prefixed)]
+    pub shared_secret: Vec<u8>,
+    #[packet(length_prefixed)]
+    pub verify_token: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Packet)]
+pub struct LoginPluginResponse {
+    #[packet(varint)]
+    pub message_id: i32,
+    pub successful: bool,
+    #[packet(length_inferred)]
+    pub data: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProtocolVersion, Readable, Writeable};
+    use std::io::Cursor;
+
+    #[test]
+    fn login_plugin_response_round_trip() {
+        let packet = LoginPluginResponse {
+            message_id: 300,
+            successful: true,
+            data: vec![1, 2, 3],
+        };
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes, ProtocolVersion::LATEST);
+        assert_eq!(bytes, vec![0xAC, 0x02, 0x01, 1, 2, 3]);
+
+        let read = LoginPluginResponse::read(&mut Cursor::new(&bytes[..]), ProtocolVersion::LATEST)
+            .unwrap();
+        assert_eq!(read.message_id, 300);
+        assert!(read.successful);
+        assert_eq!(read.data, vec![1, 2, 3]);
     }
 
-    LoginPluginResponse {
-        message_id VarInt;
-        successful bool;
-        data LengthInferredVecU8;
+    #[test]
+    fn encryption_response_round_trip() {
+        let packet = EncryptionResponse {
+            shared_secret: vec![7; 2],
+            verify_token: vec![9],
+        };
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes, ProtocolVersion::LATEST);
+        assert_eq!(bytes, vec![2, 7, 7, 1, 9]);
+
+        let read = EncryptionResponse::read(&mut Cursor::new(&bytes[..]), ProtocolVersion::LATEST)
+            .unwrap();
+        assert_eq!(read.shared_secret, vec![7, 7]);
+        assert_eq!(read.verify_token, vec![9]);
     }
 }
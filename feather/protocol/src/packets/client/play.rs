@@ -1,28 +1,36 @@
 use super::*;
 use crate::packets::server::Hand;
 
-packets! {
-    TeleportConfirm {
-        teleport_id VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct TeleportConfirm {
+    #[packet(varint)]
+    pub teleport_id: i32,
+}
 
-    QueryBlockNbt {
-        transaction_id VarInt;
-        position BlockPosition;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct QueryBlockNbt {
+    #[packet(varint)]
+    pub transaction_id: i32,
+    pub position: BlockPosition,
+}
 
-    QueryEntityNbt {
-        transaction_id VarInt;
-        entity_id VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct QueryEntityNbt {
+    #[packet(varint)]
+    pub transaction_id: i32,
+    #[packet(varint)]
+    pub entity_id: i32,
+}
 
-    SetDifficulty {
-        new_difficulty u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetDifficulty {
+    pub new_difficulty: u8,
+}
 
-    ChatMessage {
-        message String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ChatMessage {
+    #[packet(max_length = 256)]
+    pub message: String,
 }
 
 def_enum! {
@@ -32,15 +40,16 @@ def_enum! {
     }
 }
 
-packets! {
-    ClientSettings {
-        locale String;
-        view_distance u8;
-        chat_mode ChatMode;
-        chat_colors bool;
-        displayed_skin_parts u8;
-        main_hand VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ClientSettings {
+    #[packet(max_length = 16)]
+    pub locale: String,
+    pub view_distance: u8,
+    pub chat_mode: ChatMode,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: u8,
+    #[packet(varint)]
+    pub main_hand: i32,
 }
 
 def_enum! {
@@ -51,52 +60,64 @@ def_enum! {
     }
 }
 
-packets! {
-    TabComplete {
-        transaction_id VarInt;
-        text String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct TabComplete {
+    #[packet(varint)]
+    pub transaction_id: i32,
+    #[packet(max_length = 32500)]
+    pub text: String,
+}
 
-    WindowConfirmation {
-        window_id u8;
-        action_number u16;
-        accepted bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct WindowConfirmation {
+    pub window_id: u8,
+    pub action_number: u16,
+    pub accepted: bool,
+}
 
-    ClickWindowButton {
-        window_id u8;
-        button_id u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ClickWindowButton {
+    pub window_id: u8,
+    pub button_id: u8,
+}
 
-    ClickWindow {
-        window_id u8;
-        slot i16;
-        button i8;
-        action_number u16;
-        mode VarInt;
-        clicked_item Slot;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ClickWindow {
+    pub window_id: u8,
+    pub slot: i16,
+    pub button: i8,
+    pub action_number: u16,
+    #[packet(varint)]
+    pub mode: i32,
+    pub clicked_item: Slot,
+}
 
-    CloseWindow {
-        window_id u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct CloseWindow {
+    pub window_id: u8,
+}
 
-    PluginMessage {
-        channel String;
-        data LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PluginMessage {
+    pub channel: String,
+    #[packet(length_inferred)]
+    pub data: Vec<u8>,
+}
 
-    EditBook {
-        new_book Slot;
-        is_signing bool;
-        hand VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EditBook {
+    pub new_book: Slot,
+    pub is_signing: bool,
+    #[packet(varint)]
+    pub hand: i32,
+}
 
-    InteractEntity {
-        entity_id VarInt;
-        kind InteractEntityKind;
-        sneaking bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct InteractEntity {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub kind: InteractEntityKind,
+    pub sneaking: bool,
 }
 
 def_enum! {
@@ -112,89 +133,105 @@ def_enum! {
     }
 }
 
-packets! {
-    GenerateStructure {
-        position BlockPosition;
-        levels VarInt;
-        keep_jigsaws bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct GenerateStructure {
+    pub position: BlockPosition,
+    #[packet(varint)]
+    pub levels: i32,
+    pub keep_jigsaws: bool,
+}
 
-    KeepAlive {
-        id u64;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct KeepAlive {
+    pub id: u64,
+}
 
-    LockDifficulty {
-        locked bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct LockDifficulty {
+    pub locked: bool,
+}
 
-    PlayerPosition {
-        x f64;
-        feet_y f64;
-        z f64;
-        on_ground bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerPosition {
+    pub x: f64,
+    pub feet_y: f64,
+    pub z: f64,
+    pub on_ground: bool,
+}
 
-    PlayerPositionAndRotation {
-        x f64;
-        feet_y f64;
-        z f64;
-        yaw f32;
-        pitch f32;
-        on_ground bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerPositionAndRotation {
+    pub x: f64,
+    pub feet_y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
 
-    PlayerRotation {
-        yaw f32;
-        pitch f32;
-        on_ground bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerRotation {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub on_ground: bool,
+}
 
-    PlayerMovement {
-        on_ground bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerMovement {
+    pub on_ground: bool,
+}
 
-    VehicleMove {
-        x f64;
-        y f64;
-        z f64;
-        yaw f32;
-        pitch f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct VehicleMove {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
 
-    SteerBoat {
-        left_paddle_turning bool;
-        right_paddle_turning bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SteerBoat {
+    pub left_paddle_turning: bool,
+    pub right_paddle_turning: bool,
+}
 
-    PickItem {
-        slot VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PickItem {
+    #[packet(varint)]
+    pub slot: i32,
+}
 
-    CraftRecipeRequest {
-        window_id u8;
-        recipe String;
-        make_all bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct CraftRecipeRequest {
+    pub window_id: u8,
+    pub recipe: String,
+    pub make_all: bool,
+}
 
-    PlayerAbilities {
-        flags u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerAbilities {
+    pub flags: u8,
+}
 
-    SetDisplayedRecipe {
-        recipe_id String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetDisplayedRecipe {
+    pub recipe_id: String,
+}
 
-    SetRecipeBookState {
-        book_id VarInt;
-        book_open bool;
-        filter_active bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetRecipeBookState {
+    #[packet(varint)]
+    pub book_id: i32,
+    pub book_open: bool,
+    pub filter_active: bool,
+}
 
-    PlayerDigging {
-        status PlayerDiggingStatus;
-        position BlockPosition;
-        face BlockFace;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerDigging {
+    pub status: PlayerDiggingStatus,
+    pub position: BlockPosition,
+    pub face: BlockFace,
 }
 
 def_enum! {
@@ -220,12 +257,13 @@ def_enum! {
     }
 }
 
-packets! {
-    EntityAction {
-        entity_id VarInt;
-        action_id EntityActionKind;
-        jump_boost VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityAction {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub action_id: EntityActionKind,
+    #[packet(varint)]
+    pub jump_boost: i32,
 }
 
 def_enum! {
@@ -242,113 +280,142 @@ def_enum! {
     }
 }
 
-packets! {
-    SteerVehicle {
-        sideways f32;
-        forward f32;
-        flags u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SteerVehicle {
+    pub sideways: f32,
+    pub forward: f32,
+    pub flags: u8,
 }
 
-packets! {
-    NameItem {
-        name String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct NameItem {
+    pub name: String,
+}
 
-    ResourcePackStatus {
-        result VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ResourcePackStatus {
+    #[packet(varint)]
+    pub result: i32,
+}
 
-    AdvancementTab {
-        tab_id Option<String>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct AdvancementTab {
+    pub tab_id: Option<String>,
+}
 
-    SelectTrade {
-        selected_slot VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SelectTrade {
+    #[packet(varint)]
+    pub selected_slot: i32,
+}
 
-    SetBeaconEffect {
-        primary_effect VarInt;
-        secondary_effect VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetBeaconEffect {
+    #[packet(varint)]
+    pub primary_effect: i32,
+    #[packet(varint)]
+    pub secondary_effect: i32,
+}
 
-    HeldItemChange {
-        slot u16;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct HeldItemChange {
+    pub slot: u16,
+}
 
-    UpdateCommandBlock {
-        position BlockPosition;
-        command String;
-        mode VarInt;
-        flags u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateCommandBlock {
+    pub position: BlockPosition,
+    pub command: String,
+    #[packet(varint)]
+    pub mode: i32,
+    pub flags: u8,
+}
 
-    UpdateCommandBlockMinecart {
-        entity_id VarInt;
-        command String;
-        track_output bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateCommandBlockMinecart {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub command: String,
+    pub track_output: bool,
+}
 
-    CreativeInventoryAction {
-        slot i16;
-        clicked_item Slot;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct CreativeInventoryAction {
+    pub slot: i16,
+    pub clicked_item: Slot,
+}
 
-    UpdateJigsawBlock {
-        position BlockPosition;
-        name String;
-        target String;
-        pool String;
-        final_state String;
-        joint_type String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateJigsawBlock {
+    pub position: BlockPosition,
+    pub name: String,
+    pub target: String,
+    pub pool: String,
+    pub final_state: String,
+    pub joint_type: String,
+}
 
-    UpdateStructureBlock {
-        position BlockPosition;
-        action VarInt;
-        mode VarInt;
-        name String;
-        offset_x i8;
-        offset_y i8;
-        offset_z i8;
-        size_x i8;
-        size_y i8;
-        size_z i8;
-        mirror VarInt;
-        rotation VarInt;
-        metadata String;
-        integrity f32;
-        seed u64;
-        flags u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateStructureBlock {
+    pub position: BlockPosition,
+    #[packet(varint)]
+    pub action: i32,
+    #[packet(varint)]
+    pub mode: i32,
+    pub name: String,
+    pub offset_x: i8,
+    pub offset_y: i8,
+    pub offset_z: i8,
+    pub size_x: i8,
+    pub size_y: i8,
+    pub size_z: i8,
+    #[packet(varint)]
+    pub mirror: i32,
+    #[packet(varint)]
+    pub rotation: i32,
+    pub metadata: String,
+    pub integrity: f32,
+    pub seed: u64,
+    pub flags: u8,
+}
 
-    UpdateSign {
-        position BlockPosition;
-        line_1 String;
-        line_2 String;
-        line_3 String;
-        line_4 String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateSign {
+    pub position: BlockPosition,
+    #[packet(max_length = 384)]
+    pub line_1: String,
+    #[packet(max_length = 384)]
+    pub line_2: String,
+    #[packet(max_length = 384)]
+    pub line_3: String,
+    #[packet(max_length = 384)]
+    pub line_4: String,
+}
 
-    Animation {
-        hand Hand;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Animation {
+    pub hand: Hand,
+}
 
-    Spectate {
-        target_player Uuid;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Spectate {
+    pub target_player: Uuid,
+}
 
-    PlayerBlockPlacement {
-        hand VarInt;
-        position BlockPosition;
-        face BlockFace;
-        cursor_position_x f32;
-        cursor_position_y f32;
-        cursor_position_z f32;
-        inside_block bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerBlockPlacement {
+    #[packet(varint)]
+    pub hand: i32,
+    pub position: BlockPosition,
+    pub face: BlockFace,
+    pub cursor_position_x: f32,
+    pub cursor_position_y: f32,
+    pub cursor_position_z: f32,
+    pub inside_block: bool,
+}
 
-    UseItem {
-        hand VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UseItem {
+    #[packet(varint)]
+    pub hand: i32,
 }
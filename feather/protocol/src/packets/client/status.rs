@@ -1,7 +1,7 @@
-packets! {
-    Request {}
+#[derive(Debug, Clone, Packet)]
+pub struct Request {}
 
-    Ping {
-        payload i64;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Ping {
+    pub payload: i64,
 }
@@ -10,12 +10,12 @@ pub use login::*;
 pub use play::*;
 pub use status::*;
 
-packet_enum!(ServerStatusPacket {
+packet_enum!(ServerStatusPacket in Status {
     0x00 = Response,
     0x01 = Pong,
 });
 
-packet_enum!(ServerLoginPacket {
+packet_enum!(ServerLoginPacket in Login {
     0x00 = DisconnectLogin,
     0x01 = EncryptionRequest,
     0x02 = LoginSuccess,
@@ -23,7 +23,7 @@ packet_enum!(ServerLoginPacket {
     0x04 = LoginPluginRequest,
 });
 
-packet_enum!(ServerPlayPacket {
+packet_enum!(ServerPlayPacket in Play {
     0x00 = SpawnEntity,
     0x01 = SpawnExperienceOrb,
     0x02 = SpawnLivingEntity,
@@ -1,28 +1,36 @@
 use super::*;
 
-packets! {
-    DisconnectLogin {
-        reason String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct DisconnectLogin {
+    pub reason: Text,
+}
 
-    EncryptionRequest {
-        server_id String;
-        public_key LengthPrefixedVec<u8>;
-        verify_token LengthPrefixedVec<u8>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EncryptionRequest {
+    pub server_id: String,
+    #[packet(length_prefixed)]
+    pub public_key: Vec<u8>,
+    #[packet(length_prefixed)]
+    pub verify_token: Vec<u8>,
+}
 
-    LoginSuccess {
-        uuid Uuid;
-        username String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct LoginSuccess {
+    pub uuid: Uuid,
+    pub username: String,
+}
 
-    SetCompression {
-        threshold VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetCompression {
+    #[packet(varint)]
+    pub threshold: i32,
+}
 
-    LoginPluginRequest {
-        message_id VarInt;
-        channel String;
-        data LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct LoginPluginRequest {
+    #[packet(varint)]
+    pub message_id: i32,
+    pub channel: String,
+    #[packet(length_inferred)]
+    pub data: Vec<u8>,
 }
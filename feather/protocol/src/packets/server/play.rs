@@ -1,61 +1,86 @@
 use anyhow::bail;
-use base::{BlockState, EntityMetadata, Gamemode, ParticleKind, ProfileProperty};
+use libcraft_blocks::BlockState;
+use libcraft_core::Gamemode;
+use libcraft_particles::ParticleKind;
+use types::{EntityMetadata, ProfileProperty};
 
 use super::*;
 use crate::{io::VarLong, Readable, Writeable};
 
 mod chunk_data;
-pub use chunk_data::{ChunkData, ChunkDataKind};
+pub use chunk_data::{ChunkData, ChunkDataKind, ChunkObfuscation};
+
+mod declare_commands;
+pub use declare_commands::{
+    ArgumentParser, CommandNode, CommandNodeKind, DeclareCommands, ASK_SERVER_SUGGESTIONS,
+};
+
+mod unlock_recipes;
+pub use unlock_recipes::{RecipeBookState, UnlockRecipes, UnlockRecipesAction};
 
 mod update_light;
 pub use update_light::UpdateLight;
 
-packets! {
-    SpawnEntity {
-        entity_id VarInt;
-        uuid String;
-        kind VarInt;
-        x f64;
-        y f64;
-        z f64;
-        pitch Angle;
-        yaw Angle;
-        data i32;
-        velocity_x i16;
-        velocity_y i16;
-        velocity_z i16;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SpawnEntity {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub uuid: Uuid,
+    #[packet(varint)]
+    pub kind: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    #[packet(angle)]
+    pub pitch: f32,
+    #[packet(angle)]
+    pub yaw: f32,
+    pub data: i32,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
 
-    SpawnExperienceOrb {
-        entity_id VarInt;
-        x f64;
-        y f64;
-        z f64;
-        count u16;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SpawnExperienceOrb {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub count: u16,
+}
 
-    SpawnLivingEntity {
-        entity_id VarInt;
-        entity_uuid Uuid;
-        kind VarInt;
-        x f64;
-        y f64;
-        z f64;
-        yaw Angle;
-        pitch Angle;
-        head_pitch Angle;
-        velocity_x i16;
-        velocity_y i16;
-        velocity_z i16;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SpawnLivingEntity {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub entity_uuid: Uuid,
+    #[packet(varint)]
+    pub kind: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    #[packet(angle)]
+    pub yaw: f32,
+    #[packet(angle)]
+    pub pitch: f32,
+    #[packet(angle)]
+    pub head_pitch: f32,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
 
-    SpawnPainting {
-        entity_id VarInt;
-        entity_uuid Uuid;
-        motive VarInt;
-        location BlockPosition;
-        direction PaintingDirection;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SpawnPainting {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub entity_uuid: Uuid,
+    #[packet(varint)]
+    pub motive: i32,
+    pub location: BlockPosition,
+    pub direction: PaintingDirection,
 }
 
 def_enum! {
@@ -67,21 +92,25 @@ def_enum! {
     }
 }
 
-packets! {
-    SpawnPlayer {
-        entity_id VarInt;
-        player_uuid Uuid;
-        x f64;
-        y f64;
-        z f64;
-        yaw Angle;
-        pitch Angle;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SpawnPlayer {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub player_uuid: Uuid,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    #[packet(angle)]
+    pub yaw: f32,
+    #[packet(angle)]
+    pub pitch: f32,
+}
 
-    EntityAnimation {
-        entity_id VarInt;
-        animation Animation;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityAnimation {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub animation: Animation,
 }
 
 def_enum! {
@@ -95,23 +124,28 @@ def_enum! {
     }
 }
 
-packets! {
-    Statistics {
-        statistics LengthPrefixedVec<Statistic>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Statistics {
+    #[packet(length_prefixed)]
+    pub statistics: Vec<Statistic>,
+}
 
-    Statistic {
-        category_id VarInt;
-        statistic_id VarInt;
-        value VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Statistic {
+    #[packet(varint)]
+    pub category_id: i32,
+    #[packet(varint)]
+    pub statistic_id: i32,
+    #[packet(varint)]
+    pub value: i32,
+}
 
-    AcknowledgePlayerDigging {
-        position BlockPosition;
-        block BlockId;
-        status PlayerDiggingStatus;
-        successful bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct AcknowledgePlayerDigging {
+    pub position: BlockPosition,
+    pub block: BlockId,
+    pub status: PlayerDiggingStatus,
+    pub successful: bool,
 }
 
 def_enum! {
@@ -122,35 +156,40 @@ def_enum! {
     }
 }
 
-packets! {
-    BlockBreakAnimation {
-        entity_id VarInt;
-        position BlockPosition;
-        destroy_stage u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct BlockBreakAnimation {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub position: BlockPosition,
+    pub destroy_stage: u8,
+}
 
-    BlockEntityData {
-        position BlockPosition;
-        action u8;
-        data Nbt<Blob>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct BlockEntityData {
+    pub position: BlockPosition,
+    pub action: u8,
+    pub data: Nbt<Blob>,
+}
 
-    BlockAction {
-        position BlockPosition;
-        action_id u8;
-        action_param u8;
-        block_type VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct BlockAction {
+    pub position: BlockPosition,
+    pub action_id: u8,
+    pub action_param: u8,
+    #[packet(varint)]
+    pub block_type: i32,
+}
 
-    BlockChange {
-        position BlockPosition;
-        block BlockId;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct BlockChange {
+    pub position: BlockPosition,
+    pub block: BlockId,
+}
 
-    BossBar {
-        uuid Uuid;
-        action BossBarAction;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct BossBar {
+    pub uuid: Uuid,
+    pub action: BossBarAction,
 }
 
 def_enum! {
@@ -192,17 +231,17 @@ def_enum! {
     }
 }
 
-packets! {
-    ServerDifficulty {
-        difficulty u8;
-        locked bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ServerDifficulty {
+    pub difficulty: u8,
+    pub locked: bool,
+}
 
-    ChatMessage {
-        message String;
-        position ChatPosition;
-        sender Uuid;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ChatMessage {
+    pub message: Text,
+    pub position: ChatPosition,
+    pub sender: Uuid,
 }
 
 def_enum! {
@@ -213,231 +252,265 @@ def_enum! {
     }
 }
 
-packets! {
-    MultiBlockChange {
-        chunk_section_coordinate u64;
-        dont_trust_edges bool;
-        records LengthPrefixedVec<VarLong>;
-    }
-
-    TabComplete {
-        id VarInt;
-        start VarInt;
-        length VarInt;
-        matches LengthPrefixedVec<TabCompleteMatch>;
-    }
-
-    TabCompleteMatch {
-        value String;
-        has_tooltip bool;
-        tooltip Option<String>;
-    }
-
-    DeclareCommands {
-        // (not implemented)
-        __todo__ LengthInferredVecU8;
-        /* nodes LengthPrefixedVec<CommandNode>;
-        root_index VarInt; */
-    }
-
-    CommandNode {
-        flags u8;
-        children LengthPrefixedVec<VarInt>;
-        redirect_node Option<VarInt>;
-        name Option<String>;
-        parser Option<String>;
-        // TODO: handle properties, which vary depending on the value of `parser`.
-        // This can be handled with an enum.
-        __todo__ LengthInferredVecU8;
-    }
-
-    WindowConfirmation {
-        window_id u8;
-        action_number i16;
-        is_accepted bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct MultiBlockChange {
+    pub chunk_section_coordinate: u64,
+    pub dont_trust_edges: bool,
+    #[packet(length_prefixed)]
+    pub records: Vec<VarLong>,
+}
 
-    CloseWindow {
-        window_id u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct TabComplete {
+    #[packet(varint)]
+    pub id: i32,
+    #[packet(varint)]
+    pub start: i32,
+    #[packet(varint)]
+    pub length: i32,
+    #[packet(length_prefixed)]
+    pub matches: Vec<TabCompleteMatch>,
+}
 
-    WindowItems {
-        window_id u8;
-        items ShortPrefixedVec<Slot>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct TabCompleteMatch {
+    pub value: String,
+    pub has_tooltip: bool,
+    pub tooltip: Option<String>,
+}
 
-    WindowProperty {
-        window_id u8;
-        property i16;
-        value i16;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct WindowConfirmation {
+    pub window_id: u8,
+    pub action_number: i16,
+    pub is_accepted: bool,
+}
 
-    SetSlot {
-        window_id u8;
-        slot i16;
-        slot_data Slot;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct CloseWindow {
+    pub window_id: u8,
+}
 
-    SetCooldown {
-        item_id VarInt;
-        cooldown_ticks VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct WindowItems {
+    pub window_id: u8,
+    #[packet(short_prefixed)]
+    pub items: Vec<Slot>,
+}
 
-    PluginMessage {
-        channel String;
-        data LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct WindowProperty {
+    pub window_id: u8,
+    pub property: i16,
+    pub value: i16,
+}
 
-    NamedSoundEffect {
-        name String;
-        category VarInt;
-        position_x i32;
-        position_y i32;
-        position_z i32;
-        volume f32;
-        pitch f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetSlot {
+    pub window_id: u8,
+    pub slot: i16,
+    pub slot_data: Slot,
+}
 
-    Disconnect {
-        reason String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetCooldown {
+    #[packet(varint)]
+    pub item_id: i32,
+    #[packet(varint)]
+    pub cooldown_ticks: i32,
+}
 
-    EntityStatus {
-        entity_id i32;
-        status i8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PluginMessage {
+    pub channel: String,
+    #[packet(length_inferred)]
+    pub data: Vec<u8>,
+}
 
-    Explosion {
-        x f32;
-        y f32;
-        z f32;
-        strength f32;
-        records LengthPrefixedVec<ExplosionRecord>;
-        player_motion_x f32;
-        player_motion_y f32;
-        player_motion_z f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct NamedSoundEffect {
+    pub name: String,
+    #[packet(varint)]
+    pub category: i32,
+    pub position_x: i32,
+    pub position_y: i32,
+    pub position_z: i32,
+    pub volume: f32,
+    pub pitch: f32,
+}
 
-    ExplosionRecord {
-        x_offset i8;
-        y_offset i8;
-        z_offset i8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Disconnect {
+    pub reason: Text,
+}
 
-    UnloadChunk {
-        chunk_x i32;
-        chunk_z i32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityStatus {
+    pub entity_id: i32,
+    pub status: i8,
+}
 
-    ChangeGameState {
-        reason u8;
-        value f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Explosion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub strength: f32,
+    #[packet(length_prefixed)]
+    pub records: Vec<ExplosionRecord>,
+    pub player_motion_x: f32,
+    pub player_motion_y: f32,
+    pub player_motion_z: f32,
+}
 
-    OpenHorseWindow {
-        window_id u8;
-        slot_count VarInt;
-        entity_id i32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ExplosionRecord {
+    pub x_offset: i8,
+    pub y_offset: i8,
+    pub z_offset: i8,
+}
 
-    KeepAlive {
-        id i64;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UnloadChunk {
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+}
 
-    Effect {
-        effect_id i32;
-        position BlockPosition;
-        data i32;
-        disable_relative_volume bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ChangeGameState {
+    pub reason: u8,
+    pub value: f32,
 }
 
-packets! {
-    JoinGame {
-        entity_id i32;
-        is_hardcore bool;
-        gamemode Gamemode;
-        previous_gamemode u8; // can be 255 if "not set," otherwise corresponds to a gamemode ID
-        world_names LengthPrefixedVec<String>;
+#[derive(Debug, Clone, Packet)]
+pub struct OpenHorseWindow {
+    pub window_id: u8,
+    #[packet(varint)]
+    pub slot_count: i32,
+    pub entity_id: i32,
+}
 
-        dimension_codec Nbt<Blob>;
-        dimension Nbt<Blob>;
+#[derive(Debug, Clone, Packet)]
+pub struct KeepAlive {
+    pub id: i64,
+}
 
-        world_name String;
-        hashed_seed u64;
-        max_players VarInt;
-        view_distance VarInt;
-        reduced_debug_info bool;
-        enable_respawn_screen bool;
+#[derive(Debug, Clone, Packet)]
+pub struct Effect {
+    pub effect_id: i32,
+    pub position: BlockPosition,
+    pub data: i32,
+    pub disable_relative_volume: bool,
+}
 
-        is_debug bool;
-        is_flat bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct JoinGame {
+    pub entity_id: i32,
+    pub is_hardcore: bool,
+    pub gamemode: Gamemode,
+    pub previous_gamemode: u8, // can be 255 if "not set," otherwise corresponds to a gamemode ID
+    #[packet(length_prefixed)]
+    pub world_names: Vec<String>,
+
+    pub dimension_codec: Nbt<Blob>,
+    pub dimension: Nbt<Blob>,
+
+    pub world_name: String,
+    pub hashed_seed: u64,
+    #[packet(varint)]
+    pub max_players: i32,
+    #[packet(varint)]
+    pub view_distance: i32,
+    pub reduced_debug_info: bool,
+    pub enable_respawn_screen: bool,
+
+    pub is_debug: bool,
+    pub is_flat: bool,
 }
 
-packets! {
-    MapData {
-        map_id VarInt;
-        scale i8;
-        show_tracking_position bool;
-        is_locked bool;
-        icons LengthPrefixedVec<Icon>;
-        // TODO: a bunch of fields only if a Columns is set to 0
-        __todo__ LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct MapData {
+    #[packet(varint)]
+    pub map_id: i32,
+    pub scale: i8,
+    pub show_tracking_position: bool,
+    pub is_locked: bool,
+    #[packet(length_prefixed)]
+    pub icons: Vec<Icon>,
+    // TODO: a bunch of fields only if a Columns is set to 0
+    #[packet(length_inferred)]
+    pub __todo__: Vec<u8>,
+}
 
-    Icon {
-        kind VarInt;
-        x i8;
-        z i8;
-        direction i8;
-        display_name Option<String>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Icon {
+    #[packet(varint)]
+    pub kind: i32,
+    pub x: i8,
+    pub z: i8,
+    pub direction: i8,
+    pub display_name: Option<String>,
+}
 
-    TradeList {
-        __todo__ LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct TradeList {
+    #[packet(length_inferred)]
+    pub __todo__: Vec<u8>,
+}
 
-    EntityPosition {
-        entity_id VarInt;
-        delta_x i16;
-        delta_y i16;
-        delta_z i16;
-        on_ground bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityPosition {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub on_ground: bool,
+}
 
-    EntityPositionAndRotation {
-        entity_id VarInt;
-        delta_x i16;
-        delta_y i16;
-        delta_z i16;
-        yaw Angle;
-        pitch Angle;
-        on_ground bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityPositionAndRotation {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    #[packet(angle)]
+    pub yaw: f32,
+    #[packet(angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
 
-    EntityRotation {
-        entity_id VarInt;
-        yaw Angle;
-        pitch Angle;
-        on_ground bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityRotation {
+    #[packet(varint)]
+    pub entity_id: i32,
+    #[packet(angle)]
+    pub yaw: f32,
+    #[packet(angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
 
-    EntityMovement {
-        entity_id VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityMovement {
+    #[packet(varint)]
+    pub entity_id: i32,
+}
 
-    VehicleMove {
-        x f64;
-        y f64;
-        z f64;
-        yaw f32;
-        pitch f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct VehicleMove {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+}
 
-    OpenBook {
-        hand Hand;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct OpenBook {
+    pub hand: Hand,
 }
 
 def_enum! {
@@ -447,31 +520,36 @@ def_enum! {
     }
 }
 
-packets! {
-    OpenWindow {
-        window_id VarInt;
-        window_kind VarInt;
-        window_title String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct OpenWindow {
+    #[packet(varint)]
+    pub window_id: i32,
+    #[packet(varint)]
+    pub window_kind: i32,
+    pub window_title: String,
+}
 
-    OpenSignEditor {
-        position BlockPosition;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct OpenSignEditor {
+    pub position: BlockPosition,
+}
 
-    CraftRecipeResponse {
-        window_id i8;
-        recipe String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct CraftRecipeResponse {
+    pub window_id: i8,
+    pub recipe: String,
+}
 
-    PlayerAbilities {
-        flags u8;
-        flying_speed f32;
-        fov_modifier f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerAbilities {
+    pub flags: u8,
+    pub flying_speed: f32,
+    pub fov_modifier: f32,
+}
 
-    CombatEvent {
-        event CombatEventKind;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct CombatEvent {
+    pub event: CombatEventKind,
 }
 
 def_enum! {
@@ -785,67 +863,78 @@ impl Writeable for PlayerInfo {
     }
 }
 
-packets! {
-    FacePlayer {
-        feet_or_eyes VarInt;
-        target_x f64;
-        target_y f64;
-        target_z f64;
-        entity Option<FacePlayerEntity>;
-    }
-
-    FacePlayerEntity {
-        entity_id VarInt;
-        feet_or_eyes VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct FacePlayer {
+    #[packet(varint)]
+    pub feet_or_eyes: i32,
+    pub target_x: f64,
+    pub target_y: f64,
+    pub target_z: f64,
+    pub entity: Option<FacePlayerEntity>,
+}
 
-    PlayerPositionAndLook {
-        x f64;
-        y f64;
-        z f64;
-        yaw f32;
-        pitch f32;
-        flags u8;
-        teleport_id VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct FacePlayerEntity {
+    #[packet(varint)]
+    pub entity_id: i32,
+    #[packet(varint)]
+    pub feet_or_eyes: i32,
+}
 
-    UnlockRecipes {
-        __todo__ LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerPositionAndLook {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub flags: u8,
+    #[packet(varint)]
+    pub teleport_id: i32,
+}
 
-    DestroyEntities {
-        entity_ids LengthPrefixedVec<VarInt>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct DestroyEntities {
+    #[packet(length_prefixed)]
+    pub entity_ids: Vec<VarInt>,
+}
 
-    RemoveEntityEffect {
-        entity_id VarInt;
-        effect_id u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct RemoveEntityEffect {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub effect_id: u8,
+}
 
-    ResourcePack {
-        url String;
-        hash String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ResourcePack {
+    pub url: String,
+    pub hash: String,
+}
 
-    Respawn {
-        dimension Nbt<Blob>;
-        world_name String;
-        hashed_seed u64;
-        gamemode Gamemode;
-        previous_gamemode Gamemode;
-        is_debug bool;
-        is_flat bool;
-        copy_metadata bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Respawn {
+    pub dimension: Nbt<Blob>,
+    pub world_name: String,
+    pub hashed_seed: u64,
+    pub gamemode: Gamemode,
+    pub previous_gamemode: Gamemode,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub copy_metadata: bool,
+}
 
-    EntityHeadLook {
-        entity_id VarInt;
-        head_yaw Angle;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityHeadLook {
+    #[packet(varint)]
+    pub entity_id: i32,
+    #[packet(angle)]
+    pub head_yaw: f32,
+}
 
-    SelectAdvancementTab {
-        identifier Option<String>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SelectAdvancementTab {
+    pub identifier: Option<String>,
 }
 
 def_enum! {
@@ -881,45 +970,57 @@ def_enum! {
     }
 }
 
-packets! {
-    Camera {
-        camera_id VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Camera {
+    #[packet(varint)]
+    pub camera_id: i32,
+}
 
-    HeldItemChange {
-        slot u8; // 0-8
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct HeldItemChange {
+    pub slot: u8, // 0-8
+}
 
-    UpdateViewPosition {
-        chunk_x VarInt;
-        chunk_z VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateViewPosition {
+    #[packet(varint)]
+    pub chunk_x: i32,
+    #[packet(varint)]
+    pub chunk_z: i32,
+}
 
-    UpdateViewDistance {
-        view_distance VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateViewDistance {
+    #[packet(varint)]
+    pub view_distance: i32,
+}
 
-    DisplayScoreboard {
-        position u8;
-        score_name String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct DisplayScoreboard {
+    pub position: u8,
+    pub score_name: String,
+}
 
-    AttachEntity {
-        attached_entity_id i32;
-        holding_entity_id i32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct AttachEntity {
+    pub attached_entity_id: i32,
+    pub holding_entity_id: i32,
+}
 
-    EntityVelocity {
-        entity_id VarInt;
-        velocity_x i16;
-        velocity_y i16;
-        velocity_z i16;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityVelocity {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
 
-    SendEntityMetadata {
-        entity_id VarInt;
-        entries EntityMetadata;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SendEntityMetadata {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub entries: EntityMetadata,
 }
 
 #[derive(Debug, Clone)]
@@ -1005,35 +1106,43 @@ def_enum! {
     }
 }
 
-packets! {
-    SetExperience {
-        experience_bar f32;
-        level VarInt;
-        total_experience VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetExperience {
+    pub experience_bar: f32,
+    #[packet(varint)]
+    pub level: i32,
+    #[packet(varint)]
+    pub total_experience: i32,
+}
 
-    UpdateHealth {
-        health f32;
-        food VarInt;
-        food_saturation f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateHealth {
+    pub health: f32,
+    #[packet(varint)]
+    pub food: i32,
+    pub food_saturation: f32,
+}
 
-    ScoreboardObjective {
-        objective_name String;
-        mode i8;
-        objective_value Option<String>;
-        kind Option<VarInt>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct ScoreboardObjective {
+    pub objective_name: String,
+    pub mode: i8,
+    pub objective_value: Option<String>,
+    pub kind: Option<VarInt>,
+}
 
-    SetPassengers {
-        entity_id VarInt;
-        passengers LengthPrefixedVec<VarInt>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SetPassengers {
+    #[packet(varint)]
+    pub entity_id: i32,
+    #[packet(length_prefixed)]
+    pub passengers: Vec<VarInt>,
+}
 
-    Teams {
-        team_name String;
-        mode TeamsMode;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Teams {
+    pub team_name: String,
+    pub mode: TeamsMode,
 }
 
 def_enum! {
@@ -1067,22 +1176,23 @@ def_enum! {
     }
 }
 
-packets! {
-    UpdateScore {
-        entity_name String;
-        action u8;
-        objective_name String;
-        value Option<VarInt>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct UpdateScore {
+    pub entity_name: String,
+    pub action: u8,
+    pub objective_name: String,
+    pub value: Option<VarInt>,
+}
 
-    SpawnPosition {
-        position BlockPosition;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SpawnPosition {
+    pub position: BlockPosition,
+}
 
-    TimeUpdate {
-        world_age u64;
-        time_of_day u64;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct TimeUpdate {
+    pub world_age: u64,
+    pub time_of_day: u64,
 }
 
 def_enum! {
@@ -1106,77 +1216,102 @@ def_enum! {
     }
 }
 
-packets! {
-    EntitySoundEffect {
-        sound_id VarInt;
-        sound_category VarInt;
-        entity_id VarInt;
-        volume f32;
-        pitch f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntitySoundEffect {
+    #[packet(varint)]
+    pub sound_id: i32,
+    #[packet(varint)]
+    pub sound_category: i32,
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub volume: f32,
+    pub pitch: f32,
+}
 
-    SoundEffect {
-        sound_id VarInt;
-        sound_category VarInt;
-        position_x i32;
-        position_y i32;
-        position_z i32;
-        volume f32;
-        pitch f32;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct SoundEffect {
+    #[packet(varint)]
+    pub sound_id: i32,
+    #[packet(varint)]
+    pub sound_category: i32,
+    pub position_x: i32,
+    pub position_y: i32,
+    pub position_z: i32,
+    pub volume: f32,
+    pub pitch: f32,
+}
 
-    StopSound {
-        flags u8;
-        source Option<VarInt>;
-        sound Option<String>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct StopSound {
+    pub flags: u8,
+    pub source: Option<VarInt>,
+    pub sound: Option<String>,
+}
 
-    PlayerListHeaderAndFooter {
-        header String;
-        footer String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct PlayerListHeaderAndFooter {
+    pub header: String,
+    pub footer: String,
+}
 
-    NbtQueryResponse {
-        transaction_id VarInt;
-        nbt Nbt<Blob>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct NbtQueryResponse {
+    #[packet(varint)]
+    pub transaction_id: i32,
+    pub nbt: Nbt<Blob>,
+}
 
-    CollectItem {
-        collected_entity_id VarInt;
-        collector_entity_id VarInt;
-        item_count VarInt;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct CollectItem {
+    #[packet(varint)]
+    pub collected_entity_id: i32,
+    #[packet(varint)]
+    pub collector_entity_id: i32,
+    #[packet(varint)]
+    pub item_count: i32,
+}
 
-    EntityTeleport {
-        entity_id VarInt;
-        x f64;
-        y f64;
-        z f64;
-        yaw Angle;
-        pitch Angle;
-        on_ground bool;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityTeleport {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    #[packet(angle)]
+    pub yaw: f32,
+    #[packet(angle)]
+    pub pitch: f32,
+    pub on_ground: bool,
+}
 
-    Advancements {
-        __todo__ LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Advancements {
+    #[packet(length_inferred)]
+    pub __todo__: Vec<u8>,
+}
 
-    EntityProperties {
-        __todo__ LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityProperties {
+    #[packet(length_inferred)]
+    pub __todo__: Vec<u8>,
+}
 
-    EntityEffect {
-        entity_id VarInt;
-        effect_id u8;
-        amplifier i8;
-        duration VarInt;
-        flags u8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct EntityEffect {
+    #[packet(varint)]
+    pub entity_id: i32,
+    pub effect_id: u8,
+    pub amplifier: i8,
+    #[packet(varint)]
+    pub duration: i32,
+    pub flags: u8,
+}
 
-    DeclareRecipes {
-        // This packet isn't currently working. Fortunately, we don't really need it.
-        __todo__ LengthInferredVecU8;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct DeclareRecipes {
+    #[packet(length_prefixed)]
+    pub recipes: Vec<Recipe>,
 }
 
 def_enum! {
@@ -1184,7 +1319,6 @@ def_enum! {
         "minecraft:crafting_shapeless" = Shapeless {
             id String;
             group String;
-            ingredient VarInt;
             ingredients LengthPrefixedVec<Ingredient>;
             result Slot;
         },
@@ -1247,24 +1381,37 @@ def_enum! {
             group String;
             ingredient Ingredient;
             result Slot;
+        },
+        "minecraft:smithing" = Smithing {
+            id String;
+            base Ingredient;
+            addition Ingredient;
+            result Slot;
         }
     }
 }
 
-packets! {
-    Ingredient {
-        allowed_items LengthPrefixedVec<Slot>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Ingredient {
+    #[packet(length_prefixed)]
+    pub allowed_items: Vec<Slot>,
+}
 
-    AllTags {
-        block_tags LengthPrefixedVec<Tag>;
-        item_tags LengthPrefixedVec<Tag>;
-        fluid_tags LengthPrefixedVec<Tag>;
-        entity_tags LengthPrefixedVec<Tag>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct AllTags {
+    #[packet(length_prefixed)]
+    pub block_tags: Vec<Tag>,
+    #[packet(length_prefixed)]
+    pub item_tags: Vec<Tag>,
+    #[packet(length_prefixed)]
+    pub fluid_tags: Vec<Tag>,
+    #[packet(length_prefixed)]
+    pub entity_tags: Vec<Tag>,
+}
 
-    Tag {
-        name String;
-        entries LengthPrefixedVec<VarInt>;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Tag {
+    pub name: String,
+    #[packet(length_prefixed)]
+    pub entries: Vec<VarInt>,
 }
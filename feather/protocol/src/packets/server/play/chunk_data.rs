@@ -1,9 +1,13 @@
 use std::fmt::{self, Debug};
 use std::sync::Arc;
 
-use base::{Chunk, ChunkSection};
-use parking_lot::RwLock;
+use anyhow::bail;
 use serde::Serialize;
+use types::{
+    anti_xray::{AntiXray, Neighbors},
+    chunk::{PackedArray, GLOBAL_BITS_PER_BLOCK, MAX_BITS_PER_BLOCK},
+    Chunk, ChunkHandle, ChunkSection,
+};
 
 use crate::{io::VarInt, Nbt, ProtocolVersion, Readable, Writeable};
 
@@ -27,11 +31,24 @@ pub enum ChunkDataKind {
 #[derive(Clone)]
 pub struct ChunkData {
     /// The chunk to send.
-    pub chunk: Arc<RwLock<Chunk>>,
+    pub chunk: ChunkHandle,
 
     /// Whether this packet will load a chunk on
     /// the client or overwrite an existing one.
     pub kind: ChunkDataKind,
+
+    /// Anti-xray obfuscation applied to the
+    /// chunk's blocks while it's encoded.
+    pub obfuscation: Option<ChunkObfuscation>,
+}
+
+/// Anti-xray settings for encoding a chunk.
+#[derive(Clone)]
+pub struct ChunkObfuscation {
+    pub anti_xray: Arc<AntiXray>,
+    /// The chunks next to the chunk, at
+    /// [`NEIGHBOR_OFFSETS`](types::anti_xray::NEIGHBOR_OFFSETS).
+    pub neighbors: [Option<ChunkHandle>; 4],
 }
 
 impl Debug for ChunkData {
@@ -39,16 +56,58 @@ impl Debug for ChunkData {
         let mut debug_struct = f.debug_struct("ChunkData");
         debug_struct.field("position", &self.chunk.read().position());
         debug_struct.field("kind", &self.kind);
+        debug_struct.field("obfuscated", &self.obfuscation.is_some());
         debug_struct.finish()
     }
 }
 
 impl ChunkData {
-    fn should_skip_section(&self, y: usize) -> bool {
-        match &self.kind {
-            ChunkDataKind::LoadChunk => false,
-            ChunkDataKind::OverwriteChunk { sections } => !sections.contains(&y),
+    /// Returns the sections to send along with their index
+    /// in `Chunk::sections`.
+    ///
+    /// When loading a chunk, sections containing only air
+    /// are skipped, since the client treats missing sections as air.
+    fn sections_to_send<'a>(
+        &'a self,
+        chunk: &'a Chunk,
+    ) -> impl Iterator<Item = (usize, &'a ChunkSection)> + 'a {
+        chunk
+            .sections()
+            .iter()
+            .enumerate()
+            .skip(1)
+            .take(16)
+            .filter_map(|(y, section)| Some((y, section.as_ref()?)))
+            .filter(move |(y, section)| match &self.kind {
+                ChunkDataKind::LoadChunk => !section.is_empty(),
+                ChunkDataKind::OverwriteChunk { sections } => sections.contains(y),
+            })
+    }
+}
+
+impl ChunkObfuscation {
+    /// Obfuscates the sections at the indices in `sections`,
+    /// returning those in which blocks were obfuscated.
+    fn obfuscate(
+        &self,
+        chunk: &Chunk,
+        sections: impl Iterator<Item = usize>,
+    ) -> Vec<(usize, ChunkSection)> {
+        let guards: Vec<_> = self
+            .neighbors
+            .iter()
+            .map(|neighbor| neighbor.as_ref().map(|neighbor| neighbor.read()))
+            .collect();
+        let mut neighbors = Neighbors::default();
+        for (neighbor, guard) in neighbors.0.iter_mut().zip(&guards) {
+            *neighbor = guard.as_deref();
         }
+        sections
+            .filter_map(|y| {
+                let section = self.anti_xray.obfuscate_section(chunk, neighbors, y)?;
+                Some((y, section))
+            })
+            .collect()
     }
 }
 
@@ -64,14 +123,8 @@ impl Writeable for ChunkData {
 
         // Compute primary bit mask
         let mut bitmask = 0;
-        for (y, section) in chunk.sections().iter().enumerate().skip(1).take(16) {
-            if section.is_some() {
-                if self.should_skip_section(y) {
-                    continue;
-                }
-
-                bitmask |= 1 << (y - 1) as i32;
-            }
+        for (y, _) in self.sections_to_send(&chunk) {
+            bitmask |= 1 << (y - 1) as i32;
         }
         VarInt(bitmask).write(buffer, version);
 
@@ -87,14 +140,19 @@ impl Writeable for ChunkData {
         }
 
         // Sections
-        let mut data = Vec::new();
-        for (y, section) in chunk.sections().iter().enumerate().skip(1).take(16) {
-            if let Some(section) = section {
-                if self.should_skip_section(y) {
-                    continue;
-                }
-                encode_section(section, &mut data, version);
+        let obfuscated = match &self.obfuscation {
+            Some(obfuscation) => {
+                obfuscation.obfuscate(&chunk, self.sections_to_send(&chunk).map(|(y, _)| y))
             }
+            None => Vec::new(),
+        };
+        let mut data = Vec::new();
+        for (y, section) in self.sections_to_send(&chunk) {
+            let section = obfuscated
+                .iter()
+                .find(|(obfuscated_y, _)| *obfuscated_y == y)
+                .map_or(section, |(_, obfuscated)| obfuscated);
+            encode_section(section, &mut data, version);
         }
         VarInt(data.len() as i32).write(buffer, version);
         buffer.extend_from_slice(&data);
@@ -112,16 +170,40 @@ fn build_heightmaps(chunk: &Chunk) -> Heightmaps {
 
 fn encode_section(section: &ChunkSection, buffer: &mut Vec<u8>, version: ProtocolVersion) {
     (section.non_air_blocks() as u16).write(buffer, version);
-    (section.blocks().data().bits_per_value() as u8).write(buffer, version);
 
-    if let Some(palette) = section.blocks().palette() {
-        VarInt(palette.len() as i32).write(buffer, version);
-        for &block in palette.as_slice() {
-            VarInt(block.vanilla_id() as i32).write(buffer, version);
+    let blocks = section.blocks();
+    match blocks.palette() {
+        Some(palette) if blocks.data().bits_per_value() <= MAX_BITS_PER_BLOCK as usize => {
+            (blocks.data().bits_per_value() as u8).write(buffer, version);
+            VarInt(palette.len() as i32).write(buffer, version);
+            for &block in palette.as_slice() {
+                VarInt(block.vanilla_id() as i32).write(buffer, version);
+            }
+            encode_section_data(blocks.data(), buffer, version);
+        }
+        Some(palette) => {
+            // The client assumes the global palette above
+            // `MAX_BITS_PER_BLOCK`, e.g. for sections
+            // loaded from a world with larger palettes.
+            let data = PackedArray::from_iter(
+                blocks
+                    .data()
+                    .iter()
+                    .map(|index| u64::from(palette.get(index as usize).vanilla_id())),
+                GLOBAL_BITS_PER_BLOCK as usize,
+            );
+            GLOBAL_BITS_PER_BLOCK.write(buffer, version);
+            encode_section_data(&data, buffer, version);
+        }
+        None => {
+            GLOBAL_BITS_PER_BLOCK.write(buffer, version);
+            encode_section_data(blocks.data(), buffer, version);
         }
     }
+}
 
-    let data = section.blocks().data().as_u64_slice();
+fn encode_section_data(data: &PackedArray, buffer: &mut Vec<u8>, version: ProtocolVersion) {
+    let data = data.as_u64_slice();
     VarInt(data.len() as i32).write(buffer, version);
     for &x in data {
         x.write(buffer, version);
@@ -133,6 +215,90 @@ impl Readable for ChunkData {
     where
         Self: Sized,
     {
-        todo!()
+        bail!("decoding Chunk Data packets is not supported")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use blocks::BlockId;
+
+    use super::*;
+
+    /// Decodes a section as the client does, returning its block IDs.
+    fn decode_section(buffer: &[u8]) -> (u16, u8, Vec<u16>) {
+        let version = ProtocolVersion::V1_16_4;
+        let mut cursor = Cursor::new(buffer);
+        let non_air = u16::read(&mut cursor, version).unwrap();
+        let bits = u8::read(&mut cursor, version).unwrap();
+        let palette = if bits <= MAX_BITS_PER_BLOCK {
+            let len = VarInt::read(&mut cursor, version).unwrap().0;
+            (0..len)
+                .map(|_| VarInt::read(&mut cursor, version).unwrap().0 as u16)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let len = VarInt::read(&mut cursor, version).unwrap().0;
+        let data: Vec<u64> = (0..len)
+            .map(|_| u64::read(&mut cursor, version).unwrap())
+            .collect();
+        assert_eq!(cursor.position() as usize, buffer.len());
+
+        let values_per_u64 = 64 / bits as usize;
+        let mask = (1 << bits) - 1;
+        let blocks = (0..4096)
+            .map(|i| {
+                let value =
+                    (data[i / values_per_u64] >> ((i % values_per_u64) * bits as usize)) & mask;
+                if palette.is_empty() {
+                    value as u16
+                } else {
+                    palette[value as usize]
+                }
+            })
+            .collect();
+        (non_air, bits, blocks)
+    }
+
+    fn encode(section: &ChunkSection) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        encode_section(section, &mut buffer, ProtocolVersion::V1_16_4);
+        buffer
+    }
+
+    #[test]
+    fn encode_paletted_section() {
+        let mut section = ChunkSection::default();
+        section.set_block_at(1, 2, 3, BlockId::stone());
+
+        let (non_air, bits, blocks) = decode_section(&encode(&section));
+        assert_eq!(non_air, 1);
+        assert_eq!(bits, 4);
+        // Blocks are ordered by Y, then Z, then X.
+        assert_eq!(blocks[2 * 256 + 3 * 16 + 1], BlockId::stone().vanilla_id());
+        assert_eq!(blocks[0], BlockId::air().vanilla_id());
+    }
+
+    #[test]
+    fn encode_global_palette_section() {
+        // More distinct blocks than a section palette can hold.
+        let mut section = ChunkSection::default();
+        // IDs above 16383 need all of the global palette's bits.
+        let ids: Vec<u16> = (0..300).map(|i| 17_000 - i * 50).collect();
+        for (index, &id) in ids.iter().enumerate() {
+            section.set_block_at(
+                index % 16,
+                index / 256,
+                index / 16 % 16,
+                BlockId::from_vanilla_id(id),
+            );
+        }
+
+        let (_, bits, blocks) = decode_section(&encode(&section));
+        assert_eq!(bits, GLOBAL_BITS_PER_BLOCK);
+        assert_eq!(&blocks[..ids.len()], &ids[..]);
     }
 }
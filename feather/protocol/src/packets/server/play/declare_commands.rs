@@ -0,0 +1,289 @@
+use std::io::{Cursor, Read};
+
+use anyhow::bail;
+
+use crate::{io::VarInt, ProtocolVersion, Readable, Writeable};
+
+const NODE_TYPE_MASK: u8 = 0x03;
+const FLAG_EXECUTABLE: u8 = 0x04;
+const FLAG_REDIRECT: u8 = 0x08;
+const FLAG_SUGGESTIONS: u8 = 0x10;
+
+/// Suggestions type which makes the client ask the
+/// server for completions with a `TabComplete` packet.
+pub const ASK_SERVER_SUGGESTIONS: &str = "minecraft:ask_server";
+
+/// Packet declaring the command tree, which the client uses
+/// to parse and highlight commands as they are typed.
+#[derive(Debug, Clone)]
+pub struct DeclareCommands {
+    pub nodes: Vec<CommandNode>,
+    /// Index of the root node in `nodes`.
+    pub root_index: i32,
+}
+
+/// A node of the command tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandNode {
+    pub kind: CommandNodeKind,
+    /// Whether the command is complete at this node.
+    pub executable: bool,
+    /// Indices of the child nodes.
+    pub children: Vec<i32>,
+    /// Index of the node parsing continues at after this one.
+    pub redirect_node: Option<i32>,
+    /// Where suggestions for an argument come from,
+    /// like [`ASK_SERVER_SUGGESTIONS`].
+    pub suggestions: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandNodeKind {
+    Root,
+    /// A fixed word, like a command name.
+    Literal {
+        name: String,
+    },
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+    },
+}
+
+/// The parser of an argument node and its encoded properties.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgumentParser {
+    pub identifier: String,
+    pub properties: Vec<u8>,
+}
+
+impl ArgumentParser {
+    /// A string argument spanning the rest of the command.
+    pub fn greedy_string() -> Self {
+        Self {
+            identifier: "brigadier:string".to_owned(),
+            // GREEDY_PHRASE
+            properties: vec![2],
+        }
+    }
+}
+
+impl Writeable for CommandNode {
+    fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) {
+        let mut flags = match self.kind {
+            CommandNodeKind::Root => 0,
+            CommandNodeKind::Literal { .. } => 1,
+            CommandNodeKind::Argument { .. } => 2,
+        };
+        if self.executable {
+            flags |= FLAG_EXECUTABLE;
+        }
+        if self.redirect_node.is_some() {
+            flags |= FLAG_REDIRECT;
+        }
+        if self.suggestions.is_some() {
+            flags |= FLAG_SUGGESTIONS;
+        }
+        flags.write(buffer, version);
+
+        VarInt(self.children.len() as i32).write(buffer, version);
+        for &child in &self.children {
+            VarInt(child).write(buffer, version);
+        }
+        if let Some(redirect_node) = self.redirect_node {
+            VarInt(redirect_node).write(buffer, version);
+        }
+
+        match &self.kind {
+            CommandNodeKind::Root => {}
+            CommandNodeKind::Literal { name } => name.write(buffer, version),
+            CommandNodeKind::Argument { name, parser } => {
+                name.write(buffer, version);
+                parser.identifier.write(buffer, version);
+                buffer.extend_from_slice(&parser.properties);
+            }
+        }
+        if let Some(suggestions) = &self.suggestions {
+            suggestions.write(buffer, version);
+        }
+    }
+}
+
+impl Readable for CommandNode {
+    fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let flags = u8::read(buffer, version)?;
+
+        let num_children = VarInt::read(buffer, version)?.0;
+        if num_children < 0 {
+            bail!("negative child count {}", num_children);
+        }
+        let children = (0..num_children)
+            .map(|_| VarInt::read(buffer, version).map(|child| child.0))
+            .collect::<anyhow::Result<_>>()?;
+        let redirect_node = if flags & FLAG_REDIRECT != 0 {
+            Some(VarInt::read(buffer, version)?.0)
+        } else {
+            None
+        };
+
+        let kind = match flags & NODE_TYPE_MASK {
+            0 => CommandNodeKind::Root,
+            1 => CommandNodeKind::Literal {
+                name: String::read(buffer, version)?,
+            },
+            2 => {
+                let name = String::read(buffer, version)?;
+                let identifier = String::read(buffer, version)?;
+                let properties = read_properties(&identifier, buffer, version)?;
+                CommandNodeKind::Argument {
+                    name,
+                    parser: ArgumentParser {
+                        identifier,
+                        properties,
+                    },
+                }
+            }
+            kind => bail!("invalid command node type {}", kind),
+        };
+        let suggestions = if flags & FLAG_SUGGESTIONS != 0 {
+            Some(String::read(buffer, version)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            kind,
+            executable: flags & FLAG_EXECUTABLE != 0,
+            children,
+            redirect_node,
+            suggestions,
+        })
+    }
+}
+
+/// Reads the properties of the parser called `parser`, which
+/// are only delimited by the format each parser gives them.
+fn read_properties(
+    parser: &str,
+    buffer: &mut Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<Vec<u8>> {
+    let start = buffer.position() as usize;
+    let bounds_width = match parser {
+        "brigadier:double" | "brigadier:long" => Some(8),
+        "brigadier:float" | "brigadier:integer" => Some(4),
+        _ => None,
+    };
+    if let Some(width) = bounds_width {
+        let flags = u8::read(buffer, version)?;
+        // Bit 0 marks a minimum, bit 1 a maximum.
+        let bounds = (flags & 1) + ((flags >> 1) & 1);
+        let mut skipped = [0; 16];
+        buffer.read_exact(&mut skipped[..bounds as usize * width])?;
+    } else {
+        match parser {
+            "brigadier:string" => {
+                VarInt::read(buffer, version)?;
+            }
+            "minecraft:entity" | "minecraft:score_holder" | "minecraft:range" => {
+                u8::read(buffer, version)?;
+            }
+            _ => {}
+        }
+    }
+    let end = buffer.position() as usize;
+    Ok(buffer.get_ref()[start..end].to_vec())
+}
+
+impl Writeable for DeclareCommands {
+    fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) {
+        VarInt(self.nodes.len() as i32).write(buffer, version);
+        for node in &self.nodes {
+            node.write(buffer, version);
+        }
+        VarInt(self.root_index).write(buffer, version);
+    }
+}
+
+impl Readable for DeclareCommands {
+    fn read(buffer: &mut Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let num_nodes = VarInt::read(buffer, version)?.0;
+        if num_nodes < 0 {
+            bail!("negative command node count {}", num_nodes);
+        }
+        let nodes = (0..num_nodes)
+            .map(|_| CommandNode::read(buffer, version))
+            .collect::<anyhow::Result<_>>()?;
+        let root_index = VarInt::read(buffer, version)?.0;
+        Ok(Self { nodes, root_index })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_tree_round_trip() {
+        let version = ProtocolVersion::V1_16_2;
+        let node = |kind, children| CommandNode {
+            kind,
+            executable: false,
+            children,
+            redirect_node: None,
+            suggestions: None,
+        };
+        let packet = DeclareCommands {
+            nodes: vec![
+                node(CommandNodeKind::Root, vec![1]),
+                node(
+                    CommandNodeKind::Literal {
+                        name: "tp".to_owned(),
+                    },
+                    vec![2],
+                ),
+                CommandNode {
+                    executable: true,
+                    suggestions: Some(ASK_SERVER_SUGGESTIONS.to_owned()),
+                    ..node(
+                        CommandNodeKind::Argument {
+                            name: "x".to_owned(),
+                            parser: ArgumentParser {
+                                identifier: "brigadier:double".to_owned(),
+                                properties: [
+                                    &[0x03][..],
+                                    &1.0f64.to_be_bytes(),
+                                    &2.0f64.to_be_bytes(),
+                                ]
+                                .concat(),
+                            },
+                        },
+                        vec![3],
+                    )
+                },
+                node(
+                    CommandNodeKind::Argument {
+                        name: "args".to_owned(),
+                        parser: ArgumentParser::greedy_string(),
+                    },
+                    vec![],
+                ),
+            ],
+            root_index: 0,
+        };
+
+        let mut buffer = Vec::new();
+        packet.write(&mut buffer, version);
+        let mut cursor = Cursor::new(buffer.as_slice());
+        let read = DeclareCommands::read(&mut cursor, version).unwrap();
+        assert_eq!(cursor.position() as usize, buffer.len());
+        assert_eq!(read.nodes, packet.nodes);
+        assert_eq!(read.root_index, 0);
+    }
+}
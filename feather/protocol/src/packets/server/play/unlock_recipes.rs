@@ -0,0 +1,142 @@
+use anyhow::bail;
+
+use crate::{io::VarInt, ProtocolVersion, Readable, Writeable};
+
+/// What an [`UnlockRecipes`] packet does with its recipes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnlockRecipesAction {
+    /// Replaces the recipes known by the client, after joining.
+    Init,
+    Add,
+    Remove,
+}
+
+/// Whether a recipe book is open and only shows craftable recipes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecipeBookState {
+    pub open: bool,
+    pub filter_active: bool,
+}
+
+/// Packet updating the recipes in the client's recipe book.
+#[derive(Debug, Clone)]
+pub struct UnlockRecipes {
+    pub action: UnlockRecipesAction,
+    /// States of the crafting, furnace, blast
+    /// furnace and smoker books, in book ID order.
+    pub books: [RecipeBookState; 4],
+    pub recipes: Vec<String>,
+    /// Recipes highlighted as new. Only sent with
+    /// [`UnlockRecipesAction::Init`]; recipes added
+    /// later are always highlighted.
+    pub highlighted: Vec<String>,
+}
+
+impl Writeable for UnlockRecipes {
+    fn write(&self, buffer: &mut Vec<u8>, version: ProtocolVersion) {
+        let action = match self.action {
+            UnlockRecipesAction::Init => 0,
+            UnlockRecipesAction::Add => 1,
+            UnlockRecipesAction::Remove => 2,
+        };
+        VarInt(action).write(buffer, version);
+
+        for book in &self.books {
+            book.open.write(buffer, version);
+            book.filter_active.write(buffer, version);
+        }
+
+        write_recipes(&self.recipes, buffer, version);
+        if self.action == UnlockRecipesAction::Init {
+            write_recipes(&self.highlighted, buffer, version);
+        }
+    }
+}
+
+fn write_recipes(recipes: &[String], buffer: &mut Vec<u8>, version: ProtocolVersion) {
+    VarInt(recipes.len() as i32).write(buffer, version);
+    for recipe in recipes {
+        recipe.write(buffer, version);
+    }
+}
+
+impl Readable for UnlockRecipes {
+    fn read(buffer: &mut std::io::Cursor<&[u8]>, version: ProtocolVersion) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let action = match VarInt::read(buffer, version)?.0 {
+            0 => UnlockRecipesAction::Init,
+            1 => UnlockRecipesAction::Add,
+            2 => UnlockRecipesAction::Remove,
+            action => bail!("invalid unlock recipes action {}", action),
+        };
+
+        let mut books = [RecipeBookState::default(); 4];
+        for book in &mut books {
+            book.open = bool::read(buffer, version)?;
+            book.filter_active = bool::read(buffer, version)?;
+        }
+
+        let recipes = read_recipes(buffer, version)?;
+        let highlighted = if action == UnlockRecipesAction::Init {
+            read_recipes(buffer, version)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            action,
+            books,
+            recipes,
+            highlighted,
+        })
+    }
+}
+
+fn read_recipes(
+    buffer: &mut std::io::Cursor<&[u8]>,
+    version: ProtocolVersion,
+) -> anyhow::Result<Vec<String>> {
+    let len = VarInt::read(buffer, version)?.0;
+    if len < 0 {
+        bail!("negative recipe count {}", len);
+    }
+    (0..len).map(|_| String::read(buffer, version)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn highlighted_recipes_are_only_sent_on_init() {
+        let version = ProtocolVersion::V1_16_2;
+        for &action in &[UnlockRecipesAction::Init, UnlockRecipesAction::Add] {
+            let mut books = [RecipeBookState::default(); 4];
+            books[2].filter_active = true;
+            let packet = UnlockRecipes {
+                action,
+                books,
+                recipes: vec!["minecraft:torch".to_owned()],
+                highlighted: vec!["minecraft:torch".to_owned()],
+            };
+
+            let mut buffer = Vec::new();
+            packet.write(&mut buffer, version);
+            let mut cursor = Cursor::new(buffer.as_slice());
+            let read = UnlockRecipes::read(&mut cursor, version).unwrap();
+            assert_eq!(cursor.position() as usize, buffer.len());
+
+            assert_eq!(read.action, action);
+            assert_eq!(read.books, books);
+            assert_eq!(read.recipes, packet.recipes);
+            assert_eq!(
+                read.highlighted.is_empty(),
+                action == UnlockRecipesAction::Add
+            );
+        }
+    }
+}
@@ -1,13 +1,14 @@
-use std::{fmt::Debug, sync::Arc};
+use std::fmt::Debug;
 
-use base::{chunk::PackedArray, Chunk};
-use parking_lot::RwLock;
+use anyhow::bail;
+
+use types::{chunk::PackedArray, ChunkHandle};
 
 use crate::{io::VarInt, ProtocolVersion, Readable, Writeable};
 
 #[derive(Clone)]
 pub struct UpdateLight {
-    pub chunk: Arc<RwLock<Chunk>>,
+    pub chunk: ChunkHandle,
 }
 
 impl Debug for UpdateLight {
@@ -67,6 +68,6 @@ impl Readable for UpdateLight {
     where
         Self: Sized,
     {
-        todo!()
+        bail!("decoding Update Light packets is not supported")
     }
 }
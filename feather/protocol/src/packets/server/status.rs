@@ -1,9 +1,9 @@
-packets! {
-    Response {
-        response String;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Response {
+    pub response: String,
+}
 
-    Pong {
-        payload i64;
-    }
+#[derive(Debug, Clone, Packet)]
+pub struct Pong {
+    pub payload: i64,
 }
@@ -0,0 +1,568 @@
+//! Translation of packets for clients running an older protocol
+//! version than the packets are written for.
+//!
+//! Packets are always encoded and decoded in the format of
+//! [`NATIVE`], the oldest natively supported version. A
+//! [`Translation`] sits between the packet bodies and the
+//! connection: it rewrites each outgoing packet into the client's
+//! format and each incoming packet into the native format.
+//!
+//! Translating a version takes two parts:
+//! * ID tables, listing the ranges of packet IDs which moved.
+//! * Rewriters for the packets whose body changed, keyed
+//! by their native packet ID.
+//!
+//! Only the packets the server sends or handles are rewritten.
+//! Registry IDs are not remapped, so entities, sounds and
+//! other registry entries added in a newer version
+//! (e.g. the piglin brute) appear wrong on older clients.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Cursor,
+    ops::RangeInclusive,
+};
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    io::VarLong,
+    packets::client::{SetDisplayedRecipe, SetRecipeBookState},
+    ClientPlayPacket, Nbt, ProtocolVersion, Readable, VarInt, Writeable,
+};
+
+/// The version packets are written in before translation.
+pub const NATIVE: ProtocolVersion = ProtocolVersion::V1_16_2;
+
+// Native IDs of the clientbound packets which need rewriting.
+const CHUNK_DATA: i32 = 0x20;
+const JOIN_GAME: i32 = 0x24;
+const UNLOCK_RECIPES: i32 = 0x35;
+const RESPAWN: i32 = 0x39;
+const MULTI_BLOCK_CHANGE: i32 = 0x3B;
+
+/// Serverbound ID of 1.16.1's Recipe Book Data packet, which
+/// was split into Set Displayed Recipe and Set Recipe Book State.
+const RECIPE_BOOK_DATA_1_16_1: i32 = 0x1E;
+
+/// A range of packet IDs which moved by the same amount.
+struct IdShift {
+    native: RangeInclusive<i32>,
+    /// The translated ID of the first packet in the range.
+    translated: i32,
+}
+
+/// Clientbound packet IDs: Multi Block Change moved to
+/// the end of the packets sorted by name in 1.16.2.
+const CLIENTBOUND_1_16_1: &[IdShift] = &[
+    IdShift {
+        native: 0x0F..=0x3A,
+        translated: 0x10,
+    },
+    IdShift {
+        native: MULTI_BLOCK_CHANGE..=MULTI_BLOCK_CHANGE,
+        translated: 0x0F,
+    },
+];
+
+/// Serverbound packet IDs: Recipe Book Data became
+/// two packets in 1.16.2, moving the packets after it.
+const SERVERBOUND_1_16_1: &[IdShift] = &[IdShift {
+    native: 0x20..=0x2F,
+    translated: 0x1F,
+}];
+
+fn to_translated(shifts: &[IdShift], native: i32) -> i32 {
+    shifts
+        .iter()
+        .find(|shift| shift.native.contains(&native))
+        .map(|shift| native - shift.native.start() + shift.translated)
+        .unwrap_or(native)
+}
+
+fn to_native(shifts: &[IdShift], translated: i32) -> i32 {
+    shifts
+        .iter()
+        .find(|shift| {
+            let offset = translated - shift.translated;
+            (0..=shift.native.end() - shift.native.start()).contains(&offset)
+        })
+        .map(|shift| translated - shift.translated + shift.native.start())
+        .unwrap_or(translated)
+}
+
+/// Translates the Play packets of one connection between
+/// the native format and the client's protocol version.
+///
+/// Clientbound translation keeps track of the dimension types
+/// sent in Join Game, so each connection needs its own instance.
+#[derive(Debug, Clone)]
+pub struct Translation {
+    version: ProtocolVersion,
+    /// Names and properties of the dimension types
+    /// sent in the dimension codec.
+    dimension_types: Vec<(String, HashMap<String, nbt::Value>)>,
+}
+
+impl Translation {
+    /// Gets the translation for clients running `version`,
+    /// or `None` if packets don't need to be translated.
+    pub fn for_version(version: ProtocolVersion) -> Option<Self> {
+        match version {
+            ProtocolVersion::V1_16_1 => Some(Self {
+                version,
+                dimension_types: Vec::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Gets the protocol version packets are translated to.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Translates a packet (ID and body) written by the server
+    /// into the client's format, appending it to `out`.
+    pub fn clientbound(&mut self, packet: &[u8], out: &mut Vec<u8>) -> anyhow::Result<()> {
+        let mut cursor = Cursor::new(packet);
+        let id = VarInt::read(&mut cursor, NATIVE)?.0;
+        VarInt(to_translated(CLIENTBOUND_1_16_1, id)).write(out, self.version);
+
+        let result = match id {
+            CHUNK_DATA => rewrite_chunk_data(&mut cursor, out),
+            JOIN_GAME => self.rewrite_join_game(&mut cursor, out),
+            UNLOCK_RECIPES => rewrite_unlock_recipes(&mut cursor, out),
+            RESPAWN => self.rewrite_respawn(&mut cursor, out),
+            MULTI_BLOCK_CHANGE => rewrite_multi_block_change(&mut cursor, out),
+            _ => Ok(()),
+        };
+        result.with_context(|| format!("failed to translate clientbound packet {:#04x}", id))?;
+
+        out.extend_from_slice(remaining(&cursor));
+        Ok(())
+    }
+
+    /// Translates a packet (ID and body) sent by the client into
+    /// the native format. The resulting packets, of which there may
+    /// be none or several, are pushed to `out`.
+    pub fn serverbound(&self, packet: Bytes, out: &mut VecDeque<Bytes>) -> anyhow::Result<()> {
+        let mut cursor = Cursor::new(&packet[..]);
+        let id = VarInt::read(&mut cursor, self.version)?.0;
+
+        if id == RECIPE_BOOK_DATA_1_16_1 {
+            return split_recipe_book_data(&mut cursor, out)
+                .context("failed to translate serverbound Recipe Book Data");
+        }
+
+        let native = to_native(SERVERBOUND_1_16_1, id);
+        if native == id {
+            out.push_back(packet);
+        } else {
+            let mut translated = Vec::with_capacity(packet.len() + 1);
+            VarInt(native).write(&mut translated, NATIVE);
+            translated.extend_from_slice(remaining(&cursor));
+            out.push_back(Bytes::from(translated));
+        }
+        Ok(())
+    }
+
+    /// Converts the dimension codec from the registry format introduced
+    /// in 1.16.2 to a list of dimension types, and sends the dimension
+    /// type and previously separate hardcore flag the old way.
+    fn rewrite_join_game(
+        &mut self,
+        body: &mut Cursor<&[u8]>,
+        out: &mut Vec<u8>,
+    ) -> anyhow::Result<()> {
+        let entity_id = i32::read(body, NATIVE)?;
+        let is_hardcore = bool::read(body, NATIVE)?;
+        let gamemode = u8::read(body, NATIVE)?;
+        let previous_gamemode = u8::read(body, NATIVE)?;
+        let world_count = VarInt::read(body, NATIVE)?;
+        let world_names = (0..world_count.0)
+            .map(|_| String::read(body, NATIVE))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let codec = Nbt::<DimensionCodec>::read(body, NATIVE)?.0;
+        let dimension = Nbt::<HashMap<String, nbt::Value>>::read(body, NATIVE)?.0;
+        let world_name = String::read(body, NATIVE)?;
+        let hashed_seed = u64::read(body, NATIVE)?;
+        let max_players = VarInt::read(body, NATIVE)?.0;
+
+        self.dimension_types = codec
+            .dimension_types
+            .value
+            .into_iter()
+            .map(|entry| (entry.name, entry.element))
+            .collect();
+        let legacy_codec = LegacyDimensionCodec {
+            dimension: self
+                .dimension_types
+                .iter()
+                .map(|(name, element)| legacy_dimension_type(name, element))
+                .collect(),
+        };
+
+        entity_id.write(out, self.version);
+        (gamemode | if is_hardcore { 0x08 } else { 0 }).write(out, self.version);
+        previous_gamemode.write(out, self.version);
+        world_count.write(out, self.version);
+        for world_name in &world_names {
+            world_name.write(out, self.version);
+        }
+        Nbt(legacy_codec).write(out, self.version);
+        self.dimension_type_name(&dimension, &world_name)
+            .write(out, self.version);
+        world_name.write(out, self.version);
+        hashed_seed.write(out, self.version);
+        (max_players.clamp(0, 255) as u8).write(out, self.version);
+        Ok(())
+    }
+
+    /// Sends the dimension type by name instead of by value.
+    fn rewrite_respawn(&self, body: &mut Cursor<&[u8]>, out: &mut Vec<u8>) -> anyhow::Result<()> {
+        let dimension = Nbt::<HashMap<String, nbt::Value>>::read(body, NATIVE)?.0;
+        let world_name = String::read(body, NATIVE)?;
+
+        self.dimension_type_name(&dimension, &world_name)
+            .write(out, self.version);
+        world_name.write(out, self.version);
+        Ok(())
+    }
+
+    /// Finds the name of the dimension type with the given properties.
+    /// Falls back to the world name, which matches the dimension
+    /// type's name for the vanilla dimensions.
+    fn dimension_type_name(&self, dimension: &HashMap<String, nbt::Value>, world: &str) -> String {
+        self.dimension_types
+            .iter()
+            .find(|(_, element)| element == dimension)
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| world.to_owned())
+    }
+}
+
+/// The parts of the 1.16.2 dimension codec older clients need.
+#[derive(Debug, Deserialize)]
+struct DimensionCodec {
+    #[serde(rename = "minecraft:dimension_type")]
+    dimension_types: Registry,
+}
+
+#[derive(Debug, Deserialize)]
+struct Registry {
+    value: Vec<RegistryEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryEntry {
+    name: String,
+    element: HashMap<String, nbt::Value>,
+}
+
+/// The dimension codec as sent to 1.16.1 clients.
+#[derive(Debug, Serialize)]
+struct LegacyDimensionCodec {
+    dimension: Vec<HashMap<String, nbt::Value>>,
+}
+
+/// Converts a dimension type to the 1.16.1 format, in which
+/// it is named and has a `shrunk` flag instead of a coordinate scale.
+fn legacy_dimension_type(
+    name: &str,
+    element: &HashMap<String, nbt::Value>,
+) -> HashMap<String, nbt::Value> {
+    let mut legacy = element.clone();
+    legacy.remove("effects");
+    let shrunk = match legacy.remove("coordinate_scale") {
+        Some(nbt::Value::Float(scale)) => scale != 1.0,
+        Some(nbt::Value::Double(scale)) => scale != 1.0,
+        _ => false,
+    };
+    legacy.insert("shrunk".to_owned(), nbt::Value::Byte(shrunk as i8));
+    legacy.insert("name".to_owned(), nbt::Value::String(name.to_owned()));
+    legacy
+}
+
+/// Adds the "ignore old data" flag and writes the biomes as
+/// a fixed-size array of ints instead of a `VarInt` array.
+fn rewrite_chunk_data(body: &mut Cursor<&[u8]>, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    let chunk_x = i32::read(body, NATIVE)?;
+    let chunk_z = i32::read(body, NATIVE)?;
+    let full_chunk = bool::read(body, NATIVE)?;
+    let primary_bit_mask = VarInt::read(body, NATIVE)?;
+
+    chunk_x.write(out, NATIVE);
+    chunk_z.write(out, NATIVE);
+    full_chunk.write(out, NATIVE);
+    // Vanilla always discards the old light data on a chunk update.
+    true.write(out, NATIVE);
+    primary_bit_mask.write(out, NATIVE);
+
+    // The heightmaps didn't change.
+    let heightmaps_start = body.position() as usize;
+    Nbt::<nbt::Blob>::read(body, NATIVE)?;
+    out.extend_from_slice(&body.get_ref()[heightmaps_start..body.position() as usize]);
+
+    if full_chunk {
+        let biome_count = VarInt::read(body, NATIVE)?.0;
+        if biome_count != 1024 {
+            bail!("expected 1024 biomes, found {}", biome_count);
+        }
+        for _ in 0..biome_count {
+            VarInt::read(body, NATIVE)?.0.write(out, NATIVE);
+        }
+    }
+    Ok(())
+}
+
+/// Drops the states of the blast furnace and smoker
+/// recipe books, which 1.16.1 clients don't have.
+fn rewrite_unlock_recipes(body: &mut Cursor<&[u8]>, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    let action = VarInt::read(body, NATIVE)?;
+    action.write(out, NATIVE);
+    // Crafting, furnace, blast furnace and smoker, in book ID order.
+    for book_id in 0..4 {
+        let book_open = bool::read(body, NATIVE)?;
+        let filter_active = bool::read(body, NATIVE)?;
+        if book_id < 2 {
+            book_open.write(out, NATIVE);
+            filter_active.write(out, NATIVE);
+        }
+    }
+    Ok(())
+}
+
+/// Converts the packed section position and block records
+/// introduced in 1.16.2 back to the chunk-relative format.
+fn rewrite_multi_block_change(body: &mut Cursor<&[u8]>, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    let section = u64::read(body, NATIVE)? as i64;
+    let _dont_trust_edges = bool::read(body, NATIVE)?;
+    let record_count = VarInt::read(body, NATIVE)?;
+
+    // Each coordinate is a signed integer: 22 bits for X and Z, 20 for Y.
+    let section_x = section >> 42;
+    let section_y = section << 44 >> 44;
+    let section_z = section << 22 >> 42;
+
+    (section_x as i32).write(out, NATIVE);
+    (section_z as i32).write(out, NATIVE);
+    record_count.write(out, NATIVE);
+    for _ in 0..record_count.0 {
+        let record = VarLong::read(body, NATIVE)?.0;
+        let x = (record >> 8) & 0xF;
+        let z = (record >> 4) & 0xF;
+        let y = section_y * 16 + (record & 0xF);
+        ((x << 4 | z) as u8).write(out, NATIVE);
+        (y as u8).write(out, NATIVE);
+        VarInt((record >> 12) as i32).write(out, NATIVE);
+    }
+    Ok(())
+}
+
+/// Splits 1.16.1's Recipe Book Data packet into
+/// the packets which replaced it.
+fn split_recipe_book_data(
+    body: &mut Cursor<&[u8]>,
+    out: &mut VecDeque<Bytes>,
+) -> anyhow::Result<()> {
+    let version = ProtocolVersion::V1_16_1;
+    let mut push = |packet: ClientPlayPacket| {
+        let mut bytes = Vec::new();
+        packet.write(&mut bytes, NATIVE);
+        out.push_back(Bytes::from(bytes));
+    };
+
+    match VarInt::read(body, version)?.0 {
+        0 => push(ClientPlayPacket::SetDisplayedRecipe(SetDisplayedRecipe {
+            recipe_id: String::read(body, version)?,
+        })),
+        1 => {
+            // Crafting, furnace, blast furnace and smoker, in book ID order.
+            for book_id in 0..4 {
+                let book_open = bool::read(body, version)?;
+                let filter_active = bool::read(body, version)?;
+                push(ClientPlayPacket::SetRecipeBookState(SetRecipeBookState {
+                    book_id,
+                    book_open,
+                    filter_active,
+                }));
+            }
+        }
+        kind => bail!("unknown recipe book data type {}", kind),
+    }
+    Ok(())
+}
+
+fn remaining<'a>(cursor: &Cursor<&'a [u8]>) -> &'a [u8] {
+    &cursor.get_ref()[cursor.position() as usize..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation() -> Translation {
+        Translation::for_version(ProtocolVersion::V1_16_1).unwrap()
+    }
+
+    fn clientbound(translation: &mut Translation, packet: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        translation.clientbound(packet, &mut out).unwrap();
+        out
+    }
+
+    fn serverbound(packet: &[u8]) -> Vec<Bytes> {
+        let mut out = VecDeque::new();
+        translation()
+            .serverbound(Bytes::copy_from_slice(packet), &mut out)
+            .unwrap();
+        out.into_iter().collect()
+    }
+
+    #[test]
+    fn native_versions_are_not_translated() {
+        assert!(Translation::for_version(ProtocolVersion::V1_16_2).is_none());
+        assert!(Translation::for_version(ProtocolVersion::LATEST).is_none());
+    }
+
+    #[test]
+    fn clientbound_ids() {
+        assert_eq!(to_translated(CLIENTBOUND_1_16_1, 0x0E), 0x0E);
+        assert_eq!(to_translated(CLIENTBOUND_1_16_1, 0x0F), 0x10);
+        assert_eq!(to_translated(CLIENTBOUND_1_16_1, 0x3A), 0x3B);
+        assert_eq!(to_translated(CLIENTBOUND_1_16_1, MULTI_BLOCK_CHANGE), 0x0F);
+        assert_eq!(to_translated(CLIENTBOUND_1_16_1, 0x3C), 0x3C);
+    }
+
+    #[test]
+    fn serverbound_ids() {
+        assert_eq!(to_native(SERVERBOUND_1_16_1, 0x1D), 0x1D);
+        assert_eq!(to_native(SERVERBOUND_1_16_1, 0x1F), 0x20);
+        assert_eq!(to_native(SERVERBOUND_1_16_1, 0x2E), 0x2F);
+
+        assert_eq!(
+            serverbound(&[0x05, 1, 2]),
+            vec![Bytes::from(vec![0x05, 1, 2])]
+        );
+        assert_eq!(
+            serverbound(&[0x2E, 1, 2]),
+            vec![Bytes::from(vec![0x2F, 1, 2])]
+        );
+    }
+
+    #[test]
+    fn recipe_book_data_is_split() {
+        let packets = serverbound(&[0x1E, 0x01, 1, 0, 0, 1, 0, 0, 1, 1]);
+        let books: Vec<_> = packets
+            .iter()
+            .map(|packet| {
+                let packet = ClientPlayPacket::read(&mut Cursor::new(&packet[..]), NATIVE);
+                match packet.unwrap() {
+                    ClientPlayPacket::SetRecipeBookState(state) => {
+                        (state.book_id, state.book_open, state.filter_active)
+                    }
+                    packet => panic!("unexpected packet {:?}", packet),
+                }
+            })
+            .collect();
+        assert_eq!(
+            books,
+            vec![
+                (0, true, false),
+                (1, false, true),
+                (2, false, false),
+                (3, true, true)
+            ]
+        );
+
+        let mut packet = vec![0x1E, 0x00];
+        "minecraft:bread".to_owned().write(&mut packet, NATIVE);
+        let packets = serverbound(&packet);
+        assert_eq!(packets.len(), 1);
+        match ClientPlayPacket::read(&mut Cursor::new(&packets[0][..]), NATIVE).unwrap() {
+            ClientPlayPacket::SetDisplayedRecipe(recipe) => {
+                assert_eq!(recipe.recipe_id, "minecraft:bread")
+            }
+            packet => panic!("unexpected packet {:?}", packet),
+        }
+    }
+
+    #[test]
+    fn multi_block_change() {
+        // Section (-1, 4, 2); one stone block at (3, 5, 7) in the section.
+        let section = ((-1i64 & 0x3F_FFFF) << 42) | (2 << 20) | 4;
+        let mut packet = Vec::new();
+        VarInt(MULTI_BLOCK_CHANGE).write(&mut packet, NATIVE);
+        (section as u64).write(&mut packet, NATIVE);
+        false.write(&mut packet, NATIVE);
+        VarInt(1).write(&mut packet, NATIVE);
+        VarLong(1 << 12 | 3 << 8 | 7 << 4 | 5).write(&mut packet, NATIVE);
+
+        let mut expected = vec![0x0F];
+        (-1i32).write(&mut expected, NATIVE);
+        2i32.write(&mut expected, NATIVE);
+        expected.extend_from_slice(&[1, 0x37, 69, 1]);
+        assert_eq!(clientbound(&mut translation(), &packet), expected);
+    }
+
+    #[test]
+    fn unlock_recipes() {
+        // Adds one recipe; the blast furnace and smoker books are open.
+        let mut packet = vec![UNLOCK_RECIPES as u8, 0x01, 1, 0, 0, 1, 1, 1, 1, 1, 1];
+        "minecraft:torch".to_owned().write(&mut packet, NATIVE);
+
+        let mut expected = vec![0x36, 0x01, 1, 0, 0, 1, 1];
+        "minecraft:torch".to_owned().write(&mut expected, NATIVE);
+        assert_eq!(clientbound(&mut translation(), &packet), expected);
+    }
+
+    #[test]
+    fn unchanged_packets_are_copied() {
+        assert_eq!(
+            clientbound(&mut translation(), &[0x05, 1, 2, 3]),
+            vec![0x05, 1, 2, 3]
+        );
+        assert_eq!(
+            clientbound(&mut translation(), &[0x1F, 1, 2, 3]),
+            vec![0x20, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn dimension_types() {
+        let element = |scale: f32| {
+            let mut element = HashMap::new();
+            element.insert("coordinate_scale".to_owned(), nbt::Value::Float(scale));
+            element.insert(
+                "effects".to_owned(),
+                nbt::Value::String("minecraft:the_nether".to_owned()),
+            );
+            element
+        };
+
+        let legacy = legacy_dimension_type("minecraft:the_nether", &element(8.0));
+        assert_eq!(legacy.len(), 2);
+        assert_eq!(legacy["shrunk"], nbt::Value::Byte(1));
+        assert_eq!(
+            legacy["name"],
+            nbt::Value::String("minecraft:the_nether".to_owned())
+        );
+
+        let mut translation = translation();
+        translation.dimension_types = vec![
+            ("minecraft:overworld".to_owned(), element(1.0)),
+            ("minecraft:the_nether".to_owned(), element(8.0)),
+        ];
+        assert_eq!(
+            translation.dimension_type_name(&element(8.0), "minecraft:overworld"),
+            "minecraft:the_nether"
+        );
+        assert_eq!(
+            translation.dimension_type_name(&HashMap::new(), "custom:world"),
+            "custom:world"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! Applies the world's [anti-xray settings](base::anti_xray)
+//! to the chunks and block changes sent to players.
+
+use ahash::AHashMap;
+use base::{anti_xray::NEIGHBOR_OFFSETS, chunk::SECTION_HEIGHT, BlockPosition, ChunkPosition};
+use common::Game;
+use protocol::packets::server::ChunkObfuscation;
+
+use crate::{chunk_packet_cache::ChunkPacketCache, Server};
+
+/// Returns how to obfuscate the chunk at `position` when
+/// sending it, or `None` if anti-xray is disabled.
+pub fn chunk_obfuscation(game: &Game, position: ChunkPosition) -> Option<ChunkObfuscation> {
+    let anti_xray = game.world.settings().anti_xray.clone()?;
+    let mut neighbors = <[_; 4]>::default();
+    for (neighbor, (dx, dz)) in neighbors.iter_mut().zip(&NEIGHBOR_OFFSETS) {
+        *neighbor = game
+            .world
+            .chunk_map()
+            .chunk_handle_at(ChunkPosition::new(position.x + dx, position.z + dz));
+    }
+    Some(ChunkObfuscation {
+        anti_xray,
+        neighbors,
+    })
+}
+
+/// Sends the real blocks which were obfuscated and are exposed
+/// after the blocks at `changed` changed to the players who can see
+/// them, e.g. when a player mines towards an ore. Sends one
+/// Multi Block Change packet per chunk section.
+///
+/// Blocks in `resent_sections`, chunk sections which are sent
+/// again with the current blocks anyway, are skipped.
+pub fn reveal_exposed(
+    game: &Game,
+    server: &Server,
+    changed: impl IntoIterator<Item = BlockPosition>,
+    resent_sections: &[(ChunkPosition, usize)],
+) {
+    let anti_xray = match &game.world.settings().anti_xray {
+        Some(anti_xray) => anti_xray,
+        None => return,
+    };
+
+    let mut sections: AHashMap<(ChunkPosition, i32), Vec<_>> = AHashMap::new();
+    for (pos, block) in anti_xray.revealed_blocks(changed, |pos| game.block(pos)) {
+        let section = (pos.chunk(), pos.y / SECTION_HEIGHT as i32);
+        if !resent_sections.contains(&(section.0, section.1 as usize)) {
+            sections.entry(section).or_default().push((pos, block));
+        }
+    }
+
+    for ((chunk, section_y), blocks) in sections {
+        server.broadcast_nearby_with(blocks[0].0.position(), |client| {
+            client.send_multi_block_change(chunk, section_y, &blocks)
+        });
+    }
+}
+
+/// Drops the cached packets of the chunks next to `position`,
+/// since whether the blocks on their edges are obfuscated
+/// depends on the chunk at `position`.
+pub fn invalidate_neighbors(cache: &ChunkPacketCache, position: ChunkPosition) {
+    for (dx, dz) in &NEIGHBOR_OFFSETS {
+        cache.invalidate(ChunkPosition::new(position.x + dx, position.z + dz));
+    }
+}
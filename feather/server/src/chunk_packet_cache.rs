@@ -0,0 +1,145 @@
+use std::sync::{Arc, Weak};
+
+use ahash::AHashMap;
+use base::{Chunk, ChunkPosition};
+use parking_lot::{Mutex, RwLock};
+use protocol::{codec::CompressionThreshold, ProtocolVersion};
+
+/// Caches the encoded Chunk Data packets sent to load chunks.
+///
+/// A chunk is encoded and compressed once per protocol version,
+/// then the frame is reused for every client loading the chunk.
+/// Frames are invalidated when a block in their chunk changes
+/// and dropped once their chunk is unloaded.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Default)]
+pub struct ChunkPacketCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    frames: AHashMap<FrameKey, CachedFrame>,
+    /// Incremented on every invalidation, so that frames encoded
+    /// while a chunk was being changed aren't cached.
+    generation: u64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct FrameKey {
+    position: ChunkPosition,
+    version: ProtocolVersion,
+    compression: Option<CompressionThreshold>,
+}
+
+struct CachedFrame {
+    /// The chunk the frame was encoded from. A chunk
+    /// loaded again at the same position doesn't match.
+    chunk: Weak<RwLock<Chunk>>,
+    frame: Arc<[u8]>,
+}
+
+impl ChunkPacketCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the frame of the Chunk Data packet loading `chunk`,
+    /// calling `encode` to encode it if it isn't cached.
+    pub fn get_or_encode(
+        &self,
+        chunk: &Arc<RwLock<Chunk>>,
+        version: ProtocolVersion,
+        compression: Option<CompressionThreshold>,
+        encode: impl FnOnce() -> Vec<u8>,
+    ) -> Arc<[u8]> {
+        let key = FrameKey {
+            position: chunk.read().position(),
+            version,
+            compression,
+        };
+        let generation = {
+            let inner = self.inner.lock();
+            if let Some(cached) = inner.frames.get(&key) {
+                if Weak::as_ptr(&cached.chunk) == Arc::as_ptr(chunk) {
+                    return Arc::clone(&cached.frame);
+                }
+            }
+            inner.generation
+        };
+
+        // Encoding is slow, so don't block other connections meanwhile.
+        let frame: Arc<[u8]> = encode().into();
+
+        let mut inner = self.inner.lock();
+        if inner.generation == generation {
+            inner.frames.insert(
+                key,
+                CachedFrame {
+                    chunk: Arc::downgrade(chunk),
+                    frame: Arc::clone(&frame),
+                },
+            );
+        }
+        frame
+    }
+
+    /// Drops the frames of the chunk at `position`,
+    /// e.g. because one of its blocks changed.
+    pub fn invalidate(&self, position: ChunkPosition) {
+        let mut inner = self.inner.lock();
+        inner.generation += 1;
+        inner.frames.retain(|key, _| key.position != position);
+    }
+
+    /// Drops the frames of unloaded chunks.
+    pub fn prune(&self) {
+        self.inner
+            .lock()
+            .frames
+            .retain(|_, cached| cached.chunk.strong_count() > 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(x: i32) -> Arc<RwLock<Chunk>> {
+        Arc::new(RwLock::new(Chunk::new(ChunkPosition::new(x, 0))))
+    }
+
+    fn get(cache: &ChunkPacketCache, chunk: &Arc<RwLock<Chunk>>, byte: u8) -> Arc<[u8]> {
+        cache.get_or_encode(chunk, ProtocolVersion::LATEST, Some(256), || vec![byte])
+    }
+
+    #[test]
+    fn frames_are_reused_until_invalidated() {
+        let cache = ChunkPacketCache::new();
+        let chunk = chunk(0);
+        assert_eq!(&*get(&cache, &chunk, 1), [1]);
+        assert_eq!(&*get(&cache, &chunk, 2), [1]);
+
+        cache.invalidate(ChunkPosition::new(1, 0));
+        assert_eq!(&*get(&cache, &chunk, 2), [1]);
+        cache.invalidate(ChunkPosition::new(0, 0));
+        assert_eq!(&*get(&cache, &chunk, 2), [2]);
+
+        // A reloaded chunk is encoded again.
+        let reloaded = self::chunk(0);
+        assert_eq!(&*get(&cache, &reloaded, 3), [3]);
+    }
+
+    #[test]
+    fn unloaded_chunks_are_pruned() {
+        let cache = ChunkPacketCache::new();
+        let chunk = chunk(0);
+        get(&cache, &chunk, 1);
+        cache.prune();
+        assert_eq!(cache.inner.lock().frames.len(), 1);
+        drop(chunk);
+        cache.prune();
+        assert!(cache.inner.lock().frames.is_empty());
+    }
+}
@@ -24,6 +24,13 @@ impl ChunkSubscriptions {
             .map(Vec::as_slice)
             .unwrap_or_default()
     }
+
+    /// Iterates over chunks and the clients subscribed to them.
+    pub fn iter(&self) -> impl Iterator<Item = (ChunkPosition, &[ClientId])> + '_ {
+        self.chunks
+            .iter()
+            .map(|(&chunk, clients)| (chunk, clients.as_slice()))
+    }
 }
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
@@ -2,42 +2,77 @@ use std::{
     cell::{Cell, RefCell},
     collections::VecDeque,
     io::Cursor,
+    net::SocketAddr,
     sync::Arc,
+    time::Duration,
 };
 
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use base::{
-    BlockId, BlockPosition, Chunk, ChunkPosition, EntityKind, EntityMetadata, Gamemode, ItemStack,
-    Position, ProfileProperty, Text,
+    BlockId, BlockPosition, Chunk, ChunkPosition, Difficulty, EntityKind, EntityMetadata, Gamemode,
+    ItemStack, Position, ProfileProperty, Text,
 };
 use common::{
     chat::{ChatKind, ChatMessage},
+    effects::{ActiveEffect, StatusEffect},
+    enchanting::{EnchantmentOffer, EnchantmentSeed},
+    fake_entities::FakeEntityId,
+    recipe_book::RecipeBook,
+    smithing::SMITHING_RECIPES,
+    stonecutter::STONECUTTING_RECIPES,
     Window,
 };
 use flume::{Receiver, Sender};
-use packets::server::{Particle, SetSlot, SpawnLivingEntity, UpdateLight, WindowConfirmation};
+use packets::server::{
+    BlockEntityData, DeclareRecipes, EntityEffect, EntityEquipment, Ingredient, OpenWindow,
+    Particle, Recipe, RecipeBookState, RemoveEntityEffect, SetSlot, SpawnEntity, SpawnLivingEntity,
+    UnlockRecipes, UnlockRecipesAction, UpdateLight, WindowConfirmation, WindowProperty,
+};
 use parking_lot::RwLock;
 use protocol::{
     packets::{
         self,
         server::{
-            AddPlayer, Animation, BlockChange, ChatPosition, ChunkData, ChunkDataKind,
-            DestroyEntities, Disconnect, EntityAnimation, EntityHeadLook, EntityTeleport, JoinGame,
-            KeepAlive, PlayerInfo, PlayerPositionAndLook, PluginMessage, SendEntityMetadata,
-            SpawnPlayer, Title, UnloadChunk, UpdateViewPosition, WindowItems,
+            AddPlayer, Animation, ArgumentParser, BlockChange, ChatPosition, ChunkData,
+            ChunkDataKind, ChunkObfuscation, CommandNode, CommandNodeKind, DeclareCommands,
+            DestroyEntities, Disconnect, EntityAnimation, EntityHeadLook, EntityPosition,
+            EntityPositionAndRotation, EntityRotation, EntityStatus, EntityTeleport, JoinGame,
+            MultiBlockChange, PlayerInfo, PlayerPositionAndLook, PluginMessage, ResourcePack,
+            SendEntityMetadata, ServerDifficulty, SetPassengers, SpawnPlayer, TabComplete,
+            TabCompleteMatch, Title, UnloadChunk, UpdateViewPosition, WindowItems,
+            ASK_SERVER_SUGGESTIONS,
         },
     },
-    ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, Writeable,
+    ClientPlayPacket, Nbt, ProtocolVersion, ServerPlayPacket, VarLong, Writeable,
 };
-use quill_common::components::OnGround;
+use quill_common::components::{HeadYaw, OnGround};
 use uuid::Uuid;
 use vec_arena::Arena;
 
-use crate::{initial_handler::NewPlayer, network_id_registry::NetworkId, Options};
+use self::{
+    fake_entities::SentFakeEntity,
+    movement::{KnownPosition, Movement},
+};
+use crate::{
+    connection_worker::WriterMessage, entities::Equipment, initial_handler::NewPlayer,
+    keep_alive::KeepAlive, network_id_registry::NetworkId, traffic::ConnectionTraffic, Options,
+    Traffic,
+};
+
+mod chunk_order;
+mod fake_blocks;
+mod fake_entities;
+mod movement;
 
 /// Max number of chunks to send to a client per tick.
 const MAX_CHUNKS_PER_TICK: usize = 10;
 
+/// Radius, in chunks, of the area around the player which is sent
+/// before the player's position. The client stays on the
+/// "Loading terrain" screen until it knows its position, so
+/// the rest of its view is streamed in afterwards.
+const SPAWN_CHUNK_RADIUS: i32 = 1;
+
 /// ID of a client. Can be reused.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ClientId(usize);
@@ -75,28 +110,46 @@ impl Clients {
 /// This struct provides methods to send packets
 /// to the client.
 pub struct Client {
-    packets_to_send: Sender<ServerPlayPacket>,
+    packets_to_send: Sender<WriterMessage>,
     received_packets: Receiver<ClientPlayPacket>,
     options: Arc<Options>,
     username: String,
     profile: Vec<ProfileProperty>,
     uuid: Uuid,
+    addr: SocketAddr,
+    keep_alive: KeepAlive,
+    traffic: ConnectionTraffic,
 
     teleport_id_counter: Cell<i32>,
 
     network_id: NetworkId,
-    sent_entities: RefCell<AHashSet<NetworkId>>,
+    /// Entities spawned on the client, with
+    /// the position the client knows them at.
+    sent_entities: RefCell<AHashMap<NetworkId, KnownPosition>>,
+    /// Fake entities spawned on the client.
+    fake_entities: RefCell<AHashMap<FakeEntityId, SentFakeEntity>>,
+    /// Blocks overridden on the client.
+    fake_blocks: RefCell<AHashMap<BlockPosition, BlockId>>,
 
     knows_position: Cell<bool>,
+    /// Position to send once the chunks around
+    /// it are sent, while the client is loading.
+    pending_spawn: Cell<Option<Position>>,
     known_chunks: RefCell<AHashSet<ChunkPosition>>,
 
     chunk_send_queue: RefCell<VecDeque<ChunkData>>,
+    /// The player position the send queue was last sorted
+    /// for, or `None` if chunks were queued since.
+    chunk_queue_sorted_for: Cell<Option<Position>>,
 
     /// The previous own position sent by the client.
     /// Used to detect when we need to teleport the client.
     client_known_position: Cell<Option<Position>>,
 
     disconnected: Cell<bool>,
+
+    /// Whether the player may see vanished players.
+    sees_vanished: Cell<bool>,
 }
 
 impl Client {
@@ -110,12 +163,20 @@ impl Client {
             network_id,
             profile: player.profile,
             uuid: player.uuid,
-            sent_entities: RefCell::new(AHashSet::new()),
+            addr: player.addr,
+            keep_alive: player.keep_alive,
+            traffic: player.traffic,
+            sent_entities: RefCell::new(AHashMap::new()),
+            fake_entities: RefCell::new(AHashMap::new()),
+            fake_blocks: RefCell::new(AHashMap::new()),
             knows_position: Cell::new(false),
+            pending_spawn: Cell::new(None),
             known_chunks: RefCell::new(AHashSet::new()),
             chunk_send_queue: RefCell::new(VecDeque::new()),
+            chunk_queue_sorted_for: Cell::new(None),
             client_known_position: Cell::new(None),
             disconnected: Cell::new(false),
+            sees_vanished: Cell::new(false),
         }
     }
 
@@ -127,6 +188,15 @@ impl Client {
         self.client_known_position.get()
     }
 
+    /// Whether vanished players are sent to this client.
+    pub fn sees_vanished(&self) -> bool {
+        self.sees_vanished.get()
+    }
+
+    pub fn set_sees_vanished(&self, sees_vanished: bool) {
+        self.sees_vanished.set(sees_vanished);
+    }
+
     pub fn profile(&self) -> &[ProfileProperty] {
         &self.profile
     }
@@ -143,6 +213,25 @@ impl Client {
         &self.username
     }
 
+    /// Gets the address of the client. Behind a load
+    /// balancer using the PROXY protocol, this is
+    /// the address the balancer forwarded.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Gets the client's round-trip latency, measured
+    /// with Keep Alive packets.
+    pub fn latency(&self) -> Option<Duration> {
+        self.keep_alive.latency()
+    }
+
+    /// Gets the traffic of the client's connection, e.g.
+    /// to find clients sending an unusual number of packets.
+    pub fn traffic(&self) -> Traffic {
+        self.traffic.get()
+    }
+
     pub fn received_packets(&self) -> impl Iterator<Item = ClientPlayPacket> + '_ {
         self.received_packets.try_iter()
     }
@@ -159,6 +248,70 @@ impl Client {
         self.knows_position.get()
     }
 
+    /// Sends the player's position once the chunks around it have
+    /// been sent, which makes the client leave the loading screen.
+    /// Does nothing if the client already knows its position.
+    pub fn spawn_when_ready(&self, position: Position) {
+        if !self.knows_own_position() {
+            self.pending_spawn.set(Some(position));
+        }
+    }
+
+    fn spawn_if_ready(&self) {
+        let position = match self.pending_spawn.get() {
+            Some(position) => position,
+            None => return,
+        };
+
+        let center = position.chunk();
+        let radius = SPAWN_CHUNK_RADIUS.min(self.options.view_distance as i32);
+        let ready = {
+            let known_chunks = self.known_chunks.borrow();
+            let queue = self.chunk_send_queue.borrow();
+            (-radius..=radius)
+                .flat_map(|dx| (-radius..=radius).map(move |dz| (dx, dz)))
+                .map(|(dx, dz)| ChunkPosition::new(center.x + dx, center.z + dz))
+                .all(|pos| {
+                    known_chunks.contains(&pos)
+                        && !queue
+                            .iter()
+                            .any(|packet| packet.chunk.read().position() == pos)
+                })
+        };
+        if ready {
+            log::debug!("Sent spawn chunks to {}; now spawning", self.username);
+            self.pending_spawn.set(None);
+            // The writer may still hold the spawn chunks in its low
+            // priority lane, which the position must not overtake.
+            let packet = self.teleport(position);
+            self.send_low_priority_packet(packet);
+        }
+    }
+
+    /// Sorts the chunks waiting to be sent so that those in
+    /// front of the player at `position` are sent first.
+    ///
+    /// The queue is only sorted again once new chunks are
+    /// queued or the player moved to another chunk or turned.
+    pub fn prioritize_chunks(&self, position: Position) {
+        let up_to_date = self
+            .chunk_queue_sorted_for
+            .get()
+            .map_or(false, |sorted_for| {
+                !chunk_order::needs_resort(sorted_for, position)
+            });
+        if up_to_date {
+            return;
+        }
+        self.chunk_send_queue
+            .borrow_mut()
+            .make_contiguous()
+            .sort_by_cached_key(|packet| {
+                chunk_order::priority(position, packet.chunk.read().position())
+            });
+        self.chunk_queue_sorted_for.set(Some(position));
+    }
+
     pub fn tick(&self) {
         let num_to_send = MAX_CHUNKS_PER_TICK.min(self.chunk_send_queue.borrow().len());
         for packet in self.chunk_send_queue.borrow_mut().drain(0..num_to_send) {
@@ -168,15 +321,34 @@ impl Client {
                 self.username
             );
             let chunk = Arc::clone(&packet.chunk);
+            let position = chunk.read().position();
             self.send_packet(UpdateLight { chunk });
             self.send_packet(packet);
+            self.reassert_fake_blocks(position);
         }
+        self.spawn_if_ready();
+        self.tick_fake_entities();
+        self.flush();
+    }
+
+    /// Writes the packets sent since the last flush
+    /// to the connection in a single batch.
+    pub fn flush(&self) {
+        let _ = self.packets_to_send.try_send(WriterMessage::Flush);
     }
 
     /// Returns whether the entity with the given ID
     /// is currently loaded on the client.
     pub fn is_entity_loaded(&self, network_id: NetworkId) -> bool {
-        self.sent_entities.borrow().contains(&network_id)
+        self.sent_entities.borrow().contains_key(&network_id)
+    }
+
+    /// Returns whether updates about the entity with the given ID
+    /// should be sent to the client: either the entity is the client's
+    /// own player, or it is loaded on the client. Entities hidden from
+    /// the client, like vanished players, are not loaded.
+    pub fn sees_entity(&self, network_id: NetworkId) -> bool {
+        network_id == self.network_id || self.is_entity_loaded(network_id)
     }
 
     pub fn send_join_game(&self, gamemode: Gamemode) {
@@ -210,11 +382,106 @@ impl Client {
         });
     }
 
+    pub fn send_difficulty(&self, difficulty: Difficulty) {
+        log::trace!("Sending difficulty {:?} to {}", difficulty, self.username);
+        self.send_packet(ServerDifficulty {
+            difficulty: difficulty as u8,
+            locked: false,
+        });
+    }
+
+    /// Declares the server's commands, so the client accepts them
+    /// and asks the server to complete their arguments.
+    pub fn send_declare_commands(&self, names: &[String]) {
+        // Every command takes a free-form argument string,
+        // shared by all of them as the last node.
+        let arguments_index = names.len() as i32 + 1;
+        let root = CommandNode {
+            kind: CommandNodeKind::Root,
+            executable: false,
+            children: (1..arguments_index).collect(),
+            redirect_node: None,
+            suggestions: None,
+        };
+        let literals = names.iter().map(|name| CommandNode {
+            kind: CommandNodeKind::Literal { name: name.clone() },
+            executable: true,
+            children: vec![arguments_index],
+            redirect_node: None,
+            suggestions: None,
+        });
+        let arguments = CommandNode {
+            kind: CommandNodeKind::Argument {
+                name: "arguments".to_owned(),
+                parser: ArgumentParser::greedy_string(),
+            },
+            executable: true,
+            children: Vec::new(),
+            redirect_node: None,
+            suggestions: Some(ASK_SERVER_SUGGESTIONS.to_owned()),
+        };
+        self.send_packet(DeclareCommands {
+            nodes: std::iter::once(root)
+                .chain(literals)
+                .chain(std::iter::once(arguments))
+                .collect(),
+            root_index: 0,
+        });
+    }
+
+    /// Declares the stonecutter and smithing recipes,
+    /// which the client needs to list them.
+    pub fn send_declare_recipes(&self) {
+        let ingredient = |item| Ingredient {
+            allowed_items: vec![Some(ItemStack::new(item, 1))],
+        };
+        let stonecutting = STONECUTTING_RECIPES
+            .iter()
+            .map(|recipe| Recipe::Stonecutting {
+                id: recipe.id(),
+                group: String::new(),
+                ingredient: ingredient(recipe.ingredient),
+                result: Some(recipe.result()),
+            });
+        let smithing = SMITHING_RECIPES.iter().map(|recipe| Recipe::Smithing {
+            id: recipe.id(),
+            base: ingredient(recipe.base),
+            addition: ingredient(recipe.addition),
+            result: Some(ItemStack::new(recipe.result, 1)),
+        });
+        self.send_packet(DeclareRecipes {
+            recipes: stonecutting.chain(smithing).collect(),
+        });
+    }
+
+    /// Sends the whole recipe book of the player,
+    /// replacing the recipes known by the client.
+    pub fn send_recipe_book(&self, book: &RecipeBook) {
+        log::trace!("Sending recipe book to {}", self.username);
+        self.send_packet(UnlockRecipes {
+            action: UnlockRecipesAction::Init,
+            books: recipe_book_states(book),
+            recipes: book.known().map(str::to_owned).collect(),
+            highlighted: book.highlighted().map(str::to_owned).collect(),
+        });
+    }
+
+    /// Sends recipes just added to the player's recipe book.
+    pub fn send_unlocked_recipes(&self, book: &RecipeBook, recipes: &[String]) {
+        log::trace!("Unlocking {} recipes for {}", recipes.len(), self.username);
+        self.send_packet(UnlockRecipes {
+            action: UnlockRecipesAction::Add,
+            books: recipe_book_states(book),
+            recipes: recipes.to_vec(),
+            highlighted: Vec::new(),
+        });
+    }
+
     pub fn send_brand(&self) {
         let mut data = Vec::new();
         "Feather"
             .to_owned()
-            .write(&mut data, ProtocolVersion::V1_16_2);
+            .write(&mut data, ProtocolVersion::LATEST);
         self.send_plugin_message("minecraft:brand", data)
     }
 
@@ -227,13 +494,52 @@ impl Client {
         })
     }
 
+    pub fn send_resource_pack(&self, url: impl Into<String>, hash: impl Into<String>) {
+        let url = url.into();
+        log::trace!("Sending resource pack {} to {}", url, self.username);
+        self.send_packet(ResourcePack {
+            url,
+            hash: hash.into(),
+        });
+    }
+
+    /// Responds to a tab completion request.
+    pub fn send_tab_complete(
+        &self,
+        transaction_id: i32,
+        start: usize,
+        length: usize,
+        matches: Vec<String>,
+    ) {
+        self.send_packet(TabComplete {
+            id: transaction_id,
+            start: start as i32,
+            length: length as i32,
+            matches: matches
+                .into_iter()
+                .map(|value| TabCompleteMatch {
+                    value,
+                    has_tooltip: false,
+                    tooltip: None,
+                })
+                .collect(),
+        });
+    }
+
     pub fn update_own_position(&self, new_position: Position) {
+        let packet = self.teleport(new_position);
+        self.send_packet(packet);
+    }
+
+    /// Creates the packet moving the player to `new_position`,
+    /// and records that the client will know that position.
+    fn teleport(&self, new_position: Position) -> PlayerPositionAndLook {
         log::trace!(
             "Updating position of {} to {:?}",
             self.username,
             new_position
         );
-        self.send_packet(PlayerPositionAndLook {
+        let packet = PlayerPositionAndLook {
             x: new_position.x,
             y: new_position.y,
             z: new_position.z,
@@ -241,11 +547,12 @@ impl Client {
             pitch: new_position.pitch,
             flags: 0,
             teleport_id: self.teleport_id_counter.get(),
-        });
+        };
         self.teleport_id_counter
             .set(self.teleport_id_counter.get() + 1);
         self.knows_position.set(true);
         self.client_known_position.set(Some(new_position));
+        packet
     }
 
     pub fn update_own_chunk(&self, pos: ChunkPosition) {
@@ -256,32 +563,79 @@ impl Client {
         });
     }
 
-    pub fn send_chunk(&self, chunk: &Arc<RwLock<Chunk>>) {
+    pub fn send_chunk(&self, chunk: &Arc<RwLock<Chunk>>, obfuscation: Option<ChunkObfuscation>) {
         self.chunk_send_queue.borrow_mut().push_back(ChunkData {
             chunk: Arc::clone(chunk),
             kind: ChunkDataKind::LoadChunk,
+            obfuscation,
         });
+        self.chunk_queue_sorted_for.set(None);
         self.known_chunks
             .borrow_mut()
             .insert(chunk.read().position());
     }
 
-    pub fn overwrite_chunk_sections(&self, chunk: &Arc<RwLock<Chunk>>, sections: Vec<usize>) {
+    pub fn overwrite_chunk_sections(
+        &self,
+        chunk: &Arc<RwLock<Chunk>>,
+        sections: Vec<usize>,
+        obfuscation: Option<ChunkObfuscation>,
+    ) {
         self.send_packet(ChunkData {
             chunk: Arc::clone(chunk),
             kind: ChunkDataKind::OverwriteChunk { sections },
+            obfuscation,
         });
+        self.reassert_fake_blocks(chunk.read().position());
     }
 
     pub fn send_block_change(&self, position: BlockPosition, new_block: BlockId) {
+        if self.is_block_faked(position) {
+            return;
+        }
         self.send_packet(BlockChange {
             position,
             block: new_block,
         });
     }
 
+    /// Sends changes to blocks within the chunk section
+    /// at `section_y` of the chunk at `chunk`.
+    pub fn send_multi_block_change(
+        &self,
+        chunk: ChunkPosition,
+        section_y: i32,
+        blocks: &[(BlockPosition, BlockId)],
+    ) {
+        let records: Vec<VarLong> = blocks
+            .iter()
+            .filter(|(position, _)| !self.is_block_faked(*position))
+            .map(|(position, block)| {
+                let offset = (position.x & 0xF) << 8 | (position.z & 0xF) << 4 | (position.y & 0xF);
+                VarLong((block.vanilla_id() as i64) << 12 | offset as i64)
+            })
+            .collect();
+        if records.is_empty() {
+            return;
+        }
+        // 22 bits for X and Z, 20 for Y.
+        let section = (chunk.x as i64 & 0x3F_FFFF) << 42
+            | (chunk.z as i64 & 0x3F_FFFF) << 20
+            | (section_y as i64 & 0xF_FFFF);
+        self.send_packet(MultiBlockChange {
+            chunk_section_coordinate: section as u64,
+            dont_trust_edges: true,
+            records,
+        });
+    }
+
     pub fn unload_chunk(&self, pos: ChunkPosition) {
         log::trace!("Unloading chunk at {:?} on {}", pos, self.username);
+        // The chunk may still be waiting in the send queue,
+        // e.g. if the view distance shrank right after growing.
+        self.chunk_send_queue
+            .borrow_mut()
+            .retain(|packet| packet.chunk.read().position() != pos);
         self.send_packet(UnloadChunk {
             chunk_x: pos.x,
             chunk_z: pos.z,
@@ -308,6 +662,19 @@ impl Client {
         self.send_packet(PlayerInfo::AddPlayers(vec![action]));
     }
 
+    pub fn update_tablist_pings(&self, pings: Vec<(Uuid, i32)>) {
+        self.send_packet(PlayerInfo::UpdatePings(pings));
+    }
+
+    /// Sets the name shown for a player in the tablist,
+    /// or resets it to their username if `None`.
+    pub fn update_tablist_display_name(&self, uuid: Uuid, display_name: Option<&Text>) {
+        self.send_packet(PlayerInfo::UpdateDisplayNames(vec![(
+            uuid,
+            display_name.map(Text::to_string),
+        )]));
+    }
+
     pub fn remove_tablist_player(&self, uuid: Uuid) {
         log::trace!("Sending RemovePlayer({}) to {}", uuid, self.username);
         self.send_packet(PlayerInfo::RemovePlayers(vec![uuid]));
@@ -323,7 +690,7 @@ impl Client {
 
     pub fn send_player(&self, network_id: NetworkId, uuid: Uuid, pos: Position) {
         log::trace!("Sending {:?} to {}", uuid, self.username);
-        assert!(!self.sent_entities.borrow().contains(&network_id));
+        assert!(!self.sent_entities.borrow().contains_key(&network_id));
         self.send_packet(SpawnPlayer {
             entity_id: network_id.0,
             player_uuid: uuid,
@@ -333,7 +700,7 @@ impl Client {
             yaw: pos.yaw,
             pitch: pos.pitch,
         });
-        self.register_entity(network_id);
+        self.register_entity(network_id, pos);
     }
 
     pub fn send_living_entity(
@@ -363,6 +730,39 @@ impl Client {
             velocity_y: 0,
             velocity_z: 0,
         });
+        self.register_entity(network_id, pos);
+    }
+
+    /// Spawns a non-living entity, like an item or a minecart.
+    pub fn send_object(
+        &self,
+        network_id: NetworkId,
+        uuid: Uuid,
+        pos: Position,
+        kind: EntityKind,
+        data: i32,
+    ) {
+        log::trace!(
+            "Spawning a {:?} on {} (entity type ID: {})",
+            kind,
+            self.username,
+            kind.id()
+        );
+        self.send_packet(SpawnEntity {
+            entity_id: network_id.0,
+            uuid,
+            kind: kind.id() as i32,
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            pitch: pos.pitch,
+            yaw: pos.yaw,
+            data,
+            velocity_x: 0,
+            velocity_y: 0,
+            velocity_z: 0,
+        });
+        self.register_entity(network_id, pos);
     }
 
     pub fn update_entity_position(
@@ -370,38 +770,102 @@ impl Client {
         network_id: NetworkId,
         position: Position,
         on_ground: OnGround,
+        head_follows_body: bool,
     ) {
         if network_id == self.network_id {
             // This entity is the client. Only update
             // the position if it has changed from the client's
             // known position.
-            if Some(position) != self.client_known_position.get() {
+            if !self.knows_own_position() {
+                // Still loading; spawn at the new position instead.
+                self.spawn_when_ready(position);
+            } else if Some(position) != self.client_known_position.get() {
                 self.update_own_position(position);
             }
             return;
         }
-        // Consider using the relative movement packets in the future.
-        // (Entity Teleport works fine, but the relative movement packets
-        // save bandwidth.)
-        self.send_packet(EntityTeleport {
-            entity_id: network_id.0,
-            x: position.x,
-            y: position.y,
-            z: position.z,
-            yaw: position.yaw,
-            pitch: position.pitch,
-            on_ground: on_ground.0,
-        });
-        // Needed for head orientation
+        // Teleport entities which weren't spawned through this client.
+        let movement = self
+            .sent_entities
+            .borrow_mut()
+            .get_mut(&network_id)
+            .map_or(Movement::Teleport, |known| known.update(position));
+        match movement {
+            Movement::None => {}
+            Movement::Move(delta_x, delta_y, delta_z) => self.send_packet(EntityPosition {
+                entity_id: network_id.0,
+                delta_x,
+                delta_y,
+                delta_z,
+                on_ground: on_ground.0,
+            }),
+            Movement::Look => self.send_packet(EntityRotation {
+                entity_id: network_id.0,
+                yaw: position.yaw,
+                pitch: position.pitch,
+                on_ground: on_ground.0,
+            }),
+            Movement::MoveAndLook(delta_x, delta_y, delta_z) => {
+                self.send_packet(EntityPositionAndRotation {
+                    entity_id: network_id.0,
+                    delta_x,
+                    delta_y,
+                    delta_z,
+                    yaw: position.yaw,
+                    pitch: position.pitch,
+                    on_ground: on_ground.0,
+                })
+            }
+            Movement::Teleport => self.send_packet(EntityTeleport {
+                entity_id: network_id.0,
+                x: position.x,
+                y: position.y,
+                z: position.z,
+                yaw: position.yaw,
+                pitch: position.pitch,
+                on_ground: on_ground.0,
+            }),
+        }
+        if movement.is_look() && head_follows_body {
+            // Needed for head orientation
+            self.send_packet(EntityHeadLook {
+                entity_id: network_id.0,
+                head_yaw: position.yaw,
+            });
+        }
+    }
+
+    /// Turns the head of an entity whose head
+    /// doesn't follow its body, see [`HeadYaw`].
+    pub fn update_entity_head_yaw(&self, network_id: NetworkId, head_yaw: HeadYaw) {
+        if network_id == self.network_id {
+            return;
+        }
         self.send_packet(EntityHeadLook {
             entity_id: network_id.0,
-            head_yaw: position.yaw,
+            head_yaw: head_yaw.0,
         });
     }
 
-    pub fn send_keepalive(&self) {
-        log::trace!("Sending keepalive to {}", self.username);
-        self.send_packet(KeepAlive { id: 0 });
+    /// Sets the entities riding the entity `vehicle`.
+    pub fn send_passengers(&self, vehicle: NetworkId, passengers: &[NetworkId]) {
+        self.send_packet(SetPassengers {
+            entity_id: vehicle.0,
+            passengers: passengers
+                .iter()
+                .map(|passenger| passenger.0.into())
+                .collect(),
+        });
+    }
+
+    pub fn send_entity_equipment(&self, network_id: NetworkId, equipment: &Equipment) {
+        if network_id == self.network_id {
+            return;
+        }
+        self.send_packet(EntityEquipment {
+            entity_id: network_id.0,
+            entries: equipment.entries(),
+        });
     }
 
     pub fn send_entity_animation(&self, network_id: NetworkId, animation: Animation) {
@@ -414,6 +878,13 @@ impl Client {
         })
     }
 
+    pub fn send_entity_status(&self, network_id: NetworkId, status: i8) {
+        self.send_packet(EntityStatus {
+            entity_id: network_id.0,
+            status,
+        })
+    }
+
     pub fn send_chat_message(&self, message: ChatMessage) {
         let packet = chat_packet(message);
         self.send_packet(packet);
@@ -473,19 +944,19 @@ impl Client {
         });
     }
 
-    pub fn send_window_items(&self, window: &Window) {
+    pub fn send_window_items(&self, window_id: u8, window: &Window) {
         log::trace!("Updating window for {}", self.username);
         let packet = WindowItems {
-            window_id: 0,
+            window_id,
             items: window.inner().to_vec(),
         };
         self.send_packet(packet);
     }
 
-    pub fn set_slot(&self, slot: i16, item: Option<ItemStack>) {
+    pub fn set_slot(&self, window_id: u8, slot: i16, item: Option<ItemStack>) {
         log::trace!("Setting slot {} of {} to {:?}", slot, self.username, item);
         self.send_packet(SetSlot {
-            window_id: 0,
+            window_id,
             slot,
             slot_data: item,
         });
@@ -508,37 +979,120 @@ impl Client {
 
     pub fn set_cursor_slot(&self, item: Option<ItemStack>) {
         log::trace!("Setting cursor slot of {} to {:?}", self.username, item);
-        self.set_slot(-1, item);
+        self.set_slot(0, -1, item);
     }
 
-    pub fn send_player_model_flags(&self, netowrk_id: NetworkId, model_flags: u8) {
-        let mut entity_metadata = EntityMetadata::new();
-        entity_metadata.set(16, model_flags);
+    pub fn send_entity_metadata(&self, network_id: NetworkId, metadata: EntityMetadata) {
         self.send_packet(SendEntityMetadata {
-            entity_id: netowrk_id.0,
-            entries: entity_metadata,
+            entity_id: network_id.0,
+            entries: metadata,
+        });
+    }
+
+    pub fn send_entity_effect(&self, network_id: NetworkId, effect: &ActiveEffect) {
+        let mut flags = 0;
+        if effect.ambient {
+            flags |= 0x01;
+        }
+        if effect.show_particles {
+            flags |= 0x02;
+        }
+        if effect.show_icon {
+            flags |= 0x04;
+        }
+        self.send_packet(EntityEffect {
+            entity_id: network_id.0,
+            effect_id: effect.effect.id(),
+            amplifier: effect.amplifier as i8,
+            duration: effect.duration as i32,
+            flags,
+        });
+    }
+
+    pub fn remove_entity_effect(&self, network_id: NetworkId, effect: StatusEffect) {
+        self.send_packet(RemoveEntityEffect {
+            entity_id: network_id.0,
+            effect_id: effect.id(),
+        });
+    }
+
+    pub fn send_block_entity_data(&self, position: BlockPosition, action: u8, data: nbt::Blob) {
+        self.send_packet(BlockEntityData {
+            position,
+            action,
+            data: Nbt(data),
+        });
+    }
+
+    pub fn open_window(&self, window_id: u8, window_kind: i32, title: Text) {
+        self.send_packet(OpenWindow {
+            window_id: window_id as i32,
+            window_kind,
+            window_title: title.into(),
         });
     }
 
-    fn register_entity(&self, network_id: NetworkId) {
-        self.sent_entities.borrow_mut().insert(network_id);
+    pub fn send_window_property(&self, window_id: u8, property: i16, value: i16) {
+        self.send_packet(WindowProperty {
+            window_id,
+            property,
+            value,
+        });
+    }
+
+    /// Sends the offers of an enchanting table.
+    pub fn send_enchantment_offers(
+        &self,
+        window_id: u8,
+        offers: &[EnchantmentOffer],
+        seed: EnchantmentSeed,
+    ) {
+        for (slot, offer) in offers.iter().enumerate() {
+            let (enchantment, level) = match offer.hint {
+                Some((info, level)) => (info.protocol_id, level as i16),
+                None => (-1, -1),
+            };
+            let slot = slot as i16;
+            self.send_window_property(window_id, slot, offer.cost as i16);
+            self.send_window_property(window_id, 4 + slot, enchantment);
+            self.send_window_property(window_id, 7 + slot, level);
+        }
+        // The client only uses the seed to generate the
+        // galactic alphabet text, so the low bits are hidden.
+        self.send_window_property(window_id, 3, (seed.0 & -16) as i16);
+    }
+
+    fn register_entity(&self, network_id: NetworkId, position: Position) {
+        self.sent_entities
+            .borrow_mut()
+            .insert(network_id, KnownPosition::new(position));
     }
 
     fn send_packet(&self, packet: impl Into<ServerPlayPacket>) {
-        let _ = self.packets_to_send.try_send(packet.into());
+        let _ = self
+            .packets_to_send
+            .try_send(WriterMessage::SendPacket(packet.into()));
+    }
+
+    /// Sends a packet after the chunks already sent.
+    fn send_low_priority_packet(&self, packet: impl Into<ServerPlayPacket>) {
+        let _ = self
+            .packets_to_send
+            .try_send(WriterMessage::SendLowPriorityPacket(packet.into()));
     }
 
-    pub fn disconnect(&self, reason: &str) {
+    pub fn disconnect(&self, reason: impl Into<Text>) {
         self.disconnected.set(true);
         self.send_packet(Disconnect {
-            reason: Text::from(reason.to_owned()).to_string(),
+            reason: reason.into(),
         });
+        self.flush();
     }
 }
 
 fn chat_packet(message: ChatMessage) -> packets::server::ChatMessage {
     packets::server::ChatMessage {
-        message: message.text().to_string(),
+        message: message.text().clone(),
         position: match message.kind() {
             ChatKind::PlayerChat => ChatPosition::Chat,
             ChatKind::System => ChatPosition::SystemMessage,
@@ -547,3 +1101,56 @@ fn chat_packet(message: ChatMessage) -> packets::server::ChatMessage {
         sender: Uuid::default(),
     }
 }
+
+fn recipe_book_states(book: &RecipeBook) -> [RecipeBookState; 4] {
+    let mut states = [RecipeBookState::default(); 4];
+    for (state, book) in states.iter_mut().zip(book.books().iter()) {
+        state.open = book.open;
+        state.filter_active = book.filter_active;
+    }
+    states
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use crate::{
+        config::{Config, DEFAULT_CONFIG},
+        traffic::TrafficStats,
+    };
+
+    use super::*;
+
+    fn client(network_id: NetworkId) -> Client {
+        let (packets_to_send, _) = flume::unbounded();
+        let (_, received_packets) = flume::unbounded();
+        let player = NewPlayer {
+            uuid: Uuid::from_u128(network_id.0 as u128),
+            username: "Steve".to_owned(),
+            profile: Vec::new(),
+            addr: "127.0.0.1:25565".parse().unwrap(),
+            received_packets,
+            packets_to_send,
+            keep_alive: KeepAlive::new(),
+            traffic: TrafficStats::new().connection(),
+        };
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        Client::new(player, Arc::new(config.to_options()), network_id)
+    }
+
+    #[test]
+    fn sees_only_loaded_entities() {
+        let client = client(NetworkId(1));
+        assert!(client.sees_entity(NetworkId(1)));
+
+        // A vanished player is never spawned on the client.
+        let hidden = NetworkId(2);
+        assert!(!client.sees_entity(hidden));
+
+        client.send_player(NetworkId(3), Uuid::from_u128(3), position!(0.0, 64.0, 0.0));
+        assert!(client.sees_entity(NetworkId(3)));
+        client.unload_entity(NetworkId(3));
+        assert!(!client.sees_entity(NetworkId(3)));
+    }
+}
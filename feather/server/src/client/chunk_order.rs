@@ -0,0 +1,94 @@
+//! Orders the chunks waiting to be sent to a client.
+//!
+//! Chunks in front of the player are sent before chunks behind
+//! them, closest first, so that after a teleport the world
+//! appears to load where the player is looking.
+
+use base::{ChunkPosition, Position};
+
+/// Change in yaw, in degrees, after which the
+/// queue is sorted again for the new direction.
+const RESORT_YAW: f32 = 45.;
+
+/// Returns the key chunks are sorted by: chunks in front of the
+/// viewer come first, then closer chunks before farther ones.
+///
+/// The viewer's own chunk and its neighbors always count as
+/// being in front, so the ground below the player loads first.
+pub fn priority(viewer: Position, chunk: ChunkPosition) -> (bool, i32) {
+    let distance = chunk.distance_squared_to(viewer.chunk());
+
+    let yaw = f64::from(viewer.yaw.to_radians());
+    let (look_x, look_z) = (-yaw.sin(), yaw.cos());
+    let offset_x = f64::from(chunk.x * 16 + 8) - viewer.x;
+    let offset_z = f64::from(chunk.z * 16 + 8) - viewer.z;
+    let behind = distance > 2 && offset_x * look_x + offset_z * look_z < 0.;
+
+    (behind, distance)
+}
+
+/// Returns whether a queue sorted for `sorted_for` should be
+/// sorted again now that the viewer is at `viewer`.
+pub fn needs_resort(sorted_for: Position, viewer: Position) -> bool {
+    if sorted_for.chunk() != viewer.chunk() {
+        return true;
+    }
+    let turned = (sorted_for.yaw - viewer.yaw).rem_euclid(360.);
+    turned.min(360. - turned) >= RESORT_YAW
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewer(yaw: f32) -> Position {
+        Position {
+            x: 8.,
+            y: 64.,
+            z: 8.,
+            pitch: 0.,
+            yaw,
+        }
+    }
+
+    #[test]
+    fn chunks_in_front_come_first() {
+        // A yaw of 0 looks toward positive Z.
+        let mut chunks = vec![
+            ChunkPosition::new(0, -3),
+            ChunkPosition::new(0, 5),
+            ChunkPosition::new(0, -1),
+            ChunkPosition::new(0, 2),
+        ];
+        chunks.sort_by_key(|&chunk| priority(viewer(0.), chunk));
+        assert_eq!(
+            chunks,
+            vec![
+                ChunkPosition::new(0, -1),
+                ChunkPosition::new(0, 2),
+                ChunkPosition::new(0, 5),
+                ChunkPosition::new(0, -3),
+            ]
+        );
+
+        chunks.sort_by_key(|&chunk| priority(viewer(180.), chunk));
+        assert_eq!(
+            chunks[..2],
+            [ChunkPosition::new(0, -1), ChunkPosition::new(0, -3)]
+        );
+    }
+
+    #[test]
+    fn resort_after_turning() {
+        assert!(!needs_resort(viewer(0.), viewer(30.)));
+        assert!(needs_resort(viewer(0.), viewer(90.)));
+        assert!(!needs_resort(viewer(350.), viewer(-20.)));
+        assert!(needs_resort(
+            viewer(0.),
+            Position {
+                x: 40.,
+                ..viewer(0.)
+            }
+        ));
+    }
+}
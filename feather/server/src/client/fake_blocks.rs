@@ -0,0 +1,59 @@
+//! Sends fake blocks, which override blocks on this client.
+//!
+//! Fake block changes go in the writer's low priority lane,
+//! behind any chunk data already queued, since a chunk written
+//! after them would overwrite them.
+
+use base::{BlockId, BlockPosition, ChunkPosition};
+use protocol::packets::server::BlockChange;
+
+use super::Client;
+
+impl Client {
+    /// Shows `block` at `pos` instead of the real block
+    /// until it is restored, even if the chunk is resent.
+    pub fn set_fake_block(&self, pos: BlockPosition, block: BlockId) {
+        self.fake_blocks.borrow_mut().insert(pos, block);
+        if self.known_chunks.borrow().contains(&pos.chunk()) {
+            self.send_fake_block_change(pos, block);
+        }
+    }
+
+    /// Shows the real block at `pos` again.
+    ///
+    /// `real_block` is `None` if its chunk isn't loaded
+    /// on the server, in which case the client doesn't have it either.
+    pub fn restore_fake_block(&self, pos: BlockPosition, real_block: Option<BlockId>) {
+        if self.fake_blocks.borrow_mut().remove(&pos).is_none() {
+            return;
+        }
+        if let Some(real_block) = real_block {
+            if self.known_chunks.borrow().contains(&pos.chunk()) {
+                self.send_fake_block_change(pos, real_block);
+            }
+        }
+    }
+
+    pub(super) fn is_block_faked(&self, pos: BlockPosition) -> bool {
+        self.fake_blocks.borrow().contains_key(&pos)
+    }
+
+    /// Sends the fake blocks in a chunk again
+    /// after the chunk was (re)sent.
+    pub(super) fn reassert_fake_blocks(&self, chunk: ChunkPosition) {
+        let fake_blocks: Vec<_> = self
+            .fake_blocks
+            .borrow()
+            .iter()
+            .filter(|(pos, _)| pos.chunk() == chunk)
+            .map(|(&pos, &block)| (pos, block))
+            .collect();
+        for (pos, block) in fake_blocks {
+            self.send_fake_block_change(pos, block);
+        }
+    }
+
+    fn send_fake_block_change(&self, position: BlockPosition, block: BlockId) {
+        self.send_low_priority_packet(BlockChange { position, block });
+    }
+}
@@ -0,0 +1,133 @@
+//! Sends fake entities, which only exist on this client.
+
+use base::{
+    metadata::{
+        EntityBitMask, META_INDEX_CUSTOM_NAME, META_INDEX_ENTITY_BITMASK,
+        META_INDEX_IS_CUSTOM_NAME_VISIBLE, META_INDEX_NO_GRAVITY,
+        META_INDEX_PLAYER_DISPLAYED_SKIN_PARTS,
+    },
+    EntityKind, EntityMetadata, Gamemode, ProfileProperty,
+};
+use common::fake_entities::{FakeEntityId, FakeEntityUpdate};
+use quill_common::{components::OnGround, fake_entity::FakeEntity};
+use uuid::Uuid;
+
+use super::Client;
+use crate::NetworkId;
+
+/// Number of ticks an NPC stays in the tablist,
+/// which gives the client time to load its skin.
+const NPC_TABLIST_TICKS: u32 = 20;
+
+const META_INDEX_ARMOR_STAND_FLAGS: u8 = 14;
+/// Gives an armor stand no hitbox, so holograms
+/// don't get in the way of the player's clicks.
+const ARMOR_STAND_MARKER: u8 = 0x10;
+
+/// A fake entity spawned on a client.
+pub(super) struct SentFakeEntity {
+    network_id: NetworkId,
+    uuid: Uuid,
+    /// Number of ticks until an NPC is removed from the tablist,
+    /// if it is still in it.
+    tablist_ticks: Option<u32>,
+}
+
+impl Client {
+    pub fn update_fake_entity(&self, update: FakeEntityUpdate) {
+        match update {
+            FakeEntityUpdate::Spawn(id, entity) => self.spawn_fake_entity(id, entity),
+            FakeEntityUpdate::Move(id, position) => {
+                if let Some(network_id) = self.fake_entity_network_id(id) {
+                    self.update_entity_position(network_id, position, OnGround(true), true);
+                }
+            }
+            FakeEntityUpdate::SetText(id, text) => {
+                if let Some(network_id) = self.fake_entity_network_id(id) {
+                    let metadata = EntityMetadata::new().with(META_INDEX_CUSTOM_NAME, Some(text));
+                    self.send_entity_metadata(network_id, metadata);
+                }
+            }
+            FakeEntityUpdate::Remove(id) => {
+                let sent = self.fake_entities.borrow_mut().remove(&id);
+                if let Some(sent) = sent {
+                    self.unload_entity(sent.network_id);
+                    if sent.tablist_ticks.is_some() {
+                        self.remove_tablist_player(sent.uuid);
+                    }
+                }
+            }
+        }
+    }
+
+    fn spawn_fake_entity(&self, id: FakeEntityId, entity: FakeEntity) {
+        let network_id = NetworkId::new();
+        let uuid = Uuid::new_v4();
+        let mut tablist_ticks = None;
+        match entity {
+            FakeEntity::Hologram { position, text } => {
+                self.send_living_entity(network_id, uuid, position, EntityKind::ArmorStand);
+                let metadata = EntityMetadata::entity_base()
+                    .with(META_INDEX_ENTITY_BITMASK, EntityBitMask::INVISIBLE.bits())
+                    .with(META_INDEX_CUSTOM_NAME, Some(text))
+                    .with(META_INDEX_IS_CUSTOM_NAME_VISIBLE, true)
+                    .with(META_INDEX_NO_GRAVITY, true)
+                    .with(META_INDEX_ARMOR_STAND_FLAGS, ARMOR_STAND_MARKER);
+                self.send_entity_metadata(network_id, metadata);
+            }
+            FakeEntity::Npc {
+                position,
+                name,
+                skin,
+            } => {
+                let profile: Vec<ProfileProperty> = skin
+                    .into_iter()
+                    .map(|skin| ProfileProperty {
+                        name: "textures".to_owned(),
+                        value: skin.value,
+                        signature: skin.signature,
+                    })
+                    .collect();
+                self.add_tablist_player(uuid, name, &profile, Gamemode::Survival);
+                self.send_player(network_id, uuid, position);
+                // Show all skin layers
+                let metadata = EntityMetadata::entity_base()
+                    .with(META_INDEX_PLAYER_DISPLAYED_SKIN_PARTS, 0x7Fu8);
+                self.send_entity_metadata(network_id, metadata);
+                tablist_ticks = Some(NPC_TABLIST_TICKS);
+            }
+        }
+        self.fake_entities.borrow_mut().insert(
+            id,
+            SentFakeEntity {
+                network_id,
+                uuid,
+                tablist_ticks,
+            },
+        );
+    }
+
+    fn fake_entity_network_id(&self, id: FakeEntityId) -> Option<NetworkId> {
+        self.fake_entities
+            .borrow()
+            .get(&id)
+            .map(|sent| sent.network_id)
+    }
+
+    /// Removes NPCs from the tablist once their skins have loaded.
+    pub(super) fn tick_fake_entities(&self) {
+        let mut to_remove = Vec::new();
+        for sent in self.fake_entities.borrow_mut().values_mut() {
+            if let Some(ticks) = &mut sent.tablist_ticks {
+                *ticks -= 1;
+                if *ticks == 0 {
+                    sent.tablist_ticks = None;
+                    to_remove.push(sent.uuid);
+                }
+            }
+        }
+        for uuid in to_remove {
+            self.remove_tablist_player(uuid);
+        }
+    }
+}
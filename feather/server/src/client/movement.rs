@@ -0,0 +1,178 @@
+//! Picks the cheapest packets to send an entity's
+//! movement to a client.
+//!
+//! Small movements are sent as deltas relative to the
+//! position the client already knows. Deltas are rounded,
+//! so we keep track of the position the client ends up with
+//! and teleport the entity once it drifts too far from the
+//! actual one.
+
+use base::Position;
+use protocol::PositionDelta;
+
+/// Number of updates after which an entity is teleported
+/// even if it only moved a little, like vanilla does.
+const RESYNC_INTERVAL: u32 = 400;
+
+/// Distance in blocks between the client's copy of a
+/// position and the actual one above which the entity
+/// is teleported.
+const MAX_DRIFT: f64 = 1. / 256.;
+
+/// The packets needed to update an entity's position.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Movement {
+    /// The client's copy of the position is close enough.
+    None,
+    /// Entity Position with the given deltas.
+    Move(i16, i16, i16),
+    /// Entity Rotation.
+    Look,
+    /// Entity Position and Rotation with the given deltas.
+    MoveAndLook(i16, i16, i16),
+    /// Entity Teleport.
+    Teleport,
+}
+
+impl Movement {
+    /// Returns whether the entity's rotation is sent,
+    /// in which case its head rotation should be sent too.
+    pub fn is_look(self) -> bool {
+        matches!(
+            self,
+            Movement::Look | Movement::MoveAndLook(..) | Movement::Teleport
+        )
+    }
+}
+
+/// An entity's position as known by a client.
+#[derive(Copy, Clone, Debug)]
+pub struct KnownPosition {
+    position: Position,
+    updates_since_teleport: u32,
+}
+
+impl KnownPosition {
+    pub fn new(position: Position) -> Self {
+        Self {
+            position,
+            updates_since_teleport: 0,
+        }
+    }
+
+    /// Returns the movement to send for the entity moving to
+    /// `position`, assuming the client receives it.
+    pub fn update(&mut self, position: Position) -> Movement {
+        self.updates_since_teleport += 1;
+        if self.updates_since_teleport >= RESYNC_INTERVAL {
+            return self.teleport(position);
+        }
+
+        let known = self.position;
+        let (delta_x, delta_y, delta_z) = match deltas(known, position) {
+            Some(deltas) => deltas,
+            None => return self.teleport(position),
+        };
+
+        let mut moved = known;
+        moved.x += delta_x.blocks();
+        moved.y += delta_y.blocks();
+        moved.z += delta_z.blocks();
+        if moved.distance_to(position) > MAX_DRIFT {
+            return self.teleport(position);
+        }
+
+        let is_move = (delta_x.0, delta_y.0, delta_z.0) != (0, 0, 0);
+        let is_look = angle_steps(known.yaw) != angle_steps(position.yaw)
+            || angle_steps(known.pitch) != angle_steps(position.pitch);
+        if is_look {
+            moved.yaw = position.yaw;
+            moved.pitch = position.pitch;
+        }
+        self.position = moved;
+
+        let (delta_x, delta_y, delta_z) = (delta_x.0, delta_y.0, delta_z.0);
+        match (is_move, is_look) {
+            (false, false) => Movement::None,
+            (true, false) => Movement::Move(delta_x, delta_y, delta_z),
+            (false, true) => Movement::Look,
+            (true, true) => Movement::MoveAndLook(delta_x, delta_y, delta_z),
+        }
+    }
+
+    fn teleport(&mut self, position: Position) -> Movement {
+        *self = Self::new(position);
+        Movement::Teleport
+    }
+}
+
+fn deltas(old: Position, new: Position) -> Option<(PositionDelta, PositionDelta, PositionDelta)> {
+    Some((
+        PositionDelta::between(old.x, new.x)?,
+        PositionDelta::between(old.y, new.y)?,
+        PositionDelta::between(old.z, new.z)?,
+    ))
+}
+
+/// Converts an angle to the 1/256ths of
+/// a turn it is sent as.
+fn angle_steps(angle: f32) -> u8 {
+    (angle / 360.0 * 256.0).round() as i32 as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn picks_cheapest_movement() {
+        let mut known = KnownPosition::new(position!(0.0, 64.0, 0.0));
+        assert_eq!(known.update(position!(0.0, 64.0, 0.0)), Movement::None);
+        assert_eq!(
+            known.update(position!(1.0, 64.0, 0.0)),
+            Movement::Move(4096, 0, 0)
+        );
+        assert_eq!(
+            known.update(position!(1.0, 64.0, 0.0, 90.0, 0.0)),
+            Movement::Look
+        );
+        assert_eq!(
+            known.update(position!(1.0, 63.5, 0.0, 0.0, 0.0)),
+            Movement::MoveAndLook(0, -2048, 0)
+        );
+        assert_eq!(
+            known.update(position!(20.0, 63.5, 0.0, 0.0, 0.0)),
+            Movement::Teleport
+        );
+    }
+
+    #[test]
+    fn small_changes_accumulate() {
+        let mut known = KnownPosition::new(position!(0.0, 64.0, 0.0));
+        // Less than 1/4096 of a block, and less
+        // than 1/256 of a turn, per update.
+        assert_eq!(
+            known.update(position!(0.0001, 64.0, 0.0, 0.5, 0.0)),
+            Movement::None
+        );
+        assert_eq!(
+            known.update(position!(0.0003, 64.0, 0.0, 0.5, 0.0)),
+            Movement::Move(1, 0, 0)
+        );
+        assert_eq!(
+            known.update(position!(0.0003, 64.0, 0.0, 2.0, 0.0)),
+            Movement::Look
+        );
+    }
+
+    #[test]
+    fn resyncs_periodically() {
+        let mut known = KnownPosition::new(position!(0.0, 64.0, 0.0));
+        for _ in 1..RESYNC_INTERVAL {
+            assert_eq!(known.update(position!(0.0, 64.0, 0.0)), Movement::None);
+        }
+        assert_eq!(known.update(position!(0.0, 64.0, 0.0)), Movement::Teleport);
+    }
+}
@@ -1,38 +1,88 @@
 //! Loads an `Options` from a TOML config.
 
-use std::{fs, net::Ipv4Addr, path::Path, str::FromStr};
+use std::{fs, io, net::Ipv4Addr, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 
 use anyhow::Context;
-use base::Gamemode;
+use base::{
+    anti_xray::EngineMode, position, BlockId, Difficulty, GameRules, Gamemode, Item, ItemStack,
+    Position, Text,
+};
+use common::world_settings::WorldSettings;
+use plugin_host::PluginQuotas;
+use quill_common::economy::Currency;
 use serde::{Deserialize, Deserializer};
+use thiserror::Error;
 
-use crate::{favicon::Favicon, Options};
+use crate::{
+    favicon::Favicon,
+    io::sniffer::SnifferOptions,
+    storage::{BackendKind, StorageOptions},
+    Options,
+};
 
-const DEFAULT_CONFIG: &str = include_str!("../config.toml");
+pub(crate) const DEFAULT_CONFIG: &str = include_str!("../config.toml");
+
+/// An error which occurred while loading the config.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to create the default config at {}", .path.display())]
+    Create {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to read {}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid config file {}", .path.display())]
+    Invalid {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+}
 
 /// Loads the config, creating a default config if needed.
-pub fn load(path: &str) -> anyhow::Result<Config> {
-    let path = Path::new(path);
+pub fn load(path: &str) -> Result<Config, ConfigError> {
+    let path = PathBuf::from(path);
     let default_config = DEFAULT_CONFIG;
 
     if !path.exists() {
         println!("Creating default config");
-        fs::write(path, default_config)?;
+        if let Err(source) = fs::write(&path, default_config) {
+            return Err(ConfigError::Create { path, source });
+        }
     }
 
-    let config_string = fs::read_to_string(path)?;
-    let config: Config = toml::from_str(&config_string).context("invalid config.toml file")?;
-
-    Ok(config)
+    let config_string = match fs::read_to_string(&path) {
+        Ok(config_string) => config_string,
+        Err(source) => return Err(ConfigError::Read { path, source }),
+    };
+    toml::from_str(&config_string).map_err(|source| ConfigError::Invalid { path, source })
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub network: Network,
+    pub runtime: Runtime,
     pub server: ServerConfig,
     pub log: Log,
+    pub sniffer: Sniffer,
+    pub capture: Capture,
+    pub join: Join,
+    pub afk: Afk,
+    pub combat_log: CombatLog,
+    pub economy: Economy,
+    pub storage: Storage,
+    pub resource_pack: ResourcePack,
     pub world: World,
+    pub plugins: Plugins,
     pub proxy: Proxy,
+    pub rcon: Rcon,
+    pub query: Query,
 }
 
 impl Config {
@@ -40,8 +90,15 @@ impl Config {
         Options {
             port: self.network.port,
             bind_address: self.network.address.to_string(),
+            bind_unix: if self.network.bind_unix.is_empty() {
+                None
+            } else {
+                Some(PathBuf::from(&self.network.bind_unix))
+            },
             favicon: Favicon::load_default(),
             motd: self.server.motd.clone(),
+            status_sample_size: self.server.status_sample_size,
+            status_sample_lines: self.server.status_sample_lines.clone(),
             online_mode: if self.proxy.proxy_mode != ProxyMode::None {
                 false
             } else {
@@ -52,15 +109,121 @@ impl Config {
             } else {
                 Some(self.network.compression_threshold as usize)
             },
+            compression_level: self.network.compression_level,
+            max_connections_per_ip: self.network.max_connections_per_ip,
+            connection_throttle_window: Duration::from_millis(
+                self.network.connection_throttle_window_ms,
+            ),
+            max_concurrent_handshakes: self.network.max_concurrent_handshakes,
+            handshake_timeout: Duration::from_secs(self.network.handshake_timeout_secs),
+            login_timeout: Duration::from_secs(self.network.login_timeout_secs),
+            encryption_timeout: Duration::from_secs(self.network.encryption_timeout_secs),
+            max_movement_packets_per_second: self.network.max_movement_packets_per_second,
+            max_packets_per_second: self.network.max_packets_per_second,
             view_distance: self.server.view_distance,
             max_players: self.server.max_players,
             default_gamemode: self.server.default_gamemode,
+            world_dir: self.world.name.clone().into(),
+            chunk_unload_delay: Duration::from_secs(self.world.chunk_unload_delay_secs),
+            max_loaded_chunks: if self.world.max_loaded_chunks == 0 {
+                None
+            } else {
+                Some(self.world.max_loaded_chunks)
+            },
+            starter_kit: self.join.starter_kit.clone(),
+            first_spawn: self.join.first_spawn,
+            join_motd: self.join.motd.clone(),
+            join_message: self.join.join_message.clone(),
+            quit_message: self.join.quit_message.clone(),
+            afk_timeout: if self.afk.timeout_secs == 0 {
+                None
+            } else {
+                Some(Duration::from_secs(self.afk.timeout_secs))
+            },
+            afk_message: self.afk.afk_message.clone(),
+            afk_return_message: self.afk.return_message.clone(),
+            combat_log_duration: if self.combat_log.combat_secs == 0 {
+                None
+            } else {
+                Some(Duration::from_secs(self.combat_log.combat_secs))
+            },
+            combat_log_action: match self.combat_log.action {
+                CombatLogAction::DropInventory => {
+                    common::combat_log::CombatLogAction::DropInventory
+                }
+                CombatLogAction::Npc => common::combat_log::CombatLogAction::Npc,
+                CombatLogAction::Event => common::combat_log::CombatLogAction::EventOnly,
+            },
+            combat_log_npc_lifetime: Duration::from_secs(self.combat_log.npc_lifetime_secs),
+            economy_currency: Currency {
+                singular: self.economy.currency_singular.clone(),
+                plural: self.economy.currency_plural.clone(),
+                fractional_digits: self.economy.fractional_digits,
+            },
+            economy_starting_balance: self.economy.starting_balance,
+            storage: StorageOptions {
+                backend: match self.storage.backend {
+                    StorageBackend::Json => BackendKind::Json,
+                    StorageBackend::Sled => BackendKind::Sled,
+                    StorageBackend::Sqlite => BackendKind::Sqlite,
+                },
+                path: PathBuf::from(&self.storage.path),
+                batch_window: Duration::from_millis(self.storage.batch_window_ms),
+            },
+            resource_pack: if self.resource_pack.url.is_empty() {
+                None
+            } else {
+                Some(crate::options::ResourcePack {
+                    url: self.resource_pack.url.clone(),
+                    hash: self.resource_pack.hash.clone(),
+                    kick_message: if self.resource_pack.required {
+                        Some(self.resource_pack.kick_message.clone())
+                    } else {
+                        None
+                    },
+                })
+            },
+            sniffer: if self.sniffer.enabled {
+                Some(SnifferOptions {
+                    hex_dump: self.sniffer.hex_dump,
+                    include: self.sniffer.include.clone(),
+                    exclude: self.sniffer.exclude.clone(),
+                })
+            } else {
+                None
+            },
+            capture_dir: if self.capture.enabled {
+                Some(self.capture.directory.clone().into())
+            } else {
+                None
+            },
+            restart_warnings: self
+                .server
+                .restart_warnings
+                .iter()
+                .map(|&secs| Duration::from_secs(secs))
+                .collect(),
+            check_invariants: self.server.check_invariants,
+            report_tick_times: self.server.report_tick_times,
             proxy_mode: match self.proxy.proxy_mode {
                 ProxyMode::None => None,
                 ProxyMode::Bungee => Some(crate::options::ProxyMode::Bungeecord),
                 ProxyMode::Velocity => Some(crate::options::ProxyMode::Velocity),
             },
             velocity_secret: self.proxy.velocity_secret.clone(),
+            proxy_protocol: self.network.proxy_protocol,
+            rcon_port: if self.rcon.enabled {
+                Some(self.rcon.port)
+            } else {
+                None
+            },
+            rcon_password: self.rcon.password.clone(),
+            query_port: if self.query.enabled {
+                Some(self.query.port)
+            } else {
+                None
+            },
+            hooks: Default::default(),
         }
     }
 }
@@ -69,16 +232,122 @@ impl Config {
 pub struct Network {
     pub address: Ipv4Addr,
     pub port: u16,
+    pub bind_unix: String,
     pub compression_threshold: i32,
+    pub compression_level: u32,
+    pub max_connections_per_ip: u32,
+    pub connection_throttle_window_ms: u64,
+    pub max_concurrent_handshakes: usize,
+    pub handshake_timeout_secs: u64,
+    pub login_timeout_secs: u64,
+    pub encryption_timeout_secs: u64,
+    pub max_movement_packets_per_second: u32,
+    pub max_packets_per_second: u32,
+    pub proxy_protocol: bool,
+}
+
+/// Sizing of the Tokio runtime which handles connections.
+#[derive(Debug, Deserialize)]
+pub struct Runtime {
+    pub flavor: RuntimeFlavor,
+    /// 0 means one thread per CPU core.
+    pub worker_threads: usize,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeFlavor {
+    /// Connections are handled by a pool of worker threads.
+    MultiThread,
+    /// Connections are all handled by one thread.
+    CurrentThread,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ServerConfig {
     pub online_mode: bool,
-    pub motd: String,
+    #[serde(deserialize_with = "deserialize_motd")]
+    pub motd: Text,
+    pub status_sample_size: usize,
+    pub status_sample_lines: Vec<String>,
     pub max_players: u32,
     pub default_gamemode: Gamemode,
     pub view_distance: u32,
+    pub restart_warnings: Vec<u64>,
+    pub restart_script: String,
+    pub check_invariants: bool,
+    pub report_tick_times: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Join {
+    #[serde(deserialize_with = "deserialize_starter_kit")]
+    pub starter_kit: Vec<ItemStack>,
+    #[serde(deserialize_with = "deserialize_first_spawn")]
+    pub first_spawn: Option<Position>,
+    #[serde(deserialize_with = "deserialize_join_motd")]
+    pub motd: Option<Text>,
+    #[serde(deserialize_with = "deserialize_message_template")]
+    pub join_message: Option<String>,
+    #[serde(deserialize_with = "deserialize_message_template")]
+    pub quit_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Afk {
+    pub timeout_secs: u64,
+    #[serde(deserialize_with = "deserialize_message_template")]
+    pub afk_message: Option<String>,
+    #[serde(deserialize_with = "deserialize_message_template")]
+    pub return_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CombatLog {
+    pub combat_secs: u64,
+    pub action: CombatLogAction,
+    pub npc_lifetime_secs: u64,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CombatLogAction {
+    DropInventory,
+    Npc,
+    Event,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Economy {
+    pub currency_singular: String,
+    pub currency_plural: String,
+    #[serde(deserialize_with = "deserialize_fractional_digits")]
+    pub fractional_digits: u8,
+    pub starting_balance: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Storage {
+    pub backend: StorageBackend,
+    pub path: String,
+    pub batch_window_ms: u64,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    Json,
+    Sled,
+    Sqlite,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResourcePack {
+    pub url: String,
+    #[serde(deserialize_with = "deserialize_sha1")]
+    pub hash: String,
+    pub required: bool,
+    pub kick_message: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -87,11 +356,97 @@ pub struct Log {
     pub level: log::LevelFilter,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Sniffer {
+    pub enabled: bool,
+    pub hex_dump: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Capture {
+    pub enabled: bool,
+    pub directory: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct World {
     pub name: String,
     pub generator: String,
     pub seed: String,
+    pub difficulty: Difficulty,
+    pub pvp: bool,
+    /// Number of threads loading and saving chunks.
+    pub chunk_workers: usize,
+    pub chunk_unload_delay_secs: u64,
+    /// Zero for no limit.
+    pub max_loaded_chunks: usize,
+    /// Whether changed chunks are saved to the world.
+    pub save_chunks: bool,
+    #[serde(default)]
+    pub game_rules: GameRules,
+    pub anti_xray: AntiXray,
+}
+
+impl World {
+    pub fn to_settings(&self) -> WorldSettings {
+        WorldSettings {
+            difficulty: self.difficulty,
+            pvp: self.pvp,
+            game_rules: self.game_rules.clone(),
+            anti_xray: self.anti_xray.to_anti_xray().map(Arc::new),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AntiXray {
+    pub enabled: bool,
+    pub engine_mode: EngineMode,
+    #[serde(deserialize_with = "deserialize_blocks")]
+    pub hidden_blocks: Vec<BlockId>,
+    #[serde(deserialize_with = "deserialize_blocks")]
+    pub replacement_blocks: Vec<BlockId>,
+    pub max_block_height: usize,
+}
+
+impl AntiXray {
+    pub fn to_anti_xray(&self) -> Option<base::anti_xray::AntiXray> {
+        if !self.enabled {
+            return None;
+        }
+        Some(base::anti_xray::AntiXray {
+            mode: self.engine_mode,
+            hidden_blocks: self.hidden_blocks.clone(),
+            replacement_blocks: self.replacement_blocks.clone(),
+            max_block_height: self.max_block_height,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Plugins {
+    pub max_systems: usize,
+    pub max_tasks: usize,
+    pub tick_time_budget_ms: u64,
+    pub suspend_after_overruns: u32,
+    pub max_wasm_memory_mb: usize,
+}
+
+impl Plugins {
+    pub fn to_quotas(&self) -> PluginQuotas {
+        PluginQuotas {
+            max_systems: self.max_systems,
+            max_tasks: self.max_tasks,
+            tick_time_budget: Duration::from_millis(self.tick_time_budget_ms),
+            suspend_after_overruns: match self.suspend_after_overruns {
+                0 => None,
+                n => Some(n),
+            },
+            max_wasm_memory: self.max_wasm_memory_mb * 1024 * 1024,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -100,6 +455,19 @@ pub struct Proxy {
     pub velocity_secret: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Rcon {
+    pub enabled: bool,
+    pub port: u16,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Query {
+    pub enabled: bool,
+    pub port: u16,
+}
+
 #[derive(Debug, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ProxyMode {
@@ -120,6 +488,133 @@ fn deserialize_log_level<'de, D: Deserializer<'de>>(
     Ok(level)
 }
 
+fn deserialize_starter_kit<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<ItemStack>, D::Error> {
+    #[derive(Deserialize)]
+    struct KitItem {
+        item: String,
+        count: u32,
+    }
+
+    let items: Vec<KitItem> = Vec::deserialize(deserializer)?;
+    items
+        .into_iter()
+        .map(|kit_item| {
+            let item = Item::from_name(kit_item.item.trim_start_matches("minecraft:")).ok_or_else(
+                || serde::de::Error::custom(format!("unknown item '{}'", kit_item.item)),
+            )?;
+            if kit_item.count == 0 || kit_item.count > item.stack_size() {
+                return Err(serde::de::Error::custom(format!(
+                    "invalid count {} for {}: must be between 1 and {}",
+                    kit_item.count,
+                    kit_item.item,
+                    item.stack_size()
+                )));
+            }
+            Ok(ItemStack::new(item, kit_item.count))
+        })
+        .collect()
+}
+
+fn deserialize_blocks<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<BlockId>, D::Error> {
+    let names: Vec<String> = Vec::deserialize(deserializer)?;
+    if names.is_empty() {
+        return Err(serde::de::Error::custom("must list at least one block"));
+    }
+    names
+        .iter()
+        .map(|name| {
+            let identifier = if name.contains(':') {
+                name.clone()
+            } else {
+                format!("minecraft:{}", name)
+            };
+            BlockId::from_identifier(&identifier)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown block '{}'", name)))
+        })
+        .collect()
+}
+
+fn deserialize_first_spawn<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Position>, D::Error> {
+    let coordinates: Vec<f64> = Vec::deserialize(deserializer)?;
+    match coordinates.as_slice() {
+        [] => Ok(None),
+        &[x, y, z] => Ok(Some(position!(x, y, z))),
+        _ => Err(serde::de::Error::custom(
+            "first_spawn must be empty or [x, y, z]",
+        )),
+    }
+}
+
+/// Parses text as a JSON text component if it looks like
+/// JSON, or else as a string with legacy `§` formatting codes.
+pub(crate) fn parse_text(string: &str) -> anyhow::Result<Text> {
+    let trimmed = string.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        return serde_json::from_str(trimmed).context("invalid JSON text");
+    }
+    Ok(Text::from_legacy(string))
+}
+
+fn deserialize_motd<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Text, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    parse_text(&string).map_err(|e| serde::de::Error::custom(format!("{:#}", e)))
+}
+
+fn deserialize_join_motd<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Text>, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    if string.is_empty() {
+        return Ok(None);
+    }
+    parse_text(&string)
+        .map(Some)
+        .map_err(|e| serde::de::Error::custom(format!("{:#}", e)))
+}
+
+/// Validates an optional SHA-1 hash, normalizing it to lowercase.
+fn deserialize_sha1<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let hash = String::deserialize(deserializer)?;
+    if !hash.is_empty() && (hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit())) {
+        return Err(serde::de::Error::custom(
+            "expected a SHA-1 hash of 40 hexadecimal digits",
+        ));
+    }
+    Ok(hash.to_ascii_lowercase())
+}
+
+fn deserialize_fractional_digits<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<u8, D::Error> {
+    let digits = u8::deserialize(deserializer)?;
+    if digits > Currency::MAX_FRACTIONAL_DIGITS {
+        return Err(serde::de::Error::custom(format!(
+            "a currency can have at most {} fractional digits",
+            Currency::MAX_FRACTIONAL_DIGITS
+        )));
+    }
+    Ok(digits)
+}
+
+/// Validates a message template, like the join message. The
+/// placeholders are replaced when the message is sent.
+fn deserialize_message_template<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let template = String::deserialize(deserializer)?;
+    if template.is_empty() {
+        return Ok(None);
+    }
+    parse_text(&template).map_err(|e| serde::de::Error::custom(format!("{:#}", e)))?;
+    Ok(Some(template))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,4 +623,82 @@ mod tests {
     fn default_config_is_valid() {
         let _config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
     }
+
+    #[test]
+    fn parse_text_formats() {
+        assert_eq!(
+            parse_text(r#"{"text":"A Feather server","color":"gold"}"#)
+                .unwrap()
+                .to_legacy_string(),
+            "\u{a7}6A Feather server"
+        );
+        assert_eq!(
+            parse_text("\u{a7}6A Feather server").unwrap(),
+            Text::from_legacy("\u{a7}6A Feather server")
+        );
+        assert_eq!(parse_text("plain").unwrap(), Text::from("plain"));
+        assert!(parse_text("{not json").is_err());
+    }
+
+    #[test]
+    fn resource_pack_hash() {
+        let pack = |hash: &str| {
+            toml::from_str::<ResourcePack>(&format!(
+                "url = \"\"\nhash = \"{}\"\nrequired = false\nkick_message = \"\"",
+                hash
+            ))
+        };
+        assert_eq!(
+            pack("2AAE6C35C94FCFB415DBE95F408B9CE91EE846ED")
+                .unwrap()
+                .hash,
+            "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"
+        );
+        assert_eq!(pack("").unwrap().hash, "");
+        assert!(pack("not a hash").is_err());
+    }
+
+    #[test]
+    fn default_world_settings() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let settings = config.world.to_settings();
+        assert_eq!(settings.difficulty, Difficulty::Normal);
+        assert!(settings.pvp);
+        assert_eq!(settings.game_rules, GameRules::default());
+        assert_eq!(settings.anti_xray, None);
+    }
+
+    #[test]
+    fn anti_xray_blocks() {
+        let config: Config = toml::from_str(
+            &DEFAULT_CONFIG
+                .replace(
+                    "[world.anti_xray]\nenabled = false",
+                    "[world.anti_xray]\nenabled = true",
+                )
+                .replace(
+                    "replacement_blocks = [\"stone\"]",
+                    "replacement_blocks = [\"minecraft:stone\"]",
+                ),
+        )
+        .unwrap();
+        let anti_xray = config.world.to_settings().anti_xray.unwrap();
+        assert_eq!(anti_xray.replacement_blocks, vec![BlockId::stone()]);
+        assert!(anti_xray.hidden_blocks.contains(&BlockId::diamond_ore()));
+
+        let unknown = DEFAULT_CONFIG.replace("\"diamond_ore\"", "\"diamond_ores\"");
+        assert!(toml::from_str::<Config>(&unknown).is_err());
+    }
+
+    #[test]
+    fn economy_fractional_digits() {
+        let config = |digits: u8| {
+            toml::from_str::<Config>(&DEFAULT_CONFIG.replace(
+                "fractional_digits = 2",
+                &format!("fractional_digits = {}", digits),
+            ))
+        };
+        assert_eq!(config(19).unwrap().economy.fractional_digits, 19);
+        assert!(config(20).is_err());
+    }
 }
@@ -1,28 +1,99 @@
-use std::{fmt::Debug, io, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io::{self, Cursor},
+    net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use anyhow::anyhow;
 use base::Text;
-use flume::{Receiver, Sender};
+use common::disconnect_reason;
+use flume::{Receiver, Sender, TryRecvError};
 use futures_lite::FutureExt;
 use io::ErrorKind;
+use parking_lot::Mutex;
 use protocol::{
-    codec::CryptKey, packets::server::Disconnect, ClientPlayPacket, MinecraftCodec, Readable,
-    ServerPlayPacket, Writeable,
+    capture::Direction as CaptureDirection,
+    codec::CryptKey,
+    packets::server::{ChunkDataKind, Disconnect, DisconnectLogin, KeepAlive as KeepAlivePacket},
+    ClientPlayPacket, MinecraftCodec, ProtocolVersion, Readable, ServerLoginPacket,
+    ServerPlayPacket, StatePacket, VarInt, Writeable,
 };
+use thiserror::Error;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
-    net::{
-        tcp::{OwnedReadHalf, OwnedWriteHalf},
-        TcpStream,
-    },
-    time::timeout,
+    sync::OwnedSemaphorePermit,
+    task::{JoinError, JoinHandle},
+    time::{timeout, timeout_at, Instant as TokioInstant},
 };
+use tracing::{field, Instrument, Span};
+use utils::panic_message;
+use uuid::Uuid;
 
+use self::{
+    priority::PacketPriority,
+    rate_limit::{PacketRateLimiter, RateLimitExceeded},
+};
 use crate::{
-    initial_handler::{InitialHandling, NewPlayer},
+    chunk_packet_cache::ChunkPacketCache,
+    initial_handler::{
+        legacy_ping::{self, LegacyPing},
+        InitialHandling, InvalidTransition, NewPlayer, State, StateTimedOut,
+    },
+    io::{
+        capture::Capture,
+        sniffer::{Direction, Sniffer},
+    },
+    keep_alive::KeepAlive,
+    listener::{
+        shutdown::ShutdownSignal,
+        stream::{ReadHalf, Stream, WriteHalf},
+        throttle::ConnectionThrottle,
+    },
     options::Options,
     player_count::PlayerCount,
+    status_cache::StatusCache,
+    traffic::ConnectionTraffic,
 };
 
+mod priority;
+mod rate_limit;
+
+/// How long to wait for the disconnect packet
+/// to be written when the server shuts down.
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long to wait for the disconnect packet to be
+/// written when kicking a client which misbehaved.
+const KICK_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Number of buffered bytes after which the writer
+/// writes packets without waiting for a flush.
+/// Also the most low priority packet bytes written at once.
+const MAX_BUFFERED_BYTES: usize = 64 * 1024;
+
+/// A message sent to the task writing packets to a connection.
+#[derive(Debug)]
+pub enum WriterMessage {
+    /// Queues a packet. Queued packets are written together
+    /// on the next flush, except for those which aren't of
+    /// normal [`PacketPriority`].
+    SendPacket(ServerPlayPacket),
+    /// Queues a packet in the low priority lane regardless of
+    /// its kind, so that it is written after the chunks already
+    /// queued, e.g. a fake block which must not be overwritten
+    /// by its chunk, or the spawn position, which must not
+    /// reach the client before the chunks around it.
+    SendLowPriorityPacket(ServerPlayPacket),
+    /// Writes all queued packets to the connection.
+    Flush,
+    /// Writes all queued packets, then stops the writer.
+    Close,
+}
+
 /// Tokio task which handles a connection and processes
 /// packets.
 ///
@@ -31,53 +102,162 @@ use crate::{
 /// * Connection goes through initial handling, i.e., the handshake process.
 /// * If the connection was not a status ping, then the main server thread
 /// is notified of the new connection via a channel.
+///
+/// Everything logged while handling the connection is
+/// recorded in its `connection` span.
 pub struct Worker {
     reader: Reader,
     writer: Writer,
     options: Arc<Options>,
     player_count: PlayerCount,
-    packets_to_send_tx: Sender<ServerPlayPacket>,
+    status_cache: StatusCache,
+    /// Only packets sent in this state are read and written.
+    state: State,
+    /// When the connection times out if it
+    /// doesn't leave its current state.
+    state_deadline: Option<TokioInstant>,
+    keep_alive: KeepAlive,
+    traffic: ConnectionTraffic,
+    packets_to_send_tx: Sender<WriterMessage>,
     received_packets_rx: Receiver<ClientPlayPacket>,
     new_players: Sender<NewPlayer>,
+    shutdown: ShutdownSignal,
+    /// Shared with the listener, which only throttles
+    /// connections itself when there is no PROXY header.
+    throttle: Arc<Mutex<ConnectionThrottle>>,
+    /// Address of the client. Replaced by the address in
+    /// the PROXY protocol header if one is expected.
+    addr: SocketAddr,
+    capture: Option<Capture>,
+    span: Span,
 }
 
 impl Worker {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        stream: TcpStream,
-        _addr: SocketAddr,
+        stream: impl Stream,
+        addr: SocketAddr,
         options: Arc<Options>,
         player_count: PlayerCount,
+        status_cache: StatusCache,
+        traffic: ConnectionTraffic,
+        chunk_packets: ChunkPacketCache,
         new_players: Sender<NewPlayer>,
+        shutdown: ShutdownSignal,
+        throttle: Arc<Mutex<ConnectionThrottle>>,
     ) -> Self {
         let (reader, writer) = stream.into_split();
 
         let (received_packets_tx, received_packets_rx) = flume::bounded(32);
         let (packets_to_send_tx, packets_to_send_rx) = flume::unbounded();
-        let reader = Reader::new(reader, received_packets_tx);
-        let writer = Writer::new(writer, packets_to_send_rx);
+        let keep_alive = KeepAlive::new();
+        let rate_limiter = PacketRateLimiter::new(
+            options.max_movement_packets_per_second,
+            options.max_packets_per_second,
+        );
+        let sniffer = |direction| {
+            options.sniffer.clone().map(|sniffer_options| {
+                Sniffer::new(Arc::new(sniffer_options), direction, addr.to_string())
+            })
+        };
+        let capture =
+            options
+                .capture_dir
+                .as_ref()
+                .and_then(|dir| match Capture::create(dir, addr) {
+                    Ok(capture) => Some(capture),
+                    Err(e) => {
+                        log::warn!("Failed to capture packets from {}: {:?}", addr, e);
+                        None
+                    }
+                });
+        let reader = Reader::new(
+            reader,
+            received_packets_tx,
+            keep_alive.clone(),
+            rate_limiter,
+            sniffer(Direction::Inbound),
+            capture.clone(),
+            traffic.clone(),
+        );
+        let writer = Writer::new(
+            writer,
+            packets_to_send_rx,
+            sniffer(Direction::Outbound),
+            capture.clone(),
+            traffic.clone(),
+            chunk_packets,
+        );
+        let state_deadline = Some(TokioInstant::now() + options.handshake_timeout);
+        // Debug level, so that status pings don't log
+        // a line at info level when their span closes.
+        let span = tracing::debug_span!("connection", %addr, forwarded_for = field::Empty);
 
         Self {
             reader,
             writer,
             options,
             player_count,
+            status_cache,
+            state: State::Handshake,
+            state_deadline,
+            keep_alive,
+            traffic,
             packets_to_send_tx,
             received_packets_rx,
             new_players,
+            shutdown,
+            throttle,
+            addr,
+            capture,
+            span,
         }
     }
 
-    pub fn start(self) {
-        tokio::task::spawn(async move {
-            self.run().await;
-        });
+    /// Starts handling the connection. `handshake_permit`
+    /// is held until initial handling completes.
+    pub fn start(self, handshake_permit: OwnedSemaphorePermit) {
+        let span = self.span.clone();
+        tokio::task::spawn(
+            async move {
+                // A panic only closes this connection.
+                if let Err(payload) = AssertUnwindSafe(self.run(handshake_permit))
+                    .catch_unwind()
+                    .await
+                {
+                    tracing::error!(
+                        "Connection panicked during initial handling: {}",
+                        panic_message(&*payload)
+                    );
+                }
+            }
+            .instrument(span),
+        );
     }
 
-    async fn run(mut self) {
-        let result = crate::initial_handler::handle(&mut self).await;
+    async fn run(mut self, handshake_permit: OwnedSemaphorePermit) {
+        let mut shutdown = self.shutdown.clone();
+        let result = tokio::select! {
+            result = crate::initial_handler::handle(&mut self) => result,
+            _ = shutdown.triggered() => return,
+        };
+        drop(handshake_permit);
         match result {
             Ok(result) => self.proceed(result).await,
-            Err(e) => log::debug!("Initial handling failed: {:?}", e),
+            Err(e) => {
+                if let Some(timed_out) = e.downcast_ref::<StateTimedOut>() {
+                    tracing::debug!("Disconnecting: {}", timed_out);
+                    if matches!(timed_out.state, State::Login | State::EncryptionPending) {
+                        self.write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+                            reason: Text::from("Took too long to log in"),
+                        }))
+                        .await
+                        .ok();
+                    }
+                    return;
+                }
+                tracing::debug!("Initial handling failed: {:?}", e)
+            }
         }
     }
 
@@ -85,9 +265,9 @@ impl Worker {
         match result {
             InitialHandling::Disconnect => (),
             InitialHandling::Join(new_player) => {
-                if self.player_count.try_add_player().is_err() {
+                if self.player_count.try_add_player(new_player.uuid).is_err() {
                     self.write(ServerPlayPacket::Disconnect(Disconnect {
-                        reason: Text::from("The server is full!").to_string(),
+                        reason: Text::from("The server is full!"),
                     }))
                     .await
                     .ok();
@@ -95,8 +275,9 @@ impl Worker {
                 }
 
                 let username = new_player.username.clone();
+                let uuid = new_player.uuid;
                 let _ = self.new_players.send_async(new_player).await;
-                self.split(username);
+                self.split(username, uuid);
             }
         }
     }
@@ -109,78 +290,401 @@ impl Worker {
         self.player_count.get()
     }
 
-    #[allow(unused)]
+    pub fn status_cache(&self) -> &StatusCache {
+        &self.status_cache
+    }
+
+    /// Gets the address of the client.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Records a connection attempt from the client's
+    /// address, returning whether it should be accepted.
+    pub fn allow_connection(&self) -> bool {
+        self.throttle.lock().allow(self.addr.ip(), Instant::now())
+    }
+
+    /// Sets the address of the client, e.g. to the
+    /// address forwarded by a load balancer.
+    pub fn set_addr(&mut self, addr: SocketAddr) {
+        self.addr = addr;
+        self.span.record("forwarded_for", &field::display(addr));
+        self.set_sniffer_connection(addr.to_string());
+    }
+
+    fn set_sniffer_connection(&mut self, connection: String) {
+        if let Some(sniffer) = &mut self.reader.sniffer {
+            sniffer.set_connection(connection.clone());
+        }
+        if let Some(sniffer) = &mut self.writer.sniffer {
+            sniffer.set_connection(connection);
+        }
+    }
+
+    /// Moves the connection to the next state of initial handling,
+    /// restarting the timeout for the new state.
+    pub fn transition(&mut self, next: State) -> Result<(), InvalidTransition> {
+        self.state = self.state.transition(next)?;
+        if next == State::Play {
+            self.enable_translation();
+        }
+        if let Some(capture) = &self.capture {
+            capture.set_state(next);
+        }
+        self.state_deadline = self
+            .state_timeout()
+            .map(|timeout| TokioInstant::now() + timeout);
+        tracing::trace!("Connection entered state {:?}", next);
+        Ok(())
+    }
+
+    /// Returns how long the connection may stay in its current state.
+    /// Connections in `Play` are covered by keep alives instead.
+    fn state_timeout(&self) -> Option<Duration> {
+        match self.state {
+            State::Handshake | State::Status => Some(self.options.handshake_timeout),
+            State::Login => Some(self.options.login_timeout),
+            State::EncryptionPending => Some(self.options.encryption_timeout),
+            State::Play => None,
+        }
+    }
+
+    /// Runs `future`, failing with [`StateTimedOut`] if
+    /// the current state's deadline passes first.
+    async fn before_deadline<T>(
+        state: State,
+        deadline: Option<TokioInstant>,
+        future: impl std::future::Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        match deadline {
+            Some(deadline) => timeout_at(deadline, future)
+                .await
+                .map_err(|_| StateTimedOut { state })?,
+            None => future.await,
+        }
+    }
+
+    /// Sets the protocol version used to encode and decode packets.
+    pub fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        self.reader.codec.set_version(version);
+        self.writer.codec.set_version(version);
+
+        tracing::debug!("Using protocol version {:?}", version);
+    }
+
+    /// Starts translating Play packets if the client's
+    /// protocol version is not supported natively.
+    fn enable_translation(&mut self) {
+        self.reader.codec.enable_translation();
+        if self.writer.codec.enable_translation() {
+            tracing::debug!(
+                "Translating packets for protocol version {:?}",
+                self.writer.codec.version()
+            );
+        }
+    }
+
     pub fn enable_compression(&mut self, threshold: usize) {
         self.reader.codec.enable_compression(threshold);
         self.writer.codec.enable_compression(threshold);
+        self.writer
+            .codec
+            .set_compression_level(self.options.compression_level);
 
-        log::debug!("Enabled compression");
+        tracing::debug!("Enabled compression");
     }
 
     pub fn enable_encryption(&mut self, key: CryptKey) {
         self.reader.codec.enable_encryption(key);
         self.writer.codec.enable_encryption(key);
 
-        log::debug!("Enabled encryption");
+        tracing::debug!("Enabled encryption");
     }
 
-    pub async fn read<P: Readable>(&mut self) -> anyhow::Result<P> {
-        self.reader.read().await
+    /// Reads a packet, failing with [`WrongState`](crate::initial_handler::WrongState) if
+    /// `P` isn't sent in the connection's current state, which is a bug
+    /// in the caller.
+    pub async fn read<P: Readable + StatePacket + Debug>(&mut self) -> anyhow::Result<P> {
+        self.state.check_packet(P::STATE)?;
+        Self::before_deadline(self.state, self.state_deadline, self.reader.read()).await
     }
 
-    pub async fn write(&mut self, packet: impl Writeable + Debug) -> anyhow::Result<()> {
+    /// Receives the first bytes of the connection without consuming
+    /// them, until they tell a legacy ping from a modern handshake.
+    /// Returns `None` for a handshake.
+    ///
+    /// Only meaningful before the first packet is read.
+    pub async fn read_legacy_ping(&mut self) -> anyhow::Result<Option<LegacyPing>> {
+        Self::before_deadline(
+            self.state,
+            self.state_deadline,
+            self.reader.read_legacy_ping(),
+        )
+        .await
+    }
+
+    /// Reads exactly `len` bytes from the stream, bypassing the codec.
+    ///
+    /// Only meaningful before the first packet is read.
+    pub async fn read_raw(&mut self, len: usize) -> anyhow::Result<Vec<u8>> {
+        Self::before_deadline(self.state, self.state_deadline, self.reader.read_raw(len)).await
+    }
+
+    /// Writes a packet, failing with [`WrongState`](crate::initial_handler::WrongState) if
+    /// `P` isn't sent in the connection's current state, which is a bug
+    /// in the caller.
+    pub async fn write<P: Writeable + StatePacket + Debug>(
+        &mut self,
+        packet: P,
+    ) -> anyhow::Result<()> {
+        self.state.check_packet(P::STATE)?;
         self.writer.write(packet).await
     }
 
-    pub fn split(self, username: String) {
+    /// Writes raw bytes to the stream, bypassing the codec.
+    pub async fn write_raw(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.writer.write_raw(bytes).await
+    }
+
+    /// Splits the connection into reader, writer and
+    /// keep-alive tasks. When any of them stops, the others
+    /// are aborted and the connection is closed.
+    ///
+    /// The tasks run in a `play` span, which closes
+    /// when the player disconnects.
+    pub fn split(mut self, username: String, uuid: Uuid) {
+        self.set_sniffer_connection(format!("{} ({})", username, self.addr));
+        let span = tracing::info_span!(parent: &self.span, "play", %username, addr = %self.addr);
         let Self {
             reader,
             writer,
             player_count,
+            keep_alive,
+            traffic,
+            packets_to_send_tx,
+            mut shutdown,
             ..
         } = self;
-        let reader = tokio::task::spawn(async move { reader.run().await });
-        let writer = tokio::task::spawn(async move { writer.run().await });
-
-        tokio::task::spawn(async move {
-            let result = reader.race(writer).await.expect("task panicked");
+        let packets_to_send = packets_to_send_tx.clone();
+        let mut reader = tokio::task::spawn(reader.run().instrument(span.clone()));
+        let mut writer = tokio::task::spawn(writer.run().instrument(span.clone()));
+        let mut keep_alive = tokio::task::spawn(
+            send_keep_alives(keep_alive, packets_to_send_tx).instrument(span.clone()),
+        );
+
+        let supervisor = async move {
+            let result = tokio::select! {
+                result = &mut reader => {
+                    let result = task_result(result);
+                    if let Err(e) = &result {
+                        if let Some(reason) = kick_reason(e) {
+                            close(&packets_to_send, &mut writer, Text::from(reason), KICK_FLUSH_TIMEOUT).await;
+                        }
+                    }
+                    result
+                }
+                result = (&mut writer).race(&mut keep_alive) => task_result(result),
+                _ = shutdown.triggered() => {
+                    let reason = disconnect_reason::server_closed();
+                    close(&packets_to_send, &mut writer, reason, SHUTDOWN_FLUSH_TIMEOUT).await;
+                    Ok(())
+                }
+            };
+            reader.abort();
+            writer.abort();
+            keep_alive.abort();
             if let Err(e) = result {
-                let message = disconnected_message(e);
-                log::debug!("{} lost connection: {}", username, message);
+                if kick_reason(&e).is_some() || e.is::<TaskPanicked>() {
+                    tracing::warn!("Disconnected {}: {:#}", username, e);
+                } else {
+                    let message = disconnected_message(e);
+                    tracing::debug!("{} lost connection: {}", username, message);
+                }
             }
-            player_count.remove_player();
-        });
+            let traffic = traffic.get();
+            tracing::debug!(
+                "{} sent {} packets ({} bytes) and received {} packets ({} bytes)",
+                username,
+                traffic.packets_in,
+                traffic.bytes_in,
+                traffic.packets_out,
+                traffic.bytes_out
+            );
+            player_count.remove_player(uuid);
+            // `shutdown` is dropped here, letting
+            // the server know this connection is closed.
+            drop(shutdown);
+        };
+        tokio::task::spawn(supervisor.instrument(span));
     }
 
-    pub fn packets_to_send(&self) -> Sender<ServerPlayPacket> {
+    pub fn packets_to_send(&self) -> Sender<WriterMessage> {
         self.packets_to_send_tx.clone()
     }
 
     pub fn received_packets(&self) -> Receiver<ClientPlayPacket> {
         self.received_packets_rx.clone()
     }
+
+    pub fn keep_alive(&self) -> KeepAlive {
+        self.keep_alive.clone()
+    }
+
+    pub fn traffic(&self) -> ConnectionTraffic {
+        self.traffic.clone()
+    }
+}
+
+/// Sends `reason` to the client in a Disconnect packet,
+/// then waits up to `flush_timeout` for the writer to stop.
+async fn close(
+    packets_to_send: &Sender<WriterMessage>,
+    writer: &mut JoinHandle<anyhow::Result<()>>,
+    reason: Text,
+    flush_timeout: Duration,
+) {
+    let _ = packets_to_send.send(WriterMessage::SendPacket(ServerPlayPacket::Disconnect(
+        Disconnect { reason },
+    )));
+    let _ = packets_to_send.send(WriterMessage::Close);
+    let _ = timeout(flush_timeout, writer).await;
+}
+
+/// Error returned when a connection task panicked.
+#[derive(Debug, Error)]
+#[error("connection task panicked: {0}")]
+struct TaskPanicked(String);
+
+/// Error returned when a client sends a packet which can't be decoded.
+#[derive(Debug, Error)]
+#[error("sent an invalid packet {} ({length} bytes): {reason}", describe_id(.id))]
+pub struct InvalidPacket {
+    /// The packet ID, if the packet is long enough to contain one.
+    id: Option<i32>,
+    length: usize,
+    reason: String,
+}
+
+fn describe_id(id: &Option<i32>) -> String {
+    match id {
+        Some(id) => format!("{:#04x}", id),
+        None => "without an ID".to_owned(),
+    }
+}
+
+/// Gets the result of a connection task. Panics are
+/// turned into errors so the connection is still cleaned up.
+fn task_result(result: Result<anyhow::Result<()>, JoinError>) -> anyhow::Result<()> {
+    match result {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => Err(TaskPanicked(panic_message(&*e.into_panic())).into()),
+        Err(e) => Err(anyhow!(e)),
+    }
+}
+
+/// Gets the message a client is kicked with after its reader
+/// failed with `error`, or `None` if the connection was lost.
+fn kick_reason(error: &anyhow::Error) -> Option<&'static str> {
+    if error.is::<InvalidPacket>() {
+        Some("Received an invalid packet")
+    } else if error.is::<RateLimitExceeded>() {
+        Some("Sent too many packets")
+    } else {
+        None
+    }
+}
+
+/// Decodes a packet. Panics while decoding, e.g. on malformed
+/// NBT, are returned as errors so that only the connection
+/// sending the packet is affected.
+fn decode<P: Readable>(frame: &[u8], version: ProtocolVersion) -> Result<P, InvalidPacket> {
+    let reason = match panic::catch_unwind(|| P::read(&mut Cursor::new(frame), version)) {
+        Ok(Ok(packet)) => return Ok(packet),
+        Ok(Err(e)) => format!("{:#}", e),
+        Err(payload) => format!("decoding panicked: {}", panic_message(&*payload)),
+    };
+    Err(InvalidPacket {
+        id: VarInt::read(&mut Cursor::new(frame), version)
+            .ok()
+            .map(|id| id.0),
+        length: frame.len(),
+        reason,
+    })
+}
+
+/// Sends Keep Alive packets to the client until
+/// it fails to reply in time.
+async fn send_keep_alives(
+    keep_alive: KeepAlive,
+    packets_to_send: Sender<WriterMessage>,
+) -> anyhow::Result<()> {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        interval.tick().await;
+        if let Some(id) = keep_alive.poll(Instant::now())? {
+            let packet = ServerPlayPacket::KeepAlive(KeepAlivePacket { id });
+            if packets_to_send
+                .send(WriterMessage::SendPacket(packet))
+                .is_err()
+            {
+                return Ok(());
+            }
+        }
+    }
 }
 
 struct Reader {
-    stream: OwnedReadHalf,
+    stream: ReadHalf,
     codec: MinecraftCodec,
     buffer: [u8; 512],
+    /// Bytes which were peeked but not yet passed to the codec.
+    peeked: Vec<u8>,
     received_packets: Sender<ClientPlayPacket>,
+    keep_alive: KeepAlive,
+    rate_limiter: PacketRateLimiter,
+    sniffer: Option<Sniffer>,
+    capture: Option<Capture>,
+    traffic: ConnectionTraffic,
 }
 
 impl Reader {
-    pub fn new(stream: OwnedReadHalf, received_packets: Sender<ClientPlayPacket>) -> Self {
+    pub fn new(
+        stream: ReadHalf,
+        received_packets: Sender<ClientPlayPacket>,
+        keep_alive: KeepAlive,
+        rate_limiter: PacketRateLimiter,
+        sniffer: Option<Sniffer>,
+        capture: Option<Capture>,
+        traffic: ConnectionTraffic,
+    ) -> Self {
         Self {
             stream,
             codec: MinecraftCodec::new(),
             buffer: [0; 512],
+            peeked: Vec::new(),
             received_packets,
+            keep_alive,
+            rate_limiter,
+            sniffer,
+            capture,
+            traffic,
         }
     }
 
     pub async fn run(mut self) -> anyhow::Result<()> {
         loop {
             let packet = self.read::<ClientPlayPacket>().await?;
+            // Checked before the packet is queued, so a flooding
+            // client can't fill up the main thread's channel.
+            self.rate_limiter.check(&packet, Instant::now())?;
+            // Keep Alive replies are handled here rather than on the
+            // main thread so that latency isn't inflated by tick timing.
+            if let ClientPlayPacket::KeepAlive(reply) = &packet {
+                self.keep_alive.on_reply(reply.id as i64, Instant::now());
+                continue;
+            }
+
             let result = self.received_packets.send_async(packet).await;
             if result.is_err() {
                 // server dropped connection
@@ -189,53 +693,241 @@ impl Reader {
         }
     }
 
-    pub async fn read<P: Readable>(&mut self) -> anyhow::Result<P> {
+    pub async fn read<P: Readable + Debug>(&mut self) -> anyhow::Result<P> {
+        if !self.peeked.is_empty() {
+            self.codec.accept(&self.peeked);
+            self.peeked.clear();
+        }
+
         // Keep reading bytes and trying to get the packet.
         loop {
-            if let Some(packet) = self.codec.next_packet::<P>()? {
+            if let Some(frame) = self.codec.next_frame()? {
+                let _span = tracing::trace_span!("packet", length = frame.len()).entered();
+                let packet = decode::<P>(&frame, self.codec.version())?;
+                self.traffic.add_packet_in();
+                if let Some(sniffer) = &self.sniffer {
+                    if let Some(name) = sniffer.filter(&packet) {
+                        sniffer.log(&name, &frame);
+                    }
+                }
+                if let Some(capture) = &self.capture {
+                    capture.record(CaptureDirection::Serverbound, &frame);
+                }
                 return Ok(packet);
             }
 
-            let duration = Duration::from_secs(10);
-            let read_bytes = timeout(duration, self.stream.read(&mut self.buffer)).await??;
-            if read_bytes == 0 {
-                return Err(io::Error::new(ErrorKind::UnexpectedEof, "read 0 bytes").into());
-            }
-
+            let read_bytes = self.read_bytes().await?;
             let bytes = &self.buffer[..read_bytes];
             self.codec.accept(bytes);
         }
     }
+
+    pub async fn read_legacy_ping(&mut self) -> anyhow::Result<Option<LegacyPing>> {
+        let received = self.peeked.len();
+        let ping = timeout(
+            Duration::from_secs(10),
+            legacy_ping::read_greeting(&mut self.stream, &mut self.peeked),
+        )
+        .await?;
+        self.traffic.add_bytes_in(self.peeked.len() - received);
+        Ok(ping?)
+    }
+
+    pub async fn read_raw(&mut self, len: usize) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(len);
+        while bytes.len() < len {
+            if self.peeked.is_empty() {
+                let read_bytes = self.read_bytes().await?;
+                self.peeked.extend_from_slice(&self.buffer[..read_bytes]);
+            }
+            let take = self.peeked.len().min(len - bytes.len());
+            bytes.extend(self.peeked.drain(..take));
+        }
+        Ok(bytes)
+    }
+
+    /// Reads the next bytes from the stream into `self.buffer`,
+    /// returning the number of bytes read.
+    async fn read_bytes(&mut self) -> anyhow::Result<usize> {
+        let duration = Duration::from_secs(10);
+        let read_bytes = timeout(duration, self.stream.read(&mut self.buffer)).await??;
+        if read_bytes == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "read 0 bytes").into());
+        }
+        self.traffic.add_bytes_in(read_bytes);
+        Ok(read_bytes)
+    }
 }
 
 struct Writer {
-    stream: OwnedWriteHalf,
+    stream: WriteHalf,
     codec: MinecraftCodec,
-    packets_to_send: Receiver<ServerPlayPacket>,
+    messages: Receiver<WriterMessage>,
+    /// Encoded packets which haven't been written yet.
     buffer: Vec<u8>,
+    /// Low priority packets which haven't been encoded yet.
+    low_priority: VecDeque<ServerPlayPacket>,
+    sniffer: Option<Sniffer>,
+    capture: Option<Capture>,
+    traffic: ConnectionTraffic,
+    chunk_packets: ChunkPacketCache,
 }
 
 impl Writer {
-    pub fn new(stream: OwnedWriteHalf, packets_to_send: Receiver<ServerPlayPacket>) -> Self {
+    pub fn new(
+        stream: WriteHalf,
+        messages: Receiver<WriterMessage>,
+        sniffer: Option<Sniffer>,
+        capture: Option<Capture>,
+        traffic: ConnectionTraffic,
+        chunk_packets: ChunkPacketCache,
+    ) -> Self {
         Self {
             stream,
             codec: MinecraftCodec::new(),
-            packets_to_send,
+            messages,
             buffer: Vec::new(),
+            low_priority: VecDeque::new(),
+            sniffer,
+            capture,
+            traffic,
+            chunk_packets,
         }
     }
 
+    /// Coalesces the packets queued between flushes
+    /// into a single write. High priority packets, like
+    /// keep alives, are written immediately so that they
+    /// aren't delayed.
+    ///
+    /// Low priority packets are written in batches whenever
+    /// no messages are waiting, and are dropped on close.
     pub async fn run(mut self) -> anyhow::Result<()> {
-        while let Ok(packet) = self.packets_to_send.recv_async().await {
-            self.write(packet).await?;
+        loop {
+            let message = if self.low_priority.is_empty() {
+                match self.messages.recv_async().await {
+                    Ok(message) => message,
+                    Err(_) => break,
+                }
+            } else {
+                match self.messages.try_recv() {
+                    Ok(message) => message,
+                    Err(TryRecvError::Empty) => {
+                        self.write_low_priority().await?;
+                        continue;
+                    }
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            };
+
+            match message {
+                WriterMessage::SendPacket(packet) => match PacketPriority::of(&packet) {
+                    PacketPriority::Low => self.low_priority.push_back(packet),
+                    PacketPriority::Normal => {
+                        self.encode(&packet);
+                        if self.buffer.len() >= MAX_BUFFERED_BYTES {
+                            self.flush().await?;
+                        }
+                    }
+                    PacketPriority::High => {
+                        self.encode(&packet);
+                        self.flush().await?;
+                    }
+                },
+                WriterMessage::SendLowPriorityPacket(packet) => self.low_priority.push_back(packet),
+                WriterMessage::Flush => self.flush().await?,
+                WriterMessage::Close => break,
+            }
+        }
+        self.flush().await
+    }
+
+    /// Writes up to `MAX_BUFFERED_BYTES` of low priority packets,
+    /// so that new messages are checked between writes.
+    async fn write_low_priority(&mut self) -> anyhow::Result<()> {
+        while self.buffer.len() < MAX_BUFFERED_BYTES {
+            match self.low_priority.pop_front() {
+                Some(packet) => self.encode_low_priority(packet),
+                None => break,
+            }
+        }
+        self.flush().await
+    }
+
+    /// Encodes a low priority packet, reusing the
+    /// cached frame of Chunk Data packets loading a chunk.
+    fn encode_low_priority(&mut self, packet: ServerPlayPacket) {
+        // Sniffed and captured packets need to be encoded anyway.
+        let sniffed = self
+            .sniffer
+            .as_ref()
+            .map_or(false, |sniffer| sniffer.filter(&packet).is_some());
+        let use_cache = !sniffed && self.capture.is_none();
+        match &packet {
+            ServerPlayPacket::ChunkData(chunk_data)
+                if use_cache && matches!(chunk_data.kind, ChunkDataKind::LoadChunk) =>
+            {
+                let codec = &mut self.codec;
+                let frame = self.chunk_packets.get_or_encode(
+                    &chunk_data.chunk,
+                    codec.version(),
+                    codec.compression(),
+                    || {
+                        let mut frame = Vec::new();
+                        codec.encode_frame(&packet, &mut frame);
+                        frame
+                    },
+                );
+                self.codec.write_frame(&frame, &mut self.buffer);
+                self.traffic.add_packet_out();
+            }
+            _ => self.encode(&packet),
         }
-        Ok(())
     }
 
     pub async fn write(&mut self, packet: impl Writeable + Debug) -> anyhow::Result<()> {
-        self.codec.encode(&packet, &mut self.buffer);
-        self.stream.write_all(&self.buffer).await?;
-        self.buffer.clear();
+        self.encode(&packet);
+        self.flush().await
+    }
+
+    fn encode(&mut self, packet: &(impl Writeable + Debug)) {
+        let sniffed = self
+            .sniffer
+            .as_ref()
+            .and_then(|sniffer| sniffer.filter(packet));
+        if sniffed.is_some() || self.capture.is_some() {
+            // Encode the packet once for the sniffer,
+            // the capture and the connection.
+            let mut data = Vec::new();
+            packet.write(&mut data, self.codec.version());
+            if let (Some(sniffer), Some(name)) = (&self.sniffer, sniffed) {
+                sniffer.log(&name, &data);
+            }
+            if let Some(capture) = &self.capture {
+                capture.record(CaptureDirection::Clientbound, &data);
+            }
+            self.codec.encode_data(&data, &mut self.buffer);
+        } else {
+            self.codec.encode(packet, &mut self.buffer);
+        }
+        self.traffic.add_packet_out();
+    }
+
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        if !self.buffer.is_empty() {
+            self.stream.write_all(&self.buffer).await?;
+            self.traffic.add_bytes_out(self.buffer.len());
+            self.buffer.clear();
+        }
+        if let Some(capture) = &self.capture {
+            capture.flush();
+        }
+        Ok(())
+    }
+
+    pub async fn write_raw(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        self.stream.write_all(bytes).await?;
+        self.traffic.add_bytes_out(bytes.len());
         Ok(())
     }
 }
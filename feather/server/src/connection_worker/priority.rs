@@ -0,0 +1,60 @@
+use protocol::ServerPlayPacket;
+
+/// Determines when the writer sends a packet.
+///
+/// Bulk chunk data goes in the low priority lane, so that
+/// on a slow link it drains in the background instead of
+/// holding back keep alives and position syncs queued after it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PacketPriority {
+    /// Written only when no other packets are waiting.
+    Low,
+    /// Written in order on the next flush.
+    Normal,
+    /// Written in order, immediately.
+    High,
+}
+
+impl PacketPriority {
+    pub fn of(packet: &ServerPlayPacket) -> Self {
+        match packet {
+            ServerPlayPacket::KeepAlive(_) | ServerPlayPacket::Disconnect(_) => {
+                PacketPriority::High
+            }
+            // Chunks are unloaded in the same lane they're loaded in
+            // so that an unload can't overtake its chunk. Block changes
+            // can overtake chunk data, which is encoded from the
+            // chunk's current state when it's written.
+            ServerPlayPacket::ChunkData(_)
+            | ServerPlayPacket::UpdateLight(_)
+            | ServerPlayPacket::UnloadChunk(_) => PacketPriority::Low,
+            // Teleports and position syncs stay in the normal lane,
+            // which is already ahead of chunk data. A higher lane
+            // would let a teleport overtake the Respawn before it.
+            _ => PacketPriority::Normal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use protocol::packets::server::{KeepAlive, TimeUpdate, UnloadChunk};
+
+    use super::*;
+
+    #[test]
+    fn lanes() {
+        let keep_alive = ServerPlayPacket::KeepAlive(KeepAlive { id: 1 });
+        let unload = ServerPlayPacket::UnloadChunk(UnloadChunk {
+            chunk_x: 0,
+            chunk_z: 0,
+        });
+        let time = ServerPlayPacket::TimeUpdate(TimeUpdate {
+            world_age: 0,
+            time_of_day: 0,
+        });
+        assert_eq!(PacketPriority::of(&keep_alive), PacketPriority::High);
+        assert_eq!(PacketPriority::of(&unload), PacketPriority::Low);
+        assert_eq!(PacketPriority::of(&time), PacketPriority::Normal);
+    }
+}
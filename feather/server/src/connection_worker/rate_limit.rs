@@ -0,0 +1,143 @@
+use std::time::Instant;
+
+use protocol::ClientPlayPacket;
+use thiserror::Error;
+
+/// Number of seconds' worth of packets a client may
+/// send in a burst, e.g. when catching up after lag.
+const BURST_SECONDS: f64 = 2.;
+
+#[derive(Debug, Error)]
+#[error("sent more than {limit} {kind} packets per second")]
+pub struct RateLimitExceeded {
+    kind: &'static str,
+    limit: u32,
+}
+
+/// Limits the rate at which a client may send packets.
+///
+/// Movement packets, which clients send every tick,
+/// have a separate budget from all other packets.
+pub struct PacketRateLimiter {
+    movement: TokenBucket,
+    other: TokenBucket,
+}
+
+impl PacketRateLimiter {
+    /// Creates a limiter. A rate of zero disables
+    /// the limit for that kind of packet.
+    pub fn new(movement_per_second: u32, other_per_second: u32) -> Self {
+        Self {
+            movement: TokenBucket::new(movement_per_second),
+            other: TokenBucket::new(other_per_second),
+        }
+    }
+
+    /// Records that `packet` was received, returning an error
+    /// if the client exceeded its budget.
+    pub fn check(
+        &mut self,
+        packet: &ClientPlayPacket,
+        now: Instant,
+    ) -> Result<(), RateLimitExceeded> {
+        let (bucket, kind) = if is_movement(packet) {
+            (&mut self.movement, "movement")
+        } else {
+            (&mut self.other, "non-movement")
+        };
+
+        if bucket.take(now) {
+            Ok(())
+        } else {
+            Err(RateLimitExceeded {
+                kind,
+                limit: bucket.per_second,
+            })
+        }
+    }
+}
+
+fn is_movement(packet: &ClientPlayPacket) -> bool {
+    matches!(
+        packet,
+        ClientPlayPacket::PlayerPosition(_)
+            | ClientPlayPacket::PlayerPositionAndRotation(_)
+            | ClientPlayPacket::PlayerRotation(_)
+            | ClientPlayPacket::PlayerMovement(_)
+            | ClientPlayPacket::VehicleMove(_)
+            | ClientPlayPacket::SteerVehicle(_)
+            | ClientPlayPacket::SteerBoat(_)
+    )
+}
+
+struct TokenBucket {
+    per_second: u32,
+    tokens: f64,
+    last_refill: Option<Instant>,
+}
+
+impl TokenBucket {
+    fn new(per_second: u32) -> Self {
+        Self {
+            per_second,
+            tokens: capacity(per_second),
+            last_refill: None,
+        }
+    }
+
+    fn take(&mut self, now: Instant) -> bool {
+        if self.per_second == 0 {
+            return true;
+        }
+
+        if let Some(last_refill) = self.last_refill {
+            let elapsed = now.saturating_duration_since(last_refill).as_secs_f64();
+            self.tokens =
+                (self.tokens + elapsed * self.per_second as f64).min(capacity(self.per_second));
+        }
+        self.last_refill = Some(now);
+
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn capacity(per_second: u32) -> f64 {
+    per_second as f64 * BURST_SECONDS
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn allows_burst_then_refills() {
+        let mut bucket = TokenBucket::new(10);
+        let now = Instant::now();
+        for _ in 0..20 {
+            assert!(bucket.take(now));
+        }
+        assert!(!bucket.take(now));
+
+        let later = now + Duration::from_millis(500);
+        for _ in 0..5 {
+            assert!(bucket.take(later));
+        }
+        assert!(!bucket.take(later));
+    }
+
+    #[test]
+    fn zero_rate_is_unlimited() {
+        let mut bucket = TokenBucket::new(0);
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert!(bucket.take(now));
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! The default [economy providers](common::economy), which keep
+//! balances in `economy.json`, or in the `economy` table of the
+//! database if one is configured.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use common::{
+    economy::{Economy, EconomyProvider},
+    Game,
+};
+use flume::{Receiver, RecvTimeoutError, Sender};
+use quill_common::economy::{Currency, EconomyError};
+use uuid::Uuid;
+
+use crate::{
+    storage::{self, Backend, Storage, Write},
+    Options,
+};
+
+/// Path of the balances file, relative to the working directory.
+pub const ECONOMY_PATH: &str = "economy.json";
+
+/// Table storing the balances in a database, keyed by UUID.
+const TABLE: &str = "economy";
+
+/// Registers the database provider if a database is
+/// configured, and the flat file provider otherwise.
+///
+/// # Panics
+/// Panics if the balances can't be loaded from the database,
+/// since transactions would then overwrite them.
+pub fn register(game: &mut Game, options: &Options) {
+    let currency = options.economy_currency.clone();
+    let starting_balance = options.economy_starting_balance;
+    let stored_balances = match game.resources.get::<Storage>() {
+        Ok(storage) => {
+            Some(load_balances(&storage).expect("failed to load the balances from the database"))
+        }
+        Err(_) => None,
+    };
+
+    let mut economy = game
+        .resources
+        .get_mut::<Economy>()
+        .expect("common must be registered before the server");
+    match stored_balances {
+        Some(balances) => economy.register_provider(StorageEconomy {
+            currency,
+            starting_balance,
+            balances,
+        }),
+        None => {
+            let path = Path::new(ECONOMY_PATH);
+            let balances =
+                storage::load_or_move_aside(path, || read_balances(path)).unwrap_or_default();
+            let provider = FlatFileEconomy::new(
+                path,
+                balances,
+                currency,
+                starting_balance,
+                options.storage.batch_window,
+            )
+            .expect("failed to start the economy writer");
+            economy.register_provider(provider);
+        }
+    }
+}
+
+/// Reads the balances saved at `path`.
+/// A missing file results in no accounts.
+pub fn read_balances(path: &Path) -> anyhow::Result<BTreeMap<Uuid, i64>> {
+    match fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn load_balances(storage: &Storage) -> anyhow::Result<BTreeMap<Uuid, i64>> {
+    storage
+        .load::<i64>(TABLE)?
+        .into_iter()
+        .map(|(key, balance)| Ok((key.parse()?, balance)))
+        .collect()
+}
+
+/// Migration importing the balances of `economy.json` into a database.
+pub(crate) fn import_file(backend: &mut dyn Backend) -> anyhow::Result<()> {
+    let batch = read_balances(Path::new(ECONOMY_PATH))?
+        .into_iter()
+        .map(|(account, balance)| {
+            Ok(Write::Put {
+                table: TABLE.to_owned(),
+                key: account.to_string(),
+                value: serde_json::to_vec(&balance)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    backend.apply(&batch)
+}
+
+/// Provider storing balances in the configured database,
+/// through the [`Storage`] resource.
+pub struct StorageEconomy {
+    currency: Currency,
+    starting_balance: i64,
+    balances: BTreeMap<Uuid, i64>,
+}
+
+impl EconomyProvider for StorageEconomy {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn balance(&self, _game: &mut Game, account: Uuid) -> i64 {
+        self.balances
+            .get(&account)
+            .copied()
+            .unwrap_or(self.starting_balance)
+    }
+
+    fn set_balance(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        balance: i64,
+    ) -> Result<(), EconomyError> {
+        let storage = game
+            .resources
+            .get::<Storage>()
+            .map_err(|_| EconomyError::ProviderFailure)?;
+        if let Err(e) = storage.put(TABLE, &account.to_string(), &balance) {
+            log::error!("Failed to save a balance: {:?}", e);
+            return Err(EconomyError::ProviderFailure);
+        }
+        self.balances.insert(account, balance);
+        Ok(())
+    }
+}
+
+/// Provider storing balances in a JSON file.
+///
+/// The file is written on a background thread, at most once
+/// per batch window, so transactions never wait for the disk.
+pub struct FlatFileEconomy {
+    currency: Currency,
+    starting_balance: i64,
+    balances: BTreeMap<Uuid, i64>,
+    writer: BalanceWriter,
+}
+
+impl FlatFileEconomy {
+    /// Creates a provider starting with `balances`,
+    /// which saves changes to `path`.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        balances: BTreeMap<Uuid, i64>,
+        currency: Currency,
+        starting_balance: i64,
+        batch_window: Duration,
+    ) -> io::Result<Self> {
+        let writer = BalanceWriter::spawn(path.into(), balances.clone(), batch_window)?;
+        Ok(Self {
+            currency,
+            starting_balance,
+            balances,
+            writer,
+        })
+    }
+}
+
+impl EconomyProvider for FlatFileEconomy {
+    fn name(&self) -> &str {
+        "flat file"
+    }
+
+    fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    fn balance(&self, _game: &mut Game, account: Uuid) -> i64 {
+        self.balances
+            .get(&account)
+            .copied()
+            .unwrap_or(self.starting_balance)
+    }
+
+    fn set_balance(
+        &mut self,
+        _game: &mut Game,
+        account: Uuid,
+        balance: i64,
+    ) -> Result<(), EconomyError> {
+        self.balances.insert(account, balance);
+        self.writer.send(account, balance);
+        Ok(())
+    }
+}
+
+/// Background thread keeping its own copy of the balances,
+/// which it writes to the file once changes stop arriving
+/// for a batch window.
+struct BalanceWriter {
+    changes: Option<Sender<(Uuid, i64)>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BalanceWriter {
+    fn spawn(path: PathBuf, balances: BTreeMap<Uuid, i64>, window: Duration) -> io::Result<Self> {
+        let (changes, receiver) = flume::unbounded();
+        let thread = thread::Builder::new()
+            .name("economy-writer".to_owned())
+            .spawn(move || run_writer(&path, balances, receiver, window))?;
+        Ok(Self {
+            changes: Some(changes),
+            thread: Some(thread),
+        })
+    }
+
+    fn send(&self, account: Uuid, balance: i64) {
+        if let Some(changes) = &self.changes {
+            // Only fails if the writer panicked.
+            let _ = changes.send((account, balance));
+        }
+    }
+}
+
+impl Drop for BalanceWriter {
+    fn drop(&mut self) {
+        // Disconnecting makes the writer save its last changes and stop.
+        self.changes.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run_writer(
+    path: &Path,
+    mut balances: BTreeMap<Uuid, i64>,
+    changes: Receiver<(Uuid, i64)>,
+    window: Duration,
+) {
+    while let Ok(first) = changes.recv() {
+        let deadline = Instant::now() + window;
+        let mut next = Ok(first);
+        let mut disconnected = false;
+        loop {
+            match next {
+                Ok((account, balance)) => {
+                    balances.insert(account, balance);
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+            next = changes.recv_deadline(deadline);
+        }
+
+        let saved = serde_json::to_vec_pretty(&balances)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(storage::write_atomically(path, &json)?));
+        if let Err(e) = saved {
+            log::error!("Failed to save {}: {:?}", path.display(), e);
+        }
+        if disconnected {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balances_persist() {
+        let path =
+            std::env::temp_dir().join(format!("feather-economy-test-{}.json", std::process::id()));
+        let account = Uuid::from_u128(1);
+
+        let mut game = Game::new();
+        let window = Duration::from_secs(60);
+
+        let mut economy = FlatFileEconomy::new(
+            path.clone(),
+            BTreeMap::new(),
+            Currency::default(),
+            100,
+            window,
+        )
+        .unwrap();
+        assert_eq!(economy.balance(&mut game, account), 100);
+        assert_eq!(economy.deposit(&mut game, account, 50), Ok(150));
+        assert_eq!(economy.deposit(&mut game, account, 50), Ok(200));
+        // Dropping the provider saves its pending changes.
+        drop(economy);
+
+        let balances = read_balances(&path).unwrap();
+        assert_eq!(balances.get(&account), Some(&200));
+        let loaded =
+            FlatFileEconomy::new(path.clone(), balances, Currency::default(), 100, window).unwrap();
+        assert_eq!(loaded.balance(&mut game, account), 200);
+        assert_eq!(loaded.balance(&mut game, Uuid::from_u128(2)), 100);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
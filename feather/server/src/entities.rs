@@ -1,6 +1,8 @@
-use base::{EntityKind, Position};
+use base::{Area, BlockId, Direction, EntityKind, EntityMetadata, Inventory, ItemStack, Position};
+use common::{entities::player::HotbarSlot, vanish::Vanished};
 use ecs::{EntityBuilder, EntityRef, SysResult};
-use quill_common::entity_init::EntityInit;
+use protocol::packets::server::{EquipmentEntry, EquipmentSlot};
+use quill_common::{components::HeadYaw, entity_init::EntityInit};
 use uuid::Uuid;
 
 use crate::{Client, NetworkId};
@@ -10,31 +12,78 @@ use crate::{Client, NetworkId};
 pub struct SpawnPacketSender(fn(&EntityRef, &Client) -> SysResult);
 
 impl SpawnPacketSender {
+    /// Sends the spawn packet, unless the entity
+    /// is vanished and hidden from `client`.
     pub fn send(&self, entity: &EntityRef, client: &Client) -> SysResult {
+        if entity.get::<Vanished>().is_ok() && !client.sees_vanished() {
+            return Ok(());
+        }
         (self.0)(entity, client)
     }
 }
 
-/// Stores the position of an entity on
-/// the previous tick. Used to determine
-/// when to send movement updates.
-#[derive(Copy, Clone, Debug)]
-pub struct PreviousPosition(pub Position);
+/// Component holding the items an entity is seen
+/// holding and wearing, derived from its inventory.
+///
+/// Kept up to date by the sync layer, which
+/// sends it to clients when it changes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Equipment {
+    main_hand: Option<ItemStack>,
+    off_hand: Option<ItemStack>,
+    boots: Option<ItemStack>,
+    leggings: Option<ItemStack>,
+    chestplate: Option<ItemStack>,
+    helmet: Option<ItemStack>,
+}
+
+impl Equipment {
+    /// Gets the equipment of an entity with the given inventory.
+    /// Without a hotbar slot, the entity holds nothing in its main hand.
+    pub fn of(inventory: &Inventory, hotbar_slot: Option<HotbarSlot>) -> Self {
+        let item = |area, slot| inventory.item(area, slot).and_then(|item| item.clone());
+        Self {
+            main_hand: hotbar_slot.and_then(|slot| item(Area::Hotbar, slot.get())),
+            off_hand: item(Area::Offhand, 0),
+            boots: item(Area::Boots, 0),
+            leggings: item(Area::Leggings, 0),
+            chestplate: item(Area::Chestplate, 0),
+            helmet: item(Area::Helmet, 0),
+        }
+    }
+
+    /// Gets the entries of an Entity Equipment packet
+    /// setting every slot.
+    pub fn entries(&self) -> Vec<EquipmentEntry> {
+        vec![
+            (EquipmentSlot::MainHand, &self.main_hand),
+            (EquipmentSlot::OffHand, &self.off_hand),
+            (EquipmentSlot::Boots, &self.boots),
+            (EquipmentSlot::Leggings, &self.leggings),
+            (EquipmentSlot::Chestplate, &self.chestplate),
+            (EquipmentSlot::Helmet, &self.helmet),
+        ]
+        .into_iter()
+        .map(|(slot, item)| EquipmentEntry {
+            slot,
+            item: item.clone(),
+        })
+        .collect()
+    }
+}
 
 pub fn add_entity_components(builder: &mut EntityBuilder, init: &EntityInit) {
     if !builder.has::<NetworkId>() {
         builder.add(NetworkId::new());
     }
-    builder.add(PreviousPosition(*builder.get::<Position>().unwrap()));
     add_spawn_packet(builder, init);
 }
 
 fn add_spawn_packet(builder: &mut EntityBuilder, init: &EntityInit) {
-    // TODO: object entities spawned with Spawn Entity
-    // (minecarts, items, ...)
+    // TODO: experience orbs and paintings have their own spawn packets
     let spawn_packet = match init {
         EntityInit::Player => spawn_player,
-        _ => spawn_living_entity,
+        _ => spawn_entity,
     };
     builder.add(SpawnPacketSender(spawn_packet));
 }
@@ -45,15 +94,107 @@ fn spawn_player(entity: &EntityRef, client: &Client) -> SysResult {
     let pos = *entity.get::<Position>()?;
 
     client.send_player(network_id, uuid, pos);
+    send_metadata(entity, client, network_id);
+    send_head_yaw(entity, client, network_id);
+    send_equipment(entity, client, network_id);
     Ok(())
 }
 
-fn spawn_living_entity(entity: &EntityRef, client: &Client) -> SysResult {
+fn spawn_entity(entity: &EntityRef, client: &Client) -> SysResult {
     let network_id = *entity.get::<NetworkId>()?;
     let uuid = *entity.get::<Uuid>()?;
     let pos = *entity.get::<Position>()?;
     let kind = *entity.get::<EntityKind>()?;
 
-    client.send_living_entity(network_id, uuid, pos, kind);
+    if kind.is_living() {
+        client.send_living_entity(network_id, uuid, pos, kind);
+    } else {
+        client.send_object(network_id, uuid, pos, kind, object_data(entity, kind));
+    }
+    send_metadata(entity, client, network_id);
+    send_head_yaw(entity, client, network_id);
+    send_equipment(entity, client, network_id);
     Ok(())
 }
+
+/// Gets the data field of an entity's Spawn Entity
+/// packet, whose meaning depends on the kind of entity.
+///
+/// Falling blocks have a `BlockId` component holding the
+/// block they are made of, and item frames a `Direction`
+/// component holding the direction they face.
+fn object_data(entity: &EntityRef, kind: EntityKind) -> i32 {
+    match kind {
+        // A falling block of air isn't rendered at all.
+        EntityKind::FallingBlock => entity
+            .get::<BlockId>()
+            .map_or_else(|_| BlockId::sand(), |block| *block)
+            .vanilla_id()
+            .into(),
+        EntityKind::ItemFrame => match entity.get::<Direction>().map(|direction| *direction) {
+            Ok(Direction::North) => 2,
+            Ok(Direction::South) | Err(_) => 3,
+            Ok(Direction::West) => 4,
+            Ok(Direction::East) => 5,
+        },
+        _ => 0,
+    }
+}
+
+/// Sends the full metadata of a newly spawned entity.
+/// Later changes are sent as deltas by the `send_entity_metadata` system.
+fn send_metadata(entity: &EntityRef, client: &Client, network_id: NetworkId) {
+    if let Ok(metadata) = entity.get::<EntityMetadata>() {
+        client.send_entity_metadata(network_id, metadata.clone());
+    }
+}
+
+/// Turns the head of a newly spawned entity, which
+/// otherwise faces the same way as its body.
+fn send_head_yaw(entity: &EntityRef, client: &Client, network_id: NetworkId) {
+    if let Ok(head_yaw) = entity.get::<HeadYaw>() {
+        client.update_entity_head_yaw(network_id, *head_yaw);
+    }
+}
+
+/// Sends the items a newly spawned entity holds and wears.
+fn send_equipment(entity: &EntityRef, client: &Client, network_id: NetworkId) {
+    if let Ok(equipment) = entity.get::<Equipment>() {
+        client.send_entity_equipment(network_id, &equipment);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::Item;
+    use ecs::Ecs;
+
+    use super::*;
+
+    #[test]
+    fn equipment_follows_hotbar_slot() {
+        let inventory = Inventory::player();
+        *inventory.item(Area::Hotbar, 2).unwrap() = Some(ItemStack::new(Item::Stick, 1));
+        *inventory.item(Area::Helmet, 0).unwrap() = Some(ItemStack::new(Item::IronHelmet, 1));
+
+        let equipment = Equipment::of(&inventory, Some(HotbarSlot::new(2)));
+        assert_eq!(equipment.main_hand, Some(ItemStack::new(Item::Stick, 1)));
+        assert_eq!(equipment.helmet, Some(ItemStack::new(Item::IronHelmet, 1)));
+        assert_eq!(equipment.entries().len(), 6);
+
+        let equipment = Equipment::of(&inventory, Some(HotbarSlot::new(0)));
+        assert_eq!(equipment.main_hand, None);
+        assert_eq!(Equipment::of(&inventory, None).main_hand, None);
+    }
+
+    #[test]
+    fn object_data_of_falling_blocks() {
+        let mut ecs = Ecs::new();
+        let stone = ecs.spawn((BlockId::stone(),));
+        let unknown = ecs.spawn((EntityKind::FallingBlock,));
+
+        let data = |entity| object_data(&ecs.entity(entity).unwrap(), EntityKind::FallingBlock);
+        assert_eq!(data(stone), i32::from(BlockId::stone().vanilla_id()));
+        assert_eq!(data(unknown), i32::from(BlockId::sand().vanilla_id()));
+    }
+}
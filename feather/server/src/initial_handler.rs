@@ -1,6 +1,11 @@
 //! Initial handling of a connection.
 
-use crate::{connection_worker::Worker, favicon::Favicon};
+use crate::{
+    connection_worker::{Worker, WriterMessage},
+    keep_alive::KeepAlive,
+    listener::proxy_protocol,
+    traffic::ConnectionTraffic,
+};
 use anyhow::bail;
 use base::{ProfileProperty, Text};
 use flume::{Receiver, Sender};
@@ -10,27 +15,35 @@ use once_cell::sync::Lazy;
 use protocol::{
     codec::CryptKey,
     packets::{
-        client::{HandshakeState, Ping},
+        client::{Handshake, HandshakeState},
         server::{
             DisconnectLogin, EncryptionRequest, LoginSuccess, Pong, Response, SetCompression,
         },
     },
     ClientHandshakePacket, ClientLoginPacket, ClientPlayPacket, ClientStatusPacket,
-    ServerLoginPacket, ServerPlayPacket, ServerStatusPacket,
+    ProtocolVersion, ServerLoginPacket, ServerStatusPacket,
 };
 use rand::rngs::OsRng;
 use rsa::{PaddingScheme, PublicKeyParts, RSAPrivateKey};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use sha1::Sha1;
-use std::convert::TryInto;
+use std::{convert::TryInto, net::SocketAddr};
+use tracing::{field, Instrument, Span};
 use uuid::Uuid;
 
-use self::proxy::ProxyData;
+use self::{legacy_ping::LegacyPing, proxy::ProxyData};
 
-const SERVER_NAME: &str = "Feather 1.16.5";
-const PROTOCOL_VERSION: i32 = 754;
+pub const SERVER_NAME: &str = "Feather 1.16.5";
+pub const PROTOCOL_VERSION: i32 = ProtocolVersion::LATEST.protocol_number();
 
+mod hooks;
+pub(crate) mod legacy_ping;
 mod proxy;
+mod session_auth;
+mod state;
+
+pub use hooks::{Hooks, PluginPreLogin, PreLogin};
+pub use state::{InvalidTransition, State, StateTimedOut, WrongState};
 
 /// Information for a newly connected player.
 #[derive(Debug)]
@@ -38,9 +51,13 @@ pub struct NewPlayer {
     pub uuid: Uuid,
     pub username: String,
     pub profile: Vec<ProfileProperty>,
+    /// Address of the client.
+    pub addr: SocketAddr,
 
     pub received_packets: Receiver<ClientPlayPacket>,
-    pub packets_to_send: Sender<ServerPlayPacket>,
+    pub packets_to_send: Sender<WriterMessage>,
+    pub keep_alive: KeepAlive,
+    pub traffic: ConnectionTraffic,
 }
 
 /// Result of initial handling.
@@ -54,94 +71,118 @@ pub enum InitialHandling {
 
 /// Handles a connection until the protocol state is switched to Play;
 /// that is, until we send Login Success. Returns the client's information.
+///
+/// Each stage runs in its own span (`handshake`, `status` or `login`.)
 pub async fn handle(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
-    // Get the handshake packet.
-    let handshake = worker.read::<ClientHandshakePacket>().await?;
-
-    let ClientHandshakePacket::Handshake(handshake) = handshake;
+    let handshake = match read_handshake(worker)
+        .instrument(tracing::debug_span!("handshake"))
+        .await?
+    {
+        Greeting::Handshake(handshake) => handshake,
+        Greeting::LegacyPing(ping) => {
+            return legacy_ping::handle(worker, ping)
+                .instrument(tracing::debug_span!("status", legacy = true))
+                .await
+        }
+    };
 
     match handshake.next_state {
-        HandshakeState::Status => handle_status(worker).await,
+        HandshakeState::Status => {
+            worker.transition(State::Status)?;
+            handle_status(worker)
+                .instrument(tracing::debug_span!("status"))
+                .await
+        }
         HandshakeState::Login => {
-            if handshake.protocol_version < PROTOCOL_VERSION {
-                worker
-                    .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
-                        reason: Text::from(
-                            "Invalid protocol! The server is running on version 1.16!",
-                        )
-                        .to_string(),
-                    }))
-                    .await
-                    .ok();
-                return Ok(InitialHandling::Disconnect);
-            }
-            let proxy_data =
-                if let Some(crate::options::ProxyMode::Bungeecord) = worker.options().proxy_mode {
-                    Some(proxy::do_bungee_ip_forwarding(&handshake)?)
-                } else {
-                    None
-                };
-            handle_login(worker, proxy_data).await
+            let span = tracing::info_span!(
+                "login",
+                addr = %worker.addr(),
+                protocol = handshake.protocol_version,
+                username = field::Empty
+            );
+            start_login(worker, &handshake).instrument(span).await
         }
     }
 }
 
-#[derive(Debug, Serialize)]
-struct StatusResponse<'a> {
-    version: Version,
-    players: Players,
-    description: Text,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    favicon: Option<&'a str>,
+/// The first thing sent by a client.
+enum Greeting {
+    Handshake(Handshake),
+    /// Sent by pre-1.7 clients instead of a handshake packet.
+    LegacyPing(LegacyPing),
 }
 
-#[derive(Debug, Serialize)]
-struct Version {
-    name: &'static str,
-    protocol: i32,
+async fn read_handshake(worker: &mut Worker) -> anyhow::Result<Greeting> {
+    // A load balancer sends the client's real address first.
+    if worker.options().proxy_protocol {
+        if let Some(addr) = proxy_protocol::read_source_address(worker).await? {
+            tracing::trace!("PROXY protocol forwarded address {}", addr);
+            worker.set_addr(addr);
+            if !worker.allow_connection() {
+                bail!("throttled connection from {}", addr);
+            }
+        }
+    }
+
+    if let Some(ping) = worker.read_legacy_ping().await? {
+        return Ok(Greeting::LegacyPing(ping));
+    }
+
+    let ClientHandshakePacket::Handshake(handshake) =
+        worker.read::<ClientHandshakePacket>().await?;
+    Ok(Greeting::Handshake(handshake))
 }
 
-#[derive(Debug, Serialize)]
-struct Players {
-    max: u32,
-    online: u32,
+async fn start_login(
+    worker: &mut Worker,
+    handshake: &Handshake,
+) -> anyhow::Result<InitialHandling> {
+    worker.transition(State::Login)?;
+    match ProtocolVersion::from_protocol_number(handshake.protocol_version) {
+        Some(version) => worker.set_protocol_version(version),
+        None => {
+            worker
+                .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+                    reason: Text::from(
+                        "Invalid protocol! The server is running on versions 1.16.1 to 1.16.5!",
+                    ),
+                }))
+                .await
+                .ok();
+            return Ok(InitialHandling::Disconnect);
+        }
+    }
+    let proxy_data =
+        if let Some(crate::options::ProxyMode::Bungeecord) = worker.options().proxy_mode {
+            Some(proxy::do_bungee_ip_forwarding(handshake)?)
+        } else {
+            None
+        };
+    handle_login(worker, proxy_data).await
 }
 
 async fn handle_status(worker: &mut Worker) -> anyhow::Result<InitialHandling> {
     let _request = worker.read::<ClientStatusPacket>().await?;
 
-    let payload = StatusResponse {
-        version: Version {
-            name: SERVER_NAME,
-            protocol: PROTOCOL_VERSION,
-        },
-        players: Players {
-            max: worker.options().max_players,
-            online: worker.player_count(),
-        },
-        description: Text::from(worker.options().motd.clone()),
-        favicon: worker
-            .options()
-            .favicon
-            .as_ref()
-            .map(Favicon::base64_encoded),
-    };
     let response = Response {
-        response: serde_json::to_string(&payload)?,
+        response: worker.status_cache().get().to_string(),
     };
     worker
         .write(&ServerStatusPacket::Response(response))
         .await?;
 
-    match worker.read::<Ping>().await {
-        Ok(ping) => {
+    match worker.read::<ClientStatusPacket>().await {
+        Ok(ClientStatusPacket::Ping(ping)) => {
             let pong = Pong {
                 payload: ping.payload,
             };
             worker.write(&ServerStatusPacket::Pong(pong)).await?;
         }
+        Ok(packet) => {
+            tracing::debug!("Expected ping packet from status call, got {:?}", packet);
+        }
         Err(e) => {
-            log::debug!("Didn't receive ping packet from status call: {}", e);
+            tracing::debug!("Didn't receive ping packet from status call: {}", e);
         }
     }
 
@@ -156,7 +197,8 @@ async fn handle_login(
         ClientLoginPacket::LoginStart(l) => l,
         _ => bail!("expected login start"),
     };
-    log::debug!("{} is logging in", login_start.name);
+    Span::current().record("username", &login_start.name.as_str());
+    tracing::debug!("{} is logging in", login_start.name);
 
     // Velocity IP forwarding runs after Login Start is received.
     if let Some(crate::options::ProxyMode::Velocity) = worker.options().proxy_mode {
@@ -215,7 +257,8 @@ async fn enable_encryption(
     worker: &mut Worker,
     username: String,
 ) -> anyhow::Result<InitialHandling> {
-    log::debug!("Authenticating {}", username);
+    tracing::debug!("Authenticating {}", username);
+    worker.transition(State::EncryptionPending)?;
     let shared_secret = do_encryption_handshake(worker).await?;
     worker.enable_encryption(shared_secret);
 
@@ -252,28 +295,19 @@ async fn do_encryption_handshake(worker: &mut Worker) -> anyhow::Result<CryptKey
     Ok((&shared_secret[..]).try_into()?)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct AuthResponse {
     id: Uuid,
     name: String,
     properties: Vec<ProfileProperty>,
 }
 
-async fn authenticate(shared_secret: CryptKey, username: String) -> anyhow::Result<AuthResponse> {
+async fn authenticate(
+    shared_secret: CryptKey,
+    username: String,
+) -> Result<AuthResponse, session_auth::AuthError> {
     let server_hash = compute_server_hash(shared_secret);
-
-    let response: AuthResponse = tokio::task::spawn_blocking(move || {
-        let url = format!(
-            "https://sessionserver.mojang.com/session/minecraft/hasJoined?username={}&serverId={}",
-            username, server_hash
-        );
-        let response = ureq::get(&url).call()?;
-
-        Result::<AuthResponse, anyhow::Error>::Ok(response.into_json()?)
-    })
-    .await??;
-
-    Ok(response)
+    session_auth::has_joined(username, server_hash).await
 }
 
 fn compute_server_hash(shared_secret: CryptKey) -> String {
@@ -294,6 +328,21 @@ async fn finish_login(
     worker: &mut Worker,
     response: AuthResponse,
 ) -> anyhow::Result<InitialHandling> {
+    let pre_login = PreLogin {
+        username: &response.name,
+        uuid: response.id,
+    };
+    if let Err(reason) = worker.options().hooks.run_pre_login(&pre_login).await {
+        tracing::debug!("{} was rejected by a pre-login hook", response.name);
+        worker
+            .write(ServerLoginPacket::DisconnectLogin(DisconnectLogin {
+                reason,
+            }))
+            .await
+            .ok();
+        return Ok(InitialHandling::Disconnect);
+    }
+
     enable_compression(worker).await?;
 
     let success = LoginSuccess {
@@ -303,15 +352,23 @@ async fn finish_login(
     worker
         .write(&ServerLoginPacket::LoginSuccess(success))
         .await?;
+    worker.transition(State::Play)?;
 
     let new_player = NewPlayer {
         username: response.name,
         uuid: response.id,
         profile: response.properties,
+        addr: worker.addr(),
         received_packets: worker.received_packets(),
         packets_to_send: worker.packets_to_send(),
+        keep_alive: worker.keep_alive(),
+        traffic: worker.traffic(),
     };
-    log::debug!("Completed initial handling for {}", new_player.username);
+    tracing::debug!(
+        "Completed initial handling for {} ({})",
+        new_player.username,
+        new_player.addr
+    );
     Ok(InitialHandling::Join(new_player))
 }
 
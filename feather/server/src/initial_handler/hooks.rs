@@ -0,0 +1,91 @@
+//! Hooks invoked at well-defined states of initial handling.
+
+use std::{fmt, sync::Arc};
+
+use base::Text;
+use flume::{Receiver, Sender};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+/// Information about a player who has been authenticated
+/// but not yet sent Login Success.
+#[derive(Debug)]
+pub struct PreLogin<'a> {
+    pub username: &'a str,
+    pub uuid: Uuid,
+}
+
+/// A pre-login waiting for plugins to allow
+/// or reject it on the server thread.
+pub struct PluginPreLogin {
+    pub username: String,
+    pub uuid: Uuid,
+    pub response: oneshot::Sender<Result<(), Text>>,
+}
+
+type PreLoginHook = Arc<dyn Fn(&PreLogin) -> Result<(), Text> + Send + Sync>;
+
+/// Callbacks for extending initial handling.
+///
+/// Hooks run on connection worker tasks, not on the
+/// server thread, so they should return quickly.
+#[derive(Clone, Default)]
+pub struct Hooks {
+    pre_login: Vec<PreLoginHook>,
+    /// Where pre-logins are sent for plugins to handle, if anywhere.
+    plugins: Option<Sender<PluginPreLogin>>,
+}
+
+impl Hooks {
+    /// Adds a hook invoked right before a player
+    /// enters the Play state. Returning an error
+    /// disconnects the player with the given reason.
+    pub fn on_pre_login(
+        &mut self,
+        hook: impl Fn(&PreLogin) -> Result<(), Text> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.pre_login.push(Arc::new(hook));
+        self
+    }
+
+    /// Makes pre-logins wait for plugins once the other
+    /// hooks have run. They are sent on the returned channel.
+    pub(crate) fn forward_to_plugins(&mut self) -> Receiver<PluginPreLogin> {
+        let (plugins, pre_logins) = flume::unbounded();
+        self.plugins = Some(plugins);
+        pre_logins
+    }
+
+    /// Runs pre-login hooks in the order they were
+    /// added, stopping at the first rejection. Plugins
+    /// have the final say.
+    pub(crate) async fn run_pre_login(&self, info: &PreLogin<'_>) -> Result<(), Text> {
+        self.pre_login.iter().try_for_each(|hook| hook(info))?;
+
+        if let Some(plugins) = &self.plugins {
+            let (response, response_rx) = oneshot::channel();
+            let pre_login = PluginPreLogin {
+                username: info.username.to_owned(),
+                uuid: info.uuid,
+                response,
+            };
+            // If the server thread is gone, the login
+            // fails later on anyway.
+            if plugins.send_async(pre_login).await.is_ok() {
+                if let Ok(result) = response_rx.await {
+                    return result;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("pre_login", &self.pre_login.len())
+            .field("plugins", &self.plugins.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,259 @@
+//! Handling of the legacy (pre-1.7) server list ping.
+//!
+//! Old clients and some server list crawlers still open
+//! the connection with a `0xFE` byte instead of a handshake
+//! packet. We answer them with a kick packet containing
+//! the server status, as vanilla does.
+//!
+//! See <https://wiki.vg/Server_List_Ping#1.6>.
+
+use std::{io, time::Duration};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    time::timeout,
+};
+
+use crate::connection_worker::Worker;
+
+use super::{InitialHandling, SERVER_NAME};
+
+/// ID of the legacy Server List Ping packet.
+const PING_PACKET_ID: u8 = 0xFE;
+/// Payload byte sent after the packet ID by 1.4+ clients.
+const PING_PAYLOAD: u8 = 0x01;
+/// ID of the plugin message 1.6 clients send after the payload.
+const PLUGIN_MESSAGE_ID: u8 = 0xFA;
+/// ID of the legacy Kick packet, used to send the response.
+const KICK_PACKET_ID: u8 = 0xFF;
+
+/// How long to wait for more bytes when those received
+/// so far could start both a ping and a handshake.
+const AMBIGUITY_WAIT: Duration = Duration::from_millis(500);
+
+/// Protocol version reported to legacy clients.
+/// It matches no legacy version, so old clients
+/// display the server as incompatible.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+/// Format of a legacy ping, determined by the bytes
+/// the client opened the connection with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LegacyPing {
+    /// Beta 1.8 to 1.3: a single `0xFE` byte.
+    Beta,
+    /// 1.4 to 1.6: `0xFE 0x01`, optionally followed
+    /// by a plugin message we don't care about.
+    V1_4,
+}
+
+/// What the first bytes received on a connection start.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Detection {
+    Ping(LegacyPing),
+    Handshake,
+    /// More bytes are needed to tell. If the client sends
+    /// nothing more, it is waiting for a response to a
+    /// ping of the given format, if any.
+    Incomplete(Option<LegacyPing>),
+}
+
+/// Tells a legacy ping from a modern handshake.
+///
+/// `0xFE` and `0xFE 0x01` also start modern handshakes of 382 and
+/// 254 bytes, so they are only pings if the client sends nothing
+/// else. After `0xFE 0x01`, a 1.6 client sends its plugin message.
+fn detect(received: &[u8]) -> Detection {
+    match received {
+        [] => Detection::Incomplete(None),
+        [PING_PACKET_ID] => Detection::Incomplete(Some(LegacyPing::Beta)),
+        [PING_PACKET_ID, PING_PAYLOAD] => Detection::Incomplete(Some(LegacyPing::V1_4)),
+        [PING_PACKET_ID, PING_PAYLOAD, PLUGIN_MESSAGE_ID, ..] => Detection::Ping(LegacyPing::V1_4),
+        _ => Detection::Handshake,
+    }
+}
+
+/// Reads the first bytes of a connection from `stream` into
+/// `received`, until they tell a legacy ping from a modern
+/// handshake. Returns `None` for a handshake.
+///
+/// A ping may arrive split across several TCP segments, so while
+/// the bytes could start both, this waits for more. Only once none
+/// arrive for [`AMBIGUITY_WAIT`] is the client taken to be waiting
+/// for a response to a ping.
+pub async fn read_greeting(
+    stream: &mut (impl AsyncRead + Unpin),
+    received: &mut Vec<u8>,
+) -> io::Result<Option<LegacyPing>> {
+    let mut buffer = [0; 64];
+    loop {
+        let read = match detect(received) {
+            Detection::Ping(ping) => return Ok(Some(ping)),
+            Detection::Handshake => return Ok(None),
+            Detection::Incomplete(None) => stream.read(&mut buffer).await?,
+            Detection::Incomplete(Some(ping)) => {
+                match timeout(AMBIGUITY_WAIT, stream.read(&mut buffer)).await {
+                    Ok(read) => read?,
+                    Err(_) => return Ok(Some(ping)),
+                }
+            }
+        };
+        if read == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "read 0 bytes"));
+        }
+        received.extend_from_slice(&buffer[..read]);
+    }
+}
+
+/// Responds to a legacy ping, then disconnects the client.
+pub async fn handle(worker: &mut Worker, ping: LegacyPing) -> anyhow::Result<InitialHandling> {
+    log::debug!("Responding to legacy server list ping ({:?})", ping);
+    let response = response_payload(
+        ping,
+        &worker.options().motd.to_legacy_string(),
+        worker.player_count(),
+        worker.options().max_players,
+    );
+    worker.write_raw(&encode_kick(&response)).await?;
+    Ok(InitialHandling::Disconnect)
+}
+
+fn response_payload(ping: LegacyPing, motd: &str, online: u32, max: u32) -> String {
+    match ping {
+        LegacyPing::Beta => format!("{}§{}§{}", strip_formatting(motd), online, max),
+        LegacyPing::V1_4 => format!(
+            "§1\0{}\0{}\0{}\0{}\0{}",
+            LEGACY_PROTOCOL_VERSION, SERVER_NAME, motd, online, max
+        ),
+    }
+}
+
+/// Removes `§` formatting codes from the MOTD, since the beta
+/// response uses `§` as a separator. Newlines aren't supported
+/// by any legacy client either.
+fn strip_formatting(motd: &str) -> String {
+    let mut stripped = String::with_capacity(motd.len());
+    let mut chars = motd.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '§' => {
+                chars.next();
+            }
+            '\n' => stripped.push(' '),
+            c => stripped.push(c),
+        }
+    }
+    stripped
+}
+
+/// Encodes a legacy Kick packet: the packet ID followed by
+/// a string prefixed with its length in UTF-16 code units,
+/// encoded as UTF-16BE.
+fn encode_kick(message: &str) -> Vec<u8> {
+    let units: Vec<u16> = message.encode_utf16().collect();
+
+    let mut bytes = Vec::with_capacity(3 + units.len() * 2);
+    bytes.push(KICK_PACKET_ID);
+    bytes.extend_from_slice(&(units.len() as u16).to_be_bytes());
+    for unit in units {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    #[test]
+    fn detect_legacy_pings() {
+        assert_eq!(
+            detect(&[0xFE]),
+            Detection::Incomplete(Some(LegacyPing::Beta))
+        );
+        assert_eq!(
+            detect(&[0xFE, 0x01]),
+            Detection::Incomplete(Some(LegacyPing::V1_4))
+        );
+        assert_eq!(
+            detect(&[0xFE, 0x01, 0xFA, 0x00, 0x0B]),
+            Detection::Ping(LegacyPing::V1_4)
+        );
+    }
+
+    #[test]
+    fn detect_modern_handshake() {
+        assert_eq!(detect(&[]), Detection::Incomplete(None));
+        // Packet length 16, packet ID 0x00 (Handshake)
+        assert_eq!(detect(&[0x10, 0x00, 0xF2, 0x05]), Detection::Handshake);
+        // Packet length 382, which starts with 0xFE as a VarInt
+        assert_eq!(detect(&[0xFE, 0x02, 0x00]), Detection::Handshake);
+        // Packet length 254, which is encoded as 0xFE 0x01
+        assert_eq!(
+            detect(&[0xFE, 0x01, 0x00, 0xF2, 0x05]),
+            Detection::Handshake
+        );
+    }
+
+    /// Sends `pieces` over a pipe with a pause after each, then
+    /// leaves it open like a client waiting for a response.
+    async fn read_pieces(pieces: &'static [&'static [u8]]) -> (Option<LegacyPing>, Vec<u8>) {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let sender = tokio::spawn(async move {
+            for piece in pieces {
+                client.write_all(piece).await.unwrap();
+                tokio::time::sleep(AMBIGUITY_WAIT / 4).await;
+            }
+            client
+        });
+        let mut received = Vec::new();
+        let ping = read_greeting(&mut server, &mut received).await.unwrap();
+        drop(sender.await.unwrap());
+        (ping, received)
+    }
+
+    #[tokio::test]
+    async fn read_split_greetings() {
+        assert_eq!(
+            read_pieces(&[&[0xFE], &[0x01], &[0xFA, 0x00]]).await,
+            (Some(LegacyPing::V1_4), vec![0xFE, 0x01, 0xFA, 0x00])
+        );
+        assert_eq!(
+            read_pieces(&[&[0xFE], &[0x01]]).await,
+            (Some(LegacyPing::V1_4), vec![0xFE, 0x01])
+        );
+        assert_eq!(
+            read_pieces(&[&[0xFE]]).await,
+            (Some(LegacyPing::Beta), vec![0xFE])
+        );
+        // A handshake of 254 bytes split after its length
+        assert_eq!(
+            read_pieces(&[&[0xFE, 0x01], &[0x00, 0xF2]]).await,
+            (None, vec![0xFE, 0x01, 0x00, 0xF2])
+        );
+    }
+
+    #[test]
+    fn v1_4_payload() {
+        let payload = response_payload(LegacyPing::V1_4, "A Feather server", 3, 16);
+        let fields: Vec<&str> = payload.split('\0').collect();
+        assert_eq!(
+            fields,
+            vec!["§1", "127", SERVER_NAME, "A Feather server", "3", "16"]
+        );
+    }
+
+    #[test]
+    fn beta_payload() {
+        let payload = response_payload(LegacyPing::Beta, "§aA Feather\nserver", 0, 20);
+        assert_eq!(payload, "A Feather server§0§20");
+    }
+
+    #[test]
+    fn kick_packet_encoding() {
+        let bytes = encode_kick("§1");
+        assert_eq!(bytes, vec![0xFF, 0x00, 0x02, 0x00, 0xA7, 0x00, 0x31]);
+    }
+}
@@ -39,8 +39,10 @@ pub fn extract(packet: &Handshake) -> anyhow::Result<ProxyData> {
 
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+
     use base::ProfileProperty;
-    use protocol::packets::client::HandshakeState;
+    use protocol::{packets::client::HandshakeState, ProtocolVersion, Readable, Writeable};
 
     use crate::initial_handler::PROTOCOL_VERSION;
 
@@ -167,4 +169,50 @@ mod tests {
 
         extract(&handshake).unwrap_err();
     }
+
+    #[test]
+    fn read_forwarded_handshake() {
+        // Forwarded by BungeeCord for a player with a skin and
+        // a cape; the signed textures alone are over 1000 characters.
+        let value = concat!(
+            "ewogICJ0aW1lc3RhbXAiOiAxNjM0NTY3ODkwMTIzLAogICJwcm9maWxlSWQiOiAiOTA1YzdlNGZiOTZiNDUxMzk2ND",
+            "VkMTIzMjI1NTc1ZTIiLAogICJwcm9maWxlTmFtZSI6ICJOb3RjaCIsCiAgInNpZ25hdHVyZVJlcXVpcmVkIjogdHJ1",
+            "ZSwKICAidGV4dHVyZXMiOiB7CiAgICAiU0tJTiI6IHsKICAgICAgInVybCI6ICJodHRwOi8vdGV4dHVyZXMubWluZW",
+            "NyYWZ0Lm5ldC90ZXh0dXJlLzI5MjAwOWE0OTI1YjU4ZjAyYzc3ZGFkYzNlY2VmMDdlYTRjNzQ3MmY2NGUwZmRjMzJj",
+            "ZTU1MjI0ODkzNjI2ODAiCiAgICB9LAogICAgIkNBUEUiOiB7CiAgICAgICJ1cmwiOiAiaHR0cDovL3RleHR1cmVzLm",
+            "1pbmVjcmFmdC5uZXQvdGV4dHVyZS8yMzQwYzBlMDNkZDI0YTExYjE1YThiMzNjMmE3ZTllMzJhYmIyMDUxYjI0ODFk",
+            "MGJhN2RlZmQ2MzVjYTdhOTMzIgogICAgfQogIH0KfQ==",
+        );
+        let signature = concat!(
+            "pU3KGCUwux1tEyze1iN7LtkeP3IfyxlxF0SU1kk8nVw0YL4xIB5p/tqg7ui5mX9cfCmZ/a/lkyU81lSvTfrXFCegrr",
+            "P+6SMvivIhH57kkcWxC+y1Vjv8Hm+TQn7LyP4pVeXNjkbcjtS3wnZNKlpNdncG+F2GkAJK1r2jQBvpyMvMyTX2zR9h",
+            "ImrhUziuGjQATTO6DSRqwEyBsbryPjv57vX3nytJNK+H9VILablLDZguhbtVtnKocmN6zXRm/LYODo/xhGOw5LK6KX",
+            "A0dPBkrGj3APWwKz3GZvRb3qosyu3NK1FXQQ5N7krys09DCgc0R95jbA6AbJV7poTWQx+16tdCTQnhXQJMWEjyPR+m",
+            "9zYdf2GNFTLnDiDipmaN5/R+hGflRtU+yOKhJXvbJWybPk+7SYFG73Awy/lTclLczq3XZLajL7sJrerhCcSplyA5dT",
+            "Urh4sUXIpC2ITPTP2nLY4dXdkliQgthSpxIoc+6AWt1YlCFno4UoYZXGefnGmU5FuKsQmAEgcJYfN95Dbd/cmdbnWv",
+            "ZUfPsRtCBySC3FMcK8OQfJYX615QieQBhrqopX0Rnm+2XQCrwyrzjmZ/Ai6HLUnMFckLmZt3K0/Hpv1MkUoW20cIdS",
+            "sPFUS4NcDnGQl9+ocB6SMvIfKBJod4aXbr/MMn9ZMXZSdLqYKbRAY=",
+        );
+        let address = format!(
+            "play.example.com\0203.0.113.7\0905c7e4fb96b45139645d123225575e2\0[{{\"name\":\"textures\",\"value\":\"{}\",\"signature\":\"{}\"}}]",
+            value, signature
+        );
+        assert!(address.len() > 1000);
+
+        let mut bytes = Vec::new();
+        Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            server_address: address,
+            server_port: 25565,
+            next_state: HandshakeState::Login,
+        }
+        .write(&mut bytes, ProtocolVersion::V1_16_2);
+        let handshake =
+            Handshake::read(&mut Cursor::new(&bytes[..]), ProtocolVersion::V1_16_2).unwrap();
+
+        let data = extract(&handshake).unwrap();
+        assert_eq!(data.client, "203.0.113.7");
+        assert_eq!(data.profile[0].value, value);
+        assert_eq!(data.profile[0].signature, signature);
+    }
 }
@@ -0,0 +1,226 @@
+//! Verifies players with Mojang's session server.
+//!
+//! Successful responses are cached briefly by username and
+//! server hash, and requests failing because of transient
+//! errors (network failures, rate limits, or server errors)
+//! are retried with exponential backoff, so a hiccup at
+//! Mojang doesn't disconnect every joining player.
+
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use thiserror::Error;
+use tokio::task::JoinError;
+
+use super::AuthResponse;
+
+/// How long successful responses are cached.
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Number of requests made before giving up.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry. Doubles on each further retry.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+static CACHE: Lazy<Mutex<SessionCache>> = Lazy::new(Default::default);
+
+/// An error which occurred while verifying a player.
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("{username} has not joined with the session server")]
+    NotJoined { username: String },
+    #[error("the session server responded with HTTP status {status} for {username}")]
+    Status { username: String, status: u16 },
+    #[error("failed to reach the session server for {username}")]
+    Transport {
+        username: String,
+        #[source]
+        source: Box<ureq::Error>,
+    },
+    #[error("the session server sent an invalid response for {username}")]
+    InvalidResponse {
+        username: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("the session server request for {username} panicked")]
+    Panicked {
+        username: String,
+        #[source]
+        source: JoinError,
+    },
+}
+
+impl AuthError {
+    /// Returns whether the request is worth retrying.
+    fn is_transient(&self) -> bool {
+        match self {
+            AuthError::Status { status, .. } => is_transient_status(*status),
+            AuthError::Transport { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Calls `hasJoined` to check that the player
+/// with `username` joined using `server_hash`.
+pub async fn has_joined(username: String, server_hash: String) -> Result<AuthResponse, AuthError> {
+    let key = (username, server_hash);
+    if let Some(response) = CACHE.lock().get(&key, Instant::now()) {
+        log::debug!("Using cached session for {}", key.0);
+        return Ok(response);
+    }
+
+    let mut attempt = 0;
+    let response = loop {
+        attempt += 1;
+        let (username, server_hash) = key.clone();
+        let result = tokio::task::spawn_blocking(move || request(&username, &server_hash))
+            .await
+            .map_err(|source| AuthError::Panicked {
+                username: key.0.clone(),
+                source,
+            })?;
+        match result {
+            Ok(response) => break response,
+            Err(e) if e.is_transient() && attempt < MAX_ATTEMPTS => {
+                let delay = backoff_delay(attempt);
+                // Includes the cause, e.g. the transport error.
+                log::warn!("{:#}, retrying in {:?}", anyhow::Error::new(e), delay);
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    CACHE.lock().insert(key, response.clone(), Instant::now());
+    Ok(response)
+}
+
+fn request(username: &str, server_hash: &str) -> Result<AuthResponse, AuthError> {
+    let url = format!(
+        "https://sessionserver.mojang.com/session/minecraft/hasJoined?username={}&serverId={}",
+        username, server_hash
+    );
+    let username = username.to_owned();
+    let response = match ureq::get(&url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, _)) => return Err(AuthError::Status { username, status }),
+        Err(e) => {
+            return Err(AuthError::Transport {
+                username,
+                source: Box::new(e),
+            })
+        }
+    };
+
+    // The session server responds with 204 No Content
+    // if the player didn't join with this server hash.
+    if response.status() == 204 {
+        return Err(AuthError::NotJoined { username });
+    }
+    response
+        .into_json()
+        .map_err(|source| AuthError::InvalidResponse { username, source })
+}
+
+fn is_transient_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+fn backoff_delay(attempt: u32) -> Duration {
+    INITIAL_BACKOFF * 2u32.pow(attempt - 1)
+}
+
+/// Successful `hasJoined` responses, keyed by username and server hash.
+#[derive(Default)]
+struct SessionCache {
+    entries: AHashMap<(String, String), (Instant, AuthResponse)>,
+}
+
+impl SessionCache {
+    fn get(&mut self, key: &(String, String), now: Instant) -> Option<AuthResponse> {
+        match self.entries.get(key) {
+            Some((cached_at, response)) if now.duration_since(*cached_at) < CACHE_TTL => {
+                Some(response.clone())
+            }
+            Some(_) => {
+                self.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&mut self, key: (String, String), response: AuthResponse, now: Instant) {
+        self.entries
+            .retain(|_, (cached_at, _)| now.duration_since(*cached_at) < CACHE_TTL);
+        self.entries.insert(key, (now, response));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn response() -> AuthResponse {
+        AuthResponse {
+            id: Uuid::nil(),
+            name: "Notch".to_owned(),
+            properties: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles() {
+        assert_eq!(backoff_delay(1), Duration::from_millis(250));
+        assert_eq!(backoff_delay(2), Duration::from_millis(500));
+        assert_eq!(backoff_delay(3), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn transient_errors() {
+        let username = "Notch".to_owned();
+        assert!(AuthError::Status {
+            username: username.clone(),
+            status: 503
+        }
+        .is_transient());
+        assert!(!AuthError::Status {
+            username: username.clone(),
+            status: 403
+        }
+        .is_transient());
+        assert!(!AuthError::NotJoined { username }.is_transient());
+    }
+
+    #[test]
+    fn transient_statuses() {
+        assert!(is_transient_status(429));
+        assert!(is_transient_status(503));
+        assert!(!is_transient_status(403));
+        assert!(!is_transient_status(404));
+    }
+
+    #[test]
+    fn cache_expires() {
+        let mut cache = SessionCache::default();
+        let key = ("Notch".to_owned(), "hash".to_owned());
+        let now = Instant::now();
+        cache.insert(key.clone(), response(), now);
+        assert_eq!(
+            cache.get(&key, now + Duration::from_secs(1)).unwrap().name,
+            "Notch"
+        );
+        assert!(cache.get(&key, now + CACHE_TTL).is_none());
+        assert!(cache.entries.is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+//! The states a connection goes through during initial handling.
+
+use protocol::ProtocolState;
+use thiserror::Error;
+
+/// State of a connection during initial handling.
+///
+/// Valid transitions are:
+/// * `Handshake` → `Status` or `Login`
+/// * `Login` → `EncryptionPending` (online mode) or `Play`
+/// * `EncryptionPending` → `Play`
+///
+/// `Status` and `Play` are final: a status ping is disconnected
+/// after the pong, and a connection in `Play` is handed off
+/// to the server thread.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum State {
+    /// Waiting for the Handshake packet.
+    Handshake,
+    /// Answering a server list ping.
+    Status,
+    /// Waiting for Login Start, or running proxy forwarding.
+    Login,
+    /// Encryption Request sent; waiting for the client's
+    /// Encryption Response and for authentication.
+    EncryptionPending,
+    /// Login Success was sent.
+    Play,
+}
+
+impl State {
+    /// Returns whether a connection in this state
+    /// may move to `next`.
+    pub fn can_transition_to(self, next: State) -> bool {
+        matches!(
+            (self, next),
+            (State::Handshake, State::Status)
+                | (State::Handshake, State::Login)
+                | (State::Login, State::EncryptionPending)
+                | (State::Login, State::Play)
+                | (State::EncryptionPending, State::Play)
+        )
+    }
+
+    /// Returns the protocol state whose packets
+    /// are sent by a connection in this state.
+    pub fn protocol_state(self) -> ProtocolState {
+        match self {
+            State::Handshake => ProtocolState::Handshake,
+            State::Status => ProtocolState::Status,
+            State::Login | State::EncryptionPending => ProtocolState::Login,
+            State::Play => ProtocolState::Play,
+        }
+    }
+
+    /// Returns an error if packets of the protocol state `packet`
+    /// aren't sent in this state.
+    ///
+    /// This only checks the packet type the server chose to read
+    /// or write, catching bugs in the initial handler. It doesn't
+    /// validate what the client sent: client packets are always
+    /// decoded as packets of the current state.
+    pub fn check_packet(self, packet: ProtocolState) -> Result<(), WrongState> {
+        if self.protocol_state() == packet {
+            Ok(())
+        } else {
+            Err(WrongState {
+                state: self,
+                packet,
+            })
+        }
+    }
+
+    /// Returns the next state, or an error if
+    /// the transition is not allowed.
+    pub fn transition(self, next: State) -> Result<State, InvalidTransition> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(InvalidTransition {
+                from: self,
+                to: next,
+            })
+        }
+    }
+}
+
+/// Error returned when a connection attempts
+/// an invalid state transition.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid connection state transition from {from:?} to {to:?}")]
+pub struct InvalidTransition {
+    pub from: State,
+    pub to: State,
+}
+
+/// Error returned when the server tries to read or write
+/// a packet which isn't sent in the connection's current state.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("{packet:?} packet not allowed in connection state {state:?}")]
+pub struct WrongState {
+    pub state: State,
+    pub packet: ProtocolState,
+}
+
+/// Error returned when a connection stays in
+/// a state for longer than its timeout.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("connection timed out in state {state:?}")]
+pub struct StateTimedOut {
+    pub state: State,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [State; 5] = [
+        State::Handshake,
+        State::Status,
+        State::Login,
+        State::EncryptionPending,
+        State::Play,
+    ];
+
+    fn assert_transitions(from: State, allowed: &[State]) {
+        for &to in &ALL {
+            let result = from.transition(to);
+            if allowed.contains(&to) {
+                assert_eq!(result, Ok(to));
+            } else {
+                assert_eq!(result, Err(InvalidTransition { from, to }));
+            }
+        }
+    }
+
+    #[test]
+    fn from_handshake() {
+        assert_transitions(State::Handshake, &[State::Status, State::Login]);
+    }
+
+    #[test]
+    fn from_status() {
+        assert_transitions(State::Status, &[]);
+    }
+
+    #[test]
+    fn from_login() {
+        assert_transitions(State::Login, &[State::EncryptionPending, State::Play]);
+    }
+
+    #[test]
+    fn from_encryption_pending() {
+        assert_transitions(State::EncryptionPending, &[State::Play]);
+    }
+
+    #[test]
+    fn from_play() {
+        assert_transitions(State::Play, &[]);
+    }
+
+    #[test]
+    fn check_packet() {
+        assert_eq!(
+            State::EncryptionPending.check_packet(ProtocolState::Login),
+            Ok(())
+        );
+        assert_eq!(
+            State::Login.check_packet(ProtocolState::Play),
+            Err(WrongState {
+                state: State::Login,
+                packet: ProtocolState::Play,
+            })
+        );
+    }
+}
@@ -0,0 +1,7 @@
+//! Network services which run alongside the game
+//! listener, used by administration and monitoring tools.
+
+pub mod capture;
+pub mod query;
+pub mod rcon;
+pub mod sniffer;
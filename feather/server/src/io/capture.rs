@@ -0,0 +1,84 @@
+//! Records the packets of each connection to a capture file
+//! when enabled with the `[capture]` config section.
+//!
+//! Captures can be replayed with `protocol::capture::Replay`
+//! to reproduce protocol bugs in tests.
+
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    net::SocketAddr,
+    path::Path,
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+use protocol::{
+    capture::{CaptureWriter, Direction},
+    ProtocolState,
+};
+
+use crate::initial_handler::State;
+
+/// Capture of a connection, shared by its reader and writer.
+#[derive(Clone)]
+pub struct Capture {
+    inner: Arc<Mutex<Inner>>,
+}
+
+struct Inner {
+    /// `None` once writing failed.
+    writer: Option<CaptureWriter<BufWriter<File>>>,
+    state: ProtocolState,
+}
+
+impl Capture {
+    /// Starts capturing a connection from `addr`
+    /// to a new file in `dir`.
+    pub fn create(dir: &Path, addr: SocketAddr) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file_name = format!(
+            "{}-{}.fcap",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            addr.to_string().replace(':', "_")
+        );
+        let path = dir.join(file_name);
+        let writer = CaptureWriter::new(BufWriter::new(File::create(&path)?))?;
+        log::debug!("Capturing packets from {} to {}", addr, path.display());
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(Inner {
+                writer: Some(writer),
+                state: ProtocolState::Handshake,
+            })),
+        })
+    }
+
+    /// Sets the state in which the following packets are sent.
+    pub fn set_state(&self, state: State) {
+        self.inner.lock().state = state.protocol_state();
+    }
+
+    /// Records a packet. `data` is the packet ID followed by the packet's fields.
+    pub fn record(&self, direction: Direction, data: &[u8]) {
+        let mut inner = self.inner.lock();
+        let state = inner.state;
+        if let Some(writer) = &mut inner.writer {
+            if let Err(e) = writer.record(direction, state, data) {
+                log::warn!("Stopped capturing packets: {}", e);
+                inner.writer = None;
+            }
+        }
+    }
+
+    /// Writes recorded packets to the file.
+    pub fn flush(&self) {
+        let mut inner = self.inner.lock();
+        if let Some(writer) = &mut inner.writer {
+            if let Err(e) = writer.flush() {
+                log::warn!("Stopped capturing packets: {}", e);
+                inner.writer = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! The GameSpy 4 query protocol, which server lists and
+//! monitoring tools use to fetch a server's status and
+//! player list over UDP.
+//!
+//! Responses are built from a [`QueryStatus`] snapshot
+//! which the game thread refreshes every second, so the
+//! query task never waits on the game.
+
+use std::{
+    cell::RefCell,
+    net::SocketAddr,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use anyhow::bail;
+use common::Game;
+use ecs::{SysResult, SystemExecutor};
+use parking_lot::RwLock;
+use plugin_host::PluginManager;
+use quill_common::components::Name;
+use tokio::net::UdpSocket;
+
+use crate::{initial_handler::SERVER_NAME, options::Options, player_count::PlayerCount, ClientId};
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const TYPE_HANDSHAKE: u8 = 9;
+const TYPE_STAT: u8 = 0;
+
+/// How long a challenge token stays valid.
+const CHALLENGE_EXPIRY: Duration = Duration::from_secs(30);
+
+/// How often the game thread refreshes the [`QueryStatus`].
+const STATUS_UPDATE_INTERVAL: u64 = 20;
+
+/// Snapshot of the server state reported to queries.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Default)]
+pub struct QueryStatus {
+    inner: Arc<RwLock<StatusSnapshot>>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct StatusSnapshot {
+    players: Vec<String>,
+    plugins: String,
+}
+
+/// Starts answering queries on `port`.
+pub async fn start(
+    options: Arc<Options>,
+    port: u16,
+    player_count: PlayerCount,
+) -> anyhow::Result<QueryStatus> {
+    let address = format!("{}:{}", options.bind_address, port);
+    let socket = UdpSocket::bind(&address).await?;
+    let status = QueryStatus::default();
+    let server = QueryServer {
+        socket,
+        options,
+        player_count,
+        status: status.clone(),
+        challenges: AHashMap::new(),
+    };
+    tokio::task::spawn(server.run());
+    log::info!("Query is listening on {} (UDP)", address);
+    Ok(status)
+}
+
+struct QueryServer {
+    socket: UdpSocket,
+    options: Arc<Options>,
+    player_count: PlayerCount,
+    status: QueryStatus,
+    /// Challenge tokens handed out to each address.
+    challenges: AHashMap<SocketAddr, (i32, Instant)>,
+}
+
+impl QueryServer {
+    async fn run(mut self) {
+        let mut buf = [0; 1460];
+        loop {
+            let (len, addr) = match self.socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    log::error!("Failed to receive query packet: {:?}", e);
+                    continue;
+                }
+            };
+            match self.handle_packet(&buf[..len], addr, Instant::now()) {
+                Ok(Some(response)) => {
+                    if let Err(e) = self.socket.send_to(&response, addr).await {
+                        log::debug!("Failed to send query response to {}: {:?}", addr, e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => log::trace!("Ignoring query packet from {}: {:?}", addr, e),
+            }
+        }
+    }
+
+    fn handle_packet(
+        &mut self,
+        packet: &[u8],
+        addr: SocketAddr,
+        now: Instant,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        if packet.len() < 7 || packet[..2] != MAGIC {
+            bail!("not a query packet");
+        }
+        let kind = packet[2];
+        let session_id = read_i32(&packet[3..7]);
+
+        match kind {
+            TYPE_HANDSHAKE => {
+                self.challenges.retain(|_, (_, issued)| {
+                    now.saturating_duration_since(*issued) < CHALLENGE_EXPIRY
+                });
+                let token = rand::random::<i32>() & 0x7FFF_FFFF;
+                self.challenges.insert(addr, (token, now));
+                Ok(Some(handshake_response(session_id, token)))
+            }
+            TYPE_STAT if packet.len() >= 11 => {
+                let token = read_i32(&packet[7..11]);
+                match self.challenges.get(&addr) {
+                    Some(&(expected, issued))
+                        if expected == token
+                            && now.saturating_duration_since(issued) < CHALLENGE_EXPIRY => {}
+                    _ => bail!("invalid challenge token"),
+                }
+
+                let info = self.server_info();
+                // Full stat requests are padded to 15 bytes.
+                if packet.len() >= 15 {
+                    Ok(Some(full_stat_response(session_id, &info)))
+                } else {
+                    Ok(Some(basic_stat_response(session_id, &info)))
+                }
+            }
+            _ => bail!("unknown packet type {}", kind),
+        }
+    }
+
+    fn server_info(&self) -> ServerInfo {
+        let snapshot = self.status.inner.read().clone();
+        ServerInfo {
+            motd: self.options.motd.to_legacy_string(),
+            num_players: self.player_count.get(),
+            max_players: self.options.max_players,
+            host_ip: self.options.bind_address.clone(),
+            host_port: self.options.port,
+            players: snapshot.players,
+            plugins: snapshot.plugins,
+        }
+    }
+}
+
+struct ServerInfo {
+    motd: String,
+    num_players: u32,
+    max_players: u32,
+    host_ip: String,
+    host_port: u16,
+    players: Vec<String>,
+    plugins: String,
+}
+
+/// Name of the world reported to queries.
+const MAP: &str = "world";
+
+fn read_i32(bytes: &[u8]) -> i32 {
+    i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn write_header(out: &mut Vec<u8>, kind: u8, session_id: i32) {
+    out.push(kind);
+    // Like vanilla, only the lower four bits of each
+    // byte of the session ID are significant.
+    out.extend_from_slice(&(session_id & 0x0F0F_0F0F).to_be_bytes());
+}
+
+fn write_string(out: &mut Vec<u8>, string: &str) {
+    out.extend_from_slice(string.as_bytes());
+    out.push(0);
+}
+
+fn handshake_response(session_id: i32, token: i32) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_header(&mut out, TYPE_HANDSHAKE, session_id);
+    write_string(&mut out, &token.to_string());
+    out
+}
+
+fn basic_stat_response(session_id: i32, info: &ServerInfo) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_header(&mut out, TYPE_STAT, session_id);
+    write_string(&mut out, &info.motd);
+    write_string(&mut out, "SMP");
+    write_string(&mut out, MAP);
+    write_string(&mut out, &info.num_players.to_string());
+    write_string(&mut out, &info.max_players.to_string());
+    out.extend_from_slice(&info.host_port.to_le_bytes());
+    write_string(&mut out, &info.host_ip);
+    out
+}
+
+fn full_stat_response(session_id: i32, info: &ServerInfo) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_header(&mut out, TYPE_STAT, session_id);
+    // Constant padding expected by clients.
+    out.extend_from_slice(b"splitnum\0\x80\0");
+
+    let version = SERVER_NAME.rsplit(' ').next().unwrap_or_default();
+    let pairs = [
+        ("hostname", info.motd.clone()),
+        ("gametype", "SMP".to_owned()),
+        ("game_id", "MINECRAFT".to_owned()),
+        ("version", version.to_owned()),
+        ("plugins", info.plugins.clone()),
+        ("map", MAP.to_owned()),
+        ("numplayers", info.num_players.to_string()),
+        ("maxplayers", info.max_players.to_string()),
+        ("hostport", info.host_port.to_string()),
+        ("hostip", info.host_ip.clone()),
+    ];
+    for (key, value) in pairs.iter() {
+        write_string(&mut out, key);
+        write_string(&mut out, value);
+    }
+    out.push(0);
+
+    out.extend_from_slice(b"\x01player_\0\0");
+    for player in &info.players {
+        write_string(&mut out, player);
+    }
+    out.push(0);
+    out
+}
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>, status: QueryStatus) {
+    game.insert_resource(status);
+    systems.add_system(update_query_status);
+}
+
+fn update_query_status(game: &mut Game) -> SysResult {
+    if game.tick_count % STATUS_UPDATE_INTERVAL != 0 {
+        return Ok(());
+    }
+
+    let players = game
+        .ecs
+        .query::<(&Name, &ClientId)>()
+        .iter()
+        .map(|(_, (name, _))| name.to_string())
+        .collect();
+
+    // Formatted like Bukkit: "<server>: <plugin> <version>; ..."
+    let mut plugins = SERVER_NAME.to_owned();
+    if let Ok(manager) = game.resources.get::<Rc<RefCell<PluginManager>>>() {
+        let list: Vec<String> = manager
+            .borrow()
+            .plugins()
+            .map(|plugin| format!("{} {}", plugin.metadata().name, plugin.metadata().version))
+            .collect();
+        if !list.is_empty() {
+            plugins.push_str(": ");
+            plugins.push_str(&list.join("; "));
+        }
+    }
+
+    let status = game.resources.get::<QueryStatus>()?;
+    *status.inner.write() = StatusSnapshot { players, plugins };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base::Text;
+
+    use super::*;
+
+    fn info() -> ServerInfo {
+        ServerInfo {
+            motd: Text::from("A Feather server"),
+            num_players: 1,
+            max_players: 16,
+            host_ip: "0.0.0.0".to_owned(),
+            host_port: 25565,
+            players: vec!["Notch".to_owned()],
+            plugins: SERVER_NAME.to_owned(),
+        }
+    }
+
+    #[test]
+    fn handshake_response_format() {
+        let response = handshake_response(0x1F2F3F4F, 9513307);
+        assert_eq!(response, b"\x09\x0F\x0F\x0F\x0F9513307\0");
+    }
+
+    #[test]
+    fn basic_stat_format() {
+        let response = basic_stat_response(1, &info());
+        let mut expected = b"\x00\x00\x00\x00\x01A Feather server\0SMP\0world\01\016\0".to_vec();
+        expected.extend_from_slice(&25565u16.to_le_bytes());
+        expected.extend_from_slice(b"0.0.0.0\0");
+        assert_eq!(response, expected);
+    }
+
+    #[test]
+    fn full_stat_ends_with_players() {
+        let response = full_stat_response(1, &info());
+        assert!(response.ends_with(b"\x01player_\0\0Notch\0\0"));
+        assert!(response
+            .windows(b"numplayers\x001\0".len())
+            .any(|window| window == b"numplayers\x001\0"));
+    }
+}
@@ -0,0 +1,304 @@
+//! A listener for the Source RCON protocol, which hosting
+//! panels and administration tools use to run commands remotely.
+//!
+//! Received commands are forwarded to the game thread and
+//! executed like console commands. Their feedback is sent
+//! back to the RCON client as plain text.
+
+use std::net::SocketAddr;
+
+use anyhow::bail;
+use common::{chat::ChatPreference, permissions::Permissions, ChatBox, Game};
+use ecs::{Entity, EntityBuilder, SysResult, SystemExecutor};
+use flume::{Receiver, Sender};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::oneshot,
+};
+
+const SERVERDATA_AUTH: i32 = 3;
+const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+const SERVERDATA_EXECCOMMAND: i32 = 2;
+const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+
+/// Request ID sent in the auth response when the password is wrong.
+const AUTH_FAILED_ID: i32 = -1;
+
+/// Number of wrong passwords after which a connection is closed.
+const MAX_AUTH_ATTEMPTS: u32 = 3;
+
+/// Maximum length of a packet sent by a client, like vanilla.
+const MAX_INCOMING_LENGTH: usize = 1446;
+
+/// Responses longer than this are split into several packets.
+const MAX_RESPONSE_BODY: usize = 4096;
+
+/// A command received over RCON, waiting to
+/// be executed on the game thread.
+pub struct RconCommand {
+    command: String,
+    response: oneshot::Sender<String>,
+}
+
+/// Starts the RCON listener. Commands it receives
+/// are sent on the returned channel.
+pub async fn start(
+    bind_address: &str,
+    port: u16,
+    password: String,
+) -> anyhow::Result<Receiver<RconCommand>> {
+    let listener = TcpListener::bind(format!("{}:{}", bind_address, port)).await?;
+    let (commands_tx, commands) = flume::unbounded();
+    tokio::task::spawn(accept_connections(listener, password, commands_tx));
+    log::info!("RCON is listening on {}:{}", bind_address, port);
+    Ok(commands)
+}
+
+async fn accept_connections(
+    listener: TcpListener,
+    password: String,
+    commands: Sender<RconCommand>,
+) {
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                log::error!("Failed to accept RCON connection: {:?}", e);
+                continue;
+            }
+        };
+        let password = password.clone();
+        let commands = commands.clone();
+        tokio::task::spawn(async move {
+            if let Err(e) = handle_connection(stream, addr, &password, commands).await {
+                log::debug!("RCON connection from {} closed: {:?}", addr, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    password: &str,
+    commands: Sender<RconCommand>,
+) -> anyhow::Result<()> {
+    let mut authenticated = false;
+    let mut failed_attempts = 0;
+    loop {
+        let packet = read_packet(&mut stream).await?;
+        match packet.kind {
+            SERVERDATA_AUTH => {
+                authenticated = passwords_match(packet.body.as_bytes(), password.as_bytes());
+                let id = if authenticated {
+                    log::info!("RCON client {} authenticated", addr);
+                    packet.id
+                } else {
+                    log::warn!("RCON client {} sent a wrong password", addr);
+                    failed_attempts += 1;
+                    AUTH_FAILED_ID
+                };
+                write_packet(&mut stream, id, SERVERDATA_AUTH_RESPONSE, "").await?;
+                if failed_attempts >= MAX_AUTH_ATTEMPTS {
+                    bail!("too many wrong passwords");
+                }
+            }
+            SERVERDATA_EXECCOMMAND if authenticated => {
+                log::info!("RCON client {} issued command: {}", addr, packet.body);
+                let (response_tx, response) = oneshot::channel();
+                commands
+                    .send_async(RconCommand {
+                        command: packet.body,
+                        response: response_tx,
+                    })
+                    .await?;
+                let output = response.await?;
+                for chunk in split_response(&output) {
+                    write_packet(&mut stream, packet.id, SERVERDATA_RESPONSE_VALUE, chunk).await?;
+                }
+            }
+            kind if !authenticated => bail!("packet of type {} before authenticating", kind),
+            kind => bail!("unknown packet type {}", kind),
+        }
+    }
+}
+
+/// Compares passwords in time independent of where they differ,
+/// so the password can't be guessed byte by byte from response times.
+/// Only the length is leaked.
+fn passwords_match(given: &[u8], expected: &[u8]) -> bool {
+    if given.len() != expected.len() {
+        return false;
+    }
+    given
+        .iter()
+        .zip(expected)
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Packet {
+    id: i32,
+    kind: i32,
+    body: String,
+}
+
+async fn read_packet(stream: &mut TcpStream) -> anyhow::Result<Packet> {
+    let length = stream.read_i32_le().await?;
+    if length < 10 || length as usize > MAX_INCOMING_LENGTH {
+        bail!("invalid packet length {}", length);
+    }
+    let mut payload = vec![0; length as usize];
+    stream.read_exact(&mut payload).await?;
+    decode_payload(&payload)
+}
+
+/// Decodes a packet after its length prefix.
+fn decode_payload(payload: &[u8]) -> anyhow::Result<Packet> {
+    if payload.len() < 10 {
+        bail!("packet too short");
+    }
+    let id = i32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+    let kind = i32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]);
+    // The body is terminated by a null byte and followed by another.
+    let body = &payload[8..payload.len() - 2];
+    let body = match body.iter().position(|&b| b == 0) {
+        Some(end) => &body[..end],
+        None => body,
+    };
+    Ok(Packet {
+        id,
+        kind,
+        body: String::from_utf8_lossy(body).into_owned(),
+    })
+}
+
+fn encode_packet(id: i32, kind: i32, body: &str) -> Vec<u8> {
+    let length = 4 + 4 + body.len() + 2;
+    let mut bytes = Vec::with_capacity(4 + length);
+    bytes.extend_from_slice(&(length as i32).to_le_bytes());
+    bytes.extend_from_slice(&id.to_le_bytes());
+    bytes.extend_from_slice(&kind.to_le_bytes());
+    bytes.extend_from_slice(body.as_bytes());
+    bytes.extend_from_slice(&[0, 0]);
+    bytes
+}
+
+async fn write_packet(
+    stream: &mut TcpStream,
+    id: i32,
+    kind: i32,
+    body: &str,
+) -> anyhow::Result<()> {
+    stream.write_all(&encode_packet(id, kind, body)).await?;
+    Ok(())
+}
+
+/// Splits a response into chunks of at most
+/// [`MAX_RESPONSE_BODY`] bytes on character boundaries.
+/// An empty response yields one empty chunk.
+fn split_response(mut response: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    while response.len() > MAX_RESPONSE_BODY {
+        let mut end = MAX_RESPONSE_BODY;
+        while !response.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, rest) = response.split_at(end);
+        chunks.push(chunk);
+        response = rest;
+    }
+    chunks.push(response);
+    chunks
+}
+
+/// Marker component for the entity that executes RCON commands.
+struct RconSender;
+
+/// Resource holding received RCON commands.
+struct RconCommands {
+    commands: Receiver<RconCommand>,
+    sender: Entity,
+}
+
+pub fn register(
+    game: &mut Game,
+    systems: &mut SystemExecutor<Game>,
+    commands: Receiver<RconCommand>,
+) {
+    // Command feedback is collected in this entity's chat box,
+    // like the console entity. Anyone with the password
+    // may run every command.
+    let mut sender = EntityBuilder::new();
+    sender
+        .add(RconSender)
+        .add(ChatBox::new(ChatPreference::System))
+        .add(Permissions::new(vec!["*".to_owned()]));
+    let sender = game.ecs.spawn(sender.build());
+
+    game.insert_resource(RconCommands { commands, sender });
+    systems.add_system(execute_rcon_commands);
+}
+
+fn execute_rcon_commands(game: &mut Game) -> SysResult {
+    let (commands, sender) = {
+        let rcon = game.resources.get::<RconCommands>()?;
+        (rcon.commands.clone(), rcon.sender)
+    };
+    for command in commands.try_iter() {
+        common::commands::execute(game, sender, command.command.trim_start_matches('/'))?;
+
+        let mut chat_box = game.ecs.get_mut::<ChatBox>(sender)?;
+        let output: Vec<String> = chat_box
+            .drain()
+            .map(|message| message.text().to_plain_string())
+            .collect();
+        // The client may have disconnected while waiting.
+        let _ = command.response.send(output.join("\n"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_round_trip() {
+        let bytes = encode_packet(7, SERVERDATA_EXECCOMMAND, "list");
+        assert_eq!(&bytes[..4], &14i32.to_le_bytes());
+        assert_eq!(
+            decode_payload(&bytes[4..]).unwrap(),
+            Packet {
+                id: 7,
+                kind: SERVERDATA_EXECCOMMAND,
+                body: "list".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn compares_passwords() {
+        assert!(passwords_match(b"hunter2", b"hunter2"));
+        assert!(!passwords_match(b"hunter3", b"hunter2"));
+        assert!(!passwords_match(b"hunter", b"hunter2"));
+        assert!(!passwords_match(b"", b"hunter2"));
+    }
+
+    #[test]
+    fn rejects_short_packet() {
+        assert!(decode_payload(&[0; 9]).is_err());
+    }
+
+    #[test]
+    fn splits_long_responses() {
+        let response = "é".repeat(MAX_RESPONSE_BODY);
+        let chunks = split_response(&response);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= MAX_RESPONSE_BODY));
+        assert_eq!(chunks.concat(), response);
+        assert_eq!(split_response(""), vec![""]);
+    }
+}
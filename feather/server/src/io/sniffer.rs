@@ -0,0 +1,209 @@
+//! Packet sniffer, logging the packets sent and received by
+//! each connection. Enabled with the `[sniffer]` config section
+//! to debug protocol mismatches with vanilla clients.
+//!
+//! Packets are logged with the `sniffer` target, along with
+//! their ID and size before compression and encryption.
+
+use std::{
+    fmt::{self, Debug, Write},
+    io::Cursor,
+    sync::Arc,
+};
+
+use protocol::{ProtocolVersion, Readable, VarInt};
+
+/// Settings of the packet sniffer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnifferOptions {
+    /// Whether to also log the contents of packets.
+    pub hex_dump: bool,
+    /// Names of packets to log, or empty to log all packets.
+    pub include: Vec<String>,
+    /// Names of packets not to log.
+    pub exclude: Vec<String>,
+}
+
+impl SnifferOptions {
+    /// Returns whether packets called `name` are logged.
+    pub fn logs(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|included| included == name))
+            && !self.exclude.iter().any(|excluded| excluded == name)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent by the client.
+    Inbound,
+    /// Sent by the server.
+    Outbound,
+}
+
+/// Logs the packets going in one direction of a connection.
+pub struct Sniffer {
+    options: Arc<SnifferOptions>,
+    direction: Direction,
+    /// Identifies the connection in logs.
+    connection: String,
+}
+
+impl Sniffer {
+    pub fn new(options: Arc<SnifferOptions>, direction: Direction, connection: String) -> Self {
+        Self {
+            options,
+            direction,
+            connection,
+        }
+    }
+
+    /// Changes how the connection is identified in logs,
+    /// e.g. once the player's name is known.
+    pub fn set_connection(&mut self, connection: String) {
+        self.connection = connection;
+    }
+
+    /// Returns the name of `packet` if it's logged, so callers
+    /// only encode the packets which pass the filters.
+    pub fn filter(&self, packet: &impl Debug) -> Option<String> {
+        let name = packet_name(packet);
+        if self.options.logs(&name) {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    /// Logs a packet called `name` which passed [`filter`](Self::filter).
+    /// `data` is the packet ID followed by the packet's fields.
+    pub fn log(&self, name: &str, data: &[u8]) {
+        let id = VarInt::read(&mut Cursor::new(data), ProtocolVersion::LATEST)
+            .map(|id| id.0)
+            .unwrap_or(-1);
+        let direction = match self.direction {
+            Direction::Inbound => "C->S",
+            Direction::Outbound => "S->C",
+        };
+        log::info!(
+            target: "sniffer",
+            "[{}] {} {} (0x{:02X}, {} bytes)",
+            self.connection,
+            direction,
+            name,
+            id,
+            data.len()
+        );
+        if self.options.hex_dump {
+            log::info!(target: "sniffer", "\n{}", hex_dump(data));
+        }
+    }
+}
+
+/// Gets the name of a packet from its `Debug` representation,
+/// e.g. `PlayerPosition` for `PlayerPosition(PlayerPosition { .. })`.
+fn packet_name(packet: &impl Debug) -> String {
+    let mut name = NameWriter(String::new());
+    // Fails once the name is written, so that the
+    // rest of the packet isn't formatted.
+    let _ = write!(name, "{:?}", packet);
+    name.0
+}
+
+/// Writer keeping the leading identifier of what's written to it.
+struct NameWriter(String);
+
+impl Write for NameWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match s.find(|c: char| !c.is_alphanumeric() && c != '_') {
+            Some(end) => {
+                self.0.push_str(&s[..end]);
+                Err(fmt::Error)
+            }
+            None => {
+                self.0.push_str(s);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Formats bytes as lines of 16 bytes in hexadecimal,
+/// each followed by their ASCII representation.
+fn hex_dump(data: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, bytes) in data.chunks(16).enumerate() {
+        if line != 0 {
+            dump.push('\n');
+        }
+        write!(dump, "{:08x} ", line * 16).unwrap();
+        for i in 0..16 {
+            match bytes.get(i) {
+                Some(byte) => write!(dump, " {:02x}", byte).unwrap(),
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        for &byte in bytes {
+            dump.push(if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            });
+        }
+        dump.push('|');
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use protocol::{packets::client::Ping, ClientPlayPacket};
+
+    use super::*;
+
+    #[test]
+    fn filters() {
+        let options = SnifferOptions {
+            hex_dump: false,
+            include: Vec::new(),
+            exclude: vec!["KeepAlive".to_owned()],
+        };
+        assert!(options.logs("ChatMessage"));
+        assert!(!options.logs("KeepAlive"));
+
+        let options = SnifferOptions {
+            include: vec!["ChatMessage".to_owned()],
+            ..options
+        };
+        assert!(options.logs("ChatMessage"));
+        assert!(!options.logs("PlayerPosition"));
+    }
+
+    #[test]
+    fn packet_names() {
+        let packet = ClientPlayPacket::KeepAlive(protocol::packets::client::KeepAlive { id: 5 });
+        assert_eq!(packet_name(&packet), "KeepAlive");
+        assert_eq!(packet_name(&Ping { payload: 0 }), "Ping");
+    }
+
+    #[test]
+    fn excluded_packets_are_filtered() {
+        let options = SnifferOptions {
+            exclude: vec!["Ping".to_owned()],
+            ..Default::default()
+        };
+        let sniffer = Sniffer::new(Arc::new(options), Direction::Inbound, String::new());
+        assert_eq!(sniffer.filter(&Ping { payload: 0 }), None);
+        let packet = ClientPlayPacket::KeepAlive(protocol::packets::client::KeepAlive { id: 5 });
+        assert_eq!(sniffer.filter(&packet).as_deref(), Some("KeepAlive"));
+    }
+
+    #[test]
+    fn hex_dumps() {
+        assert_eq!(
+            hex_dump(b"\x0ffeather!"),
+            "00000000  0f 66 65 61 74 68 65 72 21                       |.feather!|"
+        );
+        assert_eq!(hex_dump(&[0; 17]).lines().count(), 2);
+    }
+}
@@ -0,0 +1,172 @@
+//! Keep-alive handling for connections in the Play state.
+//!
+//! Each connection's worker sends a Keep Alive packet every
+//! [`KEEP_ALIVE_INTERVAL`] and disconnects the client if it
+//! doesn't reply within [`KEEP_ALIVE_TIMEOUT`]. Replies are used
+//! to measure the client's round-trip latency.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use thiserror::Error;
+
+/// How often a Keep Alive packet is sent.
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a client has to reply to a Keep Alive packet.
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+#[error("timed out: no keep alive reply in {} seconds", KEEP_ALIVE_TIMEOUT.as_secs())]
+pub struct KeepAliveTimedOut;
+
+/// Keep-alive state of a connection.
+///
+/// Shared between the connection's worker tasks and its
+/// [`Client`](crate::Client). Can be cloned to create a new handle.
+#[derive(Clone, Debug)]
+pub struct KeepAlive {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    next_id: i64,
+    /// The ID and send time of the Keep Alive
+    /// the client has yet to reply to.
+    pending: Option<(i64, Instant)>,
+    last_sent: Option<Instant>,
+    latency: Option<Duration>,
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeepAlive {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                next_id: 0,
+                pending: None,
+                last_sent: None,
+                latency: None,
+            })),
+        }
+    }
+
+    /// Called periodically by the worker. Returns the ID of
+    /// a Keep Alive packet to send, if one is due.
+    ///
+    /// Returns an error if the client failed to reply
+    /// to the previous Keep Alive in time.
+    pub fn poll(&self, now: Instant) -> Result<Option<i64>, KeepAliveTimedOut> {
+        let mut inner = self.inner.lock();
+        if let Some((_, sent)) = inner.pending {
+            if now.saturating_duration_since(sent) >= KEEP_ALIVE_TIMEOUT {
+                return Err(KeepAliveTimedOut);
+            }
+            return Ok(None);
+        }
+
+        let due = match inner.last_sent {
+            Some(last_sent) => now.saturating_duration_since(last_sent) >= KEEP_ALIVE_INTERVAL,
+            None => true,
+        };
+        if !due {
+            return Ok(None);
+        }
+
+        let id = inner.next_id;
+        inner.next_id = inner.next_id.wrapping_add(1);
+        inner.pending = Some((id, now));
+        inner.last_sent = Some(now);
+        Ok(Some(id))
+    }
+
+    /// Handles a Keep Alive reply from the client.
+    ///
+    /// Replies that don't match the pending Keep Alive are ignored.
+    pub fn on_reply(&self, id: i64, now: Instant) {
+        let mut inner = self.inner.lock();
+        let sent = match inner.pending {
+            Some((pending_id, sent)) if pending_id == id => sent,
+            _ => return,
+        };
+        inner.pending = None;
+
+        // Smooth the latency like vanilla does, so that
+        // one slow reply doesn't cause the tab list ping to jump.
+        let sample = now.saturating_duration_since(sent);
+        inner.latency = Some(match inner.latency {
+            Some(latency) => (latency * 3 + sample) / 4,
+            None => sample,
+        });
+    }
+
+    /// Gets the measured round-trip latency, or `None`
+    /// if the client hasn't replied to a Keep Alive yet.
+    pub fn latency(&self) -> Option<Duration> {
+        self.inner.lock().latency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_at_interval_after_reply() {
+        let keep_alive = KeepAlive::new();
+        let start = Instant::now();
+        let id = keep_alive.poll(start).unwrap().unwrap();
+        assert_eq!(keep_alive.poll(start).unwrap(), None);
+
+        keep_alive.on_reply(id, start + Duration::from_millis(40));
+        assert_eq!(keep_alive.latency(), Some(Duration::from_millis(40)));
+        assert_eq!(
+            keep_alive.poll(start + Duration::from_secs(1)).unwrap(),
+            None
+        );
+        assert_eq!(
+            keep_alive.poll(start + KEEP_ALIVE_INTERVAL).unwrap(),
+            Some(id + 1)
+        );
+    }
+
+    #[test]
+    fn ignores_mismatched_reply() {
+        let keep_alive = KeepAlive::new();
+        let start = Instant::now();
+        let id = keep_alive.poll(start).unwrap().unwrap();
+        keep_alive.on_reply(id + 5, start);
+        assert_eq!(keep_alive.latency(), None);
+    }
+
+    #[test]
+    fn times_out_without_reply() {
+        let keep_alive = KeepAlive::new();
+        let start = Instant::now();
+        keep_alive.poll(start).unwrap();
+        assert!(keep_alive.poll(start + Duration::from_secs(29)).is_ok());
+        assert!(keep_alive.poll(start + KEEP_ALIVE_TIMEOUT).is_err());
+    }
+
+    #[test]
+    fn smooths_latency() {
+        let keep_alive = KeepAlive::new();
+        let start = Instant::now();
+        let id = keep_alive.poll(start).unwrap().unwrap();
+        keep_alive.on_reply(id, start + Duration::from_millis(100));
+
+        let start = start + KEEP_ALIVE_INTERVAL;
+        let id = keep_alive.poll(start).unwrap().unwrap();
+        keep_alive.on_reply(id, start + Duration::from_millis(20));
+        assert_eq!(keep_alive.latency(), Some(Duration::from_millis(80)));
+    }
+}
@@ -0,0 +1,119 @@
+//! Persistence of world settings in the world's `level.dat`.
+//!
+//! The difficulty and game rules are stored under their vanilla
+//! keys, so worlds keep them when opened by a vanilla server.
+//! PvP has no vanilla key and is stored as `FeatherPvp`. Other
+//! keys in the file are left as they were.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader},
+    path::Path,
+};
+
+use anyhow::Context;
+use base::{Difficulty, GameRules};
+use common::Game;
+use serde::{Deserialize, Serialize};
+
+const LEVEL_FILE: &str = "level.dat";
+
+/// The root compound of `level.dat`.
+#[derive(Default, Serialize, Deserialize)]
+struct Root {
+    #[serde(rename = "Data", default)]
+    data: HashMap<String, nbt::Value>,
+}
+
+fn read(world_dir: &Path) -> anyhow::Result<Option<Root>> {
+    let path = world_dir.join(LEVEL_FILE);
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let root = nbt::from_gzip_reader(BufReader::new(file))
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(root))
+}
+
+/// Applies the settings saved in `world_dir` to the game's world.
+/// A missing `level.dat` keeps the configured settings.
+pub fn load_world_settings(game: &mut Game, world_dir: &Path) -> anyhow::Result<()> {
+    let root = match read(world_dir)? {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+    let settings = game.world.settings_mut();
+    if let Some(nbt::Value::Byte(id)) = root.data.get("Difficulty") {
+        if let Some(difficulty) = Difficulty::from_id(*id as u8) {
+            settings.difficulty = difficulty;
+        }
+    }
+    if let Some(nbt::Value::Byte(pvp)) = root.data.get("FeatherPvp") {
+        settings.pvp = *pvp != 0;
+    }
+    if let Some(nbt::Value::Compound(rules)) = root.data.get("GameRules") {
+        for (name, value) in rules {
+            if let nbt::Value::String(value) = value {
+                // Rules unknown to this version are dropped.
+                let _ = settings.game_rules.set(name, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Saves the settings of the game's world to `world_dir`.
+///
+/// Fails without writing if the existing `level.dat`
+/// can't be parsed, so its other data isn't lost.
+pub fn save_world_settings(game: &Game, world_dir: &Path) -> anyhow::Result<()> {
+    let mut root = read(world_dir)?.unwrap_or_default();
+    let settings = game.world.settings();
+    root.data.insert(
+        "Difficulty".to_owned(),
+        nbt::Value::Byte(settings.difficulty as i8),
+    );
+    root.data.insert(
+        "FeatherPvp".to_owned(),
+        nbt::Value::Byte(settings.pvp as i8),
+    );
+    let rules = GameRules::NAMES
+        .iter()
+        .filter_map(|&name| {
+            let value = settings.game_rules.get(name)?;
+            Some((name.to_owned(), nbt::Value::String(value)))
+        })
+        .collect();
+    root.data
+        .insert("GameRules".to_owned(), nbt::Value::Compound(rules));
+
+    let mut bytes = Vec::new();
+    nbt::to_gzip_writer(&mut bytes, &root, None)?;
+    fs::create_dir_all(world_dir)?;
+    fs::write(world_dir.join(LEVEL_FILE), bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip() {
+        let dir = std::env::temp_dir().join(format!("feather-level-{}", std::process::id()));
+        let mut game = Game::new();
+        let settings = game.world.settings_mut();
+        settings.difficulty = Difficulty::Hard;
+        settings.pvp = false;
+        settings.game_rules.do_mob_spawning = false;
+        save_world_settings(&game, &dir).unwrap();
+
+        let mut loaded = Game::new();
+        load_world_settings(&mut loaded, &dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.world.settings(), game.world.settings());
+    }
+}
@@ -2,33 +2,55 @@
 
 use std::{sync::Arc, time::Instant};
 
+use anyhow::Context;
 use base::Position;
+use chunk_packet_cache::ChunkPacketCache;
 use chunk_subscriptions::ChunkSubscriptions;
 use common::Game;
 use ecs::SystemExecutor;
 use flume::Receiver;
-use initial_handler::NewPlayer;
+use initial_handler::{NewPlayer, PluginPreLogin};
+use io::{query::QueryStatus, rcon::RconCommand};
 use listener::Listener;
+pub use listener::ListenerHandle;
+use uuid::Uuid;
 
+mod anti_xray;
+mod chunk_packet_cache;
 mod chunk_subscriptions;
 pub mod client;
 pub mod config;
 mod connection_worker;
+pub mod economy;
 mod entities;
 pub mod favicon;
 mod initial_handler;
+mod io;
+mod keep_alive;
+pub mod level;
 mod listener;
 mod network_id_registry;
 mod options;
 mod packet_handlers;
+pub mod permissions;
 mod player_count;
+pub mod regions;
+mod status_cache;
+pub mod storage;
 mod systems;
+pub mod tick_times;
+mod traffic;
+pub mod user_cache;
 
 pub use client::{Client, ClientId, Clients};
+pub use initial_handler::{Hooks, PreLogin};
 pub use network_id_registry::NetworkId;
 pub use options::Options;
 use player_count::PlayerCount;
+use status_cache::StatusCache;
 use systems::view::WaitingChunks;
+pub use traffic::Traffic;
+use traffic::TrafficStats;
 
 /// A Minecraft server.
 ///
@@ -41,40 +63,98 @@ pub struct Server {
     options: Arc<Options>,
     clients: Clients,
     new_players: Receiver<NewPlayer>,
+    listener: Option<ListenerHandle>,
+    /// Commands received over RCON, taken
+    /// when the server is linked with a `Game`.
+    rcon_commands: Option<Receiver<RconCommand>>,
+    /// Status reported to GameSpy 4 queries, taken
+    /// when the server is linked with a `Game`.
+    query_status: Option<QueryStatus>,
+    /// Pre-logins waiting for plugins, taken
+    /// when the server is linked with a `Game`.
+    plugin_pre_logins: Option<Receiver<PluginPreLogin>>,
 
     waiting_chunks: WaitingChunks,
     chunk_subscriptions: ChunkSubscriptions,
 
-    last_keepalive_time: Instant,
-
     player_count: PlayerCount,
+    traffic: TrafficStats,
+
+    status_cache: StatusCache,
+    last_status_update_time: Instant,
+
+    chunk_packet_cache: ChunkPacketCache,
 }
 
 impl Server {
     /// Starts a server with the given `Options`.
     ///
     /// Must be called within the context of a Tokio runtime.
-    pub async fn bind(options: Options) -> anyhow::Result<Self> {
+    pub async fn bind(mut options: Options) -> anyhow::Result<Self> {
+        let plugin_pre_logins = options.hooks.forward_to_plugins();
         let options = Arc::new(options);
         let player_count = PlayerCount::new(options.max_players);
+        let status_cache = StatusCache::new(&options);
+        let traffic = TrafficStats::new();
+        let chunk_packet_cache = ChunkPacketCache::new();
 
         let (new_players_tx, new_players) = flume::bounded(4);
-        Listener::start(Arc::clone(&options), player_count.clone(), new_players_tx).await?;
+        let listener = Listener::start(
+            Arc::clone(&options),
+            player_count.clone(),
+            status_cache.clone(),
+            traffic.clone(),
+            chunk_packet_cache.clone(),
+            new_players_tx,
+        )
+        .await?;
 
         log::info!(
             "Server is listening on {}:{}",
             options.bind_address,
             options.port
         );
+        if let Some(path) = &options.bind_unix {
+            log::info!("Server is also listening on {}", path.display());
+        }
+
+        let rcon_commands = match options.rcon_port {
+            Some(_) if options.rcon_password.is_empty() => {
+                log::warn!("RCON is enabled but has no password set, so it will stay disabled");
+                None
+            }
+            Some(port) => Some(
+                io::rcon::start(&options.bind_address, port, options.rcon_password.clone())
+                    .await
+                    .context("failed to bind the RCON port")?,
+            ),
+            None => None,
+        };
+
+        let query_status = match options.query_port {
+            Some(port) => Some(
+                io::query::start(Arc::clone(&options), port, player_count.clone())
+                    .await
+                    .context("failed to bind the query port")?,
+            ),
+            None => None,
+        };
 
         Ok(Self {
             options,
             clients: Clients::new(),
             new_players,
+            listener: Some(listener),
+            rcon_commands,
+            query_status,
+            plugin_pre_logins: Some(plugin_pre_logins),
             waiting_chunks: WaitingChunks::default(),
             chunk_subscriptions: ChunkSubscriptions::default(),
-            last_keepalive_time: Instant::now(),
             player_count,
+            traffic,
+            status_cache,
+            last_status_update_time: Instant::now(),
+            chunk_packet_cache,
         })
     }
 
@@ -85,10 +165,35 @@ impl Server {
         game.add_entity_spawn_callback(entities::add_entity_components);
     }
 
+    /// Gets the options the server was created with.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Takes the handle to the server's listener,
+    /// used to shut down networking when the server stops.
+    ///
+    /// Returns `None` if the handle was already taken.
+    pub fn take_listener_handle(&mut self) -> Option<ListenerHandle> {
+        self.listener.take()
+    }
+
     /// Gets the number of online players.
     pub fn player_count(&self) -> u32 {
         self.player_count.get()
     }
+
+    /// Gets the traffic of all connections since the server started.
+    /// The traffic of each player is given by [`Client::traffic`].
+    pub fn traffic(&self) -> Traffic {
+        self.traffic.get()
+    }
+
+    /// Saves the player data of every online player,
+    /// for when the server stops before they leave.
+    pub fn save_player_data(&self, game: &Game) {
+        systems::player_data::save_all(game, &self.options.world_dir);
+    }
 }
 
 /// Low-level functions, mostly used internally.
@@ -143,8 +248,28 @@ impl Server {
         }
     }
 
-    pub fn broadcast_keepalive(&mut self) {
-        self.broadcast_with(|client| client.send_keepalive());
-        self.last_keepalive_time = Instant::now();
+    /// Like [`broadcast_nearby_with`](Self::broadcast_nearby_with),
+    /// but only for clients which see the entity with `network_id`
+    /// (see [`Client::sees_entity`]). Used for entity updates,
+    /// so clients don't learn about entities hidden from them.
+    pub fn broadcast_entity_update_with(
+        &self,
+        network_id: NetworkId,
+        position: Position,
+        mut callback: impl FnMut(&Client),
+    ) {
+        self.broadcast_nearby_with(position, |client| {
+            if client.sees_entity(network_id) {
+                callback(client);
+            }
+        });
+    }
+
+    /// Rebuilds the response sent to status pings, sampling
+    /// players from `players` (names and UUIDs).
+    pub fn update_status_cache(&mut self, players: &[(String, Uuid)]) {
+        self.status_cache
+            .update(&self.options, self.player_count.get(), players);
+        self.last_status_update_time = Instant::now();
     }
 }
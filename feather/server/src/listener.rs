@@ -1,61 +1,185 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Context;
 use flume::Sender;
-use tokio::net::{TcpListener, TcpStream};
+use parking_lot::Mutex;
+use tokio::{net::TcpListener, sync::Semaphore, task::JoinHandle};
 
 use crate::{
-    connection_worker::Worker, initial_handler::NewPlayer, options::Options,
-    player_count::PlayerCount,
+    chunk_packet_cache::ChunkPacketCache, connection_worker::Worker, initial_handler::NewPlayer,
+    options::Options, player_count::PlayerCount, status_cache::StatusCache, traffic::TrafficStats,
+};
+
+use self::{
+    shutdown::{ShutdownSignal, ShutdownTrigger},
+    stream::Stream,
+    throttle::ConnectionThrottle,
+    unix::UnixSocketListener,
 };
 
+pub(crate) mod proxy_protocol;
+pub(crate) mod shutdown;
+pub(crate) mod stream;
+pub(crate) mod throttle;
+mod unix;
+
 /// Listens for and accepts incoming connections.
+///
+/// Connections are dropped without a response if their
+/// IP address connected too often recently, or if too
+/// many connections are already in initial handling.
+///
+/// Connections can also be accepted on a Unix socket, for
+/// proxies on the same machine. These aren't throttled.
+///
+/// Behind a load balancer speaking the PROXY protocol, every
+/// connection comes from the balancer, so connections are
+/// throttled by the forwarded address once the worker has
+/// read the PROXY header instead.
 pub struct Listener {
     listener: TcpListener,
+    unix_listener: Option<UnixSocketListener>,
     options: Arc<Options>,
     player_count: PlayerCount,
+    status_cache: StatusCache,
+    traffic: TrafficStats,
+    chunk_packet_cache: ChunkPacketCache,
     new_players: Sender<NewPlayer>,
+    throttle: Arc<Mutex<ConnectionThrottle>>,
+    /// Permits for connections in initial handling.
+    handshakes: Arc<Semaphore>,
+    shutdown: ShutdownSignal,
+}
+
+/// Handle to a running [`Listener`], used to shut it down.
+pub struct ListenerHandle {
+    task: JoinHandle<()>,
+    shutdown: ShutdownTrigger,
+}
+
+impl ListenerHandle {
+    /// Stops accepting connections and disconnects all
+    /// clients, then waits for their connections to close.
+    ///
+    /// Connections still open after `timeout` are dropped.
+    pub async fn shutdown(mut self, timeout: Duration) {
+        self.shutdown.trigger();
+        let _ = self.task.await;
+
+        if tokio::time::timeout(timeout, self.shutdown.wait_for_tasks())
+            .await
+            .is_err()
+        {
+            log::warn!("Timed out waiting for connections to close");
+        }
+    }
 }
 
 impl Listener {
     pub async fn start(
         options: Arc<Options>,
         player_count: PlayerCount,
+        status_cache: StatusCache,
+        traffic: TrafficStats,
+        chunk_packet_cache: ChunkPacketCache,
         new_players: Sender<NewPlayer>,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<ListenerHandle> {
         let listener = TcpListener::bind(format!("{}:{}", options.bind_address, options.port))
             .await
             .context("failed to bind to port - maybe a server is already running?")?;
+        let unix_listener = match &options.bind_unix {
+            Some(path) => Some(
+                UnixSocketListener::bind(path)
+                    .with_context(|| format!("failed to bind to Unix socket {}", path.display()))?,
+            ),
+            None => None,
+        };
 
+        let throttle = Arc::new(Mutex::new(ConnectionThrottle::new(
+            options.max_connections_per_ip,
+            options.connection_throttle_window,
+        )));
+        let handshakes = Arc::new(Semaphore::new(options.max_concurrent_handshakes));
+        let (shutdown_trigger, shutdown) = shutdown::channel();
         let listener = Listener {
             listener,
+            unix_listener,
             options,
             player_count,
+            status_cache,
+            traffic,
+            chunk_packet_cache,
             new_players,
+            throttle,
+            handshakes,
+            shutdown,
         };
-        tokio::task::spawn(async move {
+        let task = tokio::task::spawn(async move {
             listener.run().await;
         });
 
-        Ok(())
+        Ok(ListenerHandle {
+            task,
+            shutdown: shutdown_trigger,
+        })
     }
 
     async fn run(mut self) {
+        let mut shutdown = self.shutdown.clone();
         loop {
-            if let Ok((stream, addr)) = self.listener.accept().await {
-                self.accept(stream, addr).await;
+            tokio::select! {
+                accepted = self.listener.accept() => {
+                    if let Ok((stream, addr)) = accepted {
+                        if self.options.proxy_protocol
+                            || self.throttle.lock().allow(addr.ip(), Instant::now())
+                        {
+                            self.accept(stream, addr).await;
+                        } else {
+                            log::debug!("Throttled connection from {}", addr);
+                        }
+                    }
+                }
+                accepted = unix::accept(self.unix_listener.as_ref()) => {
+                    if let Ok(stream) = accepted {
+                        self.accept(stream, unix::peer_addr()).await;
+                    }
+                }
+                _ = shutdown.triggered() => {
+                    log::debug!("Listener stopped accepting connections");
+                    return;
+                }
             }
         }
     }
 
-    async fn accept(&mut self, stream: TcpStream, addr: SocketAddr) {
+    async fn accept(&mut self, stream: impl Stream, addr: SocketAddr) {
+        let handshake_permit = match Arc::clone(&self.handshakes).try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                log::debug!(
+                    "Dropped connection from {}: too many connections in initial handling",
+                    addr
+                );
+                return;
+            }
+        };
+
         let worker = Worker::new(
             stream,
             addr,
             Arc::clone(&self.options),
             self.player_count.clone(),
+            self.status_cache.clone(),
+            self.traffic.connection(),
+            self.chunk_packet_cache.clone(),
             self.new_players.clone(),
+            self.shutdown.clone(),
+            Arc::clone(&self.throttle),
         );
-        worker.start();
+        worker.start(handshake_permit);
     }
 }
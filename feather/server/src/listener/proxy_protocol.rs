@@ -0,0 +1,154 @@
+//! Version 2 of the HAProxy PROXY protocol.
+//!
+//! TCP load balancers send a PROXY header before any other
+//! data so the server learns the real address of the client,
+//! rather than the balancer's. See
+//! <https://www.haproxy.org/download/2.3/doc/proxy-protocol.txt>.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::bail;
+
+use crate::connection_worker::Worker;
+
+/// Bytes every version 2 header starts with.
+const SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Length of the fixed part of the header.
+pub const HEADER_LEN: usize = 16;
+
+const COMMAND_LOCAL: u8 = 0x0;
+const COMMAND_PROXY: u8 = 0x1;
+
+const FAMILY_TCP4: u8 = 0x11;
+const FAMILY_TCP6: u8 = 0x21;
+
+/// The fixed part of a PROXY header.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Header {
+    command: u8,
+    family: u8,
+    /// Length of the address block which follows.
+    len: usize,
+}
+
+impl Header {
+    pub fn parse(bytes: &[u8; HEADER_LEN]) -> anyhow::Result<Self> {
+        if bytes[..12] != SIGNATURE {
+            bail!("missing PROXY protocol header");
+        }
+        let version = bytes[12] >> 4;
+        if version != 2 {
+            bail!("unsupported PROXY protocol version {}", version);
+        }
+        let command = bytes[12] & 0x0F;
+        if command != COMMAND_LOCAL && command != COMMAND_PROXY {
+            bail!("unknown PROXY protocol command {}", command);
+        }
+        Ok(Self {
+            command,
+            family: bytes[13],
+            len: u16::from_be_bytes([bytes[14], bytes[15]]) as usize,
+        })
+    }
+
+    /// Length of the address block following the header.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Gets the client's address from the address block.
+    ///
+    /// Returns `None` for connections made by the
+    /// balancer itself (like health checks) and for
+    /// non-TCP address families, which should keep the
+    /// address of the TCP peer.
+    pub fn source_address(&self, addresses: &[u8]) -> anyhow::Result<Option<SocketAddr>> {
+        if self.command == COMMAND_LOCAL {
+            return Ok(None);
+        }
+
+        let (ip, port_offset) = match self.family {
+            FAMILY_TCP4 if addresses.len() >= 12 => {
+                let mut ip = [0; 4];
+                ip.copy_from_slice(&addresses[..4]);
+                (IpAddr::V4(Ipv4Addr::from(ip)), 8)
+            }
+            FAMILY_TCP6 if addresses.len() >= 36 => {
+                let mut ip = [0; 16];
+                ip.copy_from_slice(&addresses[..16]);
+                (IpAddr::V6(Ipv6Addr::from(ip)), 32)
+            }
+            FAMILY_TCP4 | FAMILY_TCP6 => bail!("PROXY protocol address block too short"),
+            _ => return Ok(None),
+        };
+        let port = u16::from_be_bytes([addresses[port_offset], addresses[port_offset + 1]]);
+        Ok(Some(SocketAddr::new(ip, port)))
+    }
+}
+
+/// Reads the PROXY header at the start of a connection.
+/// Returns the client's address, if the header gives one.
+pub async fn read_source_address(worker: &mut Worker) -> anyhow::Result<Option<SocketAddr>> {
+    let mut bytes = [0; HEADER_LEN];
+    bytes.copy_from_slice(&worker.read_raw(HEADER_LEN).await?);
+    let header = Header::parse(&bytes)?;
+    let addresses = worker.read_raw(header.len()).await?;
+    header.source_address(&addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(command: u8, family: u8, len: u16) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[..12].copy_from_slice(&SIGNATURE);
+        bytes[12] = 0x20 | command;
+        bytes[13] = family;
+        bytes[14..].copy_from_slice(&len.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn parses_ipv4_source() {
+        let header = Header::parse(&header(COMMAND_PROXY, FAMILY_TCP4, 12)).unwrap();
+        assert_eq!(header.len(), 12);
+        let addresses = [203, 0, 113, 7, 10, 0, 0, 1, 0xD4, 0x31, 0x63, 0xDD];
+        assert_eq!(
+            header.source_address(&addresses).unwrap(),
+            Some("203.0.113.7:54321".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn parses_ipv6_source() {
+        let header = Header::parse(&header(COMMAND_PROXY, FAMILY_TCP6, 36)).unwrap();
+        let mut addresses = [0; 36];
+        addresses[15] = 1;
+        addresses[32..34].copy_from_slice(&25565u16.to_be_bytes());
+        assert_eq!(
+            header.source_address(&addresses).unwrap(),
+            Some("[::1]:25565".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn local_command_keeps_peer_address() {
+        let header = Header::parse(&header(COMMAND_LOCAL, 0, 0)).unwrap();
+        assert_eq!(header.source_address(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_missing_signature() {
+        let mut bytes = header(COMMAND_PROXY, FAMILY_TCP4, 12);
+        bytes[0] = 0x10;
+        assert!(Header::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_short_address_block() {
+        let header = Header::parse(&header(COMMAND_PROXY, FAMILY_TCP4, 4)).unwrap();
+        assert!(header.source_address(&[127, 0, 0, 1]).is_err());
+    }
+}
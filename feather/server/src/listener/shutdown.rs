@@ -0,0 +1,58 @@
+use tokio::sync::{mpsc, watch};
+
+/// Creates a shutdown signal and a tracker for the
+/// tasks which must finish before shutdown completes.
+pub fn channel() -> (ShutdownTrigger, ShutdownSignal) {
+    let (trigger, signal) = watch::channel(false);
+    let (task_guard, tasks_done) = mpsc::channel(1);
+    (
+        ShutdownTrigger {
+            trigger,
+            tasks_done,
+        },
+        ShutdownSignal { signal, task_guard },
+    )
+}
+
+/// Triggers a shutdown and waits for the tasks
+/// holding a `ShutdownSignal` to finish.
+pub struct ShutdownTrigger {
+    trigger: watch::Sender<bool>,
+    tasks_done: mpsc::Receiver<()>,
+}
+
+impl ShutdownTrigger {
+    pub fn trigger(&self) {
+        let _ = self.trigger.send(true);
+    }
+
+    /// Waits until every `ShutdownSignal` has been dropped.
+    pub async fn wait_for_tasks(&mut self) {
+        // Nothing is ever sent; `recv` returns `None`
+        // once all senders are dropped.
+        let _ = self.tasks_done.recv().await;
+    }
+}
+
+/// Received by tasks which should stop when the server shuts down.
+///
+/// Shutdown waits for all clones of the signal to be
+/// dropped, so a task should hold its signal until it finishes.
+#[derive(Clone)]
+pub struct ShutdownSignal {
+    signal: watch::Receiver<bool>,
+    task_guard: mpsc::Sender<()>,
+}
+
+impl ShutdownSignal {
+    /// Completes once shutdown is triggered.
+    pub async fn triggered(&mut self) {
+        while !*self.signal.borrow() {
+            if self.signal.changed().await.is_err() {
+                // The trigger was dropped; the server
+                // is gone, so stop as well.
+                return;
+            }
+        }
+    }
+}
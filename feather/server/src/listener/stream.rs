@@ -0,0 +1,41 @@
+//! Abstracts over the kinds of connections the listener accepts.
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+
+use super::unix::UnixStream;
+
+pub type ReadHalf = Box<dyn AsyncRead + Send + Unpin>;
+pub type WriteHalf = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// A connection accepted by the [`Listener`](super::Listener),
+/// over TCP or a Unix socket.
+pub trait Stream: Send + 'static {
+    /// Splits the stream into halves which can
+    /// be read and written from different tasks.
+    fn into_split(self) -> (ReadHalf, WriteHalf);
+}
+
+impl Stream for TcpStream {
+    fn into_split(self) -> (ReadHalf, WriteHalf) {
+        let (reader, writer) = TcpStream::into_split(self);
+        (Box::new(reader), Box::new(writer))
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn into_split(self) -> (ReadHalf, WriteHalf) {
+        let (reader, writer) = UnixStream::into_split(self);
+        (Box::new(reader), Box::new(writer))
+    }
+}
+
+#[cfg(not(unix))]
+impl Stream for UnixStream {
+    fn into_split(self) -> (ReadHalf, WriteHalf) {
+        match self {}
+    }
+}
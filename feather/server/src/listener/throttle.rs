@@ -0,0 +1,85 @@
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+
+/// Number of tracked addresses after which
+/// expired entries are pruned.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Limits how many connections a single IP
+/// address may open within a time window.
+pub struct ConnectionThrottle {
+    max_per_window: u32,
+    window: Duration,
+    /// Start of the current window and the number
+    /// of connections made in it, for each address.
+    windows: AHashMap<IpAddr, (Instant, u32)>,
+}
+
+impl ConnectionThrottle {
+    /// Creates a throttle allowing `max_per_window` connections
+    /// per address in each `window`. A maximum of zero
+    /// disables throttling.
+    pub fn new(max_per_window: u32, window: Duration) -> Self {
+        Self {
+            max_per_window,
+            window,
+            windows: AHashMap::new(),
+        }
+    }
+
+    /// Records a connection attempt from `ip`, returning
+    /// whether it should be accepted.
+    pub fn allow(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.max_per_window == 0 {
+            return true;
+        }
+
+        if self.windows.len() >= PRUNE_THRESHOLD {
+            let window = self.window;
+            self.windows
+                .retain(|_, (start, _)| now.saturating_duration_since(*start) < window);
+        }
+
+        let (start, count) = self.windows.entry(ip).or_insert((now, 0));
+        if now.saturating_duration_since(*start) >= self.window {
+            *start = now;
+            *count = 0;
+        }
+
+        *count += 1;
+        *count <= self.max_per_window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    #[test]
+    fn limits_connections_within_window() {
+        let mut throttle = ConnectionThrottle::new(2, Duration::from_secs(4));
+        let now = Instant::now();
+        assert!(throttle.allow(IP, now));
+        assert!(throttle.allow(IP, now));
+        assert!(!throttle.allow(IP, now + Duration::from_secs(1)));
+        assert!(throttle.allow(IpAddr::V4(Ipv4Addr::LOCALHOST), now));
+        assert!(throttle.allow(IP, now + Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn zero_disables_throttling() {
+        let mut throttle = ConnectionThrottle::new(0, Duration::from_secs(4));
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert!(throttle.allow(IP, now));
+        }
+    }
+}
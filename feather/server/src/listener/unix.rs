@@ -0,0 +1,118 @@
+//! Listening on a Unix domain socket, so that proxies
+//! on the same machine can skip the TCP stack.
+
+use std::{
+    fs, io,
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+};
+
+#[cfg(unix)]
+pub use tokio::net::UnixStream;
+
+/// Unix sockets are not supported on this platform,
+/// so no connections are ever accepted.
+#[cfg(not(unix))]
+pub enum UnixStream {}
+
+/// Gets the address of connections over the Unix socket,
+/// which have no IP address. Proxies can forward the
+/// client's address with the PROXY protocol or IP forwarding.
+pub fn peer_addr() -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::LOCALHOST, 0))
+}
+
+/// Listens on a Unix socket. The socket file
+/// is removed when the listener is dropped.
+pub struct UnixSocketListener {
+    #[cfg(unix)]
+    listener: tokio::net::UnixListener,
+    path: PathBuf,
+}
+
+impl UnixSocketListener {
+    /// Binds to the socket at `path`, replacing a socket
+    /// file left behind by a server which didn't shut down cleanly.
+    #[cfg(unix)]
+    pub fn bind(path: &Path) -> anyhow::Result<Self> {
+        use std::os::unix::fs::FileTypeExt;
+
+        if let Ok(metadata) = fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                anyhow::bail!("{} exists and is not a socket", path.display());
+            }
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                anyhow::bail!("the socket is in use - maybe a server is already running?");
+            }
+            fs::remove_file(path)?;
+        }
+        Ok(Self {
+            listener: tokio::net::UnixListener::bind(path)?,
+            path: path.to_owned(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(_path: &Path) -> anyhow::Result<Self> {
+        anyhow::bail!("Unix sockets are not supported on this platform")
+    }
+
+    #[cfg(unix)]
+    pub async fn accept(&self) -> io::Result<UnixStream> {
+        let (stream, _) = self.listener.accept().await?;
+        Ok(stream)
+    }
+
+    #[cfg(not(unix))]
+    pub async fn accept(&self) -> io::Result<UnixStream> {
+        std::future::pending().await
+    }
+}
+
+impl Drop for UnixSocketListener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Accepts a connection on `listener`, or never
+/// completes if there is no Unix socket to listen on.
+pub async fn accept(listener: Option<&UnixSocketListener>) -> io::Result<UnixStream> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn accepts_connections() {
+        let path =
+            std::env::temp_dir().join(format!("feather-unix-test-{}.sock", std::process::id()));
+        // Other files are never removed.
+        fs::write(&path, b"").unwrap();
+        assert!(UnixSocketListener::bind(&path).is_err());
+        assert!(path.exists());
+        fs::remove_file(&path).unwrap();
+
+        // A stale socket file is replaced.
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let listener = UnixSocketListener::bind(&path).unwrap();
+        assert!(UnixSocketListener::bind(&path).is_err());
+
+        let mut client = UnixStream::connect(&path).await.unwrap();
+        let mut server = accept(Some(&listener)).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        let mut received = [0; 4];
+        server.read_exact(&mut received).await.unwrap();
+        assert_eq!(&received, b"ping");
+
+        drop(listener);
+        assert!(!path.exists());
+    }
+}
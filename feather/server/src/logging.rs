@@ -1,33 +1,24 @@
-use colored::Colorize;
-use log::{Level, LevelFilter};
+use log::LevelFilter;
+use tracing_subscriber::{
+    fmt::{format::FmtSpan, time::ChronoLocal},
+    EnvFilter,
+};
 
+/// Initializes logging to stdout.
+///
+/// Records from `log` are forwarded to `tracing`, so every
+/// line is prefixed with the spans it was emitted in (e.g. the
+/// player's address and username.) Spans log how long they
+/// were busy and idle when they close.
 pub fn init(level: LevelFilter) {
-    fern::Dispatch::new()
-        .format(|out, message, record| {
-            let level_string = match record.level() {
-                Level::Error => record.level().to_string().red(),
-                Level::Warn => record.level().to_string().yellow(),
-                Level::Info => record.level().to_string().cyan(),
-                Level::Debug => record.level().to_string().purple(),
-                Level::Trace => record.level().to_string().normal(),
-            };
-            let target = if !record.target().is_empty() {
-                record.target()
-            } else {
-                record.module_path().unwrap_or_default()
-            };
-            out.finish(format_args!(
-                "{} {:<5} [{}] {}",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S,%3f"),
-                level_string,
-                target,
-                message,
-            ));
-        })
-        .level(level)
-        // cranelift_codegen spams debug-level logs
-        .level_for("cranelift_codegen", LevelFilter::Info)
-        .chain(std::io::stdout())
-        .apply()
-        .unwrap();
+    // cranelift_codegen spams debug-level logs
+    let filter = EnvFilter::new(format!(
+        "{},cranelift_codegen=info",
+        level.to_string().to_lowercase()
+    ));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_timer(ChronoLocal::with_format("%Y-%m-%d %H:%M:%S,%3f".to_owned()))
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
 }
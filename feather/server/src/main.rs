@@ -1,43 +1,163 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    path::Path,
+    process::Command,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::Context;
+use argh::FromArgs;
 use common::{
+    shutdown::{Shutdown, StopReason},
     world_source::{flat::FlatWorldSource, region::RegionWorldSource, WorldSource},
     Game, TickLoop, World,
 };
 use ecs::SystemExecutor;
-use feather_server::Server;
+use feather_server::{config::Config, tick_times::TickTimes, Server};
 use plugin_host::PluginManager;
+use runtime::IoRuntime;
 
 mod logging;
+mod runtime;
+mod world_inspect;
 
 const PLUGINS_DIRECTORY: &str = "plugins";
-const WORLD_DIRECTORY: &str = "world";
 const CONFIG_PATH: &str = "config.toml";
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    println!("Loading configuration");
-    let config =
-        feather_server::config::load(CONFIG_PATH).context("failed to load configuration file")?;
-    logging::init(config.log.level);
+/// How long to wait for connections to close on shutdown.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Exit code when the server was stopped on purpose.
+const EXIT_CODE_STOP: i32 = 0;
+/// Exit code when the server failed to start or crashed.
+const EXIT_CODE_CRASH: i32 = 1;
+/// Exit code when a restart was requested with `/restart`,
+/// so supervisors know to start the server again.
+const EXIT_CODE_RESTART: i32 = 2;
+
+/// Runs a Feather server.
+#[derive(Debug, FromArgs)]
+struct Args {
+    #[argh(subcommand)]
+    command: Option<Command>,
+}
 
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    World(WorldCommand),
+}
+
+/// Works with world directories.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "world")]
+struct WorldCommand {
+    #[argh(subcommand)]
+    command: WorldSubcommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum WorldSubcommand {
+    Inspect(world_inspect::Inspect),
+}
+
+fn main() {
+    let args: Args = argh::from_env();
+    if let Some(Command::World(world)) = args.command {
+        let result = match world.command {
+            WorldSubcommand::Inspect(inspect) => inspect.run(),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {:?}", e);
+            std::process::exit(EXIT_CODE_CRASH);
+        }
+        return;
+    }
+
+    let result = std::panic::catch_unwind(|| {
+        println!("Loading configuration");
+        let config = feather_server::config::load(CONFIG_PATH)
+            .context("failed to load configuration file")?;
+        logging::init(config.log.level);
+
+        IoRuntime::new(&config.runtime)
+            .context("failed to start the Tokio runtime")?
+            .block_on(run_server(config))
+    });
+    let code = match result {
+        Ok(Ok(StopReason::Stop)) => EXIT_CODE_STOP,
+        Ok(Ok(StopReason::Restart)) => EXIT_CODE_RESTART,
+        Ok(Err(e)) => {
+            log::error!("The server crashed: {:?}", e);
+            eprintln!("Error: {:?}", e);
+            EXIT_CODE_CRASH
+        }
+        // The panic hook already printed the panic.
+        Err(_) => EXIT_CODE_CRASH,
+    };
+    std::process::exit(code);
+}
+
+async fn run_server(config: Config) -> anyhow::Result<StopReason> {
+    log::info!(
+        "Handling connections on a {:?} runtime",
+        config.runtime.flavor
+    );
     log::info!("Creating server");
     let options = config.to_options();
-    let server = Server::bind(options).await?;
+    let mut server = Server::bind(options).await?;
+    let listener = server
+        .take_listener_handle()
+        .expect("listener handle was just created");
 
-    let game = init_game(server)?;
+    let game = init_game(server, &config)?;
 
-    run(game);
+    let stopping = Arc::new(AtomicBool::new(false));
+    stop_on_ctrl_c(Arc::clone(&stopping));
+    let reason = run(game, stopping);
 
-    Ok(())
+    log::info!("Shutting down");
+    listener.shutdown(SHUTDOWN_TIMEOUT).await;
+
+    if reason == StopReason::Restart {
+        run_restart_script(&config.server.restart_script);
+    }
+
+    Ok(reason)
+}
+
+/// Starts the configured restart script, if any. The script
+/// is expected to start the server again once this process exits.
+fn run_restart_script(script: &str) {
+    if script.is_empty() {
+        return;
+    }
+    log::info!("Running restart script {}", script);
+    if let Err(e) = Command::new(script).spawn() {
+        log::error!("Failed to run restart script {}: {}", script, e);
+    }
 }
 
-fn init_game(server: Server) -> anyhow::Result<Game> {
+fn stop_on_ctrl_c(stopping: Arc<AtomicBool>) {
+    tokio::task::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            stopping.store(true, Ordering::SeqCst);
+        }
+    });
+}
+
+fn init_game(server: Server, config: &Config) -> anyhow::Result<Game> {
     let mut game = Game::new();
+    feather_server::storage::register(&mut game, server.options())?;
     init_systems(&mut game, server);
-    init_world_source(&mut game);
-    init_plugin_manager(&mut game)?;
+    init_world_source(&mut game, config)?;
+    init_plugin_manager(&mut game, config)?;
     Ok(game)
 }
 
@@ -55,19 +175,28 @@ fn init_systems(game: &mut Game, server: Server) {
     game.system_executor = Rc::new(RefCell::new(systems));
 }
 
-fn init_world_source(game: &mut Game) {
+fn init_world_source(game: &mut Game, config: &Config) -> anyhow::Result<()> {
     // Load chunks from the world save first,
     // and fall back to generating a superflat
     // world otherwise. This is a placeholder:
     // we don't have proper world generation yet.
     let world_source =
-        RegionWorldSource::new(WORLD_DIRECTORY).with_fallback(FlatWorldSource::new());
+        RegionWorldSource::with_workers(&config.world.name, config.world.chunk_workers)
+            .with_fallback(FlatWorldSource::new());
     game.world = World::with_source(world_source);
+    game.world.set_name(&config.world.name);
+    game.world
+        .chunk_manager_mut()
+        .set_saving(config.world.save_chunks);
+    *game.world.settings_mut() = config.world.to_settings();
+    feather_server::level::load_world_settings(game, Path::new(&config.world.name))?;
+    feather_server::regions::load_world_regions(game)
 }
 
-fn init_plugin_manager(game: &mut Game) -> anyhow::Result<()> {
-    let mut plugin_manager = PluginManager::new();
+fn init_plugin_manager(game: &mut Game, config: &Config) -> anyhow::Result<()> {
+    let mut plugin_manager = PluginManager::with_quotas(config.plugins.to_quotas());
     plugin_manager.load_dir(game, PLUGINS_DIRECTORY)?;
+    plugin_host::register(game)?;
 
     let plugin_manager_rc = Rc::new(RefCell::new(plugin_manager));
     game.insert_resource(plugin_manager_rc);
@@ -79,18 +208,66 @@ fn print_systems(systems: &SystemExecutor<Game>) {
     log::debug!("---SYSTEMS---\n{:#?}\n", systems);
 }
 
-fn run(game: Game) {
-    let tick_loop = create_tick_loop(game);
+fn run(game: Game, stopping: Arc<AtomicBool>) -> StopReason {
+    let reason = Rc::new(Cell::new(StopReason::Stop));
+    let tick_loop = create_tick_loop(game, stopping, Rc::clone(&reason));
     log::debug!("Launching the game loop");
     tick_loop.run();
+    reason.get()
 }
 
-fn create_tick_loop(mut game: Game) -> TickLoop {
+fn create_tick_loop(
+    mut game: Game,
+    stopping: Arc<AtomicBool>,
+    reason: Rc<Cell<StopReason>>,
+) -> TickLoop {
     TickLoop::new(move || {
+        let started = Instant::now();
         let systems = Rc::clone(&game.system_executor);
         systems.borrow_mut().run(&mut game);
+        if let Err(e) = plugin_host::process_pending_reloads(&mut game) {
+            log::error!("Failed to process plugin reloads: {:?}", e);
+        }
+        if let Ok(mut tick_times) = game.resources.get_mut::<TickTimes>() {
+            tick_times.record(started.elapsed());
+        }
         game.tick_count += 1;
 
-        false
+        let stop = if stopping.load(Ordering::SeqCst) {
+            true
+        } else if let Some(requested) = stop_requested(&game) {
+            reason.set(requested);
+            true
+        } else {
+            false
+        };
+        if stop {
+            save_world(&mut game);
+        }
+        stop
     })
 }
+
+/// Writes chunks changed since they were loaded, the
+/// world settings and player data to the world save.
+fn save_world(game: &mut Game) {
+    if let Ok(server) = game.resources.get::<Server>() {
+        server.save_player_data(game);
+    }
+    let saved = game.world.save_chunks();
+    log::info!("Saved {} changed chunks", saved);
+    if game.world.chunk_manager().is_saving() {
+        let world_dir = Path::new(game.world.name());
+        if let Err(e) = feather_server::level::save_world_settings(game, world_dir) {
+            log::error!("Failed to save world settings: {:?}", e);
+        }
+    }
+}
+
+/// Whether `/stop` or `/restart` asked the server to stop.
+fn stop_requested(game: &Game) -> Option<StopReason> {
+    game.resources
+        .get::<Shutdown>()
+        .ok()
+        .and_then(|shutdown| shutdown.requested())
+}
@@ -1,6 +1,12 @@
-use base::Gamemode;
+use std::{path::PathBuf, time::Duration};
 
-use crate::favicon::Favicon;
+use base::{Gamemode, ItemStack, Position, Text};
+use common::combat_log::CombatLogAction;
+use quill_common::economy::Currency;
+
+use crate::{
+    favicon::Favicon, initial_handler::Hooks, io::sniffer::SnifferOptions, storage::StorageOptions,
+};
 
 /// Options for building a [`Server`](crate::Server).
 #[derive(Debug, Clone)]
@@ -9,11 +15,20 @@ pub struct Options {
     pub port: u16,
     /// Addresses to bind to.
     pub bind_address: String,
+    /// Path of a Unix socket to also listen on.
+    pub bind_unix: Option<PathBuf>,
 
     /// The server favicon.
     pub favicon: Option<Favicon>,
-    /// The server MOTD.
-    pub motd: String,
+    /// The server MOTD, shown in the server list.
+    pub motd: Text,
+
+    /// Maximum number of online players listed
+    /// when hovering over the player count.
+    pub status_sample_size: usize,
+    /// Lines listed instead of online players when
+    /// hovering over the player count, if not empty.
+    pub status_sample_lines: Vec<String>,
 
     /// Whether the server should authenticate players.
     pub online_mode: bool,
@@ -28,13 +43,134 @@ pub struct Options {
     /// The default gamemode for new players.
     pub default_gamemode: Gamemode,
 
+    /// Directory containing the world.
+    pub world_dir: PathBuf,
+    /// How long chunks no player can see stay loaded.
+    pub chunk_unload_delay: Duration,
+    /// Maximum number of loaded chunks, or `None` for no limit.
+    pub max_loaded_chunks: Option<usize>,
+
+    /// Items given to players joining for the first time.
+    pub starter_kit: Vec<ItemStack>,
+    /// Where players joining for the first time spawn.
+    pub first_spawn: Option<Position>,
+    /// Message sent to players when they join.
+    pub join_motd: Option<Text>,
+    /// Template for the message broadcast when a player joins,
+    /// or `None` for the vanilla message.
+    pub join_message: Option<String>,
+    /// Template for the message broadcast when a player
+    /// leaves, or `None` for the vanilla message.
+    pub quit_message: Option<String>,
+
+    /// Inactivity after which players are marked
+    /// AFK, or `None` to never mark them AFK.
+    pub afk_timeout: Option<Duration>,
+    /// Template for the message broadcast when a player becomes AFK.
+    pub afk_message: Option<String>,
+    /// Template for the message broadcast when a player comes back.
+    pub afk_return_message: Option<String>,
+
+    /// Time after a PvP attack during which disconnecting counts
+    /// as combat logging, or `None` to disable combat logging.
+    pub combat_log_duration: Option<Duration>,
+    pub combat_log_action: CombatLogAction,
+    /// How long the stand-in of a combat logger stays.
+    pub combat_log_npc_lifetime: Duration,
+
+    /// Currency of the default economy provider.
+    pub economy_currency: Currency,
+    /// Balance of new accounts in the default economy provider.
+    pub economy_starting_balance: i64,
+
+    /// Where server data like the user cache is stored.
+    pub storage: StorageOptions,
+
+    /// Resource pack sent to players when they join.
+    pub resource_pack: Option<ResourcePack>,
+
+    /// Packets logged by the sniffer, or `None`
+    /// to not log packets.
+    pub sniffer: Option<SnifferOptions>,
+    /// Directory to record connections' packets
+    /// to, or `None` to not record them.
+    pub capture_dir: Option<PathBuf>,
+
+    /// Time before a scheduled restart at
+    /// which players are warned.
+    pub restart_warnings: Vec<Duration>,
+
+    /// Whether to check the consistency of the server's state
+    /// at the end of each tick. Only has an effect in debug builds.
+    pub check_invariants: bool,
+
+    /// Whether to send tick durations to clients
+    /// which register the tick times channel.
+    pub report_tick_times: bool,
+
     /// Proxy IP forwarding mode
     pub proxy_mode: Option<ProxyMode>,
     // HMAC key used with Velocity IP forwarding.
     pub velocity_secret: String,
 
+    /// Whether connections start with a PROXY protocol (v2)
+    /// header giving the client's real address.
+    pub proxy_protocol: bool,
+
     /// Packet size threshold at which to compress data
     pub compression_threshold: Option<usize>,
+    /// zlib compression level, from 0 to 9.
+    pub compression_level: u32,
+
+    /// Maximum number of connections a single IP address
+    /// may open per `connection_throttle_window`. Zero
+    /// disables the limit.
+    pub max_connections_per_ip: u32,
+    pub connection_throttle_window: Duration,
+
+    /// Maximum number of connections which may be
+    /// in initial handling at the same time.
+    pub max_concurrent_handshakes: usize,
+
+    /// How long a connection may take to send its Handshake
+    /// packet, or to finish a server list ping.
+    pub handshake_timeout: Duration,
+    /// How long a connection may take to send Login Start
+    /// (and complete proxy forwarding) after the Handshake.
+    pub login_timeout: Duration,
+    /// How long a client may take to send its Encryption
+    /// Response after the Encryption Request.
+    pub encryption_timeout: Duration,
+
+    /// Maximum rate at which a client may send movement
+    /// packets before it is disconnected. Zero disables the limit.
+    pub max_movement_packets_per_second: u32,
+    /// Maximum rate at which a client may send all other
+    /// packets before it is disconnected. Zero disables the limit.
+    pub max_packets_per_second: u32,
+
+    /// Port for the RCON listener, or `None` to disable RCON.
+    pub rcon_port: Option<u16>,
+    /// Password RCON clients must authenticate with.
+    pub rcon_password: String,
+
+    /// UDP port to answer GameSpy 4 queries on,
+    /// or `None` to disable queries.
+    pub query_port: Option<u16>,
+
+    /// Hooks invoked by connection workers during initial handling.
+    pub hooks: Hooks,
+}
+
+/// A server resource pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourcePack {
+    pub url: String,
+    /// SHA-1 hash of the pack file, or empty.
+    pub hash: String,
+    /// Message players are kicked with if they don't use
+    /// the pack, or `None` if the pack is optional.
+    pub kick_message: Option<String>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
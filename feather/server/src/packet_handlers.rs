@@ -1,5 +1,21 @@
-use base::{Position, Text};
-use common::{chat::ChatKind, Game};
+use std::convert::TryFrom;
+
+use base::{metadata::META_INDEX_PLAYER_DISPLAYED_SKIN_PARTS, EntityMetadata, Position, Text};
+use common::{
+    afk,
+    beacon::{self, BeaconEffects, OpenBeacon},
+    cartography::{self, OpenCartographyTable},
+    chat::ChatKind,
+    commands,
+    effects::StatusEffect,
+    enchanting::{self, EnchantmentSeed, OpenEnchantingTable},
+    plugin_channels,
+    recipe_book::{BookState, RecipeBook, RecipeBookKind},
+    resource_pack::{self, ResourcePackStatus},
+    smithing::{self, OpenSmithingTable},
+    stonecutter::{self, OpenStonecutter},
+    view, Game, Window,
+};
 use ecs::{Entity, EntityRef, SysResult};
 use interaction::{
     handle_held_item_change, handle_interact_entity, handle_player_block_placement,
@@ -14,7 +30,7 @@ use protocol::{
 };
 use quill_common::components::Name;
 
-use crate::{NetworkId, Server};
+use crate::{ClientId, NetworkId, Server};
 
 mod interaction;
 pub mod inventory;
@@ -26,6 +42,49 @@ pub fn handle_packet(
     server: &mut Server,
     player_id: Entity,
     packet: ClientPlayPacket,
+) -> SysResult {
+    let is_input = is_player_input(&packet);
+    let position_before = *game.ecs.get::<Position>(player_id)?;
+
+    dispatch_packet(game, server, player_id, packet)?;
+
+    // Movement packets are also sent while standing still,
+    // so they only count as activity if the player moved.
+    let moved = game
+        .ecs
+        .get::<Position>(player_id)
+        .map(|position| *position != position_before)
+        .unwrap_or(false);
+    if is_input || moved {
+        afk::record_activity(game, player_id)?;
+    }
+    Ok(())
+}
+
+/// Returns whether a packet is sent because
+/// of player input, apart from movement.
+fn is_player_input(packet: &ClientPlayPacket) -> bool {
+    matches!(
+        packet,
+        ClientPlayPacket::PlayerRotation(_)
+            | ClientPlayPacket::Animation(_)
+            | ClientPlayPacket::ChatMessage(_)
+            | ClientPlayPacket::PlayerDigging(_)
+            | ClientPlayPacket::PlayerBlockPlacement(_)
+            | ClientPlayPacket::UseItem(_)
+            | ClientPlayPacket::InteractEntity(_)
+            | ClientPlayPacket::HeldItemChange(_)
+            | ClientPlayPacket::ClickWindow(_)
+            | ClientPlayPacket::CreativeInventoryAction(_)
+            | ClientPlayPacket::EntityAction(_)
+    )
+}
+
+fn dispatch_packet(
+    game: &mut Game,
+    server: &mut Server,
+    player_id: Entity,
+    packet: ClientPlayPacket,
 ) -> SysResult {
     let player = game.ecs.entity(player_id)?;
     match packet {
@@ -44,15 +103,23 @@ pub fn handle_packet(
 
         ClientPlayPacket::Animation(packet) => handle_animation(server, player, packet),
 
-        ClientPlayPacket::ChatMessage(packet) => handle_chat_message(game, player, packet),
+        ClientPlayPacket::ChatMessage(packet) => handle_chat_message(game, player_id, packet),
 
-        ClientPlayPacket::PlayerDigging(packet) => handle_player_digging(game, packet, player_id),
+        ClientPlayPacket::PlayerDigging(packet) => {
+            handle_player_digging(game, server, packet, player_id)
+        }
 
         ClientPlayPacket::CreativeInventoryAction(packet) => {
             inventory::handle_creative_inventory_action(player, packet)
         }
         ClientPlayPacket::ClickWindow(packet) => {
-            inventory::handle_click_window(server, player, packet)
+            let window_id = packet.window_id;
+            inventory::handle_click_window(server, player, packet)?;
+            update_enchanting_offers(game, server, player_id, window_id)?;
+            update_crafting_stations(game, server, player_id, window_id)
+        }
+        ClientPlayPacket::ClickWindowButton(packet) => {
+            handle_click_window_button(game, server, player_id, packet)
         }
 
         ClientPlayPacket::PlayerBlockPlacement(packet) => {
@@ -64,17 +131,42 @@ pub fn handle_packet(
             handle_interact_entity(game, server, packet, player_id)
         }
 
-        ClientPlayPacket::ClientSettings(packet) => handle_client_settings(server, player, packet),
+        ClientPlayPacket::ClientSettings(packet) => {
+            handle_client_settings(game, server, player_id, packet)
+        }
+
+        ClientPlayPacket::CloseWindow(packet) => {
+            handle_close_window(game, server, player_id, packet)
+        }
+        ClientPlayPacket::SetBeaconEffect(packet) => {
+            handle_set_beacon_effect(game, server, player_id, packet)
+        }
+        ClientPlayPacket::ResourcePackStatus(packet) => {
+            match ResourcePackStatus::from_id(packet.result.0) {
+                Some(status) => resource_pack::on_status(game, player_id, status),
+                None => Ok(()),
+            }
+        }
+        ClientPlayPacket::TabComplete(packet) => {
+            handle_tab_complete(game, server, player_id, packet)
+        }
+        ClientPlayPacket::PluginMessage(packet) => {
+            let data: Vec<u8> = packet.data.into();
+            plugin_channels::handle_message(game, player_id, &packet.channel, &data)
+        }
+        ClientPlayPacket::SetRecipeBookState(packet) => {
+            handle_set_recipe_book_state(player, packet)
+        }
+        ClientPlayPacket::SetDisplayedRecipe(packet) => {
+            player.get_mut::<RecipeBook>()?.mark_seen(&packet.recipe_id);
+            Ok(())
+        }
 
         ClientPlayPacket::TeleportConfirm(_)
         | ClientPlayPacket::QueryBlockNbt(_)
         | ClientPlayPacket::SetDifficulty(_)
         | ClientPlayPacket::ClientStatus(_)
-        | ClientPlayPacket::TabComplete(_)
         | ClientPlayPacket::WindowConfirmation(_)
-        | ClientPlayPacket::ClickWindowButton(_)
-        | ClientPlayPacket::CloseWindow(_)
-        | ClientPlayPacket::PluginMessage(_)
         | ClientPlayPacket::EditBook(_)
         | ClientPlayPacket::QueryEntityNbt(_)
         | ClientPlayPacket::GenerateStructure(_)
@@ -87,13 +179,9 @@ pub fn handle_packet(
         | ClientPlayPacket::PlayerAbilities(_)
         | ClientPlayPacket::EntityAction(_)
         | ClientPlayPacket::SteerVehicle(_)
-        | ClientPlayPacket::SetDisplayedRecipe(_)
-        | ClientPlayPacket::SetRecipeBookState(_)
         | ClientPlayPacket::NameItem(_)
-        | ClientPlayPacket::ResourcePackStatus(_)
         | ClientPlayPacket::AdvancementTab(_)
         | ClientPlayPacket::SelectTrade(_)
-        | ClientPlayPacket::SetBeaconEffect(_)
         | ClientPlayPacket::UpdateCommandBlock(_)
         | ClientPlayPacket::UpdateCommandBlockMinecart(_)
         | ClientPlayPacket::UpdateJigsawBlock(_)
@@ -117,27 +205,232 @@ fn handle_animation(
         Hand::Off => Animation::SwingOffhand,
     };
 
-    server.broadcast_nearby_with(pos, |client| {
+    server.broadcast_entity_update_with(network_id, pos, |client| {
         client.send_entity_animation(network_id, animation.clone())
     });
     Ok(())
 }
 
-fn handle_chat_message(game: &Game, player: EntityRef, packet: client::ChatMessage) -> SysResult {
-    let name = player.get::<Name>()?;
+fn handle_set_recipe_book_state(
+    player: EntityRef,
+    packet: client::SetRecipeBookState,
+) -> SysResult {
+    let kind = match RecipeBookKind::from_id(packet.book_id.0) {
+        Some(kind) => kind,
+        None => {
+            log::debug!("Client sent unknown recipe book {}", packet.book_id.0);
+            return Ok(());
+        }
+    };
+    player.get_mut::<RecipeBook>()?.set_book(
+        kind,
+        BookState {
+            open: packet.book_open,
+            filter_active: packet.filter_active,
+        },
+    );
+    Ok(())
+}
+
+fn handle_chat_message(game: &mut Game, player: Entity, packet: client::ChatMessage) -> SysResult {
+    if let Some(command) = packet.message.strip_prefix('/') {
+        return commands::execute(game, player, command);
+    }
+
+    let name = game.ecs.get::<Name>(player)?;
     let message = Text::translate_with("chat.type.text", vec![name.to_string(), packet.message]);
     game.broadcast_chat(ChatKind::PlayerChat, message);
     Ok(())
 }
 
-fn handle_client_settings(
+fn handle_close_window(
+    game: &mut Game,
     server: &mut Server,
-    player: EntityRef,
-    packet: client::ClientSettings,
+    player: Entity,
+    packet: client::CloseWindow,
 ) -> SysResult {
-    let network_id = *player.get::<NetworkId>()?;
-    server.broadcast_with(|client| {
-        client.send_player_model_flags(network_id, packet.displayed_skin_parts)
-    });
+    let closes_beacon = game
+        .ecs
+        .get::<OpenBeacon>(player)
+        .map_or(false, |beacon| beacon.window_id == packet.window_id);
+
+    if game.ecs.get::<OpenEnchantingTable>(player).is_ok() {
+        enchanting::close_enchanting_table(game, player)?;
+        let client = server
+            .clients
+            .get(*game.ecs.get::<ClientId>(player)?)
+            .unwrap();
+        client.send_window_items(0, &*game.ecs.get::<Window>(player)?);
+    }
+
+    let closes_station = if closes_beacon {
+        beacon::close_beacon(game, player)?;
+        true
+    } else if game.ecs.get::<OpenStonecutter>(player).is_ok() {
+        stonecutter::close_stonecutter(game, player)?;
+        true
+    } else if game.ecs.get::<OpenSmithingTable>(player).is_ok() {
+        smithing::close_smithing_table(game, player)?;
+        true
+    } else if game.ecs.get::<OpenCartographyTable>(player).is_ok() {
+        cartography::close_cartography_table(game, player)?;
+        true
+    } else {
+        false
+    };
+    if closes_station {
+        let client = server
+            .clients
+            .get(*game.ecs.get::<ClientId>(player)?)
+            .unwrap();
+        client.send_window_items(0, &*game.ecs.get::<Window>(player)?);
+    }
     Ok(())
 }
+
+fn handle_click_window_button(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    packet: client::ClickWindowButton,
+) -> SysResult {
+    if game.ecs.get::<OpenEnchantingTable>(player).is_ok() {
+        enchanting::enchant(game, player, packet.button_id as usize)?;
+        let client = server
+            .clients
+            .get(*game.ecs.get::<ClientId>(player)?)
+            .unwrap();
+        client.send_window_items(packet.window_id, &*game.ecs.get::<Window>(player)?);
+        update_enchanting_offers(game, server, player, packet.window_id)?;
+    } else if game.ecs.get::<OpenStonecutter>(player).is_ok() {
+        stonecutter::select_recipe(game, player, packet.button_id as usize)?;
+        let client = server
+            .clients
+            .get(*game.ecs.get::<ClientId>(player)?)
+            .unwrap();
+        client.send_window_items(packet.window_id, &*game.ecs.get::<Window>(player)?);
+    }
+    Ok(())
+}
+
+/// Updates the output slot of the player's stonecutter, smithing
+/// table or cartography table, if one is open, and resends the window.
+fn update_crafting_stations(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    window_id: u8,
+) -> SysResult {
+    if game.ecs.get::<OpenStonecutter>(player).is_ok() {
+        stonecutter::update(game, player)?;
+    } else if game.ecs.get::<OpenSmithingTable>(player).is_ok() {
+        smithing::update(game, player)?;
+    } else if game.ecs.get::<OpenCartographyTable>(player).is_ok() {
+        cartography::update(game, player)?;
+    } else {
+        return Ok(());
+    }
+
+    let client = server
+        .clients
+        .get(*game.ecs.get::<ClientId>(player)?)
+        .unwrap();
+    client.send_window_items(window_id, &*game.ecs.get::<Window>(player)?);
+    Ok(())
+}
+
+/// Recomputes and sends the offers of the player's
+/// enchanting table, if one is open.
+fn update_enchanting_offers(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    window_id: u8,
+) -> SysResult {
+    if game.ecs.get::<OpenEnchantingTable>(player).is_err() {
+        return Ok(());
+    }
+    enchanting::update_offers(game, player)?;
+
+    let client = server
+        .clients
+        .get(*game.ecs.get::<ClientId>(player)?)
+        .unwrap();
+    let table = game.ecs.get::<OpenEnchantingTable>(player)?;
+    let seed = *game.ecs.get::<EnchantmentSeed>(player)?;
+    client.send_enchantment_offers(window_id, &table.offers, seed);
+    Ok(())
+}
+
+fn handle_tab_complete(
+    game: &mut Game,
+    server: &Server,
+    player: Entity,
+    packet: client::TabComplete,
+) -> SysResult {
+    // Only commands are completed by the server.
+    let command = match packet.text.strip_prefix('/') {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+    let completions = commands::complete(game, player, command)?;
+
+    let client = server
+        .clients
+        .get(*game.ecs.get::<ClientId>(player)?)
+        .unwrap();
+    client.send_tab_complete(
+        packet.transaction_id,
+        completions.start + 1,
+        completions.length,
+        completions.matches,
+    );
+    Ok(())
+}
+
+fn handle_set_beacon_effect(
+    game: &mut Game,
+    server: &mut Server,
+    player: Entity,
+    packet: client::SetBeaconEffect,
+) -> SysResult {
+    // Effect IDs are 0 (or -1) if no effect is selected.
+    let effect = |id: i32| u8::try_from(id).ok().and_then(StatusEffect::from_id);
+    let effects = BeaconEffects {
+        primary: effect(packet.primary_effect),
+        secondary: effect(packet.secondary_effect),
+    };
+    beacon::select_effects(game, player, effects)?;
+
+    // Show the consumed payment.
+    let window_id = game.ecs.get::<OpenBeacon>(player)?.window_id;
+    let client = server
+        .clients
+        .get(*game.ecs.get::<ClientId>(player)?)
+        .unwrap();
+    client.send_window_items(window_id, &*game.ecs.get::<Window>(player)?);
+    Ok(())
+}
+
+/// Smallest view distance the client may request.
+const MIN_VIEW_DISTANCE: u32 = 2;
+
+fn handle_client_settings(
+    game: &mut Game,
+    server: &Server,
+    player: Entity,
+    packet: client::ClientSettings,
+) -> SysResult {
+    game.ecs.get_mut::<EntityMetadata>(player)?.set(
+        META_INDEX_PLAYER_DISPLAYED_SKIN_PARTS,
+        packet.displayed_skin_parts,
+    );
+
+    // Clients send their settings again whenever the
+    // render distance changes. The server's view
+    // distance is the maximum.
+    let view_distance = (packet.view_distance as u32)
+        .max(MIN_VIEW_DISTANCE)
+        .min(server.options.view_distance);
+    view::set_view_distance(game, player, view_distance)
+}
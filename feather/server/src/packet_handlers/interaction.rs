@@ -1,10 +1,12 @@
 use crate::{ClientId, NetworkId, Server};
+use base::Position;
 use common::entities::player::HotbarSlot;
 use common::interactable::InteractableRegistry;
-use common::Game;
+use common::{combat_log, conversion, regions, world_settings};
+use common::{Game, Window};
 use ecs::{Entity, EntityRef, SysResult};
 use libcraft_core::{BlockFace as LibcraftBlockFace, Hand};
-use libcraft_core::{InteractionType, Vec3f};
+use libcraft_core::{BlockPosition, InteractionType, Vec3f};
 use protocol::packets::client::{
     BlockFace, HeldItemChange, InteractEntity, InteractEntityKind, PlayerBlockPlacement,
     PlayerDigging, PlayerDiggingStatus,
@@ -77,6 +79,10 @@ pub fn handle_player_block_placement(
         .expect("Failed to get the interactable registry");
 
     if interactable_registry.is_registered(block_kind) {
+        if !regions::can_interact(game, player, packet.position) {
+            return Ok(());
+        }
+
         // Handle this as a block interaction
         let event = BlockInteractEvent {
             hand,
@@ -88,6 +94,19 @@ pub fn handle_player_block_placement(
 
         game.ecs.insert_entity_event(player, event)?;
     } else {
+        let placed = placed_position(packet.position, face);
+        if !regions::can_build(game, player, placed) {
+            // The client already placed the block; undo that.
+            let client_id = *game.ecs.get::<ClientId>(player)?;
+            let client = _server.clients.get(client_id).unwrap();
+            for pos in [packet.position, placed].iter().copied() {
+                if let Some(block) = game.block(pos) {
+                    client.send_block_change(pos, block);
+                }
+            }
+            return Ok(());
+        }
+
         // Handle this as a block placement
         let event = BlockPlacementEvent {
             hand,
@@ -103,6 +122,18 @@ pub fn handle_player_block_placement(
     Ok(())
 }
 
+/// Returns the position of a block placed against `face` of the block at `pos`.
+fn placed_position(pos: BlockPosition, face: LibcraftBlockFace) -> BlockPosition {
+    match face {
+        LibcraftBlockFace::Top => pos.up(),
+        LibcraftBlockFace::Bottom => pos.down(),
+        LibcraftBlockFace::North => pos.north(),
+        LibcraftBlockFace::South => pos.south(),
+        LibcraftBlockFace::East => pos.east(),
+        LibcraftBlockFace::West => pos.west(),
+    }
+}
+
 /// Handles the Player Digging packet sent for the following
 /// actions:
 /// * Breaking blocks.
@@ -110,10 +141,24 @@ pub fn handle_player_block_placement(
 /// * Shooting arrows.
 /// * Eating.
 /// * Swapping items between the main and off hand.
-pub fn handle_player_digging(game: &mut Game, packet: PlayerDigging, _player: Entity) -> SysResult {
+pub fn handle_player_digging(
+    game: &mut Game,
+    server: &mut Server,
+    packet: PlayerDigging,
+    player: Entity,
+) -> SysResult {
     log::trace!("Got player digging with status {:?}", packet.status);
     match packet.status {
         PlayerDiggingStatus::StartDigging | PlayerDiggingStatus::CancelDigging => {
+            if !regions::can_build(game, player, packet.position) {
+                // The client already removed the block; restore it.
+                let client_id = *game.ecs.get::<ClientId>(player)?;
+                if let Some(block) = game.block(packet.position) {
+                    let client = server.clients.get(client_id).unwrap();
+                    client.send_block_change(packet.position, block);
+                }
+                return Ok(());
+            }
             game.break_block(packet.position);
             Ok(())
         }
@@ -150,6 +195,26 @@ pub fn handle_interact_entity(
         }
     };
 
+    if let InteractEntityKind::Attack = packet.kind {
+        if !world_settings::can_attack(game, player, target) {
+            return Ok(());
+        }
+        combat_log::on_attack(game, player, target)?;
+    } else {
+        let target_pos = game.ecs.get::<Position>(target)?.block();
+        if !regions::can_interact(game, player, target_pos) {
+            return Ok(());
+        }
+        if let InteractEntityKind::Interact = packet.kind {
+            if conversion::on_interact(game, player, target)? {
+                let client_id = *game.ecs.get::<ClientId>(player)?;
+                if let Some(client) = _server.clients.get(client_id) {
+                    client.send_window_items(0, &*game.ecs.get::<Window>(player)?);
+                }
+            }
+        }
+    }
+
     let event = match packet.kind {
         InteractEntityKind::Attack => InteractEntityEvent {
             target: EntityId(target.id() as u64),
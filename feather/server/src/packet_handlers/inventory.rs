@@ -45,11 +45,15 @@ pub fn handle_click_window(
     let window = player.get::<Window>()?;
 
     if packet.slot >= 0 {
-        client.set_slot(packet.slot, window.item(packet.slot as usize)?.clone());
+        client.set_slot(
+            packet.window_id,
+            packet.slot,
+            window.item(packet.slot as usize)?.clone(),
+        );
     }
     client.set_cursor_slot(window.cursor_item());
 
-    client.send_window_items(&*window);
+    client.send_window_items(packet.window_id, &*window);
 
     result
 }
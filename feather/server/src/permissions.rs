@@ -0,0 +1,89 @@
+//! Loads `permissions.json`, which grants permission
+//! nodes to players.
+//!
+//! Entries are keyed by UUID so grants survive name changes;
+//! the name is only informational, like in vanilla's `ops.json`:
+//!
+//! ```json
+//! [{"uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5", "name": "Notch", "permissions": ["server.*"]}]
+//! ```
+
+use std::{fs, io, path::Path};
+
+use ahash::AHashMap;
+use common::{permissions::Permissions, Game};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Path of the permissions file, relative to the working directory.
+pub const PERMISSIONS_PATH: &str = "permissions.json";
+
+pub fn register(game: &mut Game) {
+    let store = match PermissionStore::load(PERMISSIONS_PATH) {
+        Ok(store) => store,
+        Err(e) => {
+            log::warn!("Failed to load {}: {:?}", PERMISSIONS_PATH, e);
+            PermissionStore::default()
+        }
+    };
+    game.insert_resource(store);
+}
+
+/// Entry in `permissions.json`. The `name` field is ignored.
+#[derive(Debug, Deserialize)]
+struct Entry {
+    uuid: Uuid,
+    permissions: Vec<String>,
+}
+
+/// Resource storing the permission nodes granted to each player.
+#[derive(Debug, Default)]
+pub struct PermissionStore {
+    grants: AHashMap<Uuid, Vec<String>>,
+}
+
+impl PermissionStore {
+    /// Loads the permissions from `path`. A missing
+    /// file results in no permissions being granted.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => Self::from_json(&json),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn from_json(json: &str) -> anyhow::Result<Self> {
+        let entries: Vec<Entry> = serde_json::from_str(json)?;
+        let mut store = Self::default();
+        for entry in entries {
+            store
+                .grants
+                .entry(entry.uuid)
+                .or_default()
+                .extend(entry.permissions);
+        }
+        Ok(store)
+    }
+
+    /// Gets the `Permissions` component for the player with `uuid`.
+    pub fn permissions_for(&self, uuid: Uuid) -> Permissions {
+        Permissions::new(self.grants.get(&uuid).cloned().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grants_by_uuid() {
+        let notch: Uuid = "069a79f4-44e9-4726-a5be-fca90e38aaf5".parse().unwrap();
+        let store = PermissionStore::from_json(
+            r#"[{"uuid":"069a79f4-44e9-4726-a5be-fca90e38aaf5","name":"Notch","permissions":["server.silentjoin"]}]"#,
+        )
+        .unwrap();
+        assert!(store.permissions_for(notch).has("server.silentjoin"));
+        assert!(!store.permissions_for(Uuid::nil()).has("server.silentjoin"));
+    }
+}
@@ -3,11 +3,19 @@ use std::sync::{
     Arc,
 };
 
+use ahash::AHashMap;
+use parking_lot::Mutex;
+use uuid::Uuid;
+
 #[derive(Debug)]
 pub struct MaxPlayersReached;
 
 /// Maintains the server player count.
 ///
+/// A player who logs in while already online takes over
+/// their own slot, so the new connection isn't turned away
+/// when the server is full.
+///
 /// Can be cloned to create a new handle.
 #[derive(Clone)]
 pub struct PlayerCount {
@@ -20,31 +28,38 @@ impl PlayerCount {
             inner: Arc::new(Inner {
                 count: AtomicU32::new(0),
                 max_players,
+                sessions: Mutex::new(AHashMap::new()),
             }),
         }
     }
 
-    pub fn try_add_player(&self) -> Result<(), MaxPlayersReached> {
-        loop {
-            let current_count = self.inner.count.load(Ordering::SeqCst);
-            let new_count = current_count + 1;
-            if new_count > self.inner.max_players {
-                return Err(MaxPlayersReached);
-            }
-
-            if self
-                .inner
-                .count
-                .compare_exchange(current_count, new_count, Ordering::SeqCst, Ordering::SeqCst)
-                .is_ok()
-            {
-                return Ok(());
-            }
+    pub fn try_add_player(&self, uuid: Uuid) -> Result<(), MaxPlayersReached> {
+        let mut sessions = self.inner.sessions.lock();
+        if let Some(count) = sessions.get_mut(&uuid) {
+            *count += 1;
+            return Ok(());
         }
+        if sessions.len() as u32 >= self.inner.max_players {
+            return Err(MaxPlayersReached);
+        }
+        sessions.insert(uuid, 1);
+        self.inner
+            .count
+            .store(sessions.len() as u32, Ordering::SeqCst);
+        Ok(())
     }
 
-    pub fn remove_player(&self) {
-        self.inner.count.fetch_sub(1, Ordering::SeqCst);
+    pub fn remove_player(&self, uuid: Uuid) {
+        let mut sessions = self.inner.sessions.lock();
+        if let Some(count) = sessions.get_mut(&uuid) {
+            *count -= 1;
+            if *count == 0 {
+                sessions.remove(&uuid);
+            }
+        }
+        self.inner
+            .count
+            .store(sessions.len() as u32, Ordering::SeqCst);
     }
 
     pub fn get(&self) -> u32 {
@@ -53,8 +68,12 @@ impl PlayerCount {
 }
 
 struct Inner {
+    /// Number of online players, readable without locking `sessions`.
     count: AtomicU32,
     max_players: u32,
+    /// Number of connections of each online player. Only more
+    /// than 1 while a duplicate login replaces the old session.
+    sessions: Mutex<AHashMap<Uuid, u32>>,
 }
 
 #[cfg(test)]
@@ -67,15 +86,32 @@ mod tests {
     fn try_add() {
         let count = PlayerCount::new(1);
         assert_eq!(count.get(), 0);
-        count.try_add_player().unwrap();
+        count.try_add_player(Uuid::from_u128(0)).unwrap();
         assert_eq!(count.get(), 1);
 
-        for _ in 0..10 {
-            count.try_add_player().unwrap_err();
+        for i in 1..=10 {
+            count.try_add_player(Uuid::from_u128(i)).unwrap_err();
             assert_eq!(count.get(), 1);
         }
     }
 
+    #[test]
+    fn duplicate_login_keeps_slot() {
+        let count = PlayerCount::new(1);
+        let uuid = Uuid::from_u128(0);
+        count.try_add_player(uuid).unwrap();
+        count.try_add_player(uuid).unwrap();
+        assert_eq!(count.get(), 1);
+
+        // The old session closes.
+        count.remove_player(uuid);
+        assert_eq!(count.get(), 1);
+        count.try_add_player(Uuid::from_u128(1)).unwrap_err();
+
+        count.remove_player(uuid);
+        assert_eq!(count.get(), 0);
+    }
+
     #[test]
     fn no_race_conditions() {
         let threads = 8;
@@ -87,10 +123,13 @@ mod tests {
         let num_added = AtomicU32::new(0);
 
         thread::scope(|s| {
-            for _ in 0..threads {
-                s.spawn(|_| {
-                    for _ in 0..players_per_thread {
-                        if count.try_add_player().is_ok() {
+            for thread in 0..threads {
+                let count = &count;
+                let num_added = &num_added;
+                s.spawn(move |_| {
+                    for i in 0..players_per_thread {
+                        let uuid = Uuid::from_u128((thread * players_per_thread + i) as u128);
+                        if count.try_add_player(uuid).is_ok() {
                             num_added.fetch_add(1, Ordering::SeqCst);
                         }
                     }
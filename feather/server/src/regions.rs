@@ -0,0 +1,171 @@
+//! Saves the [protected regions](common::regions) of each
+//! world in `regions.json`, so that they survive restarts.
+//!
+//! When a database is configured, the regions are kept
+//! in its `regions` table instead of the file.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io, mem,
+    path::{Path, PathBuf},
+};
+
+use common::{events::RegionsChangeEvent, Game};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::region::Region;
+
+use crate::storage::{self, Backend, Storage, Write};
+
+/// Path of the regions file, relative to the working directory.
+pub const REGIONS_PATH: &str = "regions.json";
+
+/// Table storing the regions in a database, keyed by world name.
+const TABLE: &str = "regions";
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    let store = match game.resources.get::<Storage>() {
+        Ok(storage) => RegionStore::load_from_storage(&storage).unwrap_or_else(|e| {
+            log::warn!("Failed to load the {} table: {:?}", TABLE, e);
+            RegionStore::new(REGIONS_PATH)
+        }),
+        Err(_) => {
+            storage::load_or_move_aside(Path::new(REGIONS_PATH), || RegionStore::load(REGIONS_PATH))
+                .unwrap_or_else(|| RegionStore::new(REGIONS_PATH))
+        }
+    };
+    game.insert_resource(store);
+    systems.add_system(save_changed_regions);
+}
+
+/// Adds the saved regions of `game.world` to it.
+///
+/// Must be called after the world is created.
+pub fn load_world_regions(game: &mut Game) -> anyhow::Result<()> {
+    let store = game.resources.get::<RegionStore>()?;
+    if let Some(regions) = store.worlds.get(game.world.name()) {
+        for region in regions {
+            game.world.regions_mut().insert(region.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Resource storing the saved regions of each world,
+/// including worlds which aren't loaded.
+pub struct RegionStore {
+    path: PathBuf,
+    worlds: BTreeMap<String, Vec<Region>>,
+    /// Worlds whose regions changed since the last save.
+    changed: BTreeSet<String>,
+}
+
+impl RegionStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            worlds: BTreeMap::new(),
+            changed: BTreeSet::new(),
+        }
+    }
+
+    /// Loads the regions from `path`.
+    /// A missing file results in no regions.
+    pub fn load(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let mut store = Self::new(path);
+        match fs::read_to_string(&store.path) {
+            Ok(json) => store.worlds = serde_json::from_str(&json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(store)
+    }
+
+    /// Loads the regions from a database.
+    pub fn load_from_storage(storage: &Storage) -> anyhow::Result<Self> {
+        let mut store = Self::new(REGIONS_PATH);
+        store.worlds = storage.load(TABLE)?.into_iter().collect();
+        Ok(store)
+    }
+
+    /// Replaces the saved regions of the world called `world`.
+    pub fn update(&mut self, world: &str, regions: Vec<Region>) {
+        if regions.is_empty() {
+            self.worlds.remove(world);
+        } else {
+            self.worlds.insert(world.to_owned(), regions);
+        }
+        self.changed.insert(world.to_owned());
+    }
+
+    /// Writes the worlds changed since the last save to `storage`,
+    /// or rewrites the file if no database is configured.
+    pub fn save(&mut self, storage: Option<&Storage>) -> anyhow::Result<()> {
+        if self.changed.is_empty() {
+            return Ok(());
+        }
+        match storage {
+            Some(storage) => {
+                for world in mem::take(&mut self.changed) {
+                    match self.worlds.get(&world) {
+                        Some(regions) => storage.put(TABLE, &world, regions)?,
+                        None => storage.remove(TABLE, &world),
+                    }
+                }
+            }
+            None => {
+                let json = serde_json::to_vec_pretty(&self.worlds)?;
+                storage::write_atomically(&self.path, &json)?;
+                self.changed.clear();
+            }
+        }
+        Ok(())
+    }
+}
+
+fn save_changed_regions(game: &mut Game) -> SysResult {
+    let mut changed: Vec<String> = game
+        .ecs
+        .query::<&RegionsChangeEvent>()
+        .iter()
+        .map(|(_, event)| event.world.clone())
+        .collect();
+    changed.sort_unstable();
+    changed.dedup();
+
+    for world in changed {
+        let regions = match game.world_named_mut(&world) {
+            Some(world) => world.regions().iter().cloned().collect(),
+            None => continue,
+        };
+        game.resources
+            .get_mut::<RegionStore>()?
+            .update(&world, regions);
+    }
+
+    let storage = game.resources.get::<Storage>().ok();
+    if let Err(e) = game
+        .resources
+        .get_mut::<RegionStore>()?
+        .save(storage.as_deref())
+    {
+        log::error!("Failed to save the regions: {:?}", e);
+    }
+    Ok(())
+}
+
+/// Migration importing the regions of `regions.json` into a database.
+pub(crate) fn import_file(backend: &mut dyn Backend) -> anyhow::Result<()> {
+    let store = RegionStore::load(REGIONS_PATH)?;
+    let batch = store
+        .worlds
+        .iter()
+        .map(|(world, regions)| {
+            Ok(Write::Put {
+                table: TABLE.to_owned(),
+                key: world.clone(),
+                value: serde_json::to_vec(regions)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    backend.apply(&batch)
+}
@@ -0,0 +1,63 @@
+//! The Tokio runtime connections are handled on.
+
+use std::{future::Future, io, thread};
+
+use feather_server::config::{self, RuntimeFlavor};
+use tokio::{
+    runtime::{Builder, Handle, Runtime},
+    sync::oneshot,
+};
+
+/// A Tokio runtime built from the `[runtime]` config section.
+pub enum IoRuntime {
+    MultiThread(Runtime),
+    /// The runtime is driven by a dedicated thread, since the
+    /// game loop blocks the thread it runs on.
+    CurrentThread {
+        handle: Handle,
+        /// Stops the driving thread when dropped.
+        _stop: oneshot::Sender<()>,
+    },
+}
+
+impl IoRuntime {
+    pub fn new(config: &config::Runtime) -> io::Result<Self> {
+        match config.flavor {
+            RuntimeFlavor::MultiThread => {
+                let mut builder = Builder::new_multi_thread();
+                if config.worker_threads > 0 {
+                    builder.worker_threads(config.worker_threads);
+                }
+                let runtime = builder.enable_all().thread_name("io-worker").build()?;
+                Ok(IoRuntime::MultiThread(runtime))
+            }
+            RuntimeFlavor::CurrentThread => {
+                let runtime = Builder::new_current_thread().enable_all().build()?;
+                let handle = runtime.handle().clone();
+                let (stop_tx, stop_rx) = oneshot::channel::<()>();
+                thread::Builder::new()
+                    .name("io-worker".to_owned())
+                    .spawn(move || {
+                        // Also returns once the sender is dropped.
+                        let _ = runtime.block_on(stop_rx);
+                    })?;
+                Ok(IoRuntime::CurrentThread {
+                    handle,
+                    _stop: stop_tx,
+                })
+            }
+        }
+    }
+
+    /// Runs a future to completion on the current thread,
+    /// within the context of the runtime.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        match self {
+            IoRuntime::MultiThread(runtime) => runtime.block_on(future),
+            IoRuntime::CurrentThread { handle, .. } => {
+                let _guard = handle.enter();
+                futures_lite::future::block_on(future)
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use base::Text;
+use parking_lot::RwLock;
+use rand::seq::SliceRandom;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    favicon::Favicon,
+    initial_handler::{PROTOCOL_VERSION, SERVER_NAME},
+    options::Options,
+};
+
+/// Caches the JSON response sent to status (server list) pings.
+///
+/// The response is rebuilt on the server thread at most once
+/// per second, so connection workers can answer pings without
+/// any round trip to the server thread.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone)]
+pub struct StatusCache {
+    inner: Arc<RwLock<Arc<str>>>,
+}
+
+impl StatusCache {
+    pub fn new(options: &Options) -> Self {
+        let cache = Self {
+            inner: Arc::new(RwLock::new(Arc::from(""))),
+        };
+        cache.update(options, 0, &[]);
+        cache
+    }
+
+    /// Gets the cached status JSON.
+    pub fn get(&self) -> Arc<str> {
+        Arc::clone(&self.inner.read())
+    }
+
+    /// Rebuilds the cached status JSON.
+    ///
+    /// `players` are the online players which may
+    /// be shown in the player sample.
+    pub fn update(&self, options: &Options, online_players: u32, players: &[(String, Uuid)]) {
+        let payload = StatusResponse {
+            version: Version {
+                name: SERVER_NAME,
+                protocol: PROTOCOL_VERSION,
+            },
+            players: Players {
+                max: options.max_players,
+                online: online_players,
+                sample: sample(options, players),
+            },
+            description: &options.motd,
+            favicon: options.favicon.as_ref().map(Favicon::base64_encoded),
+        };
+        let json = serde_json::to_string(&payload).expect("failed to serialize status response");
+        *self.inner.write() = Arc::from(json);
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StatusResponse<'a> {
+    version: Version,
+    players: Players,
+    description: &'a Text,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    favicon: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Version {
+    name: &'static str,
+    protocol: i32,
+}
+
+#[derive(Debug, Serialize)]
+struct Players {
+    max: u32,
+    online: u32,
+    sample: Vec<SamplePlayer>,
+}
+
+/// Entry in the player list shown when
+/// hovering over the player count.
+#[derive(Debug, Serialize)]
+struct SamplePlayer {
+    name: String,
+    id: Uuid,
+}
+
+/// Builds the player sample: the custom lines if configured,
+/// or else a random selection of online players, as in vanilla.
+fn sample(options: &Options, players: &[(String, Uuid)]) -> Vec<SamplePlayer> {
+    if !options.status_sample_lines.is_empty() {
+        return options
+            .status_sample_lines
+            .iter()
+            .map(|line| SamplePlayer {
+                name: line.clone(),
+                id: Uuid::nil(),
+            })
+            .collect();
+    }
+
+    players
+        .choose_multiple(&mut rand::thread_rng(), options.status_sample_size)
+        .map(|(name, uuid)| SamplePlayer {
+            name: name.clone(),
+            id: *uuid,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use base::Gamemode;
+    use common::combat_log::CombatLogAction;
+
+    use super::*;
+
+    fn options() -> Options {
+        Options {
+            port: 25565,
+            bind_address: "0.0.0.0".to_owned(),
+            bind_unix: None,
+            favicon: None,
+            motd: Text::from("A Feather server"),
+            online_mode: false,
+            view_distance: 8,
+            max_players: 16,
+            default_gamemode: Gamemode::Creative,
+            world_dir: "world".into(),
+            chunk_unload_delay: Default::default(),
+            max_loaded_chunks: None,
+            starter_kit: Vec::new(),
+            first_spawn: None,
+            join_motd: None,
+            join_message: None,
+            quit_message: None,
+            afk_timeout: None,
+            afk_message: None,
+            afk_return_message: None,
+            combat_log_duration: None,
+            combat_log_action: CombatLogAction::EventOnly,
+            combat_log_npc_lifetime: Default::default(),
+            economy_currency: Default::default(),
+            economy_starting_balance: 0,
+            storage: Default::default(),
+            resource_pack: None,
+            sniffer: None,
+            capture_dir: None,
+            status_sample_size: 2,
+            status_sample_lines: Vec::new(),
+            restart_warnings: Vec::new(),
+            check_invariants: false,
+            report_tick_times: false,
+            proxy_mode: None,
+            velocity_secret: String::new(),
+            proxy_protocol: false,
+            compression_threshold: None,
+            compression_level: 6,
+            max_connections_per_ip: 0,
+            connection_throttle_window: Default::default(),
+            max_concurrent_handshakes: 128,
+            handshake_timeout: Default::default(),
+            login_timeout: Default::default(),
+            encryption_timeout: Default::default(),
+            max_movement_packets_per_second: 0,
+            max_packets_per_second: 0,
+            rcon_port: None,
+            rcon_password: String::new(),
+            query_port: None,
+            hooks: Default::default(),
+        }
+    }
+
+    #[test]
+    fn update_rebuilds_json() {
+        let options = options();
+        let cache = StatusCache::new(&options);
+        let json: serde_json::Value = serde_json::from_str(&cache.get()).unwrap();
+        assert_eq!(json["players"]["online"], 0);
+        assert_eq!(json["players"]["max"], 16);
+        assert_eq!(json["version"]["protocol"], PROTOCOL_VERSION);
+        assert!(json.get("favicon").is_none());
+
+        cache.update(&options, 5, &[]);
+        let json: serde_json::Value = serde_json::from_str(&cache.get()).unwrap();
+        assert_eq!(json["players"]["online"], 5);
+    }
+
+    #[test]
+    fn player_sample() {
+        let mut options = options();
+        let players = vec![
+            ("Notch".to_owned(), Uuid::from_u128(1)),
+            ("jeb_".to_owned(), Uuid::from_u128(2)),
+            ("Dinnerbone".to_owned(), Uuid::from_u128(3)),
+        ];
+        let cache = StatusCache::new(&options);
+        cache.update(&options, 3, &players);
+        let json: serde_json::Value = serde_json::from_str(&cache.get()).unwrap();
+        let sample = json["players"]["sample"].as_array().unwrap();
+        assert_eq!(sample.len(), 2);
+        assert!(players
+            .iter()
+            .any(|(name, uuid)| sample[0]["name"] == name.as_str()
+                && sample[0]["id"] == uuid.to_string()));
+
+        options.status_sample_lines = vec!["\u{a7}6Welcome!".to_owned()];
+        cache.update(&options, 3, &players);
+        let json: serde_json::Value = serde_json::from_str(&cache.get()).unwrap();
+        assert_eq!(json["players"]["sample"][0]["name"], "\u{a7}6Welcome!");
+        assert_eq!(
+            json["players"]["sample"][0]["id"],
+            "00000000-0000-0000-0000-000000000000"
+        );
+    }
+
+    #[test]
+    fn description_is_escaped() {
+        let mut options = options();
+        options.motd = Text::from_legacy("\u{a7}6\"Quoted\" \\ server\nline two");
+        let cache = StatusCache::new(&options);
+        let json: serde_json::Value = serde_json::from_str(&cache.get()).unwrap();
+        assert_eq!(
+            json["description"][1]["text"],
+            "\"Quoted\" \\ server\nline two"
+        );
+        assert_eq!(json["description"][1]["color"], "gold");
+    }
+}
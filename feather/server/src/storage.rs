@@ -0,0 +1,453 @@
+//! Database storage for server data, as an alternative to
+//! the JSON files written by default.
+//!
+//! Data is kept in named tables mapping string keys to JSON values.
+//! Writes are sent to a background thread which commits them in
+//! batches, so the game loop never waits for the disk. Stores which
+//! support a database check for the [`Storage`] resource and fall back
+//! to their file when it's absent.
+//!
+//! Opening a database runs any [`MIGRATIONS`] it hasn't seen yet,
+//! such as importing the data of the JSON files it replaces.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use common::Game;
+use flume::{Receiver, RecvTimeoutError, Sender};
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::Options;
+
+pub use player_set::PlayerSet;
+
+mod player_set;
+#[cfg(feature = "storage-sled")]
+mod sled;
+#[cfg(feature = "storage-sqlite")]
+mod sqlite;
+
+/// Options for storing server data.
+#[derive(Debug, Clone)]
+pub struct StorageOptions {
+    pub backend: BackendKind,
+    /// Path of the database.
+    pub path: PathBuf,
+    /// How long writes are gathered before being committed together.
+    pub batch_window: Duration,
+}
+
+impl Default for StorageOptions {
+    fn default() -> Self {
+        Self {
+            backend: BackendKind::Json,
+            path: PathBuf::from("server_data"),
+            batch_window: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BackendKind {
+    /// Each store keeps its own JSON file.
+    Json,
+    Sled,
+    Sqlite,
+}
+
+/// Opens the configured database, if any,
+/// and adds it as the [`Storage`] resource.
+pub fn register(game: &mut Game, options: &Options) -> anyhow::Result<()> {
+    if let Some(storage) = Storage::open(&options.storage)? {
+        game.insert_resource(storage);
+    }
+    Ok(())
+}
+
+/// Renames a data file which couldn't be loaded, so that the
+/// store starting out empty doesn't overwrite it when it saves.
+///
+/// The file is renamed to e.g. `regions.json.corrupt-1700000000`.
+/// Returns the new path, or `None` if there is no file at `path`.
+pub fn move_aside(path: &Path) -> io::Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let mut moved = path.as_os_str().to_owned();
+    moved.push(format!(".corrupt-{}", timestamp));
+    let moved = PathBuf::from(moved);
+    fs::rename(path, &moved)?;
+    Ok(Some(moved))
+}
+
+/// Replaces the file at `path` with `contents`. The contents are
+/// written to a temporary file first, so that the file is never
+/// left half-written if the server stops while writing it.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+/// Loads a data file with `load`, moving it aside with [`move_aside`]
+/// if it can't be loaded. Returns `None` in that case, so that the
+/// caller can start with empty data.
+///
+/// # Panics
+/// Panics if the file can't be moved aside either, as the
+/// server would otherwise overwrite it.
+pub fn load_or_move_aside<T>(path: &Path, load: impl FnOnce() -> anyhow::Result<T>) -> Option<T> {
+    let e = match load() {
+        Ok(data) => return Some(data),
+        Err(e) => e,
+    };
+    log::error!("Failed to load {}: {:?}", path.display(), e);
+    match move_aside(path) {
+        Ok(Some(moved)) => {
+            log::error!("Moved {} to {}", path.display(), moved.display());
+            None
+        }
+        Ok(None) => None,
+        Err(move_error) => panic!(
+            "{} can't be loaded, and moving it aside failed: {}",
+            path.display(),
+            move_error
+        ),
+    }
+}
+
+/// A change to a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Write {
+    Put {
+        table: String,
+        key: String,
+        value: Vec<u8>,
+    },
+    Remove {
+        table: String,
+        key: String,
+    },
+}
+
+/// A database storing server data.
+pub trait Backend: Send + 'static {
+    /// Reads all entries of a table.
+    fn load(&self, table: &str) -> anyhow::Result<Vec<(String, Vec<u8>)>>;
+
+    /// Applies a batch of writes in order. Backends apply
+    /// the whole batch or none of it where they can.
+    fn apply(&mut self, batch: &[Write]) -> anyhow::Result<()>;
+
+    /// Returns the number of [`MIGRATIONS`] run on this database.
+    fn schema_version(&self) -> anyhow::Result<u32>;
+
+    fn set_schema_version(&mut self, version: u32) -> anyhow::Result<()>;
+}
+
+/// A step bringing a database up to date.
+pub struct Migration {
+    pub description: &'static str,
+    pub run: fn(&mut dyn Backend) -> anyhow::Result<()>,
+}
+
+/// Migrations run on databases in order. Append new
+/// migrations to the end; never reorder or remove them.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "import usercache.json",
+        run: crate::user_cache::import_file,
+    },
+    Migration {
+        description: "import economy.json",
+        run: crate::economy::import_file,
+    },
+    Migration {
+        description: "import regions.json",
+        run: crate::regions::import_file,
+    },
+    Migration {
+        description: "import combat_loggers.json",
+        run: crate::systems::combat_log::import_file,
+    },
+    Migration {
+        description: "import vanished.json",
+        run: crate::systems::vanish::import_file,
+    },
+];
+
+enum Message {
+    Write(Write),
+    /// Commits pending writes, then notifies the sender.
+    Flush(Sender<()>),
+}
+
+/// Resource giving access to the configured database.
+pub struct Storage {
+    backend: Arc<Mutex<Box<dyn Backend>>>,
+    messages: Option<Sender<Message>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Storage {
+    /// Opens the database described by `options`, returning
+    /// `None` if data is stored in JSON files instead.
+    pub fn open(options: &StorageOptions) -> anyhow::Result<Option<Self>> {
+        let backend: Box<dyn Backend> = match options.backend {
+            BackendKind::Json => return Ok(None),
+            #[cfg(feature = "storage-sled")]
+            BackendKind::Sled => Box::new(self::sled::SledBackend::open(&options.path)?),
+            #[cfg(feature = "storage-sqlite")]
+            BackendKind::Sqlite => Box::new(self::sqlite::SqliteBackend::open(&options.path)?),
+            #[allow(unreachable_patterns)]
+            backend => anyhow::bail!(
+                "the {:?} storage backend is not enabled in this build of Feather",
+                backend
+            ),
+        };
+        log::info!(
+            "Storing server data in {} ({:?})",
+            options.path.display(),
+            options.backend
+        );
+        Self::with_backend(backend, options.batch_window).map(Some)
+    }
+
+    /// Wraps a backend, running pending migrations.
+    pub fn with_backend(
+        mut backend: Box<dyn Backend>,
+        batch_window: Duration,
+    ) -> anyhow::Result<Self> {
+        migrate(&mut *backend)?;
+
+        let backend = Arc::new(Mutex::new(backend));
+        let (messages, receiver) = flume::unbounded();
+        let writer = {
+            let backend = Arc::clone(&backend);
+            thread::Builder::new()
+                .name("storage-writer".to_owned())
+                .spawn(move || run_writer(&backend, receiver, batch_window))?
+        };
+        Ok(Self {
+            backend,
+            messages: Some(messages),
+            writer: Some(writer),
+        })
+    }
+
+    /// Reads all entries of a table, including
+    /// writes which haven't been committed yet.
+    pub fn load<T: DeserializeOwned>(&self, table: &str) -> anyhow::Result<Vec<(String, T)>> {
+        self.flush();
+        self.backend
+            .lock()
+            .load(table)?
+            .into_iter()
+            .map(|(key, value)| Ok((key, serde_json::from_slice(&value)?)))
+            .collect()
+    }
+
+    /// Sets the value of `key`. The write is committed in the background.
+    pub fn put<T: Serialize>(&self, table: &str, key: &str, value: &T) -> anyhow::Result<()> {
+        let value = serde_json::to_vec(value)?;
+        self.send(Message::Write(Write::Put {
+            table: table.to_owned(),
+            key: key.to_owned(),
+            value,
+        }));
+        Ok(())
+    }
+
+    /// Removes `key`. The write is committed in the background.
+    pub fn remove(&self, table: &str, key: &str) {
+        self.send(Message::Write(Write::Remove {
+            table: table.to_owned(),
+            key: key.to_owned(),
+        }));
+    }
+
+    /// Blocks until all writes so far are committed.
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = flume::bounded(1);
+        self.send(Message::Flush(done_tx));
+        let _ = done_rx.recv();
+    }
+
+    fn send(&self, message: Message) {
+        if let Some(messages) = &self.messages {
+            // Only fails if the writer panicked, which it logs.
+            let _ = messages.send(message);
+        }
+    }
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        // Disconnecting makes the writer commit its last batch and stop.
+        self.messages.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+fn migrate(backend: &mut dyn Backend) -> anyhow::Result<()> {
+    let version = backend.schema_version()? as usize;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        log::info!("Migrating storage: {}", migration.description);
+        (migration.run)(backend)?;
+        backend.set_schema_version(index as u32 + 1)?;
+    }
+    Ok(())
+}
+
+fn run_writer(backend: &Mutex<Box<dyn Backend>>, messages: Receiver<Message>, window: Duration) {
+    let mut batch = Vec::new();
+    let mut flushes = Vec::new();
+    while let Ok(first) = messages.recv() {
+        let deadline = Instant::now() + window;
+        let mut next = Ok(first);
+        let mut disconnected = false;
+        loop {
+            match next {
+                Ok(Message::Write(write)) => batch.push(write),
+                Ok(Message::Flush(done)) => {
+                    flushes.push(done);
+                    break;
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+            next = messages.recv_deadline(deadline);
+        }
+
+        if !batch.is_empty() {
+            if let Err(e) = backend.lock().apply(&batch) {
+                log::error!("Failed to commit {} storage writes: {:?}", batch.len(), e);
+            }
+            batch.clear();
+        }
+        for done in flushes.drain(..) {
+            let _ = done.send(());
+        }
+        if disconnected {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    /// Backend recording each batch it commits.
+    #[derive(Default)]
+    struct MemoryBackend {
+        tables: BTreeMap<String, BTreeMap<String, Vec<u8>>>,
+        batches: Arc<Mutex<Vec<usize>>>,
+        version: u32,
+    }
+
+    impl Backend for MemoryBackend {
+        fn load(&self, table: &str) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+            Ok(self
+                .tables
+                .get(table)
+                .map(|table| table.clone().into_iter().collect())
+                .unwrap_or_default())
+        }
+
+        fn apply(&mut self, batch: &[Write]) -> anyhow::Result<()> {
+            for write in batch {
+                match write {
+                    Write::Put { table, key, value } => {
+                        self.tables
+                            .entry(table.clone())
+                            .or_default()
+                            .insert(key.clone(), value.clone());
+                    }
+                    Write::Remove { table, key } => {
+                        if let Some(table) = self.tables.get_mut(table) {
+                            table.remove(key);
+                        }
+                    }
+                }
+            }
+            self.batches.lock().push(batch.len());
+            Ok(())
+        }
+
+        fn schema_version(&self) -> anyhow::Result<u32> {
+            Ok(self.version)
+        }
+
+        fn set_schema_version(&mut self, version: u32) -> anyhow::Result<()> {
+            self.version = version;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_are_batched() {
+        let backend = MemoryBackend::default();
+        let batches = Arc::clone(&backend.batches);
+        let storage = Storage::with_backend(Box::new(backend), Duration::from_secs(60)).unwrap();
+
+        storage.put("scores", "a", &1).unwrap();
+        storage.put("scores", "b", &2).unwrap();
+        storage.put("scores", "a", &3).unwrap();
+        storage.remove("scores", "b");
+
+        let scores: Vec<(String, i32)> = storage.load("scores").unwrap();
+        assert_eq!(scores, vec![("a".to_owned(), 3)]);
+        assert_eq!(*batches.lock(), vec![4]);
+    }
+
+    #[test]
+    fn migrations_run_once() {
+        let backend = MemoryBackend {
+            version: MIGRATIONS.len() as u32,
+            ..Default::default()
+        };
+        let batches = Arc::clone(&backend.batches);
+        let storage = Storage::with_backend(Box::new(backend), Duration::from_secs(60)).unwrap();
+        drop(storage);
+        assert!(batches.lock().is_empty());
+    }
+
+    #[test]
+    fn unloadable_files_are_moved_aside() {
+        let dir = std::env::temp_dir().join(format!("feather-move-aside-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("regions.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        let loaded = load_or_move_aside(&path, || {
+            Ok(serde_json::from_str::<Vec<u32>>(&fs::read_to_string(
+                &path,
+            )?)?)
+        });
+        assert!(loaded.is_none());
+        assert!(!path.exists());
+        let moved: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(moved.len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use ahash::AHashSet;
+use uuid::Uuid;
+
+use super::{Backend, Storage, Write};
+
+/// A set of players, kept in a JSON file listing their UUIDs
+/// or in a database table keyed by UUID.
+///
+/// Changes are only written by [`PlayerSet::save`].
+pub struct PlayerSet {
+    path: PathBuf,
+    table: &'static str,
+    players: AHashSet<Uuid>,
+    /// Players added or removed since the last save.
+    changed: AHashSet<Uuid>,
+}
+
+impl PlayerSet {
+    /// Creates an empty set which saves to the file
+    /// at `path`, or to `table` in a database.
+    pub fn new(path: impl Into<PathBuf>, table: &'static str) -> Self {
+        Self {
+            path: path.into(),
+            table,
+            players: AHashSet::new(),
+            changed: AHashSet::new(),
+        }
+    }
+
+    /// Loads the set from `storage` if a database is
+    /// configured, and from its file otherwise.
+    ///
+    /// A file which can't be loaded is moved aside
+    /// with [`super::load_or_move_aside`].
+    pub fn load(path: impl Into<PathBuf>, table: &'static str, storage: Option<&Storage>) -> Self {
+        let mut set = Self::new(path, table);
+        let players = match storage {
+            Some(storage) => match load_table(storage, table) {
+                Ok(players) => players,
+                Err(e) => {
+                    log::warn!("Failed to load the {} table: {:?}", table, e);
+                    Vec::new()
+                }
+            },
+            None => {
+                super::load_or_move_aside(&set.path, || read_file(&set.path)).unwrap_or_default()
+            }
+        };
+        set.players = players.into_iter().collect();
+        set
+    }
+
+    pub fn contains(&self, uuid: Uuid) -> bool {
+        self.players.contains(&uuid)
+    }
+
+    /// Adds `uuid`, returning whether it was absent.
+    pub fn insert(&mut self, uuid: Uuid) -> bool {
+        let inserted = self.players.insert(uuid);
+        if inserted {
+            self.changed.insert(uuid);
+        }
+        inserted
+    }
+
+    /// Removes `uuid`, returning whether it was present.
+    pub fn remove(&mut self, uuid: Uuid) -> bool {
+        let removed = self.players.remove(&uuid);
+        if removed {
+            self.changed.insert(uuid);
+        }
+        removed
+    }
+
+    /// Writes the changes since the last save to `storage`,
+    /// or rewrites the file if no database is configured.
+    pub fn save(&mut self, storage: Option<&Storage>) -> anyhow::Result<()> {
+        if self.changed.is_empty() {
+            return Ok(());
+        }
+        match storage {
+            Some(storage) => {
+                for uuid in self.changed.drain() {
+                    if self.players.contains(&uuid) {
+                        storage.put(self.table, &uuid.to_string(), &true)?;
+                    } else {
+                        storage.remove(self.table, &uuid.to_string());
+                    }
+                }
+            }
+            None => {
+                let players: Vec<&Uuid> = self.players.iter().collect();
+                super::write_atomically(&self.path, &serde_json::to_vec(&players)?)?;
+                self.changed.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Imports the players listed in the file at `path` into `table`.
+    /// Used by [`super::MIGRATIONS`].
+    pub(crate) fn import_file(
+        backend: &mut dyn Backend,
+        path: &Path,
+        table: &str,
+    ) -> anyhow::Result<()> {
+        let batch = read_file(path)?
+            .into_iter()
+            .map(|uuid| {
+                Ok(Write::Put {
+                    table: table.to_owned(),
+                    key: uuid.to_string(),
+                    value: serde_json::to_vec(&true)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        backend.apply(&batch)
+    }
+}
+
+/// Reads the UUIDs listed in the file at `path`.
+/// A missing file lists no players.
+fn read_file(path: &Path) -> anyhow::Result<Vec<Uuid>> {
+    match fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn load_table(storage: &Storage, table: &str) -> anyhow::Result<Vec<Uuid>> {
+    storage
+        .load::<bool>(table)?
+        .into_iter()
+        .map(|(key, _)| Ok(key.parse()?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_to_file() {
+        let path =
+            std::env::temp_dir().join(format!("feather-player-set-{}.json", std::process::id()));
+        let (a, b) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+        let mut set = PlayerSet::new(&path, "players");
+        assert!(set.insert(a));
+        assert!(set.insert(b));
+        assert!(!set.insert(a));
+        assert!(set.remove(b));
+        set.save(None).unwrap();
+
+        let loaded = PlayerSet::load(&path, "players", None);
+        assert!(loaded.contains(a));
+        assert!(!loaded.contains(b));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
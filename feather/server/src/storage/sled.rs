@@ -0,0 +1,79 @@
+use std::{convert::TryInto, path::Path};
+
+use sled::{Batch, Db, Tree};
+
+use super::{Backend, Write};
+
+/// Key of the schema version in the default tree.
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// Stores each table in its own sled tree.
+pub struct SledBackend {
+    db: Db,
+}
+
+impl SledBackend {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            db: sled::open(path)?,
+        })
+    }
+
+    fn tree(&self, table: &str) -> anyhow::Result<Tree> {
+        Ok(self.db.open_tree(table)?)
+    }
+}
+
+impl Backend for SledBackend {
+    fn load(&self, table: &str) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+        self.tree(table)?
+            .iter()
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((String::from_utf8(key.to_vec())?, value.to_vec()))
+            })
+            .collect()
+    }
+
+    /// Batches are atomic per table, not across tables.
+    fn apply(&mut self, batch: &[Write]) -> anyhow::Result<()> {
+        let mut batches: Vec<(&str, Batch)> = Vec::new();
+        for write in batch {
+            let table = match write {
+                Write::Put { table, .. } | Write::Remove { table, .. } => table.as_str(),
+            };
+            let index = match batches.iter().position(|(name, _)| *name == table) {
+                Some(index) => index,
+                None => {
+                    batches.push((table, Batch::default()));
+                    batches.len() - 1
+                }
+            };
+            let tree_batch = &mut batches[index].1;
+            match write {
+                Write::Put { key, value, .. } => tree_batch.insert(key.as_bytes(), &value[..]),
+                Write::Remove { key, .. } => tree_batch.remove(key.as_bytes()),
+            }
+        }
+
+        for (table, tree_batch) in batches {
+            self.tree(table)?.apply_batch(tree_batch)?;
+        }
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn schema_version(&self) -> anyhow::Result<u32> {
+        match self.db.get(SCHEMA_VERSION_KEY)? {
+            Some(bytes) => Ok(u32::from_be_bytes(bytes.as_ref().try_into()?)),
+            None => Ok(0),
+        }
+    }
+
+    fn set_schema_version(&mut self, version: u32) -> anyhow::Result<()> {
+        self.db
+            .insert(SCHEMA_VERSION_KEY, &version.to_be_bytes()[..])?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use super::{Backend, Write};
+
+/// Stores all tables in a single SQLite table keyed by
+/// table name and key. The schema version is kept in
+/// SQLite's `user_version`.
+pub struct SqliteBackend {
+    connection: Connection,
+}
+
+impl SqliteBackend {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+            CREATE TABLE IF NOT EXISTS entries (
+                tbl TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value BLOB NOT NULL,
+                PRIMARY KEY (tbl, key)
+            );",
+        )?;
+        Ok(Self { connection })
+    }
+}
+
+impl Backend for SqliteBackend {
+    fn load(&self, table: &str) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+        let mut statement = self
+            .connection
+            .prepare_cached("SELECT key, value FROM entries WHERE tbl = ?1")?;
+        let rows = statement.query_map(params![table], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn apply(&mut self, batch: &[Write]) -> anyhow::Result<()> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut put = transaction.prepare_cached(
+                "INSERT OR REPLACE INTO entries (tbl, key, value) VALUES (?1, ?2, ?3)",
+            )?;
+            let mut remove =
+                transaction.prepare_cached("DELETE FROM entries WHERE tbl = ?1 AND key = ?2")?;
+            for write in batch {
+                match write {
+                    Write::Put { table, key, value } => put.execute(params![table, key, value])?,
+                    Write::Remove { table, key } => remove.execute(params![table, key])?,
+                };
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn schema_version(&self) -> anyhow::Result<u32> {
+        Ok(self
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    fn set_schema_version(&mut self, version: u32) -> anyhow::Result<()> {
+        // PRAGMA statements don't support parameters.
+        self.connection
+            .execute_batch(&format!("PRAGMA user_version = {}", version))?;
+        Ok(())
+    }
+}
@@ -1,43 +1,151 @@
 //! Systems linking a `Server` and a `Game`.
 
+mod afk;
+mod beacon;
 mod block;
 mod chat;
+pub(crate) mod combat_log;
+mod conduit;
+mod effects;
+mod enchanting;
 mod entity;
+mod fake_blocks;
+mod fake_entities;
+mod invariants;
+mod join_message;
+mod kick;
+mod maps;
 mod particle;
+pub mod player_data;
 mod player_join;
 mod player_leave;
 mod plugin_message;
+mod pre_login;
+pub mod recipe_book;
+mod resource_pack;
+mod stations;
 mod tablist;
+mod transfer;
+pub(crate) mod vanish;
 pub mod view;
+mod world_settings;
 
 use std::time::{Duration, Instant};
 
-use common::Game;
+use base::Position;
+use common::{
+    afk::AfkSettings, chunk_loading::ChunkLoadSettings, combat_log::CombatLogSettings,
+    permissions::Permissions, resource_pack::ResourcePackPolicy, shutdown::Shutdown,
+    vanish::Vanished, Game,
+};
 use ecs::{SysResult, SystemExecutor};
 use quill_common::components::Name;
+use uuid::Uuid;
 
 use crate::{client::ClientId, Server};
 
 /// Registers systems for a `Server` with a `Game`.
-pub fn register(server: Server, game: &mut Game, systems: &mut SystemExecutor<Game>) {
+pub fn register(mut server: Server, game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    if let Some(rcon_commands) = server.rcon_commands.take() {
+        crate::io::rcon::register(game, systems, rcon_commands);
+    }
+    if let Some(query_status) = server.query_status.take() {
+        crate::io::query::register(game, systems, query_status);
+    }
+    if let Some(pre_logins) = server.plugin_pre_logins.take() {
+        pre_login::register(game, systems, pre_logins);
+    }
+    game.resources
+        .get_mut::<Shutdown>()
+        .expect("common must be registered before the server")
+        .set_warnings(server.options.restart_warnings.clone());
+    game.resources
+        .get_mut::<AfkSettings>()
+        .expect("common must be registered before the server")
+        .set_timeout(server.options.afk_timeout);
+    {
+        let mut chunk_loading = game
+            .resources
+            .get_mut::<ChunkLoadSettings>()
+            .expect("common must be registered before the server");
+        chunk_loading.set_unload_delay(server.options.chunk_unload_delay);
+        chunk_loading.set_max_loaded_chunks(server.options.max_loaded_chunks);
+    }
+    {
+        let mut combat_log = game
+            .resources
+            .get_mut::<CombatLogSettings>()
+            .expect("common must be registered before the server");
+        combat_log.set_combat_duration(server.options.combat_log_duration);
+        combat_log.set_action(server.options.combat_log_action);
+        combat_log.set_npc_lifetime(server.options.combat_log_npc_lifetime);
+    }
+    if let Some(kick_message) = server
+        .options
+        .resource_pack
+        .as_ref()
+        .and_then(|pack| pack.kick_message.clone())
+    {
+        game.resources
+            .get_mut::<ResourcePackPolicy>()
+            .expect("common must be registered before the server")
+            .require(kick_message);
+    }
+    crate::economy::register(game, &server.options);
+    maps::register(game, systems, &server.options.world_dir);
+    let check_invariants = server.options.check_invariants;
+    let report_tick_times = server.options.report_tick_times;
+    let behind_proxy = server.options.proxy_mode.is_some();
     game.insert_resource(server);
 
+    crate::permissions::register(game);
+    join_message::register(systems);
     player_join::register(systems);
     systems
         .group::<Server>()
         .add_system(handle_packets)
-        .add_system(send_keepalives);
+        .add_system(update_status_cache);
     view::register(game, systems);
     crate::chunk_subscriptions::register(systems);
     player_leave::register(systems);
+    crate::user_cache::register(game, systems);
+    crate::regions::register(game, systems);
     tablist::register(systems);
+    vanish::register(game, systems);
+    afk::register(systems);
+    combat_log::register(game, systems);
     block::register(systems);
     entity::register(game, systems);
+    fake_blocks::register(systems);
+    fake_entities::register(systems);
     chat::register(game, systems);
+    effects::register(systems);
+    beacon::register(systems);
+    conduit::register(systems);
+    enchanting::register(systems);
+    stations::register(systems);
     particle::register(systems);
-    plugin_message::register(systems);
+    plugin_message::register(game, systems);
+    resource_pack::register(systems);
+    recipe_book::register(game, systems);
+    transfer::register(game, systems, behind_proxy)
+        .expect("common must be registered before the server");
+    kick::register(systems);
+    world_settings::register(systems);
+    if report_tick_times {
+        crate::tick_times::register(game, systems);
+    }
 
     systems.group::<Server>().add_system(tick_clients);
+
+    // Runs last so that it sees the state at the end of the tick.
+    if check_invariants {
+        if cfg!(debug_assertions) {
+            invariants::register(systems);
+        } else {
+            log::warn!("check_invariants is ignored in release builds");
+        }
+    }
 }
 
 /// Polls for packets received from clients
@@ -54,6 +162,17 @@ fn handle_packets(game: &mut Game, server: &mut Server) -> SysResult {
     }
 
     for (player, packet) in packets {
+        let span = tracing::trace_span!(
+            "handle_packet",
+            player = tracing::field::Empty,
+            id = packet.id()
+        );
+        if !span.is_disabled() {
+            if let Ok(name) = game.ecs.get::<Name>(player) {
+                span.record("player", &tracing::field::display(&**name));
+            }
+        }
+        let _entered = span.enter();
         if let Err(e) = crate::packet_handlers::handle_packet(game, server, player, packet) {
             log::warn!(
                 "Failed to handle packet from '{}': {:?}",
@@ -66,17 +185,32 @@ fn handle_packets(game: &mut Game, server: &mut Server) -> SysResult {
     Ok(())
 }
 
-/// Sends out keepalive packets at an interval.
-fn send_keepalives(_game: &mut Game, server: &mut Server) -> SysResult {
-    let interval = Duration::from_secs(5);
-    if server.last_keepalive_time + interval < Instant::now() {
-        server.broadcast_keepalive();
+/// Refreshes the cached status response at most once per second.
+fn update_status_cache(game: &mut Game, server: &mut Server) -> SysResult {
+    let interval = Duration::from_secs(1);
+    if server.last_status_update_time + interval < Instant::now() {
+        // Players joining silently or vanished are left out of the sample.
+        let players: Vec<(String, Uuid)> = game
+            .ecs
+            .query::<(&Name, &Uuid, &Permissions, Option<&Vanished>)>()
+            .iter()
+            .filter(|(_, (_, _, permissions, vanished))| {
+                vanished.is_none() && !permissions.has(join_message::SILENT_JOIN_PERMISSION)
+            })
+            .map(|(_, (name, &uuid, _, _))| (name.to_string(), uuid))
+            .collect();
+        server.update_status_cache(&players);
     }
     Ok(())
 }
 
 /// Ticks `Client`s.
-fn tick_clients(_game: &mut Game, server: &mut Server) -> SysResult {
+fn tick_clients(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, &position)) in game.ecs.query::<(&ClientId, &Position)>().iter() {
+        if let Some(client) = server.clients.get(client_id) {
+            client.prioritize_chunks(position);
+        }
+    }
     for client in server.clients.iter() {
         client.tick();
     }
@@ -0,0 +1,95 @@
+//! Shows AFK players to other players: AFK players are
+//! greyed out in the tablist, and changes are announced in chat.
+
+use base::Text;
+use common::{
+    chat::ChatKind,
+    events::{AfkChangeEvent, PlayerJoinEvent},
+    vanish::Vanished,
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::{Afk, Name};
+use uuid::Uuid;
+
+use crate::{config::parse_text, ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(announce_afk_changes)
+        .add_system(send_afk_players_to_new_clients);
+}
+
+/// Name shown in the tablist for an AFK player.
+fn afk_display_name(name: &str) -> Text {
+    Text::from_legacy(&format!("\u{a7}7{}", name))
+}
+
+fn announce_afk_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    let mut messages = Vec::new();
+    for (_, (event, name, &uuid, vanished)) in game
+        .ecs
+        .query::<(&AfkChangeEvent, &Name, &Uuid, Option<&Vanished>)>()
+        .iter()
+    {
+        let display_name = if event.afk {
+            Some(afk_display_name(name))
+        } else {
+            None
+        };
+        server.broadcast_with(|client| {
+            if vanished.is_none() || client.sees_vanished() || client.uuid() == uuid {
+                client.update_tablist_display_name(uuid, display_name.as_ref());
+            }
+        });
+
+        let template = if event.afk {
+            &server.options.afk_message
+        } else {
+            &server.options.afk_return_message
+        };
+        if let (Some(template), None) = (template, vanished) {
+            let message = template.replace("{player}", name);
+            messages.push(parse_text(&message).unwrap_or_else(|_| Text::from(message)));
+        }
+    }
+
+    for message in messages {
+        game.broadcast_chat(ChatKind::System, message);
+    }
+    Ok(())
+}
+
+/// Greys out AFK players in the tablists of
+/// clients that just joined.
+fn send_afk_players_to_new_clients(game: &mut Game, server: &mut Server) -> SysResult {
+    let new_clients: Vec<ClientId> = game
+        .ecs
+        .query::<(&PlayerJoinEvent, &ClientId)>()
+        .iter()
+        .map(|(_, (_, &client_id))| client_id)
+        .collect();
+    if new_clients.is_empty() {
+        return Ok(());
+    }
+
+    for (_, (afk, name, &uuid, vanished)) in game
+        .ecs
+        .query::<(&Afk, &Name, &Uuid, Option<&Vanished>)>()
+        .iter()
+    {
+        if !afk.0 {
+            continue;
+        }
+        let display_name = afk_display_name(name);
+        for &client_id in &new_clients {
+            if let Some(client) = server.clients.get(client_id) {
+                if vanished.is_none() || client.sees_vanished() {
+                    client.update_tablist_display_name(uuid, Some(&display_name));
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Opens beacon windows for players interacting with beacons.
+
+use base::{BlockPosition, Text, TextValue};
+use common::{
+    beacon::{self, Beacons},
+    Game, Window,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::events::BlockInteractEvent;
+
+use super::stations::STATION_WINDOW_ID;
+use crate::{Client, ClientId, Server};
+
+/// Window type of beacons in the Open Window packet.
+const BEACON_WINDOW_KIND: i32 = 8;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(open_beacons);
+}
+
+fn open_beacons(game: &mut Game, server: &mut Server) -> SysResult {
+    let mut opened = Vec::new();
+    for (player, (event, &client_id)) in game.ecs.query::<(&BlockInteractEvent, &ClientId)>().iter()
+    {
+        if beacon::is_beacon(game, event.location) {
+            opened.push((player, client_id, event.location));
+        }
+    }
+
+    for (player, client_id, position) in opened {
+        beacon::open_beacon(game, player, position, STATION_WINDOW_ID)?;
+        if let Some(client) = server.clients.get(client_id) {
+            send_beacon_window(game, client, player, position)?;
+        }
+    }
+    Ok(())
+}
+
+fn send_beacon_window(
+    game: &Game,
+    client: &Client,
+    player: Entity,
+    position: BlockPosition,
+) -> SysResult {
+    let effects = game.resources.get::<Beacons>()?.get(position);
+    client.open_window(
+        STATION_WINDOW_ID,
+        BEACON_WINDOW_KIND,
+        Text::from(TextValue::translate("container.beacon")),
+    );
+    client.send_window_property(
+        STATION_WINDOW_ID,
+        0,
+        beacon::pyramid_level(game, position) as i16,
+    );
+    client.send_window_property(
+        STATION_WINDOW_ID,
+        1,
+        effects.primary.map_or(-1, |effect| effect.id() as i16),
+    );
+    client.send_window_property(
+        STATION_WINDOW_ID,
+        2,
+        effects.secondary.map_or(-1, |effect| effect.id() as i16),
+    );
+    client.send_window_items(STATION_WINDOW_ID, &*game.ecs.get::<Window>(player)?);
+    Ok(())
+}
@@ -15,21 +15,67 @@
 
 use ahash::AHashMap;
 use base::{chunk::SECTION_VOLUME, position, ChunkPosition, CHUNK_WIDTH};
-use common::{events::BlockChangeEvent, Game};
+use common::{
+    events::{BlockChangeEvent, ChunkLoadEvent},
+    Game,
+};
 use ecs::{SysResult, SystemExecutor};
 
-use crate::Server;
+use crate::{anti_xray, Server};
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems
         .group::<Server>()
+        // Invalidate first, so a chunk packet sent after
+        // the block changes never contains the old blocks.
+        .add_system(invalidate_chunk_packets)
         .add_system(broadcast_block_changes);
 }
 
 fn broadcast_block_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    let anti_xray_enabled = game.world.settings().anti_xray.is_some();
+    let mut changed = Vec::new();
+    let mut resent_sections = Vec::new();
     for (_, event) in game.ecs.query::<&BlockChangeEvent>().iter() {
         broadcast_block_change(event, game, server);
+        if anti_xray_enabled {
+            changed.extend(event.iter_changed_blocks());
+            if event.count() >= CHUNK_OVERWRITE_THRESHOLD {
+                resent_sections.extend(
+                    event
+                        .iter_affected_chunk_sections()
+                        .map(|(chunk, section, _)| (chunk, section)),
+                );
+            }
+        }
+    }
+    if anti_xray_enabled {
+        anti_xray::reveal_exposed(game, server, changed, &resent_sections);
+    }
+    Ok(())
+}
+
+/// Drops the cached Chunk Data packets
+/// of changed and unloaded chunks.
+///
+/// With anti-xray, the packets of the chunks next to
+/// changed and newly loaded chunks are dropped too.
+fn invalidate_chunk_packets(game: &mut Game, server: &mut Server) -> SysResult {
+    let anti_xray_enabled = game.world.settings().anti_xray.is_some();
+    for (_, event) in game.ecs.query::<&BlockChangeEvent>().iter() {
+        for (chunk, _, _) in event.iter_affected_chunk_sections() {
+            server.chunk_packet_cache.invalidate(chunk);
+            if anti_xray_enabled {
+                anti_xray::invalidate_neighbors(&server.chunk_packet_cache, chunk);
+            }
+        }
+    }
+    if anti_xray_enabled {
+        for (_, event) in game.ecs.query::<&ChunkLoadEvent>().iter() {
+            anti_xray::invalidate_neighbors(&server.chunk_packet_cache, event.position);
+        }
     }
+    server.chunk_packet_cache.prune();
     Ok(())
 }
 
@@ -58,13 +104,14 @@ fn broadcast_block_change_chunk_overwrite(
     for (chunk_pos, sections) in sections {
         let chunk = game.world.chunk_map().chunk_handle_at(chunk_pos);
         if let Some(chunk) = chunk {
+            let obfuscation = anti_xray::chunk_obfuscation(game, chunk_pos);
             let position = position!(
                 (chunk_pos.x * CHUNK_WIDTH as i32) as f64,
                 0.0,
                 (chunk_pos.z * CHUNK_WIDTH as i32) as f64,
             );
             server.broadcast_nearby_with(position, |client| {
-                client.overwrite_chunk_sections(&chunk, sections.clone());
+                client.overwrite_chunk_sections(&chunk, sections.clone(), obfuscation.clone());
             })
         }
     }
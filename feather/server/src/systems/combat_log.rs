@@ -0,0 +1,71 @@
+//! Remembers which combat loggers lost their items,
+//! so they can be told when they next join.
+//!
+//! The players are stored in `combat_loggers.json`, or the
+//! database if one is configured, so that the notice survives restarts.
+
+use common::{events::CombatLogPunishEvent, Game};
+use ecs::{SysResult, SystemExecutor};
+use uuid::Uuid;
+
+use crate::storage::{Backend, PlayerSet, Storage};
+
+/// Path of the punished players file, relative to the working directory.
+pub const PUNISHED_PLAYERS_PATH: &str = "combat_loggers.json";
+
+/// Table storing the punished players in a database.
+const TABLE: &str = "combat_loggers";
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    let punished = PlayerSet::load(
+        PUNISHED_PLAYERS_PATH,
+        TABLE,
+        game.resources.get::<Storage>().ok().as_deref(),
+    );
+    game.insert_resource(PunishedPlayers(punished));
+    systems
+        .add_system(record_punishments)
+        .add_system(save_punished_players);
+}
+
+/// Resource storing the UUIDs of combat loggers who lost
+/// their items and haven't been told yet.
+pub struct PunishedPlayers(PlayerSet);
+
+impl PunishedPlayers {
+    pub fn insert(&mut self, uuid: Uuid) {
+        self.0.insert(uuid);
+    }
+
+    /// Removes `uuid`, returning whether it was punished.
+    pub fn take(&mut self, uuid: Uuid) -> bool {
+        self.0.remove(uuid)
+    }
+}
+
+fn record_punishments(game: &mut Game) -> SysResult {
+    for (_, event) in game.ecs.query::<&CombatLogPunishEvent>().iter() {
+        game.resources
+            .get_mut::<PunishedPlayers>()?
+            .insert(event.player);
+    }
+    Ok(())
+}
+
+fn save_punished_players(game: &mut Game) -> SysResult {
+    let storage = game.resources.get::<Storage>().ok();
+    if let Err(e) = game
+        .resources
+        .get_mut::<PunishedPlayers>()?
+        .0
+        .save(storage.as_deref())
+    {
+        log::error!("Failed to save the combat loggers: {:?}", e);
+    }
+    Ok(())
+}
+
+/// Migration importing `combat_loggers.json` into a database.
+pub(crate) fn import_file(backend: &mut dyn Backend) -> anyhow::Result<()> {
+    PlayerSet::import_file(backend, PUNISHED_PLAYERS_PATH.as_ref(), TABLE)
+}
@@ -0,0 +1,45 @@
+//! Sends conduit state to clients, which they
+//! use to animate the conduit and its target.
+
+use common::{conduit::ConduitUpdateEvent, Game};
+use ecs::{SysResult, SystemExecutor};
+use uuid::Uuid;
+
+use crate::Server;
+
+/// Block entity data action for conduits.
+const CONDUIT_ACTION: u8 = 5;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(send_conduit_updates);
+}
+
+fn send_conduit_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, event) in game.ecs.query::<&ConduitUpdateEvent>().iter() {
+        let data = conduit_nbt(event)?;
+        server.broadcast_nearby_with(event.position.position(), |client| {
+            client.send_block_entity_data(event.position, CONDUIT_ACTION, data.clone())
+        });
+    }
+    Ok(())
+}
+
+fn conduit_nbt(event: &ConduitUpdateEvent) -> anyhow::Result<nbt::Blob> {
+    let mut blob = nbt::Blob::new();
+    blob.insert("id", "minecraft:conduit")?;
+    blob.insert("x", event.position.x)?;
+    blob.insert("y", event.position.y)?;
+    blob.insert("z", event.position.z)?;
+    if let Some(target) = event.target {
+        blob.insert("Target", nbt::Value::IntArray(uuid_to_int_array(target)))?;
+    }
+    Ok(blob)
+}
+
+/// Encodes a UUID as four big-endian integers, like Minecraft stores UUIDs in NBT.
+fn uuid_to_int_array(uuid: Uuid) -> Vec<i32> {
+    let value = uuid.as_u128();
+    (0..4)
+        .map(|i| (value >> (96 - i * 32)) as u32 as i32)
+        .collect()
+}
@@ -0,0 +1,53 @@
+//! Sends status effect icons to players.
+
+use common::{
+    effects::{EffectsAddedEvent, EffectsExpiredEvent, EffectsRemovedEvent},
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, NetworkId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(send_effects);
+}
+
+fn send_effects(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (event, &client_id, &network_id)) in game
+        .ecs
+        .query::<(&EffectsAddedEvent, &ClientId, &NetworkId)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            for effect in &event.effects {
+                client.send_entity_effect(network_id, effect);
+            }
+        }
+    }
+
+    for (_, (event, &client_id, &network_id)) in game
+        .ecs
+        .query::<(&EffectsRemovedEvent, &ClientId, &NetworkId)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            for &effect in &event.effects {
+                client.remove_entity_effect(network_id, effect);
+            }
+        }
+    }
+
+    for (_, (event, &client_id, &network_id)) in game
+        .ecs
+        .query::<(&EffectsExpiredEvent, &ClientId, &NetworkId)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            for &effect in &event.effects {
+                client.remove_entity_effect(network_id, effect);
+            }
+        }
+    }
+
+    Ok(())
+}
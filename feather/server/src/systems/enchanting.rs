@@ -0,0 +1,48 @@
+//! Opens enchanting table windows for players
+//! interacting with enchanting tables.
+
+use base::{Text, TextValue};
+use common::{
+    enchanting::{self, EnchantmentSeed, OpenEnchantingTable},
+    Game, Window,
+};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::events::BlockInteractEvent;
+
+use super::stations::STATION_WINDOW_ID;
+use crate::{ClientId, Server};
+
+/// Window type of enchanting tables in the Open Window packet.
+const ENCHANTING_WINDOW_KIND: i32 = 12;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(open_enchanting_tables);
+}
+
+fn open_enchanting_tables(game: &mut Game, server: &mut Server) -> SysResult {
+    let mut opened = Vec::new();
+    for (player, (event, &client_id)) in game.ecs.query::<(&BlockInteractEvent, &ClientId)>().iter()
+    {
+        if enchanting::is_enchanting_table(game, event.location) {
+            opened.push((player, client_id, event.location));
+        }
+    }
+
+    for (player, client_id, position) in opened {
+        enchanting::open_enchanting_table(game, player, position)?;
+        if let Some(client) = server.clients.get(client_id) {
+            client.open_window(
+                STATION_WINDOW_ID,
+                ENCHANTING_WINDOW_KIND,
+                Text::from(TextValue::translate("container.enchant")),
+            );
+            client.send_window_items(STATION_WINDOW_ID, &*game.ecs.get::<Window>(player)?);
+            client.send_enchantment_offers(
+                STATION_WINDOW_ID,
+                &game.ecs.get::<OpenEnchantingTable>(player)?.offers,
+                *game.ecs.get::<EnchantmentSeed>(player)?,
+            );
+        }
+    }
+    Ok(())
+}
@@ -2,32 +2,33 @@
 //! Spawn packets, position updates, equipment, animations, etc.
 
 use base::Position;
-use common::Game;
+use common::{health::EntityDamageEvent, Game};
 use ecs::{SysResult, SystemExecutor};
-use quill_common::components::OnGround;
 
-use crate::{entities::PreviousPosition, NetworkId, Server};
+use crate::{NetworkId, Server};
 
 mod spawn_packet;
+mod sync;
+
+/// Entity status which plays the hurt animation.
+const STATUS_HURT: i8 = 2;
 
 pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    sync::register_derived(systems);
     spawn_packet::register(game, systems);
-    systems.group::<Server>().add_system(send_entity_movement);
+    sync::register(systems);
+    systems.group::<Server>().add_system(send_hurt_animations);
 }
 
-/// Sends entity movement packets.
-fn send_entity_movement(game: &mut Game, server: &mut Server) -> SysResult {
-    for (_, (&position, prev_position, &on_ground, &network_id)) in game
+fn send_hurt_animations(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (_, &network_id, &position)) in game
         .ecs
-        .query::<(&Position, &mut PreviousPosition, &OnGround, &NetworkId)>()
+        .query::<(&EntityDamageEvent, &NetworkId, &Position)>()
         .iter()
     {
-        if position != prev_position.0 {
-            server.broadcast_nearby_with(position, |client| {
-                client.update_entity_position(network_id, position, on_ground);
-            });
-            prev_position.0 = position;
-        }
+        server.broadcast_entity_update_with(network_id, position, |client| {
+            client.send_entity_status(network_id, STATUS_HURT)
+        });
     }
     Ok(())
 }
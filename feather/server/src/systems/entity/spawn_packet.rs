@@ -3,11 +3,12 @@ use anyhow::Context;
 use base::Position;
 use common::{
     events::{ChunkCrossEvent, EntityCreateEvent, EntityRemoveEvent, ViewUpdateEvent},
+    riding::{Passengers, Vehicle},
     Game,
 };
-use ecs::{SysResult, SystemExecutor};
+use ecs::{Entity, SysResult, SystemExecutor};
 
-use crate::{entities::SpawnPacketSender, ClientId, NetworkId, Server};
+use crate::{entities::SpawnPacketSender, Client, ClientId, NetworkId, Server};
 
 pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
     systems
@@ -36,6 +37,7 @@ pub fn update_visible_entities(game: &mut Game, server: &mut Server) -> SysResul
                         spawn_packet
                             .send(&entity_ref, client)
                             .context("failed to send spawn packet")?;
+                        send_passengers(game, entity_id, client);
                     }
                 }
             }
@@ -67,7 +69,8 @@ fn send_entities_when_created(game: &mut Game, server: &mut Server) -> SysResult
         server.broadcast_nearby_with(position, |client| {
             spawn_packet
                 .send(&entity_ref, client)
-                .expect("failed to create spawn packet")
+                .expect("failed to create spawn packet");
+            send_passengers(game, entity, client);
         });
     }
 
@@ -117,9 +120,42 @@ fn update_entities_on_chunk_cross(game: &mut Game, server: &mut Server) -> SysRe
         for send_client in new_clients.difference(&old_clients) {
             if let Some(client) = server.clients.get(*send_client) {
                 spawn_packet.send(&entity_ref, client)?;
+                send_passengers(game, entity, client);
             }
         }
     }
 
     Ok(())
 }
+
+/// Sends the riders of a newly sent entity and, if it rides
+/// another entity, the riders of its vehicle.
+///
+/// Clients ignore riders they don't know yet, so whichever of
+/// a vehicle and its riders is sent last completes the mount.
+fn send_passengers(game: &Game, entity: Entity, client: &Client) {
+    let vehicles = game
+        .ecs
+        .get::<Vehicle>(entity)
+        .ok()
+        .map(|vehicle| vehicle.0)
+        .into_iter()
+        .chain(Some(entity));
+    for vehicle in vehicles {
+        let passengers = match game.ecs.get::<Passengers>(vehicle) {
+            Ok(passengers) => passengers,
+            Err(_) => continue,
+        };
+        let network_id = match game.ecs.get::<NetworkId>(vehicle) {
+            Ok(network_id) => *network_id,
+            Err(_) => continue,
+        };
+        let passengers: Vec<NetworkId> = passengers
+            .0
+            .iter()
+            .filter_map(|&passenger| game.ecs.get::<NetworkId>(passenger).ok())
+            .map(|network_id| *network_id)
+            .collect();
+        client.send_passengers(network_id, &passengers);
+    }
+}
@@ -0,0 +1,132 @@
+//! Sends entity components to clients when they change.
+//!
+//! Gameplay systems only need to update a component;
+//! implementing [`Synced`] for it and calling [`register_synced`]
+//! makes the server broadcast the new value to clients
+//! that have the entity loaded.
+//!
+//! Values derived from other components, like [`Equipment`],
+//! are kept up to date here as well. [`EntityMetadata`] tracks
+//! its own changes, so only those are sent.
+
+use ahash::AHashMap;
+use base::{EntityMetadata, Inventory, Position};
+use common::{entities::player::HotbarSlot, Game};
+use ecs::{Component, Entity, EntityRef, SysResult, SystemExecutor};
+use quill_common::components::{HeadYaw, OnGround};
+
+use crate::{entities::Equipment, Client, NetworkId, Server};
+
+/// Registers the systems deriving components like [`Equipment`].
+/// They must run before spawn packets are sent.
+pub fn register_derived(systems: &mut SystemExecutor<Game>) {
+    systems.add_system(update_equipment);
+}
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    register_synced::<Position>(systems);
+    register_synced::<HeadYaw>(systems);
+    register_synced::<Equipment>(systems);
+    systems.group::<Server>().add_system(send_entity_metadata);
+}
+
+/// A component whose changes are sent to clients.
+pub trait Synced: Component + Clone + PartialEq {
+    /// Sends the current value of the component to a client.
+    fn send(&self, entity: &EntityRef, network_id: NetworkId, client: &Client) -> SysResult;
+}
+
+/// Adds a system which sends `T` to nearby clients
+/// whenever it changes.
+///
+/// The initial value isn't sent, as it is sent
+/// along with the entity's spawn packet.
+pub fn register_synced<T: Synced>(systems: &mut SystemExecutor<Game>) {
+    let mut last_synced: AHashMap<Entity, T> = AHashMap::new();
+    systems
+        .group::<Server>()
+        .add_system(move |game: &mut Game, server: &mut Server| {
+            last_synced.retain(|&entity, _| game.ecs.entity(entity).is_ok());
+
+            for (entity, (value, &position, &network_id)) in
+                game.ecs.query::<(&T, &Position, &NetworkId)>().iter()
+            {
+                match last_synced.get(&entity) {
+                    Some(last) if last == value => continue,
+                    Some(_) => {
+                        let entity_ref = game.ecs.entity(entity)?;
+                        let mut result = Ok(());
+                        server.broadcast_entity_update_with(network_id, position, |client| {
+                            if result.is_ok() {
+                                result = value.send(&entity_ref, network_id, client);
+                            }
+                        });
+                        result?;
+                    }
+                    None => {}
+                }
+                last_synced.insert(entity, value.clone());
+            }
+            Ok(())
+        });
+}
+
+impl Synced for Position {
+    fn send(&self, entity: &EntityRef, network_id: NetworkId, client: &Client) -> SysResult {
+        let on_ground = *entity.get::<OnGround>()?;
+        let head_follows_body = entity.get::<HeadYaw>().is_err();
+        client.update_entity_position(network_id, *self, on_ground, head_follows_body);
+        Ok(())
+    }
+}
+
+impl Synced for HeadYaw {
+    fn send(&self, _entity: &EntityRef, network_id: NetworkId, client: &Client) -> SysResult {
+        client.update_entity_head_yaw(network_id, *self);
+        Ok(())
+    }
+}
+
+impl Synced for Equipment {
+    fn send(&self, _entity: &EntityRef, network_id: NetworkId, client: &Client) -> SysResult {
+        client.send_entity_equipment(network_id, self);
+        Ok(())
+    }
+}
+
+/// Derives the [`Equipment`] of entities from their inventories.
+fn update_equipment(game: &mut Game) -> SysResult {
+    let mut changed = Vec::new();
+    for (entity, (inventory, hotbar_slot, equipment)) in game
+        .ecs
+        .query::<(&Inventory, Option<&HotbarSlot>, Option<&Equipment>)>()
+        .iter()
+    {
+        let new_equipment = Equipment::of(inventory, hotbar_slot.copied());
+        if equipment != Some(&new_equipment) {
+            changed.push((entity, new_equipment));
+        }
+    }
+    for (entity, equipment) in changed {
+        game.ecs.insert(entity, equipment)?;
+    }
+    Ok(())
+}
+
+/// Sends changed entity metadata to clients
+/// which already have the entity.
+fn send_entity_metadata(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&position, metadata, &network_id)) in game
+        .ecs
+        .query::<(&Position, &mut EntityMetadata, &NetworkId)>()
+        .iter()
+    {
+        if metadata.has_changes() {
+            let changes = metadata.take_changes();
+            server.broadcast_entity_update_with(network_id, position, |client| {
+                client.send_entity_metadata(network_id, changes.clone());
+            });
+        }
+    }
+    Ok(())
+}
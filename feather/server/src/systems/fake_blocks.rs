@@ -0,0 +1,29 @@
+//! Sends players' fake blocks to their clients.
+
+use common::{fake_blocks::FakeBlocks, Game};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_fake_block_changes);
+}
+
+fn send_fake_block_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, fake_blocks)) in game.ecs.query::<(&ClientId, &mut FakeBlocks)>().iter() {
+        let changes = fake_blocks.take_changes();
+        let client = match server.clients.get(client_id) {
+            Some(client) => client,
+            None => continue,
+        };
+        for (pos, block) in changes {
+            match block {
+                Some(block) => client.set_fake_block(pos, block),
+                None => client.restore_fake_block(pos, game.block(pos)),
+            }
+        }
+    }
+    Ok(())
+}
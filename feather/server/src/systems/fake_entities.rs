@@ -0,0 +1,26 @@
+//! Sends players' fake entities to their clients.
+
+use common::{fake_entities::FakeEntities, Game};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_fake_entity_updates);
+}
+
+fn send_fake_entity_updates(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, fake_entities)) in
+        game.ecs.query::<(&ClientId, &mut FakeEntities)>().iter()
+    {
+        let updates = fake_entities.take_updates();
+        if let Some(client) = server.clients.get(client_id) {
+            for update in updates {
+                client.update_fake_entity(update);
+            }
+        }
+    }
+    Ok(())
+}
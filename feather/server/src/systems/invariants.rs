@@ -0,0 +1,207 @@
+//! End-of-tick invariant checks, enabled by `check_invariants`
+//! in debug builds.
+//!
+//! Systems keep several indexes in sync with the ECS. A bug in a
+//! new system usually shows up as a client desync much later, so
+//! these checks log any inconsistency on the tick it appears.
+
+use ahash::AHashMap;
+use base::{ChunkPosition, ItemStack};
+use common::{
+    events::{EntityRemoveEvent, PlayerJoinEvent},
+    view::View,
+    Game, Window,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::components::Name;
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    log::info!("Checking invariants at the end of each tick");
+    systems.group::<Server>().add_system(check_invariants);
+}
+
+fn check_invariants(game: &mut Game, server: &mut Server) -> SysResult {
+    let mut violations = Vec::new();
+    check_chunk_entities(game, &mut violations);
+    check_views(game, server, &mut violations);
+    check_windows(game, &mut violations);
+
+    for violation in &violations {
+        log::error!(
+            "Invariant violated on tick {}: {}",
+            game.tick_count,
+            violation
+        );
+    }
+    Ok(())
+}
+
+/// Entities in the chunk index exist and are
+/// indexed under the chunk they're in.
+fn check_chunk_entities(game: &Game, violations: &mut Vec<String>) {
+    for (chunk, entities) in game.chunk_entities.iter() {
+        for &entity in entities {
+            match game.ecs.get::<ChunkPosition>(entity) {
+                Ok(actual) if *actual == chunk => {}
+                Ok(actual) => violations.push(format!(
+                    "{} is indexed in chunk {} but is in chunk {}",
+                    describe(game, entity),
+                    chunk,
+                    *actual
+                )),
+                Err(_) => violations.push(format!(
+                    "chunk {} indexes {:?}, which was despawned or has no chunk position",
+                    chunk, entity
+                )),
+            }
+        }
+    }
+}
+
+/// Players' views are centered on their chunk, and chunk
+/// subscriptions are exactly the chunks in players' views.
+fn check_views(game: &Game, server: &Server, violations: &mut Vec<String>) {
+    let mut views = AHashMap::new();
+    for (player, (&client_id, view, join_event)) in game
+        .ecs
+        .query::<(&ClientId, &View, Option<&PlayerJoinEvent>)>()
+        .iter()
+    {
+        // Subscriptions of joining players are added on the next tick.
+        if join_event.is_some() {
+            continue;
+        }
+        views.insert(client_id, (player, *view));
+
+        if let Ok(chunk) = game.ecs.get::<ChunkPosition>(player) {
+            if view.center() != *chunk {
+                violations.push(format!(
+                    "view of {} is centered on chunk {} but they are in chunk {}",
+                    describe(game, player),
+                    view.center(),
+                    *chunk
+                ));
+            }
+        }
+
+        // Subscriptions of leaving players are removed on the next tick.
+        if game.ecs.get::<EntityRemoveEvent>(player).is_ok() {
+            continue;
+        }
+        let missing = view
+            .iter()
+            .filter(|&chunk| {
+                !server
+                    .chunk_subscriptions
+                    .subscriptions_for(chunk)
+                    .contains(&client_id)
+            })
+            .count();
+        if missing != 0 {
+            violations.push(format!(
+                "{} is not subscribed to {} chunks in their view (centered on {}, distance {})",
+                describe(game, player),
+                missing,
+                view.center(),
+                view.view_distance()
+            ));
+        }
+    }
+
+    for (chunk, clients) in server.chunk_subscriptions.iter() {
+        for client_id in clients {
+            match views.get(client_id) {
+                Some((_, view)) if view.contains(chunk) => {}
+                Some((player, view)) => violations.push(format!(
+                    "{} is subscribed to chunk {} outside their view (centered on {}, distance {})",
+                    describe(game, *player),
+                    chunk,
+                    view.center(),
+                    view.view_distance()
+                )),
+                None => violations.push(format!(
+                    "chunk {} is subscribed to by {:?}, which has no player",
+                    chunk, client_id
+                )),
+            }
+        }
+    }
+}
+
+/// Item stacks in windows have between one item and a full stack.
+fn check_windows(game: &Game, violations: &mut Vec<String>) {
+    for (entity, window) in game.ecs.query::<&Window>().iter() {
+        if let Some(stack) = window.cursor_item() {
+            if let Some(problem) = check_stack(&stack) {
+                violations.push(format!(
+                    "cursor item of {}: {}",
+                    describe(game, entity),
+                    problem
+                ));
+            }
+        }
+
+        let mut index = 0;
+        while let Ok(slot) = window.item(index) {
+            if let Some(problem) = slot.as_ref().and_then(check_stack) {
+                violations.push(format!(
+                    "slot {} in the window of {}: {}",
+                    index,
+                    describe(game, entity),
+                    problem
+                ));
+            }
+            index += 1;
+        }
+    }
+}
+
+fn check_stack(stack: &ItemStack) -> Option<String> {
+    let stack_size = stack.item.stack_size();
+    if stack.count == 0 || stack.count > stack_size {
+        Some(format!(
+            "{} {:?} (stack size {})",
+            stack.count, stack.item, stack_size
+        ))
+    } else {
+        None
+    }
+}
+
+fn describe(game: &Game, entity: Entity) -> String {
+    match game.ecs.get::<Name>(entity) {
+        Ok(name) => format!("{} ({:?})", &**name, entity),
+        Err(_) => format!("{:?}", entity),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{Inventory, Item};
+    use common::window::BackingWindow;
+
+    use super::*;
+
+    #[test]
+    fn oversized_stacks_are_reported() {
+        let mut game = Game::new();
+        let inventory = Inventory::player();
+        let window = Window::new(BackingWindow::Player {
+            player: inventory.new_handle(),
+        });
+        window
+            .set_item(36, Some(ItemStack::new(Item::Stone, 64)))
+            .unwrap();
+        window
+            .set_item(37, Some(ItemStack::new(Item::EnderPearl, 17)))
+            .unwrap();
+        game.ecs.spawn((window,));
+
+        let mut violations = Vec::new();
+        check_windows(&game, &mut violations);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("slot 37"));
+    }
+}
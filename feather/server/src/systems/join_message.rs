@@ -0,0 +1,162 @@
+//! Join and quit messages.
+//!
+//! Instead of broadcasting the messages right away, we trigger
+//! a `PlayerJoinMessageEvent` or `PlayerQuitMessageEvent`
+//! so plugins get a chance to rewrite or cancel them.
+//! The systems here run before the triggering systems, so they
+//! broadcast the messages on the next tick, after every other system
+//! (including plugins) has observed the events.
+
+use base::Text;
+use common::{chat::ChatKind, Game};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::events::{PlayerJoinMessageEvent, PlayerQuitMessageEvent};
+use uuid::Uuid;
+
+use crate::{config::parse_text, Options};
+
+/// Permission suppressing a player's join and quit messages.
+pub const SILENT_JOIN_PERMISSION: &str = "server.silentjoin";
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .add_system(broadcast_join_messages)
+        .add_system(broadcast_quit_messages);
+}
+
+/// Triggers the join message event for a player.
+pub fn announce_join(
+    game: &mut Game,
+    options: &Options,
+    player: Entity,
+    details: MessageDetails,
+    old_name: Option<String>,
+) -> SysResult {
+    let message = match &options.join_message {
+        Some(template) => format_template(template, &details),
+        None => match old_name {
+            Some(old_name) => Text::translate_with(
+                "multiplayer.player.joined.renamed",
+                vec![details.username.to_owned(), old_name],
+            ),
+            None => Text::translate_with(
+                "multiplayer.player.joined",
+                vec![details.username.to_owned()],
+            ),
+        },
+    };
+    game.ecs.insert_entity_event(
+        player,
+        PlayerJoinMessageEvent {
+            player: details.username.to_owned(),
+            uuid: details.uuid,
+            message: message.to_string(),
+            cancelled: details.silent,
+        },
+    )?;
+    Ok(())
+}
+
+/// Triggers the quit message event for a player.
+pub fn announce_quit(game: &mut Game, options: &Options, details: MessageDetails) {
+    let message = match &options.quit_message {
+        Some(template) => format_template(template, &details),
+        None => Text::translate_with("multiplayer.player.left", vec![details.username.to_owned()]),
+    };
+    game.ecs.insert_event(PlayerQuitMessageEvent {
+        player: details.username.to_owned(),
+        uuid: details.uuid,
+        message: message.to_string(),
+        cancelled: details.silent,
+    });
+}
+
+/// Values for the placeholders in message templates.
+pub struct MessageDetails<'a> {
+    pub username: &'a str,
+    pub uuid: Uuid,
+    pub online_players: u32,
+    pub max_players: u32,
+    /// Whether the player has the silent join permission.
+    pub silent: bool,
+}
+
+/// Replaces the placeholders `{player}`, `{uuid}`, `{online}`,
+/// and `{max}` in a message template.
+fn format_template(template: &str, details: &MessageDetails) -> Text {
+    let message = template
+        .replace("{player}", details.username)
+        .replace("{uuid}", &details.uuid.to_hyphenated().to_string())
+        .replace("{online}", &details.online_players.to_string())
+        .replace("{max}", &details.max_players.to_string());
+    // The template was validated when the config
+    // was loaded, so this only fails in odd cases.
+    parse_text(&message).unwrap_or_else(|_| Text::from(message))
+}
+
+/// Parses a message set by a plugin, which may be
+/// either JSON or plain text.
+pub(super) fn parse_message(message: &str) -> Text {
+    serde_json::from_str(message).unwrap_or_else(|_| Text::from(message.to_owned()))
+}
+
+fn broadcast_join_messages(game: &mut Game) -> SysResult {
+    let mut messages = Vec::new();
+    for (_, event) in game.ecs.query::<&PlayerJoinMessageEvent>().iter() {
+        if event.cancelled {
+            log::debug!("Join message for {} was suppressed", event.player);
+        } else {
+            messages.push(parse_message(&event.message));
+        }
+    }
+    for message in messages {
+        game.broadcast_chat(ChatKind::System, message);
+    }
+    Ok(())
+}
+
+fn broadcast_quit_messages(game: &mut Game) -> SysResult {
+    let mut messages = Vec::new();
+    for (_, event) in game.ecs.query::<&PlayerQuitMessageEvent>().iter() {
+        if event.cancelled {
+            log::debug!("Quit message for {} was suppressed", event.player);
+        } else {
+            messages.push(parse_message(&event.message));
+        }
+    }
+    for message in messages {
+        game.broadcast_chat(ChatKind::System, message);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_placeholders() {
+        let details = MessageDetails {
+            username: "Notch",
+            uuid: Uuid::nil(),
+            online_players: 3,
+            max_players: 16,
+            silent: false,
+        };
+        assert_eq!(
+            format_template("{player} joined ({online}/{max})", &details),
+            Text::from("Notch joined (3/16)")
+        );
+        assert_eq!(
+            format_template(r#"{"text":"+ {player}","color":"green"}"#, &details)
+                .to_legacy_string(),
+            "\u{a7}a+ Notch"
+        );
+    }
+
+    #[test]
+    fn plugin_messages_may_be_plain() {
+        assert_eq!(parse_message("hello"), Text::from("hello"));
+        assert_eq!(parse_message(r#""hello""#), Text::from("hello"));
+    }
+}
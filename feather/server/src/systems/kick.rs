@@ -0,0 +1,27 @@
+use common::{events::PlayerKickEvent, Game};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::Name;
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(disconnect_kicked_players);
+}
+
+/// Disconnects the clients of kicked players. Their
+/// entities are then removed by `player_leave`.
+fn disconnect_kicked_players(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, event, name)) in game
+        .ecs
+        .query::<(&ClientId, &PlayerKickEvent, &Name)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            log::info!("Kicked {}: {}", &**name, event.reason.to_plain_string());
+            client.disconnect(event.reason.clone());
+        }
+    }
+    Ok(())
+}
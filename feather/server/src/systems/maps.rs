@@ -0,0 +1,51 @@
+//! Loads the maps of the world into the [`MapStore`]
+//! and saves new maps.
+
+use std::path::Path;
+
+use base::anvil::map::{load_last_map_id, load_map, map_ids, save_last_map_id, save_map};
+use common::{maps::MapStore, Game};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::Server;
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>, world_dir: &Path) {
+    // Starting with an empty store would give out IDs from 0
+    // again and overwrite the existing maps.
+    let maps = load_maps(world_dir).expect("failed to load maps");
+    *game
+        .resources
+        .get_mut::<MapStore>()
+        .expect("common must be registered before the server") = maps;
+    systems.group::<Server>().add_system(save_maps);
+}
+
+fn load_maps(world_dir: &Path) -> anyhow::Result<MapStore> {
+    let ids = map_ids(world_dir)?;
+    let mut maps = Vec::new();
+    for &id in &ids {
+        match load_map(world_dir, id) {
+            Ok(data) => maps.push((id, data)),
+            Err(e) => log::warn!("Failed to load map {}: {:?}", id, e),
+        }
+    }
+    // Maps which failed to load still keep their IDs.
+    let last_id = load_last_map_id(world_dir)?.into_iter().chain(ids).max();
+    Ok(MapStore::new(maps, last_id))
+}
+
+fn save_maps(game: &mut Game, server: &mut Server) -> SysResult {
+    let world_dir = &server.options.world_dir;
+    let mut maps = game.resources.get_mut::<MapStore>()?;
+    if let Some(last_id) = maps.take_unsaved_last_id() {
+        if let Err(e) = save_last_map_id(world_dir, last_id) {
+            log::error!("Failed to save the last map ID: {:?}", e);
+        }
+    }
+    for (id, data) in maps.take_unsaved() {
+        if let Err(e) = save_map(world_dir, id, data) {
+            log::error!("Failed to save map {}: {:?}", id, e);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Loads and saves the parts of a player's state which
+//! are kept in their player data file: the inventory,
+//! the recipe book and the enchantment seed.
+
+use std::path::Path;
+
+use base::{
+    anvil::player::{load_player_data, player_data_exists, save_player_data, InventorySlot},
+    Inventory, ItemStack,
+};
+use common::{
+    enchanting::EnchantmentSeed,
+    recipe_book::RecipeBook,
+    window::{BackingWindow, Window},
+    Game,
+};
+use ecs::{Entity, SysResult};
+use uuid::Uuid;
+
+/// State of a player stored in their data file.
+#[derive(Debug)]
+pub struct StoredState {
+    /// The items of the player's inventory.
+    pub inventory: Vec<InventorySlot>,
+    pub recipe_book: RecipeBook,
+    pub enchantment_seed: EnchantmentSeed,
+}
+
+impl StoredState {
+    /// Returns the state of a player who joins for the first time.
+    pub fn first_join() -> Self {
+        Self {
+            inventory: Vec::new(),
+            recipe_book: RecipeBook::default(),
+            enchantment_seed: EnchantmentSeed::random(),
+        }
+    }
+
+    /// Puts the stored items into `inventory`, a player inventory.
+    pub fn restore_inventory(&self, inventory: &Inventory) {
+        let window = player_window(inventory);
+        for slot in &self.inventory {
+            match slot.convert_index() {
+                Some(index) => {
+                    if let Err(e) = window.set_item(index, Some(ItemStack::from(slot))) {
+                        log::warn!("Failed to restore inventory slot {}: {}", slot.slot, e);
+                    }
+                }
+                None => log::warn!("Ignoring item in unknown inventory slot {}", slot.slot),
+            }
+        }
+    }
+}
+
+/// Loads the state stored in the player's data file.
+pub fn load(world_dir: &Path, uuid: Uuid) -> StoredState {
+    match load_player_data(world_dir, uuid) {
+        Ok(data) => StoredState {
+            inventory: data.inventory,
+            recipe_book: RecipeBook::from_data(&data.recipe_book),
+            enchantment_seed: EnchantmentSeed(data.enchantment_seed),
+        },
+        Err(e) => {
+            log::error!("Failed to load player data of {}: {:?}", uuid, e);
+            StoredState::first_join()
+        }
+    }
+}
+
+/// Returns the items of a player inventory, as stored in data files.
+fn stored_inventory(inventory: &Inventory) -> Vec<InventorySlot> {
+    let window = player_window(inventory);
+    let mut slots = Vec::new();
+    let mut index = 0;
+    while let Ok(item) = window.item(index) {
+        if let Some(stack) = item.clone() {
+            // Crafting slots aren't stored
+            slots.extend(InventorySlot::from_network_index(index, stack));
+        }
+        index += 1;
+    }
+    slots
+}
+
+/// Returns a window over a player inventory, which
+/// maps network slot indices to its areas.
+fn player_window(inventory: &Inventory) -> Window {
+    Window::new(BackingWindow::Player {
+        player: inventory.new_handle(),
+    })
+}
+
+/// Stores the state of `player` in their data file,
+/// keeping the rest of the file.
+///
+/// Nothing is saved if the file exists but can't be
+/// read, so that it isn't overwritten.
+pub fn save(game: &Game, world_dir: &Path, player: Entity) -> SysResult {
+    let uuid = *game.ecs.get::<Uuid>(player)?;
+    let mut data = if player_data_exists(world_dir, uuid) {
+        match load_player_data(world_dir, uuid) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!(
+                    "Not saving player data of {}, as the existing file can't be read: {:?}",
+                    uuid,
+                    e
+                );
+                return Ok(());
+            }
+        }
+    } else {
+        Default::default()
+    };
+
+    data.inventory = stored_inventory(&game.ecs.get::<Inventory>(player)?);
+    data.recipe_book = game.ecs.get::<RecipeBook>(player)?.to_data();
+    data.enchantment_seed = game.ecs.get::<EnchantmentSeed>(player)?.0;
+    if let Err(e) = save_player_data(world_dir, uuid, &data) {
+        log::error!("Failed to save player data of {}: {:?}", uuid, e);
+    }
+    Ok(())
+}
+
+/// Stores the state of every player in their data file.
+pub fn save_all(game: &Game, world_dir: &Path) {
+    let players: Vec<Entity> = game
+        .ecs
+        .query::<(&Uuid, &Inventory, &RecipeBook, &EnchantmentSeed)>()
+        .iter()
+        .map(|(player, _)| player)
+        .collect();
+    for player in players {
+        if let Err(e) = save(game, world_dir, player) {
+            log::error!("Failed to save player data: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::{Area, Item};
+
+    use super::*;
+
+    #[test]
+    fn enchantment_seed_round_trip() {
+        let world_dir =
+            std::env::temp_dir().join(format!("feather-player-data-test-{}", std::process::id()));
+        let uuid = Uuid::new_v4();
+        let mut game = Game::new();
+        let inventory = Inventory::player();
+        *inventory.item(Area::Hotbar, 3).unwrap() = Some(ItemStack::new(Item::DiamondSword, 1));
+        *inventory.item(Area::Helmet, 0).unwrap() = Some(ItemStack::new(Item::IronHelmet, 1));
+        let player = game.ecs.spawn((
+            uuid,
+            inventory,
+            RecipeBook::default(),
+            EnchantmentSeed(-1234),
+        ));
+
+        save(&game, &world_dir, player).unwrap();
+        let stored = load(&world_dir, uuid);
+        std::fs::remove_dir_all(&world_dir).unwrap();
+        assert_eq!(stored.enchantment_seed, EnchantmentSeed(-1234));
+        assert_eq!(stored.recipe_book, RecipeBook::default());
+
+        let restored = Inventory::player();
+        stored.restore_inventory(&restored);
+        assert_eq!(
+            *restored.item(Area::Hotbar, 3).unwrap(),
+            Some(ItemStack::new(Item::DiamondSword, 1))
+        );
+        assert_eq!(
+            *restored.item(Area::Helmet, 0).unwrap(),
+            Some(ItemStack::new(Item::IronHelmet, 1))
+        );
+    }
+}
@@ -1,15 +1,42 @@
-use base::{Inventory, Position, Text};
+use std::path::Path;
+
+use base::{
+    anvil::player::{player_data_exists, save_player_data, PlayerData},
+    inventory::{HOTBAR_SIZE, INVENTORY_SIZE},
+    metadata::META_INDEX_PLAYER_DISPLAYED_SKIN_PARTS,
+    Area, EntityMetadata, Inventory, ItemStack, Position, Text,
+};
+use chrono::Utc;
 use common::{
-    chat::{ChatKind, ChatPreference},
+    afk::LastActivity,
+    chat::{ChatKind, ChatMessage, ChatPreference},
+    combat_log, commands, disconnect_reason,
     entities::player::HotbarSlot,
+    events::FirstJoinEvent,
+    plugin_channels::{self, PluginChannels, RegisteredChannels},
+    resource_pack,
+    vanish::{self, Vanished},
     view::View,
     window::BackingWindow,
+    world::EntityWorld,
     ChatBox, Game, Window,
 };
 use ecs::{SysResult, SystemExecutor};
-use quill_common::{components::Name, entity_init::EntityInit};
+use quill_common::{
+    components::{Afk, Name},
+    entity_init::EntityInit,
+    events::NameChangedEvent,
+};
+use uuid::Uuid;
 
-use crate::{ClientId, Server};
+use crate::{permissions::PermissionStore, user_cache::UserCache, ClientId, Options, Server};
+
+use super::{
+    combat_log::PunishedPlayers,
+    join_message::{self, MessageDetails, SILENT_JOIN_PERMISSION},
+    player_data::{self, StoredState},
+    vanish::VanishedPlayers,
+};
 
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems.group::<Server>().add_system(poll_new_players);
@@ -19,49 +46,219 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
 /// to join the game.
 fn poll_new_players(game: &mut Game, server: &mut Server) -> SysResult {
     for client_id in server.accept_new_players() {
+        replace_old_session(game, server, client_id)?;
         accept_new_player(game, server, client_id)?;
     }
     Ok(())
 }
 
+/// Disconnects the old session of a player who logged in again
+/// from another location, so that the new client takes their place.
+fn replace_old_session(game: &mut Game, server: &mut Server, client_id: ClientId) -> SysResult {
+    let uuid = server.clients.get(client_id).unwrap().uuid();
+    let old_player = game
+        .ecs
+        .query::<(&ClientId, &Uuid)>()
+        .iter()
+        .find(|(_, (&id, &player_uuid))| id != client_id && player_uuid == uuid)
+        .map(|(player, (&id, _))| (player, id));
+
+    if let Some((old_player, old_client_id)) = old_player {
+        if let Some(old_client) = server.clients.get(old_client_id) {
+            log::info!("{} logged in from another location", old_client.username());
+            old_client.disconnect(disconnect_reason::duplicate_login());
+        }
+        super::player_leave::remove_player(game, server, old_player)?;
+    }
+    Ok(())
+}
+
 fn accept_new_player(game: &mut Game, server: &mut Server, client_id: ClientId) -> SysResult {
     let client = server.clients.get(client_id).unwrap();
     client.send_join_game(server.options.default_gamemode);
     client.send_brand();
+    client.send_difficulty(game.world.settings().difficulty);
+    {
+        let channels = game.resources.get::<PluginChannels>()?;
+        if channels.channels().next().is_some() {
+            client.send_plugin_message(
+                plugin_channels::REGISTER_CHANNEL,
+                plugin_channels::encode_channel_list(channels.channels()),
+            );
+        }
+    }
+
+    let first_join = !player_data_exists(&server.options.world_dir, client.uuid());
+    let position = match server.options.first_spawn {
+        Some(first_spawn) if first_join => first_spawn,
+        _ => Position::default(),
+    };
+
+    let mut builder = game.create_entity_builder(position, EntityInit::Player);
 
-    let mut builder = game.create_entity_builder(Position::default(), EntityInit::Player);
+    let stored = if first_join {
+        StoredState::first_join()
+    } else {
+        player_data::load(&server.options.world_dir, client.uuid())
+    };
 
     let inventory = Inventory::player();
+    if first_join {
+        give_starter_kit(&inventory, &server.options.starter_kit);
+    }
+    stored.restore_inventory(&inventory);
+    combat_log::on_rejoin(game, client.uuid(), &inventory)?;
     let window = Window::new(BackingWindow::Player {
         player: inventory.new_handle(),
     });
 
-    client.send_window_items(&window);
+    client.send_window_items(0, &window);
+
+    client.send_declare_commands(&commands::command_names(game)?);
+    client.send_declare_recipes();
+    client.send_recipe_book(&stored.recipe_book);
+
+    let permissions = game
+        .resources
+        .get::<PermissionStore>()?
+        .permissions_for(client.uuid());
+    client.set_sees_vanished(vanish::sees_vanished(&permissions));
+    let vanished = game
+        .resources
+        .get::<VanishedPlayers>()?
+        .contains(client.uuid());
+    if vanished {
+        builder.add(Vanished);
+    }
 
     builder
         .add(client.network_id())
         .add(client_id)
-        .add(View::new(
-            Position::default().chunk(),
-            server.options.view_distance,
-        ))
+        .add(View::new(position.chunk(), server.options.view_distance))
         .add(server.options.default_gamemode)
         .add(Name::new(client.username()))
         .add(client.uuid())
+        .add(EntityWorld(game.world.name().to_owned()))
         .add(client.profile().to_vec())
         .add(ChatBox::new(ChatPreference::All))
+        .add(permissions.clone())
+        .add(LastActivity(game.tick_count))
+        .add(Afk(false))
+        .add(RegisteredChannels::default())
         .add(inventory)
         .add(window)
-        .add(HotbarSlot::default());
+        .add(HotbarSlot::default())
+        .add(stored.enchantment_seed)
+        .add(stored.recipe_book)
+        .add(EntityMetadata::entity_base().with(META_INDEX_PLAYER_DISPLAYED_SKIN_PARTS, 0u8));
 
-    game.spawn_entity(builder);
+    let player = game.spawn_entity(builder);
 
-    broadcast_player_join(game, client.username());
+    if first_join {
+        log::info!("{} joined for the first time", client.username());
+        game.ecs.insert_entity_event(player, FirstJoinEvent)?;
+        record_first_join(&server.options.world_dir, client.uuid(), &server.options);
+    }
+
+    let old_name = reconcile_name(game, client.username(), client.uuid())?;
+    if let Some(old_name) = &old_name {
+        game.ecs.insert_entity_event(
+            player,
+            NameChangedEvent {
+                old_name: old_name.clone(),
+                new_name: client.username().to_owned(),
+            },
+        )?;
+    }
+
+    join_message::announce_join(
+        game,
+        &server.options,
+        player,
+        MessageDetails {
+            username: client.username(),
+            uuid: client.uuid(),
+            online_players: server.player_count(),
+            max_players: server.options.max_players,
+            silent: vanished || permissions.has(SILENT_JOIN_PERMISSION),
+        },
+        old_name,
+    )?;
+    if let Some(motd) = &server.options.join_motd {
+        game.send_message(player, ChatMessage::new(ChatKind::System, motd.clone()))?;
+    }
+
+    if let Some(pack) = &server.options.resource_pack {
+        resource_pack::send_resource_pack(game, player, pack.url.clone(), pack.hash.clone())?;
+    }
+
+    let punished = game
+        .resources
+        .get_mut::<PunishedPlayers>()?
+        .take(client.uuid());
+    if punished {
+        game.send_message(
+            player,
+            ChatMessage::new(
+                ChatKind::System,
+                Text::from_legacy("\u{a7}cYou logged out during combat and lost your items."),
+            ),
+        )?;
+    }
 
     Ok(())
 }
 
-fn broadcast_player_join(game: &mut Game, username: &str) {
-    let message = Text::translate_with("multiplayer.player.joined", vec![username.to_owned()]);
-    game.broadcast_chat(ChatKind::System, message);
+/// Puts the starter kit into the hotbar, then into
+/// the rest of the inventory.
+fn give_starter_kit(inventory: &Inventory, kit: &[ItemStack]) {
+    let mut slots = (0..HOTBAR_SIZE)
+        .map(|slot| (Area::Hotbar, slot))
+        .chain((0..INVENTORY_SIZE).map(|slot| (Area::Storage, slot)));
+    for stack in kit {
+        match slots.next() {
+            Some((area, slot)) => {
+                if let Some(mut item) = inventory.item(area, slot) {
+                    *item = Some(stack.clone());
+                }
+            }
+            None => {
+                log::warn!("Starter kit doesn't fit in the inventory");
+                return;
+            }
+        }
+    }
+}
+
+/// Creates the player's data file, so that they're
+/// not detected as joining for the first time again.
+fn record_first_join(world_dir: &Path, uuid: Uuid, options: &Options) {
+    let data = PlayerData {
+        gamemode: options.default_gamemode as i32,
+        ..Default::default()
+    };
+    if let Err(e) = save_player_data(world_dir, uuid, &data) {
+        log::error!("Failed to save player data for {}: {:?}", uuid, e);
+    }
+}
+
+/// Records the player's name in the user cache. Returns
+/// the name they last played with if it changed.
+///
+/// Persistent player data is keyed by UUID, so
+/// nothing else needs to be migrated on a name change.
+fn reconcile_name(game: &mut Game, username: &str, uuid: Uuid) -> anyhow::Result<Option<String>> {
+    let old_name = game
+        .resources
+        .get_mut::<UserCache>()?
+        .insert(username, uuid, Utc::now());
+    if let Some(old_name) = &old_name {
+        log::info!(
+            "{} ({}) was previously known as {}",
+            username,
+            uuid,
+            old_name
+        );
+    }
+    Ok(old_name)
 }
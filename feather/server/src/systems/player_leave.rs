@@ -1,10 +1,15 @@
-use base::Text;
-use common::{chat::ChatKind, Game};
-use ecs::{SysResult, SystemExecutor};
+use common::{combat_log, permissions::Permissions, vanish::Vanished, Game};
+use ecs::{Entity, SysResult, SystemExecutor};
 use quill_common::components::Name;
+use uuid::Uuid;
 
 use crate::{ClientId, Server};
 
+use super::{
+    join_message::{self, MessageDetails, SILENT_JOIN_PERMISSION},
+    player_data,
+};
+
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems
         .group::<Server>()
@@ -12,24 +17,48 @@ pub fn register(systems: &mut SystemExecutor<Game>) {
 }
 
 fn remove_disconnected_clients(game: &mut Game, server: &mut Server) -> SysResult {
-    let mut entities_to_remove = Vec::new();
-    for (player, (&client_id, name)) in game.ecs.query::<(&ClientId, &Name)>().iter() {
-        let client = server.clients.get(client_id).unwrap();
-        if client.is_disconnected() {
-            server.remove_client(client_id);
-            entities_to_remove.push(player);
-            broadcast_player_leave(game, name);
-        }
-    }
+    let disconnected: Vec<Entity> = game
+        .ecs
+        .query::<&ClientId>()
+        .iter()
+        .filter(|(_, &client_id)| server.clients.get(client_id).unwrap().is_disconnected())
+        .map(|(player, _)| player)
+        .collect();
 
-    for player in entities_to_remove {
-        game.remove_entity(player)?;
+    for player in disconnected {
+        remove_player(game, server, player)?;
     }
-
     Ok(())
 }
 
-fn broadcast_player_leave(game: &Game, username: &Name) {
-    let message = Text::translate_with("multiplayer.player.left", vec![username.to_string()]);
-    game.broadcast_chat(ChatKind::System, message);
+/// Removes a player and their client, announcing that they left.
+pub fn remove_player(game: &mut Game, server: &mut Server, player: Entity) -> SysResult {
+    let client_id = *game.ecs.get::<ClientId>(player)?;
+    let username = game.ecs.get::<Name>(player)?.to_string();
+    let uuid = *game.ecs.get::<Uuid>(player)?;
+    let silent = game.ecs.get::<Vanished>(player).is_ok()
+        || game
+            .ecs
+            .get::<Permissions>(player)?
+            .has(SILENT_JOIN_PERMISSION);
+    // Punishments for combat logging empty the inventory,
+    // so they have to happen before it is saved.
+    combat_log::on_disconnect(game, player)?;
+    player_data::save(game, &server.options.world_dir, player)?;
+    server.remove_client(client_id);
+
+    join_message::announce_quit(
+        game,
+        &server.options,
+        MessageDetails {
+            username: &username,
+            uuid,
+            online_players: server.player_count(),
+            max_players: server.options.max_players,
+            silent,
+        },
+    );
+    super::transfer::on_player_leave(game, player)?;
+    game.remove_entity(player)?;
+    Ok(())
 }
@@ -1,8 +1,19 @@
+use std::io::Cursor;
+
 use crate::{ClientId, Server};
-use common::{events::PluginMessageEvent, Game};
-use ecs::{SysResult, SystemExecutor};
+use common::{
+    events::PluginMessageEvent,
+    plugin_channels::{ClientBrand, PluginChannels},
+    Game,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
+use protocol::{ProtocolVersion, Readable};
 
-pub fn register(systems: &mut SystemExecutor<Game>) {
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.resources
+        .get_mut::<PluginChannels>()
+        .expect("common must be registered before the server")
+        .subscribe("minecraft:brand", handle_brand);
     systems
         .group::<Server>()
         .add_system(send_plugin_message_packets);
@@ -17,3 +28,11 @@ fn send_plugin_message_packets(game: &mut Game, server: &mut Server) -> SysResul
 
     Ok(())
 }
+
+/// Records the client brand sent by a player.
+fn handle_brand(game: &mut Game, player: Entity, data: &[u8]) -> SysResult {
+    let brand = String::read(&mut Cursor::new(data), ProtocolVersion::LATEST)?;
+    log::debug!("Player {:?} uses client brand {}", player, brand);
+    game.ecs.insert(player, ClientBrand(brand))?;
+    Ok(())
+}
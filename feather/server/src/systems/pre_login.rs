@@ -0,0 +1,82 @@
+//! Lets plugins reject players before they join.
+//!
+//! Connection workers send each authenticated player here
+//! and wait for the outcome. We trigger a `PlayerPreLoginEvent`
+//! and respond on the next tick, after every other system
+//! (including plugins) has observed the event.
+
+use std::mem;
+
+use base::{Text, TextValue};
+use common::Game;
+use ecs::{SysResult, SystemExecutor};
+use flume::Receiver;
+use quill_common::events::PlayerPreLoginEvent;
+
+use crate::initial_handler::PluginPreLogin;
+
+use super::join_message::parse_message;
+
+/// Resource holding pre-logins whose events were triggered.
+struct PreLogins {
+    received: Receiver<PluginPreLogin>,
+    pending: Vec<PluginPreLogin>,
+}
+
+pub fn register(
+    game: &mut Game,
+    systems: &mut SystemExecutor<Game>,
+    received: Receiver<PluginPreLogin>,
+) {
+    game.insert_resource(PreLogins {
+        received,
+        pending: Vec::new(),
+    });
+    // Responds before triggering, so that the
+    // events of the last tick are still around.
+    systems
+        .add_system(respond_to_pre_logins)
+        .add_system(trigger_pre_login_events);
+}
+
+fn respond_to_pre_logins(game: &mut Game) -> SysResult {
+    let pending = mem::take(&mut game.resources.get_mut::<PreLogins>()?.pending);
+    for pre_login in pending {
+        let rejection = game
+            .ecs
+            .query::<&PlayerPreLoginEvent>()
+            .iter()
+            .find(|(_, event)| event.uuid == pre_login.uuid && event.cancelled)
+            .map(|(_, event)| parse_message(&event.kick_message));
+        let result = match rejection {
+            Some(reason) => {
+                log::debug!("{} was rejected by a plugin", pre_login.username);
+                Err(reason)
+            }
+            None => Ok(()),
+        };
+        // The player may have disconnected while waiting.
+        let _ = pre_login.response.send(result);
+    }
+    Ok(())
+}
+
+fn trigger_pre_login_events(game: &mut Game) -> SysResult {
+    let received = game.resources.get::<PreLogins>()?.received.clone();
+    for pre_login in received.try_iter() {
+        game.ecs.insert_event(PlayerPreLoginEvent {
+            player: pre_login.username.clone(),
+            uuid: pre_login.uuid,
+            kick_message: Text::from(TextValue::translate(
+                "multiplayer.disconnect.not_allowed_to_join",
+            ))
+            .to_string(),
+            cancelled: false,
+        });
+        game.resources
+            .get_mut::<PreLogins>()?
+            .pending
+            .push(pre_login);
+    }
+    Ok(())
+}
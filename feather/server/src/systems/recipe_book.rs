@@ -0,0 +1,64 @@
+//! Loads the ingredients unlocking each [recipe](common::recipe_book)
+//! and sends newly unlocked recipes.
+//!
+//! Recipe books are stored through [`player_data`](super::player_data).
+
+use std::{collections::BTreeMap, fs, io};
+
+use base::Item;
+use common::{
+    events::RecipesUnlockedEvent,
+    recipe_book::{RecipeBook, RecipeUnlocks},
+    Game,
+};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, Server};
+
+/// Path of the file listing the ingredients which unlock
+/// each recipe, relative to the working directory.
+pub const RECIPE_UNLOCKS_PATH: &str = "recipe_unlocks.json";
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    if let Err(e) = load_recipe_unlocks(game, RECIPE_UNLOCKS_PATH) {
+        log::warn!("Failed to load {}: {:?}", RECIPE_UNLOCKS_PATH, e);
+    }
+    systems.group::<Server>().add_system(send_unlocked_recipes);
+}
+
+/// Adds the recipes in `path`, a JSON object mapping recipe IDs
+/// to the items which unlock them, to the [`RecipeUnlocks`].
+/// A missing file adds no recipes.
+fn load_recipe_unlocks(game: &mut Game, path: &str) -> anyhow::Result<()> {
+    let recipes: BTreeMap<String, Vec<String>> = match fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut unlocks = game.resources.get_mut::<RecipeUnlocks>()?;
+    for (recipe, ingredients) in &recipes {
+        let ingredients = ingredients.iter().filter_map(|name| {
+            let item = Item::from_name(name.trim_start_matches("minecraft:"));
+            if item.is_none() {
+                log::warn!("Unknown item {} unlocking recipe {}", name, recipe);
+            }
+            item
+        });
+        unlocks.add(recipe, ingredients);
+    }
+    Ok(())
+}
+
+fn send_unlocked_recipes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, book, event)) in game
+        .ecs
+        .query::<(&ClientId, &RecipeBook, &RecipesUnlockedEvent)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_unlocked_recipes(book, &event.recipes);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use common::{events::ResourcePackSendEvent, Game};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems.group::<Server>().add_system(send_resource_packs);
+}
+
+fn send_resource_packs(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, (&client_id, event)) in game
+        .ecs
+        .query::<(&ClientId, &ResourcePackSendEvent)>()
+        .iter()
+    {
+        if let Some(client) = server.clients.get(client_id) {
+            client.send_resource_pack(event.url.clone(), event.hash.clone());
+        }
+    }
+    Ok(())
+}
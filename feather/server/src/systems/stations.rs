@@ -0,0 +1,94 @@
+//! Opens the windows of crafting stations, like
+//! stonecutters, for players interacting with them.
+
+use base::{BlockPosition, Text, TextValue};
+use common::{cartography, smithing, stonecutter, Game, Window};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::events::BlockInteractEvent;
+
+use crate::{ClientId, Server};
+
+/// Window ID used for the windows players open by interacting
+/// with blocks. A player has at most one such window open.
+pub const STATION_WINDOW_ID: u8 = 1;
+
+/// Window types in the Open Window packet.
+const SMITHING_WINDOW_KIND: i32 = 20;
+const CARTOGRAPHY_WINDOW_KIND: i32 = 22;
+const STONECUTTER_WINDOW_KIND: i32 = 23;
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(open_stonecutters)
+        .add_system(open_smithing_tables)
+        .add_system(open_cartography_tables);
+}
+
+fn open_stonecutters(game: &mut Game, server: &mut Server) -> SysResult {
+    open_station(
+        game,
+        server,
+        STONECUTTER_WINDOW_KIND,
+        "container.stonecutter",
+        stonecutter::is_stonecutter,
+        stonecutter::open_stonecutter,
+    )
+}
+
+fn open_smithing_tables(game: &mut Game, server: &mut Server) -> SysResult {
+    open_station(
+        game,
+        server,
+        SMITHING_WINDOW_KIND,
+        "container.upgrade",
+        smithing::is_smithing_table,
+        smithing::open_smithing_table,
+    )
+}
+
+fn open_cartography_tables(game: &mut Game, server: &mut Server) -> SysResult {
+    open_station(
+        game,
+        server,
+        CARTOGRAPHY_WINDOW_KIND,
+        "container.cartography_table",
+        cartography::is_cartography_table,
+        cartography::open_cartography_table,
+    )
+}
+
+/// Opens a window of type `kind` for each player who interacted
+/// with a block matching `is_station`, after `open` gave the
+/// player the station's [`Window`].
+///
+/// `title` is the translation key of the window's title.
+fn open_station(
+    game: &mut Game,
+    server: &mut Server,
+    kind: i32,
+    title: &str,
+    is_station: fn(&Game, BlockPosition) -> bool,
+    open: fn(&mut Game, Entity, BlockPosition) -> SysResult,
+) -> SysResult {
+    let mut opened = Vec::new();
+    for (player, (event, &client_id)) in game.ecs.query::<(&BlockInteractEvent, &ClientId)>().iter()
+    {
+        if is_station(game, event.location) {
+            opened.push((player, client_id, event.location));
+        }
+    }
+
+    for (player, client_id, position) in opened {
+        open(game, player, position)?;
+        if let Some(client) = server.clients.get(client_id) {
+            client.open_window(
+                STATION_WINDOW_ID,
+                kind,
+                Text::from(TextValue::translate(title)),
+            );
+            client.send_window_items(STATION_WINDOW_ID, &*game.ecs.get::<Window>(player)?);
+        }
+    }
+    Ok(())
+}
@@ -3,6 +3,7 @@
 use base::{Gamemode, ProfileProperty};
 use common::{
     events::{EntityRemoveEvent, PlayerJoinEvent},
+    vanish::Vanished,
     Game,
 };
 use ecs::{SysResult, SystemExecutor};
@@ -11,11 +12,15 @@ use uuid::Uuid;
 
 use crate::{ClientId, Server};
 
+/// Ticks between updates of the pings shown in the tablist.
+const PING_UPDATE_INTERVAL: u64 = 20 * 5;
+
 pub fn register(systems: &mut SystemExecutor<Game>) {
     systems
         .group::<Server>()
         .add_system(remove_tablist_players)
-        .add_system(add_tablist_players);
+        .add_system(add_tablist_players)
+        .add_system(update_tablist_pings);
 }
 
 fn remove_tablist_players(game: &mut Game, server: &mut Server) -> SysResult {
@@ -43,18 +48,27 @@ fn add_tablist_players(game: &mut Game, server: &mut Server) -> SysResult {
         .iter()
     {
         // Add this player to other players' tablists
+        let vanished = game.ecs.get::<Vanished>(player).is_ok();
         server.broadcast_with(|client| {
-            client.add_tablist_player(uuid, name.to_string(), profile, gamemode)
+            if !vanished || client.sees_vanished() || client.uuid() == uuid {
+                client.add_tablist_player(uuid, name.to_string(), profile, gamemode)
+            }
         });
 
         // Add other players to this player's tablist
-        for (other_player, (&uuid, name, &gamemode, profile)) in game
+        for (other_player, (&uuid, name, &gamemode, profile, vanished)) in game
             .ecs
-            .query::<(&Uuid, &Name, &Gamemode, &Vec<ProfileProperty>)>()
+            .query::<(
+                &Uuid,
+                &Name,
+                &Gamemode,
+                &Vec<ProfileProperty>,
+                Option<&Vanished>,
+            )>()
             .iter()
         {
             if let Some(client) = server.clients.get(client_id) {
-                if other_player != player {
+                if other_player != player && (vanished.is_none() || client.sees_vanished()) {
                     client.add_tablist_player(uuid, name.to_string(), profile, gamemode);
                 }
             }
@@ -62,3 +76,24 @@ fn add_tablist_players(game: &mut Game, server: &mut Server) -> SysResult {
     }
     Ok(())
 }
+
+fn update_tablist_pings(game: &mut Game, server: &mut Server) -> SysResult {
+    if game.tick_count % PING_UPDATE_INTERVAL != 0 {
+        return Ok(());
+    }
+
+    let pings: Vec<(Uuid, i32)> = server
+        .clients
+        .iter()
+        .filter_map(|client| {
+            let latency = client.latency()?;
+            Some((client.uuid(), latency.as_millis() as i32))
+        })
+        .collect();
+    if pings.is_empty() {
+        return Ok(());
+    }
+
+    server.broadcast_with(|client| client.update_tablist_pings(pings.clone()));
+    Ok(())
+}
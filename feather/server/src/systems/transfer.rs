@@ -0,0 +1,153 @@
+//! Asks the proxy to transfer players to other servers.
+//!
+//! Both BungeeCord and Velocity accept the
+//! `Connect` message on the BungeeCord plugin channel.
+
+use base::Text;
+use common::{
+    commands::{check_permission, CommandRegistry},
+    events::{PlayerTransferEvent, PlayerTransferredEvent},
+    transfer::{transfer_player, PendingTransfer},
+    Game,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
+use quill_common::components::Name;
+
+use crate::{ClientId, Server};
+
+/// Plugin channel understood by BungeeCord and Velocity.
+const BUNGEECORD_CHANNEL: &str = "bungeecord:main";
+
+/// Whether transfers can be carried out, copied from the
+/// server options so commands can check it.
+struct TransferSettings {
+    behind_proxy: bool,
+}
+
+pub fn register(
+    game: &mut Game,
+    systems: &mut SystemExecutor<Game>,
+    behind_proxy: bool,
+) -> anyhow::Result<()> {
+    game.insert_resource(TransferSettings { behind_proxy });
+    game.resources
+        .get_mut::<CommandRegistry>()?
+        .register("server", server_command);
+    systems
+        .group::<Server>()
+        .add_system(send_transfer_requests)
+        .add_system(expire_pending_transfers);
+    Ok(())
+}
+
+/// Permission node required for `/server`.
+pub const SERVER_PERMISSION: &str = "feather.server";
+
+/// `/server <name>`
+fn server_command(game: &mut Game, sender: Entity, args: &[&str]) -> anyhow::Result<Text> {
+    check_permission(game, sender, SERVER_PERMISSION)?;
+    if !game.resources.get::<TransferSettings>()?.behind_proxy {
+        anyhow::bail!("this server is not behind a proxy");
+    }
+    match args {
+        [server] => {
+            transfer_player(game, sender, *server)?;
+            Ok(Text::of(format!("Connecting to {}...", server)))
+        }
+        _ => anyhow::bail!("usage: /server <name>"),
+    }
+}
+
+fn send_transfer_requests(game: &mut Game, server: &mut Server) -> SysResult {
+    let mut failed = Vec::new();
+    for (player, (&client_id, event, name)) in game
+        .ecs
+        .query::<(&ClientId, &PlayerTransferEvent, &Name)>()
+        .iter()
+    {
+        if server.options.proxy_mode.is_none() {
+            log::warn!(
+                "Cannot transfer {} to {}: the server is not behind a proxy",
+                &**name,
+                event.server
+            );
+            failed.push(player);
+            continue;
+        }
+
+        if let Some(client) = server.clients.get(client_id) {
+            log::debug!(
+                "Asking the proxy to transfer {} to {}",
+                &**name,
+                event.server
+            );
+            client.send_plugin_message(BUNGEECORD_CHANNEL, connect_message(&event.server));
+        }
+    }
+
+    for player in failed {
+        game.ecs.remove::<PendingTransfer>(player)?;
+    }
+    Ok(())
+}
+
+/// Forgets transfers the proxy didn't carry out in time,
+/// e.g. because the target server doesn't exist.
+fn expire_pending_transfers(game: &mut Game, _server: &mut Server) -> SysResult {
+    let expired: Vec<Entity> = game
+        .ecs
+        .query::<&PendingTransfer>()
+        .iter()
+        .filter(|(_, pending)| pending.is_expired(game.tick_count))
+        .map(|(player, _)| player)
+        .collect();
+    for player in expired {
+        game.ecs.remove::<PendingTransfer>(player)?;
+    }
+    Ok(())
+}
+
+/// Triggers a `PlayerTransferredEvent` if `player`
+/// is leaving because of a transfer.
+pub fn on_player_leave(game: &mut Game, player: Entity) -> SysResult {
+    let pending = game
+        .ecs
+        .get::<PendingTransfer>(player)
+        .ok()
+        .map(|p| p.clone());
+    if let Some(PendingTransfer { server, .. }) = pending {
+        game.ecs
+            .insert_entity_event(player, PlayerTransferredEvent { server })?;
+    }
+    Ok(())
+}
+
+/// Encodes a BungeeCord `Connect` message.
+fn connect_message(server: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+    write_java_utf(&mut data, "Connect");
+    write_java_utf(&mut data, server);
+    data
+}
+
+/// Writes a string like Java's `DataOutput.writeUTF`.
+///
+/// Proxy server names are plain text, so the
+/// differences between modified UTF-8 and UTF-8 don't matter.
+fn write_java_utf(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    data.extend_from_slice(s.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_connect_message() {
+        assert_eq!(
+            connect_message("lobby"),
+            b"\x00\x07Connect\x00\x05lobby".to_vec()
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Hides vanished players from other clients.
+//!
+//! Spawn packets and tablist entries for vanished players are
+//! skipped for clients that can't see them (see `SpawnPacketSender`
+//! and the `tablist` systems). As they are never loaded on those
+//! clients, their movement, metadata and animations aren't sent
+//! either; see `Server::broadcast_entity_update_with`.
+//!
+//! This module handles players vanishing or reappearing while
+//! online, and remembers which players are vanished in
+//! `vanished.json`, or the database if one is configured,
+//! so the state survives relogs.
+
+use base::{Gamemode, Position, ProfileProperty};
+use common::{events::VanishChangeEvent, Game};
+use ecs::{SysResult, SystemExecutor};
+use quill_common::components::Name;
+use uuid::Uuid;
+
+use crate::{
+    entities::SpawnPacketSender,
+    storage::{Backend, PlayerSet, Storage},
+    ClientId, NetworkId, Server,
+};
+
+/// Path of the vanished players file, relative to the working directory.
+pub const VANISHED_PLAYERS_PATH: &str = "vanished.json";
+
+/// Table storing the vanished players in a database.
+const TABLE: &str = "vanished";
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    let vanished = PlayerSet::load(
+        VANISHED_PLAYERS_PATH,
+        TABLE,
+        game.resources.get::<Storage>().ok().as_deref(),
+    );
+    game.insert_resource(VanishedPlayers(vanished));
+    systems
+        .group::<Server>()
+        .add_system(update_vanished_players);
+    systems.add_system(save_vanished_players);
+}
+
+/// Resource storing the UUIDs of vanished players.
+pub struct VanishedPlayers(PlayerSet);
+
+impl VanishedPlayers {
+    pub fn contains(&self, uuid: Uuid) -> bool {
+        self.0.contains(uuid)
+    }
+
+    /// Records whether a player is vanished.
+    pub fn set(&mut self, uuid: Uuid, vanished: bool) {
+        if vanished {
+            self.0.insert(uuid);
+        } else {
+            self.0.remove(uuid);
+        }
+    }
+}
+
+fn save_vanished_players(game: &mut Game) -> SysResult {
+    let storage = game.resources.get::<Storage>().ok();
+    if let Err(e) = game
+        .resources
+        .get_mut::<VanishedPlayers>()?
+        .0
+        .save(storage.as_deref())
+    {
+        log::error!("Failed to save the vanished players: {:?}", e);
+    }
+    Ok(())
+}
+
+/// Migration importing `vanished.json` into a database.
+pub(crate) fn import_file(backend: &mut dyn Backend) -> anyhow::Result<()> {
+    PlayerSet::import_file(backend, VANISHED_PLAYERS_PATH.as_ref(), TABLE)
+}
+
+/// Hides or shows players who vanished or reappeared.
+fn update_vanished_players(game: &mut Game, server: &mut Server) -> SysResult {
+    for (player, (event, &client_id, &network_id, &uuid, name, &gamemode, profile, &position)) in
+        game.ecs
+            .query::<(
+                &VanishChangeEvent,
+                &ClientId,
+                &NetworkId,
+                &Uuid,
+                &Name,
+                &Gamemode,
+                &Vec<ProfileProperty>,
+                &Position,
+            )>()
+            .iter()
+    {
+        game.resources
+            .get_mut::<VanishedPlayers>()?
+            .set(uuid, event.vanished);
+
+        let others = server
+            .clients
+            .iter()
+            .filter(|client| client.uuid() != uuid && !client.sees_vanished());
+        if event.vanished {
+            log::info!("{} vanished", name);
+            for client in others {
+                if client.is_entity_loaded(network_id) {
+                    client.unload_entity(network_id);
+                }
+                client.remove_tablist_player(uuid);
+            }
+        } else {
+            log::info!("{} reappeared", name);
+            for client in others {
+                client.add_tablist_player(uuid, name.to_string(), profile, gamemode);
+            }
+
+            let entity_ref = game.ecs.entity(player)?;
+            let spawn_packet = entity_ref.get::<SpawnPacketSender>()?;
+            for &other_id in server
+                .chunk_subscriptions
+                .subscriptions_for(position.chunk())
+            {
+                match server.clients.get(other_id) {
+                    Some(client) if other_id != client_id && !client.sees_vanished() => {
+                        spawn_packet.send(&entity_ref, client)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -7,11 +7,12 @@ use ahash::AHashMap;
 use base::{ChunkPosition, Position};
 use common::{
     events::{ChunkLoadEvent, ViewUpdateEvent},
+    view::View,
     Game,
 };
 use ecs::{Entity, SysResult, SystemExecutor};
 
-use crate::{Client, ClientId, Server};
+use crate::{anti_xray, Client, ClientId, Server};
 
 pub fn register(_game: &mut Game, systems: &mut SystemExecutor<Game>) {
     systems
@@ -65,7 +66,7 @@ fn update_chunks(
     // Send chunks that are in the new view but not the old view.
     for &pos in &event.new_chunks {
         if let Some(chunk) = game.world.chunk_map().chunk_handle_at(pos) {
-            client.send_chunk(&chunk);
+            client.send_chunk(&chunk, anti_xray::chunk_obfuscation(game, pos));
         } else {
             waiting_chunks.insert(player, pos);
         }
@@ -76,7 +77,7 @@ fn update_chunks(
         client.unload_chunk(pos);
     }
 
-    spawn_client_if_needed(client, position);
+    client.spawn_when_ready(position);
 
     Ok(())
 }
@@ -89,20 +90,24 @@ fn send_loaded_chunks(game: &mut Game, server: &mut Server) -> SysResult {
             .waiting_chunks
             .drain_players_waiting_for(event.position)
         {
+            // The chunk may have left the player's view while
+            // loading, after they moved or lowered their view distance.
+            let still_visible = game
+                .ecs
+                .get::<View>(player)
+                .map_or(false, |view| view.contains(event.position));
+            if !still_visible {
+                continue;
+            }
             if let Ok(client_id) = game.ecs.get::<ClientId>(player) {
                 if let Some(client) = server.clients.get(*client_id) {
-                    client.send_chunk(&event.chunk);
-                    spawn_client_if_needed(client, *game.ecs.get::<Position>(player)?);
+                    client.send_chunk(
+                        &event.chunk,
+                        anti_xray::chunk_obfuscation(game, event.position),
+                    );
                 }
             }
         }
     }
     Ok(())
 }
-
-fn spawn_client_if_needed(client: &Client, pos: Position) {
-    if !client.knows_own_position() && client.known_chunks() >= 9 * 9 {
-        log::debug!("Sent all chunks to {}; now spawning", client.username());
-        client.update_own_position(pos);
-    }
-}
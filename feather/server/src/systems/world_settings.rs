@@ -0,0 +1,25 @@
+use common::{events::DifficultyChangeEvent, Game};
+use ecs::{SysResult, SystemExecutor};
+
+use crate::{ClientId, Server};
+
+pub fn register(systems: &mut SystemExecutor<Game>) {
+    systems
+        .group::<Server>()
+        .add_system(send_difficulty_changes);
+}
+
+/// Sends a world's new difficulty to the players in it.
+fn send_difficulty_changes(game: &mut Game, server: &mut Server) -> SysResult {
+    for (_, event) in game.ecs.query::<&DifficultyChangeEvent>().iter() {
+        for (player, &client_id) in game.ecs.query::<&ClientId>().iter() {
+            if game.world_of(player).name() != event.world {
+                continue;
+            }
+            if let Some(client) = server.clients.get(client_id) {
+                client.send_difficulty(event.difficulty);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Reports how long each tick takes on the `feather:tick_times`
+//! plugin channel, when `report_tick_times` is enabled.
+//!
+//! Clients receive the reports once they register the channel.
+//! The load test tool uses them to measure the server itself,
+//! rather than guessing tick times from the packets it receives.
+
+use std::{mem, time::Duration};
+
+use common::{
+    plugin_channels::{self, RegisteredChannels},
+    Game,
+};
+use ecs::{Entity, SysResult, SystemExecutor};
+
+/// Channel on which tick times are sent.
+///
+/// Each message holds the durations of the ticks since the
+/// previous one, as big-endian `u32`s in microseconds.
+pub const TICK_TIMES_CHANNEL: &str = "feather:tick_times";
+
+/// Number of ticks between reports.
+const REPORT_INTERVAL_TICKS: u64 = 20;
+
+/// Resource storing the durations of the ticks since the last report.
+/// Only present when tick times are reported.
+#[derive(Debug, Default)]
+pub struct TickTimes {
+    pending: Vec<Duration>,
+}
+
+impl TickTimes {
+    /// Records the duration of a tick.
+    pub fn record(&mut self, duration: Duration) {
+        self.pending.push(duration);
+    }
+}
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    game.insert_resource(TickTimes::default());
+    systems.add_system(send_tick_times);
+}
+
+fn send_tick_times(game: &mut Game) -> SysResult {
+    if game.tick_count % REPORT_INTERVAL_TICKS != 0 {
+        return Ok(());
+    }
+    let times = mem::take(&mut game.resources.get_mut::<TickTimes>()?.pending);
+    if times.is_empty() {
+        return Ok(());
+    }
+
+    let data = encode_tick_times(&times);
+    let receivers: Vec<Entity> = game
+        .ecs
+        .query::<&RegisteredChannels>()
+        .iter()
+        .filter(|(_, channels)| channels.contains(TICK_TIMES_CHANNEL))
+        .map(|(player, _)| player)
+        .collect();
+    for player in receivers {
+        plugin_channels::send_message(game, player, TICK_TIMES_CHANNEL, data.clone())?;
+    }
+    Ok(())
+}
+
+/// Encodes tick durations as sent on [`TICK_TIMES_CHANNEL`].
+fn encode_tick_times(times: &[Duration]) -> Vec<u8> {
+    times
+        .iter()
+        .flat_map(|time| (time.as_micros().min(u32::MAX as u128) as u32).to_be_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_times_are_sent_as_microseconds() {
+        let times = [Duration::from_micros(51_200), Duration::from_secs(1)];
+        assert_eq!(
+            encode_tick_times(&times),
+            [0, 0, 0xC8, 0x00, 0, 0x0F, 0x42, 0x40]
+        );
+    }
+}
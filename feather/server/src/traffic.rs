@@ -0,0 +1,141 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// Bytes and packets sent and received, as counted
+/// on the wire (after compression and encryption).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Traffic {
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub packets_in: u64,
+    pub packets_out: u64,
+}
+
+/// Traffic of all connections to the server.
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone, Default)]
+pub struct TrafficStats {
+    total: Arc<Counters>,
+}
+
+impl TrafficStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts counting the traffic of a new connection.
+    pub fn connection(&self) -> ConnectionTraffic {
+        ConnectionTraffic {
+            connection: Arc::new(Counters::default()),
+            total: Arc::clone(&self.total),
+        }
+    }
+
+    pub fn get(&self) -> Traffic {
+        self.total.get()
+    }
+}
+
+/// Traffic of one connection, also counted
+/// towards the server's [`TrafficStats`].
+///
+/// Can be cloned to create a new handle.
+#[derive(Clone)]
+pub struct ConnectionTraffic {
+    connection: Arc<Counters>,
+    total: Arc<Counters>,
+}
+
+impl ConnectionTraffic {
+    pub fn add_bytes_in(&self, bytes: usize) {
+        for counters in self.counters() {
+            counters.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn add_bytes_out(&self, bytes: usize) {
+        for counters in self.counters() {
+            counters
+                .bytes_out
+                .fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn add_packet_in(&self) {
+        for counters in self.counters() {
+            counters.packets_in.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn add_packet_out(&self) {
+        for counters in self.counters() {
+            counters.packets_out.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn get(&self) -> Traffic {
+        self.connection.get()
+    }
+
+    fn counters(&self) -> [&Counters; 2] {
+        [&self.connection, &self.total]
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    packets_in: AtomicU64,
+    packets_out: AtomicU64,
+}
+
+impl Counters {
+    fn get(&self) -> Traffic {
+        Traffic {
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            packets_in: self.packets_in.load(Ordering::Relaxed),
+            packets_out: self.packets_out.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connections_add_to_total() {
+        let stats = TrafficStats::new();
+        let first = stats.connection();
+        let second = stats.connection();
+
+        first.add_bytes_in(100);
+        first.add_packet_in();
+        second.add_bytes_out(50);
+        second.add_packet_out();
+        second.add_packet_out();
+
+        assert_eq!(
+            first.get(),
+            Traffic {
+                bytes_in: 100,
+                packets_in: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            stats.get(),
+            Traffic {
+                bytes_in: 100,
+                bytes_out: 50,
+                packets_in: 1,
+                packets_out: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Maintains `usercache.json`, which maps player
+//! names to UUIDs in the same format as vanilla.
+//!
+//! Entries are added when players log in (see
+//! `player_join`, which also detects name changes) and expire
+//! after a month.
+//!
+//! When a database is configured, entries are kept in
+//! its `user_cache` table instead of the file.
+
+use std::{fs, io, path::PathBuf, time::Duration};
+
+use ahash::{AHashMap, AHashSet};
+use chrono::{DateTime, FixedOffset, Utc};
+use common::Game;
+use ecs::{SysResult, SystemExecutor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
+
+use crate::storage::{Backend, Storage, Write};
+
+/// Path of the cache file, relative to the working directory.
+pub const USER_CACHE_PATH: &str = "usercache.json";
+
+/// Maximum number of entries written to the cache file.
+const MAX_ENTRIES: usize = 1000;
+
+/// Table storing the entries in a database, keyed by lowercase name.
+const TABLE: &str = "user_cache";
+
+/// How long an entry stays valid after it's updated.
+const EXPIRY: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+
+pub fn register(game: &mut Game, systems: &mut SystemExecutor<Game>) {
+    let loaded = match game.resources.get::<Storage>() {
+        Ok(storage) => UserCache::load_from_storage(&storage),
+        Err(_) => UserCache::load(USER_CACHE_PATH),
+    };
+    let cache = match loaded {
+        Ok(cache) => cache,
+        Err(e) => {
+            log::warn!("Failed to load {}: {:?}", USER_CACHE_PATH, e);
+            UserCache::new(USER_CACHE_PATH)
+        }
+    };
+    game.insert_resource(cache);
+    systems.add_system(save_user_cache);
+}
+
+/// Entry in `usercache.json`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    name: String,
+    uuid: Uuid,
+    #[serde(
+        serialize_with = "serialize_date",
+        deserialize_with = "deserialize_date"
+    )]
+    expires_on: DateTime<FixedOffset>,
+    /// Order of last use, used to keep the most recently
+    /// used entries. Not saved; entries are saved in this order.
+    #[serde(skip)]
+    last_used: u64,
+}
+
+/// Resource caching the UUIDs of player names.
+pub struct UserCache {
+    path: PathBuf,
+    /// Keyed by lowercase name.
+    entries: AHashMap<String, Entry>,
+    use_counter: u64,
+    dirty: bool,
+    /// Keys changed since the last save to a database.
+    changed: AHashSet<String>,
+}
+
+impl UserCache {
+    /// Creates an empty cache which saves to `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            entries: AHashMap::new(),
+            use_counter: 0,
+            dirty: false,
+            changed: AHashSet::new(),
+        }
+    }
+
+    /// Loads the cache from `path`. A missing
+    /// file results in an empty cache.
+    pub fn load(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let mut cache = Self::new(path);
+        let json = match fs::read_to_string(&cache.path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(cache),
+            Err(e) => return Err(e.into()),
+        };
+        cache.load_json(&json)?;
+        Ok(cache)
+    }
+
+    /// Loads the cache from a database.
+    pub fn load_from_storage(storage: &Storage) -> anyhow::Result<Self> {
+        let mut cache = Self::new(USER_CACHE_PATH);
+        for (key, mut entry) in storage.load::<Entry>(TABLE)? {
+            entry.last_used = cache.next_use();
+            cache.entries.insert(key, entry);
+        }
+        Ok(cache)
+    }
+
+    fn load_json(&mut self, json: &str) -> anyhow::Result<()> {
+        let entries: Vec<Entry> = serde_json::from_str(json)?;
+        // The file is ordered from most to least recently used.
+        for mut entry in entries.into_iter().rev() {
+            entry.last_used = self.next_use();
+            self.entries.insert(entry.name.to_lowercase(), entry);
+        }
+        Ok(())
+    }
+
+    fn to_json(&self) -> anyhow::Result<String> {
+        let mut entries: Vec<&Entry> = self.entries.values().collect();
+        entries.sort_unstable_by_key(|entry| std::cmp::Reverse(entry.last_used));
+        entries.truncate(MAX_ENTRIES);
+        Ok(serde_json::to_string(&entries)?)
+    }
+
+    /// Writes the cache to its file if it changed since the last save.
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        fs::write(&self.path, self.to_json()?)?;
+        self.dirty = false;
+        self.changed.clear();
+        Ok(())
+    }
+
+    /// Writes the entries changed since the last save to a database.
+    pub fn save_to_storage(&mut self, storage: &Storage) -> anyhow::Result<()> {
+        for key in self.changed.drain() {
+            match self.entries.get(&key) {
+                Some(entry) => storage.put(TABLE, &key, entry)?,
+                None => storage.remove(TABLE, &key),
+            }
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn next_use(&mut self) -> u64 {
+        self.use_counter += 1;
+        self.use_counter
+    }
+
+    /// Adds or refreshes the entry for a player.
+    ///
+    /// An existing entry with the same UUID but a
+    /// different name is replaced, since the player
+    /// has changed their name. Returns that previous name.
+    pub fn insert(&mut self, name: &str, uuid: Uuid, now: DateTime<Utc>) -> Option<String> {
+        let mut previous_name = None;
+        let changed = &mut self.changed;
+        self.entries.retain(|key, entry| {
+            let renamed = entry.uuid == uuid && entry.name != name;
+            if renamed {
+                previous_name = Some(entry.name.clone());
+                changed.insert(key.clone());
+            }
+            !renamed
+        });
+        let last_used = self.next_use();
+        self.changed.insert(name.to_lowercase());
+        self.entries.insert(
+            name.to_lowercase(),
+            Entry {
+                name: name.to_owned(),
+                uuid,
+                expires_on: (now + chrono::Duration::from_std(EXPIRY).unwrap()).into(),
+                last_used,
+            },
+        );
+        self.dirty = true;
+        previous_name
+    }
+
+    /// Gets the last known name of the player with the given UUID.
+    pub fn name_for_uuid(&self, uuid: Uuid) -> Option<&str> {
+        self.entries
+            .values()
+            .find(|entry| entry.uuid == uuid)
+            .map(|entry| entry.name.as_str())
+    }
+}
+
+fn serialize_date<S: Serializer>(
+    date: &DateTime<FixedOffset>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&date.format(DATE_FORMAT).to_string())
+}
+
+fn deserialize_date<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<FixedOffset>, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    DateTime::parse_from_str(&string, DATE_FORMAT).map_err(serde::de::Error::custom)
+}
+
+fn save_user_cache(game: &mut Game) -> SysResult {
+    let mut cache = game.resources.get_mut::<UserCache>()?;
+    match game.resources.get::<Storage>() {
+        Ok(storage) => cache.save_to_storage(&storage),
+        Err(_) => cache.save(),
+    }
+}
+
+/// Migration importing the entries of `usercache.json` into a database.
+pub(crate) fn import_file(backend: &mut dyn Backend) -> anyhow::Result<()> {
+    let cache = UserCache::load(USER_CACHE_PATH)?;
+    let batch = cache
+        .entries
+        .iter()
+        .map(|(key, entry)| {
+            Ok(Write::Put {
+                table: TABLE.to_owned(),
+                key: key.clone(),
+                value: serde_json::to_vec(entry)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    backend.apply(&batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"[{"name":"Notch","uuid":"069a79f4-44e9-4726-a5be-fca90e38aaf5","expiresOn":"2030-01-01 00:00:00 +0000"},{"name":"jeb_","uuid":"853c80ef-3c37-49fd-aa49-938b674adae6","expiresOn":"2020-01-01 00:00:00 +0000"}]"#;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .into()
+    }
+
+    fn notch() -> Uuid {
+        "069a79f4-44e9-4726-a5be-fca90e38aaf5".parse().unwrap()
+    }
+
+    #[test]
+    fn loads_vanilla_format() {
+        let mut cache = UserCache::new("usercache.json");
+        cache.load_json(JSON).unwrap();
+        assert_eq!(cache.name_for_uuid(notch()), Some("Notch"));
+        assert_eq!(cache.to_json().unwrap(), JSON);
+    }
+
+    #[test]
+    fn insert_replaces_old_name() {
+        let mut cache = UserCache::new("usercache.json");
+        cache.load_json(JSON).unwrap();
+        assert_eq!(
+            cache.insert("Notch2", notch(), now()),
+            Some("Notch".to_owned())
+        );
+        assert_eq!(cache.name_for_uuid(notch()), Some("Notch2"));
+        assert!(!cache.entries.contains_key("notch"));
+        assert!(cache.dirty);
+        assert!(cache.changed.contains("notch") && cache.changed.contains("notch2"));
+    }
+
+    #[test]
+    fn most_recently_used_is_saved_first() {
+        let mut cache = UserCache::new("usercache.json");
+        cache.load_json(JSON).unwrap();
+        cache.insert(
+            "jeb_",
+            "853c80ef-3c37-49fd-aa49-938b674adae6".parse().unwrap(),
+            now(),
+        );
+        let json = cache.to_json().unwrap();
+        assert!(json.starts_with(r#"[{"name":"jeb_""#));
+    }
+}
@@ -0,0 +1,318 @@
+//! `feather-server world inspect`: prints what is stored in
+//! a world directory without starting the server.
+
+use std::{
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use argh::FromArgs;
+use base::{
+    anvil::{
+        block_entity::BlockEntityData,
+        entity::{EntityData, EntityDataKind},
+        region::{self, RegionHandle, RegionPosition},
+    },
+    BlockId, BlockPosition, Chunk, ChunkPosition,
+};
+
+/// Inspects a world directory without starting the server.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "inspect")]
+pub struct Inspect {
+    /// the world directory.
+    #[argh(option, short = 'w', default = "PathBuf::from(\"world\")")]
+    world: PathBuf,
+    #[argh(subcommand)]
+    command: InspectCommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum InspectCommand {
+    Chunk(ChunkCommand),
+    Entities(EntitiesCommand),
+    Block(BlockCommand),
+    Validate(ValidateCommand),
+}
+
+/// Prints the palette of each section of a chunk
+/// and the chunk's block entities.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "chunk")]
+struct ChunkCommand {
+    /// chunk X coordinate.
+    #[argh(option)]
+    x: i32,
+    /// chunk Z coordinate.
+    #[argh(option)]
+    z: i32,
+}
+
+/// Lists the entities stored in a chunk, or in the
+/// whole world if no chunk is given.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "entities")]
+struct EntitiesCommand {
+    /// chunk X coordinate.
+    #[argh(option)]
+    x: Option<i32>,
+    /// chunk Z coordinate.
+    #[argh(option)]
+    z: Option<i32>,
+}
+
+/// Prints the block state at a position.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "block")]
+struct BlockCommand {
+    /// block X coordinate.
+    #[argh(option)]
+    x: i32,
+    /// block Y coordinate.
+    #[argh(option)]
+    y: i32,
+    /// block Z coordinate.
+    #[argh(option)]
+    z: i32,
+}
+
+/// Checks that every region file has a valid header
+/// and that every chunk in it can be loaded.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "validate")]
+struct ValidateCommand {}
+
+impl Inspect {
+    pub fn run(self) -> anyhow::Result<()> {
+        if !self.world.is_dir() {
+            bail!("world directory {} does not exist", self.world.display());
+        }
+        match self.command {
+            InspectCommand::Chunk(command) => {
+                dump_chunk(&self.world, ChunkPosition::new(command.x, command.z))
+            }
+            InspectCommand::Entities(command) => match (command.x, command.z) {
+                (Some(x), Some(z)) => list_entities(&self.world, ChunkPosition::new(x, z)),
+                (None, None) => list_all_entities(&self.world),
+                _ => bail!("both --x and --z must be given to select a chunk"),
+            },
+            InspectCommand::Block(command) => print_block(
+                &self.world,
+                BlockPosition::new(command.x, command.y, command.z),
+            ),
+            InspectCommand::Validate(_) => validate(&self.world),
+        }
+    }
+}
+
+type LoadedChunk = (Chunk, Vec<EntityData>, Vec<BlockEntityData>);
+
+fn open_region(world: &Path, region: RegionPosition) -> anyhow::Result<RegionHandle> {
+    region::load_region(&world.to_path_buf(), region)
+        .with_context(|| format!("failed to open region file {}", region.file_name()))
+}
+
+fn load_chunk(world: &Path, pos: ChunkPosition) -> anyhow::Result<LoadedChunk> {
+    let mut region = open_region(world, RegionPosition::from_chunk(pos))?;
+    load_from_region(&mut region, pos)
+}
+
+/// Loads a chunk, turning panics caused by
+/// corrupt data into errors.
+fn load_from_region(region: &mut RegionHandle, pos: ChunkPosition) -> anyhow::Result<LoadedChunk> {
+    match panic::catch_unwind(AssertUnwindSafe(|| region.load_chunk(pos))) {
+        Ok(result) => result.with_context(|| format!("failed to load chunk {}", pos)),
+        Err(payload) => bail!(
+            "panicked while loading chunk {}: {}",
+            pos,
+            utils::panic_message(&*payload)
+        ),
+    }
+}
+
+fn dump_chunk(world: &Path, pos: ChunkPosition) -> anyhow::Result<()> {
+    let (chunk, _, block_entities) = load_chunk(world, pos)?;
+    println!("Chunk {}", pos);
+
+    // The first and last sections only hold light.
+    for (index, section) in chunk.sections().iter().enumerate() {
+        let section = match section {
+            Some(section) => section,
+            None => continue,
+        };
+        let y = index as isize - 1;
+        println!(
+            "Section {} (y {}..{}): {} non-air blocks",
+            y,
+            y * 16,
+            y * 16 + 15,
+            section.non_air_blocks()
+        );
+        match section.blocks().palette() {
+            Some(palette) => {
+                for (i, block) in palette.as_slice().iter().enumerate() {
+                    println!("  {:>3}: {}", i, block_state(*block));
+                }
+            }
+            None => println!("  (global palette)"),
+        }
+    }
+
+    println!("Block entities: {}", block_entities.len());
+    for block_entity in &block_entities {
+        let base = &block_entity.base;
+        println!(
+            "  ({}, {}, {}) {:?}",
+            base.x, base.y, base.z, block_entity.kind
+        );
+    }
+    Ok(())
+}
+
+fn list_entities(world: &Path, pos: ChunkPosition) -> anyhow::Result<()> {
+    let (_, entities, _) = load_chunk(world, pos)?;
+    print_entities(pos, &entities);
+    Ok(())
+}
+
+fn list_all_entities(world: &Path) -> anyhow::Result<()> {
+    let mut total = 0;
+    for region in region_positions(world)? {
+        let mut region = open_region(world, region)?;
+        let chunks: Vec<_> = region.chunks().collect();
+        for pos in chunks {
+            match load_from_region(&mut region, pos) {
+                Ok((_, entities, _)) => {
+                    total += entities.len();
+                    if !entities.is_empty() {
+                        print_entities(pos, &entities);
+                    }
+                }
+                Err(e) => eprintln!("Skipping chunk {}: {:#}", pos, e),
+            }
+        }
+    }
+    println!("{} entities in total", total);
+    Ok(())
+}
+
+fn print_entities(pos: ChunkPosition, entities: &[EntityData]) {
+    println!("Chunk {}: {} entities", pos, entities.len());
+    for entity in entities {
+        let kind = EntityDataKind::from(entity);
+        match entity.base().map(|base| base.read_position()) {
+            Some(Ok(position)) => println!(
+                "  {:?} at ({:.2}, {:.2}, {:.2})",
+                kind, position.x, position.y, position.z
+            ),
+            _ => println!("  {:?} at unknown position", kind),
+        }
+    }
+}
+
+fn print_block(world: &Path, pos: BlockPosition) -> anyhow::Result<()> {
+    let (chunk, _, block_entities) = load_chunk(world, pos.chunk())?;
+    let block = chunk
+        .block_at(
+            pos.x.rem_euclid(16) as usize,
+            pos.y.max(0) as usize,
+            pos.z.rem_euclid(16) as usize,
+        )
+        .filter(|_| pos.y >= 0)
+        .with_context(|| format!("Y coordinate {} is outside the world", pos.y))?;
+    println!("{}", block_state(block));
+
+    let block_entity = block_entities
+        .iter()
+        .find(|block_entity| {
+            let base = &block_entity.base;
+            (base.x, base.y, base.z) == (pos.x, pos.y, pos.z)
+        })
+        .map(|block_entity| &block_entity.kind);
+    if let Some(kind) = block_entity {
+        println!("Block entity: {:?}", kind);
+    }
+    Ok(())
+}
+
+fn validate(world: &Path) -> anyhow::Result<()> {
+    let mut chunks = 0;
+    let mut problems = 0;
+    for region_pos in region_positions(world)? {
+        let file_name = region_pos.file_name();
+        let mut region = match open_region(world, region_pos) {
+            Ok(region) => region,
+            Err(e) => {
+                println!("{}: {:#}", file_name, e);
+                problems += 1;
+                continue;
+            }
+        };
+
+        let invalid = region.validate_header()?;
+        for (pos, error) in &invalid {
+            println!("{}: chunk {}: {}", file_name, pos, error);
+        }
+        problems += invalid.len();
+
+        let stored: Vec<_> = region.chunks().collect();
+        for pos in stored {
+            chunks += 1;
+            if invalid.iter().any(|(invalid, _)| *invalid == pos) {
+                continue;
+            }
+            if let Err(e) = load_from_region(&mut region, pos) {
+                println!("{}: {:#}", file_name, e);
+                problems += 1;
+            }
+        }
+    }
+
+    println!("Checked {} chunks: {} problems found", chunks, problems);
+    if problems > 0 {
+        bail!("the world contains invalid region files");
+    }
+    Ok(())
+}
+
+/// Finds the region files of a world.
+fn region_positions(world: &Path) -> anyhow::Result<Vec<RegionPosition>> {
+    let dir = world.join("region");
+    let mut regions = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let name = entry?.file_name();
+        if let Some(region) = name.to_str().and_then(RegionPosition::from_file_name) {
+            regions.push(region);
+        }
+    }
+    regions.sort_by_key(|region| region.file_name());
+    Ok(regions)
+}
+
+/// Formats a block state like vanilla commands do,
+/// e.g. `minecraft:oak_log[axis=y]`.
+fn block_state(block: BlockId) -> String {
+    let properties = block.to_properties_map();
+    if properties.is_empty() {
+        return block.identifier().to_owned();
+    }
+    let properties: Vec<_> = properties
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    format!("{}[{}]", block.identifier(), properties.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_states() {
+        assert_eq!(block_state(BlockId::stone()), "minecraft:stone");
+        assert_eq!(block_state(BlockId::oak_log()), "minecraft:oak_log[axis=y]");
+    }
+}
@@ -0,0 +1,373 @@
+//! Anti-xray: hides ores from clients which see through
+//! blocks, like x-ray mods and texture packs.
+//!
+//! Before a chunk is sent, blocks which aren't exposed, i.e.
+//! have no transparent block next to them, are obfuscated. A
+//! client can then only tell where an ore is once it's
+//! exposed. The server reveals the real blocks which become
+//! exposed when blocks change, e.g. as a player mines towards them.
+
+use std::collections::HashSet;
+
+use blocks::BlockId;
+use libcraft_core::{BlockPosition, ChunkPosition};
+use serde::Deserialize;
+
+use crate::{chunk::SECTION_HEIGHT, Chunk, ChunkSection, CHUNK_HEIGHT, CHUNK_WIDTH};
+
+/// How blocks which aren't exposed are obfuscated.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineMode {
+    /// Hidden blocks are replaced with the
+    /// first replacement block, e.g. stone.
+    Hide,
+    /// Hidden and replacement blocks are replaced with a
+    /// random hidden or replacement block, so that x-ray
+    /// shows fake ores everywhere. Costs more CPU time
+    /// and bandwidth than `Hide`.
+    Randomize,
+}
+
+/// Anti-xray settings of a world.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AntiXray {
+    pub mode: EngineMode,
+    /// Blocks to hide, e.g. ores. Block states are ignored.
+    pub hidden_blocks: Vec<BlockId>,
+    /// Blocks hidden blocks are replaced with. Must not be empty.
+    pub replacement_blocks: Vec<BlockId>,
+    /// Only blocks below this height are obfuscated.
+    pub max_block_height: usize,
+}
+
+/// Offsets of the blocks sharing a face with a block.
+const FACE_OFFSETS: [(i32, i32, i32); 6] = [
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, -1, 0),
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+/// Offsets of the chunks in [`Neighbors`].
+pub const NEIGHBOR_OFFSETS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// The chunks next to a chunk being obfuscated, at
+/// [`NEIGHBOR_OFFSETS`]. Used to check whether blocks on
+/// the chunk's edges are exposed. Missing chunks are
+/// treated as if they contained no transparent blocks.
+#[derive(Copy, Clone, Default)]
+pub struct Neighbors<'a>(pub [Option<&'a Chunk>; 4]);
+
+impl AntiXray {
+    /// Determines whether `block` is obfuscated when it isn't exposed.
+    pub fn is_obfuscated(&self, block: BlockId) -> bool {
+        contains_kind(&self.hidden_blocks, block)
+            || (self.mode == EngineMode::Randomize
+                && contains_kind(&self.replacement_blocks, block))
+    }
+
+    /// Returns a copy of the section at index `section` of `chunk`,
+    /// as in [`Chunk::sections`], with its blocks which aren't
+    /// exposed obfuscated. Returns `None` if no block was obfuscated.
+    pub fn obfuscate_section(
+        &self,
+        chunk: &Chunk,
+        neighbors: Neighbors,
+        section: usize,
+    ) -> Option<ChunkSection> {
+        let original = chunk.section(section)?;
+        // Index 0 is the section below the world.
+        let base_y = section.checked_sub(1)? * SECTION_HEIGHT;
+        if base_y >= self.max_block_height {
+            return None;
+        }
+        // Most sections have a palette, which tells
+        // us if none of their blocks are obfuscated.
+        if let Some(palette) = original.blocks().palette() {
+            if !palette
+                .as_slice()
+                .iter()
+                .any(|&block| self.is_obfuscated(block))
+            {
+                return None;
+            }
+        }
+
+        let height = SECTION_HEIGHT.min(self.max_block_height - base_y);
+        let mut obfuscated: Option<ChunkSection> = None;
+        for y in 0..height {
+            for z in 0..CHUNK_WIDTH {
+                for x in 0..CHUNK_WIDTH {
+                    let block = original.block_at(x, y, z).unwrap_or_default();
+                    if !self.is_obfuscated(block) || is_exposed(chunk, neighbors, x, base_y + y, z)
+                    {
+                        continue;
+                    }
+                    let replacement = self.replacement(chunk.position(), x, base_y + y, z);
+                    if replacement != block {
+                        obfuscated
+                            .get_or_insert_with(|| original.clone())
+                            .set_block_at(x, y, z, replacement);
+                    }
+                }
+            }
+        }
+        obfuscated
+    }
+
+    /// Returns the blocks which were hidden and are exposed after
+    /// the blocks at `changed` changed, along with their real block.
+    ///
+    /// These are the obfuscated blocks next to a changed block
+    /// which is now transparent, and which aren't next to any other
+    /// transparent block. `block_at` gets the current block at a
+    /// position, or `None` if it isn't loaded.
+    pub fn revealed_blocks(
+        &self,
+        changed: impl IntoIterator<Item = BlockPosition>,
+        block_at: impl Fn(BlockPosition) -> Option<BlockId>,
+    ) -> Vec<(BlockPosition, BlockId)> {
+        let changed: HashSet<BlockPosition> = changed.into_iter().collect();
+        let is_transparent = |pos| block_at(pos).map_or(false, |block| !block.is_opaque());
+
+        let mut revealed = Vec::new();
+        let mut checked = HashSet::new();
+        for &pos in &changed {
+            if !is_transparent(pos) {
+                continue;
+            }
+            for neighbor in faces(pos) {
+                if neighbor.y < 0
+                    || neighbor.y as usize >= self.max_block_height
+                    || changed.contains(&neighbor)
+                    || !checked.insert(neighbor)
+                {
+                    continue;
+                }
+                let block = match block_at(neighbor) {
+                    Some(block) => block,
+                    None => continue,
+                };
+                // Blocks sent as themselves were never hidden,
+                // nor were blocks exposed before the change.
+                let was_hidden = self.is_obfuscated(block)
+                    && self.replacement_at(neighbor) != block
+                    && faces(neighbor)
+                        .all(|adjacent| changed.contains(&adjacent) || !is_transparent(adjacent));
+                if was_hidden {
+                    revealed.push((neighbor, block));
+                }
+            }
+        }
+        revealed
+    }
+
+    /// Chooses the block an obfuscated block is sent as.
+    ///
+    /// Randomized blocks only depend on their position,
+    /// so that sending a chunk again gives the same result.
+    fn replacement_at(&self, pos: BlockPosition) -> BlockId {
+        let width = CHUNK_WIDTH as i32;
+        self.replacement(
+            pos.chunk(),
+            pos.x.rem_euclid(width) as usize,
+            pos.y as usize,
+            pos.z.rem_euclid(width) as usize,
+        )
+    }
+
+    fn replacement(&self, chunk: ChunkPosition, x: usize, y: usize, z: usize) -> BlockId {
+        match self.mode {
+            EngineMode::Hide => self.replacement_blocks[0],
+            EngineMode::Randomize => {
+                let x = chunk.x as i64 * CHUNK_WIDTH as i64 + x as i64;
+                let z = chunk.z as i64 * CHUNK_WIDTH as i64 + z as i64;
+                let hash = mix((x as u64) ^ mix((y as u64) ^ mix(z as u64)));
+                let count = self.hidden_blocks.len() + self.replacement_blocks.len();
+                let index = (hash % count as u64) as usize;
+                match self.hidden_blocks.get(index) {
+                    Some(&block) => block,
+                    None => self.replacement_blocks[index - self.hidden_blocks.len()],
+                }
+            }
+        }
+    }
+}
+
+fn faces(pos: BlockPosition) -> impl Iterator<Item = BlockPosition> {
+    FACE_OFFSETS
+        .iter()
+        .map(move |(dx, dy, dz)| BlockPosition::new(pos.x + dx, pos.y + dy, pos.z + dz))
+}
+
+fn contains_kind(blocks: &[BlockId], block: BlockId) -> bool {
+    blocks.iter().any(|b| b.kind() == block.kind())
+}
+
+/// Determines whether the block at `x, y, z` in `chunk`
+/// has a transparent block next to it.
+fn is_exposed(chunk: &Chunk, neighbors: Neighbors, x: usize, y: usize, z: usize) -> bool {
+    let last = CHUNK_WIDTH - 1;
+    let [west, east, north, south] = neighbors.0;
+    let adjacent = [
+        if x == 0 {
+            west.and_then(|c| c.block_at(last, y, z))
+        } else {
+            chunk.block_at(x - 1, y, z)
+        },
+        if x == last {
+            east.and_then(|c| c.block_at(0, y, z))
+        } else {
+            chunk.block_at(x + 1, y, z)
+        },
+        if z == 0 {
+            north.and_then(|c| c.block_at(x, y, last))
+        } else {
+            chunk.block_at(x, y, z - 1)
+        },
+        if z == last {
+            south.and_then(|c| c.block_at(x, y, 0))
+        } else {
+            chunk.block_at(x, y, z + 1)
+        },
+        y.checked_sub(1).and_then(|y| chunk.block_at(x, y, z)),
+        if y + 1 < CHUNK_HEIGHT {
+            chunk.block_at(x, y + 1, z)
+        } else {
+            None
+        },
+    ];
+    adjacent.iter().flatten().any(|block| !block.is_opaque())
+}
+
+/// The SplitMix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anti_xray(mode: EngineMode) -> AntiXray {
+        AntiXray {
+            mode,
+            hidden_blocks: vec![BlockId::diamond_ore()],
+            replacement_blocks: vec![BlockId::stone()],
+            max_block_height: 64,
+        }
+    }
+
+    fn stone_chunk() -> Chunk {
+        let mut chunk = Chunk::new(ChunkPosition::new(0, 0));
+        for section in 1..=4 {
+            chunk.fill_section(section, BlockId::stone());
+        }
+        chunk
+    }
+
+    #[test]
+    fn hides_ores_which_arent_exposed() {
+        let mut chunk = stone_chunk();
+        chunk.set_block_at(8, 10, 8, BlockId::diamond_ore());
+        chunk.set_block_at(8, 20, 8, BlockId::diamond_ore());
+        chunk.set_block_at(8, 21, 8, BlockId::air());
+
+        let anti_xray = anti_xray(EngineMode::Hide);
+        let section = anti_xray
+            .obfuscate_section(&chunk, Neighbors::default(), 1)
+            .unwrap();
+        assert_eq!(section.block_at(8, 10, 8), Some(BlockId::stone()));
+
+        // The second ore is exposed to air.
+        assert!(anti_xray
+            .obfuscate_section(&chunk, Neighbors::default(), 2)
+            .is_none());
+    }
+
+    #[test]
+    fn checks_neighboring_chunks() {
+        let mut chunk = stone_chunk();
+        chunk.set_block_at(0, 10, 8, BlockId::diamond_ore());
+        let neighbor = Chunk::new(ChunkPosition::new(-1, 0));
+
+        let anti_xray = anti_xray(EngineMode::Hide);
+        assert!(anti_xray
+            .obfuscate_section(&chunk, Neighbors([Some(&neighbor), None, None, None]), 1)
+            .is_none());
+        assert!(anti_xray
+            .obfuscate_section(&chunk, Neighbors::default(), 1)
+            .is_some());
+    }
+
+    #[test]
+    fn ignores_blocks_above_max_height() {
+        let mut chunk = stone_chunk();
+        chunk.set_block_at(8, 60, 8, BlockId::diamond_ore());
+
+        let mut anti_xray = anti_xray(EngineMode::Hide);
+        anti_xray.max_block_height = 56;
+        assert!(anti_xray
+            .obfuscate_section(&chunk, Neighbors::default(), 4)
+            .is_none());
+    }
+
+    #[test]
+    fn randomizes_deterministically() {
+        let chunk = stone_chunk();
+        let anti_xray = anti_xray(EngineMode::Randomize);
+        let first = anti_xray
+            .obfuscate_section(&chunk, Neighbors::default(), 1)
+            .unwrap();
+        let second = anti_xray
+            .obfuscate_section(&chunk, Neighbors::default(), 1)
+            .unwrap();
+        let mut ores = 0;
+        for y in 0..SECTION_HEIGHT {
+            for z in 0..CHUNK_WIDTH {
+                for x in 0..CHUNK_WIDTH {
+                    assert_eq!(first.block_at(x, y, z), second.block_at(x, y, z));
+                    if first.block_at(x, y, z) == Some(BlockId::diamond_ore()) {
+                        ores += 1;
+                    }
+                }
+            }
+        }
+        assert!(ores > 0);
+    }
+
+    #[test]
+    fn reveals_blocks_exposed_by_changes() {
+        // Stone with two ores; the block between them was mined.
+        let mined = BlockPosition::new(0, 10, 0);
+        let ores = [BlockPosition::new(1, 10, 0), BlockPosition::new(-1, 10, 0)];
+        let air = BlockPosition::new(-1, 11, 0);
+        let block_at = |pos: BlockPosition| {
+            Some(if pos == mined || pos == air {
+                BlockId::air()
+            } else if ores.contains(&pos) {
+                BlockId::diamond_ore()
+            } else {
+                BlockId::stone()
+            })
+        };
+
+        // Stone is sent as itself, and the second ore
+        // was already exposed to air.
+        let anti_xray = anti_xray(EngineMode::Hide);
+        assert_eq!(
+            anti_xray.revealed_blocks(vec![mined], block_at),
+            vec![(ores[0], BlockId::diamond_ore())]
+        );
+
+        // Nothing is exposed by placing a block.
+        let placed = BlockPosition::new(2, 10, 0);
+        assert!(anti_xray.revealed_blocks(vec![placed], block_at).is_empty());
+    }
+}
@@ -1,13 +1,15 @@
-use std::usize;
+use std::{sync::Arc, usize};
 
 use ::blocks::BlockId;
 use generated::Biome;
-
-use crate::ChunkPosition;
+use libcraft_core::ChunkPosition;
+use parking_lot::RwLock;
 
 /// The number of bits used for each block
-/// in the global palette.
-pub const GLOBAL_BITS_PER_BLOCK: u8 = 14;
+/// in the global palette. Must fit every
+/// vanilla block state ID, as the client
+/// decodes sections with this size.
+pub const GLOBAL_BITS_PER_BLOCK: u8 = 15;
 
 /// The minimum bits per block allowed when
 /// using a section palette.
@@ -52,6 +54,10 @@ pub use light::LightStore;
 pub use packed_array::PackedArray;
 pub use palette::Palette;
 
+/// A chunk shared between the world and
+/// the packets which send it to clients.
+pub type ChunkHandle = Arc<RwLock<Chunk>>;
+
 /// A 16x256x16 chunk of blocks plus associated
 /// light, biome, and heightmap data.
 /// Consists of 16 `ChunkSection`s.
@@ -1,4 +1,3 @@
-use crate::DEFAULT_NAMESPACE;
 use serde::{de, Deserialize, Serialize};
 use smartstring::{LazyCompact, SmartString};
 use std::{
@@ -6,6 +5,9 @@ use std::{
     str::FromStr,
 };
 
+/// The default namespace for resource locations (NamespacedIds).
+pub const DEFAULT_NAMESPACE: &str = "minecraft";
+
 /// A namespaced identifier, also known as a "resource location"
 /// in Forge. See https://minecraft.gamepedia.com/Namespaced_ID.
 ///
@@ -0,0 +1,133 @@
+//! The NBT tags of items, as stored in
+//! world saves and sent in item slots.
+
+use generated::{Enchantment, Item, ItemStack, ItemStackMeta};
+use serde::{Deserialize, Serialize};
+
+/// Represents NBT tags on an item.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ItemNbt {
+    #[serde(rename = "Damage")]
+    pub damage: Option<i32>,
+    #[serde(rename = "display")]
+    pub display: Option<ItemDisplayNbt>,
+    #[serde(
+        rename = "Enchantments",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub enchantments: Vec<EnchantmentNbt>,
+    #[serde(
+        rename = "StoredEnchantments",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub stored_enchantments: Vec<EnchantmentNbt>,
+    /// ID of the map shown by a filled map.
+    #[serde(rename = "map")]
+    pub map: Option<i32>,
+}
+
+/// The `display` compound of an item's NBT.
+#[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ItemDisplayNbt {
+    /// JSON text component.
+    #[serde(rename = "Name")]
+    pub name: Option<String>,
+    /// JSON text components, one per line.
+    #[serde(rename = "Lore", default, skip_serializing_if = "Vec::is_empty")]
+    pub lore: Vec<String>,
+}
+
+/// An entry in an item's `Enchantments` list.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnchantmentNbt {
+    pub id: String,
+    #[serde(rename = "lvl")]
+    pub level: i16,
+}
+
+impl ItemNbt {
+    /// Create an `ItemStack` of the specified item and amount, setting any nbt present.
+    pub fn item_stack(nbt: &Option<Self>, item: Item, count: u8) -> ItemStack {
+        ItemStack {
+            count: count as u32,
+            item,
+            damage: nbt.as_ref().map(|n| n.damage).flatten().map(|x| x as u32),
+            meta: nbt.as_ref().and_then(ItemNbt::meta),
+        }
+    }
+
+    /// Returns the `ItemStackMeta` described by these tags,
+    /// or `None` if they contain no display data, enchantments or map ID.
+    fn meta(&self) -> Option<ItemStackMeta> {
+        let display = self.display.clone().unwrap_or_default();
+        let meta = ItemStackMeta {
+            display_name: display.name,
+            lore: display.lore,
+            enchantments: self.enchantments.iter().map(Enchantment::from).collect(),
+            stored_enchantments: self
+                .stored_enchantments
+                .iter()
+                .map(Enchantment::from)
+                .collect(),
+            map_id: self.map,
+        };
+        if meta == ItemStackMeta::default() {
+            None
+        } else {
+            Some(meta)
+        }
+    }
+}
+
+impl<S> From<S> for ItemNbt
+where
+    S: std::borrow::Borrow<ItemStack>,
+{
+    fn from(s: S) -> Self {
+        let stack = s.borrow();
+        let meta = stack.meta.clone().unwrap_or_default();
+        let display = if meta.display_name.is_none() && meta.lore.is_empty() {
+            None
+        } else {
+            Some(ItemDisplayNbt {
+                name: meta.display_name,
+                lore: meta.lore,
+            })
+        };
+        Self {
+            damage: stack.damage.map(|d| d as i32),
+            display,
+            enchantments: meta
+                .enchantments
+                .into_iter()
+                .map(EnchantmentNbt::from)
+                .collect(),
+            stored_enchantments: meta
+                .stored_enchantments
+                .into_iter()
+                .map(EnchantmentNbt::from)
+                .collect(),
+            map: meta.map_id,
+        }
+    }
+}
+
+impl From<&EnchantmentNbt> for Enchantment {
+    fn from(enchantment: &EnchantmentNbt) -> Self {
+        Self {
+            id: enchantment.id.clone(),
+            level: enchantment.level.max(0) as u16,
+        }
+    }
+}
+
+impl From<Enchantment> for EnchantmentNbt {
+    fn from(enchantment: Enchantment) -> Self {
+        Self {
+            id: enchantment.id,
+            level: enchantment.level as i16,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Data types shared by Feather and its protocol implementation:
+//! * The chunk data structure and anti-xray obfuscation of it
+//! * Entity metadata
+//! * Item NBT tags
+//! * Namespaced IDs
+//!
+//! `feather-base` reexports these along with the rest of
+//! Feather's core types. This crate lets `feather-protocol`
+//! be used without depending on `feather-base`.
+
+use num_derive::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+pub mod anti_xray;
+pub mod chunk;
+pub mod id;
+pub mod item_nbt;
+pub mod metadata;
+
+pub use chunk::{Chunk, ChunkHandle, ChunkSection, CHUNK_HEIGHT, CHUNK_WIDTH};
+#[doc(inline)]
+pub use id::NamespacedId;
+#[doc(inline)]
+pub use metadata::EntityMetadata;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, FromPrimitive, ToPrimitive)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+/// A profile property, which stores metadata
+/// for some player's account. This is usually
+/// used to store skin data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: String,
+}
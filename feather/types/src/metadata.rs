@@ -2,10 +2,12 @@
 //! metadata format. See https://wiki.vg/Entity_metadata
 //! for the specification.
 
-use crate::{BlockPosition, Direction};
+use crate::Direction;
 use bitflags::bitflags;
 use generated::ItemStack;
-use std::collections::BTreeMap;
+use libcraft_core::BlockPosition;
+use libcraft_particles::ParticleKind;
+use std::collections::{BTreeMap, BTreeSet};
 use uuid::Uuid;
 
 pub type OptUuid = Option<Uuid>;
@@ -24,6 +26,16 @@ pub const META_INDEX_ITEM_SLOT: u8 = 6;
 
 pub const META_INDEX_FALLING_BLOCK_SPAWN_POSITION: u8 = 7;
 
+pub const META_INDEX_LIVING_POTION_COLOR: u8 = 9;
+pub const META_INDEX_LIVING_POTION_AMBIENT: u8 = 10;
+
+pub const META_INDEX_AREA_EFFECT_CLOUD_RADIUS: u8 = 7;
+pub const META_INDEX_AREA_EFFECT_CLOUD_COLOR: u8 = 8;
+pub const META_INDEX_AREA_EFFECT_CLOUD_IGNORE_RADIUS: u8 = 9;
+pub const META_INDEX_AREA_EFFECT_CLOUD_PARTICLE: u8 = 10;
+
+pub const META_INDEX_PLAYER_DISPLAYED_SKIN_PARTS: u8 = 16;
+
 bitflags! {
     pub struct EntityBitMask: u8 {
         const ON_FIRE = 0x01;
@@ -53,8 +65,9 @@ pub enum MetaEntry {
     OptUuid(OptUuid),
     OptBlockId(Option<i32>),
     Nbt(nbt::Blob),
-    Particle,
-    VillagerData,
+    Particle(ParticleKind),
+    /// Villager type, profession and level.
+    VillagerData(i32, i32, i32),
     OptVarInt(OptVarInt),
     Pose(i32),
 }
@@ -77,8 +90,8 @@ impl MetaEntry {
             MetaEntry::OptUuid(_) => 12,
             MetaEntry::OptBlockId(_) => 13,
             MetaEntry::Nbt(_) => 14,
-            MetaEntry::Particle => 15,
-            MetaEntry::VillagerData => 16,
+            MetaEntry::Particle(_) => 15,
+            MetaEntry::VillagerData(_, _, _) => 16,
             MetaEntry::OptVarInt(_) => 17,
             MetaEntry::Pose(_) => 18,
         }
@@ -89,6 +102,12 @@ pub trait ToMetaEntry {
     fn to_meta_entry(&self) -> MetaEntry;
 }
 
+impl ToMetaEntry for MetaEntry {
+    fn to_meta_entry(&self) -> MetaEntry {
+        self.clone()
+    }
+}
+
 impl ToMetaEntry for u8 {
     fn to_meta_entry(&self) -> MetaEntry {
         MetaEntry::Byte(*self as i8)
@@ -149,15 +168,28 @@ impl ToMetaEntry for OptVarInt {
     }
 }
 
+impl ToMetaEntry for ParticleKind {
+    fn to_meta_entry(&self) -> MetaEntry {
+        MetaEntry::Particle(*self)
+    }
+}
+
+/// An entity's metadata.
+///
+/// Tracks which indices changed since the last call
+/// to `take_changes`, so only those need to be sent
+/// to clients which already have the entity.
 #[derive(Clone, Debug)]
 pub struct EntityMetadata {
     pub values: BTreeMap<u8, MetaEntry>,
+    dirty: BTreeSet<u8>,
 }
 
 impl EntityMetadata {
     pub fn new() -> Self {
         Self {
             values: BTreeMap::new(),
+            dirty: BTreeSet::new(),
         }
     }
 
@@ -174,14 +206,23 @@ impl EntityMetadata {
 
     pub fn with_many(mut self, values: &[(u8, MetaEntry)]) -> Self {
         for val in values {
-            self.values.insert(val.0, val.1.clone());
+            self.insert(val.0, val.1.clone());
         }
 
         self
     }
 
+    /// Sets the value at `index`. The index is marked
+    /// as changed if the value differs from the old one.
     pub fn set(&mut self, index: u8, entry: impl ToMetaEntry) {
-        self.values.insert(index, entry.to_meta_entry());
+        self.insert(index, entry.to_meta_entry());
+    }
+
+    fn insert(&mut self, index: u8, entry: MetaEntry) {
+        if self.values.get(&index) != Some(&entry) {
+            self.values.insert(index, entry);
+            self.dirty.insert(index);
+        }
     }
 
     pub fn with(mut self, index: u8, entry: impl ToMetaEntry) -> Self {
@@ -196,6 +237,24 @@ impl EntityMetadata {
     pub fn iter(&self) -> impl Iterator<Item = (u8, &MetaEntry)> {
         self.values.iter().map(|(key, entry)| (*key, entry))
     }
+
+    /// Returns whether any index changed since
+    /// the last call to `take_changes`.
+    pub fn has_changes(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Returns the entries which changed since the last call
+    /// to this function, and resets the change tracking.
+    pub fn take_changes(&mut self) -> EntityMetadata {
+        let mut changes = EntityMetadata::new();
+        for index in std::mem::take(&mut self.dirty) {
+            if let Some(entry) = self.values.get(&index) {
+                changes.values.insert(index, entry.clone());
+            }
+        }
+        changes
+    }
 }
 
 impl Default for EntityMetadata {
@@ -203,3 +262,31 @@ impl Default for EntityMetadata {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_changes_returns_only_changed_entries() {
+        let mut meta = EntityMetadata::entity_base();
+        assert_eq!(meta.take_changes().values.len(), 6);
+        assert!(!meta.has_changes());
+
+        meta.set(META_INDEX_AIR, 0i32);
+        assert!(!meta.has_changes());
+
+        meta.set(META_INDEX_AIR, 300i32);
+        meta.set(META_INDEX_NO_GRAVITY, true);
+        let changes = meta.take_changes();
+        assert_eq!(
+            changes.iter().collect::<Vec<_>>(),
+            vec![
+                (META_INDEX_AIR, &MetaEntry::VarInt(300)),
+                (META_INDEX_NO_GRAVITY, &MetaEntry::Boolean(true)),
+            ]
+        );
+        assert!(!meta.has_changes());
+        assert_eq!(meta.values.len(), 6);
+    }
+}
@@ -1,5 +1,7 @@
 //! Assorted utilities not directly related to Minecraft/Feather.
 
+use std::any::Any;
+
 /// Swap-removes an item from a vector by equality.
 pub fn vec_remove_item<T: PartialEq>(vec: &mut Vec<T>, item: &T) {
     let index = vec.iter().position(|x| x == item);
@@ -7,3 +9,75 @@ pub fn vec_remove_item<T: PartialEq>(vec: &mut Vec<T>, item: &T) {
         vec.swap_remove(index);
     }
 }
+
+/// Port of `java.util.Random`, for algorithms which
+/// have to produce the same results as Java code.
+#[derive(Debug, Clone)]
+pub struct JavaRandom {
+    seed: i64,
+}
+
+impl JavaRandom {
+    const MULTIPLIER: i64 = 0x5DEECE66D;
+    const MASK: i64 = (1 << 48) - 1;
+
+    pub fn new(seed: i64) -> Self {
+        Self {
+            seed: (seed ^ Self::MULTIPLIER) & Self::MASK,
+        }
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        self.seed = (self.seed.wrapping_mul(Self::MULTIPLIER).wrapping_add(0xB)) & Self::MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    pub fn next_int(&mut self) -> i32 {
+        self.next(32)
+    }
+
+    /// Returns a value in `0..bound`.
+    ///
+    /// # Panics
+    /// Panics if `bound` is not positive.
+    pub fn next_int_bounded(&mut self, bound: i32) -> i32 {
+        assert!(bound > 0, "bound must be positive");
+        if bound & -bound == bound {
+            return ((bound as i64 * self.next(31) as i64) >> 31) as i32;
+        }
+        loop {
+            let bits = self.next(31);
+            let value = bits % bound;
+            if bits.wrapping_sub(value).wrapping_add(bound - 1) >= 0 {
+                return value;
+            }
+        }
+    }
+
+    pub fn next_float(&mut self) -> f32 {
+        self.next(24) as f32 / (1 << 24) as f32
+    }
+}
+
+/// Gets the message of a panic caught with `catch_unwind`.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn java_random_matches_java() {
+        let mut random = JavaRandom::new(0);
+        assert_eq!(random.next_int(), -1155484576);
+        assert_eq!(random.next_int(), -723955400);
+    }
+}
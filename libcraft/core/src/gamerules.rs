@@ -1,82 +1,141 @@
 //! Data sourced from: https://minecraft.gamepedia.com/Game_rule
 
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-/// All game rules.
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct GameRules {
-    announce_advancements: bool,
-    command_block_output: bool,
-    disable_elytra_movement_check: bool,
-    disable_raids: bool,
-    do_daylight_cycle: bool,
-    do_entity_drops: bool,
-    do_fire_tick: bool,
-    do_insomnia: bool,
-    do_immediate_respawn: bool,
-    do_limited_crafting: bool,
-    do_mob_loot: bool,
-    do_mob_spawning: bool,
-    do_patrol_spawning: bool,
-    do_tile_drops: bool,
-    do_trader_spawning: bool,
-    do_weather_cycle: bool,
-    drowning_damage: bool,
-    fall_damage: bool,
-    fire_damage: bool,
-    forgive_dead_players: bool,
-    keep_inventory: bool,
-    log_admin_commands: bool,
-    max_command_chain_length: u32,
-    max_entity_cramming: u32,
-    mob_griefing: bool,
-    natural_regeneration: bool,
-    random_tick_speed: u32,
-    reduced_debug_info: bool,
-    send_command_feedback: bool,
-    show_death_messages: bool,
-    spawn_radius: u32,
-    spectators_generate_chunks: bool,
-    universal_anger: bool,
+macro_rules! gamerules {
+    {$($field:ident($name:literal): $ty:ty = $default:literal),*$(,)*} => {
+        /// All game rules.
+        #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(rename_all = "camelCase", default)]
+        pub struct GameRules {
+            $(
+                pub $field: $ty,
+            )*
+        }
+
+        impl Default for GameRules {
+            fn default() -> Self {
+                Self {
+                    $(
+                        $field: $default,
+                    )*
+                }
+            }
+        }
+
+        impl GameRules {
+            /// Names of all game rules, as used by
+            /// the `/gamerule` command.
+            pub const NAMES: &'static [&'static str] = &[$($name),*];
+
+            /// Gets the value of the game rule called `name`,
+            /// formatted as in the `/gamerule` command.
+            pub fn get(&self, name: &str) -> Option<String> {
+                match name {
+                    $(
+                        $name => Some(self.$field.to_string()),
+                    )*
+                    _ => None,
+                }
+            }
+
+            /// Sets the game rule called `name`
+            /// by parsing `value`.
+            pub fn set(&mut self, name: &str, value: &str) -> Result<(), GameRuleError> {
+                match name {
+                    $(
+                        $name => {
+                            self.$field = value.parse().map_err(|_| {
+                                GameRuleError::InvalidValue(value.to_owned())
+                            })?;
+                            Ok(())
+                        }
+                    )*
+                    _ => Err(GameRuleError::UnknownRule(name.to_owned())),
+                }
+            }
+        }
+    };
+}
+
+gamerules! {
+    announce_advancements("announceAdvancements"): bool = true,
+    command_block_output("commandBlockOutput"): bool = true,
+    disable_elytra_movement_check("disableElytraMovementCheck"): bool = false,
+    disable_raids("disableRaids"): bool = false,
+    do_daylight_cycle("doDaylightCycle"): bool = true,
+    do_entity_drops("doEntityDrops"): bool = true,
+    do_fire_tick("doFireTick"): bool = true,
+    do_insomnia("doInsomnia"): bool = true,
+    do_immediate_respawn("doImmediateRespawn"): bool = false,
+    do_limited_crafting("doLimitedCrafting"): bool = false,
+    do_mob_loot("doMobLoot"): bool = true,
+    do_mob_spawning("doMobSpawning"): bool = true,
+    do_patrol_spawning("doPatrolSpawning"): bool = true,
+    do_tile_drops("doTileDrops"): bool = true,
+    do_trader_spawning("doTraderSpawning"): bool = true,
+    do_weather_cycle("doWeatherCycle"): bool = true,
+    drowning_damage("drowningDamage"): bool = true,
+    fall_damage("fallDamage"): bool = true,
+    fire_damage("fireDamage"): bool = true,
+    forgive_dead_players("forgiveDeadPlayers"): bool = true,
+    keep_inventory("keepInventory"): bool = false,
+    log_admin_commands("logAdminCommands"): bool = true,
+    max_command_chain_length("maxCommandChainLength"): u32 = 65536,
+    max_entity_cramming("maxEntityCramming"): u32 = 24,
+    mob_griefing("mobGriefing"): bool = true,
+    natural_regeneration("naturalRegeneration"): bool = true,
+    random_tick_speed("randomTickSpeed"): u32 = 3,
+    reduced_debug_info("reducedDebugInfo"): bool = false,
+    send_command_feedback("sendCommandFeedback"): bool = true,
+    show_death_messages("showDeathMessages"): bool = true,
+    spawn_radius("spawnRadius"): u32 = 10,
+    spectators_generate_chunks("spectatorsGenerateChunks"): bool = true,
+    universal_anger("universalAnger"): bool = false,
 }
 
-impl Default for GameRules {
-    fn default() -> Self {
-        Self {
-            announce_advancements: true,
-            command_block_output: true,
-            disable_elytra_movement_check: false,
-            disable_raids: false,
-            do_daylight_cycle: true,
-            do_entity_drops: true,
-            do_fire_tick: true,
-            do_insomnia: true,
-            do_immediate_respawn: false,
-            do_limited_crafting: false,
-            do_mob_loot: true,
-            do_mob_spawning: true,
-            do_patrol_spawning: true,
-            do_tile_drops: true,
-            do_trader_spawning: true,
-            do_weather_cycle: true,
-            drowning_damage: true,
-            fall_damage: true,
-            fire_damage: true,
-            forgive_dead_players: true,
-            keep_inventory: false,
-            log_admin_commands: true,
-            max_command_chain_length: 65536,
-            max_entity_cramming: 24,
-            mob_griefing: true,
-            natural_regeneration: true,
-            random_tick_speed: 3,
-            reduced_debug_info: false,
-            send_command_feedback: true,
-            show_death_messages: true,
-            spawn_radius: 10,
-            spectators_generate_chunks: true,
-            universal_anger: false,
+/// Error returned when setting a game rule fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameRuleError {
+    UnknownRule(String),
+    InvalidValue(String),
+}
+
+impl fmt::Display for GameRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameRuleError::UnknownRule(name) => write!(f, "unknown game rule '{}'", name),
+            GameRuleError::InvalidValue(value) => write!(f, "invalid value '{}'", value),
         }
     }
 }
+
+impl std::error::Error for GameRuleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_and_set_by_name() {
+        let mut rules = GameRules::default();
+        assert_eq!(rules.get("keepInventory").as_deref(), Some("false"));
+
+        rules.set("keepInventory", "true").unwrap();
+        rules.set("randomTickSpeed", "10").unwrap();
+        assert!(rules.keep_inventory);
+        assert_eq!(rules.random_tick_speed, 10);
+
+        assert_eq!(
+            rules.set("randomTickSpeed", "fast"),
+            Err(GameRuleError::InvalidValue("fast".to_owned()))
+        );
+        assert_eq!(
+            rules.set("doCheating", "true"),
+            Err(GameRuleError::UnknownRule("doCheating".to_owned()))
+        );
+        assert!(rules.get("doCheating").is_none());
+    }
+}
@@ -439,6 +439,27 @@ impl TextValue {
     }
 }
 
+impl TextValue {
+    fn write_plain(&self, out: &mut String) {
+        match self {
+            TextValue::Text { text } => out.push_str(text),
+            TextValue::Translate { translate, with } => {
+                // We don't have the client's translations, so
+                // write the key followed by its arguments.
+                out.push_str(&String::from(translate));
+                for (i, arg) in with.iter().enumerate() {
+                    out.push_str(if i == 0 { " " } else { ", " });
+                    arg.write_plain(out);
+                }
+            }
+            TextValue::Score { value, .. } => out.push_str(value.as_deref().unwrap_or_default()),
+            TextValue::Selector { selector } => out.push_str(selector),
+            TextValue::Keybind { keybind } => out.push_str(&String::from(keybind)),
+            TextValue::Nbt { .. } => {}
+        }
+    }
+}
+
 #[serde_with::skip_serializing_none]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 /// Text json object that holds all styles.
@@ -966,6 +987,229 @@ impl Text {
     pub fn nbt<A: Into<nbt::Blob>>(nbt: A) -> Text {
         Text::from(TextValue::nbt(nbt))
     }
+
+    /// Converts this text to a string without formatting,
+    /// for outputs that can't display JSON text like
+    /// the console or RCON.
+    pub fn to_plain_string(&self) -> String {
+        let mut out = String::new();
+        self.write_plain(&mut out);
+        out
+    }
+
+    fn write_plain(&self, out: &mut String) {
+        match self {
+            Text::String(s) => out.push_str(s),
+            Text::Array(texts) => texts.iter().for_each(|text| text.write_plain(out)),
+            Text::Component(component) => {
+                component.value.write_plain(out);
+                for text in component.extra.iter().flatten() {
+                    text.write_plain(out);
+                }
+            }
+        }
+    }
+
+    /// Parses a string with legacy `§` formatting codes,
+    /// as used in `server.properties` MOTDs.
+    ///
+    /// Unknown codes are kept as text. A string
+    /// without any codes is returned as-is.
+    pub fn from_legacy(legacy: &str) -> Text {
+        if !legacy.contains(LEGACY_PREFIX) {
+            return Text::from(legacy.to_owned());
+        }
+
+        let mut parts = vec![Text::empty()];
+        let mut format = LegacyFormat::default();
+        let mut segment = String::new();
+        let mut chars = legacy.chars().peekable();
+        while let Some(c) = chars.next() {
+            let code = match chars.peek() {
+                Some(&code) if c == LEGACY_PREFIX && LegacyFormat::is_code(code) => code,
+                _ => {
+                    segment.push(c);
+                    continue;
+                }
+            };
+            chars.next();
+
+            if !segment.is_empty() {
+                parts.push(format.apply(std::mem::take(&mut segment)));
+            }
+            format.update(code);
+        }
+        if !segment.is_empty() {
+            parts.push(format.apply(segment));
+        }
+        Text::Array(parts)
+    }
+
+    /// Converts this text to a string with legacy `§` formatting
+    /// codes, for outputs that predate JSON text like legacy
+    /// server list pings and queries.
+    pub fn to_legacy_string(&self) -> String {
+        let mut out = String::new();
+        let mut current = LegacyFormat::default();
+        self.write_legacy(&LegacyFormat::default(), &mut current, &mut out);
+        out
+    }
+
+    fn write_legacy(&self, parent: &LegacyFormat, current: &mut LegacyFormat, out: &mut String) {
+        match self {
+            Text::String(s) => parent.write_segment(s, current, out),
+            Text::Array(texts) => {
+                // Elements after the first inherit its formatting.
+                if let Some((first, rest)) = texts.split_first() {
+                    let format = match first {
+                        Text::Component(component) => parent.inherit(component),
+                        _ => parent.clone(),
+                    };
+                    first.write_legacy(parent, current, out);
+                    for text in rest {
+                        text.write_legacy(&format, current, out);
+                    }
+                }
+            }
+            Text::Component(component) => {
+                let format = parent.inherit(component);
+                let mut value = String::new();
+                component.value.write_plain(&mut value);
+                format.write_segment(&value, current, out);
+                for text in component.extra.iter().flatten() {
+                    text.write_legacy(&format, current, out);
+                }
+            }
+        }
+    }
+}
+
+const LEGACY_PREFIX: char = '\u{a7}';
+
+/// Formatting state of legacy `§` codes.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct LegacyFormat {
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+    obfuscated: bool,
+}
+
+impl LegacyFormat {
+    const COLORS: [(char, Color); 16] = [
+        ('0', Color::Black),
+        ('1', Color::DarkBlue),
+        ('2', Color::DarkGreen),
+        ('3', Color::DarkAqua),
+        ('4', Color::DarkRed),
+        ('5', Color::DarkPurple),
+        ('6', Color::Gold),
+        ('7', Color::Gray),
+        ('8', Color::DarkGray),
+        ('9', Color::Blue),
+        ('a', Color::Green),
+        ('b', Color::Aqua),
+        ('c', Color::Red),
+        ('d', Color::LightPurple),
+        ('e', Color::Yellow),
+        ('f', Color::White),
+    ];
+
+    fn is_code(code: char) -> bool {
+        matches!(code.to_ascii_lowercase(), '0'..='9' | 'a'..='f' | 'k'..='o' | 'r')
+    }
+
+    fn color_code(color: &Color) -> Option<char> {
+        Self::COLORS
+            .iter()
+            .find(|(_, c)| c == color)
+            .map(|&(code, _)| code)
+    }
+
+    /// Applies a formatting code. As in vanilla, a
+    /// color code also resets the styles.
+    fn update(&mut self, code: char) {
+        let code = code.to_ascii_lowercase();
+        if let Some((_, color)) = Self::COLORS.iter().find(|(c, _)| *c == code) {
+            *self = LegacyFormat {
+                color: Some(color.clone()),
+                ..Default::default()
+            };
+            return;
+        }
+        match code {
+            'k' => self.obfuscated = true,
+            'l' => self.bold = true,
+            'm' => self.strikethrough = true,
+            'n' => self.underlined = true,
+            'o' => self.italic = true,
+            _ => *self = LegacyFormat::default(),
+        }
+    }
+
+    fn apply(&self, text: String) -> Text {
+        let mut component = TextComponent::from(text);
+        component.color = self.color.clone();
+        component.bold = Some(true).filter(|_| self.bold);
+        component.italic = Some(true).filter(|_| self.italic);
+        component.underlined = Some(true).filter(|_| self.underlined);
+        component.strikethrough = Some(true).filter(|_| self.strikethrough);
+        component.obfuscated = Some(true).filter(|_| self.obfuscated);
+        component.into()
+    }
+
+    /// Returns the formatting of `component` when it's a child
+    /// of text formatted with `self`.
+    fn inherit(&self, component: &TextComponent) -> LegacyFormat {
+        LegacyFormat {
+            // Custom (hex) colors have no legacy code.
+            color: match &component.color {
+                Some(Color::Custom(_)) => None,
+                Some(color) => Some(color.clone()),
+                None => self.color.clone(),
+            },
+            bold: component.bold.unwrap_or(self.bold),
+            italic: component.italic.unwrap_or(self.italic),
+            underlined: component.underlined.unwrap_or(self.underlined),
+            strikethrough: component.strikethrough.unwrap_or(self.strikethrough),
+            obfuscated: component.obfuscated.unwrap_or(self.obfuscated),
+        }
+    }
+
+    /// Writes `text` formatted with `self`, emitting
+    /// codes only if the formatting changed.
+    fn write_segment(&self, text: &str, current: &mut LegacyFormat, out: &mut String) {
+        if text.is_empty() {
+            return;
+        }
+        if self != current {
+            let code = self
+                .color
+                .as_ref()
+                .and_then(Self::color_code)
+                .unwrap_or('r');
+            out.push(LEGACY_PREFIX);
+            out.push(code);
+            for (enabled, code) in [
+                (self.obfuscated, 'k'),
+                (self.bold, 'l'),
+                (self.strikethrough, 'm'),
+                (self.underlined, 'n'),
+                (self.italic, 'o'),
+            ]
+            .iter()
+            {
+                if *enabled {
+                    out.push(LEGACY_PREFIX);
+                    out.push(*code);
+                }
+            }
+            *current = self.clone();
+        }
+        out.push_str(text);
+    }
 }
 
 impl From<Text> for String {
@@ -1201,4 +1445,42 @@ mod tests {
 
         assert_eq!(root_json, r#"{"text":"hello"}"#);
     }
+
+    #[test]
+    fn text_to_plain_string() {
+        let text = Text::from("hello ").color(Color::Yellow).extra(vec![
+            Text::from("world"),
+            Text::translate_with("multiplayer.player.joined", vec!["The_Defman"]),
+        ]);
+
+        assert_eq!(
+            text.to_plain_string(),
+            "hello worldmultiplayer.player.joined The_Defman"
+        );
+    }
+
+    #[test]
+    fn text_from_legacy() {
+        let text = Text::from_legacy("\u{a7}6\u{a7}lGold\u{a7}r plain \u{a7}zkept");
+        assert_eq!(
+            serde_json::to_string(&text).unwrap(),
+            r#"["",{"text":"Gold","color":"gold","bold":true},{"text":" plain §zkept"}]"#
+        );
+
+        assert_eq!(Text::from_legacy("no codes"), Text::from("no codes"));
+    }
+
+    #[test]
+    fn text_to_legacy_string() {
+        let legacy = "\u{a7}6\u{a7}lGold\u{a7}r plain \u{a7}cred";
+        assert_eq!(Text::from_legacy(legacy).to_legacy_string(), legacy);
+
+        let text = Text::from("A ")
+            .color(Color::Red)
+            .extra(vec![Text::from("nested").bold(), Text::from(" server")]);
+        assert_eq!(
+            text.to_legacy_string(),
+            "\u{a7}cA \u{a7}c\u{a7}lnested\u{a7}c server"
+        );
+    }
 }
@@ -0,0 +1,27 @@
+/// A custom entity type registered with
+/// [`Setup::register_entity_type`](crate::Setup::register_entity_type).
+///
+/// Clients see custom entities as the vanilla entity
+/// type they were registered with. Spawn them with
+/// [`Game::create_custom_entity_builder`](crate::Game::create_custom_entity_builder).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CustomEntityType {
+    kind: String,
+}
+
+impl CustomEntityType {
+    pub(crate) fn new(kind: String) -> Self {
+        Self { kind }
+    }
+
+    /// Gets the kind of this type, i.e. its name
+    /// namespaced with your plugin's identifier,
+    /// e.g. `my_plugin:golem`.
+    ///
+    /// Entities of this type have a
+    /// [`CustomEntityKind`](crate::components::CustomEntityKind)
+    /// component holding the kind.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+}
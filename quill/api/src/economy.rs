@@ -0,0 +1,225 @@
+//! Access to the server's economy, shared by all plugins.
+//!
+//! Amounts are counted in the currency's smallest unit,
+//! e.g. cents if [`Currency::fractional_digits`] is 2.
+//!
+//! Plugins can also store the balances themselves,
+//! e.g. in a database, by registering an [`EconomyProvider`].
+
+use std::{marker::PhantomData, ptr};
+
+use quill_common::{economy::EconomyOp, Pointer, PointerMut};
+use uuid::Uuid;
+
+use crate::Game;
+
+pub use quill_common::economy::{Currency, EconomyError};
+pub use quill_common::events::{EconomyTransactionEvent, TransactionKind};
+
+/// Handle to the server's economy, obtained
+/// from [`Game::economy`](crate::Game::economy).
+///
+/// Accounts are identified by the UUID of their player.
+/// Successful deposits and withdrawals trigger an
+/// [`EconomyTransactionEvent`].
+#[derive(Debug)]
+pub struct Economy<'a> {
+    _game: PhantomData<&'a mut crate::Game>,
+}
+
+impl<'a> Economy<'a> {
+    pub(crate) fn new() -> Self {
+        Self { _game: PhantomData }
+    }
+
+    /// Gets the balance of `account`.
+    pub fn balance(&self, account: Uuid) -> i64 {
+        let (high, low) = split(account);
+        unsafe { quill_sys::economy_balance(high, low) }
+    }
+
+    /// Determines whether `account` can afford to pay `amount`.
+    pub fn has(&self, account: Uuid, amount: i64) -> bool {
+        self.balance(account) >= amount
+    }
+
+    /// Deposits `amount` into `account`, returning the new balance.
+    pub fn deposit(&mut self, account: Uuid, amount: i64) -> Result<i64, EconomyError> {
+        let (high, low) = split(account);
+        let mut balance = 0i64;
+        let code =
+            unsafe { quill_sys::economy_deposit(high, low, amount, PointerMut::new(&mut balance)) };
+        to_result(code, balance)
+    }
+
+    /// Withdraws `amount` from `account`, returning the new balance.
+    pub fn withdraw(&mut self, account: Uuid, amount: i64) -> Result<i64, EconomyError> {
+        let (high, low) = split(account);
+        let mut balance = 0i64;
+        let code = unsafe {
+            quill_sys::economy_withdraw(high, low, amount, PointerMut::new(&mut balance))
+        };
+        to_result(code, balance)
+    }
+
+    /// Gets the currency of the economy.
+    pub fn currency(&self) -> Currency {
+        unsafe {
+            let mut bytes_ptr = Pointer::new(ptr::null());
+            let mut bytes_len = 0u32;
+            quill_sys::economy_currency(
+                PointerMut::new(&mut bytes_ptr),
+                PointerMut::new(&mut bytes_len),
+            );
+
+            let bytes = std::slice::from_raw_parts(bytes_ptr.as_ptr(), bytes_len as usize);
+            bincode::deserialize(bytes).expect("host sent malformed currency")
+        }
+    }
+
+    /// Formats an amount for display, e.g. `1,234.50 dollars`.
+    pub fn format(&self, amount: i64) -> String {
+        self.currency().format(amount)
+    }
+
+    /// Makes `provider` store the balances of the server's economy,
+    /// replacing the active provider. Balances aren't moved to it.
+    ///
+    /// The provider is used until another provider is registered
+    /// or the plugin is unloaded. While one of its methods runs,
+    /// transactions fail with [`EconomyError::ProviderFailure`].
+    ///
+    /// # Panics
+    /// Panics if the currency has more than
+    /// [`Currency::MAX_FRACTIONAL_DIGITS`] fractional digits.
+    pub fn register_provider(&mut self, provider: impl EconomyProvider) {
+        let currency = provider.currency();
+        assert!(
+            currency.fractional_digits <= Currency::MAX_FRACTIONAL_DIGITS,
+            "a currency can have at most {} fractional digits",
+            Currency::MAX_FRACTIONAL_DIGITS
+        );
+        let currency = bincode::serialize(&currency).expect("failed to serialize Currency");
+        let name = provider.name().to_owned();
+
+        unsafe {
+            PROVIDER = Some(Box::new(provider));
+            quill_sys::economy_register_provider(
+                name.as_ptr().into(),
+                name.len() as u32,
+                currency.as_ptr().into(),
+                currency.len() as u32,
+            );
+        }
+    }
+}
+
+/// Stores the balances of the server's economy, replacing
+/// the server's own storage. Register it with
+/// [`Economy::register_provider`].
+pub trait EconomyProvider: 'static {
+    /// Name of the provider, for the server's logs.
+    fn name(&self) -> &str;
+
+    fn currency(&self) -> Currency;
+
+    /// Returns the balance of `account`, in the currency's smallest unit.
+    fn balance(&mut self, game: &mut Game, account: Uuid) -> i64;
+
+    /// Overwrites the balance of `account`.
+    fn set_balance(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        balance: i64,
+    ) -> Result<(), EconomyError>;
+
+    /// Adds a non-negative `amount` to the balance
+    /// of `account`, returning the new balance.
+    fn deposit(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        amount: i64,
+    ) -> Result<i64, EconomyError> {
+        let balance = self
+            .balance(game, account)
+            .checked_add(amount)
+            .ok_or(EconomyError::BalanceOverflow)?;
+        self.set_balance(game, account, balance)?;
+        Ok(balance)
+    }
+
+    /// Subtracts a non-negative `amount` from the balance
+    /// of `account`, returning the new balance.
+    ///
+    /// The default implementation doesn't allow negative balances.
+    fn withdraw(
+        &mut self,
+        game: &mut Game,
+        account: Uuid,
+        amount: i64,
+    ) -> Result<i64, EconomyError> {
+        let balance = self
+            .balance(game, account)
+            .checked_sub(amount)
+            .filter(|&balance| balance >= 0)
+            .ok_or(EconomyError::InsufficientFunds)?;
+        self.set_balance(game, account, balance)?;
+        Ok(balance)
+    }
+}
+
+/// The provider registered by this plugin.
+static mut PROVIDER: Option<Box<dyn EconomyProvider>> = None;
+
+/// Implements the `quill_run_economy` export.
+///
+/// # Safety
+/// `balance` must be valid for writes.
+#[doc(hidden)]
+pub unsafe fn run_provider(
+    op: u32,
+    account_high: u64,
+    account_low: u64,
+    amount: i64,
+    balance: *mut i64,
+) -> u32 {
+    // Taken out while it runs, in case it registers another provider.
+    let mut provider = match PROVIDER.take() {
+        Some(provider) => provider,
+        None => return EconomyError::ProviderFailure.to_u32(),
+    };
+    let account = Uuid::from_u128((account_high as u128) << 64 | account_low as u128);
+    let game = &mut Game::new();
+    let result = match EconomyOp::from_u32(op) {
+        Some(EconomyOp::Balance) => Ok(provider.balance(game, account)),
+        Some(EconomyOp::SetBalance) => provider.set_balance(game, account, amount).map(|()| amount),
+        Some(EconomyOp::Deposit) => provider.deposit(game, account, amount),
+        Some(EconomyOp::Withdraw) => provider.withdraw(game, account, amount),
+        None => Err(EconomyError::ProviderFailure),
+    };
+    if PROVIDER.is_none() {
+        PROVIDER = Some(provider);
+    }
+
+    match result {
+        Ok(new_balance) => {
+            *balance = new_balance;
+            0
+        }
+        Err(e) => e.to_u32(),
+    }
+}
+
+fn split(account: Uuid) -> (u64, u64) {
+    let value = account.as_u128();
+    ((value >> 64) as u64, value as u64)
+}
+
+fn to_result(code: u32, balance: i64) -> Result<i64, EconomyError> {
+    match code {
+        0 => Ok(balance),
+        code => Err(EconomyError::from_u32(code).expect("host sent invalid economy error")),
+    }
+}
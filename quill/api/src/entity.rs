@@ -1,6 +1,14 @@
 use std::{marker::PhantomData, ptr};
 
-use quill_common::{Component, Pointer, PointerMut};
+use libcraft_blocks::BlockState;
+use libcraft_core::{BlockPosition, Position};
+use libcraft_text::Text;
+use quill_common::{components::Afk, metadata::MetadataValue, Component, Pointer, PointerMut};
+
+use crate::{
+    fake_entity::{FakeEntity, FakeEntityHandle},
+    goal::{self, Goal, GoalControls, GoalInfo},
+};
 
 /// Unique internal ID of an entity.
 ///
@@ -117,6 +125,165 @@ impl Entity {
         self.send_title(&libcraft_text::title::Title::RESET)
     }
 
+    /// Returns whether this entity is a player who is AFK.
+    pub fn is_afk(&self) -> bool {
+        self.get::<Afk>().map(|afk| afk.0).unwrap_or(false)
+    }
+
+    /// Gets the AI goals of this entity, most important first.
+    pub fn goals(&self) -> Vec<GoalInfo> {
+        unsafe {
+            let mut bytes_ptr = Pointer::new(ptr::null());
+            let mut bytes_len = 0u32;
+            quill_sys::entity_goals(
+                self.id.0,
+                PointerMut::new(&mut bytes_ptr),
+                PointerMut::new(&mut bytes_len),
+            );
+
+            let bytes = std::slice::from_raw_parts(bytes_ptr.as_ptr(), bytes_len as usize);
+            bincode::deserialize(bytes).expect("host sent malformed goals")
+        }
+    }
+
+    /// Removes this entity's goals with the given name,
+    /// e.g. `look_at_player`. Returns whether a goal was removed.
+    pub fn remove_goal(&self, name: &str) -> bool {
+        unsafe {
+            quill_sys::entity_remove_goal(self.id.0, name.as_ptr().into(), name.len() as u32) > 0
+        }
+    }
+
+    /// Adds an AI goal to this entity.
+    ///
+    /// Lower priority numbers are more important;
+    /// vanilla goals use priorities from 0 to 8.
+    /// The goal's name is prefixed with your plugin's
+    /// identifier, e.g. `my_plugin/follow`.
+    pub fn add_goal(&self, name: &str, priority: u32, controls: GoalControls, goal: impl Goal) {
+        let goal_data = goal::leak(goal);
+        unsafe {
+            quill_sys::entity_add_goal(
+                self.id.0,
+                goal_data.into(),
+                name.as_ptr().into(),
+                name.len() as u32,
+                priority,
+                controls.bits(),
+            );
+        }
+    }
+
+    /// Sets a value in this entity's metadata,
+    /// e.g. to change how its disguise looks to clients.
+    ///
+    /// See [the wiki](https://wiki.vg/Entity_metadata)
+    /// for the meaning of each index.
+    pub fn set_metadata(&self, index: u8, value: MetadataValue) {
+        let value = bincode::serialize(&value).expect("failed to serialize MetadataValue");
+        unsafe {
+            quill_sys::entity_set_metadata(
+                self.id.0,
+                index as u32,
+                value.as_ptr().into(),
+                value.len() as u32,
+            );
+        }
+    }
+
+    /// Gets the tag of this entity with the given key.
+    ///
+    /// Tags are namespaced to your plugin
+    /// and are kept when it is reloaded.
+    pub fn get_tag(&self, key: &str) -> Option<String> {
+        unsafe {
+            let mut value_ptr = Pointer::new(ptr::null());
+            let mut value_len = 0u32;
+            quill_sys::entity_get_tag(
+                self.id.0,
+                key.as_ptr().into(),
+                key.len() as u32,
+                PointerMut::new(&mut value_ptr),
+                PointerMut::new(&mut value_len),
+            );
+
+            if value_ptr.as_ptr().is_null() {
+                return None;
+            }
+
+            let bytes = std::slice::from_raw_parts(value_ptr.as_ptr(), value_len as usize);
+            Some(String::from_utf8(bytes.to_vec()).expect("host sent invalid UTF-8"))
+        }
+    }
+
+    /// Sets the tag of this entity with the given key.
+    pub fn set_tag(&self, key: &str, value: &str) {
+        unsafe {
+            quill_sys::entity_set_tag(
+                self.id.0,
+                key.as_ptr().into(),
+                key.len() as u32,
+                value.as_ptr().into(),
+                value.len() as u32,
+            );
+        }
+    }
+
+    /// Removes the tag of this entity with the given key.
+    pub fn remove_tag(&self, key: &str) {
+        unsafe {
+            quill_sys::entity_remove_tag(self.id.0, key.as_ptr().into(), key.len() as u32);
+        }
+    }
+
+    /// Shows a fake entity to this player.
+    /// See the [`fake_entity`](crate::fake_entity) module.
+    ///
+    /// # Panics
+    /// Panics if this entity is not a player,
+    /// or if an NPC's name is longer than 16 characters.
+    pub fn spawn_fake(&self, entity: FakeEntity) -> FakeEntityHandle {
+        let entity = bincode::serialize(&entity).expect("failed to serialize FakeEntity");
+        let id = unsafe {
+            quill_sys::entity_spawn_fake(self.id.0, entity.as_ptr().into(), entity.len() as u32)
+        };
+        FakeEntityHandle::new(self.id, id)
+    }
+
+    /// Shows floating text to this player.
+    ///
+    /// # Panics
+    /// Panics if this entity is not a player.
+    pub fn show_hologram(&self, position: Position, text: impl Into<Text>) -> FakeEntityHandle {
+        self.spawn_fake(FakeEntity::Hologram {
+            position,
+            text: text.into().to_string(),
+        })
+    }
+
+    /// Shows `block` to this player at `pos` instead of
+    /// the real block, without changing the world.
+    ///
+    /// The fake block is shown until it is restored,
+    /// even if the player's client reloads its chunk.
+    ///
+    /// # Panics
+    /// Panics if this entity is not a player.
+    pub fn set_fake_block(&self, pos: BlockPosition, block: BlockState) {
+        unsafe { quill_sys::entity_set_fake_block(self.id.0, pos.x, pos.y, pos.z, block.id()) }
+    }
+
+    /// Shows the real block at `pos` to this player again.
+    /// Returns `false` if the block wasn't faked.
+    pub fn restore_fake_block(&self, pos: BlockPosition) -> bool {
+        unsafe { quill_sys::entity_restore_fake_block(self.id.0, pos.x, pos.y, pos.z) }
+    }
+
+    /// Shows all the real blocks to this player again.
+    pub fn restore_fake_blocks(&self) {
+        unsafe { quill_sys::entity_restore_fake_blocks(self.id.0) }
+    }
+
     /// Gets the unique ID of this entity.
     pub fn id(&self) -> EntityId {
         self.id
@@ -0,0 +1,65 @@
+//! Fake entities, which only exist on the client of a single
+//! player, like holograms and NPCs.
+//!
+//! Fake entities are not part of the game world: other players
+//! can't see them, and they don't show up in queries. They are
+//! removed when their player leaves.
+
+use libcraft_core::Position;
+use libcraft_text::Text;
+
+use crate::EntityId;
+
+#[doc(inline)]
+pub use quill_common::fake_entity::{FakeEntity, Skin};
+
+/// A handle to a fake entity shown to a player.
+///
+/// Use [`Entity::spawn_fake`](crate::Entity::spawn_fake)
+/// to get a `FakeEntityHandle`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FakeEntityHandle {
+    player: EntityId,
+    id: u32,
+}
+
+impl FakeEntityHandle {
+    pub(crate) fn new(player: EntityId, id: u32) -> Self {
+        Self { player, id }
+    }
+
+    /// Gets the player the fake entity is shown to.
+    pub fn player(&self) -> EntityId {
+        self.player
+    }
+
+    /// Moves the fake entity.
+    ///
+    /// Returns `false` if it was removed.
+    pub fn move_to(&self, position: Position) -> bool {
+        let position: &[u8] = bytemuck::cast_slice(std::slice::from_ref(&position));
+        unsafe { quill_sys::fake_entity_move(self.player.0, self.id, position.as_ptr().into()) }
+    }
+
+    /// Sets the text of a hologram.
+    ///
+    /// Returns `false` if it was removed or isn't a hologram.
+    pub fn set_text(&self, text: impl Into<Text>) -> bool {
+        let text = text.into().to_string();
+        unsafe {
+            quill_sys::fake_entity_set_text(
+                self.player.0,
+                self.id,
+                text.as_ptr().into(),
+                text.len() as u32,
+            )
+        }
+    }
+
+    /// Removes the fake entity.
+    ///
+    /// Returns `false` if it was already removed.
+    pub fn remove(self) -> bool {
+        unsafe { quill_sys::fake_entity_remove(self.player.0, self.id) }
+    }
+}
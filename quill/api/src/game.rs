@@ -3,11 +3,12 @@ use std::marker::PhantomData;
 use libcraft_blocks::BlockState;
 use libcraft_core::{BlockPosition, ChunkPosition, Position, CHUNK_HEIGHT};
 use libcraft_particles::Particle;
-use quill_common::entity_init::EntityInit;
+use quill_common::{entity_init::EntityInit, region::Region};
 
 use crate::{
+    economy::Economy,
     query::{Query, QueryIter},
-    EntityBuilder,
+    CustomEntityType, EntityBuilder,
 };
 use crate::{Entity, EntityId};
 
@@ -97,6 +98,27 @@ impl Game {
         EntityBuilder::new(id)
     }
 
+    /// Creates an entity builder for a custom entity type.
+    ///
+    /// The builder is initialized with the default components
+    /// of the type's vanilla disguise.
+    pub fn create_custom_entity_builder(
+        &self,
+        entity_type: &CustomEntityType,
+        position: Position,
+    ) -> EntityBuilder {
+        let kind = entity_type.kind();
+        let position: &[u8] = bytemuck::cast_slice(std::slice::from_ref(&position));
+        let id = unsafe {
+            quill_sys::entity_builder_new_custom(
+                position.as_ptr().into(),
+                kind.as_ptr().into(),
+                kind.len() as u32,
+            )
+        };
+        EntityBuilder::new(id)
+    }
+
     /// Returns an iterator over all entities
     /// with the given components.
     ///
@@ -204,6 +226,44 @@ impl Game {
         }
     }
 
+    /// Hands off state to the next instance of this plugin
+    /// when it is reloaded. The new instance receives it from
+    /// [`Setup::take_handed_off_state`](crate::Setup::take_handed_off_state).
+    ///
+    /// Call this from [`Plugin::disable`](crate::Plugin::disable)
+    /// to keep long-lived data across reloads.
+    pub fn hand_off_state(&mut self, bytes: &[u8]) {
+        unsafe { quill_sys::plugin_state_store(bytes.as_ptr().into(), bytes.len() as u32) }
+    }
+
+    /// Adds a protected region to the world, replacing the
+    /// region with the same name. The server enforces its flags
+    /// and saves it across restarts.
+    pub fn define_region(&mut self, region: &Region) {
+        let region = bincode::serialize(region).expect("failed to serialize Region");
+        unsafe { quill_sys::region_define(region.as_ptr().into(), region.len() as u32) }
+    }
+
+    /// Removes the protected region called `name`.
+    /// Returns whether it existed.
+    pub fn remove_region(&mut self, name: &str) -> bool {
+        unsafe { quill_sys::region_remove(name.as_ptr().into(), name.len() as u32) }
+    }
+
+    /// Determines whether a mob may spawn naturally at `pos`
+    /// under the world's difficulty, `doMobSpawning` game rule
+    /// and protected regions. Plugins spawning mobs over time
+    /// should check this; mobs spawned on a player's request needn't.
+    pub fn mob_spawning_allowed(&self, pos: BlockPosition, hostile: bool) -> bool {
+        unsafe { quill_sys::world_mob_spawning_allowed(pos.x, pos.y, pos.z, hostile) }
+    }
+
+    /// Gets the server's economy, which stores
+    /// the balances shared by all plugins.
+    pub fn economy(&mut self) -> Economy<'_> {
+        Economy::new()
+    }
+
     /// Sends a custom packet to an entity.
     pub fn send_plugin_message(entity: EntityId, channel: &str, data: &[u8]) {
         let channel_ptr = channel.as_ptr().into();
@@ -0,0 +1,74 @@
+//! Mob AI goals.
+//!
+//! Mobs act through goals, like `look_at_player`.
+//! Use [`Entity::goals`] to inspect a mob's goals,
+//! [`Entity::remove_goal`] to remove vanilla goals and
+//! [`Entity::add_goal`] to add your own.
+
+use quill_common::goals::GoalOp;
+pub use quill_common::goals::{GoalControls, GoalInfo};
+
+use crate::{Entity, EntityId, Game};
+
+/// Something a mob does, like following a player.
+///
+/// Each tick, goals which can start are started, most
+/// important (lowest priority number) first. Goals sharing
+/// a [`GoalControls`] can't run at the same time.
+pub trait Goal: 'static {
+    /// Returns whether the goal should start this tick.
+    fn can_start(&mut self, game: &mut Game, entity: &Entity) -> bool;
+
+    /// Returns whether the running goal should keep running.
+    fn should_continue(&mut self, game: &mut Game, entity: &Entity) -> bool {
+        self.can_start(game, entity)
+    }
+
+    fn start(&mut self, _game: &mut Game, _entity: &Entity) {}
+
+    /// Called each tick while the goal is running.
+    fn tick(&mut self, game: &mut Game, entity: &Entity);
+
+    fn stop(&mut self, _game: &mut Game, _entity: &Entity) {}
+}
+
+/// Leaks a goal so the host can pass it to `quill_run_goal`.
+/// The host frees it with [`GoalOp::Drop`].
+pub(crate) fn leak(goal: impl Goal) -> *mut u8 {
+    let goal: Box<dyn Goal> = Box::new(goal);
+    Box::leak(Box::new(goal)) as *mut Box<_> as *mut u8
+}
+
+/// Implements the `quill_run_goal` export.
+///
+/// # Safety
+/// `data` must have been returned by `leak`,
+/// and must not be used after `GoalOp::Drop`.
+#[doc(hidden)]
+pub unsafe fn run_goal(data: *mut u8, entity: u64, op: u32) -> u32 {
+    if GoalOp::from_u32(op) == Some(GoalOp::Drop) {
+        drop(Box::from_raw(data.cast::<Box<dyn Goal>>()));
+        return 0;
+    }
+
+    let goal = &mut *data.cast::<Box<dyn Goal>>();
+    let entity = Entity::new(EntityId(quill_common::EntityId(entity)));
+    let game = &mut Game::new();
+    match GoalOp::from_u32(op) {
+        Some(GoalOp::CanStart) => goal.can_start(game, &entity) as u32,
+        Some(GoalOp::ShouldContinue) => goal.should_continue(game, &entity) as u32,
+        Some(GoalOp::Start) => {
+            goal.start(game, &entity);
+            0
+        }
+        Some(GoalOp::Tick) => {
+            goal.tick(game, &entity);
+            0
+        }
+        Some(GoalOp::Stop) => {
+            goal.stop(game, &entity);
+            0
+        }
+        Some(GoalOp::Drop) | None => 0,
+    }
+}
@@ -1,15 +1,21 @@
 //! A WebAssembly-based plugin API for Minecraft servers.
 
+mod custom_entity;
+pub mod economy;
 pub mod entities;
 mod entity;
 mod entity_builder;
+pub mod fake_entity;
 mod game;
+pub mod goal;
 pub mod query;
 mod setup;
 
+pub use custom_entity::CustomEntityType;
 pub use entity::{Entity, EntityId};
 pub use entity_builder::EntityBuilder;
 pub use game::Game;
+pub use goal::Goal;
 pub use setup::Setup;
 
 #[doc(inline)]
@@ -22,7 +28,15 @@ pub use libcraft_particles::{Particle, ParticleKind};
 pub use libcraft_text::*;
 
 #[doc(inline)]
-pub use quill_common::{components, entity_init::EntityInit, events, Component};
+pub use quill_common::{
+    components,
+    entity_init::EntityInit,
+    events,
+    metadata::MetadataValue,
+    movement::MovementController,
+    region::{Region, RegionFlag, RegionShape},
+    Component,
+};
 #[doc(inline)]
 pub use uuid::Uuid;
 
@@ -134,6 +148,14 @@ macro_rules! plugin {
             )
         }
 
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_disable() {
+            if let Some(plugin) = PLUGIN.take() {
+                plugin.disable(&mut $crate::Game::new());
+            }
+        }
+
         #[no_mangle]
         #[doc(hidden)]
         pub unsafe extern "C" fn quill_run_system(data: *mut u8) {
@@ -142,6 +164,24 @@ macro_rules! plugin {
             system(plugin, &mut $crate::Game::new());
         }
 
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_run_goal(data: *mut u8, entity: u64, op: u32) -> u32 {
+            $crate::goal::run_goal(data, entity, op)
+        }
+
+        #[no_mangle]
+        #[doc(hidden)]
+        pub unsafe extern "C" fn quill_run_economy(
+            op: u32,
+            account_high: u64,
+            account_low: u64,
+            amount: i64,
+            balance: *mut i64,
+        ) -> u32 {
+            $crate::economy::run_provider(op, account_high, account_low, amount, balance)
+        }
+
         /// Never called by Quill, but this is needed
         /// to avoid linker errors with WASI.
         #[doc(hidden)]
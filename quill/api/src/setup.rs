@@ -1,6 +1,13 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, ptr};
 
-use crate::Game;
+use quill_common::{
+    components::CustomEntityKind,
+    entity_init::EntityInit,
+    events::{Event, EventPriority},
+    Pointer, PointerMut,
+};
+
+use crate::{CustomEntityType, Entity, Game};
 
 /// Struct passed to your plugin's `enable()` function.
 ///
@@ -36,4 +43,158 @@ impl<Plugin> Setup<Plugin> {
 
         self
     }
+
+    /// Schedules a function to be invoked once
+    /// every `period_ticks` ticks.
+    ///
+    /// The function takes the same parameters as a system.
+    /// Each plugin may only schedule a limited number of tasks;
+    /// the server's plugin quotas decide how many.
+    ///
+    /// # Panics
+    /// Panics if `period_ticks` is zero.
+    pub fn add_task<T: FnMut(&mut Plugin, &mut Game)>(
+        &mut self,
+        period_ticks: u32,
+        task: T,
+    ) -> &mut Self {
+        assert!(period_ticks > 0, "task period must be at least one tick");
+        let task: Box<dyn FnMut(&mut Plugin, &mut Game)> = Box::new(task);
+        let task_data = Box::leak(Box::new(task)) as *mut Box<_> as *mut u8;
+
+        let name = std::any::type_name::<T>();
+
+        unsafe {
+            quill_sys::register_task(
+                task_data.into(),
+                name.as_ptr().into(),
+                name.len() as u32,
+                period_ticks,
+            );
+        }
+
+        self
+    }
+
+    /// Registers a handler for events of type `E`.
+    ///
+    /// Each tick, the handler is called for every `E` event.
+    /// Handlers of all plugins run one after another, ordered
+    /// by `priority`; see [`EventPriority`]. A handler may modify
+    /// the event, and handlers running after it see the changes.
+    ///
+    /// If `ignore_cancelled` is set, the handler is not called
+    /// for events cancelled by a handler which ran earlier.
+    ///
+    /// Changes made by `Monitor` handlers are discarded.
+    pub fn add_event_handler<E, T>(
+        &mut self,
+        priority: EventPriority,
+        ignore_cancelled: bool,
+        mut handler: T,
+    ) -> &mut Self
+    where
+        E: Event,
+        [E]: ToOwned,
+        T: FnMut(&mut Plugin, &mut Game, &Entity, &mut E) + 'static,
+    {
+        let system = move |plugin: &mut Plugin, game: &mut Game| {
+            let events: Vec<(Entity, E)> = game.query::<&E>().collect();
+            for (entity, mut event) in events {
+                if ignore_cancelled && event.is_cancelled() {
+                    continue;
+                }
+                handler(plugin, game, &entity, &mut event);
+                if priority != EventPriority::Monitor {
+                    entity.set(event);
+                }
+            }
+        };
+        let system: Box<dyn FnMut(&mut Plugin, &mut Game)> = Box::new(system);
+        let system_data = Box::leak(Box::new(system)) as *mut Box<_> as *mut u8;
+
+        let name = std::any::type_name::<T>();
+
+        unsafe {
+            quill_sys::register_event_handler(
+                system_data.into(),
+                name.as_ptr().into(),
+                name.len() as u32,
+                priority.to_u32(),
+            );
+        }
+
+        self
+    }
+
+    /// Registers a custom entity type which clients
+    /// see as the vanilla entity `disguise`.
+    ///
+    /// `name` must be non-empty and must not contain a `:`;
+    /// it is namespaced with your plugin's identifier.
+    pub fn register_entity_type(&mut self, name: &str, disguise: EntityInit) -> CustomEntityType {
+        let disguise = bincode::serialize(&disguise).expect("failed to serialize EntityInit");
+        let kind = unsafe {
+            let mut kind_ptr = Pointer::new(ptr::null());
+            let mut kind_len = 0u32;
+            quill_sys::register_custom_entity(
+                name.as_ptr().into(),
+                name.len() as u32,
+                disguise.as_ptr().into(),
+                disguise.len() as u32,
+                PointerMut::new(&mut kind_ptr),
+                PointerMut::new(&mut kind_len),
+            );
+
+            let bytes = std::slice::from_raw_parts(kind_ptr.as_ptr(), kind_len as usize);
+            String::from_utf8(bytes.to_vec()).expect("host sent invalid UTF-8")
+        };
+        CustomEntityType::new(kind)
+    }
+
+    /// Registers a function invoked every tick
+    /// for each entity of the given custom type.
+    pub fn add_entity_ticker<T>(
+        &mut self,
+        entity_type: &CustomEntityType,
+        mut ticker: T,
+    ) -> &mut Self
+    where
+        T: FnMut(&mut Plugin, &mut Game, &Entity) + 'static,
+    {
+        let kind = entity_type.kind().to_owned();
+        self.add_system(move |plugin: &mut Plugin, game: &mut Game| {
+            let entities: Vec<Entity> = game
+                .query::<&CustomEntityKind>()
+                .filter(|(_, entity_kind)| entity_kind.0 == kind)
+                .map(|(entity, _)| entity)
+                .collect();
+            for entity in entities {
+                ticker(plugin, game, &entity);
+            }
+        })
+    }
+
+    /// Takes the state handed off by the previous instance
+    /// of this plugin with [`Game::hand_off_state`].
+    ///
+    /// Returns `None` if the plugin was not reloaded
+    /// or the previous instance didn't hand off any state.
+    pub fn take_handed_off_state(&mut self) -> Option<Vec<u8>> {
+        unsafe {
+            let mut bytes_ptr = Pointer::new(ptr::null());
+            let mut bytes_len = 0u32;
+            quill_sys::plugin_state_take(
+                PointerMut::new(&mut bytes_ptr),
+                PointerMut::new(&mut bytes_len),
+            );
+
+            if bytes_ptr.as_ptr().is_null() {
+                return None;
+            }
+
+            let bytes = std::slice::from_raw_parts(bytes_ptr.as_ptr(), bytes_len as usize);
+            Some(bytes.to_vec())
+        }
+    }
 }
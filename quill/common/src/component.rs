@@ -9,6 +9,7 @@ use uuid::Uuid;
 use crate::components::*;
 use crate::entities::*;
 use crate::events::*;
+use crate::movement::MovementController;
 
 /// Used to convert dynamic `HostComponent`s to
 /// statically-typed generic `T`s.
@@ -183,7 +184,16 @@ host_component_enum! {
         Particle = 1005,
         InteractEntityEvent = 1006,
         BlockPlacementEvent = 1007,
-        BlockInteractEvent = 1008
+        BlockInteractEvent = 1008,
+        NameChangedEvent = 1009,
+        PlayerJoinMessageEvent = 1010,
+        PlayerQuitMessageEvent = 1011,
+        Afk = 1012,
+        CustomEntityKind = 1013,
+        HeadYaw = 1014,
+        MovementController = 1015,
+        EconomyTransactionEvent = 1016,
+        PlayerPreLoginEvent = 1017
     }
 }
 
@@ -336,3 +346,8 @@ bincode_component_impl!(Particle);
 bincode_component_impl!(InteractEntityEvent);
 bincode_component_impl!(BlockPlacementEvent);
 bincode_component_impl!(BlockInteractEvent);
+bincode_component_impl!(NameChangedEvent);
+bincode_component_impl!(PlayerJoinMessageEvent);
+bincode_component_impl!(PlayerQuitMessageEvent);
+bincode_component_impl!(EconomyTransactionEvent);
+bincode_component_impl!(PlayerPreLoginEvent);
@@ -17,6 +17,38 @@ pub struct OnGround(pub bool);
 
 bincode_component_impl!(OnGround);
 
+/// Whether a player is AFK (away from keyboard),
+/// meaning they haven't done anything for a while.
+///
+/// This component is managed by the server. Do not
+/// attempt to change it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Afk(pub bool);
+
+bincode_component_impl!(Afk);
+
+/// The type of a custom entity registered by a plugin,
+/// namespaced with the plugin's identifier, e.g. `my_plugin:boss`.
+///
+/// Clients see custom entities as the vanilla
+/// type they were registered with.
+///
+/// This component is managed by the server. Do not
+/// attempt to change it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CustomEntityKind(pub String);
+
+bincode_component_impl!(CustomEntityKind);
+
+/// The yaw of an entity's head, in degrees.
+///
+/// Entities without this component turn
+/// their head along with their body.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HeadYaw(pub f32);
+
+bincode_component_impl!(HeadYaw);
+
 /// A player's username.
 ///
 /// This component is immutable. Do not
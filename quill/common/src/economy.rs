@@ -0,0 +1,187 @@
+//! Types shared by the server's economy service
+//! and the plugins using it.
+//!
+//! Amounts are integers in the currency's smallest unit,
+//! e.g. cents for a currency with two fractional digits,
+//! so that balances never suffer from rounding errors.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Describes the currency of an economy.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Currency {
+    /// Name of one unit, e.g. "dollar".
+    pub singular: String,
+    /// Name of several units, e.g. "dollars".
+    pub plural: String,
+    /// Number of digits after the decimal point. Amounts
+    /// are counted in units of `10^-fractional_digits`.
+    /// At most [`Currency::MAX_FRACTIONAL_DIGITS`].
+    pub fractional_digits: u8,
+}
+
+impl Currency {
+    /// The most fractional digits a currency can have,
+    /// so that one unit still fits in a `u64`.
+    pub const MAX_FRACTIONAL_DIGITS: u8 = 19;
+
+    /// Formats an amount for display, e.g. `1,234.50 dollars`.
+    ///
+    /// # Panics
+    /// Panics if `fractional_digits` is greater than
+    /// [`Currency::MAX_FRACTIONAL_DIGITS`].
+    pub fn format(&self, amount: i64) -> String {
+        let scale = 10u64.pow(self.fractional_digits as u32);
+        let magnitude = amount.unsigned_abs();
+        let (whole, fraction) = (magnitude / scale, magnitude % scale);
+
+        let digits = whole.to_string();
+        let mut formatted = String::new();
+        if amount < 0 {
+            formatted.push('-');
+        }
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                formatted.push(',');
+            }
+            formatted.push(digit);
+        }
+        if self.fractional_digits > 0 {
+            formatted.push_str(&format!(
+                ".{:0width$}",
+                fraction,
+                width = self.fractional_digits as usize
+            ));
+        }
+
+        let name = if magnitude == scale {
+            &self.singular
+        } else {
+            &self.plural
+        };
+        format!("{} {}", formatted, name)
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self {
+            singular: "coin".to_owned(),
+            plural: "coins".to_owned(),
+            fractional_digits: 2,
+        }
+    }
+}
+
+/// Operation invoked on a plugin's economy provider
+/// through the plugin's `quill_run_economy` export.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum EconomyOp {
+    Balance = 0,
+    SetBalance = 1,
+    Deposit = 2,
+    Withdraw = 3,
+}
+
+impl EconomyOp {
+    pub fn from_u32(op: u32) -> Option<Self> {
+        Some(match op {
+            0 => EconomyOp::Balance,
+            1 => EconomyOp::SetBalance,
+            2 => EconomyOp::Deposit,
+            3 => EconomyOp::Withdraw,
+            _ => return None,
+        })
+    }
+}
+
+/// Why a deposit or withdrawal failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u32)]
+pub enum EconomyError {
+    /// The amount was negative.
+    NegativeAmount = 1,
+    /// The account's balance is lower than the amount withdrawn.
+    InsufficientFunds = 2,
+    /// The new balance would exceed the provider's limit.
+    BalanceOverflow = 3,
+    /// The provider failed, e.g. because its storage is unavailable.
+    ProviderFailure = 4,
+}
+
+impl EconomyError {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Some(match value {
+            1 => EconomyError::NegativeAmount,
+            2 => EconomyError::InsufficientFunds,
+            3 => EconomyError::BalanceOverflow,
+            4 => EconomyError::ProviderFailure,
+            _ => return None,
+        })
+    }
+
+    pub fn to_u32(self) -> u32 {
+        self as u32
+    }
+}
+
+impl fmt::Display for EconomyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EconomyError::NegativeAmount => "amount is negative",
+            EconomyError::InsufficientFunds => "insufficient funds",
+            EconomyError::BalanceOverflow => "balance would overflow",
+            EconomyError::ProviderFailure => "economy provider failed",
+        })
+    }
+}
+
+impl std::error::Error for EconomyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_amounts() {
+        let currency = Currency {
+            singular: "dollar".to_owned(),
+            plural: "dollars".to_owned(),
+            fractional_digits: 2,
+        };
+        assert_eq!(currency.format(123_450), "1,234.50 dollars");
+        assert_eq!(currency.format(100), "1.00 dollar");
+        assert_eq!(currency.format(-5), "-0.05 dollars");
+
+        let whole = Currency {
+            fractional_digits: 0,
+            ..Currency::default()
+        };
+        assert_eq!(whole.format(1), "1 coin");
+        assert_eq!(whole.format(1_000_000), "1,000,000 coins");
+
+        let finest = Currency {
+            fractional_digits: Currency::MAX_FRACTIONAL_DIGITS,
+            ..Currency::default()
+        };
+        assert_eq!(finest.format(i64::MAX), "0.9223372036854775807 coins");
+    }
+
+    #[test]
+    fn errors_round_trip() {
+        for error in [
+            EconomyError::NegativeAmount,
+            EconomyError::InsufficientFunds,
+            EconomyError::BalanceOverflow,
+            EconomyError::ProviderFailure,
+        ]
+        .iter()
+        {
+            assert_eq!(EconomyError::from_u32(error.to_u32()), Some(*error));
+        }
+        assert_eq!(EconomyError::from_u32(0), None);
+    }
+}
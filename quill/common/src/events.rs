@@ -1,5 +1,15 @@
 mod block_interact;
+mod economy;
 mod interact_entity;
+mod join_message;
+mod name_changed;
+mod pre_login;
+mod priority;
 
 pub use block_interact::{BlockInteractEvent, BlockPlacementEvent};
+pub use economy::{EconomyTransactionEvent, TransactionKind};
 pub use interact_entity::InteractEntityEvent;
+pub use join_message::{PlayerJoinMessageEvent, PlayerQuitMessageEvent};
+pub use name_changed::NameChangedEvent;
+pub use pre_login::PlayerPreLoginEvent;
+pub use priority::{Event, EventPriority};
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Triggered after the balance of an account changes.
+///
+/// This is a standalone event, since the account's
+/// player doesn't need to be online.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EconomyTransactionEvent {
+    pub account: Uuid,
+    pub kind: TransactionKind,
+    /// The amount deposited or withdrawn, in
+    /// the currency's smallest unit.
+    pub amount: i64,
+    /// The account's balance after the transaction.
+    pub balance: i64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionKind {
+    Deposit,
+    Withdrawal,
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Triggered before a player's join message is broadcast.
+///
+/// Plugins may rewrite `message` or set `cancelled`
+/// by replacing this component; the message is broadcast
+/// once all systems have observed the event. It starts
+/// out cancelled for players joining silently.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerJoinMessageEvent {
+    pub player: String,
+    pub uuid: Uuid,
+    /// The message as JSON text.
+    pub message: String,
+    pub cancelled: bool,
+}
+
+/// Triggered before a player's quit message is broadcast.
+///
+/// This is a standalone event, since the player entity
+/// is removed when it's triggered. Works like [`PlayerJoinMessageEvent`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerQuitMessageEvent {
+    pub player: String,
+    pub uuid: Uuid,
+    /// The message as JSON text.
+    pub message: String,
+    pub cancelled: bool,
+}
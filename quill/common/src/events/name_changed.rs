@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// Triggered on a player who joins with a different
+/// username than the one they last played with.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NameChangedEvent {
+    pub old_name: String,
+    pub new_name: String,
+}
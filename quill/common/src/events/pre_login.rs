@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Triggered once a player has been authenticated,
+/// before they join the game.
+///
+/// Plugins may set `cancelled` by replacing this component
+/// to disconnect the player with `kick_message`. This is a
+/// standalone event, since the player has no entity yet.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerPreLoginEvent {
+    pub player: String,
+    pub uuid: Uuid,
+    /// The message shown to the player if the
+    /// event is cancelled, as JSON text.
+    pub kick_message: String,
+    pub cancelled: bool,
+}
@@ -0,0 +1,108 @@
+use crate::Component;
+
+use super::{
+    BlockInteractEvent, BlockPlacementEvent, EconomyTransactionEvent, InteractEntityEvent,
+    NameChangedEvent, PlayerJoinMessageEvent, PlayerPreLoginEvent, PlayerQuitMessageEvent,
+};
+
+/// Determines when an event handler runs
+/// relative to the handlers of other plugins.
+///
+/// Handlers run from `Lowest` to `Highest`, so the handler
+/// with the highest priority has the final say on the event.
+/// Handlers with equal priorities run in plugin load order.
+///
+/// `Monitor` handlers run last, once the outcome of the event
+/// is final. They must not modify the event; any changes
+/// they make are discarded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u32)]
+pub enum EventPriority {
+    Lowest = 0,
+    Low = 1,
+    Normal = 2,
+    High = 3,
+    Highest = 4,
+    Monitor = 5,
+}
+
+impl EventPriority {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Some(match value {
+            0 => EventPriority::Lowest,
+            1 => EventPriority::Low,
+            2 => EventPriority::Normal,
+            3 => EventPriority::High,
+            4 => EventPriority::Highest,
+            5 => EventPriority::Monitor,
+            _ => return None,
+        })
+    }
+
+    pub fn to_u32(self) -> u32 {
+        self as u32
+    }
+}
+
+impl Default for EventPriority {
+    fn default() -> Self {
+        EventPriority::Normal
+    }
+}
+
+/// An event which plugins can register handlers for.
+pub trait Event: Component {
+    /// Whether a handler which ran earlier cancelled the event.
+    ///
+    /// Always `false` for events which can't be cancelled.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl Event for BlockInteractEvent {}
+impl Event for BlockPlacementEvent {}
+impl Event for EconomyTransactionEvent {}
+impl Event for InteractEntityEvent {}
+impl Event for NameChangedEvent {}
+
+impl Event for PlayerJoinMessageEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+impl Event for PlayerQuitMessageEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+impl Event for PlayerPreLoginEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priorities_round_trip() {
+        for priority in [
+            EventPriority::Lowest,
+            EventPriority::Low,
+            EventPriority::Normal,
+            EventPriority::High,
+            EventPriority::Highest,
+            EventPriority::Monitor,
+        ]
+        .iter()
+        {
+            assert_eq!(EventPriority::from_u32(priority.to_u32()), Some(*priority));
+        }
+        assert_eq!(EventPriority::from_u32(6), None);
+        assert!(EventPriority::Highest < EventPriority::Monitor);
+    }
+}
@@ -0,0 +1,49 @@
+//! Fake entities, which only exist on the client
+//! of a single player and not in the game world.
+
+use libcraft_core::Position;
+use serde::{Deserialize, Serialize};
+
+/// A fake entity shown to a single player.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FakeEntity {
+    /// Floating text, shown as an invisible armor stand
+    /// with a custom name.
+    Hologram {
+        position: Position,
+        /// JSON text.
+        text: String,
+    },
+    /// A player-like entity. The NPC is briefly added to
+    /// the player's tablist so their client loads its skin.
+    Npc {
+        position: Position,
+        /// Name shown above the NPC, at most 16 characters.
+        name: String,
+        skin: Option<Skin>,
+    },
+}
+
+impl FakeEntity {
+    pub fn position(&self) -> Position {
+        match self {
+            FakeEntity::Hologram { position, .. } | FakeEntity::Npc { position, .. } => *position,
+        }
+    }
+
+    pub fn set_position(&mut self, new_position: Position) {
+        match self {
+            FakeEntity::Hologram { position, .. } | FakeEntity::Npc { position, .. } => {
+                *position = new_position
+            }
+        }
+    }
+}
+
+/// A skin, i.e. the value and signature
+/// of a `textures` profile property.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Skin {
+    pub value: String,
+    pub signature: String,
+}
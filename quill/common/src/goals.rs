@@ -0,0 +1,109 @@
+//! Types shared between the host and plugins
+//! for the mob AI goal API.
+
+use std::ops::BitOr;
+
+use serde::{Deserialize, Serialize};
+
+/// Controls of an entity a goal takes over while it runs,
+/// like its movement or where it looks.
+///
+/// Goals sharing a control can't run at the same time;
+/// the goal with the lower priority number wins.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GoalControls(u32);
+
+impl GoalControls {
+    pub const NONE: GoalControls = GoalControls(0);
+    pub const MOVE: GoalControls = GoalControls(1);
+    pub const LOOK: GoalControls = GoalControls(1 << 1);
+    pub const JUMP: GoalControls = GoalControls(1 << 2);
+    pub const TARGET: GoalControls = GoalControls(1 << 3);
+
+    const ALL: u32 = 0b1111;
+
+    /// Creates controls from their bits, ignoring unknown bits.
+    pub fn from_bits(bits: u32) -> Self {
+        GoalControls(bits & Self::ALL)
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(self, other: GoalControls) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersects(self, other: GoalControls) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for GoalControls {
+    type Output = GoalControls;
+
+    fn bitor(self, rhs: GoalControls) -> GoalControls {
+        GoalControls(self.0 | rhs.0)
+    }
+}
+
+/// Describes one of an entity's goals.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoalInfo {
+    /// Name of the goal, e.g. `look_at_player`.
+    ///
+    /// Goals added by plugins are prefixed with
+    /// the plugin's identifier, e.g. `my_plugin/follow`.
+    pub name: String,
+    /// Lower numbers are more important.
+    pub priority: u32,
+    pub controls: GoalControls,
+    /// Whether the goal is currently running.
+    pub running: bool,
+}
+
+/// Callback invoked on a plugin goal through
+/// the plugin's `quill_run_goal` export.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum GoalOp {
+    /// Returns 1 if the goal should start.
+    CanStart = 0,
+    /// Returns 1 if the running goal should keep running.
+    ShouldContinue = 1,
+    Start = 2,
+    Tick = 3,
+    Stop = 4,
+    /// Frees the goal once the host has removed it.
+    Drop = 5,
+}
+
+impl GoalOp {
+    pub fn from_u32(op: u32) -> Option<Self> {
+        Some(match op {
+            0 => GoalOp::CanStart,
+            1 => GoalOp::ShouldContinue,
+            2 => GoalOp::Start,
+            3 => GoalOp::Tick,
+            4 => GoalOp::Stop,
+            5 => GoalOp::Drop,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn controls() {
+        let controls = GoalControls::MOVE | GoalControls::LOOK;
+        assert!(controls.contains(GoalControls::LOOK));
+        assert!(!controls.contains(GoalControls::LOOK | GoalControls::JUMP));
+        assert!(controls.intersects(GoalControls::LOOK | GoalControls::JUMP));
+        assert!(!controls.intersects(GoalControls::NONE));
+        assert_eq!(GoalControls::from_bits(u32::MAX).bits(), 0b1111);
+    }
+}
@@ -4,10 +4,16 @@ mod utils;
 pub mod component;
 pub mod block;
 pub mod components;
+pub mod economy;
 pub mod entities;
 pub mod entity;
 pub mod entity_init;
 pub mod events;
+pub mod fake_entity;
+pub mod goals;
+pub mod metadata;
+pub mod movement;
+pub mod region;
 
 use std::marker::PhantomData;
 
@@ -0,0 +1,21 @@
+//! Entity metadata values plugins can send to clients.
+
+use serde::{Deserialize, Serialize};
+
+/// A value in an entity's metadata.
+///
+/// See [the wiki](https://wiki.vg/Entity_metadata) for the
+/// indices and types each entity type uses.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MetadataValue {
+    Byte(i8),
+    VarInt(i32),
+    Float(f32),
+    String(String),
+    /// JSON text.
+    Chat(String),
+    /// Optional JSON text, e.g. a custom name.
+    OptChat(Option<String>),
+    Boolean(bool),
+    Pose(i32),
+}
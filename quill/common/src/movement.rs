@@ -0,0 +1,276 @@
+//! Smooth movement of entities controlled by the server,
+//! like NPCs and cinematic cameras.
+
+use libcraft_core::Position;
+use serde::{Deserialize, Serialize};
+
+/// Moves an entity smoothly, one step each tick.
+///
+/// Give the controller a destination with [`move_to`](Self::move_to)
+/// or [`move_towards`](Self::move_towards) and something to face with
+/// [`look_at`](Self::look_at), then add it to the entity. Each tick,
+/// the server moves the entity's [`Position`] towards the destination
+/// and turns its head and body at limited speeds. Like vanilla mobs,
+/// the body faces where the entity walks and the head stays within
+/// [`max_head_offset`](Self::max_head_offset) degrees of it.
+///
+/// Clients are sent relative moves while the entity moves less
+/// than 8 blocks per tick, and teleports for longer steps.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovementController {
+    /// Most degrees the body turns per tick.
+    pub max_body_turn: f32,
+    /// Most degrees the head turns per tick.
+    pub max_head_turn: f32,
+    /// Most degrees between the yaw of the head and of the body.
+    pub max_head_offset: f32,
+    destination: Option<Destination>,
+    look_target: Option<[f64; 3]>,
+}
+
+bincode_component_impl!(MovementController);
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Destination {
+    position: [f64; 3],
+    pace: Pace,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum Pace {
+    /// Arrive after this many ticks.
+    Ticks(u32),
+    /// Move this many blocks per tick.
+    Speed(f64),
+}
+
+impl Default for MovementController {
+    fn default() -> Self {
+        Self {
+            max_body_turn: 15.,
+            max_head_turn: 30.,
+            max_head_offset: 75.,
+            destination: None,
+            look_target: None,
+        }
+    }
+}
+
+impl MovementController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the entity to `destination` at a constant speed,
+    /// arriving after `ticks` ticks. The rotation of
+    /// `destination` is ignored.
+    pub fn move_to(&mut self, destination: Position, ticks: u32) {
+        self.destination = Some(Destination {
+            position: [destination.x, destination.y, destination.z],
+            pace: Pace::Ticks(ticks),
+        });
+    }
+
+    /// Moves the entity to `destination` at `blocks_per_tick`.
+    /// The rotation of `destination` is ignored.
+    ///
+    /// The entity stops instead if `blocks_per_tick` isn't
+    /// positive, since it would never arrive.
+    pub fn move_towards(&mut self, destination: Position, blocks_per_tick: f64) {
+        if blocks_per_tick.is_nan() || blocks_per_tick <= 0. {
+            self.stop();
+            return;
+        }
+        self.destination = Some(Destination {
+            position: [destination.x, destination.y, destination.z],
+            pace: Pace::Speed(blocks_per_tick),
+        });
+    }
+
+    /// Stops moving the entity where it currently is.
+    pub fn stop(&mut self) {
+        self.destination = None;
+    }
+
+    /// Returns whether the entity hasn't reached its destination yet.
+    pub fn is_moving(&self) -> bool {
+        self.destination.is_some()
+    }
+
+    /// Turns the entity's head towards `target`
+    /// until [`look_ahead`](Self::look_ahead) is called.
+    pub fn look_at(&mut self, target: Position) {
+        self.look_target = Some([target.x, target.y, target.z]);
+    }
+
+    /// Makes the entity look where it's going.
+    pub fn look_ahead(&mut self) {
+        self.look_target = None;
+    }
+
+    /// Moves `position` and turns `head_yaw` by one tick.
+    pub fn tick(&mut self, position: &mut Position, head_yaw: &mut f32) {
+        let (old_x, old_z) = (position.x, position.z);
+        self.step(position);
+        let (moved_x, moved_z) = (position.x - old_x, position.z - old_z);
+        let walking_yaw = if moved_x.hypot(moved_z) > 1e-3 {
+            Some(yaw_towards(moved_x, moved_z))
+        } else {
+            None
+        };
+
+        let (target_yaw, target_pitch) = match self.look_target {
+            Some([x, y, z]) => {
+                let (dx, dy, dz) = (x - position.x, y - position.y, z - position.z);
+                let pitch = (-dy).atan2(dx.hypot(dz)).to_degrees() as f32;
+                (yaw_towards(dx, dz), pitch)
+            }
+            None => match walking_yaw {
+                Some(yaw) => (yaw, 0.),
+                None => (*head_yaw, position.pitch),
+            },
+        };
+        *head_yaw = turn(*head_yaw, target_yaw, self.max_head_turn);
+        position.pitch = (position.pitch
+            + (target_pitch - position.pitch).clamp(-self.max_head_turn, self.max_head_turn))
+        .clamp(-90., 90.);
+
+        // The body faces where the entity walks. Standing
+        // still, it only turns if the head turned too far.
+        match walking_yaw {
+            Some(yaw) => position.yaw = turn(position.yaw, yaw, self.max_body_turn),
+            None if wrap_degrees(*head_yaw - position.yaw).abs() > self.max_head_offset => {
+                position.yaw = turn(position.yaw, *head_yaw, self.max_body_turn)
+            }
+            None => {}
+        }
+        let offset = wrap_degrees(*head_yaw - position.yaw)
+            .clamp(-self.max_head_offset, self.max_head_offset);
+        *head_yaw = wrap_degrees(position.yaw + offset);
+    }
+
+    /// Moves `position` towards the destination.
+    fn step(&mut self, position: &mut Position) {
+        let destination = match &mut self.destination {
+            Some(destination) => destination,
+            None => return,
+        };
+        let [x, y, z] = destination.position;
+        let (dx, dy, dz) = (x - position.x, y - position.y, z - position.z);
+        let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+        let step = match &mut destination.pace {
+            Pace::Ticks(0) => distance,
+            Pace::Ticks(ticks) => {
+                let step = distance / *ticks as f64;
+                *ticks -= 1;
+                step
+            }
+            Pace::Speed(speed) => *speed,
+        };
+        if step >= distance {
+            position.x = x;
+            position.y = y;
+            position.z = z;
+            self.destination = None;
+        } else {
+            let fraction = step / distance;
+            position.x += dx * fraction;
+            position.y += dy * fraction;
+            position.z += dz * fraction;
+        }
+    }
+}
+
+/// Returns the yaw facing along `dx, dz`.
+fn yaw_towards(dx: f64, dz: f64) -> f32 {
+    (-dx).atan2(dz).to_degrees() as f32
+}
+
+/// Turns `current` towards `target` by at most `max` degrees.
+fn turn(current: f32, target: f32, max: f32) -> f32 {
+    wrap_degrees(current + wrap_degrees(target - current).clamp(-max, max))
+}
+
+/// Wraps `angle` to [-180, 180).
+fn wrap_degrees(angle: f32) -> f32 {
+    (angle + 180.).rem_euclid(360.) - 180.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_over_ticks() {
+        let mut controller = MovementController::new();
+        let mut position = Position::default();
+        let mut head_yaw = 0.;
+        controller.move_to(
+            Position {
+                x: 10.,
+                ..Default::default()
+            },
+            4,
+        );
+        for expected in &[2.5, 5., 7.5, 10.] {
+            assert!(controller.is_moving());
+            controller.tick(&mut position, &mut head_yaw);
+            assert!((position.x - expected).abs() < 1e-9);
+        }
+        assert!(!controller.is_moving());
+    }
+
+    #[test]
+    fn limits_turning() {
+        let mut controller = MovementController::new();
+        let mut position = Position::default();
+        let mut head_yaw = 0.;
+        // Behind the entity, which faces +z at yaw 0.
+        controller.look_at(Position {
+            z: -10.,
+            ..Default::default()
+        });
+        controller.tick(&mut position, &mut head_yaw);
+        assert_eq!(head_yaw.abs(), 30.);
+        assert_eq!(position.yaw, 0.);
+
+        for _ in 0..20 {
+            controller.tick(&mut position, &mut head_yaw);
+            assert!(wrap_degrees(head_yaw - position.yaw).abs() <= 75.);
+        }
+        assert_eq!(head_yaw.abs(), 180.);
+    }
+
+    #[test]
+    fn body_faces_walking_direction() {
+        let mut controller = MovementController::new();
+        let mut position = Position::default();
+        let mut head_yaw = 0.;
+        controller.move_towards(
+            Position {
+                x: -100.,
+                ..Default::default()
+            },
+            0.5,
+        );
+        for _ in 0..10 {
+            controller.tick(&mut position, &mut head_yaw);
+        }
+        assert_eq!(position.yaw, 90.);
+        assert_eq!(head_yaw, 90.);
+    }
+
+    #[test]
+    fn non_positive_speeds_stop() {
+        let mut controller = MovementController::new();
+        let destination = Position {
+            x: 10.,
+            ..Default::default()
+        };
+        for &speed in &[0., -1., f64::NAN] {
+            controller.move_towards(destination, 1.);
+            controller.move_towards(destination, speed);
+            assert!(!controller.is_moving());
+        }
+    }
+}
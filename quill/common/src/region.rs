@@ -0,0 +1,217 @@
+//! Protected regions of a world, the foundation of
+//! claim and protection plugins.
+//!
+//! The server enforces the [`RegionFlag`]s of regions;
+//! plugins only decide which regions exist, which flags
+//! they set and who their members are.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use libcraft_core::BlockPosition;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A named area of a world with flags restricting
+/// what can happen inside it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    /// Unique within the region's world.
+    pub name: String,
+    pub shape: RegionShape,
+    /// Where regions overlap, the flags of the region
+    /// with the highest priority apply.
+    pub priority: i32,
+    /// Flags set by this region. Flags which no region at a
+    /// position sets are allowed there.
+    pub flags: BTreeMap<RegionFlag, bool>,
+    /// Players who may build and interact in the region
+    /// regardless of its flags, e.g. the owner of a claim.
+    pub members: BTreeSet<Uuid>,
+}
+
+impl Region {
+    /// Creates a region with no flags or members.
+    pub fn new(name: impl Into<String>, shape: RegionShape) -> Self {
+        Self {
+            name: name.into(),
+            shape,
+            priority: 0,
+            flags: BTreeMap::new(),
+            members: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_flag(mut self, flag: RegionFlag, allowed: bool) -> Self {
+        self.flags.insert(flag, allowed);
+        self
+    }
+
+    pub fn with_member(mut self, member: Uuid) -> Self {
+        self.members.insert(member);
+        self
+    }
+
+    /// Returns whether `flag` is allowed in this region,
+    /// or `None` if the region doesn't set it.
+    pub fn flag(&self, flag: RegionFlag) -> Option<bool> {
+        self.flags.get(&flag).copied()
+    }
+
+    pub fn contains(&self, pos: BlockPosition) -> bool {
+        self.shape.contains(pos)
+    }
+}
+
+/// The blocks a [`Region`] covers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegionShape {
+    /// The blocks between two corners, inclusive.
+    Cuboid {
+        min: BlockPosition,
+        max: BlockPosition,
+    },
+    /// The blocks inside or on the edges of a polygon on
+    /// the X/Z plane, from `min_y` to `max_y` inclusive.
+    Polygon {
+        /// `(x, z)` coordinates of the corners, in order.
+        points: Vec<(i32, i32)>,
+        min_y: i32,
+        max_y: i32,
+    },
+}
+
+impl RegionShape {
+    /// Creates a cuboid between any two opposite corners.
+    pub fn cuboid(a: BlockPosition, b: BlockPosition) -> Self {
+        RegionShape::Cuboid {
+            min: BlockPosition::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: BlockPosition::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    pub fn contains(&self, pos: BlockPosition) -> bool {
+        match self {
+            RegionShape::Cuboid { min, max } => {
+                (min.x..=max.x).contains(&pos.x)
+                    && (min.y..=max.y).contains(&pos.y)
+                    && (min.z..=max.z).contains(&pos.z)
+            }
+            RegionShape::Polygon {
+                points,
+                min_y,
+                max_y,
+            } => (*min_y..=*max_y).contains(&pos.y) && polygon_contains(points, pos.x, pos.z),
+        }
+    }
+
+    /// Returns the corners of the smallest cuboid containing
+    /// the shape, or `None` if the shape contains no blocks.
+    pub fn bounds(&self) -> Option<(BlockPosition, BlockPosition)> {
+        match self {
+            RegionShape::Cuboid { min, max } => Some((*min, *max)),
+            RegionShape::Polygon {
+                points,
+                min_y,
+                max_y,
+            } => {
+                let min_x = points.iter().map(|&(x, _)| x).min()?;
+                let max_x = points.iter().map(|&(x, _)| x).max()?;
+                let min_z = points.iter().map(|&(_, z)| z).min()?;
+                let max_z = points.iter().map(|&(_, z)| z).max()?;
+                Some((
+                    BlockPosition::new(min_x, *min_y, min_z),
+                    BlockPosition::new(max_x, *max_y, max_z),
+                ))
+            }
+        }
+    }
+}
+
+/// Something a [`Region`] can allow or deny.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegionFlag {
+    /// Players breaking and placing blocks.
+    Build,
+    /// Players using blocks, like doors and
+    /// enchanting tables, and interacting with entities.
+    Interact,
+    /// Players attacking each other. Denied if
+    /// either player stands where it's denied.
+    Pvp,
+    /// Mobs spawning naturally.
+    MobSpawning,
+}
+
+/// Determines whether `(x, z)` is inside or on an edge of `points`.
+fn polygon_contains(points: &[(i32, i32)], x: i32, z: i32) -> bool {
+    let (x, z) = (x as i64, z as i64);
+    let mut inside = false;
+    for (i, &(x1, z1)) in points.iter().enumerate() {
+        let (x2, z2) = points[(i + 1) % points.len()];
+        let (x1, z1, x2, z2) = (x1 as i64, z1 as i64, x2 as i64, z2 as i64);
+
+        let on_line = (x2 - x1) * (z - z1) == (z2 - z1) * (x - x1);
+        if on_line && x1.min(x2) <= x && x <= x1.max(x2) && z1.min(z2) <= z && z <= z1.max(z2) {
+            return true;
+        }
+
+        // Cast a ray towards +x and count the edges it crosses.
+        if (z1 > z) != (z2 > z) {
+            let crosses = if z2 > z1 {
+                (x - x1) * (z2 - z1) < (z - z1) * (x2 - x1)
+            } else {
+                (x - x1) * (z2 - z1) > (z - z1) * (x2 - x1)
+            };
+            if crosses {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuboid_contains() {
+        let shape =
+            RegionShape::cuboid(BlockPosition::new(10, 80, -5), BlockPosition::new(0, 0, 5));
+        assert!(shape.contains(BlockPosition::new(0, 0, -5)));
+        assert!(shape.contains(BlockPosition::new(10, 80, 5)));
+        assert!(!shape.contains(BlockPosition::new(11, 40, 0)));
+        assert!(!shape.contains(BlockPosition::new(5, 81, 0)));
+    }
+
+    #[test]
+    fn polygon_contains() {
+        // An L shape.
+        let shape = RegionShape::Polygon {
+            points: vec![(0, 0), (10, 0), (10, 4), (4, 4), (4, 10), (0, 10)],
+            min_y: 0,
+            max_y: 255,
+        };
+        assert!(shape.contains(BlockPosition::new(2, 64, 8)));
+        assert!(shape.contains(BlockPosition::new(8, 64, 2)));
+        assert!(!shape.contains(BlockPosition::new(8, 64, 8)));
+        assert!(!shape.contains(BlockPosition::new(-1, 64, 2)));
+
+        // Corners and edges are inside.
+        assert!(shape.contains(BlockPosition::new(10, 64, 4)));
+        assert!(shape.contains(BlockPosition::new(4, 64, 7)));
+        assert!(shape.contains(BlockPosition::new(0, 64, 10)));
+
+        assert_eq!(
+            shape.bounds(),
+            Some((BlockPosition::new(0, 0, 0), BlockPosition::new(10, 255, 10)))
+        );
+    }
+}
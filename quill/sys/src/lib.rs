@@ -40,6 +40,32 @@ extern "C" {
     /// to this host call.
     pub fn register_system(system_data: PointerMut<u8>, name_ptr: Pointer<u8>, name_len: u32);
 
+    /// Registers a scheduled task.
+    ///
+    /// Like a system, the task is invoked through
+    /// `quill_run_system` with the `system_data` pointer,
+    /// but only once every `period` ticks.
+    pub fn register_task(
+        system_data: PointerMut<u8>,
+        name_ptr: Pointer<u8>,
+        name_len: u32,
+        period: u32,
+    );
+
+    /// Registers an event handler.
+    ///
+    /// Like a system, the handler is invoked through
+    /// `quill_run_system` with the `system_data` pointer.
+    /// Event handlers of all plugins run together once per tick,
+    /// ordered by `priority`, which is an `EventPriority`
+    /// converted with `EventPriority::to_u32`.
+    pub fn register_event_handler(
+        system_data: PointerMut<u8>,
+        name_ptr: Pointer<u8>,
+        name_len: u32,
+        priority: u32,
+    );
+
     /// Initiates a query. Returns the query data.
     ///
     /// The returned query buffers are allocated within
@@ -99,6 +125,130 @@ extern "C" {
     /// Does nothing if the entity does not exist or if it does not have the `Chat` component.
     pub fn entity_send_title(entity: EntityId, title_ptr: Pointer<u8>, title_len: u32);
 
+    /// Gets the AI goals of an entity.
+    ///
+    /// Sets `bytes_ptr` to a pointer to a bincode-encoded
+    /// `Vec<GoalInfo>` and `bytes_len` to the number of bytes.
+    /// The list is empty if the entity has no goals.
+    ///
+    /// The bytes are allocated within the plugin's bump allocator.
+    pub fn entity_goals(
+        entity: EntityId,
+        bytes_ptr: PointerMut<Pointer<u8>>,
+        bytes_len: PointerMut<u32>,
+    );
+
+    /// Removes the goals of an entity with the given name.
+    /// Returns the number of removed goals.
+    pub fn entity_remove_goal(entity: EntityId, name_ptr: Pointer<u8>, name_len: u32) -> u32;
+
+    /// Adds an AI goal to an entity.
+    ///
+    /// The goal's callbacks are invoked by calling the plugin's
+    /// exported `quill_run_goal` method with `goal_data`, the entity
+    /// and a `GoalOp`. `controls` are the bits of a `GoalControls`.
+    /// Once the goal is removed, it is called with `GoalOp::Drop`
+    /// so the plugin can free `goal_data`.
+    ///
+    /// The name is prefixed with the plugin's identifier.
+    /// Does nothing if the entity does not exist.
+    pub fn entity_add_goal(
+        entity: EntityId,
+        goal_data: PointerMut<u8>,
+        name_ptr: Pointer<u8>,
+        name_len: u32,
+        priority: u32,
+        controls: u32,
+    );
+
+    /// Registers a custom entity type named `name` which
+    /// clients see as the vanilla entity `disguise`.
+    ///
+    /// `disguise` is a `bincode`-serialized `EntityInit`.
+    ///
+    /// Sets `kind_ptr` and `kind_len` to the type's kind,
+    /// which is `name` namespaced with the plugin's identifier.
+    /// The kind is allocated within the plugin's bump allocator.
+    pub fn register_custom_entity(
+        name_ptr: Pointer<u8>,
+        name_len: u32,
+        disguise_ptr: Pointer<u8>,
+        disguise_len: u32,
+        kind_ptr: PointerMut<Pointer<u8>>,
+        kind_len: PointerMut<u32>,
+    );
+
+    /// Sets a value in an entity's metadata, which is sent
+    /// to clients.
+    ///
+    /// `value` is a `bincode`-serialized `MetadataValue`.
+    pub fn entity_set_metadata(
+        entity: EntityId,
+        index: u32,
+        value_ptr: Pointer<u8>,
+        value_len: u32,
+    );
+
+    /// Gets the tag of an entity with the given key.
+    ///
+    /// Tags are namespaced to the plugin and are kept
+    /// when the plugin is reloaded.
+    ///
+    /// Sets `value_ptr` to a pointer to the UTF-8 value and
+    /// `value_len` to its length. If the entity has no such tag,
+    /// `value_ptr` is set to null.
+    ///
+    /// The value is allocated within the plugin's bump allocator.
+    pub fn entity_get_tag(
+        entity: EntityId,
+        key_ptr: Pointer<u8>,
+        key_len: u32,
+        value_ptr: PointerMut<Pointer<u8>>,
+        value_len: PointerMut<u32>,
+    );
+
+    /// Sets the tag of an entity with the given key.
+    pub fn entity_set_tag(
+        entity: EntityId,
+        key_ptr: Pointer<u8>,
+        key_len: u32,
+        value_ptr: Pointer<u8>,
+        value_len: u32,
+    );
+
+    /// Removes the tag of an entity with the given key.
+    pub fn entity_remove_tag(entity: EntityId, key_ptr: Pointer<u8>, key_len: u32);
+
+    /// Shows a fake entity to a player. Fake entities only
+    /// exist on the player's client.
+    ///
+    /// `entity` is a `bincode`-serialized `FakeEntity`.
+    ///
+    /// Returns the ID of the fake entity, which is
+    /// unique among those of the player.
+    pub fn entity_spawn_fake(player: EntityId, entity_ptr: Pointer<u8>, entity_len: u32) -> u32;
+
+    /// Moves a fake entity of a player.
+    ///
+    /// Returns `false` if the fake entity does not exist.
+    pub fn fake_entity_move(player: EntityId, id: u32, position: Pointer<u8>) -> bool;
+
+    /// Sets the JSON text of a hologram shown to a player.
+    ///
+    /// Returns `false` if the fake entity does not
+    /// exist or is not a hologram.
+    pub fn fake_entity_set_text(
+        player: EntityId,
+        id: u32,
+        text_ptr: Pointer<u8>,
+        text_len: u32,
+    ) -> bool;
+
+    /// Removes a fake entity of a player.
+    ///
+    /// Returns `false` if the fake entity does not exist.
+    pub fn fake_entity_remove(player: EntityId, id: u32) -> bool;
+
     /// Creates an empty entity builder.
     ///
     /// This builder is used for creating an ecs-entity
@@ -119,6 +269,17 @@ extern "C" {
         entity_init_len: u32,
     ) -> u32;
 
+    /// Creates an entity builder for a custom entity type
+    /// registered by this plugin with `register_custom_entity`.
+    ///
+    /// The builder is initialized with the default components
+    /// of the type's disguise and the type's `CustomEntityKind`.
+    pub fn entity_builder_new_custom(
+        position: Pointer<u8>,
+        kind_ptr: Pointer<u8>,
+        kind_len: u32,
+    ) -> u32;
+
     /// Adds a component to an entity builder.
     ///
     /// `bytes` is the serialized component.
@@ -164,6 +325,21 @@ extern "C" {
     pub fn block_fill_chunk_section(chunk_x: i32, section_y: u32, chunk_z: i32, block: u16)
         -> bool;
 
+    /// Shows `block` to a player at the given position
+    /// instead of the real block, without changing the world.
+    ///
+    /// The fake block is shown until it is restored,
+    /// even if the player's client reloads its chunk.
+    pub fn entity_set_fake_block(player: EntityId, x: i32, y: i32, z: i32, block: u16);
+
+    /// Shows the real block to a player at the given position again.
+    ///
+    /// Returns `false` if the block wasn't faked.
+    pub fn entity_restore_fake_block(player: EntityId, x: i32, y: i32, z: i32) -> bool;
+
+    /// Shows all the real blocks to a player again.
+    pub fn entity_restore_fake_blocks(player: EntityId);
+
     /// Sends a custom packet to an entity.
     ///
     /// Does nothing if the entity does not have the `ClientId` component.
@@ -174,4 +350,85 @@ extern "C" {
         data_ptr: Pointer<u8>,
         data_len: u32,
     );
+
+    /// Stores bytes to hand off to the next instance
+    /// of the plugin when it is reloaded.
+    ///
+    /// Replaces any previously stored bytes.
+    pub fn plugin_state_store(bytes_ptr: Pointer<u8>, bytes_len: u32);
+
+    /// Takes the bytes stored by the previous instance
+    /// of the plugin with `plugin_state_store`.
+    ///
+    /// Sets `bytes_ptr` to a pointer to the bytes and `bytes_len`
+    /// to the number of bytes. If no state was handed off,
+    /// `bytes_ptr` is set to null and `bytes_len` is left untouched.
+    ///
+    /// The bytes are allocated within the plugin's bump allocator.
+    pub fn plugin_state_take(bytes_ptr: PointerMut<Pointer<u8>>, bytes_len: PointerMut<u32>);
+
+    /// Adds a protected region to the world, replacing
+    /// the region with the same name.
+    ///
+    /// `region_ptr` is a pointer to a bincode-encoded `Region`.
+    pub fn region_define(region_ptr: Pointer<u8>, region_len: u32);
+
+    /// Removes the protected region called `name`.
+    ///
+    /// Returns `false` if no such region exists.
+    pub fn region_remove(name_ptr: Pointer<u8>, name_len: u32) -> bool;
+
+    /// Returns whether the world's difficulty, game rules
+    /// and regions let a mob spawn naturally at the given position.
+    pub fn world_mob_spawning_allowed(x: i32, y: i32, z: i32, hostile: bool) -> bool;
+
+    /// Gets the balance of an account in the server's economy.
+    ///
+    /// Accounts are identified by a player's UUID, passed
+    /// as its most and least significant 64 bits.
+    pub fn economy_balance(account_high: u64, account_low: u64) -> i64;
+
+    /// Deposits `amount` into an account.
+    ///
+    /// On success, writes the new balance to `balance_ptr` and
+    /// returns 0. Otherwise returns the code of an `EconomyError`.
+    pub fn economy_deposit(
+        account_high: u64,
+        account_low: u64,
+        amount: i64,
+        balance_ptr: PointerMut<i64>,
+    ) -> u32;
+
+    /// Withdraws `amount` from an account. Works like `economy_deposit`.
+    pub fn economy_withdraw(
+        account_high: u64,
+        account_low: u64,
+        amount: i64,
+        balance_ptr: PointerMut<i64>,
+    ) -> u32;
+
+    /// Gets the currency of the server's economy.
+    ///
+    /// Sets `bytes_ptr` to a pointer to a bincode-encoded
+    /// `Currency` and `bytes_len` to the number of bytes.
+    ///
+    /// The bytes are allocated within the plugin's bump allocator.
+    pub fn economy_currency(bytes_ptr: PointerMut<Pointer<u8>>, bytes_len: PointerMut<u32>);
+
+    /// Makes the plugin's economy provider the server's active provider.
+    ///
+    /// The provider's operations are invoked by calling the plugin's
+    /// exported `quill_run_economy` method with an `EconomyOp`, the
+    /// account's UUID as its most and least significant 64 bits, an
+    /// amount or balance, and a pointer receiving the new balance.
+    /// It returns 0 on success or the code of an `EconomyError`.
+    ///
+    /// `currency_ptr` and `currency_len` point to a bincode-encoded
+    /// `Currency`, which can have at most 19 fractional digits.
+    pub fn economy_register_provider(
+        name_ptr: Pointer<u8>,
+        name_len: u32,
+        currency_ptr: Pointer<u8>,
+        currency_len: u32,
+    );
 }
@@ -0,0 +1,308 @@
+//! Load tests a server by connecting a swarm of bots which
+//! move around, then reports the latency they experienced
+//! and the tick times the server measured.
+//!
+//! Usage: `cargo run --release -p feather-loadtest -- --bots 200 --duration 60`
+//!
+//! Tick times are only reported by Feather servers
+//! with `report_tick_times` enabled.
+
+use std::{
+    fmt::Write as _,
+    net::SocketAddr,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use anyhow::bail;
+use argh::FromArgs;
+use feather_bot::{Bot, Event};
+use feather_protocol::{
+    packets::client::{PluginMessage, TabComplete},
+    ServerPlayPacket,
+};
+use simple_logger::SimpleLogger;
+use tokio::time::{interval, sleep_until, Instant as TokioInstant};
+
+use pattern::{Movement, Pattern};
+use stats::{decode_tick_times, Samples};
+
+mod pattern;
+mod stats;
+
+/// How often bots send their position, like the vanilla client.
+const MOVE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often each bot measures its latency.
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the network latency is measured with status pings.
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Ticks taking longer than this make the server fall behind.
+const TICK_BUDGET: Duration = Duration::from_millis(50);
+
+/// Channel on which Feather reports the duration of each tick.
+const TICK_TIMES_CHANNEL: &str = "feather:tick_times";
+
+/// Connects a swarm of bots to a server and reports
+/// the latency and tick times they measured.
+#[derive(Debug, FromArgs)]
+struct Args {
+    /// address of the server.
+    #[argh(option, short = 's', default = "\"127.0.0.1:25565\".parse().unwrap()")]
+    server: SocketAddr,
+    /// number of bots to connect.
+    #[argh(option, short = 'n', default = "100")]
+    bots: usize,
+    /// how long to run the test for once all bots
+    /// have started connecting, in seconds.
+    #[argh(option, short = 'd', default = "60")]
+    duration: u64,
+    /// how bots move: idle, random-walk, circle or line.
+    #[argh(option, short = 'p', default = "Pattern::RandomWalk")]
+    pattern: Pattern,
+    /// milliseconds between bots connecting.
+    #[argh(option, default = "50")]
+    join_interval: u64,
+    /// file to also write the report to.
+    #[argh(option, short = 'o')]
+    report: Option<PathBuf>,
+}
+
+/// What one bot measured.
+#[derive(Debug, Default)]
+struct BotReport {
+    name: String,
+    /// Time taken to log in and join, if the bot joined.
+    join_time: Option<Duration>,
+    /// Why the bot stopped before the end of the test.
+    error: Option<String>,
+    /// Round-trip times of tab completions, which the server
+    /// answers while processing packets on its next tick.
+    latencies: Samples,
+    /// Latency probes the server didn't answer.
+    unanswered: usize,
+    events: u64,
+    /// Tick durations reported by the server.
+    ticks: Samples,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    SimpleLogger::new()
+        .with_level(log::LevelFilter::Info)
+        .init()
+        .unwrap();
+    let args: Args = argh::from_env();
+
+    let join_interval = Duration::from_millis(args.join_interval);
+    let end =
+        TokioInstant::now() + join_interval * args.bots as u32 + Duration::from_secs(args.duration);
+    log::info!(
+        "Connecting {} bots to {} ({:?})",
+        args.bots,
+        args.server,
+        args.pattern
+    );
+
+    let pings = tokio::spawn(measure_pings(args.server, end));
+    let mut bots = Vec::with_capacity(args.bots);
+    for index in 0..args.bots {
+        bots.push(tokio::spawn(run_bot(index, args.server, args.pattern, end)));
+        tokio::time::sleep(join_interval).await;
+    }
+    log::info!(
+        "All bots started connecting; running for {}s",
+        args.duration
+    );
+
+    let mut reports = Vec::with_capacity(bots.len());
+    for bot in bots {
+        reports.push(bot.await?);
+    }
+    let pings = pings.await?;
+
+    let report = write_report(&args, reports, pings);
+    println!("{}", report);
+    if let Some(path) = &args.report {
+        std::fs::write(path, &report)?;
+    }
+    Ok(())
+}
+
+async fn run_bot(index: usize, addr: SocketAddr, pattern: Pattern, end: TokioInstant) -> BotReport {
+    let mut report = BotReport {
+        name: format!("bot{}", index),
+        ..Default::default()
+    };
+    if let Err(e) = drive_bot(index, addr, pattern, end, &mut report).await {
+        log::warn!("{} stopped: {:#}", report.name, e);
+        report.error = Some(format!("{:#}", e));
+    }
+    report
+}
+
+async fn drive_bot(
+    index: usize,
+    addr: SocketAddr,
+    pattern: Pattern,
+    end: TokioInstant,
+    report: &mut BotReport,
+) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let mut bot = Bot::connect(addr, &report.name).await?;
+    report.join_time = Some(started.elapsed());
+    // A single bot is enough to receive every tick time.
+    if index == 0 {
+        bot.send_play_packet(PluginMessage {
+            channel: "minecraft:register".to_owned(),
+            data: TICK_TIMES_CHANNEL.as_bytes().to_vec(),
+        })
+        .await?;
+    }
+
+    // Starts once the server sends the spawn position.
+    let mut movement = None;
+    let mut move_timer = interval(MOVE_INTERVAL);
+    let mut probe_timer = interval(PROBE_INTERVAL);
+    let mut probes = AHashMap::new();
+    let mut next_probe = 0;
+    loop {
+        tokio::select! {
+            event = bot.next_event() => {
+                report.events += 1;
+                match event? {
+                    Event::Teleported(position) => {
+                        if movement.is_none() {
+                            movement = Some(Movement::new(pattern, position, index as u64));
+                        }
+                    }
+                    Event::Other(ServerPlayPacket::PluginMessage(message))
+                        if message.channel == TICK_TIMES_CHANNEL =>
+                    {
+                        for time in decode_tick_times(&message.data) {
+                            report.ticks.push(time);
+                        }
+                    }
+                    Event::Other(ServerPlayPacket::TabComplete(response)) => {
+                        if let Some(sent) = probes.remove(&response.id) {
+                            report.latencies.push(sent.elapsed());
+                        }
+                    }
+                    Event::Disconnected(reason) => {
+                        bail!("kicked: {}", reason.to_plain_string())
+                    }
+                    _ => {}
+                }
+            }
+            _ = move_timer.tick() => {
+                if let Some(movement) = &mut movement {
+                    let position = movement.step(bot.position(), MOVE_INTERVAL);
+                    bot.move_to(position).await?;
+                }
+            }
+            _ = probe_timer.tick() => {
+                probes.insert(next_probe, Instant::now());
+                bot.send_play_packet(TabComplete {
+                    transaction_id: next_probe,
+                    text: "/".to_owned(),
+                })
+                .await?;
+                next_probe += 1;
+            }
+            _ = sleep_until(end) => {
+                report.unanswered = probes.len();
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Measures the network latency to the server
+/// until `end`, using status pings.
+async fn measure_pings(addr: SocketAddr, end: TokioInstant) -> Samples {
+    let mut pings = Samples::default();
+    let mut timer = interval(PING_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = timer.tick() => match feather_bot::ping(addr).await {
+                Ok((_, rtt)) => pings.push(rtt),
+                Err(e) => log::warn!("Failed to ping the server: {:#}", e),
+            },
+            _ = sleep_until(end) => return pings,
+        }
+    }
+}
+
+fn write_report(args: &Args, reports: Vec<BotReport>, mut pings: Samples) -> String {
+    let mut join_times = Samples::default();
+    let mut latencies = Samples::default();
+    let mut ticks = Samples::default();
+    let mut unanswered = 0;
+    let mut events = 0;
+    let mut errors = Vec::new();
+    let mut joined = 0;
+    let mut disconnected = 0;
+    for report in reports {
+        if let Some(join_time) = report.join_time {
+            joined += 1;
+            join_times.push(join_time);
+            if report.error.is_some() {
+                disconnected += 1;
+            }
+        }
+        if let Some(error) = report.error {
+            errors.push(format!("{}: {}", report.name, error));
+        }
+        latencies.extend(report.latencies);
+        ticks.extend(report.ticks);
+        unanswered += report.unanswered;
+        events += report.events;
+    }
+
+    let mut report = String::new();
+    let out = &mut report;
+    writeln!(out, "Feather load test").unwrap();
+    writeln!(out, "Server:        {}", args.server).unwrap();
+    writeln!(
+        out,
+        "Bots:          {} ({:?}, {}s)",
+        args.bots, args.pattern, args.duration
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "Joined:        {} ({} failed to join, {} disconnected early)",
+        joined,
+        args.bots - joined,
+        disconnected
+    )
+    .unwrap();
+    writeln!(out, "Join time:     {}", join_times.summary()).unwrap();
+    writeln!(
+        out,
+        "Latency:       {}, {} unanswered",
+        latencies.summary(),
+        unanswered
+    )
+    .unwrap();
+    writeln!(out, "Network RTT:   {}", pings.summary()).unwrap();
+    writeln!(
+        out,
+        "Tick time:     {}, {} over {}ms",
+        ticks.summary(),
+        ticks.count_over(TICK_BUDGET),
+        TICK_BUDGET.as_millis()
+    )
+    .unwrap();
+    writeln!(out, "Events:        {} received", events).unwrap();
+    if !errors.is_empty() {
+        writeln!(out, "Errors:").unwrap();
+        for error in &errors {
+            writeln!(out, "  {}", error).unwrap();
+        }
+    }
+    report
+}
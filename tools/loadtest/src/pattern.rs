@@ -0,0 +1,151 @@
+use std::{f64::consts::PI, str::FromStr, time::Duration};
+
+use anyhow::bail;
+use base::Position;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Walking speed of bots, in blocks per second.
+const SPEED: f64 = 4.3;
+
+/// How bots move during a test.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Bots stand still.
+    Idle,
+    /// Bots walk in a random direction,
+    /// turning every few seconds.
+    RandomWalk,
+    /// Bots walk around a circle starting where they spawned.
+    Circle,
+    /// Bots walk back and forth along a line.
+    Line,
+}
+
+impl FromStr for Pattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "idle" => Pattern::Idle,
+            "random-walk" => Pattern::RandomWalk,
+            "circle" => Pattern::Circle,
+            "line" => Pattern::Line,
+            _ => bail!(
+                "unknown pattern '{}' (expected idle, random-walk, circle or line)",
+                s
+            ),
+        })
+    }
+}
+
+/// Moves one bot following a [`Pattern`].
+pub struct Movement {
+    pattern: Pattern,
+    origin: Position,
+    elapsed: Duration,
+    rng: StdRng,
+    /// Direction of a random walk, in radians.
+    heading: f64,
+    /// When a random walk next changes direction.
+    next_turn: Duration,
+}
+
+impl Movement {
+    /// Starts moving a bot which spawned at `origin`.
+    /// `seed` makes each bot's random walk different.
+    pub fn new(pattern: Pattern, origin: Position, seed: u64) -> Self {
+        Self {
+            pattern,
+            origin,
+            elapsed: Duration::default(),
+            rng: StdRng::seed_from_u64(seed),
+            heading: 0.0,
+            next_turn: Duration::default(),
+        }
+    }
+
+    /// Gets the position a bot at `current` moves to in `step`.
+    pub fn step(&mut self, current: Position, step: Duration) -> Position {
+        self.elapsed += step;
+        let distance = SPEED * step.as_secs_f64();
+        let walked = SPEED * self.elapsed.as_secs_f64();
+
+        let (x, z) = match self.pattern {
+            Pattern::Idle => return current,
+            Pattern::RandomWalk => {
+                if self.elapsed >= self.next_turn {
+                    self.heading = self.rng.gen_range(0.0, 2.0 * PI);
+                    self.next_turn = self.elapsed + Duration::from_secs(self.rng.gen_range(2, 6));
+                }
+                (
+                    current.x + distance * self.heading.cos(),
+                    current.z + distance * self.heading.sin(),
+                )
+            }
+            Pattern::Circle => {
+                let radius = 8.0;
+                let angle = walked / radius;
+                (
+                    self.origin.x - radius + radius * angle.cos(),
+                    self.origin.z + radius * angle.sin(),
+                )
+            }
+            Pattern::Line => {
+                let length = 16.0;
+                let offset = walked % (2.0 * length);
+                let offset = if offset > length {
+                    2.0 * length - offset
+                } else {
+                    offset
+                };
+                (self.origin.x + offset, self.origin.z)
+            }
+        };
+
+        let yaw = ((z - current.z).atan2(x - current.x).to_degrees() - 90.0) as f32;
+        Position {
+            x,
+            y: current.y,
+            z,
+            yaw,
+            pitch: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::position;
+
+    use super::*;
+
+    #[test]
+    fn parse_patterns() {
+        assert_eq!(
+            "random-walk".parse::<Pattern>().unwrap(),
+            Pattern::RandomWalk
+        );
+        assert!("teleport".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn line_stays_on_segment() {
+        let origin = position!(0.0, 64.0, 0.0);
+        let mut movement = Movement::new(Pattern::Line, origin, 0);
+        let mut position = origin;
+        for _ in 0..200 {
+            position = movement.step(position, Duration::from_millis(50));
+            assert!((0.0..=16.0).contains(&position.x), "{:?}", position);
+            assert_eq!(position.z, 0.0);
+        }
+    }
+
+    #[test]
+    fn random_walk_moves_at_walking_speed() {
+        let origin = position!(0.0, 64.0, 0.0);
+        let mut movement = Movement::new(Pattern::RandomWalk, origin, 1);
+        let next = movement.step(origin, Duration::from_secs(1));
+        let distance = ((next.x - origin.x).powi(2) + (next.z - origin.z).powi(2)).sqrt();
+        assert!((distance - SPEED).abs() < 1e-9);
+    }
+}
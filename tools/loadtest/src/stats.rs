@@ -0,0 +1,108 @@
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+/// Durations measured during a test.
+#[derive(Debug, Default)]
+pub struct Samples {
+    samples: Vec<Duration>,
+}
+
+impl Samples {
+    pub fn push(&mut self, sample: Duration) {
+        self.samples.push(sample);
+    }
+
+    pub fn extend(&mut self, other: Samples) {
+        self.samples.extend(other.samples);
+    }
+
+    /// Gets the sample below which `percentile` percent
+    /// of the samples are, or `None` if there are none.
+    pub fn percentile(&mut self, percentile: f64) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        self.samples.sort_unstable();
+        let rank = (percentile / 100.0 * self.samples.len() as f64).ceil() as usize;
+        Some(self.samples[rank.clamp(1, self.samples.len()) - 1])
+    }
+
+    /// Counts the samples longer than `threshold`.
+    pub fn count_over(&self, threshold: Duration) -> usize {
+        self.samples
+            .iter()
+            .filter(|&&sample| sample > threshold)
+            .count()
+    }
+
+    /// Summarizes the samples with the usual percentiles.
+    pub fn summary(&mut self) -> Summary {
+        Summary {
+            percentiles: [
+                self.percentile(50.0),
+                self.percentile(90.0),
+                self.percentile(99.0),
+                self.percentile(100.0),
+            ],
+            count: self.samples.len(),
+        }
+    }
+}
+
+pub struct Summary {
+    percentiles: [Option<Duration>; 4],
+    count: usize,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.count == 0 {
+            return write!(f, "no samples");
+        }
+        let labels = ["p50", "p90", "p99", "max"];
+        for (label, percentile) in labels.iter().zip(self.percentiles.iter()) {
+            let millis = percentile.unwrap_or_default().as_secs_f64() * 1000.0;
+            write!(f, "{} {:>7.1}ms  ", label, millis)?;
+        }
+        write!(f, "({} samples)", self.count)
+    }
+}
+
+/// Decodes the tick durations in a message on the
+/// tick times channel, sent as big-endian `u32` microseconds.
+pub fn decode_tick_times(data: &[u8]) -> impl Iterator<Item = Duration> + '_ {
+    data.chunks_exact(4).map(|bytes| {
+        let micros = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        Duration::from_micros(micros.into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles() {
+        let mut samples = Samples::default();
+        assert_eq!(samples.percentile(50.0), None);
+        for millis in (1..=100).rev() {
+            samples.push(Duration::from_millis(millis));
+        }
+        assert_eq!(samples.percentile(50.0), Some(Duration::from_millis(50)));
+        assert_eq!(samples.percentile(99.0), Some(Duration::from_millis(99)));
+        assert_eq!(samples.percentile(100.0), Some(Duration::from_millis(100)));
+        assert_eq!(samples.count_over(Duration::from_millis(90)), 10);
+    }
+
+    #[test]
+    fn tick_times() {
+        let times: Vec<Duration> =
+            decode_tick_times(&[0, 0, 0xC8, 0x00, 0, 0x0F, 0x42, 0x40]).collect();
+        assert_eq!(
+            times,
+            vec![Duration::from_micros(51_200), Duration::from_secs(1)]
+        );
+    }
+}
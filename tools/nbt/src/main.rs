@@ -0,0 +1,142 @@
+//! Converts between NBT files, SNBT and JSON.
+//!
+//! Usage: `cargo run -p feather-nbt -- world/level.dat --to json`
+
+use std::{
+    fs,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context};
+use argh::FromArgs;
+use base::nbt_conversion;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use nbt::Value;
+
+/// ID of the compound tag, which must be the root of an NBT file.
+const COMPOUND_ID: u8 = 0x0a;
+
+/// Converts between NBT files, SNBT and JSON.
+#[derive(Debug, FromArgs)]
+struct Args {
+    /// the file to convert.
+    #[argh(positional)]
+    input: PathBuf,
+    /// format of the input: nbt, snbt or json.
+    /// Guessed from the file extension by default.
+    #[argh(option)]
+    from: Option<Format>,
+    /// format to convert to: nbt, snbt or json.
+    #[argh(option, default = "Format::Snbt")]
+    to: Format,
+    /// file to write to instead of standard output.
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Format {
+    /// Binary NBT, optionally gzipped.
+    Nbt,
+    Snbt,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "nbt" => Format::Nbt,
+            "snbt" => Format::Snbt,
+            "json" => Format::Json,
+            _ => bail!("unknown format '{}' (expected nbt, snbt or json)", s),
+        })
+    }
+}
+
+impl Format {
+    fn from_extension(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("snbt") => Format::Snbt,
+            Some("json") => Format::Json,
+            _ => Format::Nbt,
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Args = argh::from_env();
+    let from = args
+        .from
+        .unwrap_or_else(|| Format::from_extension(&args.input));
+
+    let input = fs::read(&args.input)
+        .with_context(|| format!("failed to read {}", args.input.display()))?;
+    let value = read(&input, from)
+        .with_context(|| format!("failed to parse {} as {:?}", args.input.display(), from))?;
+    let output = write(&value, args.to)?;
+
+    match &args.output {
+        Some(path) => {
+            fs::write(path, &output).with_context(|| format!("failed to write {}", path.display()))
+        }
+        None => Ok(std::io::stdout().write_all(&output)?),
+    }
+}
+
+fn read(input: &[u8], format: Format) -> anyhow::Result<Value> {
+    Ok(match format {
+        Format::Nbt => read_nbt(input)?,
+        Format::Snbt => nbt_conversion::from_snbt(std::str::from_utf8(input)?.trim())?,
+        Format::Json => nbt_conversion::from_json(&serde_json::from_slice(input)?)?,
+    })
+}
+
+fn write(value: &Value, format: Format) -> anyhow::Result<Vec<u8>> {
+    Ok(match format {
+        Format::Nbt => write_nbt(value)?,
+        Format::Snbt => format!("{}\n", nbt_conversion::to_snbt(value)).into_bytes(),
+        Format::Json => {
+            let mut json = serde_json::to_vec_pretty(&nbt_conversion::to_json(value))?;
+            json.push(b'\n');
+            json
+        }
+    })
+}
+
+/// Reads the root compound of an NBT file,
+/// which is gzipped if it starts with the gzip magic number.
+fn read_nbt(input: &[u8]) -> anyhow::Result<Value> {
+    let mut data = Vec::new();
+    if input.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(input).read_to_end(&mut data)?;
+    } else {
+        data.extend_from_slice(input);
+    }
+
+    let mut reader = Cursor::new(data);
+    let mut header = [0; 3];
+    reader.read_exact(&mut header)?;
+    if header[0] != COMPOUND_ID {
+        bail!("the root tag is not a compound");
+    }
+    // The root's name is usually empty and is not kept.
+    let name_len = u16::from_be_bytes([header[1], header[2]]);
+    reader.set_position(reader.position() + u64::from(name_len));
+
+    Ok(Value::from_reader(COMPOUND_ID, &mut reader)?)
+}
+
+/// Writes `value` as a gzipped NBT file with an unnamed root.
+fn write_nbt(value: &Value) -> anyhow::Result<Vec<u8>> {
+    if !matches!(value, Value::Compound(_)) {
+        bail!("only compounds can be written as NBT files");
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&[COMPOUND_ID, 0, 0])?;
+    value.to_writer(&mut encoder)?;
+    Ok(encoder.finish()?)
+}